@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! Feeds arbitrary TOML into the manifest editors, which must never panic, and checks with
+//! `verify_roundtrip` that they only change the intended keys.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustilities::manifest::{
+	self, DependencyStyle, ManifestDependencyConfig, ManifestDependencyOrigin,
+};
+
+fuzz_target!(|content: &str| {
+	let _ = manifest::changed_keys(content, content);
+
+	for style in [DependencyStyle::Inline, DependencyStyle::Table] {
+		let config = ManifestDependencyConfig::builder(ManifestDependencyOrigin::crates_io("1.0"))
+			.feature("derive")
+			.optional()
+			.build();
+		if let Ok(edited) =
+			manifest::add_crate_to_dependencies_str_with_style(content, "fuzzed", config, style)
+		{
+			manifest::verify_roundtrip(
+				content,
+				&edited,
+				&[&["dependencies", "fuzzed"], &["workspace", "dependencies", "fuzzed"]],
+			)
+			.expect("The editor should only change the added dependency");
+			let _ = ManifestDependencyConfig::read_from_manifest(&edited, "fuzzed");
+		}
+	}
+
+	let Ok(tempdir) = tempfile::tempdir() else { return };
+	let manifest_path = tempdir.path().join("Cargo.toml");
+	if std::fs::write(&manifest_path, content).is_err() {
+		return;
+	}
+	for key_path in [&["dependencies", "fuzzed"][..], &["package"]] {
+		let Ok(original) = std::fs::read_to_string(&manifest_path) else { return };
+		if let Ok(true) = manifest::remove_key(&manifest_path, key_path) {
+			let edited = std::fs::read_to_string(&manifest_path).unwrap_or_default();
+			manifest::verify_roundtrip(&original, &edited, &[key_path])
+				.expect("The editor should only remove the given key");
+		}
+	}
+	let _ = manifest::remove_table_if_empty(&manifest_path, &["dependencies"]);
+});
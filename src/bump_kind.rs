@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: GPL-3.0
+
+/// The kinds of version bumps defined by [semantic versioning](https://semver.org), ordered from
+/// the smallest to the biggest one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "snake_case")
+)]
+pub enum BumpKind {
+	/// Backward compatible bug fixes.
+	Patch,
+	/// Backward compatible additions.
+	Minor,
+	/// Breaking changes.
+	Major,
+}
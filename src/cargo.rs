@@ -0,0 +1,420 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! Wrappers around the cargo commands editing manifests and lockfiles, shelling out to the `cargo`
+//! binary, which must be available in the `PATH`. Prefer the pure TOML edits of the
+//! [`manifest`](crate::manifest) module unless the exact cargo semantics are needed, eg to resolve
+//! versions from the registry or to validate the requested features.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{Error, macros::debug};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	ffi::OsString,
+	path::{Path, PathBuf},
+	process::Command,
+};
+use toml_edit::DocumentMut;
+
+/// The options used by [`add`], mirroring the flags of `cargo add`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AddOptions<'a> {
+	/// The version requirement of the dependency. If `None`, cargo picks the latest compatible
+	/// version.
+	pub version: Option<&'a str>,
+	/// The path of a local dependency.
+	pub path: Option<&'a Path>,
+	/// The features to activate.
+	pub features: Vec<&'a str>,
+	/// Whether the default features are disabled.
+	pub no_default_features: bool,
+	/// Whether the dependency is optional.
+	pub optional: bool,
+	/// The name the dependency is renamed to.
+	pub rename: Option<&'a str>,
+	/// Whether the dependency is added to `dev-dependencies`.
+	pub dev: bool,
+	/// Whether the dependency is added to `build-dependencies`.
+	pub build: bool,
+	/// The target platform the dependency is added for, eg `cfg(unix)`.
+	pub target: Option<&'a str>,
+	/// The workspace member the dependency is added to. If `None`, it's added to the crate
+	/// containing the dir.
+	pub package: Option<&'a str>,
+	/// Whether cargo must run without accessing the network.
+	pub offline: bool,
+}
+
+/// What `cargo add` reported after adding a dependency, as returned by [`add`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AddOutcome {
+	/// The name of the dependency.
+	pub name: String,
+	/// The version requirement written to the manifest, or `None` for local dependencies.
+	pub version: Option<String>,
+	/// The section the dependency was added to, as reported by cargo, eg `dependencies`,
+	/// `optional dependencies` or ``dev-dependencies for target `cfg(unix)` ``.
+	pub section: String,
+	/// The features of the dependency activated by the build, sorted.
+	pub activated_features: Vec<String>,
+	/// The features of the dependency not activated by the build, sorted. cargo summarizes the
+	/// list if it's too long, in which case it's empty.
+	pub deactivated_features: Vec<String>,
+}
+
+/// The options used by [`update`], mirroring the flags of `cargo update`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UpdateOptions<'a> {
+	/// The package to update. If `None`, every package in the lockfile is updated.
+	pub package: Option<&'a str>,
+	/// The exact version the package is updated to. Requires `package`.
+	pub precise: Option<&'a str>,
+	/// Whether only the workspace members are updated, eg to refresh the lockfile after bumping
+	/// their versions.
+	pub workspace: bool,
+	/// Whether cargo must run without accessing the network.
+	pub offline: bool,
+}
+
+/// A change of the packages locked in `Cargo.lock`, as returned by [`remove`] and [`update`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockfileChange {
+	/// The name of the package.
+	pub name: String,
+	/// The version locked before the command, or `None` if the package was added.
+	pub old_version: Option<String>,
+	/// The version locked after the command, or `None` if the package was removed.
+	pub new_version: Option<String>,
+}
+
+/// Given a dir, the name of a crate and some options, this function runs `cargo add` in that dir
+/// and returns the summary printed by cargo, including the features activated and deactivated for
+/// the dependency.
+///
+/// Unlike [`add_crate_to_dependencies`](crate::manifest::add_crate_to_dependencies), this function
+/// follows the exact cargo semantics: the version is resolved from the registry, unknown features
+/// are rejected, optional dependencies get their implicit feature and the lockfile is updated.
+///
+/// # Errors
+///
+/// - If `cargo add` cannot be run or fails, eg because the crate or some feature doesn't exist.
+/// - If the output of `cargo add` doesn't contain its summary.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::cargo::AddOptions;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// for (path, content) in [
+///     ("app/Cargo.toml", "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2024\"\n"),
+///     ("app/src/lib.rs", ""),
+///     (
+///         "utils/Cargo.toml",
+///         "[package]\nname = \"utils\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n[features]\ndefault = [\"std\"]\nstd = []\nserde = []\n",
+///     ),
+///     ("utils/src/lib.rs", ""),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(path).parent().unwrap()).unwrap();
+///     std::fs::write(tempdir.path().join(path), content).unwrap();
+/// }
+///
+/// let outcome = rustilities::cargo::add(
+///     tempdir.path().join("app"),
+///     "utils",
+///     &AddOptions {
+///         path: Some(&tempdir.path().join("utils")),
+///         features: vec!["serde"],
+///         offline: true,
+///         ..Default::default()
+///     },
+/// )
+/// .unwrap();
+///
+/// assert_eq!(outcome.activated_features, ["serde", "std"]);
+/// assert!(outcome.deactivated_features.is_empty());
+/// ```
+pub fn add<P: AsRef<Path>>(dir: P, name: &str, options: &AddOptions) -> Result<AddOutcome, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_add(dir: &Path, name: &str, options: &AddOptions) -> Result<AddOutcome, Error> {
+		let mut args: Vec<OsString> = vec![
+			"add".into(),
+			match options.version {
+				Some(version) => format!("{name}@{version}").into(),
+				None => name.into(),
+			},
+		];
+		if let Some(path) = options.path {
+			args.extend(["--path".into(), path.into()]);
+		}
+		if !options.features.is_empty() {
+			args.extend(["--features".into(), options.features.join(",").into()]);
+		}
+		for (enabled, flag) in [
+			(options.no_default_features, "--no-default-features"),
+			(options.optional, "--optional"),
+			(options.dev, "--dev"),
+			(options.build, "--build"),
+			(options.offline, "--offline"),
+		] {
+			if enabled {
+				args.push(flag.into());
+			}
+		}
+		for (value, flag) in [
+			(options.rename, "--rename"),
+			(options.target, "--target"),
+			(options.package, "--package"),
+		] {
+			if let Some(value) = value {
+				args.extend([flag.into(), value.into()]);
+			}
+		}
+
+		let stderr = run_cargo(dir, &args)?;
+		parse_add_output(&stderr).ok_or_else(|| {
+			Error::Descriptive(format!("Cannot understand the output of cargo add: {stderr}"))
+		})
+	}
+	do_add(dir.as_ref(), name, options)
+}
+
+/// Given a dir and the name of a dependency, this function runs `cargo remove` in that dir and
+/// returns the changes made to the lockfile, sorted by package name.
+///
+/// # Errors
+///
+/// - If `cargo remove` cannot be run or fails, eg because the crate doesn't declare the dependency.
+/// - If the lockfile cannot be read or parsed.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::cargo::LockfileChange;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// for (path, content) in [
+///     (
+///         "app/Cargo.toml",
+///         "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n[dependencies]\nutils = { path = \"../utils\" }\n",
+///     ),
+///     ("app/src/lib.rs", ""),
+///     ("utils/Cargo.toml", "[package]\nname = \"utils\"\nversion = \"0.1.0\"\nedition = \"2024\"\n"),
+///     ("utils/src/lib.rs", ""),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(path).parent().unwrap()).unwrap();
+///     std::fs::write(tempdir.path().join(path), content).unwrap();
+/// }
+/// std::process::Command::new("cargo")
+///     .args(["generate-lockfile", "--offline"])
+///     .current_dir(tempdir.path().join("app"))
+///     .output()
+///     .unwrap();
+///
+/// assert_eq!(
+///     rustilities::cargo::remove(tempdir.path().join("app"), "utils").unwrap(),
+///     [LockfileChange {
+///         name: "utils".to_owned(),
+///         old_version: Some("0.1.0".to_owned()),
+///         new_version: None,
+///     }]
+/// );
+/// ```
+pub fn remove<P: AsRef<Path>>(dir: P, name: &str) -> Result<Vec<LockfileChange>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_remove(dir: &Path, name: &str) -> Result<Vec<LockfileChange>, Error> {
+		tracking_lockfile(dir, |dir| run_cargo(dir, &["remove".into(), name.into()]))
+	}
+	do_remove(dir.as_ref(), name)
+}
+
+/// Given a dir and some options, this function runs `cargo update` in that dir and returns the
+/// changes made to the lockfile, sorted by package name. If the lockfile doesn't exist, cargo
+/// creates it and every package is reported as added.
+///
+/// # Errors
+///
+/// - If `cargo update` cannot be run or fails, eg because the package isn't locked or the precise
+///   version doesn't exist.
+/// - If the lockfile cannot be read or parsed.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::cargo::{LockfileChange, UpdateOptions};
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(&manifest_path, "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2024\"\n").unwrap();
+/// std::fs::create_dir(tempdir.path().join("src")).unwrap();
+/// std::fs::write(tempdir.path().join("src/lib.rs"), "").unwrap();
+/// let options = UpdateOptions { workspace: true, offline: true, ..Default::default() };
+/// rustilities::cargo::update(tempdir.path(), &options).unwrap();
+///
+/// std::fs::write(&manifest_path, "[package]\nname = \"app\"\nversion = \"0.2.0\"\nedition = \"2024\"\n").unwrap();
+/// assert_eq!(
+///     rustilities::cargo::update(tempdir.path(), &options).unwrap(),
+///     [LockfileChange {
+///         name: "app".to_owned(),
+///         old_version: Some("0.1.0".to_owned()),
+///         new_version: Some("0.2.0".to_owned()),
+///     }]
+/// );
+/// ```
+pub fn update<P: AsRef<Path>>(
+	dir: P,
+	options: &UpdateOptions,
+) -> Result<Vec<LockfileChange>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_update(dir: &Path, options: &UpdateOptions) -> Result<Vec<LockfileChange>, Error> {
+		let mut args: Vec<OsString> = vec!["update".into()];
+		if let Some(package) = options.package {
+			args.push(package.into());
+		}
+		if let Some(precise) = options.precise {
+			args.extend(["--precise".into(), precise.into()]);
+		}
+		for (enabled, flag) in [(options.workspace, "--workspace"), (options.offline, "--offline")]
+		{
+			if enabled {
+				args.push(flag.into());
+			}
+		}
+		tracking_lockfile(dir, |dir| run_cargo(dir, &args))
+	}
+	do_update(dir.as_ref(), options)
+}
+
+/// Runs a cargo command in the given dir and returns the changes it made to the lockfile of the
+/// workspace containing the dir.
+fn tracking_lockfile<F>(dir: &Path, run: F) -> Result<Vec<LockfileChange>, Error>
+where
+	F: FnOnce(&Path) -> Result<String, Error>,
+{
+	let lockfile = lockfile_path(dir)?;
+	let before = locked_packages(&lockfile)?;
+	run(dir)?;
+	Ok(lockfile_diff(&before, &locked_packages(&lockfile)?))
+}
+
+/// The path of the lockfile of the workspace containing the given dir, which may not exist yet.
+fn lockfile_path(dir: &Path) -> Result<PathBuf, Error> {
+	let stdout = Command::new("cargo")
+		.args(["locate-project", "--workspace", "--message-format", "plain"])
+		.current_dir(dir)
+		.output()
+		.map_err(Error::from)
+		.and_then(|output| {
+			if output.status.success() {
+				Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+			} else {
+				Err(Error::Descriptive(String::from_utf8_lossy(&output.stderr).trim().to_owned()))
+			}
+		})?;
+	Ok(Path::new(stdout.trim()).with_file_name("Cargo.lock"))
+}
+
+/// The versions of each package locked in the given lockfile. A missing lockfile doesn't lock
+/// anything.
+fn locked_packages(lockfile: &Path) -> Result<BTreeMap<String, BTreeSet<String>>, Error> {
+	let content = match std::fs::read_to_string(lockfile) {
+		Ok(content) => content,
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+		Err(source) => return Err(Error::IOAt { path: lockfile.to_path_buf(), source }),
+	};
+	let doc = content.parse::<DocumentMut>()?;
+	let mut packages = BTreeMap::<String, BTreeSet<String>>::new();
+	for package in doc
+		.get("package")
+		.and_then(|item| item.as_array_of_tables())
+		.into_iter()
+		.flatten()
+	{
+		if let (Some(name), Some(version)) = (
+			package.get("name").and_then(|name| name.as_str()),
+			package.get("version").and_then(|version| version.as_str()),
+		) {
+			packages.entry(name.to_owned()).or_default().insert(version.to_owned());
+		}
+	}
+	Ok(packages)
+}
+
+/// The changes between two sets of locked packages. For each package, the versions that
+/// disappeared are paired with the versions that appeared, both sorted by semver precedence.
+fn lockfile_diff(
+	before: &BTreeMap<String, BTreeSet<String>>,
+	after: &BTreeMap<String, BTreeSet<String>>,
+) -> Vec<LockfileChange> {
+	let empty = BTreeSet::new();
+	let sorted = |versions: std::collections::btree_set::Difference<'_, String>| {
+		let mut versions = versions.cloned().collect::<Vec<_>>();
+		versions.sort_by_cached_key(|version| semver::Version::parse(version).ok());
+		versions
+	};
+	let mut changes = Vec::new();
+	for name in before.keys().chain(after.keys()).collect::<BTreeSet<_>>() {
+		let (before, after) =
+			(before.get(name).unwrap_or(&empty), after.get(name).unwrap_or(&empty));
+		let old_versions = sorted(before.difference(after));
+		let new_versions = sorted(after.difference(before));
+		for index in 0..old_versions.len().max(new_versions.len()) {
+			changes.push(LockfileChange {
+				name: name.clone(),
+				old_version: old_versions.get(index).cloned(),
+				new_version: new_versions.get(index).cloned(),
+			});
+		}
+	}
+	changes
+}
+
+/// Parses the summary printed by `cargo add` to its stderr.
+fn parse_add_output(stderr: &str) -> Option<AddOutcome> {
+	let mut lines = stderr.lines().map(str::trim);
+	let (name, rest) = lines.find_map(|line| line.strip_prefix("Adding "))?.split_once(' ')?;
+	let (source, section) = rest.split_once(" to ")?;
+	let mut outcome = AddOutcome {
+		name: name.to_owned(),
+		version: source.strip_prefix('v').map(str::to_owned),
+		section: section.to_owned(),
+		activated_features: Vec::new(),
+		deactivated_features: Vec::new(),
+	};
+
+	// The features are listed under a `Features:` or `Features as of <version>:` header
+	if lines.next().is_some_and(|line| line.starts_with("Features")) {
+		for line in lines {
+			if let Some(feature) = line.strip_prefix("+ ") {
+				outcome.activated_features.push(feature.to_owned());
+			} else if let Some(feature) = line.strip_prefix("- ") {
+				outcome.deactivated_features.push(feature.to_owned());
+			} else if !line.ends_with("deactivated features") {
+				break;
+			}
+		}
+	}
+	outcome.activated_features.sort();
+	outcome.deactivated_features.sort();
+	Some(outcome)
+}
+
+/// Runs cargo with the given args in the given dir, returning its stderr, where cargo prints its
+/// progress.
+fn run_cargo(dir: &Path, args: &[OsString]) -> Result<String, Error> {
+	debug!(dir = %dir.display(), ?args, "Running cargo");
+	let output = Command::new("cargo")
+		.args(args)
+		.current_dir(dir)
+		.env("CARGO_TERM_COLOR", "never")
+		.output()?;
+	let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+	if output.status.success() {
+		Ok(stderr)
+	} else {
+		Err(Error::Descriptive(stderr.trim().to_owned()))
+	}
+}
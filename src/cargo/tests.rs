@@ -0,0 +1,290 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use crate::test_utils::tempdir_with_files;
+
+// Two unrelated crates, `app` and `utils`, the latter declaring a few features.
+const CRATES: &[(&str, &str)] = &[
+	("app/Cargo.toml", "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2024\"\n"),
+	("app/src/lib.rs", ""),
+	(
+		"utils/Cargo.toml",
+		"[package]\nname = \"utils\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n[features]\ndefault = [\"std\"]\nstd = []\nserde = []\nalloc = []\n",
+	),
+	("utils/src/lib.rs", ""),
+];
+
+#[test]
+fn add_writes_the_dependency_and_returns_the_summary() {
+	let tempdir = tempdir_with_files(CRATES);
+	let utils_dir = tempdir.path().join("utils");
+
+	assert_eq!(
+		add(
+			tempdir.path().join("app"),
+			"utils",
+			&AddOptions {
+				path: Some(&utils_dir),
+				features: vec!["serde"],
+				no_default_features: true,
+				optional: true,
+				offline: true,
+				..Default::default()
+			},
+		)
+		.expect("This should be Ok; qed;"),
+		AddOutcome {
+			name: "utils".to_owned(),
+			version: None,
+			section: "optional dependencies".to_owned(),
+			activated_features: vec!["serde".to_owned()],
+			deactivated_features: vec!["alloc".to_owned(), "std".to_owned()],
+		}
+	);
+	let manifest = std::fs::read_to_string(tempdir.path().join("app/Cargo.toml"))
+		.expect("The file should be readable; qed;");
+	assert!(manifest.contains(
+		r#"utils = { version = "0.1.0", path = "../utils", default-features = false, features = ["serde"], optional = true }"#
+	));
+	assert!(manifest.contains("utils = [\"dep:utils\"]"));
+}
+
+#[test]
+fn add_supports_dev_and_target_dependencies() {
+	let tempdir = tempdir_with_files(CRATES);
+	let utils_dir = tempdir.path().join("utils");
+
+	let outcome = add(
+		tempdir.path().join("app"),
+		"utils",
+		&AddOptions {
+			path: Some(&utils_dir),
+			rename: Some("helpers"),
+			dev: true,
+			target: Some("cfg(unix)"),
+			offline: true,
+			..Default::default()
+		},
+	)
+	.expect("This should be Ok; qed;");
+
+	assert_eq!(outcome.section, "dev-dependencies for target `cfg(unix)`");
+	assert_eq!(outcome.activated_features, ["std"]);
+	assert!(
+		std::fs::read_to_string(tempdir.path().join("app/Cargo.toml"))
+			.expect("The file should be readable; qed;")
+			.contains("[target.\"cfg(unix)\".dev-dependencies]\nhelpers = { path = \"../utils\", package = \"utils\" }")
+	);
+}
+
+#[test]
+fn add_fails_if_cargo_rejects_the_dependency() {
+	let tempdir = tempdir_with_files(CRATES);
+	let utils_dir = tempdir.path().join("utils");
+	let manifest_path = tempdir.path().join("app/Cargo.toml");
+	let manifest =
+		std::fs::read_to_string(&manifest_path).expect("The file should be readable; qed;");
+
+	assert!(matches!(
+		add(
+			tempdir.path().join("app"),
+			"utils",
+			&AddOptions {
+				path: Some(&utils_dir),
+				features: vec!["unknown"],
+				offline: true,
+				..Default::default()
+			},
+		),
+		Err(Error::Descriptive(msg)) if msg.contains("unrecognized feature for crate utils: unknown")
+	));
+	assert_eq!(
+		std::fs::read_to_string(&manifest_path).expect("The file should be readable; qed;"),
+		manifest
+	);
+}
+
+#[test]
+fn parse_add_output_works() {
+	assert_eq!(
+		parse_add_output(
+			"      Adding tokio v1 to dependencies\n             Features as of v1.2.0:\n             + rt\n             - fs\n             - full\n     Locking 2 packages to latest compatible versions\n      Adding tokio v1.45.0\n"
+		),
+		Some(AddOutcome {
+			name: "tokio".to_owned(),
+			version: Some("1".to_owned()),
+			section: "dependencies".to_owned(),
+			activated_features: vec!["rt".to_owned()],
+			deactivated_features: vec!["fs".to_owned(), "full".to_owned()],
+		})
+	);
+	assert_eq!(
+		parse_add_output(
+			"    Updating crates.io index\n      Adding regex v1.11.1 to build-dependencies\n     Locking 1 package\n"
+		),
+		Some(AddOutcome {
+			name: "regex".to_owned(),
+			version: Some("1.11.1".to_owned()),
+			section: "build-dependencies".to_owned(),
+			activated_features: vec![],
+			deactivated_features: vec![],
+		})
+	);
+	assert_eq!(parse_add_output("     Locking 1 package\n      Adding regex v1.11.1\n"), None);
+}
+
+fn generate_lockfile(dir: &Path) {
+	let output = Command::new("cargo")
+		.args(["generate-lockfile", "--offline"])
+		.current_dir(dir)
+		.output()
+		.expect("cargo should run; qed;");
+	assert!(output.status.success());
+}
+
+#[test]
+fn remove_returns_the_packages_removed_from_the_lockfile() {
+	let tempdir = tempdir_with_files(&[
+		("Cargo.toml", "[workspace]\nmembers = [\"app\", \"utils\"]\n"),
+		(
+			"app/Cargo.toml",
+			"[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n[dependencies]\nhelpers = { path = \"../helpers\" }\nutils = { path = \"../utils\" }\n",
+		),
+		("app/src/lib.rs", ""),
+		(
+			"utils/Cargo.toml",
+			"[package]\nname = \"utils\"\nversion = \"0.1.0\"\nedition = \"2024\"\n",
+		),
+		("utils/src/lib.rs", ""),
+		(
+			"helpers/Cargo.toml",
+			"[package]\nname = \"helpers\"\nversion = \"0.3.0\"\nedition = \"2024\"\n",
+		),
+		("helpers/src/lib.rs", ""),
+	]);
+	generate_lockfile(tempdir.path());
+
+	// Workspace members stay locked, the lockfile lives in the workspace root
+	assert!(
+		remove(tempdir.path().join("app"), "utils")
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+	assert_eq!(
+		remove(tempdir.path().join("app"), "helpers").expect("This should be Ok; qed;"),
+		[LockfileChange {
+			name: "helpers".to_owned(),
+			old_version: Some("0.3.0".to_owned()),
+			new_version: None,
+		}]
+	);
+	assert!(matches!(
+		remove(tempdir.path().join("app"), "helpers"),
+		Err(Error::Descriptive(msg)) if msg.contains("the dependency `helpers` could not be found")
+	));
+}
+
+#[test]
+fn update_returns_the_lockfile_changes() {
+	let tempdir = tempdir_with_files(CRATES);
+	let app_dir = tempdir.path().join("app");
+	add(
+		&app_dir,
+		"utils",
+		&AddOptions {
+			path: Some(&tempdir.path().join("utils")),
+			offline: true,
+			..Default::default()
+		},
+	)
+	.expect("This should be Ok; qed;");
+	std::fs::write(
+		tempdir.path().join("utils/Cargo.toml"),
+		"[package]\nname = \"utils\"\nversion = \"0.2.0\"\nedition = \"2024\"\n",
+	)
+	.expect("The file should be writable; qed;");
+	std::fs::write(
+		tempdir.path().join("app/Cargo.toml"),
+		"[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n[dependencies]\nutils = { path = \"../utils\" }\n",
+	)
+	.expect("The file should be writable; qed;");
+
+	assert_eq!(
+		update(
+			&app_dir,
+			&UpdateOptions { package: Some("utils"), offline: true, ..Default::default() }
+		)
+		.expect("This should be Ok; qed;"),
+		[LockfileChange {
+			name: "utils".to_owned(),
+			old_version: Some("0.1.0".to_owned()),
+			new_version: Some("0.2.0".to_owned()),
+		}]
+	);
+	assert!(
+		update(&app_dir, &UpdateOptions { offline: true, ..Default::default() })
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+	assert!(matches!(
+		update(
+			&app_dir,
+			&UpdateOptions { package: Some("missing"), offline: true, ..Default::default() }
+		),
+		Err(Error::Descriptive(msg)) if msg.contains("missing")
+	));
+}
+
+#[test]
+fn update_reports_every_package_if_the_lockfile_doesnt_exist() {
+	let tempdir = tempdir_with_files(CRATES);
+
+	assert_eq!(
+		update(
+			tempdir.path().join("utils"),
+			&UpdateOptions { workspace: true, offline: true, ..Default::default() }
+		)
+		.expect("This should be Ok; qed;"),
+		[LockfileChange {
+			name: "utils".to_owned(),
+			old_version: None,
+			new_version: Some("0.1.0".to_owned()),
+		}]
+	);
+	assert!(tempdir.path().join("utils/Cargo.lock").is_file());
+}
+
+#[test]
+fn lockfile_diff_pairs_versions_by_precedence() {
+	let locked = |packages: &[(&str, &[&str])]| {
+		packages
+			.iter()
+			.map(|(name, versions)| {
+				(name.to_string(), versions.iter().map(|version| version.to_string()).collect())
+			})
+			.collect::<BTreeMap<_, BTreeSet<_>>>()
+	};
+	let change =
+		|name: &str, old_version: Option<&str>, new_version: Option<&str>| LockfileChange {
+			name: name.to_owned(),
+			old_version: old_version.map(str::to_owned),
+			new_version: new_version.map(str::to_owned),
+		};
+
+	assert_eq!(
+		lockfile_diff(
+			&locked(&[("syn", &["1.0.9", "2.0.1"]), ("libc", &["0.2.1"]), ("old", &["1.0.0"])]),
+			&locked(&[
+				("syn", &["1.0.10", "2.0.1", "2.0.9"]),
+				("libc", &["0.2.1"]),
+				("new", &["0.1.0"])
+			]),
+		),
+		[
+			change("new", None, Some("0.1.0")),
+			change("old", Some("1.0.0"), None),
+			change("syn", Some("1.0.9"), Some("1.0.10")),
+			change("syn", None, Some("2.0.9")),
+		]
+	);
+}
@@ -0,0 +1,544 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! This module offers functionalities to read and edit the cargo config files
+//! (`.cargo/config.toml`) applicable to a path.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{Error, macros::debug};
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, Item, Table, TableLike};
+
+/// The configuration resulting from merging all the cargo config files applicable to a path.
+#[derive(Debug, Clone)]
+pub struct CargoConfig {
+	files: Vec<(PathBuf, DocumentMut)>,
+	merged: Item,
+}
+
+impl CargoConfig {
+	/// Given a path, loads the cargo config files applicable to it (see [`config_files`]) and
+	/// merges them following cargo's precedence rules:
+	/// - Values in a config file closer to the path override values in config files further away.
+	/// - Tables are merged key by key.
+	/// - Arrays are joined, with the values of the config files closer to the path placed last.
+	///
+	/// # Errors
+	///
+	/// - If a config file cannot be read.
+	/// - If a config file isn't valid TOML.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use rustilities::cargo_config::CargoConfig;
+	///
+	/// let tempdir = tempfile::tempdir().unwrap();
+	/// let project = tempdir.path().join("project");
+	/// std::fs::create_dir_all(project.join(".cargo")).unwrap();
+	/// std::fs::create_dir_all(tempdir.path().join(".cargo")).unwrap();
+	/// std::fs::write(
+	///     tempdir.path().join(".cargo").join("config.toml"),
+	///     "[build]\nrustflags = [\"-Dwarnings\"]\njobs = 2\n",
+	/// ).unwrap();
+	/// std::fs::write(
+	///     project.join(".cargo").join("config.toml"),
+	///     "[build]\nrustflags = [\"-Copt-level=1\"]\njobs = 4\n",
+	/// ).unwrap();
+	///
+	/// let config = CargoConfig::load(&project).unwrap();
+	///
+	/// assert_eq!(config.get(&["build", "jobs"]).unwrap().as_integer(), Some(4));
+	/// assert_eq!(
+	///     config.get(&["build", "rustflags"]).unwrap().as_array().unwrap().iter()
+	///         .filter_map(|flag| flag.as_str())
+	///         .collect::<Vec<_>>(),
+	///     vec!["-Dwarnings", "-Copt-level=1"]
+	/// );
+	/// assert_eq!(
+	///     config.origin(&["build", "jobs"]),
+	///     Some(project.join(".cargo").join("config.toml").as_path())
+	/// );
+	/// ```
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+		Self::load_with_cargo_home(path.as_ref(), cargo_home())
+	}
+
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	pub(crate) fn load_with_cargo_home(
+		path: &Path,
+		cargo_home: Option<PathBuf>,
+	) -> Result<Self, Error> {
+		let files = do_config_files(path, cargo_home)?
+			.into_iter()
+			.map(|file| {
+				let doc = std::fs::read_to_string(&file)?.parse::<DocumentMut>()?;
+				Ok((file, doc))
+			})
+			.collect::<Result<Vec<_>, Error>>()?;
+
+		let mut merged = Table::new();
+		files.iter().rev().for_each(|(_, doc)| merge(&mut merged, doc.as_table()));
+		Ok(Self { files, merged: Item::Table(merged) })
+	}
+
+	/// The config files contributing to this configuration, from highest to lowest precedence.
+	pub fn files(&self) -> impl Iterator<Item = &Path> {
+		self.files.iter().map(|(file, _)| file.as_path())
+	}
+
+	/// Returns the merged value under the given key path, if any.
+	pub fn get(&self, key_path: &[&str]) -> Option<&Item> {
+		get(&self.merged, key_path)
+	}
+
+	/// Returns the config file with the highest precedence defining the given key path, if any.
+	/// This is useful to resolve relative paths, which cargo resolves against the dir containing
+	/// the `.cargo` dir of the config file defining them.
+	pub fn origin(&self, key_path: &[&str]) -> Option<&Path> {
+		self.files
+			.iter()
+			.find(|(_, doc)| get(doc.as_item(), key_path).is_some())
+			.map(|(file, _)| file.as_path())
+	}
+}
+
+/// Returns the cargo home dir: the `CARGO_HOME` environment variable if it's set, `~/.cargo`
+/// otherwise.
+pub fn cargo_home() -> Option<PathBuf> {
+	std::env::var_os("CARGO_HOME")
+		.map(PathBuf::from)
+		.or_else(|| std::env::home_dir().map(|home| home.join(".cargo")))
+}
+
+/// Given a path, this function returns the cargo config files applicable to it, from highest to
+/// lowest precedence: the config files in the `.cargo` dir of the path and each of its parent
+/// dirs, followed by the config file in the cargo home (see [`cargo_home`]). As cargo does, the
+/// legacy `config` file takes precedence over `config.toml` if a dir contains both.
+///
+/// # Errors
+///
+/// - If the path cannot be made absolute.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let project = tempdir.path().join("project");
+/// std::fs::create_dir_all(project.join(".cargo")).unwrap();
+/// std::fs::write(project.join(".cargo").join("config.toml"), "").unwrap();
+///
+/// assert_eq!(
+///     rustilities::cargo_config::config_files(project.join("src")).unwrap().first(),
+///     Some(&project.join(".cargo").join("config.toml"))
+/// );
+/// ```
+pub fn config_files<P: AsRef<Path>>(path: P) -> Result<Vec<PathBuf>, Error> {
+	do_config_files(path.as_ref(), cargo_home())
+}
+
+fn do_config_files(path: &Path, cargo_home: Option<PathBuf>) -> Result<Vec<PathBuf>, Error> {
+	let absolute_path = std::path::absolute(path)?;
+	let mut config_dirs: Vec<PathBuf> = Vec::new();
+	for config_dir in absolute_path.ancestors().map(|dir| dir.join(".cargo")).chain(cargo_home) {
+		// The cargo home is usually one of the ancestors, no need to include it twice
+		if !config_dirs.contains(&config_dir) {
+			config_dirs.push(config_dir);
+		}
+	}
+
+	Ok(config_dirs
+		.iter()
+		.filter_map(|config_dir| {
+			let config_file = ["config", "config.toml"]
+				.iter()
+				.map(|name| config_dir.join(name))
+				.find(|config_file| config_file.is_file());
+			if config_file.is_none() {
+				debug!(probed = %config_dir.display(), "No cargo config file");
+			}
+			config_file
+		})
+		.collect())
+}
+
+fn get<'a>(item: &'a Item, key_path: &[&str]) -> Option<&'a Item> {
+	key_path.iter().try_fold(item, |item, key| item.get(key))
+}
+
+fn merge(base: &mut dyn TableLike, overlay: &dyn TableLike) {
+	for (key, value) in overlay.iter() {
+		match (base.get_mut(key), value) {
+			(Some(base_value), value) if base_value.is_table_like() && value.is_table_like() => {
+				let base_table = base_value.as_table_like_mut().expect("This is a table; qed;");
+				merge(base_table, value.as_table_like().expect("This is a table; qed;"));
+			},
+			(
+				Some(Item::Value(toml_edit::Value::Array(base_array))),
+				Item::Value(toml_edit::Value::Array(array)),
+			) => {
+				base_array.extend(array.iter().cloned());
+				base_array.fmt();
+			},
+			_ => {
+				base.insert(key, value.clone());
+			},
+		}
+	}
+}
+
+/// Given a cargo config file path, this function adds an alias to its `[alias]` section, replacing
+/// any existing alias with the same name. The file and its parent dirs are created if needed, and
+/// the formatting of an existing file is preserved.
+///
+/// # Errors
+///
+/// - If the file cannot be read or created.
+/// - If the file isn't valid TOML.
+/// - If the `alias` section isn't a table.
+/// - If the file cannot be written.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let config_file = tempdir.path().join(".cargo").join("config.toml");
+///
+/// rustilities::cargo_config::add_alias(&config_file, "xtask", "run --package xtask --").unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&config_file).unwrap(),
+///     "[alias]\nxtask = \"run --package xtask --\"\n"
+/// );
+/// ```
+pub fn add_alias<P: AsRef<Path>>(config_file: P, name: &str, command: &str) -> Result<(), Error> {
+	edit_config_file(config_file.as_ref(), |doc| {
+		get_or_insert_table(doc.as_table_mut(), "alias")?.insert(name, toml_edit::value(command));
+		Ok(())
+	})
+}
+
+/// Given a cargo config file path, this function sets the `rustflags` key of its `[build]` section,
+/// replacing the existing flags. The file and its parent dirs are created if needed, and the
+/// formatting of an existing file is preserved.
+///
+/// # Errors
+///
+/// - If the file cannot be read or created.
+/// - If the file isn't valid TOML.
+/// - If the `build` section isn't a table.
+/// - If the file cannot be written.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let config_file = tempdir.path().join(".cargo").join("config.toml");
+/// std::fs::create_dir_all(tempdir.path().join(".cargo")).unwrap();
+/// std::fs::write(&config_file, "# Build settings\n[build]\njobs = 4\n").unwrap();
+///
+/// rustilities::cargo_config::set_rustflags(&config_file, &["-C", "target-cpu=native"]).unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&config_file).unwrap(),
+///     "# Build settings\n[build]\njobs = 4\nrustflags = [\"-C\", \"target-cpu=native\"]\n"
+/// );
+/// ```
+pub fn set_rustflags<P: AsRef<Path>>(config_file: P, flags: &[&str]) -> Result<(), Error> {
+	edit_config_file(config_file.as_ref(), |doc| {
+		get_or_insert_table(doc.as_table_mut(), "build")?
+			.insert("rustflags", toml_edit::value(flags.iter().copied().collect::<Array>()));
+		Ok(())
+	})
+}
+
+/// The different kinds of sources a source can be replaced with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplacementSource<'a> {
+	/// A dir containing vendored crates, as produced by `cargo vendor`.
+	Directory(&'a Path),
+	/// A dir containing a local registry.
+	LocalRegistry(&'a Path),
+	/// A remote registry, given its index URL.
+	Registry(&'a str),
+}
+
+/// Given a cargo config file path, this function replaces a source (e.g. `crates-io`) with another
+/// one, as described in the
+/// [cargo book](https://doc.rust-lang.org/cargo/reference/source-replacement.html). The
+/// replacement is declared under `[source.<replacement_name>]`, and the replaced source points to
+/// it through its `replace-with` key. The file and its parent dirs are created if needed, and the
+/// formatting of an existing file is preserved.
+///
+/// # Errors
+///
+/// - If the file cannot be read or created.
+/// - If the file isn't valid TOML.
+/// - If the `source` section, or the section of one of the involved sources, isn't a table.
+/// - If the file cannot be written.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::cargo_config::ReplacementSource;
+/// use std::path::Path;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let config_file = tempdir.path().join(".cargo").join("config.toml");
+///
+/// rustilities::cargo_config::add_source_replacement(
+///     &config_file,
+///     "crates-io",
+///     "vendored-sources",
+///     ReplacementSource::Directory(Path::new("vendor")),
+/// ).unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&config_file).unwrap(),
+///     r#"[source.crates-io]
+/// replace-with = "vendored-sources"
+///
+/// [source.vendored-sources]
+/// directory = "vendor"
+/// "#
+/// );
+/// ```
+pub fn add_source_replacement<P: AsRef<Path>>(
+	config_file: P,
+	source: &str,
+	replacement_name: &str,
+	replacement: ReplacementSource,
+) -> Result<(), Error> {
+	edit_config_file(config_file.as_ref(), |doc| {
+		let sources = get_or_insert_table(doc.as_table_mut(), "source")?;
+		sources.set_implicit(true);
+		get_or_insert_table(sources, source)?
+			.insert("replace-with", toml_edit::value(replacement_name));
+
+		set_replacement_source(sources, replacement_name, replacement)
+	})
+}
+
+/// The reference of a git repository a dependency is pinned to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GitReference<'a> {
+	Branch(&'a str),
+	Tag(&'a str),
+	Rev(&'a str),
+}
+
+/// A git source, as declared by a git dependency (`{ git = "...", branch = "..." }`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitSource<'a> {
+	/// The URL of the repository.
+	pub url: &'a str,
+	/// The reference the dependency is pinned to, if any.
+	pub reference: Option<GitReference<'a>>,
+}
+
+impl GitSource<'_> {
+	/// The name cargo gives to the source, eg `git+https://github.com/foo/bar?branch=main`. It's
+	/// the name used by `cargo vendor` to declare the source replacement.
+	pub fn source_name(&self) -> String {
+		match &self.reference {
+			Some(GitReference::Branch(branch)) => format!("git+{}?branch={branch}", self.url),
+			Some(GitReference::Tag(tag)) => format!("git+{}?tag={tag}", self.url),
+			Some(GitReference::Rev(rev)) => format!("git+{}?rev={rev}", self.url),
+			None => format!("git+{}", self.url),
+		}
+	}
+}
+
+/// Given a cargo config file path and some git sources, this function replaces every git source
+/// with another source (typically, the dir produced by `cargo vendor` or a mirror), writing the
+/// same stanzas `cargo vendor` prints, so builds don't need access to the git repositories. See
+/// [`add_source_replacement`]. The replacements can be undone with
+/// [`remove_source_replacements`].
+///
+/// # Errors
+///
+/// - If the file cannot be read or created.
+/// - If the file isn't valid TOML.
+/// - If the `source` section, or the section of one of the involved sources, isn't a table.
+/// - If the file cannot be written.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::cargo_config::{GitReference, GitSource, ReplacementSource};
+/// use std::path::Path;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let config_file = tempdir.path().join(".cargo").join("config.toml");
+///
+/// rustilities::cargo_config::add_git_source_replacements(
+///     &config_file,
+///     &[GitSource {
+///         url: "https://github.com/foo/bar",
+///         reference: Some(GitReference::Branch("main")),
+///     }],
+///     "vendored-sources",
+///     ReplacementSource::Directory(Path::new("vendor")),
+/// ).unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&config_file).unwrap(),
+///     r#"[source."git+https://github.com/foo/bar?branch=main"]
+/// git = "https://github.com/foo/bar"
+/// branch = "main"
+/// replace-with = "vendored-sources"
+///
+/// [source.vendored-sources]
+/// directory = "vendor"
+/// "#
+/// );
+/// ```
+pub fn add_git_source_replacements<P: AsRef<Path>>(
+	config_file: P,
+	git_sources: &[GitSource],
+	replacement_name: &str,
+	replacement: ReplacementSource,
+) -> Result<(), Error> {
+	edit_config_file(config_file.as_ref(), |doc| {
+		let sources = get_or_insert_table(doc.as_table_mut(), "source")?;
+		sources.set_implicit(true);
+		for git_source in git_sources {
+			let source = get_or_insert_table(sources, &git_source.source_name())?;
+			source.clear();
+			source.insert("git", toml_edit::value(git_source.url));
+			match git_source.reference {
+				Some(GitReference::Branch(branch)) =>
+					source.insert("branch", toml_edit::value(branch)),
+				Some(GitReference::Tag(tag)) => source.insert("tag", toml_edit::value(tag)),
+				Some(GitReference::Rev(rev)) => source.insert("rev", toml_edit::value(rev)),
+				None => None,
+			};
+			source.insert("replace-with", toml_edit::value(replacement_name));
+		}
+		set_replacement_source(sources, replacement_name, replacement)
+	})
+}
+
+/// Given a cargo config file path and the name of a replacement source, this function undoes the
+/// source replacements pointing to it (see [`add_source_replacement`] and
+/// [`add_git_source_replacements`]): the replaced sources stop pointing to the replacement, and
+/// the replacement source is removed. The sections of the replaced sources are removed if they
+/// only declared the replacement, as the ones of git sources do. Nothing is done if the file
+/// doesn't exist.
+///
+/// # Errors
+///
+/// - If the file cannot be read.
+/// - If the file isn't valid TOML.
+/// - If the `source` section isn't a table.
+/// - If the file cannot be written.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::cargo_config::ReplacementSource;
+/// use std::path::Path;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let config_file = tempdir.path().join(".cargo").join("config.toml");
+/// std::fs::create_dir_all(tempdir.path().join(".cargo")).unwrap();
+/// std::fs::write(&config_file, "[build]\njobs = 4\n").unwrap();
+///
+/// rustilities::cargo_config::add_source_replacement(
+///     &config_file,
+///     "crates-io",
+///     "vendored-sources",
+///     ReplacementSource::Directory(Path::new("vendor")),
+/// ).unwrap();
+/// rustilities::cargo_config::remove_source_replacements(&config_file, "vendored-sources").unwrap();
+///
+/// assert_eq!(std::fs::read_to_string(&config_file).unwrap(), "[build]\njobs = 4\n");
+/// ```
+pub fn remove_source_replacements<P: AsRef<Path>>(
+	config_file: P,
+	replacement_name: &str,
+) -> Result<(), Error> {
+	let config_file = config_file.as_ref();
+	if !config_file.exists() {
+		return Ok(());
+	}
+	edit_config_file(config_file, |doc| {
+		let Some(sources) = doc.get_mut("source") else {
+			return Ok(());
+		};
+		let sources = sources
+			.as_table_like_mut()
+			.ok_or_else(|| Error::Descriptive("The `source` section isn't a table".to_owned()))?;
+		sources.remove(replacement_name);
+
+		let replaced = sources
+			.iter()
+			.filter(|(_, source)| {
+				source.get("replace-with").and_then(Item::as_str) == Some(replacement_name)
+			})
+			.map(|(name, _)| name.to_owned())
+			.collect::<Vec<_>>();
+		for name in replaced {
+			let Some(source) = sources.get_mut(&name).and_then(Item::as_table_like_mut) else {
+				continue;
+			};
+			source.remove("replace-with");
+			let is_git_source = name.starts_with("git+") &&
+				source.iter().all(|(key, _)| ["git", "branch", "tag", "rev"].contains(&key));
+			if source.is_empty() || is_git_source {
+				sources.remove(&name);
+			}
+		}
+
+		if sources.is_empty() {
+			doc.remove("source");
+		}
+		Ok(())
+	})
+}
+
+fn set_replacement_source(
+	sources: &mut Table,
+	replacement_name: &str,
+	replacement: ReplacementSource,
+) -> Result<(), Error> {
+	let replacement_table = get_or_insert_table(sources, replacement_name)?;
+	["directory", "local-registry", "registry"].iter().for_each(|key| {
+		replacement_table.remove(key);
+	});
+	let (key, value) = match replacement {
+		ReplacementSource::Directory(path) => ("directory", path.to_string_lossy()),
+		ReplacementSource::LocalRegistry(path) => ("local-registry", path.to_string_lossy()),
+		ReplacementSource::Registry(url) => ("registry", url.into()),
+	};
+	replacement_table.insert(key, toml_edit::value(value.as_ref()));
+	Ok(())
+}
+
+fn get_or_insert_table<'a>(table: &'a mut Table, key: &str) -> Result<&'a mut Table, Error> {
+	table
+		.entry(key)
+		.or_insert_with(|| Item::Table(Table::new()))
+		.as_table_mut()
+		.ok_or_else(|| Error::Descriptive(format!("The `{key}` section isn't a table")))
+}
+
+fn edit_config_file<F>(config_file: &Path, edit: F) -> Result<(), Error>
+where
+	F: FnOnce(&mut DocumentMut) -> Result<(), Error>,
+{
+	let mut doc = match std::fs::read_to_string(config_file) {
+		Ok(content) => content.parse::<DocumentMut>()?,
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => DocumentMut::new(),
+		Err(err) => return Err(err.into()),
+	};
+	edit(&mut doc)?;
+
+	if let Some(parent) = config_file.parent() {
+		std::fs::create_dir_all(parent)?;
+	}
+	debug!(path = %config_file.display(), "Writing cargo config");
+	std::fs::write(config_file, doc.to_string())?;
+	Ok(())
+}
@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+// Creates a tempdir containing a `project/src` dir, with the given config files (path relative to
+// the tempdir, content)
+fn project_with_configs(configs: &[(&str, &str)]) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::create_dir_all(tempdir.path().join("project").join("src"))
+		.expect("This should be created; qed;");
+	configs.iter().for_each(|(path, content)| {
+		let path = tempdir.path().join(path);
+		std::fs::create_dir_all(path.parent().expect("This should have a parent; qed;"))
+			.expect("This should be created; qed;");
+		std::fs::write(path, content).expect("The config should be writable; qed;");
+	});
+	tempdir
+}
+
+#[test]
+fn config_files_are_sorted_by_precedence() {
+	let tempdir = project_with_configs(&[
+		(".cargo/config.toml", ""),
+		("project/.cargo/config", ""),
+		("project/.cargo/config.toml", ""),
+		("home/.cargo/config.toml", ""),
+	]);
+	let cargo_home = tempdir.path().join("home").join(".cargo");
+
+	let files = do_config_files(&tempdir.path().join("project").join("src"), Some(cargo_home))
+		.expect("This should be Ok; qed;");
+
+	assert_eq!(
+		files,
+		vec![
+			tempdir.path().join("project/.cargo/config"),
+			tempdir.path().join(".cargo/config.toml"),
+			tempdir.path().join("home/.cargo/config.toml"),
+		]
+	);
+}
+
+#[test]
+fn config_files_doesnt_duplicate_cargo_home() {
+	let tempdir = project_with_configs(&[(".cargo/config.toml", "")]);
+
+	let files =
+		do_config_files(&tempdir.path().join("project"), Some(tempdir.path().join(".cargo")))
+			.expect("This should be Ok; qed;");
+
+	assert_eq!(files, vec![tempdir.path().join(".cargo/config.toml")]);
+}
+
+#[test]
+fn load_merges_config_files() {
+	let tempdir = project_with_configs(&[
+		(
+			"home/.cargo/config.toml",
+			"[alias]\nb = \"build\"\n\n[build]\nrustflags = [\"-Dwarnings\"]\ntarget-dir = \"home\"",
+		),
+		(
+			"project/.cargo/config.toml",
+			"[build]\nrustflags = [\"-Copt-level=1\"]\ntarget-dir = \"project\"\n\n[env]\nKEY = { value = \"a\", force = true }",
+		),
+		(".cargo/config.toml", "[env]\nKEY = { value = \"b\", relative = true }"),
+	]);
+
+	let config = CargoConfig::load_with_cargo_home(
+		&tempdir.path().join("project"),
+		Some(tempdir.path().join("home").join(".cargo")),
+	)
+	.expect("This should be Ok; qed;");
+
+	assert_eq!(config.files().count(), 3);
+	assert_eq!(config.get(&["alias", "b"]).and_then(Item::as_str), Some("build"));
+	assert_eq!(config.get(&["build", "target-dir"]).and_then(Item::as_str), Some("project"));
+	assert_eq!(
+		config
+			.get(&["build", "rustflags"])
+			.and_then(Item::as_array)
+			.map(|flags| flags.iter().filter_map(|flag| flag.as_str()).collect::<Vec<_>>()),
+		Some(vec!["-Dwarnings", "-Copt-level=1"])
+	);
+	assert_eq!(config.get(&["env", "KEY", "value"]).and_then(Item::as_str), Some("a"));
+	assert_eq!(config.get(&["env", "KEY", "force"]).and_then(Item::as_bool), Some(true));
+	assert_eq!(config.get(&["env", "KEY", "relative"]).and_then(Item::as_bool), Some(true));
+	assert!(config.get(&["build", "jobs"]).is_none());
+
+	assert_eq!(
+		config.origin(&["alias", "b"]),
+		Some(tempdir.path().join("home/.cargo/config.toml").as_path())
+	);
+	assert_eq!(
+		config.origin(&["env", "KEY"]),
+		Some(tempdir.path().join("project/.cargo/config.toml").as_path())
+	);
+	assert_eq!(config.origin(&["build", "jobs"]), None);
+}
+
+#[test]
+fn load_fails_if_config_file_isnt_valid() {
+	let tempdir = project_with_configs(&[("project/.cargo/config.toml", "[build")]);
+
+	assert!(matches!(
+		CargoConfig::load_with_cargo_home(&tempdir.path().join("project"), None),
+		Err(Error::TomlEdit(_))
+	));
+}
+
+#[test]
+fn edits_preserve_existing_content() {
+	let tempdir = project_with_configs(&[(
+		"project/.cargo/config.toml",
+		"# Shortcuts\n[alias]\nb = \"build\" # Build\n\n[build]\nrustflags = \"-Dwarnings\"\n",
+	)]);
+	let config_file = tempdir.path().join("project/.cargo/config.toml");
+
+	add_alias(&config_file, "t", "test --all-features").expect("This should be Ok; qed;");
+	set_rustflags(&config_file, &["-Copt-level=1"]).expect("This should be Ok; qed;");
+	add_source_replacement(
+		&config_file,
+		"crates-io",
+		"mirror",
+		ReplacementSource::Registry("sparse+https://mirror.example.com/index/"),
+	)
+	.expect("This should be Ok; qed;");
+	// Replacing the source again only keeps the new kind of source
+	add_source_replacement(
+		&config_file,
+		"crates-io",
+		"mirror",
+		ReplacementSource::LocalRegistry(Path::new("registry")),
+	)
+	.expect("This should be Ok; qed;");
+
+	assert_eq!(
+		std::fs::read_to_string(&config_file).expect("This should be readable; qed;"),
+		r#"# Shortcuts
+[alias]
+b = "build" # Build
+t = "test --all-features"
+
+[build]
+rustflags = ["-Copt-level=1"]
+
+[source.crates-io]
+replace-with = "mirror"
+
+[source.mirror]
+local-registry = "registry"
+"#
+	);
+}
+
+#[test]
+fn edits_fail_if_section_isnt_a_table() {
+	let tempdir = project_with_configs(&[("project/.cargo/config.toml", "alias = 1\nbuild = 1\n")]);
+	let config_file = tempdir.path().join("project/.cargo/config.toml");
+
+	assert!(matches!(
+		add_alias(&config_file, "b", "build"),
+		Err(Error::Descriptive(msg)) if msg == "The `alias` section isn't a table"
+	));
+	assert!(matches!(
+		set_rustflags(&config_file, &[]),
+		Err(Error::Descriptive(msg)) if msg == "The `build` section isn't a table"
+	));
+}
+
+#[test]
+fn git_source_replacements_can_be_added_and_removed() {
+	let tempdir = project_with_configs(&[(
+		"project/.cargo/config.toml",
+		"[source.crates-io]\nreplace-with = \"vendored-sources\"\n\n[source.my-registry]\nregistry = \"https://example.com/index\"\nreplace-with = \"vendored-sources\"\n",
+	)]);
+	let config_file = tempdir.path().join("project/.cargo/config.toml");
+
+	add_git_source_replacements(
+		&config_file,
+		&[
+			GitSource { url: "https://github.com/foo/bar", reference: None },
+			GitSource {
+				url: "https://github.com/foo/baz",
+				reference: Some(GitReference::Tag("v1.0.0")),
+			},
+			GitSource {
+				url: "https://github.com/foo/baz",
+				reference: Some(GitReference::Rev("abc123")),
+			},
+		],
+		"vendored-sources",
+		ReplacementSource::Directory(Path::new("vendor")),
+	)
+	.expect("This should be Ok; qed;");
+	assert_eq!(
+		std::fs::read_to_string(&config_file).expect("This should be readable; qed;"),
+		r#"[source.crates-io]
+replace-with = "vendored-sources"
+
+[source.my-registry]
+registry = "https://example.com/index"
+replace-with = "vendored-sources"
+
+[source."git+https://github.com/foo/bar"]
+git = "https://github.com/foo/bar"
+replace-with = "vendored-sources"
+
+[source."git+https://github.com/foo/baz?tag=v1.0.0"]
+git = "https://github.com/foo/baz"
+tag = "v1.0.0"
+replace-with = "vendored-sources"
+
+[source."git+https://github.com/foo/baz?rev=abc123"]
+git = "https://github.com/foo/baz"
+rev = "abc123"
+replace-with = "vendored-sources"
+
+[source.vendored-sources]
+directory = "vendor"
+"#
+	);
+
+	remove_source_replacements(&config_file, "vendored-sources").expect("This should be Ok; qed;");
+	assert_eq!(
+		std::fs::read_to_string(&config_file).expect("This should be readable; qed;"),
+		"\n[source.my-registry]\nregistry = \"https://example.com/index\"\n"
+	);
+
+	let missing_file = tempdir.path().join("missing/config.toml");
+	remove_source_replacements(&missing_file, "vendored-sources").expect("This should be Ok; qed;");
+	assert!(!missing_file.exists());
+}
@@ -0,0 +1,422 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! Utilities to manipulate changelogs following the [Keep a
+//! Changelog](https://keepachangelog.com) format: adding entries to the `Unreleased` section and
+//! releasing it under a version.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{Error, macros::debug};
+use std::{fmt, path::Path};
+
+/// The kinds of changes defined by Keep a Changelog, in the order their sections are listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+	Added,
+	Changed,
+	Deprecated,
+	Removed,
+	Fixed,
+	Security,
+}
+
+impl ChangeKind {
+	const ALL: [ChangeKind; 6] =
+		[Self::Added, Self::Changed, Self::Deprecated, Self::Removed, Self::Fixed, Self::Security];
+
+	fn from_title(title: &str) -> Option<Self> {
+		Self::ALL.into_iter().find(|kind| kind.to_string() == title)
+	}
+}
+
+impl fmt::Display for ChangeKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let title = match self {
+			Self::Added => "Added",
+			Self::Changed => "Changed",
+			Self::Deprecated => "Deprecated",
+			Self::Removed => "Removed",
+			Self::Fixed => "Fixed",
+			Self::Security => "Security",
+		};
+		f.write_str(title)
+	}
+}
+
+/// A `### Title` section of a release, listing its entries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeSection {
+	/// The title of the section, usually a [`ChangeKind`].
+	pub title: String,
+	/// The entries of the section, without their bullet. Entries spanning several lines keep their
+	/// line breaks.
+	pub entries: Vec<String>,
+}
+
+/// A `## ...` section of a changelog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+	/// The released version, or `None` for the `Unreleased` section.
+	pub version: Option<String>,
+	/// The release date, as written in the changelog.
+	pub date: Option<String>,
+	/// The text between the release heading and its first section.
+	pub description: String,
+	/// The sections of the release.
+	pub sections: Vec<ChangeSection>,
+}
+
+impl Release {
+	fn unreleased() -> Self {
+		Self { version: None, date: None, description: String::new(), sections: Vec::new() }
+	}
+}
+
+/// A parsed changelog. Rendering it with [`Display`](fmt::Display) produces a normalized version
+/// of the original document: blank lines are normalized, but the content is kept.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::changelog::{ChangeKind, Changelog};
+///
+/// let mut changelog: Changelog = r#"# Changelog
+///
+/// ### [Unreleased]
+///
+/// #### Fixed
+///
+/// - A bug.
+///
+/// ### [1.0.0] - 2025-01-01
+///
+/// #### Added
+///
+/// - Everything.
+/// "#
+/// .parse()
+/// .unwrap();
+///
+/// changelog.add_entry(ChangeKind::Added, "A feature.");
+/// changelog.release("1.1.0", "2025-02-01").unwrap();
+///
+/// assert_eq!(
+///     changelog.to_string(),
+///     r#"# Changelog
+///
+/// ### [Unreleased]
+///
+/// ### [1.1.0] - 2025-02-01
+///
+/// #### Added
+///
+/// - A feature.
+///
+/// #### Fixed
+///
+/// - A bug.
+///
+/// ### [1.0.0] - 2025-01-01
+///
+/// #### Added
+///
+/// - Everything.
+/// "#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Changelog {
+	/// The text before the first release, usually the title and an introduction.
+	pub preamble: String,
+	/// The releases, newest first.
+	pub releases: Vec<Release>,
+	/// The link reference definitions found after the first release, eg, `[1.0.0]: https://...`.
+	pub links: Vec<String>,
+}
+
+impl std::str::FromStr for Changelog {
+	type Err = Error;
+
+	fn from_str(content: &str) -> Result<Self, Self::Err> {
+		let mut preamble = Vec::new();
+		let mut releases: Vec<Release> = Vec::new();
+		let mut links = Vec::new();
+		// Whether the last line was part of an entry, so indented lines continue it
+		let mut in_entry = false;
+
+		for line in content.lines() {
+			let trimmed = line.trim();
+			if let Some(heading) = line.strip_prefix("## ") {
+				releases.push(parse_release_heading(heading)?);
+				in_entry = false;
+				continue;
+			}
+			let Some(release) = releases.last_mut() else {
+				preamble.push(line);
+				continue;
+			};
+
+			if is_link_definition(trimmed) {
+				links.push(trimmed.to_owned());
+				in_entry = false;
+			} else if let Some(title) = line.strip_prefix("### ") {
+				release
+					.sections
+					.push(ChangeSection { title: title.trim().to_owned(), entries: Vec::new() });
+				in_entry = false;
+			} else if trimmed.is_empty() {
+				in_entry = false;
+			} else if let Some(section) = release.sections.last_mut() {
+				match line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+					Some(entry) => {
+						section.entries.push(entry.trim_end().to_owned());
+						in_entry = true;
+					},
+					None => match section.entries.last_mut() {
+						Some(entry) if in_entry || line.starts_with(char::is_whitespace) => {
+							entry.push('\n');
+							entry.push_str(line.trim_end());
+						},
+						_ => {
+							section.entries.push(trimmed.to_owned());
+							in_entry = true;
+						},
+					},
+				}
+			} else {
+				if !release.description.is_empty() {
+					release.description.push('\n');
+				}
+				release.description.push_str(line.trim_end());
+			}
+		}
+
+		Ok(Self { preamble: preamble.join("\n").trim().to_owned(), releases, links })
+	}
+}
+
+impl fmt::Display for Changelog {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut blocks = Vec::new();
+		if !self.preamble.is_empty() {
+			blocks.push(self.preamble.clone());
+		}
+		for release in &self.releases {
+			blocks.push(match (&release.version, &release.date) {
+				(None, _) => "## [Unreleased]".to_owned(),
+				(Some(version), None) => format!("## [{version}]"),
+				(Some(version), Some(date)) => format!("## [{version}] - {date}"),
+			});
+			if !release.description.is_empty() {
+				blocks.push(release.description.clone());
+			}
+			for section in &release.sections {
+				blocks.push(format!("### {}", section.title));
+				if !section.entries.is_empty() {
+					blocks.push(
+						section
+							.entries
+							.iter()
+							.map(|entry| format!("- {entry}"))
+							.collect::<Vec<_>>()
+							.join("\n"),
+					);
+				}
+			}
+		}
+		if !self.links.is_empty() {
+			blocks.push(self.links.join("\n"));
+		}
+		writeln!(f, "{}", blocks.join("\n\n"))
+	}
+}
+
+impl Changelog {
+	/// Reads and parses the changelog at the given path.
+	///
+	/// # Errors
+	///
+	/// - If the path cannot be read.
+	/// - If a release heading doesn't specify a version.
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+		std::fs::read_to_string(path)?.parse()
+	}
+
+	/// Writes the changelog to the given path.
+	///
+	/// # Errors
+	///
+	/// - If the path cannot be written.
+	pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+		debug!(path = %path.as_ref().display(), "Writing changelog");
+		std::fs::write(path, self.to_string())?;
+		Ok(())
+	}
+
+	/// The `Unreleased` section of the changelog, if any.
+	pub fn unreleased(&self) -> Option<&Release> {
+		self.releases.iter().find(|release| release.version.is_none())
+	}
+
+	/// The release of the given version, if any.
+	pub fn release_of(&self, version: &str) -> Option<&Release> {
+		self.releases.iter().find(|release| release.version.as_deref() == Some(version))
+	}
+
+	/// Adds an entry to the `Unreleased` section, creating it at the top of the changelog if
+	/// needed. New sections are inserted following the order of [`ChangeKind`].
+	pub fn add_entry(&mut self, kind: ChangeKind, entry: &str) {
+		let index = match self.releases.iter().position(|release| release.version.is_none()) {
+			Some(index) => index,
+			None => {
+				self.releases.insert(0, Release::unreleased());
+				0
+			},
+		};
+		let sections = &mut self.releases[index].sections;
+		let title = kind.to_string();
+		let section = match sections.iter().position(|section| section.title == title) {
+			Some(position) => &mut sections[position],
+			None => {
+				let position = sections
+					.iter()
+					.position(|section| {
+						ChangeKind::from_title(&section.title).is_some_and(|other| other > kind)
+					})
+					.unwrap_or(sections.len());
+				sections.insert(position, ChangeSection { title, entries: Vec::new() });
+				&mut sections[position]
+			},
+		};
+		section.entries.push(entry.to_owned());
+	}
+
+	/// Releases the `Unreleased` section under the given version and date, leaving an empty
+	/// `Unreleased` section on top of it.
+	///
+	/// If the changelog defines an `[unreleased]` link comparing a tag with `HEAD` (eg,
+	/// `https://github.com/owner/repo/compare/v1.0.0...HEAD`), it's updated to start at the new
+	/// release, and a link comparing the previous tag with the new one is added. The new tag reuses
+	/// the prefix of the previous one (eg, `v`).
+	///
+	/// # Errors
+	///
+	/// - If the changelog doesn't contain an `Unreleased` section with entries.
+	/// - If the version is already released.
+	pub fn release(&mut self, version: &str, date: &str) -> Result<(), Error> {
+		if self.release_of(version).is_some() {
+			return Err(Error::Descriptive(format!("The version {version} is already released")));
+		}
+		let Some(index) = self
+			.releases
+			.iter()
+			.position(|release| release.version.is_none() && !release.sections.is_empty())
+		else {
+			return Err(Error::Descriptive("There aren't unreleased changes".to_owned()));
+		};
+
+		let release = &mut self.releases[index];
+		release.version = Some(version.to_owned());
+		release.date = Some(date.to_owned());
+		self.releases.insert(index, Release::unreleased());
+		self.update_links(version);
+		Ok(())
+	}
+
+	fn update_links(&mut self, version: &str) {
+		let Some(position) = self.links.iter().position(|link| {
+			link.to_lowercase().starts_with("[unreleased]:") && link.ends_with("...HEAD")
+		}) else {
+			return;
+		};
+		let url = self.links[position]["[unreleased]:".len()..].trim();
+		let Some((base, previous_tag)) = url
+			.strip_suffix("...HEAD")
+			.and_then(|compare| compare.rsplit_once('/'))
+			.map(|(base, previous_tag)| (base.to_owned(), previous_tag.to_owned()))
+		else {
+			return;
+		};
+
+		let prefix = self
+			.releases
+			.iter()
+			.filter_map(|release| release.version.as_deref())
+			.find_map(|version| previous_tag.strip_suffix(version))
+			.unwrap_or_default();
+		let tag = format!("{prefix}{version}");
+		self.links[position] = format!("[unreleased]: {base}/{tag}...HEAD");
+		self.links
+			.insert(position + 1, format!("[{version}]: {base}/{previous_tag}...{tag}"));
+	}
+}
+
+/// Given a changelog path, a kind of change and an entry, this function adds the entry to the
+/// `Unreleased` section of the changelog, as [`Changelog::add_entry`] does.
+///
+/// # Errors
+///
+/// - If the changelog cannot be loaded, see [`Changelog::load`].
+/// - If the path cannot be overwritten.
+pub fn add_unreleased_entry<P: AsRef<Path>>(
+	changelog_path: P,
+	kind: ChangeKind,
+	entry: &str,
+) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_add_unreleased_entry(
+		changelog_path: &Path,
+		kind: ChangeKind,
+		entry: &str,
+	) -> Result<(), Error> {
+		let mut changelog = Changelog::load(changelog_path)?;
+		changelog.add_entry(kind, entry);
+		changelog.write(changelog_path)
+	}
+	do_add_unreleased_entry(changelog_path.as_ref(), kind, entry)
+}
+
+/// Given a changelog path, a version and a date, this function releases the `Unreleased` section
+/// of the changelog, as [`Changelog::release`] does.
+///
+/// # Errors
+///
+/// - If the changelog cannot be loaded, see [`Changelog::load`].
+/// - If the section cannot be released, see [`Changelog::release`].
+/// - If the path cannot be overwritten.
+pub fn release<P: AsRef<Path>>(changelog_path: P, version: &str, date: &str) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_release(changelog_path: &Path, version: &str, date: &str) -> Result<(), Error> {
+		let mut changelog = Changelog::load(changelog_path)?;
+		changelog.release(version, date)?;
+		changelog.write(changelog_path)
+	}
+	do_release(changelog_path.as_ref(), version, date)
+}
+
+fn parse_release_heading(heading: &str) -> Result<Release, Error> {
+	let (name, date) = match heading.split_once(" - ") {
+		Some((name, date)) => (name, Some(date.trim().to_owned())),
+		None => (heading, None),
+	};
+	let name = name.trim().trim_start_matches('[').trim_end_matches(']').trim();
+	if name.is_empty() {
+		return Err(Error::Descriptive(format!(
+			"The release heading `## {heading}` doesn't specify a version"
+		)));
+	}
+	if name.eq_ignore_ascii_case("unreleased") {
+		return Ok(Release::unreleased());
+	}
+	Ok(Release {
+		version: Some(name.to_owned()),
+		date,
+		description: String::new(),
+		sections: Vec::new(),
+	})
+}
+
+fn is_link_definition(line: &str) -> bool {
+	line.starts_with('[') && line.split_once("]:").is_some_and(|(label, _)| !label.contains(']'))
+}
@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+const CHANGELOG: &str = r#"# Changelog
+
+All notable changes to this project will be documented in this file.
+
+## [Unreleased]
+### Fixed
+- A bug.
+* Another bug, whose description
+  spans several lines.
+
+## [1.0.0] - 2025-01-01
+
+The first release.
+
+### Added
+
+- Everything.
+
+### Other
+Some note.
+
+[unreleased]: https://github.com/owner/repo/compare/v1.0.0...HEAD
+[1.0.0]: https://github.com/owner/repo/releases/tag/v1.0.0
+"#;
+
+#[test]
+fn parse_changelog_works() {
+	let changelog: Changelog = CHANGELOG.parse().expect("This should be Ok; qed;");
+
+	assert_eq!(
+		changelog.preamble,
+		"# Changelog\n\nAll notable changes to this project will be documented in this file."
+	);
+	assert_eq!(
+		changelog.unreleased(),
+		Some(&Release {
+			version: None,
+			date: None,
+			description: String::new(),
+			sections: vec![ChangeSection {
+				title: "Fixed".to_owned(),
+				entries: vec![
+					"A bug.".to_owned(),
+					"Another bug, whose description\n  spans several lines.".to_owned()
+				]
+			}]
+		})
+	);
+	assert_eq!(
+		changelog.release_of("1.0.0"),
+		Some(&Release {
+			version: Some("1.0.0".to_owned()),
+			date: Some("2025-01-01".to_owned()),
+			description: "The first release.".to_owned(),
+			sections: vec![
+				ChangeSection {
+					title: "Added".to_owned(),
+					entries: vec!["Everything.".to_owned()]
+				},
+				ChangeSection { title: "Other".to_owned(), entries: vec!["Some note.".to_owned()] }
+			]
+		})
+	);
+	assert_eq!(changelog.links.len(), 2);
+}
+
+#[test]
+fn changelog_rendering_roundtrips() {
+	let changelog: Changelog = CHANGELOG.parse().expect("This should be Ok; qed;");
+	let rendered = changelog.to_string();
+
+	assert_eq!(rendered.parse::<Changelog>().expect("This should be Ok; qed;"), changelog);
+	assert_eq!(
+		rendered,
+		r#"# Changelog
+
+All notable changes to this project will be documented in this file.
+
+## [Unreleased]
+
+### Fixed
+
+- A bug.
+- Another bug, whose description
+  spans several lines.
+
+## [1.0.0] - 2025-01-01
+
+The first release.
+
+### Added
+
+- Everything.
+
+### Other
+
+- Some note.
+
+[unreleased]: https://github.com/owner/repo/compare/v1.0.0...HEAD
+[1.0.0]: https://github.com/owner/repo/releases/tag/v1.0.0
+"#
+	);
+}
+
+#[test]
+fn add_entry_creates_unreleased_section_and_sorts_sections() {
+	let mut changelog: Changelog = "# Changelog\n\n## [1.0.0]\n\n### Added\n\n- Everything.\n"
+		.parse()
+		.expect("This should be Ok; qed;");
+
+	changelog.add_entry(ChangeKind::Security, "A vulnerability.");
+	changelog.add_entry(ChangeKind::Changed, "Something.");
+	changelog.add_entry(ChangeKind::Security, "Another vulnerability.");
+
+	assert_eq!(changelog.releases.len(), 2);
+	assert_eq!(
+		changelog.unreleased().map(|release| release
+			.sections
+			.iter()
+			.map(|section| (section.title.as_str(), section.entries.len()))
+			.collect::<Vec<_>>()),
+		Some(vec![("Changed", 1), ("Security", 2)])
+	);
+}
+
+#[test]
+fn release_updates_compare_links() {
+	let mut changelog: Changelog = CHANGELOG.parse().expect("This should be Ok; qed;");
+
+	changelog.release("1.1.0", "2025-02-01").expect("This should be Ok; qed;");
+
+	assert_eq!(changelog.releases.len(), 3);
+	assert!(changelog.unreleased().is_some_and(|release| release.sections.is_empty()));
+	assert_eq!(
+		changelog.release_of("1.1.0").and_then(|release| release.date.as_deref()),
+		Some("2025-02-01")
+	);
+	assert_eq!(
+		changelog.links,
+		vec![
+			"[unreleased]: https://github.com/owner/repo/compare/v1.1.0...HEAD",
+			"[1.1.0]: https://github.com/owner/repo/compare/v1.0.0...v1.1.0",
+			"[1.0.0]: https://github.com/owner/repo/releases/tag/v1.0.0",
+		]
+	);
+}
+
+#[test]
+fn release_fails_without_unreleased_changes_or_if_version_exists() {
+	let mut changelog: Changelog = CHANGELOG.parse().expect("This should be Ok; qed;");
+
+	assert!(matches!(
+		changelog.release("1.0.0", "2025-02-01"),
+		Err(Error::Descriptive(msg)) if msg == "The version 1.0.0 is already released"
+	));
+	changelog.release("1.1.0", "2025-02-01").expect("This should be Ok; qed;");
+	assert!(matches!(
+		changelog.release("1.2.0", "2025-02-01"),
+		Err(Error::Descriptive(msg)) if msg == "There aren't unreleased changes"
+	));
+}
+
+#[test]
+fn parse_fails_if_release_heading_doesnt_have_version() {
+	assert!(matches!(
+		"## [] - 2025-01-01".parse::<Changelog>(),
+		Err(Error::Descriptive(msg)) if msg == "The release heading `## [] - 2025-01-01` doesn't specify a version"
+	));
+}
+
+#[test]
+fn add_unreleased_entry_and_release_edit_files() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let changelog_path = tempdir.path().join("CHANGELOG.md");
+	std::fs::write(&changelog_path, "# Changelog\n").expect("The file should be writable; qed;");
+
+	add_unreleased_entry(&changelog_path, ChangeKind::Added, "A feature.")
+		.expect("This should be Ok; qed;");
+	release(&changelog_path, "0.1.0", "2025-01-01").expect("This should be Ok; qed;");
+
+	assert_eq!(
+		std::fs::read_to_string(&changelog_path).expect("This should be Ok; qed;"),
+		"# Changelog\n\n## [Unreleased]\n\n## [0.1.0] - 2025-01-01\n\n### Added\n\n- A feature.\n"
+	);
+	assert!(matches!(
+		release(tempdir.path().join("unexisting.md"), "0.1.0", "2025-01-01"),
+		Err(Error::IO(err)) if err.kind() == std::io::ErrorKind::NotFound
+	));
+}
@@ -0,0 +1,148 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! This module ties together the [`parsing`](crate::parsing), [`manifest`](crate::manifest),
+//! [`paths`](crate::paths) and [`fmt`](crate::fmt) modules to apply generated code to a crate or
+//! workspace in one go: a [`Plan`] lists the operations to run, and [`apply`] runs all of them or
+//! none.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{Error, macros::debug, manifest::ManifestDependencyConfig, paths::EditSession};
+use quote::ToTokens;
+use std::path::{Path, PathBuf};
+use syn::{Item, Visibility};
+
+/// An operation of a [`Plan`]. Relative paths are resolved against the root of the plan.
+#[derive(Debug, Clone)]
+pub enum Operation<'a> {
+	/// Creates a file with the given contents, creating its parent dirs if needed. The file
+	/// mustn't exist yet.
+	CreateFile { path: PathBuf, contents: String },
+	/// Appends an item at the end of a Rust file.
+	InsertItem { file: PathBuf, item: Box<Item> },
+	/// Declares a module in a Rust file and creates its module file, as
+	/// [`add_mod_declaration`](crate::parsing::add_mod_declaration) does.
+	AddModDeclaration { file: PathBuf, name: String, visibility: Visibility },
+	/// Adds a dependency to a manifest, as
+	/// [`add_crate_to_dependencies`](crate::manifest::add_crate_to_dependencies) does.
+	AddDependency { manifest: PathBuf, name: String, config: ManifestDependencyConfig<'a> },
+	/// Adds a feature to a manifest, as [`add_feature`](crate::manifest::add_feature) does.
+	AddFeature { manifest: PathBuf, name: String, enables: Vec<String> },
+}
+
+/// A list of operations applied to the crate or workspace living in `root` by [`apply`].
+#[derive(Debug, Clone)]
+pub struct Plan<'a> {
+	/// The dir of the crate or workspace.
+	pub root: PathBuf,
+	/// The operations, run in order.
+	pub operations: Vec<Operation<'a>>,
+}
+
+/// Given a [`Plan`], this function runs its operations in order and then formats the root of the
+/// plan with [`format_dir`](crate::fmt::format_dir), so the generated code doesn't need to be
+/// formatted by hand.
+///
+/// The plan is applied transactionally within an [`EditSession`]: the original contents of every
+/// file are recorded before an operation touches it, so if an operation or the final formatting
+/// fails, the files are restored, and the files and dirs created by the plan are removed, before
+/// returning the error.
+///
+/// ## Errors:
+/// - If some operation fails. [`Operation::CreateFile`] fails if the file exists and
+///   [`Operation::InsertItem`] if the file isn't valid Rust code, the errors of the other
+///   operations are described in the functions they call.
+/// - If the root cannot be formatted.
+/// - If the changes cannot be rolled back after a failure. The error describes both failures.
+///
+/// # Example
+///
+/// ```no_run
+/// use rustilities::codegen::{Operation, Plan, apply};
+/// use syn::parse_quote;
+///
+/// let plan = Plan {
+///     root: "my_crate".into(),
+///     operations: vec![
+///         Operation::AddModDeclaration {
+///             file: "src/lib.rs".into(),
+///             name: "generated".to_owned(),
+///             visibility: parse_quote!(pub),
+///         },
+///         Operation::InsertItem {
+///             file: "src/generated.rs".into(),
+///             item: Box::new(parse_quote! { pub fn answer() -> u8 { 42 } }),
+///         },
+///         Operation::AddFeature {
+///             manifest: "Cargo.toml".into(),
+///             name: "generated".to_owned(),
+///             enables: vec![],
+///         },
+///     ],
+/// };
+///
+/// apply(plan).unwrap();
+/// ```
+pub fn apply(plan: Plan) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_apply(plan: Plan) -> Result<(), Error> {
+		EditSession::run(|session| {
+			for operation in plan.operations {
+				run_operation(&plan.root, operation, session)?;
+			}
+			crate::fmt::format_dir(&plan.root)
+		})
+	}
+	do_apply(plan)
+}
+
+fn run_operation(
+	root: &Path,
+	operation: Operation,
+	session: &mut EditSession,
+) -> Result<(), Error> {
+	match operation {
+		Operation::CreateFile { path, contents } => {
+			let path = root.join(path);
+			if path.exists() {
+				return Err(Error::Descriptive(format!("{} already exists", path.display())));
+			}
+			session.write(&path, contents)
+		},
+		Operation::InsertItem { file, item } => {
+			let file = root.join(file);
+			session.track(&file)?;
+			let content = std::fs::read_to_string(&file)?;
+			syn::parse_file(&content)?;
+			let item = item.into_token_stream();
+			let content = match content.trim_end() {
+				"" => format!("{item}\n"),
+				content => format!("{content}\n\n{item}\n"),
+			};
+			debug!(path = %file.display(), "Writing source file");
+			std::fs::write(&file, content)?;
+			Ok(())
+		},
+		Operation::AddModDeclaration { file, name, visibility } => {
+			let file = root.join(file);
+			session.track(&file)?;
+			session.track(crate::parsing::module_dir(&file).join(format!("{name}.rs")))?;
+			crate::parsing::add_mod_declaration(&file, &name, &visibility).map(|_| ())
+		},
+		Operation::AddDependency { manifest, name, config } => {
+			let manifest = root.join(manifest);
+			session.track(&manifest)?;
+			crate::manifest::add_crate_to_dependencies(&manifest, &name, config)
+		},
+		Operation::AddFeature { manifest, name, enables } => {
+			let manifest = root.join(manifest);
+			session.track(&manifest)?;
+			crate::manifest::add_feature(
+				&manifest,
+				&name,
+				&enables.iter().map(String::as_str).collect::<Vec<_>>(),
+			)
+		},
+	}
+}
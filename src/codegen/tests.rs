@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use crate::manifest::ManifestDependencyOrigin;
+use syn::parse_quote;
+use tempfile::TempDir;
+
+const MANIFEST: &str = "[package]\nname = \"test\"\nversion = \"0.1.0\"\nedition = \"2024\"\n";
+const LIB: &str = "pub mod a;\n";
+
+fn crate_dir() -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::create_dir(tempdir.path().join("src")).expect("The dir should be created; qed;");
+	std::fs::write(tempdir.path().join("Cargo.toml"), MANIFEST)
+		.expect("The file should be writable; qed;");
+	std::fs::write(tempdir.path().join("src/lib.rs"), LIB)
+		.expect("The file should be writable; qed;");
+	std::fs::write(tempdir.path().join("src/a.rs"), "").expect("The file should be writable; qed;");
+	tempdir
+}
+
+fn read(tempdir: &TempDir, path: &str) -> String {
+	std::fs::read_to_string(tempdir.path().join(path)).expect("The file should be readable; qed;")
+}
+
+#[test]
+fn apply_runs_every_operation_and_formats_the_crate() {
+	let tempdir = crate_dir();
+	let plan = Plan {
+		root: tempdir.path().to_path_buf(),
+		operations: vec![
+			Operation::CreateFile {
+				path: "src/b/c.rs".into(),
+				contents: "pub   struct C;".to_owned(),
+			},
+			Operation::AddModDeclaration {
+				file: "src/lib.rs".into(),
+				name: "b".to_owned(),
+				visibility: parse_quote!(pub),
+			},
+			Operation::AddModDeclaration {
+				file: "src/b.rs".into(),
+				name: "c".to_owned(),
+				visibility: parse_quote!(pub(crate)),
+			},
+			Operation::InsertItem {
+				file: "src/a.rs".into(),
+				item: Box::new(parse_quote! { pub fn answer() -> u8 { 42 } }),
+			},
+			Operation::AddDependency {
+				manifest: "Cargo.toml".into(),
+				name: "serde".to_owned(),
+				config: ManifestDependencyConfig::new(
+					ManifestDependencyOrigin::crates_io("1.0"),
+					true,
+					vec![],
+					true,
+				),
+			},
+			Operation::AddFeature {
+				manifest: "Cargo.toml".into(),
+				name: "serde".to_owned(),
+				enables: vec!["dep:serde".to_owned()],
+			},
+		],
+	};
+
+	apply(plan).expect("This should be Ok; qed;");
+
+	assert_eq!(read(&tempdir, "src/lib.rs"), "pub mod a;\npub mod b;\n");
+	assert_eq!(read(&tempdir, "src/b.rs"), "pub(crate) mod c;\n");
+	assert_eq!(read(&tempdir, "src/b/c.rs"), "pub struct C;\n");
+	assert_eq!(read(&tempdir, "src/a.rs"), "pub fn answer() -> u8 {\n    42\n}\n");
+	assert_eq!(
+		read(&tempdir, "Cargo.toml"),
+		format!(
+			"{MANIFEST}\n[dependencies]\nserde = {{ version = \"1.0\", optional = true }}\n\n[features]\nserde = [\"dep:serde\"]\n"
+		)
+	);
+}
+
+#[test]
+fn apply_rolls_back_every_change_if_an_operation_fails() {
+	let tempdir = crate_dir();
+	let plan = Plan {
+		root: tempdir.path().to_path_buf(),
+		operations: vec![
+			Operation::CreateFile {
+				path: "src/generated/deep/file.rs".into(),
+				contents: "pub struct C;\n".to_owned(),
+			},
+			Operation::AddModDeclaration {
+				file: "src/lib.rs".into(),
+				name: "b".to_owned(),
+				visibility: parse_quote!(pub),
+			},
+			Operation::AddFeature {
+				manifest: "Cargo.toml".into(),
+				name: "b".to_owned(),
+				enables: vec![],
+			},
+			Operation::CreateFile { path: "src/a.rs".into(), contents: String::new() },
+		],
+	};
+
+	assert!(matches!(
+		apply(plan),
+		Err(Error::Descriptive(msg)) if msg == format!("{} already exists", tempdir.path().join("src/a.rs").display())
+	));
+
+	assert_eq!(read(&tempdir, "src/lib.rs"), LIB);
+	assert_eq!(read(&tempdir, "Cargo.toml"), MANIFEST);
+	assert_eq!(read(&tempdir, "src/a.rs"), "");
+	assert!(!tempdir.path().join("src/b.rs").exists());
+	assert!(!tempdir.path().join("src/generated").exists());
+}
+
+#[test]
+fn apply_rolls_back_if_a_file_isnt_valid_rust_code() {
+	let tempdir = crate_dir();
+	std::fs::write(tempdir.path().join("src/a.rs"), "fn f(")
+		.expect("The file should be writable; qed;");
+	let plan = Plan {
+		root: tempdir.path().to_path_buf(),
+		operations: vec![
+			Operation::InsertItem {
+				file: "src/lib.rs".into(),
+				item: Box::new(parse_quote! { struct S; }),
+			},
+			Operation::InsertItem {
+				file: "src/a.rs".into(),
+				item: Box::new(parse_quote! { struct S; }),
+			},
+		],
+	};
+
+	assert!(matches!(apply(plan), Err(Error::Syn(_))));
+	assert_eq!(read(&tempdir, "src/lib.rs"), LIB);
+	assert_eq!(read(&tempdir, "src/a.rs"), "fn f(");
+}
+
+#[test]
+fn apply_rolls_back_if_the_crate_cannot_be_formatted() {
+	let tempdir = crate_dir();
+	std::fs::write(tempdir.path().join("Cargo.toml"), "")
+		.expect("The file should be writable; qed;");
+	let plan = Plan {
+		root: tempdir.path().to_path_buf(),
+		operations: vec![Operation::InsertItem {
+			file: "src/a.rs".into(),
+			item: Box::new(parse_quote! { struct S; }),
+		}],
+	};
+
+	assert!(matches!(apply(plan), Err(Error::Descriptive(_))));
+	assert_eq!(read(&tempdir, "src/a.rs"), "");
+}
@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! A uniform shape for the problems found by the checks of this crate, so tools can report the
+//! findings of different subsystems (eg, [`check_headers`](crate::headers::check_headers) or
+//! [`audit_version_pins`](crate::manifest::audit_version_pins)) the same way, instead of handling
+//! each report type on its own.
+//!
+//! The report types of the enabled features implement [`ToDiagnostics`]. A [`Diagnostic`] can be
+//! rendered as plain text through its [`Display`](std::fmt::Display) implementation, with ANSI
+//! colors through [`Diagnostic::render_colored`], and as JSON with the `serde` feature (see the
+//! [`json`](crate::json) module).
+//!
+//! ```
+//! use rustilities::diagnostic::{Diagnostic, Severity};
+//!
+//! let diagnostic = Diagnostic::new(Severity::Warning, "example::code", "Something is off")
+//!     .with_path("src/lib.rs")
+//!     .with_line(3);
+//!
+//! assert_eq!(
+//!     diagnostic.to_string(),
+//!     "warning[example::code]: Something is off\n  --> src/lib.rs:3"
+//! );
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+	fmt::{self, Display, Formatter},
+	path::PathBuf,
+};
+
+/// How serious a [`Diagnostic`] is, ordered from the least to the most serious.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "snake_case")
+)]
+pub enum Severity {
+	/// Something worth knowing, which doesn't need to be fixed.
+	Note,
+	/// Something that should probably be fixed.
+	Warning,
+	/// Something that must be fixed.
+	Error,
+}
+
+impl Severity {
+	/// The lowercase name of the severity, as rendered in the diagnostics.
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Note => "note",
+			Self::Warning => "warning",
+			Self::Error => "error",
+		}
+	}
+
+	/// The ANSI escape sequence coloring the name of the severity.
+	fn color(self) -> &'static str {
+		match self {
+			Self::Note => "\x1b[1;36m",
+			Self::Warning => "\x1b[1;33m",
+			Self::Error => "\x1b[1;31m",
+		}
+	}
+}
+
+/// A problem found by a check, located in a file if it's known.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+	/// How serious the problem is.
+	pub severity: Severity,
+	/// A stable identifier of the kind of problem, made of the subsystem reporting it and a name,
+	/// eg `headers::missing`, so tools can filter the diagnostics.
+	pub code: String,
+	/// The description of the problem.
+	pub message: String,
+	/// The file containing the problem, if any.
+	pub path: Option<PathBuf>,
+	/// The line of the file containing the problem, counting from 1, if known.
+	pub line: Option<usize>,
+}
+
+impl Diagnostic {
+	/// Creates a diagnostic not located in any file.
+	pub fn new(severity: Severity, code: impl Into<String>, message: impl Into<String>) -> Self {
+		Self { severity, code: code.into(), message: message.into(), path: None, line: None }
+	}
+
+	/// Locates the diagnostic in the given file.
+	pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+		self.path = Some(path.into());
+		self
+	}
+
+	/// Locates the diagnostic in the given line of its file, counting from 1.
+	pub fn with_line(mut self, line: usize) -> Self {
+		self.line = Some(line);
+		self
+	}
+
+	/// Renders the diagnostic as its [`Display`] implementation does, coloring the severity with
+	/// ANSI escape sequences for terminals.
+	pub fn render_colored(&self) -> String {
+		const BOLD: &str = "\x1b[1m";
+		const BLUE: &str = "\x1b[1;34m";
+		const RESET: &str = "\x1b[0m";
+
+		let mut rendered = format!(
+			"{}{}[{}]{RESET}{BOLD}: {}{RESET}",
+			self.severity.color(),
+			self.severity.as_str(),
+			self.code,
+			self.message
+		);
+		if let Some(location) = self.location() {
+			rendered.push_str(&format!("\n  {BLUE}-->{RESET} {location}"));
+		}
+		rendered
+	}
+
+	/// The location of the diagnostic, as `path` or `path:line`.
+	fn location(&self) -> Option<String> {
+		let path = self.path.as_ref()?.display();
+		Some(match self.line {
+			Some(line) => format!("{path}:{line}"),
+			None => path.to_string(),
+		})
+	}
+}
+
+impl Display for Diagnostic {
+	/// Renders the diagnostic as `severity[code]: message`, followed by a line pointing to its
+	/// location if any, eg `  --> src/lib.rs:3`.
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}[{}]: {}", self.severity.as_str(), self.code, self.message)?;
+		if let Some(location) = self.location() {
+			write!(f, "\n  --> {location}")?;
+		}
+		Ok(())
+	}
+}
+
+/// Conversion of a report into the [`Diagnostic`]s describing its findings, in the order of the
+/// report.
+pub trait ToDiagnostics {
+	/// The diagnostics describing the findings of the report.
+	fn to_diagnostics(&self) -> Vec<Diagnostic>;
+}
+
+impl<T: ToDiagnostics> ToDiagnostics for [T] {
+	fn to_diagnostics(&self) -> Vec<Diagnostic> {
+		self.iter().flat_map(ToDiagnostics::to_diagnostics).collect()
+	}
+}
+
+impl<T: ToDiagnostics> ToDiagnostics for Vec<T> {
+	fn to_diagnostics(&self) -> Vec<Diagnostic> {
+		self.as_slice().to_diagnostics()
+	}
+}
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+#[test]
+fn diagnostics_render_their_location_if_known() {
+	let diagnostic = Diagnostic::new(Severity::Error, "test::code", "Broken");
+	assert_eq!(diagnostic.to_string(), "error[test::code]: Broken");
+
+	let diagnostic = diagnostic.with_path("Cargo.toml");
+	assert_eq!(diagnostic.to_string(), "error[test::code]: Broken\n  --> Cargo.toml");
+
+	let diagnostic = diagnostic.with_line(7);
+	assert_eq!(diagnostic.to_string(), "error[test::code]: Broken\n  --> Cargo.toml:7");
+}
+
+#[test]
+fn render_colored_colors_the_severity() {
+	let diagnostic = Diagnostic::new(Severity::Note, "test::code", "Info").with_path("src/lib.rs");
+	assert_eq!(
+		diagnostic.render_colored(),
+		"\x1b[1;36mnote[test::code]\x1b[0m\x1b[1m: Info\x1b[0m\n  \x1b[1;34m-->\x1b[0m src/lib.rs"
+	);
+	assert!(
+		Diagnostic::new(Severity::Warning, "test::code", "Info")
+			.render_colored()
+			.starts_with("\x1b[1;33mwarning")
+	);
+	assert!(
+		Diagnostic::new(Severity::Error, "test::code", "Info")
+			.render_colored()
+			.starts_with("\x1b[1;31merror")
+	);
+}
+
+#[test]
+fn severities_are_ordered_by_seriousness() {
+	assert!(Severity::Note < Severity::Warning);
+	assert!(Severity::Warning < Severity::Error);
+}
+
+#[cfg(feature = "headers")]
+#[test]
+fn reports_turn_into_diagnostics() {
+	use crate::headers::{HeaderViolation, HeaderViolationKind};
+
+	let violations = vec![
+		HeaderViolation { path: "a.rs".into(), kind: HeaderViolationKind::Missing },
+		HeaderViolation { path: "b.rs".into(), kind: HeaderViolationKind::Outdated },
+	];
+	assert_eq!(
+		violations.to_diagnostics(),
+		vec![
+			Diagnostic::new(Severity::Warning, "headers::missing", "The license header is missing")
+				.with_path("a.rs")
+				.with_line(1),
+			Diagnostic::new(
+				Severity::Warning,
+				"headers::outdated",
+				"The license header is outdated"
+			)
+			.with_path("b.rs")
+			.with_line(1),
+		]
+	);
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn version_pins_turn_into_diagnostics_following_their_severity() {
+	use crate::manifest::{PinProblem, VersionPin};
+
+	let pin = |problem| VersionPin {
+		manifest: "Cargo.toml".into(),
+		key_path: vec!["dependencies".to_owned(), "serde".to_owned()],
+		problem,
+	};
+	let pins = vec![
+		pin(PinProblem::Wildcard { requirement: "*".to_owned() }),
+		pin(PinProblem::BareVersion { requirement: "1.0".to_owned() }),
+		pin(PinProblem::GitBranch {
+			url: "https://github.com/serde-rs/serde".to_owned(),
+			branch: None,
+		}),
+	];
+	assert_eq!(
+		pins.to_diagnostics().iter().map(ToString::to_string).collect::<Vec<_>>(),
+		vec![
+			"error[manifest::wildcard_version]: `dependencies.serde` accepts any version matching `*`\n  --> Cargo.toml",
+			"note[manifest::bare_version]: `dependencies.serde` uses the bare version `1.0`, read as `^1.0`\n  --> Cargo.toml",
+			"warning[manifest::git_branch]: `dependencies.serde` follows the default branch of https://github.com/serde-rs/serde instead of a rev or a tag\n  --> Cargo.toml",
+		]
+	);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn diagnostics_serialize_to_json() {
+	use crate::json::ToJson;
+
+	let diagnostic = Diagnostic::new(Severity::Warning, "test::code", "Broken").with_path("a.rs");
+	assert_eq!(
+		diagnostic.to_json().expect("This should be Ok; qed;"),
+		r#"{"severity":"warning","code":"test::code","message":"Broken","path":"a.rs","line":null}"#
+	);
+}
@@ -9,12 +9,22 @@ pub enum Error {
 	Descriptive(String),
 	#[error("IO error: {0}")]
 	IO(#[from] std::io::Error),
+	#[error("IO error at {}: {source}", path.display())]
+	IOAt { path: std::path::PathBuf, source: std::io::Error },
 	#[cfg(feature = "manifest")]
 	#[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
 	#[error("StripPrefixError")]
 	StripPrefixError(#[from] std::path::StripPrefixError),
-	#[cfg(feature = "manifest")]
-	#[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
+	#[cfg(any(feature = "cargo", feature = "cargo_config"))]
+	#[cfg_attr(docsrs, doc(cfg(any(feature = "cargo", feature = "cargo_config"))))]
 	#[error("toml_edit error: {0}")]
 	TomlEdit(#[from] toml_edit::TomlError),
+	#[cfg(feature = "parsing")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
+	#[error("syn error: {0}")]
+	Syn(#[from] syn::Error),
+	#[cfg(any(feature = "registry", feature = "serde"))]
+	#[cfg_attr(docsrs, doc(cfg(any(feature = "registry", feature = "serde"))))]
+	#[error("serde_json error: {0}")]
+	Json(#[from] serde_json::Error),
 }
@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! A mechanism letting long-running operations report their progress, so frontends can render
+//! progress bars. The operations supporting it are suffixed with `_with_events` and take an
+//! [`EventSink`], while their non-suffixed counterparts use [`NoopSink`].
+//!
+//! A sink can be a closure, or the sending half of a channel if the events are consumed from
+//! another thread:
+//!
+//! ```
+//! use rustilities::events::{Event, EventSink};
+//!
+//! let (sender, receiver) = std::sync::mpsc::channel();
+//! std::thread::spawn(move || {
+//!     sender.emit(Event::Started { operation: "example", total: Some(1) });
+//!     sender.emit(Event::Progress {
+//!         operation: "example",
+//!         current: 1,
+//!         total: Some(1),
+//!         item: "first".to_owned(),
+//!     });
+//!     sender.emit(Event::Finished { operation: "example" });
+//! });
+//!
+//! assert_eq!(receiver.iter().count(), 3);
+//! ```
+
+use std::sync::mpsc::Sender;
+
+/// An event reported by a long-running operation. Every operation documents its name and what its
+/// items are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event {
+	/// The operation started, and will process `total` items if it's known in advance.
+	Started { operation: &'static str, total: Option<usize> },
+	/// The operation processed its `current`-th item (counting from 1), described by `item`.
+	Progress { operation: &'static str, current: usize, total: Option<usize>, item: String },
+	/// The operation finished successfully.
+	Finished { operation: &'static str },
+}
+
+/// A receiver of [`Event`]s.
+pub trait EventSink {
+	/// Receives an event.
+	fn emit(&self, event: Event);
+}
+
+impl<F: Fn(Event)> EventSink for F {
+	fn emit(&self, event: Event) {
+		self(event)
+	}
+}
+
+impl EventSink for Sender<Event> {
+	/// Sends the event through the channel. The event is dropped if the receiver hung up.
+	fn emit(&self, event: Event) {
+		let _ = self.send(event);
+	}
+}
+
+/// The [`EventSink`] ignoring every event.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopSink;
+
+impl EventSink for NoopSink {
+	fn emit(&self, _event: Event) {}
+}
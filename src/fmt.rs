@@ -3,50 +3,898 @@
 #[cfg(test)]
 mod tests;
 
-use crate::Error;
-use std::{path::Path, process::Command};
+#[cfg(feature = "manifest")]
+use crate::events::{Event, EventSink};
+use crate::{
+	Error,
+	diagnostic::{Diagnostic, Severity, ToDiagnostics},
+	macros::debug,
+};
+#[cfg(all(feature = "git", feature = "manifest"))]
+use std::{collections::BTreeMap, ffi::OsStr};
+use std::{
+	path::{Path, PathBuf},
+	process::{Command, Output},
+	time::{Duration, Instant},
+};
+#[cfg(feature = "parsing")]
+use syn::{ItemUse, UseTree, spanned::Spanned};
+#[cfg(feature = "manifest")]
+use toml_edit::{DocumentMut, Item, RawString, Table, Value};
 
-const EXPECT_MSG: &str = "If cargo fmt were to fail with an IO error, it would have already failed with 'cargo +nightly fmt --all'; qed;";
+const FMT_CACHE_FILE: &str = ".rustilities-fmt-cache";
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const RUSTFMT_CONFIG_FILES: [&str; 2] = ["rustfmt.toml", ".rustfmt.toml"];
+
+#[cfg(feature = "parsing")]
+const STD_CRATES: [&str; 5] = ["std", "core", "alloc", "proc_macro", "test"];
+
+const EXPECT_MSG: &str = "If cargo fmt were to fail with an IO error, it would have already failed with 'cargo +nightly fmt'; qed;";
+
+/// The toolchain whose rustfmt formatted the code, as reported by [`FmtOutcome`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "snake_case")
+)]
+pub enum FmtToolchain {
+	/// The nightly toolchain, run with `cargo +nightly fmt`.
+	Nightly,
+	/// The toolchain selected by rustup for the formatted path, run with `cargo fmt`.
+	Default,
+}
+
+/// How the code was formatted, as returned by [`format_dir_with_outcome`]. The stable rustfmt
+/// ignores the nightly-only options found in the rustfmt config files, so callers may warn their
+/// users if the nightly toolchain wasn't used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FmtOutcome {
+	/// The toolchain whose rustfmt formatted the code.
+	pub toolchain_used: FmtToolchain,
+	/// Whether `cargo +nightly fmt` was run and failed, so `cargo fmt` was run instead.
+	pub fallback_occurred: bool,
+	/// How long formatting took, including the failed nightly attempt if any.
+	pub duration: Duration,
+}
+
+impl ToDiagnostics for FmtOutcome {
+	/// A `fmt::nightly_unavailable` note if the nightly toolchain didn't format the code, as the
+	/// nightly-only rustfmt options were ignored.
+	fn to_diagnostics(&self) -> Vec<Diagnostic> {
+		match self.toolchain_used {
+			FmtToolchain::Nightly => Vec::new(),
+			FmtToolchain::Default => vec![Diagnostic::new(
+				Severity::Note,
+				"fmt::nightly_unavailable",
+				"The nightly rustfmt isn't available, so the nightly-only rustfmt options were ignored",
+			)],
+		}
+	}
+}
 
 /// Given a path, this function firstly tries to:
 /// - Apply `cargo +nightly fmt --all` to it.
 /// - In case of failure, it tries to apply `cargo fmt --all` to it.
 /// - Otherwise it returns an error explaining why the command failed.
+///
+/// Use [`format_dir_with_outcome`] to know which toolchain formatted the code.
 /// ## Errors:
 /// - If neither `cargo +nightly fmt --all` nor `cargo fmt --all` can be successfully applied to the
 ///   path.
 pub fn format_dir<P: AsRef<Path>>(path: P) -> Result<(), Error> {
-	fn do_format_dir(path: &Path) -> Result<(), Error> {
-		Command::new("cargo")
+	format_dir_with_outcome(path).map(|_| ())
+}
+
+/// Same as [`format_dir`], but reports which toolchain formatted the code, whether the nightly
+/// toolchain failed and how long formatting took.
+///
+/// ## Errors:
+/// - If neither `cargo +nightly fmt --all` nor `cargo fmt --all` can be successfully applied to the
+///   path.
+///
+/// # Example
+///
+/// ```no_run
+/// use rustilities::fmt::FmtToolchain;
+///
+/// let outcome = rustilities::fmt::format_dir_with_outcome("path/to/crate").unwrap();
+/// if outcome.toolchain_used != FmtToolchain::Nightly {
+///     eprintln!("Nightly rustfmt unavailable, nightly-only rustfmt options were ignored");
+/// }
+/// ```
+pub fn format_dir_with_outcome<P: AsRef<Path>>(path: P) -> Result<FmtOutcome, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", ret))]
+	fn do_format_dir_with_outcome(path: &Path) -> Result<FmtOutcome, Error> {
+		outcome_of(cargo_fmt_run(path, &["--all"], Some(&[]))?)
+	}
+	do_format_dir_with_outcome(path.as_ref())
+}
+
+/// The options used by [`format_dir_with_options`], built from the default ones.
+#[cfg(feature = "toolchain")]
+#[cfg_attr(docsrs, doc(cfg(feature = "toolchain")))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FmtOptions<'a> {
+	nightly_options: Vec<&'a str>,
+}
+
+#[cfg(feature = "toolchain")]
+impl<'a> FmtOptions<'a> {
+	/// Adds some rustfmt config options only supported by the nightly rustfmt, written as
+	/// `key=value`, eg `imports_granularity=Crate` or `group_imports=StdExternalCrate`. They're
+	/// only passed to rustfmt if the nightly toolchain is installed, and dropped when falling back
+	/// to `cargo fmt`, as the stable rustfmt would ignore them with a warning.
+	pub fn nightly_options(mut self, options: &[&'a str]) -> Self {
+		self.nightly_options.extend_from_slice(options);
+		self
+	}
+}
+
+/// Same as [`format_dir_with_outcome`], passing the given options to rustfmt. The nightly toolchain
+/// is detected with the [`toolchain`](crate::toolchain) module before passing the
+/// [`nightly_options`](FmtOptions::nightly_options), so they're only used if `cargo +nightly fmt`
+/// actually runs the nightly rustfmt.
+///
+/// ## Errors:
+/// - If neither `cargo +nightly fmt --all` nor `cargo fmt --all` can be successfully applied to the
+///   path.
+///
+/// # Example
+///
+/// ```no_run
+/// use rustilities::fmt::FmtOptions;
+///
+/// let options = FmtOptions::default()
+///     .nightly_options(&["imports_granularity=Crate", "group_imports=StdExternalCrate"]);
+/// rustilities::fmt::format_dir_with_options("path/to/crate", &options).unwrap();
+/// ```
+#[cfg(feature = "toolchain")]
+#[cfg_attr(docsrs, doc(cfg(feature = "toolchain")))]
+pub fn format_dir_with_options<P: AsRef<Path>>(
+	path: P,
+	options: &FmtOptions,
+) -> Result<FmtOutcome, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", ret))]
+	fn do_format_dir_with_options(path: &Path, options: &FmtOptions) -> Result<FmtOutcome, Error> {
+		if options.nightly_options.is_empty() {
+			return outcome_of(cargo_fmt_run(path, &["--all"], Some(&[]))?);
+		}
+
+		let nightly_installed = crate::toolchain::cargo_version_of("nightly")
+			.is_ok_and(|version| version.channel == crate::toolchain::Channel::Nightly);
+		let config = options.nightly_options.join(",");
+		let nightly_args = ["--", "--config", &config];
+		let (output, outcome) =
+			cargo_fmt_run(path, &["--all"], nightly_installed.then_some(&nightly_args[..]))?;
+		if outcome.toolchain_used != FmtToolchain::Nightly {
+			debug!(
+				nightly_options = ?options.nightly_options,
+				"The nightly rustfmt isn't available, dropping the nightly options"
+			);
+		}
+		outcome_of((output, outcome))
+	}
+	do_format_dir_with_options(path.as_ref(), options)
+}
+
+/// Given a workspace dir and a list of package names, this function formats only those packages
+/// using `cargo fmt -p <package>`, which is way faster than formatting the whole workspace in big
+/// monorepos. As [`format_dir`], it firstly tries to use `cargo +nightly fmt`, falling back to
+/// `cargo fmt` in case of failure.
+///
+/// The package names are resolved against the workspace members using the
+/// [`manifest`](crate::manifest) module, so a typo in a package name is reported before running
+/// any command. If the list of packages is empty, this function doesn't have any effect.
+///
+/// ## Errors:
+/// - If the dir doesn't contain a workspace manifest.
+/// - If some of the packages isn't a member of the workspace.
+/// - If neither `cargo +nightly fmt` nor `cargo fmt` can be successfully applied to the packages.
+#[cfg(feature = "manifest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
+pub fn format_packages<P: AsRef<Path>>(workspace_dir: P, packages: &[&str]) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_format_packages(workspace_dir: &Path, packages: &[&str]) -> Result<(), Error> {
+		if packages.is_empty() {
+			return Ok(());
+		}
+
+		check_workspace_members(workspace_dir, packages)?;
+		let mut args = Vec::with_capacity(packages.len() * 2);
+		for package in packages {
+			args.extend_from_slice(&["-p", package]);
+		}
+
+		run_cargo_fmt(workspace_dir, &args)
+	}
+	do_format_packages(workspace_dir.as_ref(), packages)
+}
+
+/// Same as [`format_packages`], but the progress is reported to the given sink as the
+/// `format_packages` operation, whose items are the package names. To report progress, the
+/// packages are formatted one by one, so this function is slower than [`format_packages`].
+#[cfg(feature = "manifest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
+pub fn format_packages_with_events<P: AsRef<Path>, E: EventSink>(
+	workspace_dir: P,
+	packages: &[&str],
+	events: &E,
+) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(events)))]
+	fn do_format_packages_with_events(
+		workspace_dir: &Path,
+		packages: &[&str],
+		events: &dyn EventSink,
+	) -> Result<(), Error> {
+		const OPERATION: &str = "format_packages";
+		check_workspace_members(workspace_dir, packages)?;
+
+		let total = Some(packages.len());
+		events.emit(Event::Started { operation: OPERATION, total });
+		for (index, package) in packages.iter().enumerate() {
+			run_cargo_fmt(workspace_dir, &["-p", package])?;
+			events.emit(Event::Progress {
+				operation: OPERATION,
+				current: index + 1,
+				total,
+				item: (*package).to_owned(),
+			});
+		}
+		events.emit(Event::Finished { operation: OPERATION });
+		Ok(())
+	}
+	do_format_packages_with_events(workspace_dir.as_ref(), packages, events)
+}
+
+/// Given the manifest path of a crate, this function formats only that crate using `cargo fmt -p
+/// <name> --manifest-path <manifest_path>`, so tools can format a crate they generated inside a big
+/// workspace without touching its sibling crates, while the workspace rustfmt config still
+/// applies. The crate name is found with [`find_crate_name`](crate::manifest::find_crate_name). As
+/// [`format_dir`], it firstly tries to use `cargo +nightly fmt`, falling back to `cargo fmt` in
+/// case of failure.
+///
+/// ## Errors:
+/// - If the manifest doesn't declare a crate, or it cannot be read.
+/// - If neither `cargo +nightly fmt` nor `cargo fmt` can be successfully applied to the crate.
+#[cfg(feature = "manifest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
+pub fn format_crate<P: AsRef<Path>>(crate_manifest_path: P) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_format_crate(crate_manifest_path: &Path) -> Result<(), Error> {
+		let manifest_path = std::path::absolute(crate_manifest_path)?;
+		let name = crate::manifest::find_crate_name(&manifest_path).ok_or_else(|| {
+			Error::Descriptive(format!("{} doesn't declare a crate", manifest_path.display()))
+		})?;
+		let dir = manifest_path.parent().unwrap_or(&manifest_path);
+		run_cargo_fmt(dir, &["-p", &name, "--manifest-path", &manifest_path.to_string_lossy()])
+	}
+	do_format_crate(crate_manifest_path.as_ref())
+}
+
+/// Checks that every package is a member of the workspace living in `workspace_dir`.
+#[cfg(feature = "manifest")]
+fn check_workspace_members(workspace_dir: &Path, packages: &[&str]) -> Result<(), Error> {
+	let members = crate::manifest::find_workspace_members(workspace_dir.join("Cargo.toml"))?
+		.iter()
+		.filter_map(crate::manifest::find_crate_name)
+		.collect::<Vec<_>>();
+	match packages.iter().find(|package| !members.iter().any(|member| member == *package)) {
+		Some(package) =>
+			Err(Error::Descriptive(format!("{package} isn't a member of the workspace"))),
+		None => Ok(()),
+	}
+}
+
+/// Given a dir inside a git repository and a git reference (a commit, branch, tag, ...), this
+/// function formats only the Rust files inside that dir changed since that reference, as found by
+/// [`changed_files_since`](crate::git::changed_files_since), running `rustfmt` on them instead of
+/// formatting every crate, which is way faster than [`format_dir`] in big monorepos. As
+/// [`format_dir`], it firstly tries to use `rustfmt +nightly`, falling back to `rustfmt`.
+///
+/// Each file is formatted with the edition of the crate containing it, and with the rustfmt config
+/// files found in its dir and the parent dirs. Deleted files and the files inside `target` dirs are
+/// ignored. Note that `rustfmt` also formats the out-of-line modules declared by the changed files.
+///
+/// The returned paths are the formatted files, sorted.
+///
+/// ## Errors:
+/// - If the dir isn't part of a git repository, or the changed files cannot be listed, eg because
+///   the reference doesn't exist.
+/// - If the manifest of the crate containing some of the files cannot be read or parsed.
+/// - If neither `rustfmt +nightly` nor `rustfmt` can be successfully applied to the files.
+#[cfg(all(feature = "git", feature = "manifest"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "git", feature = "manifest"))))]
+pub fn format_changed<P: AsRef<Path>>(dir: P, since_ref: &str) -> Result<Vec<PathBuf>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_format_changed(dir: &Path, since_ref: &str) -> Result<Vec<PathBuf>, Error> {
+		let dir = std::path::absolute(dir)?;
+		let files = crate::git::changed_files_since(&dir, since_ref)?
+			.into_iter()
+			.filter(|file| {
+				file.starts_with(&dir) &&
+					file.is_file() && file.extension().is_some_and(|extension| extension == "rs") &&
+					!file.strip_prefix(&dir).is_ok_and(|relative| {
+						relative.components().any(|component| component.as_os_str() == "target")
+					})
+			})
+			.collect::<Vec<_>>();
+
+		// Files are formatted in one rustfmt run per edition
+		let mut by_edition: BTreeMap<Option<String>, Vec<&Path>> = BTreeMap::new();
+		for file in &files {
+			let edition = match crate::manifest::find_innermost_manifest(file) {
+				Some(manifest_path) => crate::manifest::package_edition(&manifest_path)?,
+				None => None,
+			};
+			by_edition.entry(edition).or_default().push(file);
+		}
+		for (edition, files) in by_edition {
+			let mut args: Vec<&OsStr> = Vec::with_capacity(files.len() + 2);
+			if let Some(edition) = &edition {
+				args.extend([OsStr::new("--edition"), OsStr::new(edition)]);
+			}
+			args.extend(files.iter().map(|file| file.as_os_str()));
+			let output = rustfmt_output(&dir, &args)?;
+			if !output.status.success() {
+				return Err(Error::Descriptive(
+					String::from_utf8_lossy(&output.stderr).into_owned(),
+				));
+			}
+		}
+		Ok(files)
+	}
+	do_format_changed(dir.as_ref(), since_ref)
+}
+
+/// Given a path, this function checks if the code it contains needs to be formatted, using `cargo
+/// +nightly fmt --all --check` (or `cargo fmt --all --check` as a fallback).
+///
+/// As running `cargo fmt` is slow, the result is cached: when the code is found to be formatted, a
+/// hash of every Rust file and rustfmt config file contained in the path is stored in
+/// `.rustilities-fmt-cache-<path hash>`, inside the target dir cargo uses for the path (see
+/// [`target_dir`](crate::manifest::target_dir)), so paths sharing a target dir, such as the members
+/// of a workspace, keep their own entry. Subsequent calls compare the current hash against
+/// the stored one, skipping `cargo fmt` entirely if nothing changed, which is specially useful for
+/// watch-mode tools. Without the `manifest` feature, the target dir is only found if the path is
+/// the root of a crate or `CARGO_TARGET_DIR` is set, and nothing is cached otherwise.
+///
+/// ## Errors:
+/// - If the path cannot be read.
+/// - If the code cannot be checked by `cargo fmt`, eg, because it isn't valid Rust code.
+/// - If the cache cannot be written.
+pub fn needs_format<P: AsRef<Path>>(path: P) -> Result<bool, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", ret))]
+	fn do_needs_format(path: &Path) -> Result<bool, Error> {
+		let hash = hash_dir_sources(path)?.to_string();
+		let cache_path = fmt_cache_path(path);
+		if let Some(cache_path) = &cache_path &&
+			matches!(std::fs::read_to_string(cache_path), Ok(cached) if cached == hash)
+		{
+			debug!(cache = %cache_path.display(), "Sources unchanged since last check");
+			return Ok(false);
+		}
+
+		let output = cargo_fmt_output(path, &["--all", "--check"])?;
+		if output.status.success() {
+			if let Some(cache_path) = cache_path {
+				if let Some(target_dir) = cache_path.parent() {
+					std::fs::create_dir_all(target_dir)?;
+				}
+				debug!(path = %cache_path.display(), "Writing fmt cache");
+				std::fs::write(&cache_path, hash)?;
+			}
+			Ok(false)
+		} else if String::from_utf8_lossy(&output.stdout).contains("Diff in") {
+			Ok(true)
+		} else {
+			Err(Error::Descriptive(String::from_utf8_lossy(&output.stderr).into_owned()))
+		}
+	}
+	do_needs_format(path.as_ref())
+}
+
+/// Given the path to a TOML file, typically a `Cargo.toml`, this function normalizes its formatting
+/// in place, so callers can tidy a manifest after a batch of programmatic edits. Comments and the
+/// order of keys and tables are preserved, while the formatting is normalized:
+/// - Keys and values are separated by ` = `, dotted keys don't contain spaces and trailing comments
+///   are separated from their value by a single space.
+/// - Lines are unindented and runs of blank lines collapse into one. Every table header is preceded
+///   by a blank line, except at the beginning of the file.
+/// - Inline tables look like `{ a = 1, b = 2 }` and single-line arrays like `["a", "b"]`, while
+///   multi-line arrays get an element per line, indented with 4 spaces and followed by a comma.
+/// - The file ends with a single line break.
+///
+/// ## Errors:
+/// - If the file cannot be read or written.
+/// - If the file isn't valid TOML.
+#[cfg(feature = "manifest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
+pub fn format_toml<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_format_toml(path: &Path) -> Result<(), Error> {
+		let content = std::fs::read_to_string(path)?;
+		let mut doc = content.parse::<DocumentMut>()?;
+		format_toml_table(doc.as_table_mut(), true);
+		let trailing = comment_lines(doc.trailing().as_str().unwrap_or_default(), "");
+		doc.set_trailing(trailing);
+
+		let formatted = format!("{}\n", doc.to_string().trim_matches('\n'));
+		if formatted != content {
+			debug!(path = %path.display(), "Writing formatted TOML file");
+			std::fs::write(path, formatted)?;
+		}
+		Ok(())
+	}
+	do_format_toml(path.as_ref())
+}
+
+/// Normalizes the decor of the key-value pairs of a table and its subtables. `doc_start` tells
+/// whether nothing precedes the table in the document.
+#[cfg(feature = "manifest")]
+fn format_toml_table(table: &mut Table, mut doc_start: bool) {
+	let mut first_key = true;
+	for (mut key, item) in table.iter_mut() {
+		let dotted = matches!(item, Item::Table(subtable) if subtable.is_dotted());
+		if item.is_value() || dotted {
+			let prefix = comment_lines(raw_decor(key.leaf_decor().prefix()), "");
+			let prefix =
+				if first_key { prefix.trim_start_matches('\n').to_owned() } else { prefix };
+			key.leaf_decor_mut().set_prefix(prefix);
+			key.leaf_decor_mut().set_suffix(if dotted { "" } else { " " });
+			key.dotted_decor_mut().clear();
+			first_key = false;
+		}
+		match item {
+			Item::Value(value) => {
+				format_toml_value(value);
+				let suffix = trailing_comment(raw_decor(value.decor().suffix()));
+				value.decor_mut().set_prefix(" ");
+				value.decor_mut().set_suffix(suffix);
+			},
+			Item::Table(subtable) => format_toml_subtable(subtable, doc_start),
+			Item::ArrayOfTables(tables) =>
+				tables.iter_mut().enumerate().for_each(|(index, subtable)| {
+					format_toml_subtable(subtable, doc_start && index == 0)
+				}),
+			Item::None => (),
+		}
+		doc_start = false;
+	}
+}
+
+#[cfg(feature = "manifest")]
+fn format_toml_subtable(table: &mut Table, doc_start: bool) {
+	if table.is_dotted() || table.is_implicit() {
+		return format_toml_table(table, doc_start);
+	}
+	let prefix = comment_lines(raw_decor(table.decor().prefix()), "");
+	let prefix = prefix.trim_start_matches('\n');
+	let suffix = trailing_comment(raw_decor(table.decor().suffix()));
+	table
+		.decor_mut()
+		.set_prefix(if doc_start { prefix.to_owned() } else { format!("\n{prefix}") });
+	table.decor_mut().set_suffix(suffix);
+	format_toml_table(table, false);
+}
+
+#[cfg(feature = "manifest")]
+fn format_toml_value(value: &mut Value) {
+	match value {
+		Value::InlineTable(table) => {
+			table.fmt();
+			table.iter_mut().for_each(|(_, value)| format_toml_value(value));
+		},
+		Value::Array(array) if array.to_string().contains('\n') => {
+			for element in array.iter_mut() {
+				format_toml_value(element);
+				let prefix = array_line_break(raw_decor(element.decor().prefix()));
+				element.decor_mut().set_prefix(format!("{prefix}    "));
+				element.decor_mut().set_suffix("");
+			}
+			let trailing = array_line_break(array.trailing().as_str().unwrap_or_default());
+			array.set_trailing(trailing);
+			array.set_trailing_comma(true);
+		},
+		Value::Array(array) => {
+			array.fmt();
+			array.iter_mut().for_each(format_toml_value);
+		},
+		_ => (),
+	}
+}
+
+#[cfg(feature = "manifest")]
+fn raw_decor(raw: Option<&RawString>) -> &str {
+	raw.and_then(RawString::as_str).unwrap_or_default()
+}
+
+/// Reduces the whitespace and comments found before a line to its comment lines, indented with
+/// `indent`, keeping a single blank line where there were blank lines. The output ends with a line
+/// break unless it's empty.
+#[cfg(feature = "manifest")]
+fn comment_lines(raw: &str, indent: &str) -> String {
+	let mut lines: Vec<&str> = raw.split('\n').map(str::trim).collect();
+	// The last line is the indentation of the line the decor precedes
+	lines.pop();
+	let mut output = String::new();
+	let mut blank = false;
+	for line in lines {
+		if line.is_empty() {
+			blank = true;
+			continue;
+		}
+		if blank {
+			output.push('\n');
+			blank = false;
+		}
+		output.push_str(indent);
+		output.push_str(line);
+		output.push('\n');
+	}
+	if blank {
+		output.push('\n');
+	}
+	output
+}
+
+/// Normalizes the whitespace and comments between two elements of a multi-line array, which start
+/// with the rest of the line of the previous element, so the output ends with a line break.
+#[cfg(feature = "manifest")]
+fn array_line_break(raw: &str) -> String {
+	let (rest_of_line, lines) = raw.split_once('\n').unwrap_or((raw, ""));
+	format!("{}\n{}", trailing_comment(rest_of_line), comment_lines(lines, "    "))
+}
+
+/// The comment ending a line, preceded by a space, or nothing if there isn't any.
+#[cfg(feature = "manifest")]
+fn trailing_comment(raw: &str) -> String {
+	match raw.trim() {
+		"" => String::new(),
+		comment => format!(" {comment}"),
+	}
+}
+
+/// How [`organize_imports`] splits the imports of a block into groups separated by a blank line.
+#[cfg(feature = "parsing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportGroups {
+	/// Every import belongs to the same group.
+	#[default]
+	One,
+	/// The imports from the standard library (`std`, `core`, `alloc`, `proc_macro` and `test`) come
+	/// first, followed by those from external crates and finally the local ones (`crate`, `self`
+	/// and `super`).
+	StdExternalCrate,
+}
+
+/// How [`organize_imports`] splits the imported paths into `use` statements.
+#[cfg(feature = "parsing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportGranularity {
+	/// A statement per crate, eg `use std::{fmt, io::Read};`.
+	#[default]
+	Crate,
+	/// A statement per imported item, eg `use std::fmt;` and `use std::io::Read;`.
+	Item,
+}
+
+/// The style applied by [`organize_imports`].
+#[cfg(feature = "parsing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ImportsStyle {
+	/// How the imports are grouped.
+	pub groups: ImportGroups,
+	/// How the imported paths are split into statements.
+	pub granularity: ImportGranularity,
+}
+
+/// Given the path to a Rust file, this function groups, sorts and deduplicates its `use`
+/// statements in place according to the given [`ImportsStyle`], without running rustfmt on the
+/// file. The imports are merged using the [`use_tree`](crate::parsing::use_tree) utilities.
+///
+/// The statements are organized by blocks: a block is a run of consecutive `use` statements at
+/// the top level of the file or of an inline module, separated only by whitespace. Statements with
+/// attributes or a leading `::`, as well as comments, end a block and are left untouched, so
+/// conditional imports and commented code keep their place. Inside a block, the statements are
+/// grouped by visibility in order of appearance, and then by [`ImportGroups`].
+///
+/// ## Errors:
+/// - If the file cannot be read or written.
+/// - If the file doesn't contain valid Rust code.
+///
+/// # Example
+///
+/// ```
+/// use rustilities::fmt::{ImportGroups, ImportsStyle, organize_imports};
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let path = tempdir.path().join("lib.rs");
+/// std::fs::write(&path, "use syn::Item;\nuse crate::Error;\nuse std::fmt;\nuse syn::File;\n").unwrap();
+///
+/// let style = ImportsStyle { groups: ImportGroups::StdExternalCrate, ..Default::default() };
+/// organize_imports(&path, style).unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&path).unwrap(),
+///     "use std::fmt;\n\nuse syn::{File, Item};\n\nuse crate::Error;\n"
+/// );
+/// ```
+#[cfg(feature = "parsing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
+pub fn organize_imports<P: AsRef<Path>>(path: P, style: ImportsStyle) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_organize_imports(path: &Path, style: ImportsStyle) -> Result<(), Error> {
+		let content = std::fs::read_to_string(path)?;
+		let file = syn::parse_file(&content)?;
+		let mut blocks = Vec::new();
+		import_blocks(&content, &file.items, &mut blocks);
+
+		let mut organized = content.clone();
+		// Replace the blocks from the end, so the ranges of the previous ones are still valid
+		for block in blocks.iter().rev() {
+			let start = block[0].span().byte_range().start;
+			let end = block[block.len() - 1].span().byte_range().end;
+			let line_start = content[..start].rfind('\n').map_or(0, |index| index + 1);
+			let indentation = &content[line_start..start];
+			let indentation = if indentation.trim().is_empty() { indentation } else { "" };
+			organized.replace_range(start..end, &render_imports(block, style, indentation));
+		}
+
+		if organized != content {
+			debug!(path = %path.display(), "Writing organized source file");
+			std::fs::write(path, organized)?;
+		}
+		Ok(())
+	}
+	do_organize_imports(path.as_ref(), style)
+}
+
+/// Collects the blocks of consecutive `use` statements among the items, and those of the inline
+/// modules they contain.
+#[cfg(feature = "parsing")]
+fn import_blocks<'a>(content: &str, items: &'a [syn::Item], blocks: &mut Vec<Vec<&'a ItemUse>>) {
+	let mut block: Vec<&ItemUse> = Vec::new();
+	for item in items {
+		match item {
+			syn::Item::Use(item_use)
+				if item_use.attrs.is_empty() && item_use.leading_colon.is_none() =>
+			{
+				if let Some(last) = block.last() &&
+					!content[last.span().byte_range().end..item_use.span().byte_range().start]
+						.trim()
+						.is_empty()
+				{
+					blocks.push(std::mem::take(&mut block));
+				}
+				block.push(item_use);
+			},
+			item => {
+				if !block.is_empty() {
+					blocks.push(std::mem::take(&mut block));
+				}
+				if let syn::Item::Mod(item_mod) = item &&
+					let Some((_, items)) = &item_mod.content
+				{
+					import_blocks(content, items, blocks);
+				}
+			},
+		}
+	}
+	if !block.is_empty() {
+		blocks.push(block);
+	}
+}
+
+/// Renders a block of `use` statements with the given style. Every line but the first one is
+/// indented, as the block replaces the statements from the start of the first one.
+#[cfg(feature = "parsing")]
+fn render_imports(block: &[&ItemUse], style: ImportsStyle, indentation: &str) -> String {
+	use crate::parsing::{
+		render_visibility,
+		use_tree::{flatten_use_tree, merge_use_trees, render_use_tree},
+	};
+
+	let mut by_visibility: Vec<(String, Vec<&UseTree>)> = Vec::new();
+	for item_use in block {
+		let visibility = render_visibility(&item_use.vis);
+		match by_visibility.iter_mut().find(|(vis, _)| *vis == visibility) {
+			Some((_, trees)) => trees.push(&item_use.tree),
+			None => by_visibility.push((visibility, vec![&item_use.tree])),
+		}
+	}
+
+	let mut sections = Vec::new();
+	for (visibility, trees) in by_visibility {
+		let mut trees = match style.granularity {
+			ImportGranularity::Crate => merge_use_trees(trees),
+			ImportGranularity::Item => {
+				let mut trees = trees.into_iter().flat_map(flatten_use_tree).collect::<Vec<_>>();
+				trees.sort_by_cached_key(render_use_tree);
+				trees.dedup_by_key(|tree| render_use_tree(tree));
+				trees
+			},
+		};
+		let groups: Vec<&[UseTree]> = match style.groups {
+			ImportGroups::One => vec![&trees],
+			ImportGroups::StdExternalCrate => {
+				trees.sort_by_key(import_group);
+				trees.chunk_by(|a, b| import_group(a) == import_group(b)).collect()
+			},
+		};
+		sections.extend(groups.into_iter().map(|group| {
+			group
+				.iter()
+				.map(|tree| format!("{indentation}{visibility}use {};", render_use_tree(tree)))
+				.collect::<Vec<_>>()
+				.join("\n")
+		}));
+	}
+
+	sections.join("\n\n")[indentation.len()..].to_owned()
+}
+
+/// The group of an import when using [`ImportGroups::StdExternalCrate`]: 0 for the standard
+/// library, 1 for external crates and 2 for local imports.
+#[cfg(feature = "parsing")]
+fn import_group(tree: &UseTree) -> u8 {
+	let first = match tree {
+		UseTree::Path(path) => path.ident.to_string(),
+		UseTree::Name(name) => name.ident.to_string(),
+		UseTree::Rename(rename) => rename.ident.to_string(),
+		UseTree::Glob(_) | UseTree::Group(_) => return 1,
+	};
+	match first.as_str() {
+		"crate" | "self" | "super" => 2,
+		first if STD_CRATES.contains(&first) => 0,
+		_ => 1,
+	}
+}
+
+/// Hashes the content of every Rust file and rustfmt config file contained in a dir, ignoring the
+/// `target` dir and hidden dirs.
+fn hash_dir_sources(path: &Path) -> Result<u64, Error> {
+	fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+		for entry in std::fs::read_dir(dir)? {
+			let entry_path = entry?.path();
+			let file_name = entry_path.file_name().map(|name| name.to_string_lossy());
+			if entry_path.is_dir() {
+				if !matches!(file_name, Some(ref name) if name == "target" || name.starts_with('.'))
+				{
+					collect_files(&entry_path, files)?;
+				}
+			} else if entry_path.extension().is_some_and(|extension| extension == "rs") ||
+				matches!(file_name, Some(ref name) if RUSTFMT_CONFIG_FILES.contains(&name.as_ref()))
+			{
+				files.push(entry_path);
+			}
+		}
+		Ok(())
+	}
+
+	let mut files = Vec::new();
+	collect_files(path, &mut files)?;
+	// Sort the files so the hash doesn't depend on the order in which the OS lists them
+	files.sort();
+
+	// The paths are hashed relative to the given one, and followed by a NUL byte so they cannot
+	// merge with the contents.
+	let mut hash = FNV_OFFSET_BASIS;
+	for file in files {
+		let relative = file.strip_prefix(path).unwrap_or(&file);
+		hash = fnv1a(hash, relative.as_os_str().as_encoded_bytes());
+		hash = fnv1a(hash, &[0]);
+		hash = fnv1a(hash, &std::fs::read(&file)?);
+	}
+	Ok(hash)
+}
+
+/// Feeds some bytes to a FNV-1a hash, which is stable across executions and platforms unlike the
+/// std hashers, so it can be persisted.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+	const PRIME: u64 = 0x0000_0100_0000_01b3;
+	bytes
+		.iter()
+		.fold(hash, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(PRIME))
+}
+
+/// The file caching the result of [`needs_format`] for the given path, inside the target dir cargo
+/// uses for it and named after the hash of the absolute path, or `None` if the target dir cannot
+/// be found.
+fn fmt_cache_path(path: &Path) -> Option<PathBuf> {
+	let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+	let file_name = format!(
+		"{FMT_CACHE_FILE}-{:016x}",
+		fnv1a(FNV_OFFSET_BASIS, absolute.as_os_str().as_encoded_bytes())
+	);
+	#[cfg(feature = "manifest")]
+	let target_dir = crate::manifest::target_dir(path).ok();
+	#[cfg(not(feature = "manifest"))]
+	let target_dir = std::env::var_os("CARGO_TARGET_DIR")
+		.map(PathBuf::from)
+		.or_else(|| path.join("Cargo.toml").is_file().then(|| path.join("target")));
+	target_dir.map(|target_dir| target_dir.join(file_name))
+}
+
+/// Runs `cargo +nightly fmt <args>` in the given path, falling back to `cargo fmt <args>` if the
+/// nightly toolchain cannot format the code.
+#[cfg(feature = "manifest")]
+fn run_cargo_fmt(path: &Path, args: &[&str]) -> Result<(), Error> {
+	outcome_of(cargo_fmt_run(path, args, Some(&[]))?).map(|_| ())
+}
+
+/// Runs `rustfmt +nightly <args>` in the given path, falling back to `rustfmt <args>` if the
+/// nightly toolchain command fails, and returns the output of the last command run.
+#[cfg(all(feature = "git", feature = "manifest"))]
+fn rustfmt_output(path: &Path, args: &[&OsStr]) -> Result<Output, Error> {
+	debug!(?args, "Running `rustfmt +nightly`");
+	let output = Command::new("rustfmt").arg("+nightly").args(args).current_dir(path).output()?;
+	if output.status.success() {
+		Ok(output)
+	} else {
+		debug!(?args, "`rustfmt +nightly` failed, falling back to `rustfmt`");
+		Ok(Command::new("rustfmt").args(args).current_dir(path).output()?)
+	}
+}
+
+/// Runs `cargo +nightly fmt <args>` in the given path, falling back to `cargo fmt <args>` if the
+/// nightly toolchain command fails, and returns the output of the last command run.
+fn cargo_fmt_output(path: &Path, args: &[&str]) -> Result<Output, Error> {
+	Ok(cargo_fmt_run(path, args, Some(&[]))?.0)
+}
+
+/// Runs `cargo +nightly fmt <args> <nightly_args>` in the given path, falling back to `cargo fmt
+/// <args>` if the nightly toolchain command fails, and returns the output of the last command run
+/// together with how it went. The nightly toolchain isn't tried at all if `nightly_args` is `None`.
+/// A `--check` run finding unformatted code isn't a failure of the nightly toolchain, so there's no
+/// fallback then: the stable rustfmt would ignore the nightly-only options and could pass.
+fn cargo_fmt_run(
+	path: &Path,
+	args: &[&str],
+	nightly_args: Option<&[&str]>,
+) -> Result<(Output, FmtOutcome), Error> {
+	let start = Instant::now();
+	if let Some(nightly_args) = nightly_args {
+		debug!(?args, ?nightly_args, "Running `cargo +nightly fmt`");
+		let output = Command::new("cargo")
 			.arg("+nightly")
 			.arg("fmt")
-			.arg("--all")
+			.args(args)
+			.args(nightly_args)
 			.current_dir(path)
-			.output()
-			.map(|output| {
-				if output.status.success() {
-					output
-				} else {
-					Command::new("cargo")
-						.arg("fmt")
-						.arg("--all")
-						.current_dir(path)
-						.output()
-						.expect(EXPECT_MSG)
-				}
-			})
-			.map_or_else(
-				|err| Err(err.into()),
-				|output| {
-					if output.status.success() {
-						Ok(())
-					} else {
-						Err(Error::Descriptive(
-							String::from_utf8_lossy(&output.stderr).into_owned(),
-						))
-					}
-				},
-			)
-	}
-	do_format_dir(path.as_ref())
+			.output()?;
+		let found_diffs = args.contains(&"--check") &&
+			String::from_utf8_lossy(&output.stdout).contains("Diff in");
+		if output.status.success() || found_diffs {
+			let outcome = FmtOutcome {
+				toolchain_used: FmtToolchain::Nightly,
+				fallback_occurred: false,
+				duration: start.elapsed(),
+			};
+			return Ok((output, outcome));
+		}
+		debug!(?args, "`cargo +nightly fmt` failed, falling back to `cargo fmt`");
+	}
+
+	let output = Command::new("cargo").arg("fmt").args(args).current_dir(path).output();
+	let output = if nightly_args.is_some() { output.expect(EXPECT_MSG) } else { output? };
+	let outcome = FmtOutcome {
+		toolchain_used: FmtToolchain::Default,
+		fallback_occurred: nightly_args.is_some(),
+		duration: start.elapsed(),
+	};
+	Ok((output, outcome))
+}
+
+/// The outcome of a `cargo fmt` run, or an error with its stderr if it failed.
+fn outcome_of((output, outcome): (Output, FmtOutcome)) -> Result<FmtOutcome, Error> {
+	if output.status.success() {
+		Ok(outcome)
+	} else {
+		Err(Error::Descriptive(String::from_utf8_lossy(&output.stderr).into_owned()))
+	}
 }
@@ -181,3 +181,687 @@ fn format_dir_fails_without_nightly_available_if_io_error() {
 		}
 	});
 }
+
+#[test]
+fn format_dir_with_outcome_reports_the_toolchain_used() {
+	TestBuilder::default().with_nightly_component().build().execute(|builder| {
+		let outcome =
+			format_dir_with_outcome(builder.tempdir.path()).expect("This should be Ok; qed;");
+		// The nightly toolchain is only skipped if it failed
+		assert_eq!(outcome.fallback_occurred, outcome.toolchain_used == FmtToolchain::Default);
+		assert_eq!(
+			std::fs::read_to_string(&builder.fmt_code_path)
+				.expect("The file should be readable; qed;"),
+			std::fs::read_to_string(&builder.not_fmt_code_path)
+				.expect("The file should be readable; qed;")
+		);
+	});
+}
+
+#[test]
+fn format_dir_with_outcome_fails_if_the_dir_cannot_be_formatted() {
+	TestBuilder::default().with_invalid_code().build().execute(|builder| {
+		assert!(matches!(
+			format_dir_with_outcome(builder.tempdir.path()),
+			Err(Error::Descriptive(msg)) if msg.contains(&format!("{}", builder.not_fmt_code_path.display()))
+		));
+	});
+}
+
+#[cfg(feature = "toolchain")]
+#[test]
+fn format_dir_with_options_only_applies_nightly_options_with_nightly() {
+	TestBuilder::default().with_nightly_component().build().execute(|builder| {
+		let lib_path = builder.tempdir.path().join("src").join("lib.rs");
+		std::fs::write(
+			&lib_path,
+			"mod fmt_code_path;\nmod not_fmt_code_path;\n\nuse std::fmt;\nuse std::io;\n",
+		)
+		.expect("The file should be writable; qed;");
+		let options = FmtOptions::default().nightly_options(&["imports_granularity=Crate"]);
+
+		let outcome = format_dir_with_options(builder.tempdir.path(), &options)
+			.expect("This should be Ok; qed;");
+		assert_eq!(
+			std::fs::read_to_string(&builder.fmt_code_path)
+				.expect("The file should be readable; qed;"),
+			std::fs::read_to_string(&builder.not_fmt_code_path)
+				.expect("The file should be readable; qed;")
+		);
+		// The imports are only merged if the nightly rustfmt formatted the code
+		let imports = if outcome.toolchain_used == FmtToolchain::Nightly {
+			"use std::{fmt, io};\n"
+		} else {
+			"use std::fmt;\nuse std::io;\n"
+		};
+		assert_eq!(
+			std::fs::read_to_string(&lib_path).expect("The file should be readable; qed;"),
+			format!("mod fmt_code_path;\nmod not_fmt_code_path;\n\n{imports}")
+		);
+	});
+}
+
+#[cfg(feature = "manifest")]
+fn workspace_with_unformatted_members(members: &[&str]) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let members_list = members
+		.iter()
+		.map(|member| format!("\"{member}\""))
+		.collect::<Vec<_>>()
+		.join(", ");
+	std::fs::write(
+		tempdir.path().join("Cargo.toml"),
+		format!("[workspace]\nresolver = \"2\"\nmembers = [{members_list}]\n"),
+	)
+	.expect("The path should be writable; qed;");
+
+	for member in members {
+		let src_path = tempdir.path().join(member).join("src");
+		std::fs::create_dir_all(&src_path).expect("The directory should be created; qed;");
+		std::fs::write(
+			tempdir.path().join(member).join("Cargo.toml"),
+			format!("[package]\nname = \"{member}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n"),
+		)
+		.expect("The path should be writable; qed;");
+		std::fs::write(src_path.join("lib.rs"), "pub enum A {A,B,C}")
+			.expect("The file should be writable; qed;");
+	}
+	tempdir
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn format_packages_only_formats_selected_packages() {
+	let tempdir = workspace_with_unformatted_members(&["first", "second"]);
+	assert!(format_packages(tempdir.path(), &["first"]).is_ok());
+	assert_ne!(
+		std::fs::read_to_string(tempdir.path().join("first/src/lib.rs"))
+			.expect("The file should be readable; qed;"),
+		"pub enum A {A,B,C}"
+	);
+	assert_eq!(
+		std::fs::read_to_string(tempdir.path().join("second/src/lib.rs"))
+			.expect("The file should be readable; qed;"),
+		"pub enum A {A,B,C}"
+	);
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn format_crate_only_formats_the_given_crate() {
+	let tempdir = workspace_with_unformatted_members(&["first", "second"]);
+	std::fs::write(tempdir.path().join("rustfmt.toml"), "hard_tabs = true\n")
+		.expect("The path should be writable; qed;");
+
+	assert!(format_crate(tempdir.path().join("first/Cargo.toml")).is_ok());
+	// The workspace rustfmt config applies to the crate
+	assert_eq!(
+		std::fs::read_to_string(tempdir.path().join("first/src/lib.rs"))
+			.expect("The file should be readable; qed;"),
+		"pub enum A {\n\tA,\n\tB,\n\tC,\n}\n"
+	);
+	assert_eq!(
+		std::fs::read_to_string(tempdir.path().join("second/src/lib.rs"))
+			.expect("The file should be readable; qed;"),
+		"pub enum A {A,B,C}"
+	);
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn format_crate_fails_if_the_manifest_doesnt_declare_a_crate() {
+	let tempdir = workspace_with_unformatted_members(&["first"]);
+	let manifest_path = tempdir.path().join("Cargo.toml");
+	assert!(matches!(
+		format_crate(&manifest_path),
+		Err(Error::Descriptive(msg)) if msg == format!("{} doesn't declare a crate", manifest_path.display())
+	));
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn format_packages_hasnt_effect_if_no_packages_selected() {
+	let tempdir = workspace_with_unformatted_members(&["first"]);
+	assert!(format_packages(tempdir.path(), &[]).is_ok());
+	assert_eq!(
+		std::fs::read_to_string(tempdir.path().join("first/src/lib.rs"))
+			.expect("The file should be readable; qed;"),
+		"pub enum A {A,B,C}"
+	);
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn format_packages_fails_if_package_isnt_a_member() {
+	let tempdir = workspace_with_unformatted_members(&["first"]);
+	assert!(matches!(
+		format_packages(tempdir.path(), &["first", "third"]),
+		Err(Error::Descriptive(msg)) if msg == "third isn't a member of the workspace"
+	));
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn format_packages_with_events_reports_every_package() {
+	let tempdir = workspace_with_unformatted_members(&["first", "second", "third"]);
+	let (sender, receiver) = std::sync::mpsc::channel();
+
+	assert!(format_packages_with_events(tempdir.path(), &["first", "third"], &sender).is_ok());
+
+	assert_eq!(
+		receiver.try_iter().collect::<Vec<_>>(),
+		vec![
+			Event::Started { operation: "format_packages", total: Some(2) },
+			Event::Progress {
+				operation: "format_packages",
+				current: 1,
+				total: Some(2),
+				item: "first".to_owned()
+			},
+			Event::Progress {
+				operation: "format_packages",
+				current: 2,
+				total: Some(2),
+				item: "third".to_owned()
+			},
+			Event::Finished { operation: "format_packages" },
+		]
+	);
+	assert_eq!(
+		std::fs::read_to_string(tempdir.path().join("second/src/lib.rs"))
+			.expect("The file should be readable; qed;"),
+		"pub enum A {A,B,C}"
+	);
+	assert_ne!(
+		std::fs::read_to_string(tempdir.path().join("third/src/lib.rs"))
+			.expect("The file should be readable; qed;"),
+		"pub enum A {A,B,C}"
+	);
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn format_packages_with_events_fails_before_reporting_if_package_isnt_a_member() {
+	let tempdir = workspace_with_unformatted_members(&["first"]);
+	let events = std::cell::RefCell::new(Vec::new());
+
+	assert!(matches!(
+		format_packages_with_events(tempdir.path(), &["third"], &|event| events.borrow_mut().push(event)),
+		Err(Error::Descriptive(msg)) if msg == "third isn't a member of the workspace"
+	));
+	assert!(events.borrow().is_empty());
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn format_packages_fails_if_dir_isnt_a_workspace() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	assert!(matches!(
+		format_packages(tempdir.path(), &["first"]),
+		Err(Error::IO(err)) if err.kind() == ErrorKind::NotFound
+	));
+}
+
+#[cfg(all(feature = "git", feature = "manifest"))]
+#[test]
+fn format_changed_only_formats_the_changed_files() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let path = |path: &str| tempdir.path().join(path);
+	let write = |file: &str, content: &str| {
+		std::fs::write(path(file), content).expect("The file should be writable; qed;")
+	};
+	let read = |file: &str| {
+		std::fs::read_to_string(path(file)).expect("The file should be readable; qed;")
+	};
+	let git = |args: &[&str]| {
+		crate::git::run_git(tempdir.path(), args).expect("The git command should succeed; qed;");
+	};
+	std::fs::create_dir_all(path("src")).expect("The dir should be created; qed;");
+	std::fs::create_dir_all(path("target")).expect("The dir should be created; qed;");
+	write("Cargo.toml", "[package]\nname = \"test\"\nedition = \"2021\"\n");
+	write("src/lib.rs", "pub async fn a(){}");
+	write("src/untouched.rs", "pub fn b(){}");
+	git(&["init", "--quiet"]);
+	git(&["add", "-A"]);
+	git(&[
+		"-c",
+		"user.name=test",
+		"-c",
+		"user.email=test@test.com",
+		"-c",
+		"commit.gpgsign=false",
+		"commit",
+		"--quiet",
+		"-m",
+		"Initial commit",
+	]);
+
+	write("src/lib.rs", "pub async fn a(){ let r#async = 1; }");
+	write("src/new.rs", "pub fn c(){}");
+	write("target/generated.rs", "pub fn d(){}");
+	write("README.md", "# Test");
+
+	assert_eq!(
+		format_changed(tempdir.path(), "HEAD").expect("This should be Ok; qed;"),
+		vec![path("src/lib.rs"), path("src/new.rs")]
+	);
+	assert_eq!(read("src/lib.rs"), "pub async fn a() {\n    let r#async = 1;\n}\n");
+	assert_eq!(read("src/new.rs"), "pub fn c() {}\n");
+	assert_eq!(read("src/untouched.rs"), "pub fn b(){}");
+	assert_eq!(read("target/generated.rs"), "pub fn d(){}");
+
+	assert!(format_changed(tempdir.path(), "missing-ref").is_err());
+}
+
+#[test]
+fn needs_format_detects_unformatted_code() {
+	TestBuilder::default().build().execute(|builder| {
+		assert!(matches!(needs_format(builder.tempdir.path()), Ok(true)));
+		assert!(
+			!fmt_cache_path(builder.tempdir.path())
+				.expect("The crate has a target dir; qed;")
+				.exists()
+		);
+	});
+}
+
+#[test]
+fn needs_format_caches_formatted_code_until_sources_change() {
+	TestBuilder::default().build().execute(|builder| {
+		let cache_path =
+			fmt_cache_path(builder.tempdir.path()).expect("The crate has a target dir; qed;");
+		assert!(format_dir(builder.tempdir.path()).is_ok());
+
+		assert!(matches!(needs_format(builder.tempdir.path()), Ok(false)));
+		assert_eq!(
+			std::fs::read_to_string(&cache_path).expect("The cache should be readable; qed;"),
+			hash_dir_sources(builder.tempdir.path())
+				.expect("The sources should be hashable; qed;")
+				.to_string()
+		);
+
+		// The cached result is used while nothing changes
+		assert!(matches!(needs_format(builder.tempdir.path()), Ok(false)));
+
+		// Changing the sources invalidates the cache
+		std::fs::write(&builder.not_fmt_code_path, "pub enum A {A,B,C}")
+			.expect("The file should be writable; qed;");
+		assert!(matches!(needs_format(builder.tempdir.path()), Ok(true)));
+	});
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn needs_format_caches_in_the_target_dir_of_the_crate() {
+	TestBuilder::default().build().execute(|builder| {
+		assert!(format_dir(builder.tempdir.path()).is_ok());
+		let src = builder.tempdir.path().join("src");
+
+		assert!(matches!(needs_format(&src), Ok(false)));
+		assert!(!src.join("target").exists());
+		let src_cache_path = fmt_cache_path(&src).expect("The crate has a target dir; qed;");
+		assert!(src_cache_path.exists());
+		assert_eq!(
+			src_cache_path.parent(),
+			crate::manifest::target_dir(builder.tempdir.path()).ok().as_deref()
+		);
+
+		// Paths sharing a target dir keep their own cache entry
+		assert!(matches!(needs_format(builder.tempdir.path()), Ok(false)));
+		let root_cache_path =
+			fmt_cache_path(builder.tempdir.path()).expect("The crate has a target dir; qed;");
+		assert_ne!(root_cache_path, src_cache_path);
+		assert!(root_cache_path.exists() && src_cache_path.exists());
+	});
+}
+
+#[test]
+fn needs_format_doesnt_fall_back_if_nightly_finds_diffs() {
+	TestBuilder::default().with_nightly_component().build().execute(|builder| {
+		let nightly_available = Command::new("cargo")
+			.args(["+nightly", "fmt", "--version"])
+			.output()
+			.is_ok_and(|output| output.status.success());
+		if !nightly_available {
+			return;
+		}
+		assert!(format_dir(builder.tempdir.path()).is_ok());
+
+		// Only the nightly-only option is violated, so the stable rustfmt would accept the code
+		std::fs::write(
+			builder.tempdir.path().join("rustfmt.toml"),
+			"imports_granularity = \"Crate\"\n",
+		)
+		.expect("The file should be writable; qed;");
+		std::fs::write(
+			builder.tempdir.path().join("src").join("lib.rs"),
+			"mod fmt_code_path;\nmod not_fmt_code_path;\n\nuse std::fmt;\nuse std::io;\n",
+		)
+		.expect("The file should be writable; qed;");
+
+		assert!(matches!(needs_format(builder.tempdir.path()), Ok(true)));
+		assert!(
+			!fmt_cache_path(builder.tempdir.path())
+				.expect("The crate has a target dir; qed;")
+				.exists()
+		);
+	});
+}
+
+#[test]
+fn hash_dir_sources_is_stable() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::write(tempdir.path().join("lib.rs"), "pub fn f() {}\n")
+		.expect("The file should be writable; qed;");
+	assert_eq!(
+		hash_dir_sources(tempdir.path()).expect("The sources should be hashable; qed;"),
+		0x631f_33a3_dede_4425
+	);
+}
+
+#[test]
+fn hash_dir_sources_ignores_target_and_non_rust_files() {
+	TestBuilder::default().build().execute(|builder| {
+		let hash =
+			hash_dir_sources(builder.tempdir.path()).expect("The sources should be hashable; qed;");
+
+		std::fs::create_dir_all(builder.tempdir.path().join("target"))
+			.expect("The directory should be created; qed;");
+		std::fs::write(builder.tempdir.path().join("target").join("build.rs"), "fn main(){}")
+			.expect("The file should be writable; qed;");
+		std::fs::write(builder.tempdir.path().join("README.md"), "# Readme")
+			.expect("The file should be writable; qed;");
+		assert_eq!(
+			hash_dir_sources(builder.tempdir.path()).expect("The sources should be hashable; qed;"),
+			hash
+		);
+
+		std::fs::write(builder.tempdir.path().join("rustfmt.toml"), "hard_tabs = true")
+			.expect("The file should be writable; qed;");
+		assert_ne!(
+			hash_dir_sources(builder.tempdir.path()).expect("The sources should be hashable; qed;"),
+			hash
+		);
+	});
+}
+
+#[test]
+fn needs_format_fails_if_the_code_cannot_be_checked() {
+	TestBuilder::default().with_invalid_code().build().execute(|builder| {
+		assert!(matches!(
+			needs_format(builder.tempdir.path()),
+			Err(Error::Descriptive(msg)) if msg.contains(&format!("{}", builder.not_fmt_code_path.display()))
+		));
+	});
+}
+
+#[test]
+fn needs_format_fails_if_io_error() {
+	TestBuilder::default().build().execute(|builder| {
+		assert!(matches!(
+			needs_format(builder.tempdir.path().join("dir")),
+			Err(Error::IO(err)) if err.kind() == ErrorKind::NotFound
+		));
+	});
+}
+
+#[cfg(feature = "manifest")]
+fn formatted_toml(content: &str) -> String {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let path = tempdir.path().join("Cargo.toml");
+	std::fs::write(&path, content).expect("The file should be writable; qed;");
+	format_toml(&path).expect("This should be Ok; qed;");
+	std::fs::read_to_string(&path).expect("The file should be readable; qed;")
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn format_toml_normalizes_formatting() {
+	let content = r#"
+
+# The package
+[package]
+  name="foo"   # The name
+version    =   "0.1.0"
+
+
+
+edition= "2024"
+[dependencies]
+serde = {version="1.0",features=["derive"]}
+syn .  version = "2.0"
+tokio={ version = "1", features = [ "rt",  "macros" ] }
+# Comment before a table
+
+
+[features]
+default = [
+"std",   # Always on
+  # Comment before an element
+
+      "serde",
+  ]
+std = [  ]
+[[bin]]
+
+name = "bar"
+[[bin]]
+name = "baz"
+[profile.release.package.foo]
+opt-level = 3
+# Trailing comment
+
+
+
+"#;
+
+	assert_eq!(
+		formatted_toml(content),
+		r#"# The package
+[package]
+name = "foo" # The name
+version = "0.1.0"
+
+edition = "2024"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+syn.version = "2.0"
+tokio = { version = "1", features = ["rt", "macros"] }
+
+# Comment before a table
+
+[features]
+default = [
+    "std", # Always on
+    # Comment before an element
+
+    "serde",
+]
+std = []
+
+[[bin]]
+name = "bar"
+
+[[bin]]
+name = "baz"
+
+[profile.release.package.foo]
+opt-level = 3
+# Trailing comment
+"#
+	);
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn format_toml_keeps_formatted_files_untouched() {
+	let content = "[package]\nname = \"foo\"\n\n[dependencies]\nserde = { version = \"1.0\" }\n";
+
+	assert_eq!(formatted_toml(content), content);
+	assert_eq!(formatted_toml(&formatted_toml(content)), content);
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn format_toml_fails_on_invalid_toml() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let path = tempdir.path().join("Cargo.toml");
+	std::fs::write(&path, "[package").expect("The file should be writable; qed;");
+
+	assert!(matches!(format_toml(&path), Err(Error::TomlEdit(_))));
+	assert!(matches!(format_toml(tempdir.path().join("missing.toml")), Err(Error::IO(_))));
+}
+
+#[cfg(feature = "parsing")]
+fn organized_imports(content: &str, style: ImportsStyle) -> String {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let path = tempdir.path().join("lib.rs");
+	std::fs::write(&path, content).expect("The file should be writable; qed;");
+	organize_imports(&path, style).expect("This should be Ok; qed;");
+	std::fs::read_to_string(&path).expect("The file should be readable; qed;")
+}
+
+#[cfg(feature = "parsing")]
+#[test]
+fn organize_imports_merges_and_sorts_imports_by_crate() {
+	let content = r#"//! Docs
+
+use syn::Item;
+use std::io::Read;
+use crate::Error;
+
+use std::{fmt, io};
+use syn::Item;
+pub use a::B;
+pub(crate) use b::C;
+pub use a::A;
+
+fn f() {}
+"#;
+
+	assert_eq!(
+		organized_imports(content, ImportsStyle::default()),
+		r#"//! Docs
+
+use crate::Error;
+use std::{fmt, io::{self, Read}};
+use syn::Item;
+
+pub use a::{A, B};
+
+pub(crate) use b::C;
+
+fn f() {}
+"#
+	);
+}
+
+#[cfg(feature = "parsing")]
+#[test]
+fn organize_imports_groups_std_external_and_local_imports() {
+	let content = r#"use self::inner::X;
+use serde::Serialize;
+use core::fmt;
+use super::Y;
+use std::io;
+use alloc::vec::Vec;
+use crate::Error;
+"#;
+	let style = ImportsStyle { groups: ImportGroups::StdExternalCrate, ..Default::default() };
+
+	assert_eq!(
+		organized_imports(content, style),
+		r#"use alloc::vec::Vec;
+use core::fmt;
+use std::io;
+
+use serde::Serialize;
+
+use crate::Error;
+use self::inner::X;
+use super::Y;
+"#
+	);
+}
+
+#[cfg(feature = "parsing")]
+#[test]
+fn organize_imports_splits_imports_by_item() {
+	let content = "use std::{fmt, io::{self, Read}};\nuse std::fmt;\nuse syn::{*, Item as I};\n";
+	let style = ImportsStyle { granularity: ImportGranularity::Item, ..Default::default() };
+
+	assert_eq!(
+		organized_imports(content, style),
+		"use std::fmt;\nuse std::io::Read;\nuse std::io::{self};\nuse syn::*;\nuse syn::Item as I;\n"
+	);
+}
+
+#[cfg(feature = "parsing")]
+#[test]
+fn organize_imports_handles_inline_modules_and_keeps_separated_blocks() {
+	let content = r#"use b::B;
+use a::A;
+// A comment
+use d::D; // Trailing comment
+use c::C;
+#[cfg(test)]
+use f::F;
+use e::E;
+
+mod inner {
+	use std::fmt;
+	use std::io;
+
+	fn f() {
+		use y::Y;
+		use x::X;
+	}
+}
+"#;
+
+	assert_eq!(
+		organized_imports(content, ImportsStyle::default()),
+		r#"use a::A;
+use b::B;
+// A comment
+use d::D; // Trailing comment
+use c::C;
+#[cfg(test)]
+use f::F;
+use e::E;
+
+mod inner {
+	use std::{fmt, io};
+
+	fn f() {
+		use y::Y;
+		use x::X;
+	}
+}
+"#
+	);
+}
+
+#[cfg(feature = "parsing")]
+#[test]
+fn organize_imports_keeps_organized_files_untouched() {
+	let content = "use std::{fmt, io};\n\nuse syn::Item;\n\nfn f() {}\n";
+	let style = ImportsStyle { groups: ImportGroups::StdExternalCrate, ..Default::default() };
+
+	assert_eq!(organized_imports(content, style), content);
+}
+
+#[cfg(feature = "parsing")]
+#[test]
+fn organize_imports_fails_on_invalid_code() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let path = tempdir.path().join("lib.rs");
+	std::fs::write(&path, "use std::fmt").expect("The file should be writable; qed;");
+
+	assert!(matches!(organize_imports(&path, ImportsStyle::default()), Err(Error::Syn(_))));
+	assert!(matches!(
+		organize_imports(tempdir.path().join("missing.rs"), ImportsStyle::default()),
+		Err(Error::IO(_))
+	));
+}
@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! A filesystem abstraction used by the functions reading or writing files, so they can run
+//! against something else than the real disk, eg, an in-memory filesystem in tests (see
+//! `rustilities::testing::MemoryFs`, available with the `testing` feature).
+
+use std::{io, path::Path};
+
+/// The filesystem operations needed by the crate's functions. The functions taking a provider are
+/// suffixed with `_with_fs`, while their non-suffixed counterparts use [`StdFs`].
+pub trait FsProvider {
+	/// Reads the whole file at the given path into a string.
+	fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+	/// Writes the given content to the file at the given path, replacing it if it exists.
+	fn write(&self, path: &Path, content: &str) -> io::Result<()>;
+
+	/// Checks if the given path points to a file.
+	fn is_file(&self, path: &Path) -> bool;
+
+	/// Checks if the given path points to a dir.
+	fn is_dir(&self, path: &Path) -> bool;
+}
+
+/// The [`FsProvider`] backed by [`std::fs`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdFs;
+
+impl FsProvider for StdFs {
+	fn read_to_string(&self, path: &Path) -> io::Result<String> {
+		std::fs::read_to_string(path)
+	}
+
+	fn write(&self, path: &Path, content: &str) -> io::Result<()> {
+		std::fs::write(path, content)
+	}
+
+	fn is_file(&self, path: &Path) -> bool {
+		path.is_file()
+	}
+
+	fn is_dir(&self, path: &Path) -> bool {
+		path.is_dir()
+	}
+}
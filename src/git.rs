@@ -0,0 +1,287 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! Git integration, shelling out to the `git` binary, which must be available in the `PATH`.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{BumpKind, Error, macros::debug};
+use std::{
+	collections::BTreeSet,
+	path::{Component, Path, PathBuf},
+	process::Command,
+};
+
+/// Given a path, this function returns the root of the git repository containing it (the dir
+/// containing the `.git` dir, or the `.git` file for worktrees and submodules), or `None` if the
+/// path isn't part of a repository. Relative paths are resolved against the current dir. This
+/// function doesn't run any git command.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir_all(tempdir.path().join(".git")).unwrap();
+/// std::fs::create_dir_all(tempdir.path().join("crate/src")).unwrap();
+///
+/// assert_eq!(
+///     rustilities::git::find_repo_root(tempdir.path().join("crate/src")),
+///     Some(tempdir.path().to_path_buf())
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip(path), fields(path = %path.as_ref().display()))
+)]
+pub fn find_repo_root<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
+	absolute(path.as_ref())?
+		.ancestors()
+		.find(|ancestor| ancestor.join(".git").exists())
+		.map(Path::to_path_buf)
+}
+
+/// Given some paths, this function checks if any of them contains uncommitted changes (staged,
+/// unstaged or untracked files not ignored by git). The paths may belong to different repositories.
+///
+/// # Errors
+///
+/// - If some of the paths isn't part of a git repository.
+/// - If `git status` cannot be run or fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// if rustilities::git::is_dirty(&["crates/my-crate"]).unwrap() {
+///     panic!("Commit your changes before releasing");
+/// }
+/// ```
+pub fn is_dirty<P: AsRef<Path>>(paths: &[P]) -> Result<bool, Error> {
+	for path in paths {
+		let path = path.as_ref();
+		let repo_root = repo_root_of(path)?;
+		// git resolves relative pathspecs against the repository root, not the current dir
+		let pathspec = absolute(path).unwrap_or_else(|| path.to_path_buf());
+		let status = run_git(
+			&repo_root,
+			&["status".as_ref(), "--porcelain".as_ref(), "--".as_ref(), pathspec.as_os_str()],
+		)?;
+		if !status.trim().is_empty() {
+			debug!(path = %path.display(), "Dirty path found");
+			return Ok(true);
+		}
+	}
+	Ok(false)
+}
+
+/// Given a path inside a git repository and a reference (a commit, branch, tag, ...), this function
+/// returns the files of the repository that changed since that reference, sorted: those modified
+/// by later commits, those with uncommitted changes, and the untracked files not ignored by git.
+/// The paths are absolute, so deleted files are included too.
+///
+/// # Errors
+///
+/// - If the path isn't part of a git repository.
+/// - If the git commands cannot be run or fail, eg, because the reference doesn't exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// let changed = rustilities::git::changed_files_since(".", "origin/main").unwrap();
+/// ```
+pub fn changed_files_since<P: AsRef<Path>>(
+	path: P,
+	reference: &str,
+) -> Result<Vec<PathBuf>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_changed_files_since(path: &Path, reference: &str) -> Result<Vec<PathBuf>, Error> {
+		let repo_root = repo_root_of(path)?;
+		let changed = run_git(&repo_root, &["diff", "--name-only", "-z", reference, "--"])?;
+		let untracked = run_git(&repo_root, &["ls-files", "--others", "--exclude-standard", "-z"])?;
+		Ok(changed
+			.split('\0')
+			.chain(untracked.split('\0'))
+			.filter(|file| !file.is_empty())
+			.map(|file| repo_root.join(file))
+			.collect::<BTreeSet<_>>()
+			.into_iter()
+			.collect())
+	}
+	do_changed_files_since(path.as_ref(), reference)
+}
+
+/// A commit whose message follows the [Conventional Commits](https://www.conventionalcommits.org)
+/// specification, eg, `feat(parser)!: support raw identifiers`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConventionalCommit {
+	/// The hash of the commit.
+	pub hash: String,
+	/// The type of the commit, eg, `feat` or `fix`.
+	pub kind: String,
+	/// The scope of the commit, if any.
+	pub scope: Option<String>,
+	/// Whether the commit introduces a breaking change, either marked with `!` after the type or
+	/// scope, or with a `BREAKING CHANGE` footer.
+	pub breaking: bool,
+	/// The description following the type and scope.
+	pub description: String,
+	/// The rest of the commit message, including the footers.
+	pub body: String,
+}
+
+impl ConventionalCommit {
+	/// The kind of version bump the commit requires: breaking changes require a major bump, `feat`
+	/// commits a minor bump and any other commit a patch bump.
+	pub fn bump_kind(&self) -> BumpKind {
+		if self.breaking {
+			BumpKind::Major
+		} else if self.kind == "feat" {
+			BumpKind::Minor
+		} else {
+			BumpKind::Patch
+		}
+	}
+}
+
+/// Given a path inside a git repository and a revision range as understood by `git log` (eg,
+/// `v1.0.0..HEAD`), this function returns the commits in the range following the Conventional
+/// Commits specification, newest first. Commits not following it are skipped.
+///
+/// # Errors
+///
+/// - If the path isn't part of a git repository.
+/// - If `git log` cannot be run or fails, eg, because the range isn't valid.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rustilities::BumpKind;
+///
+/// let commits = rustilities::git::parse_conventional_commits(".", "v1.0.0..HEAD").unwrap();
+/// if rustilities::git::suggest_bump(&commits) == BumpKind::Major {
+///     println!("Time for a major release!");
+/// }
+/// ```
+pub fn parse_conventional_commits<P: AsRef<Path>>(
+	path: P,
+	range: &str,
+) -> Result<Vec<ConventionalCommit>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_parse_conventional_commits(
+		path: &Path,
+		range: &str,
+	) -> Result<Vec<ConventionalCommit>, Error> {
+		let repo_root = repo_root_of(path)?;
+		// Commits are separated by the record separator char, and hashes and messages by the unit
+		// separator char
+		let log = run_git(&repo_root, &["log", "--format=%H%x1f%B%x1e", range, "--"])?;
+		Ok(log
+			.split('\x1e')
+			.filter_map(|commit| commit.trim_start().split_once('\x1f'))
+			.filter_map(|(hash, message)| parse_conventional_commit(hash, message))
+			.collect())
+	}
+	do_parse_conventional_commits(path.as_ref(), range)
+}
+
+/// Given some commits, this function returns the biggest bump they require, as computed by
+/// [`ConventionalCommit::bump_kind`]. A patch bump is suggested if there aren't commits.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::{BumpKind, git::ConventionalCommit};
+///
+/// let commit = |kind: &str, breaking: bool| ConventionalCommit {
+///     hash: String::new(),
+///     kind: kind.to_owned(),
+///     scope: None,
+///     breaking,
+///     description: String::new(),
+///     body: String::new(),
+/// };
+///
+/// assert_eq!(rustilities::git::suggest_bump(&[commit("fix", false)]), BumpKind::Patch);
+/// assert_eq!(
+///     rustilities::git::suggest_bump(&[commit("fix", false), commit("feat", false)]),
+///     BumpKind::Minor
+/// );
+/// assert_eq!(rustilities::git::suggest_bump(&[commit("fix", true)]), BumpKind::Major);
+/// ```
+pub fn suggest_bump(commits: &[ConventionalCommit]) -> BumpKind {
+	commits
+		.iter()
+		.map(ConventionalCommit::bump_kind)
+		.max()
+		.unwrap_or(BumpKind::Patch)
+}
+
+/// Parses a commit message following the Conventional Commits specification.
+fn parse_conventional_commit(hash: &str, message: &str) -> Option<ConventionalCommit> {
+	let message = message.trim();
+	let (header, body) = message.split_once('\n').unwrap_or((message, ""));
+	let (prefix, description) = header.split_once(": ")?;
+	let (prefix, breaking_mark) = match prefix.strip_suffix('!') {
+		Some(prefix) => (prefix, true),
+		None => (prefix, false),
+	};
+	let (kind, scope) = match prefix.split_once('(') {
+		Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+		None => (prefix, None),
+	};
+	let is_word = |word: &str| {
+		!word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+	};
+	if !is_word(kind) || scope.is_some_and(|scope| scope.is_empty() || scope.contains(')')) {
+		return None;
+	}
+
+	let body = body.trim().to_owned();
+	let breaking = breaking_mark ||
+		body.lines().any(|line| {
+			line.starts_with("BREAKING CHANGE: ") || line.starts_with("BREAKING-CHANGE: ")
+		});
+	Some(ConventionalCommit {
+		hash: hash.trim().to_owned(),
+		kind: kind.to_lowercase(),
+		scope: scope.map(str::to_owned),
+		breaking,
+		description: description.trim().to_owned(),
+		body,
+	})
+}
+
+/// The absolute form of a path, resolved against the current dir if it's relative and without `.`
+/// or `..` components, so its ancestors are the dirs actually containing it.
+fn absolute(path: &Path) -> Option<PathBuf> {
+	let mut absolute = PathBuf::new();
+	for component in std::path::absolute(path).ok()?.components() {
+		match component {
+			Component::CurDir => (),
+			Component::ParentDir
+				if matches!(absolute.components().next_back(), Some(Component::Normal(_))) =>
+			{
+				absolute.pop();
+			},
+			component => absolute.push(component),
+		}
+	}
+	Some(absolute)
+}
+
+fn repo_root_of(path: &Path) -> Result<PathBuf, Error> {
+	find_repo_root(path).ok_or_else(|| {
+		Error::Descriptive(format!("{} isn't part of a git repository", path.display()))
+	})
+}
+
+/// Runs git with the given args in the given dir, returning its stdout.
+pub(crate) fn run_git<S: AsRef<std::ffi::OsStr>>(dir: &Path, args: &[S]) -> Result<String, Error> {
+	debug!(dir = %dir.display(), "Running git");
+	let output = Command::new("git").args(args).current_dir(dir).output()?;
+	if output.status.success() {
+		Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+	} else {
+		Err(Error::Descriptive(String::from_utf8_lossy(&output.stderr).trim().to_owned()))
+	}
+}
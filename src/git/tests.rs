@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) {
+	run_git(dir, args).expect("The git command should succeed; qed;");
+}
+
+fn repo_with_commit() -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	git(tempdir.path(), &["init", "--quiet"]);
+	git(tempdir.path(), &["config", "user.name", "test"]);
+	git(tempdir.path(), &["config", "user.email", "test@test.com"]);
+	git(tempdir.path(), &["config", "commit.gpgsign", "false"]);
+	std::fs::create_dir_all(tempdir.path().join("a")).expect("This should be created; qed;");
+	std::fs::write(tempdir.path().join("a/file"), "a").expect("The file should be writable; qed;");
+	std::fs::write(tempdir.path().join("b"), "b").expect("The file should be writable; qed;");
+	std::fs::write(tempdir.path().join(".gitignore"), "ignored\n")
+		.expect("The file should be writable; qed;");
+	git(tempdir.path(), &["add", "-A"]);
+	git(tempdir.path(), &["commit", "--quiet", "-m", "Initial commit"]);
+	tempdir
+}
+
+#[test]
+fn find_repo_root_works_with_git_files() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let submodule = tempdir.path().join("submodule");
+	std::fs::create_dir_all(submodule.join("src")).expect("This should be created; qed;");
+	std::fs::write(submodule.join(".git"), "gitdir: ../.git/modules/submodule")
+		.expect("The file should be writable; qed;");
+
+	assert_eq!(find_repo_root(submodule.join("src")), Some(submodule));
+	assert!(find_repo_root(tempdir.path()).is_none_or(|root| !root.starts_with(tempdir.path())));
+}
+
+#[test]
+fn is_dirty_detects_uncommitted_changes() {
+	let tempdir = repo_with_commit();
+	let path = |path: &str| tempdir.path().join(path);
+
+	assert!(!is_dirty(&[path("a"), path("b")]).expect("This should be Ok; qed;"));
+	assert!(!is_dirty::<PathBuf>(&[]).expect("This should be Ok; qed;"));
+
+	std::fs::write(path("ignored"), "").expect("The file should be writable; qed;");
+	assert!(!is_dirty(&[tempdir.path()]).expect("This should be Ok; qed;"));
+
+	std::fs::write(path("a/file"), "changed").expect("The file should be writable; qed;");
+	assert!(is_dirty(&[path("a")]).expect("This should be Ok; qed;"));
+	assert!(!is_dirty(&[path("b")]).expect("This should be Ok; qed;"));
+
+	std::fs::write(path("c"), "").expect("The file should be writable; qed;");
+	assert!(is_dirty(&[path("c")]).expect("This should be Ok; qed;"));
+}
+
+#[test]
+fn git_functions_resolve_relative_paths_against_the_current_dir() {
+	let tempdir = repo_with_commit();
+	let current_dir = std::env::current_dir().expect("The current dir should be known; qed;");
+	// Reach the repo dir going up to the root from the current dir
+	let relative: PathBuf = current_dir
+		.components()
+		.skip(1)
+		.map(|_| Component::ParentDir.as_os_str())
+		.chain(tempdir.path().components().skip(1).map(|component| component.as_os_str()))
+		.collect();
+	let path = |path: &str| relative.join(path);
+
+	assert_eq!(find_repo_root(path("a")), Some(tempdir.path().to_path_buf()));
+	assert!(!is_dirty(&[path("a")]).expect("This should be Ok; qed;"));
+
+	std::fs::write(tempdir.path().join("a/file"), "changed")
+		.expect("The file should be writable; qed;");
+	assert!(is_dirty(&[path("a")]).expect("This should be Ok; qed;"));
+	assert!(!is_dirty(&[path("b")]).expect("This should be Ok; qed;"));
+}
+
+#[test]
+fn changed_files_since_includes_commits_changes_and_untracked_files() {
+	let tempdir = repo_with_commit();
+	let path = |path: &str| tempdir.path().join(path);
+	git(tempdir.path(), &["tag", "v1"]);
+
+	std::fs::write(path("a/file"), "changed").expect("The file should be writable; qed;");
+	git(tempdir.path(), &["commit", "--quiet", "-am", "Change a"]);
+	std::fs::remove_file(path("b")).expect("The file should be removed; qed;");
+	std::fs::write(path("c"), "").expect("The file should be writable; qed;");
+	std::fs::write(path("ignored"), "").expect("The file should be writable; qed;");
+
+	assert_eq!(
+		changed_files_since(path("a"), "v1").expect("This should be Ok; qed;"),
+		vec![path("a/file"), path("b"), path("c")]
+	);
+	assert!(matches!(
+		changed_files_since(tempdir.path(), "unexisting"),
+		Err(Error::Descriptive(msg)) if msg.contains("unexisting")
+	));
+}
+
+#[test]
+fn git_functions_fail_outside_repositories() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	// Skip the test if the tempdir lives inside a repository
+	if find_repo_root(tempdir.path()).is_some() {
+		return;
+	}
+
+	let expected_msg = format!("{} isn't part of a git repository", tempdir.path().display());
+	assert!(matches!(
+		is_dirty(&[tempdir.path()]),
+		Err(Error::Descriptive(msg)) if msg == expected_msg
+	));
+	assert!(matches!(
+		changed_files_since(tempdir.path(), "HEAD"),
+		Err(Error::Descriptive(msg)) if msg == expected_msg
+	));
+}
+
+#[test]
+fn parse_conventional_commit_works() {
+	assert_eq!(
+		parse_conventional_commit("abc", "feat(parser)!: support raw identifiers\n\nSome body.\n"),
+		Some(ConventionalCommit {
+			hash: "abc".to_owned(),
+			kind: "feat".to_owned(),
+			scope: Some("parser".to_owned()),
+			breaking: true,
+			description: "support raw identifiers".to_owned(),
+			body: "Some body.".to_owned(),
+		})
+	);
+	assert_eq!(
+		parse_conventional_commit("abc", "Fix: a bug\n\nBREAKING CHANGE: the API changed")
+			.map(|commit| (commit.kind, commit.scope, commit.breaking)),
+		Some(("fix".to_owned(), None, true))
+	);
+	assert_eq!(
+		parse_conventional_commit("abc", "docs: typo").map(|commit| commit.bump_kind()),
+		Some(BumpKind::Patch)
+	);
+}
+
+#[test]
+fn parse_conventional_commit_rejects_other_messages() {
+	for message in ["Fix a bug", "feat():  empty scope", "feat(scope: x", "two words: x", ": x"] {
+		assert!(parse_conventional_commit("abc", message).is_none(), "{message}");
+	}
+}
+
+#[test]
+fn parse_conventional_commits_reads_range() {
+	let tempdir = repo_with_commit();
+	git(tempdir.path(), &["tag", "v1"]);
+	for message in ["fix: a bug", "Not conventional", "feat(api): a feature\n\nWith body"] {
+		git(tempdir.path(), &["commit", "--quiet", "--allow-empty", "-m", message]);
+	}
+
+	let commits =
+		parse_conventional_commits(tempdir.path(), "v1..HEAD").expect("This should be Ok; qed;");
+
+	assert_eq!(
+		commits
+			.iter()
+			.map(|commit| (commit.kind.as_str(), commit.description.as_str(), commit.body.as_str()))
+			.collect::<Vec<_>>(),
+		vec![("feat", "a feature", "With body"), ("fix", "a bug", "")]
+	);
+	assert!(commits.iter().all(|commit| commit.hash.len() == 40));
+	assert_eq!(suggest_bump(&commits), BumpKind::Minor);
+	assert_eq!(suggest_bump(&[]), BumpKind::Patch);
+	assert!(parse_conventional_commits(tempdir.path(), "unexisting..HEAD").is_err());
+}
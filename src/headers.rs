@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: GPL-3.0
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+	Error,
+	diagnostic::{Diagnostic, Severity, ToDiagnostics},
+	macros::debug,
+};
+use std::path::{Path, PathBuf};
+
+/// Words identifying a leading comment block as a license header, compared case-insensitively.
+const HEADER_MARKERS: [&str; 3] = ["spdx-license-identifier", "copyright", "license"];
+
+/// The reason why a file doesn't have the required header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "snake_case")
+)]
+pub enum HeaderViolationKind {
+	/// The file doesn't start with a license header.
+	Missing,
+	/// The file starts with a license header, but it's different from the required one.
+	Outdated,
+}
+
+/// A file that doesn't have the required header, as reported by [`check_headers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeaderViolation {
+	/// The path to the file.
+	pub path: PathBuf,
+	/// The reason why the file doesn't have the required header.
+	pub kind: HeaderViolationKind,
+}
+
+impl ToDiagnostics for HeaderViolation {
+	/// A `headers::missing` or `headers::outdated` warning pointing to the first line of the file.
+	fn to_diagnostics(&self) -> Vec<Diagnostic> {
+		let (code, message) = match self.kind {
+			HeaderViolationKind::Missing => ("headers::missing", "The license header is missing"),
+			HeaderViolationKind::Outdated =>
+				("headers::outdated", "The license header is outdated"),
+		};
+		vec![
+			Diagnostic::new(Severity::Warning, code, message)
+				.with_path(&self.path)
+				.with_line(1),
+		]
+	}
+}
+
+/// Given a dir and a header, eg `// SPDX-License-Identifier: GPL-3.0`, this function checks that
+/// every `.rs` file in the dir (recursively, skipping hidden and `target` dirs) starts with that
+/// header, returning the files that don't, sorted by path.
+///
+/// A file whose first lines are a `//` comment block mentioning a SPDX identifier, a copyright or a
+/// license is deemed to have an outdated header. Otherwise, the header is deemed missing.
+///
+/// # Errors
+///
+/// - If the dir or some of the files cannot be read.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::headers::{HeaderViolation, HeaderViolationKind};
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::write(tempdir.path().join("lib.rs"), "// SPDX-License-Identifier: GPL-3.0\n\nmod a;\n")
+///     .unwrap();
+/// std::fs::write(tempdir.path().join("a.rs"), "fn f() {}\n").unwrap();
+///
+/// assert_eq!(
+///     rustilities::headers::check_headers(tempdir.path(), "// SPDX-License-Identifier: GPL-3.0")
+///         .unwrap(),
+///     vec![HeaderViolation {
+///         path: tempdir.path().join("a.rs"),
+///         kind: HeaderViolationKind::Missing
+///     }]
+/// );
+/// ```
+pub fn check_headers<P: AsRef<Path>>(dir: P, header: &str) -> Result<Vec<HeaderViolation>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_check_headers(dir: &Path, header: &str) -> Result<Vec<HeaderViolation>, Error> {
+		let mut violations = Vec::new();
+		for path in rust_files(dir)? {
+			let content = std::fs::read_to_string(&path)?;
+			if let Some(kind) = violation_kind(&content, header) {
+				violations.push(HeaderViolation { path, kind });
+			}
+		}
+		Ok(violations)
+	}
+	do_check_headers(dir.as_ref(), header)
+}
+
+/// Given a dir and a header, this function fixes the violations reported by [`check_headers`],
+/// returning them:
+/// - Missing headers are inserted at the beginning of the files, followed by a blank line.
+/// - Outdated headers are replaced by the required one.
+///
+/// # Errors
+///
+/// - If the dir or some of the files cannot be read.
+/// - If some of the files cannot be overwritten.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let file_path = tempdir.path().join("lib.rs");
+/// std::fs::write(&file_path, "// SPDX-License-Identifier: MIT\n\nmod a;\n").unwrap();
+///
+/// let fixed =
+///     rustilities::headers::fix_headers(tempdir.path(), "// SPDX-License-Identifier: GPL-3.0")
+///         .unwrap();
+///
+/// assert_eq!(fixed.len(), 1);
+/// assert_eq!(
+///     std::fs::read_to_string(&file_path).unwrap(),
+///     "// SPDX-License-Identifier: GPL-3.0\n\nmod a;\n"
+/// );
+/// ```
+pub fn fix_headers<P: AsRef<Path>>(dir: P, header: &str) -> Result<Vec<HeaderViolation>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_fix_headers(dir: &Path, header: &str) -> Result<Vec<HeaderViolation>, Error> {
+		let header = header.trim_end_matches('\n');
+		let violations = check_headers(dir, header)?;
+		for violation in &violations {
+			let content = std::fs::read_to_string(&violation.path)?;
+			let (bom, content) = split_bom(&content);
+			let content = match violation.kind {
+				HeaderViolationKind::Missing if content.is_empty() => format!("{bom}{header}\n"),
+				HeaderViolationKind::Missing => format!("{bom}{header}\n\n{content}"),
+				HeaderViolationKind::Outdated => {
+					let block_len = leading_comment_block(content).len();
+					format!("{bom}{header}{}", &content[block_len..])
+				},
+			};
+			debug!(path = %violation.path.display(), "Writing source file");
+			std::fs::write(&violation.path, content)?;
+		}
+		Ok(violations)
+	}
+	do_fix_headers(dir.as_ref(), header)
+}
+
+fn violation_kind(content: &str, header: &str) -> Option<HeaderViolationKind> {
+	let header = header.trim_end_matches('\n');
+	let (_, content) = split_bom(content);
+	match content.strip_prefix(header) {
+		Some(rest) if rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n") => None,
+		_ => {
+			let block = leading_comment_block(content).to_lowercase();
+			if HEADER_MARKERS.iter().any(|marker| block.contains(marker)) {
+				Some(HeaderViolationKind::Outdated)
+			} else {
+				Some(HeaderViolationKind::Missing)
+			}
+		},
+	}
+}
+
+fn split_bom(content: &str) -> (&str, &str) {
+	match content.strip_prefix('\u{feff}') {
+		Some(content) => ("\u{feff}", content),
+		None => ("", content),
+	}
+}
+
+/// The leading lines of the content that are `//` comments (but not doc comments), without the
+/// line break ending the block.
+fn leading_comment_block(content: &str) -> &str {
+	let mut len = 0;
+	for line in content.split_inclusive('\n') {
+		let is_comment =
+			line.starts_with("//") && !line.starts_with("///") && !line.starts_with("//!");
+		if !is_comment {
+			break;
+		}
+		len += line.len();
+	}
+	content[..len].trim_end_matches(['\n', '\r'])
+}
+
+/// The `.rs` files inside a dir, recursively and sorted by path. Hidden and `target` dirs are
+/// skipped.
+fn rust_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+	let mut files = Vec::new();
+	let mut dirs = vec![dir.to_path_buf()];
+	while let Some(dir) = dirs.pop() {
+		for entry in std::fs::read_dir(&dir)? {
+			let path = entry?.path();
+			let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+			if path.is_dir() {
+				if !name.starts_with('.') && name != "target" {
+					dirs.push(path);
+				}
+			} else if path.extension().is_some_and(|extension| extension == "rs") {
+				files.push(path);
+			}
+		}
+	}
+	files.sort();
+	Ok(files)
+}
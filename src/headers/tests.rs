@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+const HEADER: &str = "// SPDX-License-Identifier: GPL-3.0";
+
+fn dir_with_files(files: &[(&str, &str)]) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	for (path, content) in files {
+		let path = tempdir.path().join(path);
+		std::fs::create_dir_all(path.parent().expect("A file always lives inside a dir; qed"))
+			.expect("This should be created; qed;");
+		std::fs::write(path, content).expect("The file should be writable; qed;");
+	}
+	tempdir
+}
+
+#[test]
+fn check_headers_reports_missing_and_outdated_headers() {
+	let tempdir = dir_with_files(&[
+		("src/lib.rs", "// SPDX-License-Identifier: GPL-3.0\n\nmod a;\n"),
+		("src/a.rs", "// Copyright (c) Someone\n// Licensed under MIT\n\nfn f() {}\n"),
+		("src/a/b.rs", "// Some comment\nfn f() {}\n"),
+		("src/c.rs", "\u{feff}// SPDX-License-Identifier: GPL-3.0"),
+		("src/d.rs", "// SPDX-License-Identifier: GPL-3.0-or-later\n"),
+		("src/e.rs", "//! Docs mentioning the license.\n"),
+		("README.md", "No header"),
+		("target/debug/build.rs", ""),
+		(".hidden/file.rs", ""),
+	]);
+	let path = |path: &str| tempdir.path().join(path);
+
+	assert_eq!(
+		check_headers(tempdir.path(), HEADER).expect("This should be Ok; qed;"),
+		vec![
+			HeaderViolation { path: path("src/a/b.rs"), kind: HeaderViolationKind::Missing },
+			HeaderViolation { path: path("src/a.rs"), kind: HeaderViolationKind::Outdated },
+			HeaderViolation { path: path("src/d.rs"), kind: HeaderViolationKind::Outdated },
+			HeaderViolation { path: path("src/e.rs"), kind: HeaderViolationKind::Missing },
+		]
+	);
+}
+
+#[test]
+fn fix_headers_inserts_and_updates_headers() {
+	let tempdir = dir_with_files(&[
+		("lib.rs", "// SPDX-License-Identifier: GPL-3.0\n\nmod a;\n"),
+		("a.rs", "// Copyright (c) Someone\n// Licensed under MIT\n\nfn f() {}\n"),
+		("b.rs", "// Some comment\nfn f() {}\n"),
+		("c.rs", ""),
+		("d.rs", "\u{feff}fn f() {}\n"),
+	]);
+	let read = |path: &str| {
+		std::fs::read_to_string(tempdir.path().join(path)).expect("This should be Ok; qed;")
+	};
+
+	assert_eq!(
+		fix_headers(tempdir.path(), &format!("{HEADER}\n"))
+			.map(|fixed| fixed.len())
+			.ok(),
+		Some(4)
+	);
+
+	assert_eq!(read("lib.rs"), "// SPDX-License-Identifier: GPL-3.0\n\nmod a;\n");
+	assert_eq!(read("a.rs"), "// SPDX-License-Identifier: GPL-3.0\n\nfn f() {}\n");
+	assert_eq!(read("b.rs"), "// SPDX-License-Identifier: GPL-3.0\n\n// Some comment\nfn f() {}\n");
+	assert_eq!(read("c.rs"), "// SPDX-License-Identifier: GPL-3.0\n");
+	assert_eq!(read("d.rs"), "\u{feff}// SPDX-License-Identifier: GPL-3.0\n\nfn f() {}\n");
+	assert!(
+		check_headers(tempdir.path(), HEADER)
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+}
+
+#[test]
+fn fix_headers_supports_multiline_headers() {
+	let header = "// Copyright (c) Someone\n// SPDX-License-Identifier: MIT";
+	let tempdir = dir_with_files(&[("lib.rs", "// SPDX-License-Identifier: GPL-3.0\nmod a;\n")]);
+
+	fix_headers(tempdir.path(), header).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		std::fs::read_to_string(tempdir.path().join("lib.rs")).expect("This should be Ok; qed;"),
+		format!("{header}\nmod a;\n")
+	);
+}
+
+#[test]
+fn check_headers_fails_if_dir_doesnt_exist() {
+	let tempdir = dir_with_files(&[]);
+	assert!(matches!(
+		check_headers(tempdir.path().join("unexisting"), HEADER),
+		Err(Error::IO(err)) if err.kind() == std::io::ErrorKind::NotFound
+	));
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! This module turns the reports produced by this crate into JSON, so they can be consumed by
+//! editors, CI bots and any other tool not written in Rust.
+//!
+//! With the `serde` feature, the report types of the enabled features (eg,
+//! [`ApiDiff`](crate::parsing::ApiDiff), [`HeaderViolation`](crate::headers::HeaderViolation) or
+//! [`FeatureConsistencyReport`](crate::manifest::FeatureConsistencyReport)) implement
+//! [`serde::Serialize`], and most of them [`serde::Deserialize`] as well. Their schemas are part of
+//! the public API of the crate, so they only change in breaking releases:
+//! - Structs are objects whose keys are the names of the fields.
+//! - Enums without data are strings, the name of the variant in `snake_case`.
+//! - Paths are strings, so paths that aren't valid UTF-8 cannot be serialized.
+//! - Rust code, as the `cfg` predicates of a [`TestFn`](crate::parsing::TestFn), is a string
+//!   containing the code.
+//!
+//! Reports returned as plain collections, eg the unused dependencies by member returned by
+//! [`unused_dependencies_all_members`](crate::manifest::unused_dependencies_all_members) or the
+//! feature combinations returned by [`feature_powerset`](crate::manifest::feature_powerset), are
+//! serialized as the corresponding JSON objects and arrays.
+
+#[cfg(test)]
+mod tests;
+
+use crate::Error;
+use serde::Serialize;
+
+/// Serialization of a value to JSON. This trait is implemented by every serializable type.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::json::ToJson;
+/// use std::collections::BTreeMap;
+///
+/// let unused = BTreeMap::from([("cli", vec!["regex"])]);
+/// assert_eq!(unused.to_json().unwrap(), r#"{"cli":["regex"]}"#);
+/// assert_eq!(unused.to_json_pretty().unwrap(), "{\n  \"cli\": [\n    \"regex\"\n  ]\n}");
+/// ```
+pub trait ToJson {
+	/// Serializes the value to compact JSON.
+	///
+	/// # Errors
+	///
+	/// - If the value cannot be serialized, eg because it contains a path that isn't valid UTF-8.
+	fn to_json(&self) -> Result<String, Error>;
+
+	/// Serializes the value to pretty-printed JSON.
+	///
+	/// # Errors
+	///
+	/// - If the value cannot be serialized, eg because it contains a path that isn't valid UTF-8.
+	fn to_json_pretty(&self) -> Result<String, Error>;
+}
+
+impl<T: Serialize + ?Sized> ToJson for T {
+	fn to_json(&self) -> Result<String, Error> {
+		Ok(serde_json::to_string(self)?)
+	}
+
+	fn to_json_pretty(&self) -> Result<String, Error> {
+		Ok(serde_json::to_string_pretty(self)?)
+	}
+}
+
+/// Serializes Rust code as the string containing it.
+#[cfg(feature = "parsing")]
+pub(crate) fn serialize_tokens<T, S>(tokens: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+	T: quote::ToTokens,
+	S: serde::Serializer,
+{
+	serializer.serialize_str(&tokens.to_token_stream().to_string())
+}
+
+/// Serializes a list of Rust code fragments as a list of strings containing them.
+#[cfg(feature = "parsing")]
+pub(crate) fn serialize_tokens_seq<T, S>(tokens: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+	T: quote::ToTokens,
+	S: serde::Serializer,
+{
+	serializer.collect_seq(tokens.iter().map(|tokens| tokens.to_token_stream().to_string()))
+}
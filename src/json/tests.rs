@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use std::collections::BTreeMap;
+
+#[test]
+fn to_json_serializes_collections() {
+	let unused = BTreeMap::from([("cli", vec!["regex", "serde"]), ("core", vec![])]);
+
+	assert_eq!(
+		unused.to_json().expect("This should be Ok; qed;"),
+		r#"{"cli":["regex","serde"],"core":[]}"#
+	);
+	assert_eq!(
+		vec![vec!["a"], vec!["a", "b"]].to_json().expect("This should be Ok; qed;"),
+		r#"[["a"],["a","b"]]"#
+	);
+}
+
+#[cfg(feature = "headers")]
+#[test]
+fn to_json_serializes_paths_and_enums_as_strings() {
+	use crate::headers::{HeaderViolation, HeaderViolationKind};
+
+	let violations = vec![
+		HeaderViolation { path: "src/lib.rs".into(), kind: HeaderViolationKind::Missing },
+		HeaderViolation { path: "src/main.rs".into(), kind: HeaderViolationKind::Outdated },
+	];
+
+	assert_eq!(
+		violations.to_json().expect("This should be Ok; qed;"),
+		r#"[{"path":"src/lib.rs","kind":"missing"},{"path":"src/main.rs","kind":"outdated"}]"#
+	);
+}
+
+#[cfg(all(feature = "headers", unix))]
+#[test]
+fn to_json_fails_if_a_path_isnt_valid_utf8() {
+	use crate::headers::{HeaderViolation, HeaderViolationKind};
+	use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+	let violation = HeaderViolation {
+		path: OsStr::from_bytes(&[0xff]).into(),
+		kind: HeaderViolationKind::Missing,
+	};
+
+	assert!(matches!(violation.to_json(), Err(Error::Json(_))));
+}
+
+#[cfg(feature = "parsing")]
+#[test]
+fn to_json_serializes_parsing_reports() {
+	use crate::parsing::{
+		ApiDiff, Invocation, InvocationPosition, Marker, MarkerKind, TestFn, TestKind,
+	};
+	use syn::parse_quote;
+
+	let diff = ApiDiff {
+		added: vec!["crate::new".to_owned()],
+		removed: vec![],
+		changed: vec!["crate::S".to_owned()],
+	};
+	assert_eq!(
+		diff.to_json().expect("This should be Ok; qed;"),
+		r#"{"added":["crate::new"],"removed":[],"changed":["crate::S"]}"#
+	);
+
+	let marker = Marker {
+		kind: MarkerKind::PanicTodo,
+		file: "src/lib.rs".into(),
+		line: 3,
+		column: 5,
+		message: "TODO: finish".to_owned(),
+	};
+	assert_eq!(
+		marker.to_json().expect("This should be Ok; qed;"),
+		r#"{"kind":"panic_todo","file":"src/lib.rs","line":3,"column":5,"message":"TODO: finish"}"#
+	);
+
+	let test = TestFn {
+		name: "works".to_owned(),
+		module_path: "crate::tests".to_owned(),
+		file: "src/tests.rs".into(),
+		kind: TestKind::Tokio,
+		cfgs: vec![parse_quote!(test), parse_quote!(feature = "std")],
+	};
+	assert_eq!(
+		test.to_json().expect("This should be Ok; qed;"),
+		r#"{"name":"works","module_path":"crate::tests","file":"src/tests.rs","kind":"tokio","cfgs":["test","feature = \"std\""]}"#
+	);
+
+	let invocation = Invocation {
+		file: "src/lib.rs".into(),
+		line: 1,
+		column: 1,
+		position: InvocationPosition::Expr,
+		args: quote::quote! { "{}", x },
+	};
+	assert_eq!(
+		invocation.to_json().expect("This should be Ok; qed;"),
+		r#"{"file":"src/lib.rs","line":1,"column":1,"position":"expr","args":"\"{}\" , x"}"#
+	);
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn to_json_serializes_manifest_reports() {
+	use crate::manifest::{DependencyKind, EffectiveFeatures, MembershipStatus, WorkspaceGraph};
+
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	for (member, manifest) in [
+		("core", "[package]\nname = \"core\""),
+		("cli", "[package]\nname = \"cli\"\n\n[dev-dependencies]\ncore = { path = \"../core\" }"),
+	] {
+		std::fs::create_dir_all(tempdir.path().join(member))
+			.expect("The dir should be created; qed;");
+		std::fs::write(tempdir.path().join(member).join("Cargo.toml"), manifest)
+			.expect("The file should be writable; qed;");
+	}
+	std::fs::write(tempdir.path().join("Cargo.toml"), "[workspace]\nmembers = [\"*\"]")
+		.expect("The file should be writable; qed;");
+	let graph =
+		WorkspaceGraph::load(tempdir.path().join("Cargo.toml")).expect("This should be Ok; qed;");
+	let path = |member: &str| tempdir.path().join(member).join("Cargo.toml").display().to_string();
+
+	assert_eq!(
+		serde_json::from_str::<serde_json::Value>(
+			&graph.to_json().expect("This should be Ok; qed;")
+		)
+		.expect("This should be Ok; qed;"),
+		serde_json::json!({
+			"members": [
+				{
+					"name": "cli",
+					"manifest_path": path("cli"),
+					"dependencies": [{ "name": "core", "kind": "dev" }],
+				},
+				{ "name": "core", "manifest_path": path("core"), "dependencies": [] },
+			]
+		})
+	);
+	assert_eq!(
+		(MembershipStatus::NotAMember, DependencyKind::Build)
+			.to_json()
+			.expect("This should be Ok; qed;"),
+		r#"["not_a_member","build"]"#
+	);
+	assert_eq!(
+		EffectiveFeatures { default_features: false, features: vec!["std".to_owned()] }
+			.to_json()
+			.expect("This should be Ok; qed;"),
+		r#"{"default_features":false,"features":["std"]}"#
+	);
+}
+
+#[cfg(all(feature = "manifest", feature = "parsing"))]
+#[test]
+fn reports_can_be_read_back() {
+	use crate::manifest::FeatureConsistencyReport;
+
+	let report = FeatureConsistencyReport {
+		unused: vec!["legacy".to_owned()],
+		undeclared: vec!["serde".to_owned()],
+	};
+
+	assert_eq!(
+		serde_json::from_str::<FeatureConsistencyReport>(
+			&report.to_json_pretty().expect("This should be Ok; qed;")
+		)
+		.expect("This should be Ok; qed;"),
+		report
+	);
+}
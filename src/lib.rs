@@ -9,10 +9,45 @@
 //!
 //! The crate splits is functionalities into several features, allowing to compile only the
 //! parts that are needed.
+//!
+//! The `tracing` feature doesn't add any functionality by itself, but instruments the operations
+//! touching the filesystem or running external commands (eg, manifest lookups and edits, `cargo
+//! fmt` invocations) with [`tracing`](https://docs.rs/tracing) spans and events.
+//!
+//! Similarly, the `rayon` feature runs some workspace-wide operations in parallel using
+//! [`rayon`](https://docs.rs/rayon), eg [`manifest::unused_dependencies_all_members`], and enables
+//! their explicitly parallel variants, eg [`manifest::WorkspaceGraph::load_parallel`]. The output
+//! of the operations doesn't depend on the order in which the work is done.
+//!
+//! The `serde` feature makes the reports produced by the other features serializable, and adds the
+//! [`json`] module to turn them into JSON. See its documentation for the guarantees about the
+//! resulting schemas.
+//!
+//! The `registry` feature adds lookups against the crates.io API to the [`manifest`] module, eg
+//! [`manifest::dependency_metadata`], caching the responses on disk (see
+//! [`manifest::RegistryCache`]).
+//!
+//! # Output ordering
+//!
+//! Tools often diff the outputs of this crate across runs, so every output is deterministic: maps
+//! and sets are returned as [`BTreeMap`](std::collections::BTreeMap) and
+//! [`BTreeSet`](std::collections::BTreeSet), and lists are explicitly sorted (their documentation
+//! states how) or follow the order of the source they're read from, eg the order of the items in a
+//! file. Hash-based collections may be used internally, but their iteration order never leaks into
+//! the returned values.
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(any(feature = "git", feature = "manifest"))]
+mod bump_kind;
 mod error;
+mod macros;
+#[cfg(all(test, any(feature = "cargo", feature = "manifest")))]
+mod test_utils;
+
+pub mod diagnostic;
+pub mod events;
+pub mod fs;
 
 #[cfg(feature = "paths")]
 #[cfg_attr(docsrs, doc(cfg(feature = "paths")))]
@@ -22,6 +57,26 @@ pub mod paths;
 #[cfg_attr(docsrs, doc(cfg(feature = "fmt")))]
 pub mod fmt;
 
+#[cfg(feature = "headers")]
+#[cfg_attr(docsrs, doc(cfg(feature = "headers")))]
+pub mod headers;
+
+#[cfg(feature = "changelog")]
+#[cfg_attr(docsrs, doc(cfg(feature = "changelog")))]
+pub mod changelog;
+
+#[cfg(feature = "git")]
+#[cfg_attr(docsrs, doc(cfg(feature = "git")))]
+pub mod git;
+
+#[cfg(feature = "cargo")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cargo")))]
+pub mod cargo;
+
+#[cfg(feature = "cargo_config")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cargo_config")))]
+pub mod cargo_config;
+
 #[cfg(feature = "manifest")]
 #[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
 pub mod manifest;
@@ -30,4 +85,27 @@ pub mod manifest;
 #[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
 pub mod parsing;
 
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub mod testing;
+
+#[cfg(feature = "codegen")]
+#[cfg_attr(docsrs, doc(cfg(feature = "codegen")))]
+pub mod codegen;
+
+#[cfg(feature = "rules")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rules")))]
+pub mod rules;
+
+#[cfg(feature = "toolchain")]
+#[cfg_attr(docsrs, doc(cfg(feature = "toolchain")))]
+pub mod toolchain;
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod json;
+
+#[cfg(any(feature = "git", feature = "manifest"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "git", feature = "manifest"))))]
+pub use bump_kind::BumpKind;
 pub use error::Error;
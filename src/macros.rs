@@ -0,0 +1,15 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Internal logging macros. They forward to `tracing` if the `tracing` feature is enabled, and
+// expand to nothing otherwise, so the instrumented code doesn't need to be feature-gated.
+
+#[allow(unused_macros)]
+macro_rules! debug {
+	($($arg:tt)*) => {{
+		#[cfg(feature = "tracing")]
+		tracing::debug!($($arg)*);
+	}};
+}
+
+#[allow(unused_imports)]
+pub(crate) use debug;
@@ -1,14 +1,80 @@
 // SPDX-License-Identifier: GPL-3.0
 
+mod docs_rs;
+mod edit;
+mod features;
+mod graph;
+mod inherit;
+mod pins;
+mod placeholders;
+mod probe;
+#[cfg(feature = "registry")]
+mod registry;
+mod relocate;
+mod remove;
+mod roundtrip;
+mod session;
+#[cfg(feature = "parsing")]
+mod sources;
 #[cfg(test)]
 mod tests;
+mod tree;
 mod types;
+mod version;
+mod workspace;
 
-use crate::Error;
+use crate::{
+	Error,
+	cargo_config::CargoConfig,
+	fs::{FsProvider, StdFs},
+	macros::debug,
+	paths::EditSession,
+};
 use cargo_toml::Manifest;
-use std::path::{Path, PathBuf};
-use toml_edit::{Array, DocumentMut, InlineTable, Item, Table, Value};
-pub use types::{ManifestDependencyConfig, ManifestDependencyOrigin};
+pub use docs_rs::{
+	DocsRsMetadata, read_docs_rs_metadata, validate_docs_rs_metadata, write_docs_rs_metadata,
+};
+pub use edit::{EditFn, edit_many};
+pub use features::{
+	EffectiveFeatures, FeatureMatrixOptions, UnifiedDependency, add_feature, detect_feature_cycles,
+	effective_features, feature_closure, feature_powerset, feature_unification_report,
+};
+pub use graph::{WorkspaceGraph, WorkspaceMember};
+pub use inherit::generate_workspace_dependencies;
+#[cfg(feature = "rules")]
+pub(crate) use pins::audit_manifest_pins;
+pub use pins::{PinProblem, PinSeverity, VersionPin, audit_version_pins};
+pub use placeholders::{DefaultPlaceholders, PlaceholderResolver, expand_placeholders};
+#[cfg(feature = "registry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "registry")))]
+pub use registry::{
+	DependencyMetadata, RegistryCache, dependency_metadata, dependency_metadata_with,
+};
+pub use relocate::relocate_crate;
+pub use remove::{remove_key, remove_table_if_empty};
+pub use roundtrip::{changed_keys, verify_roundtrip};
+pub use session::Workspace;
+#[cfg(feature = "parsing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
+pub use sources::{
+	FeatureConsistencyReport, check_feature_consistency, rename_crate, undeclared_crates,
+	unused_dependencies, unused_dependencies_all_members,
+};
+use std::{
+	collections::{BTreeMap, HashMap},
+	path::{Path, PathBuf},
+};
+use toml_edit::{Array, DocumentMut, Item, Table, TableLike, Value};
+pub use tree::render_dependency_tree;
+pub use types::{
+	DependencyKind, DependencyStyle, ManifestDependencyConfig, ManifestDependencyConfigBuilder,
+	ManifestDependencyOrigin,
+};
+pub use version::bump_version;
+pub use workspace::{
+	MembershipStatus, add_excluded_path, membership_status, set_default_members,
+	validate_default_members,
+};
 
 /// Given a path, this function finds the manifest corresponding to the innermost crate/workspace
 /// containing that path if there's any.
@@ -53,33 +119,120 @@ pub use types::{ManifestDependencyConfig, ManifestDependencyOrigin};
 /// assert_eq!(rustilities::manifest::find_innermost_manifest(&non_crate_inner_path), None);
 /// ```
 pub fn find_innermost_manifest<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
-	fn do_find_innermost_manifest(path: &Path) -> Option<PathBuf> {
-		let mut path = path;
-		// If the target itself contains a manifest, return it
-		let cargo_toml_path = path.join("Cargo.toml");
-		match Manifest::from_path(&cargo_toml_path) {
-			Ok(manifest) if manifest.package.is_some() || manifest.workspace.is_some() =>
-				return Some(cargo_toml_path),
-			_ => (),
-		}
+	do_find_innermost_manifest(
+		&crate::paths::prefix_with_current_dir_cow(path.as_ref()),
+		&probe::probe,
+	)
+}
 
-		// Otherwise, search in the parent dirs
-		while let Some(parent) = path.parent() {
-			let cargo_toml_path = parent.join("Cargo.toml");
-			match Manifest::from_path(&cargo_toml_path) {
-				Ok(manifest) if manifest.package.is_some() || manifest.workspace.is_some() =>
-					return Some(cargo_toml_path),
-				_ => path = parent,
-			}
+/// Same as [`find_innermost_manifest`], but the manifests are looked up through the given
+/// filesystem provider.
+pub fn find_innermost_manifest_with_fs<F: FsProvider, P: AsRef<Path>>(
+	fs: &F,
+	path: P,
+) -> Option<PathBuf> {
+	do_find_innermost_manifest(
+		&crate::paths::prefix_with_current_dir_cow(path.as_ref()),
+		&|manifest_path| probe::probe_with_fs(fs, manifest_path),
+	)
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(probe), ret))]
+fn do_find_innermost_manifest(
+	path: &Path,
+	probe: &dyn Fn(&Path) -> probe::ManifestProbe,
+) -> Option<PathBuf> {
+	let mut path = path;
+	// If the target itself contains a manifest, return it
+	let cargo_toml_path = path.join("Cargo.toml");
+	match probe(&cargo_toml_path) {
+		probe if probe.is_crate_or_workspace() => return Some(cargo_toml_path),
+		_ => debug!(probed = %cargo_toml_path.display(), "Not a crate/workspace manifest"),
+	}
+
+	// Otherwise, search in the parent dirs
+	while let Some(parent) = path.parent() {
+		let cargo_toml_path = parent.join("Cargo.toml");
+		match probe(&cargo_toml_path) {
+			probe if probe.is_crate_or_workspace() => return Some(cargo_toml_path),
+			_ => {
+				debug!(probed = %cargo_toml_path.display(), "Not a crate/workspace manifest");
+				path = parent
+			},
 		}
-		None
 	}
-	do_find_innermost_manifest(&crate::paths::prefix_with_current_dir(path))
+	None
+}
+
+/// Given some paths, this function finds the manifest corresponding to the innermost
+/// crate/workspace containing each of them, as [`find_innermost_manifest`] does. The directory
+/// walks are shared between the paths, so mapping many files (e.g. the files changed in a commit)
+/// to their crates only probes each directory once.
+///
+/// The returned map contains an entry for every given path, sorted by path.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let crate_path = tempdir.path().join("crate");
+/// std::fs::create_dir_all(crate_path.join("src")).unwrap();
+/// std::fs::write(crate_path.join("Cargo.toml"), "[package]\nname = \"test\"").unwrap();
+///
+/// let lib_path = crate_path.join("src").join("lib.rs");
+/// let main_path = crate_path.join("src").join("main.rs");
+/// let outside_path = tempdir.path().join("README.md");
+///
+/// let manifests = rustilities::manifest::find_innermost_manifests(&[
+///     lib_path.clone(),
+///     main_path.clone(),
+///     outside_path.clone(),
+/// ]);
+///
+/// assert_eq!(manifests[&lib_path], Some(crate_path.join("Cargo.toml")));
+/// assert_eq!(manifests[&main_path], Some(crate_path.join("Cargo.toml")));
+/// assert_eq!(manifests[&outside_path], None);
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(paths = paths.len())))]
+pub fn find_innermost_manifests(paths: &[PathBuf]) -> BTreeMap<PathBuf, Option<PathBuf>> {
+	// The manifest found for every directory walked so far
+	let mut resolved_dirs: HashMap<PathBuf, Option<PathBuf>> = HashMap::new();
+
+	paths
+		.iter()
+		.map(|path| {
+			let prefixed_path = crate::paths::prefix_with_current_dir_cow(path);
+			let mut walked_dirs = Vec::new();
+			let mut dir = Some(prefixed_path.as_ref());
+			let manifest = loop {
+				let Some(current_dir) = dir else { break None };
+				if let Some(manifest) = resolved_dirs.get(current_dir) {
+					break manifest.clone();
+				}
+				walked_dirs.push(current_dir.to_path_buf());
+				let cargo_toml_path = current_dir.join("Cargo.toml");
+				if probe::probe(&cargo_toml_path).is_crate_or_workspace() {
+					break Some(cargo_toml_path);
+				}
+				debug!(probed = %cargo_toml_path.display(), "Not a crate/workspace manifest");
+				dir = current_dir.parent();
+			};
+
+			walked_dirs.into_iter().for_each(|walked_dir| {
+				resolved_dirs.insert(walked_dir, manifest.clone());
+			});
+			(path.clone(), manifest)
+		})
+		.collect()
 }
 
 /// Given a path, this function finds the manifest corresponding to the workspace
 /// containing that path if there's any.
 ///
+/// This is the nearest manifest with a `[workspace]` table found in the path and its parent dirs,
+/// even if it doesn't include the crate containing the path. Use
+/// [`find_member_workspace_manifest`] to only find workspaces including that crate.
+///
 /// # Examples
 /// ```
 /// use std::fs::File;
@@ -126,26 +279,671 @@ pub fn find_innermost_manifest<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
 /// );
 /// ```
 pub fn find_workspace_manifest<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
-	fn do_find_workspace_manifest(path: &Path) -> Option<PathBuf> {
-		let mut path = path;
-		// If the target itself contains a manifest, return it
-		let cargo_toml_path = path.join("Cargo.toml");
-		match Manifest::from_path(&cargo_toml_path) {
-			Ok(manifest) if manifest.workspace.is_some() => return Some(cargo_toml_path),
-			_ => (),
+	do_find_workspace_manifest(
+		&crate::paths::prefix_with_current_dir_cow(path.as_ref()),
+		&probe::probe,
+	)
+}
+
+/// Same as [`find_workspace_manifest`], but the manifests are looked up through the given
+/// filesystem provider.
+pub fn find_workspace_manifest_with_fs<F: FsProvider, P: AsRef<Path>>(
+	fs: &F,
+	path: P,
+) -> Option<PathBuf> {
+	do_find_workspace_manifest(
+		&crate::paths::prefix_with_current_dir_cow(path.as_ref()),
+		&|manifest_path| probe::probe_with_fs(fs, manifest_path),
+	)
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(probe), ret))]
+fn do_find_workspace_manifest(
+	path: &Path,
+	probe: &dyn Fn(&Path) -> probe::ManifestProbe,
+) -> Option<PathBuf> {
+	let mut path = path;
+	// If the target itself contains a manifest, return it
+	let cargo_toml_path = path.join("Cargo.toml");
+	match probe(&cargo_toml_path) {
+		probe if probe.workspace => return Some(cargo_toml_path),
+		_ => debug!(probed = %cargo_toml_path.display(), "Not a workspace manifest"),
+	}
+
+	// Otherwise, search in the parent dirs
+	while let Some(parent) = path.parent() {
+		let cargo_toml_path = parent.join("Cargo.toml");
+		match probe(&cargo_toml_path) {
+			probe if probe.workspace => return Some(cargo_toml_path),
+			_ => {
+				debug!(probed = %cargo_toml_path.display(), "Not a workspace manifest");
+				path = parent
+			},
+		}
+	}
+	None
+}
+
+/// Same as [`find_workspace_manifest`], but if the path is inside a crate, only a workspace that
+/// actually includes that crate is returned: either the one its `package.workspace` key points to,
+/// or the nearest ancestor workspace listing it in its `members` (directly or through a glob) and
+/// not excluding it. Unrelated workspaces enclosing the crate, as found in nested-project layouts,
+/// are skipped. A crate that is itself a workspace root returns its own manifest, and paths outside
+/// any crate behave as in [`find_workspace_manifest`].
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let workspace_manifest_path = tempdir.path().join("Cargo.toml");
+/// for member in ["member", "vendor/standalone"] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{}\"", member.replace("/", "-")),
+///     ).unwrap();
+/// }
+/// std::fs::write(&workspace_manifest_path, "[workspace]\nmembers = [\"member\"]\n").unwrap();
+///
+/// assert_eq!(
+///     rustilities::manifest::find_member_workspace_manifest(tempdir.path().join("member")),
+///     Some(workspace_manifest_path.clone())
+/// );
+/// assert_eq!(
+///     rustilities::manifest::find_workspace_manifest(tempdir.path().join("vendor/standalone")),
+///     Some(workspace_manifest_path)
+/// );
+/// assert_eq!(
+///     rustilities::manifest::find_member_workspace_manifest(
+///         tempdir.path().join("vendor/standalone")
+///     ),
+///     None
+/// );
+/// ```
+pub fn find_member_workspace_manifest<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
+	do_find_member_workspace_manifest(&crate::paths::prefix_with_current_dir_cow(path.as_ref()))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", ret))]
+fn do_find_member_workspace_manifest(path: &Path) -> Option<PathBuf> {
+	let crate_toml = find_innermost_manifest(path)?;
+	if probe::probe(&crate_toml).workspace {
+		return Some(crate_toml);
+	}
+	let crate_dir = crate_toml.parent().expect("A file always lives inside a dir; qed");
+
+	let doc = std::fs::read_to_string(&crate_toml).ok()?.parse::<DocumentMut>().ok()?;
+	if let Some(workspace_dir) = doc
+		.get("package")
+		.and_then(|package| package.get("workspace"))
+		.and_then(Item::as_str)
+	{
+		let workspace_toml = graph::normalize(&crate_dir.join(workspace_dir).join("Cargo.toml"));
+		return probe::probe(&workspace_toml).workspace.then_some(workspace_toml);
+	}
+
+	let normalized_crate_toml = graph::normalize(&crate_toml);
+	crate_dir
+		.ancestors()
+		.skip(1)
+		.map(|dir| dir.join("Cargo.toml"))
+		.find(|workspace_toml| {
+			if !probe::probe(workspace_toml).workspace {
+				return false;
+			}
+			let is_member = std::fs::read_to_string(workspace_toml)
+				.ok()
+				.and_then(|content| content.parse::<DocumentMut>().ok())
+				.and_then(|doc| workspace_members_of(&doc, workspace_toml).ok())
+				.is_some_and(|members| {
+					members.iter().any(|member| graph::normalize(member) == normalized_crate_toml)
+				});
+			if !is_member {
+				debug!(probed = %workspace_toml.display(), "The workspace doesn't include the crate");
+			}
+			is_member
+		})
+}
+
+/// Given a path, this function resolves the target directory cargo would use when building the
+/// crate/workspace containing that path, so tools can find build artifacts without running
+/// `cargo metadata`. The following sources are checked in order:
+/// 1. The `CARGO_TARGET_DIR` environment variable.
+/// 2. The `build.target-dir` key of the `.cargo/config.toml` files found in the path and its parent
+///    dirs (innermost first), and then in `CARGO_HOME`. Relative values are resolved against the
+///    dir containing the `.cargo` dir, as cargo does.
+/// 3. The `target` dir next to the manifest of the workspace including the crate containing the
+///    path as a member (see [`find_member_workspace_manifest`]), or next to the innermost crate
+///    manifest if the crate isn't a member of any workspace.
+///
+/// Note that cargo looks for config files from the dir it's called from, while this function looks
+/// for them from the given path.
+///
+/// # Errors
+///
+/// - If a config file cannot be read or isn't valid TOML.
+/// - If the `build.target-dir` key of a config file isn't a string.
+/// - If the target dir comes from the manifests and the path isn't part of a crate/workspace.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let crate_path = tempdir.path().join("crate");
+/// std::fs::create_dir_all(crate_path.join("src")).unwrap();
+/// std::fs::write(crate_path.join("Cargo.toml"), "[package]\nname = \"test\"").unwrap();
+/// std::fs::create_dir_all(tempdir.path().join(".cargo")).unwrap();
+/// std::fs::write(
+///     tempdir.path().join(".cargo").join("config.toml"),
+///     "[build]\ntarget-dir = \"artifacts\"",
+/// ).unwrap();
+///
+/// if std::env::var_os("CARGO_TARGET_DIR").is_none() {
+///     assert_eq!(
+///         rustilities::manifest::target_dir(crate_path.join("src")).unwrap(),
+///         tempdir.path().join("artifacts")
+///     );
+/// }
+/// ```
+pub fn target_dir<P: AsRef<Path>>(path: P) -> Result<PathBuf, Error> {
+	do_target_dir(
+		path.as_ref(),
+		std::env::var_os("CARGO_TARGET_DIR").map(PathBuf::from),
+		crate::cargo_config::cargo_home(),
+	)
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", ret))]
+fn do_target_dir(
+	path: &Path,
+	env_target_dir: Option<PathBuf>,
+	cargo_home: Option<PathBuf>,
+) -> Result<PathBuf, Error> {
+	const TARGET_DIR_KEY: [&str; 2] = ["build", "target-dir"];
+
+	if let Some(env_target_dir) = env_target_dir {
+		return Ok(std::path::absolute(env_target_dir)?);
+	}
+
+	let config = CargoConfig::load_with_cargo_home(path, cargo_home)?;
+	if let (Some(target_dir), Some(origin)) =
+		(config.get(&TARGET_DIR_KEY), config.origin(&TARGET_DIR_KEY))
+	{
+		let target_dir = target_dir.as_str().ok_or_else(|| {
+			Error::Descriptive(format!(
+				"The build.target-dir key in {} isn't a string",
+				origin.display()
+			))
+		})?;
+		// Relative paths are resolved against the dir containing the `.cargo` dir
+		let config_root = origin.parent().and_then(Path::parent).unwrap_or(origin);
+		return Ok(config_root.join(target_dir));
+	}
+
+	find_member_workspace_manifest(path)
+		.or_else(|| find_innermost_manifest(path))
+		.map(|manifest| {
+			manifest.parent().expect("A file always lives inside a dir; qed").join("target")
+		})
+		.ok_or_else(|| {
+			Error::Descriptive("The provided path isn't part of a crate or workspace".to_owned())
+		})
+}
+
+/// Given a workspace manifest file path, this function returns the manifest paths of all the
+/// workspace members.
+///
+/// Glob patterns in the `members` section are resolved, and the paths listed in the `exclude`
+/// section are left out. If the workspace manifest is also a crate manifest, it's included as well,
+/// as the root package is always a workspace member. Members without a manifest are ignored.
+///
+/// The returned paths are sorted and don't contain duplicates.
+///
+/// # Errors
+///
+/// - If the path cannot be read.
+/// - If the path doesn't correspond to a valid Rust manifest.
+/// - If the path doesn't correspond to a workspace manifest.
+/// - If a `members` glob pattern is invalid.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let workspace_manifest_path = tempdir.path().join("Cargo.toml");
+/// for member in ["crates/a", "crates/b", "tool"] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{}\"", member.replace("/", "-")),
+///     ).unwrap();
+/// }
+/// std::fs::write(
+///     &workspace_manifest_path,
+///     r#"
+/// [workspace]
+/// members = ["crates/*", "tool"]
+/// exclude = ["crates/b"]
+/// "#,
+/// ).unwrap();
+///
+/// assert_eq!(
+///     rustilities::manifest::find_workspace_members(&workspace_manifest_path).unwrap(),
+///     vec![
+///         tempdir.path().join("crates/a/Cargo.toml"),
+///         tempdir.path().join("tool/Cargo.toml")
+///     ]
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip_all, fields(workspace_toml = %workspace_toml.as_ref().display()))
+)]
+pub fn find_workspace_members<P: AsRef<Path>>(workspace_toml: P) -> Result<Vec<PathBuf>, Error> {
+	fn do_find_workspace_members(workspace_toml: &Path) -> Result<Vec<PathBuf>, Error> {
+		let doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+		workspace_members_of(&doc, workspace_toml)
+	}
+	do_find_workspace_members(workspace_toml.as_ref())
+}
+
+/// Same as [`find_workspace_members`], given the already parsed workspace manifest.
+fn workspace_members_of(doc: &DocumentMut, workspace_toml: &Path) -> Result<Vec<PathBuf>, Error> {
+	let workspace_dir = workspace_toml.parent().expect("A file always lives inside a dir; qed");
+
+	let Some(Item::Table(workspace_table)) = doc.get("workspace") else {
+		return Err(Error::Descriptive(
+			"The provided manifest path isn't a workspace manifest".to_owned(),
+		));
+	};
+
+	let string_array = |key: &str| -> Vec<String> {
+		workspace_table
+			.get(key)
+			.and_then(|item| item.as_array())
+			.map(|array| array.iter().filter_map(|v| v.as_str().map(str::to_owned)).collect())
+			.unwrap_or_default()
+	};
+
+	let excluded = string_array("exclude")
+		.into_iter()
+		.map(|path| workspace_dir.join(path))
+		.collect::<Vec<_>>();
+
+	let mut members = Vec::new();
+	if matches!(doc.get("package"), Some(Item::Table(_))) {
+		members.push(workspace_toml.to_path_buf());
+	}
+
+	for member in string_array("members") {
+		let pattern = workspace_dir.join(&member);
+		let paths = glob::glob(&pattern.to_string_lossy()).map_err(|err| {
+			Error::Descriptive(format!("Invalid workspace member pattern {member}: {err}"))
+		})?;
+		for member_dir in paths.filter_map(Result::ok) {
+			let member_manifest = member_dir.join("Cargo.toml");
+			if excluded.iter().any(|excluded| member_dir.starts_with(excluded)) {
+				debug!(member = %member_dir.display(), "Member excluded");
+			} else if member_manifest.is_file() {
+				members.push(member_manifest);
+			}
+		}
+	}
+
+	members.sort();
+	members.dedup();
+	Ok(members)
+}
+
+/// Given a workspace manifest file path and a dependency name, this function returns the manifest
+/// paths of the workspace members depending on that crate, which is useful for impact analysis
+/// (eg, to know which crates need a version bump when the dependency changes).
+///
+/// Every dependency section is taken into account: `dependencies`, `dev-dependencies`,
+/// `build-dependencies` and their platform specific counterparts under `target`. Renamed
+/// dependencies (`alias = { package = "name", ... }`) are matched by their package name.
+///
+/// # Errors
+///
+/// - If the workspace members cannot be resolved.
+/// - If some of the member manifests cannot be read or parsed.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let workspace_manifest_path = tempdir.path().join("Cargo.toml");
+/// for (member, dependencies) in [
+///     ("a", "[dependencies]\nserde = \"1.0\""),
+///     ("b", "[target.'cfg(unix)'.dev-dependencies]\nserde = \"1.0\""),
+///     ("c", "[dependencies]\nsyn = \"2.0\""),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{member}\"\n{dependencies}"),
+///     ).unwrap();
+/// }
+/// std::fs::write(&workspace_manifest_path, "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\n").unwrap();
+///
+/// assert_eq!(
+///     rustilities::manifest::members_depending_on(&workspace_manifest_path, "serde").unwrap(),
+///     vec![tempdir.path().join("a/Cargo.toml"), tempdir.path().join("b/Cargo.toml")]
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip(workspace_toml), fields(workspace_toml = %workspace_toml.as_ref().display()))
+)]
+pub fn members_depending_on<P: AsRef<Path>>(
+	workspace_toml: P,
+	dependency_name: &str,
+) -> Result<Vec<PathBuf>, Error> {
+	let mut dependents = Vec::new();
+	for member in find_workspace_members(workspace_toml)? {
+		let doc = std::fs::read_to_string(&member)?.parse::<DocumentMut>()?;
+		let depends_on = dependency_tables(&doc).into_iter().any(|(_, table)| {
+			table.iter().any(|(key, dependency)| {
+				dependency_package_name(key, dependency) == dependency_name
+			})
+		});
+		if depends_on {
+			dependents.push(member);
+		}
+	}
+	Ok(dependents)
+}
+
+/// Given a list of changed file paths and a workspace manifest file path, this function returns
+/// the names of the workspace members affected by the changes, sorted alphabetically.
+///
+/// A member is affected if it owns some of the changed paths (as found by
+/// [`find_innermost_manifest`]), or if it depends, directly or transitively, on an affected
+/// member according to the [`WorkspaceGraph`]. Paths not owned by any workspace member are
+/// ignored. This is a building block for selective CI tooling.
+///
+/// # Errors
+///
+/// - If the [`WorkspaceGraph`] cannot be loaded.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// for (member, dependencies) in [
+///     ("core", ""),
+///     ("api", "[dependencies]\ncore = { path = \"../core\" }"),
+///     ("other", ""),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(member).join("src")).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{member}\"\n{dependencies}"),
+///     ).unwrap();
+/// }
+/// std::fs::write(tempdir.path().join("Cargo.toml"), "[workspace]\nmembers = [\"*\"]").unwrap();
+///
+/// assert_eq!(
+///     rustilities::manifest::crates_affected_by(
+///         &[tempdir.path().join("core/src/lib.rs"), tempdir.path().join("README.md")],
+///         tempdir.path().join("Cargo.toml")
+///     )
+///     .unwrap(),
+///     vec!["api", "core"]
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip_all, fields(workspace_toml = %workspace_toml.as_ref().display()))
+)]
+pub fn crates_affected_by<P: AsRef<Path>>(
+	paths: &[PathBuf],
+	workspace_toml: P,
+) -> Result<Vec<String>, Error> {
+	let graph = WorkspaceGraph::load(workspace_toml)?;
+	// Compare canonical paths, as the changed paths may be relative while the member manifests
+	// are absolute or the other way around.
+	let member_manifests = graph
+		.members()
+		.iter()
+		.map(|member| member.manifest_path.canonicalize().ok())
+		.collect::<Vec<_>>();
+
+	let mut affected = std::collections::BTreeSet::new();
+	for path in paths {
+		let Some(manifest) =
+			find_innermost_manifest(path).and_then(|path| path.canonicalize().ok())
+		else {
+			continue;
+		};
+		if let Some(index) =
+			member_manifests.iter().position(|member| member.as_ref() == Some(&manifest))
+		{
+			let name = graph.members()[index].name.as_str();
+			debug!(path = %path.display(), member = name, "Changed path owned by member");
+			affected.insert(name);
+			affected.extend(graph.transitive_dependents(name));
 		}
+	}
+	Ok(affected.into_iter().map(str::to_owned).collect())
+}
 
-		// Otherwise, search in the parent dirs
-		while let Some(parent) = path.parent() {
-			let cargo_toml_path = parent.join("Cargo.toml");
-			match Manifest::from_path(&cargo_toml_path) {
-				Ok(manifest) if manifest.workspace.is_some() => return Some(cargo_toml_path),
-				_ => path = parent,
+/// Given a workspace manifest file path, this function returns the names of the workspace members
+/// in the order they have to be published, so release automation can drive `cargo publish`
+/// correctly. See [`WorkspaceGraph::publish_order`] for the details.
+///
+/// # Errors
+///
+/// - If the [`WorkspaceGraph`] cannot be loaded.
+/// - If the members dependencies contain a cycle. The error message contains the cycle path.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::Error;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// for (member, dependencies) in [
+///     ("cli", "[dependencies]\napi = { path = \"../api\", version = \"0.1.0\" }"),
+///     ("api", "[dependencies]\ncore = { path = \"../core\", version = \"0.1.0\" }"),
+///     ("core", "[dev-dependencies]\ncli = { path = \"../cli\" }"),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{member}\"\n{dependencies}"),
+///     ).unwrap();
+/// }
+/// std::fs::write(tempdir.path().join("Cargo.toml"), "[workspace]\nmembers = [\"*\"]").unwrap();
+///
+/// assert_eq!(
+///     rustilities::manifest::publish_order(tempdir.path().join("Cargo.toml")).unwrap(),
+///     vec!["core", "api", "cli"]
+/// );
+///
+/// // A cycle makes it impossible to publish the crates
+/// std::fs::write(
+///     tempdir.path().join("core").join("Cargo.toml"),
+///     "[package]\nname = \"core\"\n[dependencies]\ncli = { path = \"../cli\" }",
+/// ).unwrap();
+/// assert!(matches!(
+///     rustilities::manifest::publish_order(tempdir.path().join("Cargo.toml")),
+///     Err(Error::Descriptive(msg)) if msg == "Dependency cycle detected: api -> core -> cli -> api"
+/// ));
+/// ```
+pub fn publish_order<P: AsRef<Path>>(workspace_toml: P) -> Result<Vec<String>, Error> {
+	Ok(WorkspaceGraph::load(workspace_toml)?
+		.publish_order()?
+		.into_iter()
+		.map(str::to_owned)
+		.collect())
+}
+
+/// Given a crate manifest file path, this function transforms the manifest as `cargo publish` does
+/// with local dependencies, returning the resulting content:
+/// - The `path` and `git` related keys are removed from the dependencies that also specify a
+///   `version`.
+/// - The dev dependencies that don't specify a `version` are removed.
+/// - The `patch` section is removed.
+///
+/// Dependencies inherited from the workspace (`{ workspace = true }`) are left untouched. The
+/// manifest file is only overwritten if `write` is `true`.
+///
+/// # Errors
+///
+/// - If the path cannot be read.
+/// - If the path doesn't correspond to a valid Rust manifest.
+/// - If a (non dev) dependency is declared using a `path` or `git` key without specifying a
+///   `version`, as it cannot be published.
+/// - If `write` is `true` and the path cannot be overwritten.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(
+///     &manifest_path,
+///     r#"[package]
+/// name = "test"
+///
+/// [dependencies]
+/// core = { path = "../core", version = "0.1.0" }
+///
+/// [dev-dependencies]
+/// test-utils = { path = "../test-utils" }
+///
+/// [patch.crates-io]
+/// serde = { path = "../serde" }
+/// "#,
+/// ).unwrap();
+///
+/// let prepared = rustilities::manifest::prepare_for_publish(&manifest_path, false).unwrap();
+/// assert_eq!(
+///     prepared,
+///     r#"[package]
+/// name = "test"
+///
+/// [dependencies]
+/// core = { version = "0.1.0" }
+///
+/// [dev-dependencies]
+/// "#
+/// );
+/// // The manifest isn't touched
+/// assert_ne!(std::fs::read_to_string(&manifest_path).unwrap(), prepared);
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip(manifest_path), fields(manifest_path = %manifest_path.as_ref().display()))
+)]
+pub fn prepare_for_publish<P: AsRef<Path>>(manifest_path: P, write: bool) -> Result<String, Error> {
+	const LOCAL_KEYS: [&str; 5] = ["path", "git", "branch", "tag", "rev"];
+
+	let mut doc = std::fs::read_to_string(manifest_path.as_ref())?.parse::<DocumentMut>()?;
+	remove::remove_item(&mut doc, &["patch"]);
+
+	for (kind, table) in dependency_tables_mut(&mut doc) {
+		let mut unpublishable = Vec::new();
+		for (key, dependency) in table.iter_mut() {
+			let Some(dependency) = dependency.as_table_like_mut() else { continue };
+			if !LOCAL_KEYS.iter().any(|local_key| dependency.contains_key(local_key)) {
+				continue;
+			}
+			if dependency.contains_key("version") {
+				LOCAL_KEYS.iter().for_each(|local_key| {
+					dependency.remove(local_key);
+				});
+			} else if kind == DependencyKind::Dev {
+				unpublishable.push(key.get().to_owned());
+			} else {
+				return Err(Error::Descriptive(format!(
+					"The dependency {} doesn't specify a version, so it cannot be published",
+					key.get()
+				)));
 			}
 		}
-		None
+		unpublishable.iter().for_each(|key| {
+			remove::remove_entry(table, key);
+		});
+	}
+
+	let content = doc.to_string();
+	if write {
+		debug!(path = %manifest_path.as_ref().display(), "Writing manifest");
+		std::fs::write(manifest_path, &content)?;
 	}
-	do_find_workspace_manifest(&crate::paths::prefix_with_current_dir(path))
+	Ok(content)
+}
+
+/// The keys of the dependency sections of a manifest, together with the kind of the dependencies
+/// they contain.
+const DEPENDENCY_KEYS: [(&str, DependencyKind); 3] = [
+	("dependencies", DependencyKind::Normal),
+	("dev-dependencies", DependencyKind::Dev),
+	("build-dependencies", DependencyKind::Build),
+];
+
+fn dependency_kind(key: &str) -> Option<DependencyKind> {
+	DEPENDENCY_KEYS
+		.iter()
+		.find(|(dependency_key, _)| *dependency_key == key)
+		.map(|(_, kind)| *kind)
+}
+
+/// Returns every dependency table contained in a manifest together with its kind, including
+/// target specific dependencies.
+fn dependency_tables(doc: &DocumentMut) -> Vec<(DependencyKind, &dyn TableLike)> {
+	fn tables_in(table: &dyn TableLike) -> Vec<(DependencyKind, &dyn TableLike)> {
+		table
+			.iter()
+			.filter_map(|(key, item)| Some((dependency_kind(key)?, item.as_table_like()?)))
+			.collect()
+	}
+
+	let mut tables = tables_in(doc.as_table());
+	if let Some(targets) = doc.get("target").and_then(Item::as_table_like) {
+		for (_, target) in targets.iter() {
+			if let Some(target) = target.as_table_like() {
+				tables.extend(tables_in(target));
+			}
+		}
+	}
+	tables
+}
+
+/// Mutable counterpart of [`dependency_tables`].
+fn dependency_tables_mut(doc: &mut DocumentMut) -> Vec<(DependencyKind, &mut dyn TableLike)> {
+	let mut tables = Vec::new();
+	for (key, item) in doc.as_table_mut().iter_mut() {
+		if key == "target" {
+			let Some(targets) = item.as_table_like_mut() else { continue };
+			for (_, target) in targets.iter_mut() {
+				let Some(target) = target.as_table_like_mut() else { continue };
+				for (key, item) in target.iter_mut() {
+					if let (Some(kind), Some(table)) =
+						(dependency_kind(&key), item.as_table_like_mut())
+					{
+						tables.push((kind, table));
+					}
+				}
+			}
+		} else if let (Some(kind), Some(table)) = (dependency_kind(&key), item.as_table_like_mut())
+		{
+			tables.push((kind, table));
+		}
+	}
+	tables
+}
+
+/// Returns the name of the package a dependency entry refers to, taking into account that the
+/// dependency may be renamed using the `package` key.
+fn dependency_package_name<'a>(key: &'a str, dependency: &'a Item) -> &'a str {
+	dependency
+		.as_table_like()
+		.and_then(|dependency| dependency.get("package"))
+		.and_then(Item::as_str)
+		.unwrap_or(key)
 }
 
 /// Given a path, this function tries to determine if it points to a crate's manifest and if that's
@@ -176,6 +974,10 @@ pub fn find_workspace_manifest<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
 /// assert_eq!(rustilities::manifest::find_crate_name(manifest_path).unwrap(), "test");
 /// assert!(rustilities::manifest::find_crate_name(crate_path).is_none());
 /// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip_all, fields(manifest_path = %manifest_path.as_ref().display()))
+)]
 pub fn find_crate_name<P: AsRef<Path>>(manifest_path: P) -> Option<String> {
 	Manifest::from_path(manifest_path.as_ref())
 		.ok()?
@@ -183,6 +985,59 @@ pub fn find_crate_name<P: AsRef<Path>>(manifest_path: P) -> Option<String> {
 		.map(|package| package.name)
 }
 
+/// Same as [`find_crate_name`], but the manifest is read through the given filesystem provider.
+pub fn find_crate_name_with_fs<F: FsProvider, P: AsRef<Path>>(
+	fs: &F,
+	manifest_path: P,
+) -> Option<String> {
+	find_crate_name_str(&fs.read_to_string(manifest_path.as_ref()).ok()?)
+}
+
+/// Given the contents of a manifest, this function returns the crate's name if the manifest
+/// declares a crate. See [`find_crate_name`].
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(
+///     rustilities::manifest::find_crate_name_str("[package]\nname = \"test\"\n"),
+///     Some("test".to_owned())
+/// );
+/// assert_eq!(rustilities::manifest::find_crate_name_str("[workspace]\n"), None);
+/// assert_eq!(rustilities::manifest::find_crate_name_str("not toml"), None);
+/// ```
+pub fn find_crate_name_str(content: &str) -> Option<String> {
+	Manifest::from_str(content).ok()?.package.map(|package| package.name)
+}
+
+/// The edition declared by the crate whose manifest lives at the given path, resolving the
+/// editions inherited from the workspace, or `None` if the crate doesn't declare one.
+#[cfg(any(feature = "parsing", all(feature = "fmt", feature = "git")))]
+pub(crate) fn package_edition(manifest_path: &Path) -> Result<Option<String>, Error> {
+	let doc = std::fs::read_to_string(manifest_path)?.parse::<DocumentMut>()?;
+	let edition = doc.get("package").and_then(|package| package.get("edition"));
+	if edition
+		.and_then(|edition| edition.get("workspace"))
+		.and_then(Item::as_bool)
+		.unwrap_or(false)
+	{
+		let workspace_toml = find_workspace_manifest(manifest_path).ok_or_else(|| {
+			Error::Descriptive(format!(
+				"{} inherits its edition from a workspace that doesn't exist",
+				manifest_path.display()
+			))
+		})?;
+		let workspace_doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+		return Ok(workspace_doc
+			.get("workspace")
+			.and_then(|workspace| workspace.get("package"))
+			.and_then(|package| package.get("edition"))
+			.and_then(Item::as_str)
+			.map(str::to_owned));
+	}
+	Ok(edition.and_then(Item::as_str).map(str::to_owned))
+}
+
 /// Given a manifest file path, this function adds a dependency to the dependencies section of the
 /// manifest based on the provided config.
 ///
@@ -192,9 +1047,17 @@ pub fn find_crate_name<P: AsRef<Path>>(manifest_path: P) -> Option<String> {
 /// with the new dependency, taking into account if the manifest is a crate manifest or a workspace
 /// manifest (an empty manifest is considered a crate manifest).
 ///
+/// The dependency is written as an inline table, replacing any previous declaration: use
+/// [`add_crate_to_dependencies_with_style`] to choose another [`DependencyStyle`], and
+/// [`ManifestDependencyConfig::read_from_manifest`] to read it back.
+///
+/// The `path` and `git` values of the origin are written as they are: use
+/// [`add_crate_to_dependencies_with_resolver`] to expand placeholders such as `${WORKSPACE_ROOT}`.
 ///
 /// # Errors
 ///
+/// - If the dependency origin isn't valid (see [`ManifestDependencyOrigin::validate`]).
+/// - If the path refers to a workspace manifest and the dependency is inherited from the workspace.
 /// - If the path cannot be read.
 /// - If the path doesn't correspond to a valid Rust manifes (empty files are valid).
 /// - If the path cannot overwritten.
@@ -274,34 +1137,362 @@ pub fn find_crate_name<P: AsRef<Path>>(manifest_path: P) -> Option<String> {
 ///     Err(Error::IO(err)) if err.kind() == ErrorKind::NotFound
 /// ));
 /// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(
+		level = "debug",
+		skip(manifest_path),
+		fields(manifest_path = %manifest_path.as_ref().display())
+	)
+)]
 pub fn add_crate_to_dependencies<P: AsRef<Path>>(
 	manifest_path: P,
 	dependency_name: &str,
 	dependency_config: ManifestDependencyConfig,
 ) -> Result<(), Error> {
-	let mut doc = std::fs::read_to_string(manifest_path.as_ref())?.parse::<DocumentMut>()?;
-	if let Some(Item::Table(dependencies)) = doc.get_mut("dependencies") {
-		add_dependency_to_dependencies_table(dependencies, dependency_name, dependency_config);
-	} else if let Some(Item::Table(workspace)) = doc.get_mut("workspace") {
-		if let Some(Item::Table(dependencies)) = workspace.get_mut("dependencies") {
-			add_dependency_to_dependencies_table(dependencies, dependency_name, dependency_config);
-		} else {
-			let mut dependencies = Table::new();
-			add_dependency_to_dependencies_table(
-				&mut dependencies,
-				dependency_name,
-				dependency_config,
-			);
-			workspace.insert("dependencies", Item::Table(dependencies));
-		}
-	} else {
-		let mut dependencies = Table::new();
-		add_dependency_to_dependencies_table(&mut dependencies, dependency_name, dependency_config);
-		doc.insert("dependencies", Item::Table(dependencies));
+	add_crate_to_dependencies_with_fs(&StdFs, manifest_path, dependency_name, dependency_config)
+}
+
+/// Same as [`add_crate_to_dependencies`], but the manifest is read and written through the given
+/// filesystem provider.
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(
+		level = "debug",
+		skip(fs, manifest_path),
+		fields(manifest_path = %manifest_path.as_ref().display())
+	)
+)]
+pub fn add_crate_to_dependencies_with_fs<F: FsProvider, P: AsRef<Path>>(
+	fs: &F,
+	manifest_path: P,
+	dependency_name: &str,
+	dependency_config: ManifestDependencyConfig,
+) -> Result<(), Error> {
+	write_dependency_with_fs(
+		fs,
+		manifest_path.as_ref(),
+		dependency_name,
+		dependency_config,
+		DependencyStyle::Inline,
+	)
+}
+
+fn write_dependency_with_fs<F: FsProvider>(
+	fs: &F,
+	manifest_path: &Path,
+	dependency_name: &str,
+	dependency_config: ManifestDependencyConfig,
+	style: DependencyStyle,
+) -> Result<(), Error> {
+	let content = add_dependency_to_manifest_str(
+		&fs.read_to_string(manifest_path)?,
+		dependency_name,
+		dependency_config,
+		style,
+		None,
+	)?;
+
+	debug!(path = %manifest_path.display(), "Writing manifest");
+	fs.write(manifest_path, &content)?;
+
+	Ok(())
+}
+
+/// Same as [`add_crate_to_dependencies`], but the placeholders contained in the `path` and `git`
+/// values of the origin are expanded by the given resolver. Use
+/// [`DefaultPlaceholders::for_manifest`] to expand `${WORKSPACE_ROOT}` and `${ENV:NAME}`. Values
+/// containing a literal `${` must write it as `$${` then.
+///
+/// # Errors
+///
+/// - The errors of [`add_crate_to_dependencies`].
+/// - If a placeholder of the origin cannot be expanded.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::{ManifestDependencyConfig, ManifestDependencyOrigin};
+/// use std::path::Path;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(&manifest_path, "[dependencies]\n").unwrap();
+///
+/// let resolver = |name: &str| (name == "TEMPLATES").then(|| "/opt/templates".to_owned());
+/// rustilities::manifest::add_crate_to_dependencies_with_resolver(
+///     &manifest_path,
+///     "template",
+///     ManifestDependencyConfig::builder(ManifestDependencyOrigin::local(Path::new(
+///         "${TEMPLATES}/template",
+///     )))
+///     .build(),
+///     &resolver,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&manifest_path).unwrap(),
+///     "[dependencies]\ntemplate = { path = \"/opt/templates/template\" }\n"
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(
+		level = "debug",
+		skip(manifest_path, resolver),
+		fields(manifest_path = %manifest_path.as_ref().display())
+	)
+)]
+pub fn add_crate_to_dependencies_with_resolver<P: AsRef<Path>>(
+	manifest_path: P,
+	dependency_name: &str,
+	dependency_config: ManifestDependencyConfig,
+	resolver: &dyn PlaceholderResolver,
+) -> Result<(), Error> {
+	let manifest_path = manifest_path.as_ref();
+	let content = add_crate_to_dependencies_str_with_resolver(
+		&std::fs::read_to_string(manifest_path)?,
+		dependency_name,
+		dependency_config,
+		resolver,
+	)?;
+
+	debug!(path = %manifest_path.display(), "Writing manifest");
+	std::fs::write(manifest_path, &content)?;
+
+	Ok(())
+}
+
+/// Same as [`add_crate_to_dependencies`], but the dependency is written following the given
+/// [`DependencyStyle`].
+///
+/// # Errors
+///
+/// - The errors of [`add_crate_to_dependencies`].
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::{DependencyStyle, ManifestDependencyConfig, ManifestDependencyOrigin};
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(&manifest_path, "[package]\nname = \"test\"\n").unwrap();
+///
+/// rustilities::manifest::add_crate_to_dependencies_with_style(
+///     &manifest_path,
+///     "serde",
+///     ManifestDependencyConfig::builder(ManifestDependencyOrigin::crates_io("1.0"))
+///         .feature("derive")
+///         .build(),
+///     DependencyStyle::Table,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&manifest_path).unwrap(),
+///     "[package]\nname = \"test\"\n\n[dependencies]\n\n[dependencies.serde]\nversion = \"1.0\"\nfeatures = [\"derive\"]\n"
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(
+		level = "debug",
+		skip(manifest_path),
+		fields(manifest_path = %manifest_path.as_ref().display())
+	)
+)]
+pub fn add_crate_to_dependencies_with_style<P: AsRef<Path>>(
+	manifest_path: P,
+	dependency_name: &str,
+	dependency_config: ManifestDependencyConfig,
+	style: DependencyStyle,
+) -> Result<(), Error> {
+	write_dependency_with_fs(
+		&StdFs,
+		manifest_path.as_ref(),
+		dependency_name,
+		dependency_config,
+		style,
+	)
+}
+
+/// Given the contents of a manifest, this function adds a dependency to the dependencies section
+/// of the manifest based on the provided config, as [`add_crate_to_dependencies`] does, and
+/// returns the updated contents. Use [`add_crate_to_dependencies_str_with_resolver`] to expand the
+/// placeholders of the origin.
+///
+/// # Errors
+///
+/// - If the dependency origin isn't valid (see [`ManifestDependencyOrigin::validate`]).
+/// - If the contents are a workspace manifest and the dependency is inherited from the workspace.
+/// - If the contents aren't a valid Rust manifest (empty contents are valid).
+/// - If the dependencies section isn't a table.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::{ManifestDependencyConfig, ManifestDependencyOrigin};
+///
+/// assert_eq!(
+///     rustilities::manifest::add_crate_to_dependencies_str(
+///         "[package]\nname = \"test\"\n",
+///         "serde",
+///         ManifestDependencyConfig::new(
+///             ManifestDependencyOrigin::crates_io("1.0.0"),
+///             true,
+///             vec![],
+///             false
+///         )
+///     )
+///     .unwrap(),
+///     "[package]\nname = \"test\"\n\n[dependencies]\nserde = { version = \"1.0.0\" }\n"
+/// );
+/// ```
+pub fn add_crate_to_dependencies_str(
+	content: &str,
+	dependency_name: &str,
+	dependency_config: ManifestDependencyConfig,
+) -> Result<String, Error> {
+	add_dependency_to_manifest_str(
+		content,
+		dependency_name,
+		dependency_config,
+		DependencyStyle::Inline,
+		None,
+	)
+}
+
+/// Same as [`add_crate_to_dependencies_str`], but the placeholders of the dependency origin are
+/// expanded by the given resolver.
+///
+/// # Errors
+///
+/// - The errors of [`add_crate_to_dependencies_str`].
+/// - If a placeholder cannot be expanded.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::{ManifestDependencyConfig, ManifestDependencyOrigin};
+/// use std::path::Path;
+///
+/// let resolver = |name: &str| (name == "TEMPLATES").then(|| "/opt/templates".to_owned());
+///
+/// assert_eq!(
+///     rustilities::manifest::add_crate_to_dependencies_str_with_resolver(
+///         "[dependencies]\n",
+///         "template",
+///         ManifestDependencyConfig::builder(ManifestDependencyOrigin::local(Path::new(
+///             "${TEMPLATES}/template"
+///         )))
+///         .build(),
+///         &resolver,
+///     )
+///     .unwrap(),
+///     "[dependencies]\ntemplate = { path = \"/opt/templates/template\" }\n"
+/// );
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(content, resolver)))]
+pub fn add_crate_to_dependencies_str_with_resolver(
+	content: &str,
+	dependency_name: &str,
+	dependency_config: ManifestDependencyConfig,
+	resolver: &dyn PlaceholderResolver,
+) -> Result<String, Error> {
+	add_dependency_to_manifest_str(
+		content,
+		dependency_name,
+		dependency_config,
+		DependencyStyle::Inline,
+		Some(resolver),
+	)
+}
+
+/// Same as [`add_crate_to_dependencies_str`], but the dependency is written following the given
+/// [`DependencyStyle`].
+///
+/// # Errors
+///
+/// - The errors of [`add_crate_to_dependencies_str`].
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::{DependencyStyle, ManifestDependencyConfig, ManifestDependencyOrigin};
+///
+/// let config = ManifestDependencyConfig::builder(ManifestDependencyOrigin::crates_io("1.0"))
+///     .feature("derive")
+///     .build();
+///
+/// assert_eq!(
+///     rustilities::manifest::add_crate_to_dependencies_str_with_style(
+///         "[dependencies]\n",
+///         "serde",
+///         config.clone(),
+///         DependencyStyle::TableIfMoreKeysThan(2),
+///     )
+///     .unwrap(),
+///     "[dependencies]\nserde = { version = \"1.0\", features = [\"derive\"] }\n"
+/// );
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(content)))]
+pub fn add_crate_to_dependencies_str_with_style(
+	content: &str,
+	dependency_name: &str,
+	dependency_config: ManifestDependencyConfig,
+	style: DependencyStyle,
+) -> Result<String, Error> {
+	add_dependency_to_manifest_str(content, dependency_name, dependency_config, style, None)
+}
+
+fn add_dependency_to_manifest_str(
+	content: &str,
+	dependency_name: &str,
+	dependency_config: ManifestDependencyConfig,
+	style: DependencyStyle,
+	resolver: Option<&dyn PlaceholderResolver>,
+) -> Result<String, Error> {
+	dependency_config.origin.validate()?;
+	let mut doc = content.parse::<DocumentMut>()?;
+	let in_workspace =
+		!doc.contains_key("dependencies") && doc.get("workspace").is_some_and(Item::is_table);
+	let mut parent = doc.as_table_mut();
+	if in_workspace {
+		ensure_not_inherited(&dependency_config)?;
+		parent = parent
+			.get_mut("workspace")
+			.and_then(Item::as_table_mut)
+			.expect("The workspace section is a table; qed;");
 	}
+	add_dependency_to_dependencies_table(
+		get_or_insert_table(parent, "dependencies")?,
+		dependency_name,
+		dependency_config,
+		style,
+		resolver,
+	)?;
 
-	std::fs::write(manifest_path, doc.to_string())?;
+	let edited = doc.to_string();
+	let intended: &[&str] = if in_workspace {
+		&["workspace", "dependencies", dependency_name]
+	} else {
+		&["dependencies", dependency_name]
+	};
+	roundtrip::test_verify(content, &edited, &[intended]);
+	Ok(edited)
+}
 
+/// Checks that a dependency added to `workspace.dependencies` isn't inherited from the workspace
+/// itself, which cargo rejects.
+fn ensure_not_inherited(dependency_config: &ManifestDependencyConfig) -> Result<(), Error> {
+	if dependency_config.origin == ManifestDependencyOrigin::Workspace {
+		return Err(Error::Descriptive(
+			"The dependencies of `workspace.dependencies` cannot be inherited from the workspace"
+				.to_owned(),
+		));
+	}
 	Ok(())
 }
 
@@ -309,84 +1500,220 @@ fn add_dependency_to_dependencies_table(
 	dependencies: &mut Table,
 	dependency_name: &str,
 	dependency_config: ManifestDependencyConfig,
-) {
-	let mut dependency_declaration = InlineTable::new();
-	match &dependency_config.origin {
-		ManifestDependencyOrigin::Workspace => {
-			dependency_declaration.insert(
-				"workspace",
-				toml_edit::value(true)
-					.into_value()
-					.expect("true is bool, so value(true) is Value::Boolean;qed;"),
-			);
-		},
-		ManifestDependencyOrigin::Git { url, branch } => {
-			dependency_declaration.insert(
-				"git",
-				toml_edit::value(url.to_owned())
-					.into_value()
-					.expect("url is String, so value(url) is Value::String; qed;"),
-			);
-			dependency_declaration.insert(
-				"branch",
-				toml_edit::value(branch.to_owned())
-					.into_value()
-					.expect("branch is String, so value(branch) is Value::String; qed;"),
-			);
-		},
-		ManifestDependencyOrigin::CratesIO { version } => {
-			dependency_declaration.insert(
-				"version",
-				toml_edit::value(version.to_owned())
-					.into_value()
-					.expect("version is String, so value(version) is Value::String; qed;"),
-			);
-		},
-		ManifestDependencyOrigin::Local { relative_path } => {
-			dependency_declaration.insert(
-				"path",
-				toml_edit::value(relative_path.to_string_lossy().into_owned())
-					.into_value()
-					.expect(
-						"relative_path is String, so value(relative_path) is Value::String; qed;",
-					),
-			);
-		},
+	style: DependencyStyle,
+	resolver: Option<&dyn PlaceholderResolver>,
+) -> Result<(), Error> {
+	let mut item = dependency_config.to_item(style);
+	if let Some(resolver) = resolver &&
+		let Some(declaration) = item.as_table_like_mut()
+	{
+		placeholders::expand_declaration(declaration, resolver)?;
 	}
+	dependencies.insert(dependency_name, item);
+	Ok(())
+}
 
-	if !dependency_config.default_features {
-		dependency_declaration.insert(
-			"default-features",
-			toml_edit::value(false)
-				.into_value()
-				.expect("false is bool so value(false) is Value::Boolean; qed;"),
-		);
-	}
+/// Given a workspace manifest file path, this function adds a dependency to the `dependencies`
+/// section of every workspace member (see [`find_workspace_members`]) for which `filter` returns
+/// `true`. The filter receives the member manifest path.
+///
+/// If `through_workspace` is `true`, the dependency is added only once, as described by
+/// `dependency_config`, to the `workspace.dependencies` section of the workspace manifest, while
+/// the members inherit it using `{ workspace = true }` (keeping the `optional` flag of the
+/// config). Otherwise, the dependency is added as described by `dependency_config` to each member.
+///
+/// Every manifest is edited before writing any of them, so nothing is written if some of them
+/// cannot be edited, and the manifests already written are restored if a later one cannot be
+/// written.
+///
+/// The `path` and `git` values of the origin are written as they are: use
+/// [`add_crate_to_all_members_with_resolver`] to expand placeholders such as `${WORKSPACE_ROOT}`.
+///
+/// # Errors
+///
+/// - If the dependency origin isn't valid (see [`ManifestDependencyOrigin::validate`]).
+/// - If `through_workspace` is `true` and the dependency is inherited from the workspace.
+/// - If the workspace members cannot be resolved.
+/// - If some of the manifests cannot be read, parsed or overwritten.
+/// - If some of the sections where the dependency has to be added isn't a table.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::{ManifestDependencyConfig, ManifestDependencyOrigin};
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let workspace_manifest_path = tempdir.path().join("Cargo.toml");
+/// for member in ["a", "b", "c"] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{member}\"\n"),
+///     ).unwrap();
+/// }
+/// std::fs::write(&workspace_manifest_path, "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\n").unwrap();
+///
+/// // Add serde to every member but "c", through the workspace
+/// assert!(rustilities::manifest::add_crate_to_all_members(
+///     &workspace_manifest_path,
+///     "serde",
+///     ManifestDependencyConfig::new(
+///         ManifestDependencyOrigin::crates_io("1.0.0"),
+///         true,
+///         vec!["derive"],
+///         false
+///     ),
+///     true,
+///     |member| !member.starts_with(tempdir.path().join("c"))
+/// )
+/// .is_ok());
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&workspace_manifest_path).unwrap(),
+///     r#"[workspace]
+/// members = ["a", "b", "c"]
+///
+/// [workspace.dependencies]
+/// serde = { version = "1.0.0", features = ["derive"] }
+/// "#
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(tempdir.path().join("a").join("Cargo.toml")).unwrap(),
+///     r#"[package]
+/// name = "a"
+///
+/// [dependencies]
+/// serde = { workspace = true }
+/// "#
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(tempdir.path().join("c").join("Cargo.toml")).unwrap(),
+///     "[package]\nname = \"c\"\n"
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(
+		level = "debug",
+		skip(workspace_toml, filter),
+		fields(workspace_toml = %workspace_toml.as_ref().display())
+	)
+)]
+pub fn add_crate_to_all_members<P: AsRef<Path>, F: Fn(&Path) -> bool>(
+	workspace_toml: P,
+	dependency_name: &str,
+	dependency_config: ManifestDependencyConfig,
+	through_workspace: bool,
+	filter: F,
+) -> Result<(), Error> {
+	do_add_crate_to_all_members(
+		workspace_toml.as_ref(),
+		dependency_name,
+		dependency_config,
+		through_workspace,
+		&filter,
+		None,
+	)
+}
+
+/// Same as [`add_crate_to_all_members`], but the placeholders contained in the `path` and `git`
+/// values of the origin are expanded by the given resolver. Use [`DefaultPlaceholders::new`] with
+/// the workspace dir to expand `${WORKSPACE_ROOT}` and `${ENV:NAME}`. Values containing a literal
+/// `${` must write it as `$${` then.
+///
+/// # Errors
+///
+/// - The errors of [`add_crate_to_all_members`].
+/// - If a placeholder of the origin cannot be expanded.
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(
+		level = "debug",
+		skip(workspace_toml, filter, resolver),
+		fields(workspace_toml = %workspace_toml.as_ref().display())
+	)
+)]
+pub fn add_crate_to_all_members_with_resolver<P: AsRef<Path>, F: Fn(&Path) -> bool>(
+	workspace_toml: P,
+	dependency_name: &str,
+	dependency_config: ManifestDependencyConfig,
+	through_workspace: bool,
+	filter: F,
+	resolver: &dyn PlaceholderResolver,
+) -> Result<(), Error> {
+	do_add_crate_to_all_members(
+		workspace_toml.as_ref(),
+		dependency_name,
+		dependency_config,
+		through_workspace,
+		&filter,
+		Some(resolver),
+	)
+}
 
-	if !dependency_config.features.is_empty() {
-		let mut features = Array::new();
+fn do_add_crate_to_all_members(
+	workspace_toml: &Path,
+	dependency_name: &str,
+	dependency_config: ManifestDependencyConfig,
+	through_workspace: bool,
+	filter: &dyn Fn(&Path) -> bool,
+	resolver: Option<&dyn PlaceholderResolver>,
+) -> Result<(), Error> {
+	dependency_config.origin.validate()?;
+	let members = find_workspace_members(workspace_toml)?;
+	// Every manifest is edited before writing any of them, so a failing edit writes nothing
+	let mut edited = Vec::new();
+
+	let member_config = if through_workspace {
+		ensure_not_inherited(&dependency_config)?;
+		let mut doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+		let workspace = get_or_insert_table(doc.as_table_mut(), "workspace")?;
+		add_dependency_to_dependencies_table(
+			get_or_insert_table(workspace, "dependencies")?,
+			dependency_name,
+			ManifestDependencyConfig { optional: false, ..dependency_config.clone() },
+			DependencyStyle::Inline,
+			resolver,
+		)?;
+		edited.push((workspace_toml.to_path_buf(), doc));
+
+		ManifestDependencyConfig::new(
+			ManifestDependencyOrigin::workspace(),
+			true,
+			vec![],
+			dependency_config.optional,
+		)
+	} else {
 		dependency_config
-			.features
-			.iter()
-			.for_each(|feature| features.push(feature.to_owned()));
-		dependency_declaration.insert(
-			"features",
-			toml_edit::value(features)
-				.into_value()
-				.expect("features is Array, so value(features) is Value::Array; qed;"),
-		);
-	}
+	};
 
-	if dependency_config.optional {
-		dependency_declaration.insert(
-			"optional",
-			toml_edit::value(true)
-				.into_value()
-				.expect("true is bool so value(true) is Value::Boolean; qed;"),
-		);
+	for member in members.iter().filter(|member| filter(member)) {
+		let mut doc = std::fs::read_to_string(member)?.parse::<DocumentMut>()?;
+		add_dependency_to_dependencies_table(
+			get_or_insert_table(doc.as_table_mut(), "dependencies")?,
+			dependency_name,
+			member_config.clone(),
+			DependencyStyle::Inline,
+			resolver,
+		)?;
+		edited.push((member.clone(), doc));
 	}
 
-	dependencies.insert(dependency_name, toml_edit::value(dependency_declaration));
+	EditSession::run(|session| {
+		for (manifest, doc) in &edited {
+			debug!(path = %manifest.display(), "Writing manifest");
+			session.write(manifest, doc.to_string())?;
+		}
+		Ok(())
+	})
+}
+
+fn get_or_insert_table<'a>(table: &'a mut Table, key: &str) -> Result<&'a mut Table, Error> {
+	table
+		.entry(key)
+		.or_insert_with(|| Item::Table(Table::new()))
+		.as_table_mut()
+		.ok_or_else(|| Error::Descriptive(format!("The `{key}` section isn't a table")))
 }
 
 /// Given a workspace manifest file path, and a path to a crate contained inside the workspace this
@@ -443,8 +1770,23 @@ pub fn add_crate_to_workspace<P: AsRef<Path>, Q: AsRef<Path>>(
 	workspace_toml: P,
 	crate_path: Q,
 ) -> Result<(), Error> {
-	fn do_add_crate_to_workspace(workspace_toml: &Path, crate_path: &Path) -> Result<(), Error> {
-		let mut doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+	add_crate_to_workspace_with_fs(&StdFs, workspace_toml, crate_path)
+}
+
+/// Same as [`add_crate_to_workspace`], but the workspace manifest is read and written through the
+/// given filesystem provider.
+pub fn add_crate_to_workspace_with_fs<F: FsProvider, P: AsRef<Path>, Q: AsRef<Path>>(
+	fs: &F,
+	workspace_toml: P,
+	crate_path: Q,
+) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(fs)))]
+	fn do_add_crate_to_workspace(
+		fs: &dyn FsProvider,
+		workspace_toml: &Path,
+		crate_path: &Path,
+	) -> Result<(), Error> {
+		let mut doc = fs.read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
 
 		// Find the workspace dir
 		let workspace_dir = workspace_toml.parent().expect("A file always lives inside a dir; qed");
@@ -482,8 +1824,149 @@ pub fn add_crate_to_workspace<P: AsRef<Path>, Q: AsRef<Path>>(
 			));
 		}
 
-		std::fs::write(workspace_toml, doc.to_string())?;
+		debug!(path = %workspace_toml.display(), "Writing manifest");
+		fs.write(workspace_toml, &doc.to_string())?;
 		Ok(())
 	}
-	do_add_crate_to_workspace(workspace_toml.as_ref(), crate_path.as_ref())
+	do_add_crate_to_workspace(fs, workspace_toml.as_ref(), crate_path.as_ref())
+}
+
+/// Given two manifest file paths and the key path of a section, this function copies the section
+/// from the first manifest into the second one, preserving its comments and formatting. This is
+/// useful to keep sections such as `[lints]`, `[profile.release]` or
+/// `[package.metadata.docs.rs]` in sync across repositories. The key path is given as a slice, so
+/// `[package.metadata.docs.rs]` is identified by `&["package", "metadata", "docs", "rs"]`.
+///
+/// The copied section replaces any existing section under the same key path in the target
+/// manifest, and it's appended to the end of the file. Missing parent sections are created.
+///
+/// # Errors
+///
+/// - If any of the paths cannot be read.
+/// - If any of the paths doesn't correspond to a valid Rust manifest.
+/// - If the key path is empty.
+/// - If the section doesn't exist in the source manifest.
+/// - If a parent section in the target manifest isn't a table.
+/// - If the target path cannot be overwritten.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let from_manifest = tempdir.path().join("From.toml");
+/// let to_manifest = tempdir.path().join("Cargo.toml");
+/// std::fs::write(
+///     &from_manifest,
+///     r#"[package]
+/// name = "from"
+///
+/// ## Keep the release binaries small
+/// [profile.release]
+/// opt-level = "z"
+/// "#,
+/// ).unwrap();
+/// std::fs::write(
+///     &to_manifest,
+///     r#"[package]
+/// name = "to"
+/// "#,
+/// ).unwrap();
+///
+/// rustilities::manifest::copy_section(&from_manifest, &to_manifest, &["profile", "release"])
+///     .unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&to_manifest).unwrap(),
+///     r#"[package]
+/// name = "to"
+///
+/// ## Keep the release binaries small
+/// [profile.release]
+/// opt-level = "z"
+/// "#
+/// );
+/// ```
+pub fn copy_section<P: AsRef<Path>, Q: AsRef<Path>>(
+	from_manifest: P,
+	to_manifest: Q,
+	key_path: &[&str],
+) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_copy_section(
+		from_manifest: &Path,
+		to_manifest: &Path,
+		key_path: &[&str],
+	) -> Result<(), Error> {
+		let Some((last_key, parent_keys)) = key_path.split_last() else {
+			return Err(Error::Descriptive("The section key path cannot be empty".to_owned()));
+		};
+
+		let from_doc = std::fs::read_to_string(from_manifest)?.parse::<DocumentMut>()?;
+		let mut section = key_path
+			.iter()
+			.try_fold(from_doc.as_item(), |item, key| item.get(key))
+			.cloned()
+			.ok_or_else(|| {
+				Error::Descriptive(format!(
+					"The `{}` section doesn't exist in the source manifest",
+					key_path.join(".")
+				))
+			})?;
+
+		let mut to_doc = std::fs::read_to_string(to_manifest)?.parse::<DocumentMut>()?;
+		// Place the copied tables after every table already in the target manifest, keeping their
+		// relative order.
+		let offset = max_table_position(to_doc.as_table()) + 1;
+		if let Item::Table(table) = &mut section {
+			table.set_dotted(false);
+		}
+		shift_table_positions(&mut section, offset);
+
+		let mut parent = to_doc.as_table_mut();
+		for key in parent_keys {
+			parent = parent
+				.entry(key)
+				.or_insert_with(|| {
+					let mut table = Table::new();
+					table.set_implicit(true);
+					Item::Table(table)
+				})
+				.as_table_mut()
+				.ok_or_else(|| Error::Descriptive(format!("The `{key}` section isn't a table")))?;
+		}
+		parent.insert(last_key, section);
+
+		debug!(path = %to_manifest.display(), "Writing manifest");
+		std::fs::write(to_manifest, to_doc.to_string())?;
+		Ok(())
+	}
+	do_copy_section(from_manifest.as_ref(), to_manifest.as_ref(), key_path)
+}
+
+fn max_table_position(table: &Table) -> usize {
+	table
+		.iter()
+		.flat_map(|(_, item)| match item {
+			Item::Table(table) => vec![max_table_position(table)],
+			Item::ArrayOfTables(array) => array.iter().map(max_table_position).collect(),
+			_ => Vec::new(),
+		})
+		.chain(table.position())
+		.max()
+		.unwrap_or(0)
+}
+
+fn shift_table_positions(item: &mut Item, offset: usize) {
+	fn shift_table(table: &mut Table, offset: usize) {
+		if let Some(position) = table.position() {
+			table.set_position(position + offset);
+		}
+		table.iter_mut().for_each(|(_, item)| shift_table_positions(item, offset));
+	}
+
+	match item {
+		Item::Table(table) => shift_table(table, offset),
+		Item::ArrayOfTables(array) => array.iter_mut().for_each(|table| shift_table(table, offset)),
+		_ => (),
+	}
 }
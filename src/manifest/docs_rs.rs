@@ -0,0 +1,282 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! Typed access to the `[package.metadata.docs.rs]` section of a manifest, which configures how
+//! [docs.rs](https://docs.rs) builds the documentation of a crate.
+
+#[cfg(test)]
+mod tests;
+
+use super::{dependency_tables, get_or_insert_table, remove::remove_entry};
+use crate::{Error, macros::debug};
+use std::{collections::BTreeSet, path::Path};
+use toml_edit::{Array, DocumentMut, Item, Table};
+
+/// The docs.rs configuration of a crate, as declared in its `[package.metadata.docs.rs]` section.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocsRsMetadata {
+	/// Whether docs.rs builds the crate with all its features enabled.
+	pub all_features: bool,
+	/// Whether docs.rs builds the crate without its default features.
+	pub no_default_features: bool,
+	/// The features enabled by docs.rs.
+	pub features: Vec<String>,
+	/// The target used for the default documentation page.
+	pub default_target: Option<String>,
+	/// The targets docs.rs builds the documentation for.
+	pub targets: Vec<String>,
+	/// The extra arguments passed to rustdoc, e.g. `["--cfg", "docsrs"]`.
+	pub rustdoc_args: Vec<String>,
+}
+
+impl DocsRsMetadata {
+	/// The configuration used by crates gating their documentation behind the `docsrs` cfg, like
+	/// this crate does: all features are enabled and rustdoc receives `--cfg docsrs`.
+	pub fn with_docsrs_cfg() -> Self {
+		Self {
+			all_features: true,
+			rustdoc_args: vec!["--cfg".to_owned(), "docsrs".to_owned()],
+			..Default::default()
+		}
+	}
+}
+
+/// Given a manifest file path, this function returns the docs.rs configuration declared in its
+/// `[package.metadata.docs.rs]` section, or `None` if the section doesn't exist. Keys not modeled
+/// by [`DocsRsMetadata`] are ignored.
+///
+/// # Errors
+///
+/// - If the path cannot be read.
+/// - If the path doesn't correspond to a valid Rust manifest.
+/// - If a key of the section doesn't have the expected type.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::DocsRsMetadata;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(
+///     &manifest_path,
+///     r#"[package]
+/// name = "test"
+///
+/// [package.metadata.docs.rs]
+/// all-features = true
+/// rustdoc-args = ["--cfg", "docsrs"]
+/// "#,
+/// ).unwrap();
+///
+/// assert_eq!(
+///     rustilities::manifest::read_docs_rs_metadata(&manifest_path).unwrap(),
+///     Some(DocsRsMetadata::with_docsrs_cfg())
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip(manifest_path), fields(manifest_path = %manifest_path.as_ref().display()))
+)]
+pub fn read_docs_rs_metadata<P: AsRef<Path>>(
+	manifest_path: P,
+) -> Result<Option<DocsRsMetadata>, Error> {
+	let doc = std::fs::read_to_string(manifest_path)?.parse::<DocumentMut>()?;
+	let Some(section) = ["package", "metadata", "docs", "rs"]
+		.iter()
+		.try_fold(doc.as_item(), |item, key| item.get(key))
+	else {
+		return Ok(None);
+	};
+
+	let bool_key = |key: &str| match section.get(key) {
+		None => Ok(false),
+		Some(item) => item
+			.as_bool()
+			.ok_or_else(|| Error::Descriptive(format!("The docs.rs `{key}` key isn't a boolean"))),
+	};
+	let strings_key = |key: &str| match section.get(key) {
+		None => Ok(Vec::new()),
+		Some(item) => item
+			.as_array()
+			.and_then(|array| array.iter().map(|value| value.as_str().map(str::to_owned)).collect())
+			.ok_or_else(|| {
+				Error::Descriptive(format!("The docs.rs `{key}` key isn't an array of strings"))
+			}),
+	};
+
+	Ok(Some(DocsRsMetadata {
+		all_features: bool_key("all-features")?,
+		no_default_features: bool_key("no-default-features")?,
+		features: strings_key("features")?,
+		default_target: section
+			.get("default-target")
+			.map(|item| {
+				item.as_str().map(str::to_owned).ok_or_else(|| {
+					Error::Descriptive("The docs.rs `default-target` key isn't a string".to_owned())
+				})
+			})
+			.transpose()?,
+		targets: strings_key("targets")?,
+		rustdoc_args: strings_key("rustdoc-args")?,
+	}))
+}
+
+/// Given a manifest file path and a docs.rs configuration, this function writes the configuration
+/// to the `[package.metadata.docs.rs]` section of the manifest, creating it if needed. Keys holding
+/// their default value are removed, while keys not modeled by [`DocsRsMetadata`] are kept.
+///
+/// # Errors
+///
+/// - If the path cannot be read.
+/// - If the path doesn't correspond to a valid Rust manifest.
+/// - If the `package` section, or one of its nested sections up to `docs.rs`, isn't a table.
+/// - If the path cannot be overwritten.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::DocsRsMetadata;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(&manifest_path, "[package]\nname = \"test\"\n").unwrap();
+///
+/// rustilities::manifest::write_docs_rs_metadata(&manifest_path, &DocsRsMetadata::with_docsrs_cfg())
+///     .unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&manifest_path).unwrap(),
+///     r#"[package]
+/// name = "test"
+///
+/// [package.metadata.docs.rs]
+/// all-features = true
+/// rustdoc-args = ["--cfg", "docsrs"]
+/// "#
+/// );
+/// ```
+pub fn write_docs_rs_metadata<P: AsRef<Path>>(
+	manifest_path: P,
+	metadata: &DocsRsMetadata,
+) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(metadata)))]
+	fn do_write_docs_rs_metadata(
+		manifest_path: &Path,
+		metadata: &DocsRsMetadata,
+	) -> Result<(), Error> {
+		let mut doc = std::fs::read_to_string(manifest_path)?.parse::<DocumentMut>()?;
+
+		let mut section: &mut Table = doc.as_table_mut();
+		for key in ["package", "metadata", "docs"] {
+			section = get_or_insert_table(section, key)?;
+			if key != "package" && section.is_empty() {
+				section.set_implicit(true);
+			}
+		}
+		let section = get_or_insert_table(section, "rs")?;
+
+		let mut set = |key: &str, item: Option<Item>| match item {
+			Some(item) => {
+				section.insert(key, item);
+			},
+			None => {
+				remove_entry(section, key);
+			},
+		};
+		let strings = |values: &[String]| {
+			(!values.is_empty()).then(|| toml_edit::value(values.iter().collect::<Array>()))
+		};
+		set("all-features", metadata.all_features.then(|| toml_edit::value(true)));
+		set("no-default-features", metadata.no_default_features.then(|| toml_edit::value(true)));
+		set("features", strings(&metadata.features));
+		set("default-target", metadata.default_target.as_deref().map(toml_edit::value));
+		set("targets", strings(&metadata.targets));
+		set("rustdoc-args", strings(&metadata.rustdoc_args));
+
+		debug!(path = %manifest_path.display(), "Writing manifest");
+		std::fs::write(manifest_path, doc.to_string())?;
+		Ok(())
+	}
+	do_write_docs_rs_metadata(manifest_path.as_ref(), metadata)
+}
+
+/// Given a manifest file path, this function checks that the features enabled in its
+/// `[package.metadata.docs.rs]` section exist: they must be declared in the `[features]` section or
+/// be the name of an optional dependency. Features of a dependency (`dependency/feature`) only
+/// require the dependency to exist. A manifest without that section is valid.
+///
+/// # Errors
+///
+/// - If the docs.rs configuration cannot be read, see [`read_docs_rs_metadata`].
+/// - If a feature enabled for docs.rs doesn't exist.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::Error;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(
+///     &manifest_path,
+///     r#"[package]
+/// name = "test"
+///
+/// [features]
+/// std = []
+///
+/// [package.metadata.docs.rs]
+/// features = ["std", "serde"]
+/// "#,
+/// ).unwrap();
+///
+/// assert!(matches!(
+///     rustilities::manifest::validate_docs_rs_metadata(&manifest_path),
+///     Err(Error::Descriptive(msg)) if msg == "The docs.rs feature serde isn't declared by the crate"
+/// ));
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip(manifest_path), fields(manifest_path = %manifest_path.as_ref().display()))
+)]
+pub fn validate_docs_rs_metadata<P: AsRef<Path>>(manifest_path: P) -> Result<(), Error> {
+	let manifest_path = manifest_path.as_ref();
+	let Some(metadata) = read_docs_rs_metadata(manifest_path)? else {
+		return Ok(());
+	};
+
+	let doc = std::fs::read_to_string(manifest_path)?.parse::<DocumentMut>()?;
+	let mut declared: BTreeSet<&str> = doc
+		.get("features")
+		.and_then(Item::as_table_like)
+		.map(|features| features.iter().map(|(feature, _)| feature).collect())
+		.unwrap_or_default();
+	let mut dependencies = BTreeSet::new();
+	for (_, table) in dependency_tables(&doc) {
+		for (key, dependency) in table.iter() {
+			dependencies.insert(key);
+			if dependency
+				.as_table_like()
+				.and_then(|dependency| dependency.get("optional"))
+				.and_then(Item::as_bool)
+				.unwrap_or(false)
+			{
+				declared.insert(key);
+			}
+		}
+	}
+
+	for feature in &metadata.features {
+		let exists = match feature.split_once('/') {
+			Some((dependency, _)) => dependencies.contains(dependency.trim_end_matches('?')),
+			None => declared.contains(feature.as_str()),
+		};
+		if !exists {
+			return Err(Error::Descriptive(format!(
+				"The docs.rs feature {feature} isn't declared by the crate"
+			)));
+		}
+	}
+	Ok(())
+}
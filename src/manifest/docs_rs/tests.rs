@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+fn crate_with_manifest(manifest: &str) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::write(tempdir.path().join("Cargo.toml"), manifest)
+		.expect("The manifest should be writable; qed;");
+	tempdir
+}
+
+#[test]
+fn read_docs_rs_metadata_reads_every_key() {
+	let tempdir = crate_with_manifest(
+		r#"[package]
+name = "test"
+
+[package.metadata.docs.rs]
+all-features = false
+no-default-features = true
+features = ["a", "b"]
+default-target = "x86_64-unknown-linux-gnu"
+targets = ["x86_64-unknown-linux-gnu", "wasm32-unknown-unknown"]
+rustdoc-args = ["--cfg", "docsrs"]
+cargo-args = ["-Zunstable-options"]
+"#,
+	);
+
+	assert_eq!(
+		read_docs_rs_metadata(tempdir.path().join("Cargo.toml")).expect("This should be Ok; qed;"),
+		Some(DocsRsMetadata {
+			all_features: false,
+			no_default_features: true,
+			features: vec!["a".to_owned(), "b".to_owned()],
+			default_target: Some("x86_64-unknown-linux-gnu".to_owned()),
+			targets: vec![
+				"x86_64-unknown-linux-gnu".to_owned(),
+				"wasm32-unknown-unknown".to_owned()
+			],
+			rustdoc_args: vec!["--cfg".to_owned(), "docsrs".to_owned()],
+		})
+	);
+}
+
+#[test]
+fn read_docs_rs_metadata_returns_none_without_section() {
+	let tempdir = crate_with_manifest("[package]\nname = \"test\"\n\n[package.metadata.other]\n");
+
+	assert_eq!(
+		read_docs_rs_metadata(tempdir.path().join("Cargo.toml")).expect("This should be Ok; qed;"),
+		None
+	);
+}
+
+#[test]
+fn read_docs_rs_metadata_fails_if_keys_have_wrong_types() {
+	let manifest_path = |section: &str| {
+		let tempdir = crate_with_manifest(&format!(
+			"[package]\nname = \"test\"\n\n[package.metadata.docs.rs]\n{section}\n"
+		));
+		(tempdir.path().join("Cargo.toml"), tempdir)
+	};
+
+	let (path, _tempdir) = manifest_path("all-features = \"yes\"");
+	assert!(matches!(
+		read_docs_rs_metadata(path),
+		Err(Error::Descriptive(msg)) if msg == "The docs.rs `all-features` key isn't a boolean"
+	));
+	let (path, _tempdir) = manifest_path("features = [\"a\", 1]");
+	assert!(matches!(
+		read_docs_rs_metadata(path),
+		Err(Error::Descriptive(msg)) if msg == "The docs.rs `features` key isn't an array of strings"
+	));
+	let (path, _tempdir) = manifest_path("default-target = 1");
+	assert!(matches!(
+		read_docs_rs_metadata(path),
+		Err(Error::Descriptive(msg)) if msg == "The docs.rs `default-target` key isn't a string"
+	));
+}
+
+#[test]
+fn write_docs_rs_metadata_updates_existing_section() {
+	let tempdir = crate_with_manifest(
+		r#"[package]
+name = "test"
+
+[package.metadata.docs.rs]
+# Unstable options needed by the docs
+cargo-args = ["-Zunstable-options"]
+all-features = true
+targets = ["x86_64-unknown-linux-gnu"]
+
+[dependencies]
+"#,
+	);
+	let manifest_path = tempdir.path().join("Cargo.toml");
+
+	let metadata = DocsRsMetadata {
+		features: vec!["std".to_owned()],
+		rustdoc_args: vec!["--cfg".to_owned(), "docsrs".to_owned()],
+		..Default::default()
+	};
+	write_docs_rs_metadata(&manifest_path, &metadata).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		std::fs::read_to_string(&manifest_path).expect("This should be readable; qed;"),
+		r#"[package]
+name = "test"
+
+[package.metadata.docs.rs]
+# Unstable options needed by the docs
+cargo-args = ["-Zunstable-options"]
+features = ["std"]
+rustdoc-args = ["--cfg", "docsrs"]
+
+[dependencies]
+"#
+	);
+	assert_eq!(
+		read_docs_rs_metadata(&manifest_path).expect("This should be Ok; qed;"),
+		Some(metadata)
+	);
+}
+
+#[test]
+fn write_docs_rs_metadata_fails_if_package_isnt_a_table() {
+	let tempdir = crate_with_manifest("package = 1\n");
+
+	assert!(matches!(
+		write_docs_rs_metadata(tempdir.path().join("Cargo.toml"), &DocsRsMetadata::default()),
+		Err(Error::Descriptive(msg)) if msg == "The `package` section isn't a table"
+	));
+}
+
+#[test]
+fn validate_docs_rs_metadata_accepts_declared_features() {
+	let tempdir = crate_with_manifest(
+		r#"[package]
+name = "test"
+
+[features]
+std = []
+
+[dependencies]
+serde = { version = "1.0", optional = true }
+tokio = "1.0"
+
+[package.metadata.docs.rs]
+features = ["std", "serde", "tokio/rt", "serde?/derive"]
+"#,
+	);
+
+	assert!(validate_docs_rs_metadata(tempdir.path().join("Cargo.toml")).is_ok());
+}
+
+#[test]
+fn validate_docs_rs_metadata_accepts_manifest_without_section() {
+	let tempdir = crate_with_manifest("[package]\nname = \"test\"\n");
+
+	assert!(validate_docs_rs_metadata(tempdir.path().join("Cargo.toml")).is_ok());
+}
+
+#[test]
+fn validate_docs_rs_metadata_fails_if_feature_isnt_declared() {
+	let tempdir = crate_with_manifest(
+		r#"[package]
+name = "test"
+
+[dependencies]
+tokio = "1.0"
+
+[package.metadata.docs.rs]
+features = ["tokio"]
+"#,
+	);
+
+	assert!(matches!(
+		validate_docs_rs_metadata(tempdir.path().join("Cargo.toml")),
+		Err(Error::Descriptive(msg)) if msg == "The docs.rs feature tokio isn't declared by the crate"
+	));
+}
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Edits spanning several manifests, written all at once so a failing edit never leaves a
+// workspace half edited.
+
+#[cfg(test)]
+mod tests;
+
+use super::graph::normalize;
+use crate::{Error, macros::debug};
+use std::{
+	collections::{BTreeMap, btree_map::Entry},
+	path::{Path, PathBuf},
+};
+use toml_edit::DocumentMut;
+
+/// An edit of a manifest, as taken by [`edit_many`].
+pub type EditFn = Box<dyn FnOnce(&mut DocumentMut) -> Result<(), Error>>;
+
+/// A manifest staged by [`edit_many`], together with its content before and after the edits.
+struct Staged {
+	path: PathBuf,
+	original: String,
+	edited: String,
+}
+
+/// Given a list of manifest paths and the edits to apply to them, this function applies every
+/// edit in memory, checks that every resulting manifest is valid TOML, and only then writes the
+/// edited manifests. Several edits of the same manifest are applied in the given order.
+///
+/// Nothing is written if any manifest cannot be read or parsed, any edit fails, or any result isn't
+/// valid TOML, so multi-manifest edits (eg, hoisting a dependency to the workspace) never leave a
+/// workspace half edited. Each manifest is written atomically, by writing a sibling temporary file
+/// and renaming it over the manifest once all of them are written. If a rename fails, the manifests
+/// already replaced are restored.
+///
+/// The returned paths are the manifests whose content changed, sorted.
+///
+/// # Errors
+///
+/// - If some of the manifests cannot be read or parsed.
+/// - If some of the edits fail.
+/// - If some of the edited manifests isn't valid TOML.
+/// - If the edited manifests cannot be written.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::EditFn;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let workspace_toml = tempdir.path().join("Cargo.toml");
+/// let crate_toml = tempdir.path().join("crate").join("Cargo.toml");
+/// std::fs::create_dir_all(tempdir.path().join("crate")).unwrap();
+/// std::fs::write(&workspace_toml, "[workspace]\nmembers = [\"crate\"]\n").unwrap();
+/// std::fs::write(&crate_toml, "[package]\nname = \"test\"\n\n[dependencies]\nserde = \"1.0\"\n")
+///     .unwrap();
+///
+/// let hoist: EditFn = Box::new(|doc| {
+///     doc["workspace"]["dependencies"]["serde"] = toml_edit::value("1.0");
+///     Ok(())
+/// });
+/// let inherit: EditFn = Box::new(|doc| {
+///     let mut serde = toml_edit::InlineTable::new();
+///     serde.insert("workspace", true.into());
+///     doc["dependencies"]["serde"] = toml_edit::value(serde);
+///     Ok(())
+/// });
+/// let fail: EditFn = Box::new(|_| Err(rustilities::Error::Descriptive("Nope".to_owned())));
+///
+/// // A failing edit leaves every manifest untouched.
+/// assert!(rustilities::manifest::edit_many(vec![
+///     (workspace_toml.clone(), hoist),
+///     (crate_toml.clone(), fail),
+/// ])
+/// .is_err());
+/// assert_eq!(
+///     std::fs::read_to_string(&workspace_toml).unwrap(),
+///     "[workspace]\nmembers = [\"crate\"]\n"
+/// );
+///
+/// let hoist: EditFn = Box::new(|doc| {
+///     doc["workspace"]["dependencies"]["serde"] = toml_edit::value("1.0");
+///     Ok(())
+/// });
+/// assert_eq!(
+///     rustilities::manifest::edit_many(vec![
+///         (workspace_toml.clone(), hoist),
+///         (crate_toml.clone(), inherit),
+///     ])
+///     .unwrap(),
+///     vec![workspace_toml.clone(), crate_toml.clone()]
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(&crate_toml).unwrap(),
+///     "[package]\nname = \"test\"\n\n[dependencies]\nserde = { workspace = true }\n"
+/// );
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(edits = edits.len())))]
+pub fn edit_many(edits: Vec<(PathBuf, EditFn)>) -> Result<Vec<PathBuf>, Error> {
+	// The parsed manifests, keyed by their normalized path so different spellings of the same path
+	// share the document.
+	let mut docs: BTreeMap<PathBuf, (PathBuf, String, DocumentMut)> = BTreeMap::new();
+	for (path, edit) in edits {
+		let key = normalize(&path);
+		let (_, _, doc) = match docs.entry(key) {
+			Entry::Occupied(entry) => entry.into_mut(),
+			Entry::Vacant(entry) => {
+				let original = std::fs::read_to_string(&path)?;
+				let doc = original.parse::<DocumentMut>()?;
+				entry.insert((path, original, doc))
+			},
+		};
+		edit(doc)?;
+	}
+
+	let mut staged = Vec::new();
+	for (path, original, doc) in docs.into_values() {
+		let edited = doc.to_string();
+		if edited == original {
+			continue;
+		}
+		if let Err(err) = edited.parse::<DocumentMut>() {
+			return Err(Error::Descriptive(format!(
+				"The edit of {} produced invalid TOML: {err}",
+				path.display()
+			)));
+		}
+		staged.push(Staged { path, original, edited });
+	}
+
+	write_all(&staged)?;
+	Ok(staged.into_iter().map(|staged| staged.path).collect())
+}
+
+/// Writes the staged manifests, first to temporary files next to them and then renaming those over
+/// the manifests, restoring the replaced manifests if a rename fails.
+fn write_all(staged: &[Staged]) -> Result<(), Error> {
+	let mut temporary_paths = Vec::with_capacity(staged.len());
+	for manifest in staged {
+		let temporary_path = temporary_path(&manifest.path);
+		if let Err(err) = std::fs::write(&temporary_path, &manifest.edited) {
+			let _ = std::fs::remove_file(&temporary_path);
+			remove_all(&temporary_paths);
+			return Err(err.into());
+		}
+		temporary_paths.push(temporary_path);
+	}
+
+	for (index, (manifest, temporary_path)) in staged.iter().zip(&temporary_paths).enumerate() {
+		debug!(path = %manifest.path.display(), "Writing manifest");
+		if let Err(err) = std::fs::rename(temporary_path, &manifest.path) {
+			remove_all(&temporary_paths[index..]);
+			for written in &staged[..index] {
+				debug!(path = %written.path.display(), "Restoring manifest");
+				let _ = std::fs::write(&written.path, &written.original);
+			}
+			return Err(err.into());
+		}
+	}
+	Ok(())
+}
+
+/// The temporary file a manifest is written to before replacing it.
+fn temporary_path(path: &Path) -> PathBuf {
+	let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+	path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()))
+}
+
+/// Removes the given temporary files, ignoring the errors.
+fn remove_all(paths: &[PathBuf]) {
+	for path in paths {
+		let _ = std::fs::remove_file(path);
+	}
+}
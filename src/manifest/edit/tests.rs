@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+fn workspace() -> (TempDir, PathBuf, PathBuf) {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let workspace_toml = tempdir.path().join("Cargo.toml");
+	let crate_toml = tempdir.path().join("crate").join("Cargo.toml");
+	std::fs::create_dir_all(tempdir.path().join("crate")).expect("The dir should be created; qed;");
+	std::fs::write(&workspace_toml, "[workspace]\nmembers = [\"crate\"]\n")
+		.expect("The manifest should be writable; qed;");
+	std::fs::write(&crate_toml, "[package]\nname = \"test\" # The name\n")
+		.expect("The manifest should be writable; qed;");
+	(tempdir, workspace_toml, crate_toml)
+}
+
+fn read(path: &Path) -> String {
+	std::fs::read_to_string(path).expect("The manifest should be readable; qed;")
+}
+
+fn set(key: &'static str, value: &'static str) -> EditFn {
+	Box::new(move |doc| {
+		doc["package"][key] = toml_edit::value(value);
+		Ok(())
+	})
+}
+
+fn temporary_files(tempdir: &TempDir) -> Vec<PathBuf> {
+	[tempdir.path().to_path_buf(), tempdir.path().join("crate")]
+		.iter()
+		.flat_map(|dir| std::fs::read_dir(dir).expect("The dir should be readable; qed;"))
+		.map(|entry| entry.expect("The entry should be readable; qed;").path())
+		.filter(|path| path.extension().is_some_and(|extension| extension == "tmp"))
+		.collect()
+}
+
+#[test]
+fn edit_many_applies_the_edits_of_the_same_manifest_in_order() {
+	let (tempdir, workspace_toml, crate_toml) = workspace();
+
+	assert_eq!(
+		edit_many(vec![
+			(crate_toml.clone(), set("version", "0.1.0")),
+			(tempdir.path().join("crate/../crate/Cargo.toml"), set("edition", "2024")),
+			(crate_toml.clone(), set("version", "0.2.0")),
+			(workspace_toml.clone(), Box::new(|_| Ok(()))),
+		])
+		.expect("This should be Ok; qed;"),
+		vec![crate_toml.clone()]
+	);
+	assert_eq!(
+		read(&crate_toml),
+		"[package]\nname = \"test\" # The name\nversion = \"0.2.0\"\nedition = \"2024\"\n"
+	);
+	assert_eq!(read(&workspace_toml), "[workspace]\nmembers = [\"crate\"]\n");
+	assert!(temporary_files(&tempdir).is_empty());
+}
+
+#[test]
+fn edit_many_doesnt_write_anything_if_an_edit_fails() {
+	let (tempdir, workspace_toml, crate_toml) = workspace();
+	let fail: EditFn = Box::new(|_| Err(Error::Descriptive("Nope".to_owned())));
+
+	assert!(matches!(
+		edit_many(vec![(workspace_toml.clone(), set("version", "0.1.0")), (crate_toml.clone(), fail)]),
+		Err(Error::Descriptive(msg)) if msg == "Nope"
+	));
+	assert!(matches!(
+		edit_many(vec![
+			(crate_toml.clone(), set("version", "0.1.0")),
+			(tempdir.path().join("missing/Cargo.toml"), set("version", "0.1.0")),
+		]),
+		Err(Error::IO(_))
+	));
+	assert_eq!(read(&workspace_toml), "[workspace]\nmembers = [\"crate\"]\n");
+	assert_eq!(read(&crate_toml), "[package]\nname = \"test\" # The name\n");
+	assert!(temporary_files(&tempdir).is_empty());
+}
+
+#[test]
+fn edit_many_doesnt_write_anything_if_an_edited_manifest_isnt_valid_toml() {
+	let (tempdir, workspace_toml, crate_toml) = workspace();
+	let corrupt: EditFn = Box::new(|doc| {
+		doc["package"]["name"]
+			.as_value_mut()
+			.expect("The name is a value; qed;")
+			.decor_mut()
+			.set_suffix(" garbage");
+		Ok(())
+	});
+
+	assert!(matches!(
+		edit_many(vec![(workspace_toml.clone(), set("version", "0.1.0")), (crate_toml.clone(), corrupt)]),
+		Err(Error::Descriptive(msg)) if msg.starts_with(&format!("The edit of {} produced invalid TOML: ", crate_toml.display()))
+	));
+	assert_eq!(read(&workspace_toml), "[workspace]\nmembers = [\"crate\"]\n");
+	assert_eq!(read(&crate_toml), "[package]\nname = \"test\" # The name\n");
+	assert!(temporary_files(&tempdir).is_empty());
+}
@@ -0,0 +1,822 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities related to the features declared in a manifest.
+
+#[cfg(test)]
+mod tests;
+
+use super::{
+	DependencyKind, WorkspaceGraph, dependency_package_name, dependency_tables, get_or_insert_table,
+};
+use crate::{Error, macros::debug};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	path::Path,
+};
+use toml_edit::{Array, DocumentMut, Item, TableLike};
+
+/// The options used by [`feature_powerset`] to build the feature combinations.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeatureMatrixOptions<'a> {
+	/// The maximum number of features in a combination, not counting the features in
+	/// `always_include`. `None` means there's no limit.
+	pub depth: Option<usize>,
+	/// Features included in every combination.
+	pub always_include: Vec<&'a str>,
+	/// Features never included in a combination.
+	pub exclude: Vec<&'a str>,
+	/// Groups of features that cannot be enabled together: a combination contains at most one
+	/// feature of each group.
+	pub mutually_exclusive: Vec<Vec<&'a str>>,
+}
+
+/// The features a build enables for a dependency, as computed by [`effective_features`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EffectiveFeatures {
+	/// Whether the default features of the dependency are enabled.
+	pub default_features: bool,
+	/// The features explicitly enabled, sorted and without duplicates.
+	pub features: Vec<String>,
+}
+
+/// Given a workspace manifest file path, the package name of a workspace member and the name of one
+/// of its dependencies, this function computes the features a build of the member enables for that
+/// dependency, which is a common source of confusion with workspace inheritance:
+/// - The features listed by the member are added to the features listed in the
+///   `workspace.dependencies` entry.
+/// - The default features are enabled unless the `workspace.dependencies` entry disables them and
+///   the member doesn't enable them back. Disabling them in the member has no effect if the
+///   workspace entry doesn't disable them too.
+///
+/// If the member doesn't inherit the dependency from the workspace, its own entry is used as is.
+/// The dependency is looked up by name or package name in every dependency section of the member,
+/// the first match being used.
+///
+/// # Errors
+///
+/// - If the [`WorkspaceGraph`] cannot be loaded.
+/// - If the member isn't part of the workspace.
+/// - If the member doesn't declare the dependency.
+/// - If the member inherits the dependency, but the workspace doesn't declare it.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::EffectiveFeatures;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir_all(tempdir.path().join("member")).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("Cargo.toml"),
+///     r#"
+/// [workspace]
+/// members = ["member"]
+///
+/// [workspace.dependencies]
+/// serde = { version = "1.0", features = ["derive"] }
+/// "#,
+/// ).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("member").join("Cargo.toml"),
+///     r#"
+/// [package]
+/// name = "member"
+///
+/// [dependencies]
+/// serde = { workspace = true, default-features = false, features = ["rc"] }
+/// "#,
+/// ).unwrap();
+///
+/// assert_eq!(
+///     rustilities::manifest::effective_features(tempdir.path().join("Cargo.toml"), "member", "serde")
+///         .unwrap(),
+///     EffectiveFeatures {
+///         // The workspace entry doesn't disable them, so the member cannot do it
+///         default_features: true,
+///         features: vec!["derive".to_owned(), "rc".to_owned()],
+///     }
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip(workspace_toml), fields(workspace_toml = %workspace_toml.as_ref().display()))
+)]
+pub fn effective_features<P: AsRef<Path>>(
+	workspace_toml: P,
+	member: &str,
+	dependency: &str,
+) -> Result<EffectiveFeatures, Error> {
+	let workspace_toml = workspace_toml.as_ref();
+	let graph = WorkspaceGraph::load(workspace_toml)?;
+	let member_manifest = &graph
+		.member(member)
+		.ok_or_else(|| Error::Descriptive(format!("{member} isn't a member of the workspace")))?
+		.manifest_path;
+
+	let member_doc = std::fs::read_to_string(member_manifest)?.parse::<DocumentMut>()?;
+	let member_entry = dependency_tables(&member_doc)
+		.into_iter()
+		.find_map(|(_, table)| {
+			table.iter().find_map(|(key, entry)| {
+				(dependency_package_name(key, entry) == dependency).then_some((key, entry))
+			})
+		})
+		.ok_or_else(|| Error::Descriptive(format!("{member} doesn't depend on {dependency}")))?;
+
+	let (key, member_entry) = member_entry;
+	let workspace_doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+	Ok(resolve_entry(member, dependency, key, member_entry, &workspace_doc)?.features)
+}
+
+/// The features a dependency is built with across a workspace, as computed by
+/// [`feature_unification_report`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnifiedDependency {
+	/// The package name of the dependency.
+	pub name: String,
+	/// The features the dependency is built with when the whole workspace is built: the union of
+	/// the features requested by the members.
+	pub unified: EffectiveFeatures,
+	/// The features requested by each member depending on the dependency, by package name.
+	pub requested: BTreeMap<String, EffectiveFeatures>,
+	/// The members whose request differs from the unified features, sorted. Building one of these
+	/// members on its own builds the dependency with other features than a workspace build, so the
+	/// dependency (and everything depending on it) is compiled twice.
+	pub diverging: Vec<String>,
+}
+
+/// Given a workspace manifest file path, this function computes, for every external dependency of
+/// the workspace members, the features cargo unifies when building the whole workspace, and flags
+/// the members that request a different set, which is the groundwork for workspace-hack style
+/// tooling. The requests of the members are computed as [`effective_features`] does.
+///
+/// Only the normal dependencies (including target-specific ones) are taken into account: cargo
+/// doesn't unify the features of dev and build dependencies with them. Dependencies declared with
+/// a `path`, either by the member or by the `workspace.dependencies` entry it inherits, aren't
+/// external, so they're ignored. The dependencies are identified by package name, and the output
+/// is sorted by it. A member declaring a dependency several times (eg, for different targets)
+/// requests the union of the entries.
+///
+/// # Errors
+///
+/// - If the [`WorkspaceGraph`] cannot be loaded.
+/// - If some member inherits a dependency, but the workspace doesn't declare it.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::EffectiveFeatures;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// for (member, dependencies) in [
+///     ("cli", "serde = { version = \"1.0\", features = [\"derive\"] }"),
+///     ("core", "serde = { version = \"1.0\", default-features = false }"),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{member}\"\n\n[dependencies]\n{dependencies}"),
+///     ).unwrap();
+/// }
+/// std::fs::write(tempdir.path().join("Cargo.toml"), "[workspace]\nmembers = [\"*\"]").unwrap();
+///
+/// let report =
+///     rustilities::manifest::feature_unification_report(tempdir.path().join("Cargo.toml"))
+///         .unwrap();
+///
+/// assert_eq!(report.len(), 1);
+/// assert_eq!(report[0].name, "serde");
+/// assert_eq!(
+///     report[0].unified,
+///     EffectiveFeatures { default_features: true, features: vec!["derive".to_owned()] }
+/// );
+/// // Building core alone builds serde without its default features
+/// assert_eq!(report[0].diverging, vec!["core"]);
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip_all, fields(workspace_toml = %workspace_toml.as_ref().display()))
+)]
+pub fn feature_unification_report<P: AsRef<Path>>(
+	workspace_toml: P,
+) -> Result<Vec<UnifiedDependency>, Error> {
+	let workspace_toml = workspace_toml.as_ref();
+	let graph = WorkspaceGraph::load(workspace_toml)?;
+	let workspace_doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+
+	// The requests of every member, by dependency and member
+	let mut requests: BTreeMap<String, BTreeMap<String, EffectiveFeatures>> = BTreeMap::new();
+	for member in graph.members() {
+		let doc = std::fs::read_to_string(&member.manifest_path)?.parse::<DocumentMut>()?;
+		for (kind, table) in dependency_tables(&doc) {
+			if kind != DependencyKind::Normal {
+				continue;
+			}
+			for (key, entry) in table.iter() {
+				let dependency = dependency_package_name(key, entry);
+				let resolved = resolve_entry(&member.name, dependency, key, entry, &workspace_doc)?;
+				if resolved.local {
+					continue;
+				}
+				requests
+					.entry(dependency.to_owned())
+					.or_default()
+					.entry(member.name.clone())
+					.and_modify(|request| request.merge(&resolved.features))
+					.or_insert(resolved.features);
+			}
+		}
+	}
+
+	Ok(requests
+		.into_iter()
+		.map(|(name, requested)| {
+			let mut unified = EffectiveFeatures { default_features: false, features: Vec::new() };
+			for request in requested.values() {
+				unified.merge(request);
+			}
+			let diverging = requested
+				.iter()
+				.filter(|(_, request)| **request != unified)
+				.map(|(member, _)| member.clone())
+				.collect();
+			UnifiedDependency { name, unified, requested, diverging }
+		})
+		.collect())
+}
+
+impl EffectiveFeatures {
+	/// Adds the features enabled by another request.
+	fn merge(&mut self, other: &EffectiveFeatures) {
+		self.default_features |= other.default_features;
+		let features =
+			self.features.iter().chain(&other.features).cloned().collect::<BTreeSet<_>>();
+		self.features = features.into_iter().collect();
+	}
+}
+
+/// A dependency entry of a member, merged with the `workspace.dependencies` entry it inherits, if
+/// any.
+struct ResolvedEntry {
+	features: EffectiveFeatures,
+	// Whether the dependency is declared with a path
+	local: bool,
+}
+
+fn resolve_entry(
+	member: &str,
+	dependency: &str,
+	key: &str,
+	member_entry: &Item,
+	workspace_doc: &DocumentMut,
+) -> Result<ResolvedEntry, Error> {
+	let member_table = member_entry.as_table_like();
+	let member_features = member_table.map(entry_features).unwrap_or_default();
+	let member_default_features = member_table.and_then(entry_default_features);
+
+	if !member_table
+		.and_then(|table| table.get("workspace"))
+		.and_then(Item::as_bool)
+		.unwrap_or(false)
+	{
+		return Ok(ResolvedEntry {
+			features: EffectiveFeatures {
+				default_features: member_default_features.unwrap_or(true),
+				features: member_features.into_iter().collect(),
+			},
+			local: member_table.is_some_and(|table| table.contains_key("path")),
+		});
+	}
+
+	let workspace_entry = workspace_doc
+		.get("workspace")
+		.and_then(|workspace| workspace.get("dependencies"))
+		.and_then(|dependencies| dependencies.get(key))
+		.ok_or_else(|| {
+			Error::Descriptive(format!(
+				"{member} inherits {dependency} from the workspace, but the workspace doesn't declare it"
+			))
+		})?;
+	let workspace_table = workspace_entry.as_table_like();
+
+	let mut features = workspace_table.map(entry_features).unwrap_or_default();
+	features.extend(member_features);
+	let default_features = workspace_table.and_then(entry_default_features).unwrap_or(true) ||
+		member_default_features.unwrap_or(false);
+
+	Ok(ResolvedEntry {
+		features: EffectiveFeatures { default_features, features: features.into_iter().collect() },
+		local: workspace_table.is_some_and(|table| table.contains_key("path")),
+	})
+}
+
+fn entry_features(entry: &dyn TableLike) -> BTreeSet<String> {
+	entry
+		.get("features")
+		.and_then(Item::as_array)
+		.map(|features| {
+			features
+				.iter()
+				.filter_map(|feature| feature.as_str().map(str::to_owned))
+				.collect()
+		})
+		.unwrap_or_default()
+}
+
+fn entry_default_features(entry: &dyn TableLike) -> Option<bool> {
+	entry
+		.get("default-features")
+		.or_else(|| entry.get("default_features"))
+		.and_then(Item::as_bool)
+}
+
+/// Given a manifest file path, a feature name and the features (or optional dependencies) it
+/// enables, this function declares the feature in the `features` section of the manifest, creating
+/// the section if needed. If the feature is already declared, the enabled features it doesn't list
+/// yet are appended to it.
+///
+/// # Errors
+///
+/// - If the path cannot be read.
+/// - If the path doesn't correspond to a valid Rust manifest.
+/// - If the `features` section isn't a table, or the feature is declared but it isn't an array.
+/// - If the path cannot be overwritten.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(&manifest_path, "[package]\nname = \"test\"\n").unwrap();
+///
+/// rustilities::manifest::add_feature(&manifest_path, "serde", &["dep:serde"]).unwrap();
+/// rustilities::manifest::add_feature(&manifest_path, "serde", &["dep:serde", "std"]).unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&manifest_path).unwrap(),
+///     r#"[package]
+/// name = "test"
+///
+/// [features]
+/// serde = ["dep:serde", "std"]
+/// "#
+/// );
+/// ```
+pub fn add_feature<P: AsRef<Path>>(
+	manifest_path: P,
+	feature: &str,
+	enables: &[&str],
+) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_add_feature(manifest_path: &Path, feature: &str, enables: &[&str]) -> Result<(), Error> {
+		let mut doc = std::fs::read_to_string(manifest_path)?.parse::<DocumentMut>()?;
+		let features = get_or_insert_table(doc.as_table_mut(), "features")?;
+		let enabled = features
+			.entry(feature)
+			.or_insert_with(|| toml_edit::value(Array::new()))
+			.as_array_mut()
+			.ok_or_else(|| Error::Descriptive(format!("The feature {feature} isn't an array")))?;
+		for enable in enables {
+			if !enabled.iter().any(|value| value.as_str() == Some(enable)) {
+				enabled.push(*enable);
+			}
+		}
+
+		debug!(path = %manifest_path.display(), "Writing manifest");
+		std::fs::write(manifest_path, doc.to_string())?;
+		Ok(())
+	}
+	do_add_feature(manifest_path.as_ref(), feature, enables)
+}
+
+/// Given a manifest file path, this function returns the combinations of the features declared in
+/// its `features` section that tools may feed into `cargo check --no-default-features --features
+/// ...` runs, as `cargo hack --feature-powerset` does.
+///
+/// The `default` feature isn't part of the combinations. Features are sorted alphabetically inside
+/// each combination (after the `always_include` ones, which keep their order), and the
+/// combinations are sorted by size and then alphabetically, starting by the combination with no
+/// features (besides the `always_include` ones).
+///
+/// # Errors
+///
+/// - If the path cannot be read.
+/// - If the path doesn't correspond to a valid Rust manifest.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::FeatureMatrixOptions;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(
+///     &manifest_path,
+///     r#"
+/// [package]
+/// name = "test"
+///
+/// [features]
+/// default = ["std"]
+/// std = []
+/// serde = []
+/// runtime-a = []
+/// runtime-b = []
+/// "#,
+/// ).unwrap();
+///
+/// let matrix = rustilities::manifest::feature_powerset(
+///     &manifest_path,
+///     FeatureMatrixOptions {
+///         depth: Some(2),
+///         always_include: vec!["std"],
+///         mutually_exclusive: vec![vec!["runtime-a", "runtime-b"]],
+///         ..Default::default()
+///     },
+/// )
+/// .unwrap();
+///
+/// assert_eq!(
+///     matrix,
+///     vec![
+///         vec!["std"],
+///         vec!["std", "runtime-a"],
+///         vec!["std", "runtime-b"],
+///         vec!["std", "serde"],
+///         vec!["std", "runtime-a", "serde"],
+///         vec!["std", "runtime-b", "serde"],
+///     ]
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip(manifest_path), fields(manifest_path = %manifest_path.as_ref().display()))
+)]
+pub fn feature_powerset<P: AsRef<Path>>(
+	manifest_path: P,
+	options: FeatureMatrixOptions,
+) -> Result<Vec<Vec<String>>, Error> {
+	let doc = std::fs::read_to_string(manifest_path.as_ref())?.parse::<DocumentMut>()?;
+	let mut features = doc
+		.get("features")
+		.and_then(|features| features.as_table_like())
+		.map(|features| features.iter().map(|(key, _)| key).collect::<Vec<_>>())
+		.unwrap_or_default();
+	features.retain(|feature| {
+		*feature != "default" &&
+			!options.always_include.contains(feature) &&
+			!options.exclude.contains(feature)
+	});
+	features.sort_unstable();
+
+	let max_size = options.depth.unwrap_or(features.len()).min(features.len());
+	let mut combinations = Vec::new();
+	for size in 0..=max_size {
+		let mut combination = Vec::with_capacity(size);
+		push_combinations(
+			&features,
+			size,
+			&options.mutually_exclusive,
+			&mut combination,
+			&mut combinations,
+		);
+	}
+
+	Ok(combinations
+		.into_iter()
+		.map(|combination| {
+			options
+				.always_include
+				.iter()
+				.chain(combination.iter())
+				.map(|feature| (*feature).to_owned())
+				.collect()
+		})
+		.collect())
+}
+
+/// Given a manifest file path and some enabled features, this function computes everything those
+/// features turn on through the `features` section of the manifest, answering questions such as
+/// "does enabling X turn on Y". The output contains:
+/// - The enabled features and the features they enable, transitively.
+/// - The optional dependencies enabled, as `dep:name`, either explicitly or through their implicit
+///   feature (only present if no feature uses the `dep:` syntax for that dependency), or through a
+///   `name/feature` entry.
+/// - The features enabled in dependencies, as `name/feature` (`name?/feature` entries are written
+///   as `name/feature` too, and only enable the optional dependency if it's enabled elsewhere).
+///
+/// The `default` feature is only followed if it's part of the enabled features.
+///
+/// # Errors
+///
+/// - If the path cannot be read.
+/// - If the path doesn't correspond to a valid Rust manifest.
+/// - If some of the enabled features isn't declared by the manifest.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(
+///     &manifest_path,
+///     r#"
+/// [package]
+/// name = "test"
+///
+/// [dependencies]
+/// serde = { version = "1.0", optional = true }
+///
+/// [features]
+/// default = ["std"]
+/// std = ["serde?/std"]
+/// full = ["std", "serde/derive"]
+/// "#,
+/// ).unwrap();
+///
+/// let closure = rustilities::manifest::feature_closure(&manifest_path, &["full"]).unwrap();
+/// assert_eq!(
+///     closure.into_iter().collect::<Vec<_>>(),
+///     vec!["dep:serde", "full", "serde", "serde/derive", "serde/std", "std"]
+/// );
+///
+/// let closure = rustilities::manifest::feature_closure(&manifest_path, &["default"]).unwrap();
+/// assert!(!closure.contains("dep:serde"));
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip(manifest_path), fields(manifest_path = %manifest_path.as_ref().display()))
+)]
+pub fn feature_closure<P: AsRef<Path>>(
+	manifest_path: P,
+	enabled: &[&str],
+) -> Result<BTreeSet<String>, Error> {
+	let doc = std::fs::read_to_string(manifest_path.as_ref())?.parse::<DocumentMut>()?;
+	closure_of(&doc, enabled)
+}
+
+/// The closure of the enabled features in the manifest `doc`, as computed by [`feature_closure`].
+pub(super) fn closure_of(doc: &DocumentMut, enabled: &[&str]) -> Result<BTreeSet<String>, Error> {
+	let features = FeatureGraph::new(doc);
+	if let Some(feature) = enabled.iter().find(|feature| !features.is_feature(feature)) {
+		return Err(Error::Descriptive(format!("The feature {feature} isn't declared")));
+	}
+
+	let mut closure = BTreeSet::new();
+	// The `name?/feature` entries, which are only kept if the dependency ends up enabled
+	let mut weak = BTreeSet::new();
+	let mut pending = enabled.iter().map(|feature| (*feature).to_owned()).collect::<Vec<_>>();
+	while let Some(entry) = pending.pop() {
+		if let Some(dependency) = entry.strip_prefix("dep:") {
+			closure.insert(entry.clone());
+			// The implicit feature of an optional dependency is enabled along with it
+			if features.has_implicit_feature(dependency) {
+				closure.insert(dependency.to_owned());
+			}
+		} else if let Some((dependency, feature)) = entry.split_once('/') {
+			match dependency.strip_suffix('?') {
+				Some(dependency) => {
+					weak.insert((dependency.to_owned(), feature.to_owned()));
+				},
+				None => {
+					closure.insert(entry.clone());
+					if features.optional_dependencies.contains(dependency) {
+						pending.push(format!("dep:{dependency}"));
+					}
+					if features.is_feature(dependency) {
+						pending.push(dependency.to_owned());
+					}
+				},
+			}
+		} else if closure.insert(entry.clone()) {
+			match features.features.get(&entry) {
+				Some(enables) => pending.extend(enables.iter().cloned()),
+				None if features.has_implicit_feature(&entry) =>
+					pending.push(format!("dep:{entry}")),
+				None => (),
+			}
+		}
+	}
+
+	for (dependency, feature) in weak {
+		if closure.contains(&format!("dep:{dependency}")) ||
+			!features.optional_dependencies.contains(&dependency)
+		{
+			closure.insert(format!("{dependency}/{feature}"));
+		}
+	}
+	Ok(closure)
+}
+
+/// Given a manifest file path, this function returns the cycles found in its `features` section,
+/// which cargo rejects with an error that doesn't tell the whole cycle. Every cycle is returned as
+/// the path of features walked, starting and ending with the same feature (the alphabetically
+/// smallest of the cycle), eg `["a", "b", "a"]`. The cycles are sorted and every one of them is
+/// returned once.
+///
+/// Every strongly connected set of features yields at least one cycle, but cycles sharing
+/// features may be reported only partially.
+///
+/// # Errors
+///
+/// - If the path cannot be read.
+/// - If the path doesn't correspond to a valid Rust manifest.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(
+///     &manifest_path,
+///     r#"
+/// [package]
+/// name = "test"
+///
+/// [features]
+/// default = ["std"]
+/// std = ["alloc"]
+/// alloc = ["std"]
+/// "#,
+/// ).unwrap();
+///
+/// assert_eq!(
+///     rustilities::manifest::detect_feature_cycles(&manifest_path).unwrap(),
+///     vec![vec!["alloc", "std", "alloc"]]
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip(manifest_path), fields(manifest_path = %manifest_path.as_ref().display()))
+)]
+pub fn detect_feature_cycles<P: AsRef<Path>>(manifest_path: P) -> Result<Vec<Vec<String>>, Error> {
+	let doc = std::fs::read_to_string(manifest_path.as_ref())?.parse::<DocumentMut>()?;
+	Ok(cycles_of(&doc))
+}
+
+/// The feature cycles of the manifest `doc`, as computed by [`detect_feature_cycles`].
+pub(super) fn cycles_of(doc: &DocumentMut) -> Vec<Vec<String>> {
+	let features = FeatureGraph::new(doc);
+
+	let mut cycles = BTreeSet::new();
+	let mut visited = BTreeSet::new();
+	for feature in features.features.keys() {
+		let mut stack = Vec::new();
+		push_cycles(&features, feature, &mut stack, &mut visited, &mut cycles);
+	}
+	cycles.into_iter().collect()
+}
+
+/// Walks the features enabled by `feature` depth first, pushing into `cycles` the cycles closed
+/// by the walk. `stack` contains the features being walked.
+fn push_cycles<'a>(
+	features: &'a FeatureGraph,
+	feature: &'a str,
+	stack: &mut Vec<&'a str>,
+	visited: &mut BTreeSet<&'a str>,
+	cycles: &mut BTreeSet<Vec<String>>,
+) {
+	if let Some(start) = stack.iter().position(|walked| *walked == feature) {
+		let cycle = &stack[start..];
+		let smallest = cycle
+			.iter()
+			.enumerate()
+			.min_by_key(|(_, feature)| **feature)
+			.map(|(index, _)| index)
+			.unwrap_or_default();
+		let mut path = cycle[smallest..]
+			.iter()
+			.chain(&cycle[..smallest])
+			.map(|feature| (*feature).to_owned())
+			.collect::<Vec<_>>();
+		path.push(path[0].clone());
+		cycles.insert(path);
+		return;
+	}
+	if !visited.insert(feature) {
+		return;
+	}
+
+	stack.push(feature);
+	for enabled in features.features.get(feature).into_iter().flatten() {
+		if features.features.contains_key(enabled) {
+			push_cycles(features, enabled, stack, visited, cycles);
+		}
+	}
+	stack.pop();
+}
+
+/// The `features` section of a manifest together with its optional dependencies.
+pub(super) struct FeatureGraph {
+	features: BTreeMap<String, Vec<String>>,
+	optional_dependencies: BTreeSet<String>,
+	// The optional dependencies referred to with the `dep:` syntax, which therefore don't have an
+	// implicit feature
+	explicit_dependencies: BTreeSet<String>,
+}
+
+impl FeatureGraph {
+	pub(super) fn new(doc: &DocumentMut) -> Self {
+		let features = doc
+			.get("features")
+			.and_then(Item::as_table_like)
+			.map(|features| {
+				features
+					.iter()
+					.map(|(feature, enables)| {
+						let enables = enables
+							.as_array()
+							.map(|enables| {
+								enables
+									.iter()
+									.filter_map(|enable| enable.as_str().map(str::to_owned))
+									.collect()
+							})
+							.unwrap_or_default();
+						(feature.to_owned(), enables)
+					})
+					.collect::<BTreeMap<_, Vec<_>>>()
+			})
+			.unwrap_or_default();
+		let optional_dependencies = dependency_tables(doc)
+			.into_iter()
+			.flat_map(|(_, table)| {
+				table
+					.iter()
+					.filter(|(_, dependency)| {
+						dependency.get("optional").and_then(Item::as_bool).unwrap_or(false)
+					})
+					.map(|(key, _)| key.to_owned())
+					.collect::<Vec<_>>()
+			})
+			.collect();
+		let explicit_dependencies = features
+			.values()
+			.flatten()
+			.filter_map(|enable| enable.strip_prefix("dep:").map(str::to_owned))
+			.collect();
+		Self { features, optional_dependencies, explicit_dependencies }
+	}
+
+	fn has_implicit_feature(&self, dependency: &str) -> bool {
+		self.optional_dependencies.contains(dependency) &&
+			!self.explicit_dependencies.contains(dependency) &&
+			!self.features.contains_key(dependency)
+	}
+
+	fn is_feature(&self, name: &str) -> bool {
+		self.features.contains_key(name) || self.has_implicit_feature(name)
+	}
+
+	/// The features that can be enabled, including the implicit features of the optional
+	/// dependencies.
+	#[cfg(feature = "parsing")]
+	pub(super) fn declared_features(&self) -> BTreeSet<&str> {
+		self.features
+			.keys()
+			.chain(
+				self.optional_dependencies
+					.iter()
+					.filter(|dependency| self.has_implicit_feature(dependency)),
+			)
+			.map(String::as_str)
+			.collect()
+	}
+
+	/// Whether the feature enables other features declared in the `features` section, rather
+	/// than only dependencies or their features.
+	#[cfg(feature = "parsing")]
+	pub(super) fn enables_features(&self, feature: &str) -> bool {
+		self.features
+			.get(feature)
+			.is_some_and(|enables| enables.iter().any(|enable| self.features.contains_key(enable)))
+	}
+}
+
+/// Pushes into `output` every combination of `size` elements taken from `features` extending
+/// `current`, skipping those containing more than one feature of a mutually exclusive group.
+fn push_combinations<'a>(
+	features: &[&'a str],
+	size: usize,
+	mutually_exclusive: &[Vec<&str>],
+	current: &mut Vec<&'a str>,
+	output: &mut Vec<Vec<&'a str>>,
+) {
+	if current.len() == size {
+		output.push(current.clone());
+		return;
+	}
+
+	for (index, feature) in features.iter().enumerate() {
+		let conflicts = mutually_exclusive.iter().any(|group| {
+			group.contains(feature) && current.iter().any(|current| group.contains(current))
+		});
+		if !conflicts {
+			current.push(feature);
+			push_combinations(&features[index + 1..], size, mutually_exclusive, current, output);
+			current.pop();
+		}
+	}
+}
@@ -0,0 +1,417 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+fn manifest_with_features(features: &str) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::write(
+		tempdir.path().join("Cargo.toml"),
+		format!("[package]\nname = \"test\"\n\n[features]\n{features}"),
+	)
+	.expect("The manifest should be writable; qed;");
+	tempdir
+}
+
+#[test]
+fn feature_powerset_without_options_returns_every_combination() {
+	let tempdir = manifest_with_features("default = [\"c\"]\nc = []\nb = []\na = [\"b\"]");
+
+	assert_eq!(
+		feature_powerset(tempdir.path().join("Cargo.toml"), FeatureMatrixOptions::default())
+			.expect("This should be Ok; qed;"),
+		vec![
+			vec![],
+			vec!["a"],
+			vec!["b"],
+			vec!["c"],
+			vec!["a", "b"],
+			vec!["a", "c"],
+			vec!["b", "c"],
+			vec!["a", "b", "c"]
+		]
+	);
+}
+
+#[test]
+fn feature_powerset_honors_options() {
+	let tempdir = manifest_with_features("a = []\nb = []\nc = []\nd = []\ne = []\nf = []");
+
+	assert_eq!(
+		feature_powerset(
+			tempdir.path().join("Cargo.toml"),
+			FeatureMatrixOptions {
+				depth: Some(2),
+				always_include: vec!["f", "e"],
+				exclude: vec!["d"],
+				mutually_exclusive: vec![vec!["a", "b"], vec!["b", "c"]],
+			}
+		)
+		.expect("This should be Ok; qed;"),
+		vec![
+			vec!["f", "e"],
+			vec!["f", "e", "a"],
+			vec!["f", "e", "b"],
+			vec!["f", "e", "c"],
+			vec!["f", "e", "a", "c"]
+		]
+	);
+}
+
+#[test]
+fn feature_powerset_depth_zero_only_includes_always_included_features() {
+	let tempdir = manifest_with_features("a = []\nb = []");
+
+	assert_eq!(
+		feature_powerset(
+			tempdir.path().join("Cargo.toml"),
+			FeatureMatrixOptions {
+				depth: Some(0),
+				always_include: vec!["b"],
+				..Default::default()
+			}
+		)
+		.expect("This should be Ok; qed;"),
+		vec![vec!["b"]]
+	);
+}
+
+#[test]
+fn feature_powerset_works_without_features_section() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::write(tempdir.path().join("Cargo.toml"), "[package]\nname = \"test\"")
+		.expect("The manifest should be writable; qed;");
+
+	assert_eq!(
+		feature_powerset(tempdir.path().join("Cargo.toml"), FeatureMatrixOptions::default())
+			.expect("This should be Ok; qed;"),
+		vec![Vec::<String>::new()]
+	);
+}
+
+#[test]
+fn feature_powerset_fails_if_manifest_cannot_be_read() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+
+	assert!(matches!(
+		feature_powerset(tempdir.path().join("Cargo.toml"), FeatureMatrixOptions::default()),
+		Err(Error::IO(err)) if err.kind() == std::io::ErrorKind::NotFound
+	));
+}
+
+#[test]
+fn add_feature_creates_and_extends_features() {
+	let tempdir = manifest_with_features("default = [\"std\"]\nstd = []");
+	let manifest_path = tempdir.path().join("Cargo.toml");
+
+	add_feature(&manifest_path, "serde", &["dep:serde"]).expect("This should be Ok; qed;");
+	add_feature(&manifest_path, "std", &["serde?/std"]).expect("This should be Ok; qed;");
+	add_feature(&manifest_path, "std", &["serde?/std"]).expect("This should be Ok; qed;");
+	add_feature(&manifest_path, "empty", &[]).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		std::fs::read_to_string(&manifest_path).expect("This should be Ok; qed;"),
+		"[package]\nname = \"test\"\n\n[features]\ndefault = [\"std\"]\nstd = [\"serde?/std\"]\nserde = \
+		 [\"dep:serde\"]\nempty = []\n"
+	);
+}
+
+#[test]
+fn add_feature_creates_features_section() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let manifest_path = tempdir.path().join("Cargo.toml");
+	std::fs::write(&manifest_path, "[package]\nname = \"test\"\n")
+		.expect("The manifest should be writable; qed;");
+
+	add_feature(&manifest_path, "std", &[]).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		std::fs::read_to_string(&manifest_path).expect("This should be Ok; qed;"),
+		"[package]\nname = \"test\"\n\n[features]\nstd = []\n"
+	);
+}
+
+#[test]
+fn add_feature_fails_if_feature_isnt_an_array() {
+	let tempdir = manifest_with_features("std = true");
+	assert!(matches!(
+		add_feature(tempdir.path().join("Cargo.toml"), "std", &[]),
+		Err(Error::Descriptive(msg)) if msg == "The feature std isn't an array"
+	));
+}
+
+fn workspace_with_member(workspace_dependencies: &str, member_dependencies: &str) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::create_dir_all(tempdir.path().join("member")).expect("This should be created; qed;");
+	std::fs::write(
+		tempdir.path().join("Cargo.toml"),
+		format!(
+			"[workspace]\nmembers = [\"member\"]\n\n[workspace.dependencies]\n{workspace_dependencies}"
+		),
+	)
+	.expect("The manifest should be writable; qed;");
+	std::fs::write(
+		tempdir.path().join("member").join("Cargo.toml"),
+		format!("[package]\nname = \"member\"\n\n{member_dependencies}"),
+	)
+	.expect("The manifest should be writable; qed;");
+	tempdir
+}
+
+#[test]
+fn effective_features_merges_workspace_and_member_features() {
+	let tempdir = workspace_with_member(
+		"dep = { version = \"1.0\", default-features = false, features = [\"b\", \"a\"] }",
+		"[dev-dependencies]\ndep = { workspace = true, features = [\"c\", \"a\"] }",
+	);
+
+	assert_eq!(
+		effective_features(tempdir.path().join("Cargo.toml"), "member", "dep")
+			.expect("This should be Ok; qed;"),
+		EffectiveFeatures {
+			default_features: false,
+			features: vec!["a".to_owned(), "b".to_owned(), "c".to_owned()]
+		}
+	);
+}
+
+#[test]
+fn effective_features_member_can_only_enable_default_features() {
+	let tempdir = workspace_with_member(
+		"dep = { version = \"1.0\", default_features = false }\nother = \"1.0\"",
+		"[dependencies]\ndep = { workspace = true, default-features = true }\nother = { workspace = true, default-features = false }",
+	);
+
+	assert_eq!(
+		effective_features(tempdir.path().join("Cargo.toml"), "member", "dep")
+			.expect("This should be Ok; qed;"),
+		EffectiveFeatures { default_features: true, features: vec![] }
+	);
+	assert_eq!(
+		effective_features(tempdir.path().join("Cargo.toml"), "member", "other")
+			.expect("This should be Ok; qed;"),
+		EffectiveFeatures { default_features: true, features: vec![] }
+	);
+}
+
+#[test]
+fn effective_features_uses_member_entry_if_not_inherited() {
+	let tempdir = workspace_with_member(
+		"dep = { version = \"1.0\", features = [\"ignored\"] }",
+		"[dependencies]\nalias = { package = \"dep\", version = \"1.0\", default-features = false, features = [\"a\"] }",
+	);
+
+	assert_eq!(
+		effective_features(tempdir.path().join("Cargo.toml"), "member", "dep")
+			.expect("This should be Ok; qed;"),
+		EffectiveFeatures { default_features: false, features: vec!["a".to_owned()] }
+	);
+}
+
+#[test]
+fn effective_features_fails_if_member_or_dependency_not_found() {
+	let tempdir = workspace_with_member("", "[dependencies]\ndep = { workspace = true }");
+
+	assert!(matches!(
+		effective_features(tempdir.path().join("Cargo.toml"), "other", "dep"),
+		Err(Error::Descriptive(msg)) if msg == "other isn't a member of the workspace"
+	));
+	assert!(matches!(
+		effective_features(tempdir.path().join("Cargo.toml"), "member", "serde"),
+		Err(Error::Descriptive(msg)) if msg == "member doesn't depend on serde"
+	));
+	assert!(matches!(
+		effective_features(tempdir.path().join("Cargo.toml"), "member", "dep"),
+		Err(Error::Descriptive(msg)) if msg == "member inherits dep from the workspace, but the workspace doesn't declare it"
+	));
+}
+
+fn workspace_with_members(workspace_dependencies: &str, members: &[(&str, &str)]) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::write(
+		tempdir.path().join("Cargo.toml"),
+		format!(
+			"[workspace]\nmembers = [\"*\"]\n\n[workspace.dependencies]\n{workspace_dependencies}"
+		),
+	)
+	.expect("The manifest should be writable; qed;");
+	for (member, dependencies) in members {
+		std::fs::create_dir_all(tempdir.path().join(member)).expect("This should be created; qed;");
+		std::fs::write(
+			tempdir.path().join(member).join("Cargo.toml"),
+			format!("[package]\nname = \"{member}\"\n\n{dependencies}"),
+		)
+		.expect("The manifest should be writable; qed;");
+	}
+	tempdir
+}
+
+fn features(default_features: bool, features: &[&str]) -> EffectiveFeatures {
+	EffectiveFeatures {
+		default_features,
+		features: features.iter().map(|feature| (*feature).to_owned()).collect(),
+	}
+}
+
+#[test]
+fn feature_unification_report_unifies_member_requests() {
+	let tempdir = workspace_with_members(
+		"serde = { version = \"1.0\", default-features = false }\nlocal = { path = \"member\" }",
+		&[
+			(
+				"a",
+				"[dependencies]\nserde = { workspace = true, features = [\"derive\"] }\nlocal.workspace = true\nrand = \"0.8\"\n\n[target.'cfg(unix)'.dependencies]\nrenamed = { package = \"tokio\", version = \"1\", features = [\"rt\"] }",
+			),
+			(
+				"b",
+				"[dependencies]\nserde = { workspace = true, features = [\"rc\"] }\ntokio = { version = \"1\", features = [\"macros\"] }\nsibling = { path = \"../a\" }\n\n[dev-dependencies]\nrand = { version = \"0.8\", features = [\"small_rng\"] }",
+			),
+		],
+	);
+
+	let report = feature_unification_report(tempdir.path().join("Cargo.toml"))
+		.expect("This should be Ok; qed;");
+
+	assert_eq!(
+		report,
+		vec![
+			UnifiedDependency {
+				name: "rand".to_owned(),
+				unified: features(true, &[]),
+				requested: BTreeMap::from([("a".to_owned(), features(true, &[]))]),
+				diverging: vec![],
+			},
+			UnifiedDependency {
+				name: "serde".to_owned(),
+				unified: features(false, &["derive", "rc"]),
+				requested: BTreeMap::from([
+					("a".to_owned(), features(false, &["derive"])),
+					("b".to_owned(), features(false, &["rc"])),
+				]),
+				diverging: vec!["a".to_owned(), "b".to_owned()],
+			},
+			UnifiedDependency {
+				name: "tokio".to_owned(),
+				unified: features(true, &["macros", "rt"]),
+				requested: BTreeMap::from([
+					("a".to_owned(), features(true, &["rt"])),
+					("b".to_owned(), features(true, &["macros"])),
+				]),
+				diverging: vec!["a".to_owned(), "b".to_owned()],
+			},
+		]
+	);
+}
+
+#[test]
+fn feature_unification_report_merges_entries_of_a_member() {
+	let tempdir = workspace_with_members(
+		"",
+		&[
+			(
+				"a",
+				"[dependencies]\nlibc = { version = \"0.2\", default-features = false }\n\n[target.'cfg(unix)'.dependencies]\nlibc = { version = \"0.2\", features = [\"extra_traits\"] }",
+			),
+			("b", "[dependencies]\nlibc = { version = \"0.2\", features = [\"extra_traits\"] }"),
+		],
+	);
+
+	let report = feature_unification_report(tempdir.path().join("Cargo.toml"))
+		.expect("This should be Ok; qed;");
+
+	assert_eq!(report.len(), 1);
+	assert_eq!(report[0].unified, features(true, &["extra_traits"]));
+	assert!(report[0].diverging.is_empty());
+}
+
+#[test]
+fn feature_unification_report_fails_if_inherited_dependency_isnt_declared() {
+	let tempdir = workspace_with_member("", "[dependencies]\ndep = { workspace = true }");
+
+	assert!(matches!(
+		feature_unification_report(tempdir.path().join("Cargo.toml")),
+		Err(Error::Descriptive(msg)) if msg == "member inherits dep from the workspace, but the workspace doesn't declare it"
+	));
+}
+
+fn manifest_with_dependencies(dependencies: &str, features: &str) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::write(
+		tempdir.path().join("Cargo.toml"),
+		format!(
+			"[package]\nname = \"test\"\n\n[dependencies]\n{dependencies}\n\n[features]\n{features}"
+		),
+	)
+	.expect("The manifest should be writable; qed;");
+	tempdir
+}
+
+#[test]
+fn feature_closure_follows_features_and_dependencies() {
+	let tempdir = manifest_with_dependencies(
+		"serde = { version = \"1.0\", optional = true }\ntokio = { version = \"1\", optional = true }\nlog = \"0.4\"",
+		"default = [\"std\"]\nstd = [\"log/std\", \"serde?/std\"]\nasync = [\"dep:tokio\", \"tokio?/rt\"]\nfull = [\"default\", \"async\", \"serde\"]",
+	);
+	let closure = |enabled: &[&str]| {
+		feature_closure(tempdir.path().join("Cargo.toml"), enabled)
+			.expect("This should be Ok; qed;")
+			.into_iter()
+			.collect::<Vec<_>>()
+	};
+
+	assert_eq!(closure(&[]), Vec::<String>::new());
+	assert_eq!(closure(&["default"]), vec!["default", "log/std", "std"]);
+	// tokio doesn't have an implicit feature, as it's referred to with `dep:`
+	assert_eq!(closure(&["async"]), vec!["async", "dep:tokio", "tokio/rt"]);
+	assert_eq!(
+		closure(&["full"]),
+		vec![
+			"async",
+			"default",
+			"dep:serde",
+			"dep:tokio",
+			"full",
+			"log/std",
+			"serde",
+			"serde/std",
+			"std",
+			"tokio/rt"
+		]
+	);
+	assert_eq!(closure(&["serde"]), vec!["dep:serde", "serde"]);
+}
+
+#[test]
+fn feature_closure_fails_if_feature_isnt_declared() {
+	let tempdir = manifest_with_dependencies(
+		"tokio = { version = \"1\", optional = true }",
+		"async = [\"dep:tokio\"]",
+	);
+
+	assert!(matches!(
+		feature_closure(tempdir.path().join("Cargo.toml"), &["tokio"]),
+		Err(Error::Descriptive(msg)) if msg == "The feature tokio isn't declared"
+	));
+}
+
+#[test]
+fn detect_feature_cycles_returns_every_cycle_once() {
+	let tempdir = manifest_with_features(
+		"default = [\"a\"]\na = [\"b\", \"dep:x\"]\nb = [\"c\"]\nc = [\"a\", \"x/std\"]\nd = [\"d\"]\ne = [\"a\"]\nf = []",
+	);
+
+	assert_eq!(
+		detect_feature_cycles(tempdir.path().join("Cargo.toml")).expect("This should be Ok; qed;"),
+		vec![vec!["a", "b", "c", "a"], vec!["d", "d"]]
+	);
+}
+
+#[test]
+fn detect_feature_cycles_works_without_cycles() {
+	let tempdir = manifest_with_features("default = [\"a\", \"b\"]\na = [\"b\"]\nb = []");
+	assert!(
+		detect_feature_cycles(tempdir.path().join("Cargo.toml"))
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+}
@@ -0,0 +1,449 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! This module provides the [`WorkspaceGraph`] type, an in-memory representation of the
+//! dependencies between the members of a workspace.
+
+#[cfg(test)]
+mod tests;
+
+use super::{DependencyKind, dependency_tables, workspace_members_of};
+use crate::{
+	Error,
+	events::{Event, EventSink, NoopSink},
+};
+use std::{
+	collections::BTreeSet,
+	path::{Component, Path, PathBuf},
+	rc::Rc,
+};
+use toml_edit::{DocumentMut, Item};
+
+/// How [`WorkspaceGraph::load_from`] gets the parsed member manifests.
+pub(super) type ReadManifest<'a> = dyn Fn(&Path) -> Result<Rc<DocumentMut>, Error> + 'a;
+
+/// A member of a workspace.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorkspaceMember {
+	/// The package name of the member.
+	pub name: String,
+	/// The path to the member manifest.
+	pub manifest_path: PathBuf,
+}
+
+/// The graph of dependencies between the members of a workspace.
+///
+/// Only the dependencies pointing to another member are part of the graph: a dependency points to a
+/// member if it's declared using a `path` resolving to the member dir, or if it's inherited from
+/// `workspace.dependencies` (`{ workspace = true }`) and the workspace entry does so.
+///
+/// With the `serde` feature, the graph is serialized as an object whose `members` key lists the
+/// members, each of them with its `name`, `manifest_path` and `dependencies`, the latter given as
+/// objects with the `name` of the dependency and its `kind`.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::{DependencyKind, WorkspaceGraph};
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// for (member, dependencies) in [
+///     ("core", ""),
+///     ("api", "[dependencies]\ncore = { path = \"../core\" }"),
+///     ("cli", "[dependencies]\napi = { workspace = true }\nserde = \"1.0\""),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{member}\"\n{dependencies}"),
+///     ).unwrap();
+/// }
+/// std::fs::write(
+///     tempdir.path().join("Cargo.toml"),
+///     r#"
+/// [workspace]
+/// members = ["core", "api", "cli"]
+///
+/// [workspace.dependencies]
+/// api = { path = "api" }
+/// "#,
+/// ).unwrap();
+///
+/// let graph = WorkspaceGraph::load(tempdir.path().join("Cargo.toml")).unwrap();
+/// assert_eq!(graph.dependencies_of("cli"), vec![("api", DependencyKind::Normal)]);
+/// assert_eq!(graph.dependents_of("core"), vec![("api", DependencyKind::Normal)]);
+/// assert_eq!(graph.transitive_dependents("core").into_iter().collect::<Vec<_>>(), vec!["api", "cli"]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceGraph {
+	members: Vec<WorkspaceMember>,
+	// dependencies[i] contains the dependencies of members[i] as (member index, kind) pairs.
+	dependencies: Vec<Vec<(usize, DependencyKind)>>,
+}
+
+impl WorkspaceGraph {
+	/// Builds the graph of the workspace defined by the given workspace manifest.
+	///
+	/// # Errors
+	///
+	/// - If the workspace members cannot be resolved.
+	/// - If some of the manifests cannot be read or parsed.
+	/// - If some member doesn't have a package name.
+	pub fn load<P: AsRef<Path>>(workspace_toml: P) -> Result<Self, Error> {
+		Self::load_with_events(workspace_toml, &NoopSink)
+	}
+
+	/// Same as [`WorkspaceGraph::load`], but the progress is reported to the given sink as the
+	/// `load_workspace_graph` operation, whose items are the names of the loaded members.
+	#[cfg_attr(
+		feature = "tracing",
+		tracing::instrument(level = "debug", skip_all, fields(workspace_toml = %workspace_toml.as_ref().display()))
+	)]
+	pub fn load_with_events<P: AsRef<Path>, E: EventSink>(
+		workspace_toml: P,
+		events: &E,
+	) -> Result<Self, Error> {
+		let workspace_toml = workspace_toml.as_ref();
+		let workspace_doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+		Self::load_from(
+			workspace_toml,
+			&workspace_doc,
+			&|manifest_path| {
+				Ok(Rc::new(std::fs::read_to_string(manifest_path)?.parse::<DocumentMut>()?))
+			},
+			events,
+		)
+	}
+
+	/// Builds the graph given the parsed workspace manifest and the way to get the member
+	/// manifests, so callers caching the manifests don't need to read them again.
+	pub(super) fn load_from<E: EventSink>(
+		workspace_toml: &Path,
+		workspace_doc: &DocumentMut,
+		read_manifest: &ReadManifest,
+		events: &E,
+	) -> Result<Self, Error> {
+		const OPERATION: &str = "load_workspace_graph";
+		let workspace_dir = workspace_toml.parent().expect("A file always lives inside a dir; qed");
+
+		let manifest_paths = workspace_members_of(workspace_doc, workspace_toml)?;
+		let member_dirs = manifest_paths
+			.iter()
+			.map(|manifest_path| {
+				normalize(manifest_path.parent().expect("A file always lives inside a dir; qed"))
+			})
+			.collect::<Vec<_>>();
+
+		let total = Some(manifest_paths.len());
+		events.emit(Event::Started { operation: OPERATION, total });
+		let mut members = Vec::with_capacity(manifest_paths.len());
+		let mut dependencies = Vec::with_capacity(manifest_paths.len());
+		for (index, manifest_path) in manifest_paths.into_iter().enumerate() {
+			let doc = read_manifest(&manifest_path)?;
+			let (member, member_dependencies) = load_member(
+				manifest_path,
+				&doc,
+				&member_dirs[index],
+				workspace_dir,
+				workspace_doc,
+				&member_dirs,
+			)?;
+			events.emit(Event::Progress {
+				operation: OPERATION,
+				current: index + 1,
+				total,
+				item: member.name.clone(),
+			});
+			members.push(member);
+			dependencies.push(member_dependencies);
+		}
+
+		events.emit(Event::Finished { operation: OPERATION });
+		Ok(Self { members, dependencies })
+	}
+
+	/// Same as [`WorkspaceGraph::load`], but the member manifests are parsed in parallel. The
+	/// resulting graph, or the error if several members are invalid, is the same.
+	#[cfg(feature = "rayon")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+	#[cfg_attr(
+		feature = "tracing",
+		tracing::instrument(level = "debug", skip_all, fields(workspace_toml = %workspace_toml.as_ref().display()))
+	)]
+	pub fn load_parallel<P: AsRef<Path>>(workspace_toml: P) -> Result<Self, Error> {
+		use rayon::prelude::*;
+
+		let workspace_toml = workspace_toml.as_ref();
+		let workspace_dir = workspace_toml.parent().expect("A file always lives inside a dir; qed");
+		let workspace_doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+		let manifest_paths = workspace_members_of(&workspace_doc, workspace_toml)?;
+		let member_dirs = manifest_paths
+			.iter()
+			.map(|manifest_path| {
+				normalize(manifest_path.parent().expect("A file always lives inside a dir; qed"))
+			})
+			.collect::<Vec<_>>();
+
+		// Collecting an indexed parallel iterator keeps the order of the members, so the first
+		// error is the same returned by `load`
+		let (members, dependencies) = manifest_paths
+			.into_par_iter()
+			.zip(&member_dirs)
+			.map(|(manifest_path, member_dir)| {
+				let doc = std::fs::read_to_string(&manifest_path)?.parse::<DocumentMut>()?;
+				load_member(
+					manifest_path,
+					&doc,
+					member_dir,
+					workspace_dir,
+					&workspace_doc,
+					&member_dirs,
+				)
+			})
+			.collect::<Vec<_>>()
+			.into_iter()
+			.collect::<Result<Vec<_>, _>>()?
+			.into_iter()
+			.unzip();
+		Ok(Self { members, dependencies })
+	}
+
+	/// The workspace members, sorted by manifest path.
+	pub fn members(&self) -> &[WorkspaceMember] {
+		&self.members
+	}
+
+	/// Finds a member by its package name.
+	pub fn member(&self, name: &str) -> Option<&WorkspaceMember> {
+		self.members.iter().find(|member| member.name == name)
+	}
+
+	/// The members the given member directly depends on, together with the dependency kind, in
+	/// the order they're declared in its manifest. If the member doesn't exist, the output is
+	/// empty.
+	pub fn dependencies_of(&self, name: &str) -> Vec<(&str, DependencyKind)> {
+		self.index_of(name)
+			.map(|index| {
+				self.dependencies[index]
+					.iter()
+					.map(|(dependency, kind)| (self.members[*dependency].name.as_str(), *kind))
+					.collect()
+			})
+			.unwrap_or_default()
+	}
+
+	/// The members directly depending on the given member, together with the dependency kind,
+	/// sorted as [`WorkspaceGraph::members`]. If the member doesn't exist, the output is empty.
+	pub fn dependents_of(&self, name: &str) -> Vec<(&str, DependencyKind)> {
+		let Some(index) = self.index_of(name) else {
+			return Vec::new();
+		};
+		self.dependencies
+			.iter()
+			.enumerate()
+			.flat_map(|(dependent, dependencies)| {
+				dependencies
+					.iter()
+					.filter(move |(dependency, _)| *dependency == index)
+					.map(move |(_, kind)| (self.members[dependent].name.as_str(), *kind))
+			})
+			.collect()
+	}
+
+	/// The members depending, directly or transitively, on the given member. The member itself
+	/// isn't included unless it's part of a dependency cycle.
+	pub fn transitive_dependents(&self, name: &str) -> BTreeSet<&str> {
+		let mut dependents = BTreeSet::new();
+		let mut pending = vec![name];
+		while let Some(current) = pending.pop() {
+			for (dependent, _) in self.dependents_of(current) {
+				if dependents.insert(dependent) {
+					pending.push(dependent);
+				}
+			}
+		}
+		dependents
+	}
+
+	/// Sorts the members so that every member comes after all the members it depends on, which is
+	/// the order in which they have to be published. Dev dependencies are ignored, as they don't
+	/// constrain the publishing order. Among the members whose dependencies are already sorted, the
+	/// alphabetical order is used, so the output is deterministic.
+	///
+	/// # Errors
+	///
+	/// - If the dependencies contain a cycle. The error message contains the cycle path.
+	pub fn publish_order(&self) -> Result<Vec<&str>, Error> {
+		let dependencies = |index: usize| {
+			self.dependencies[index]
+				.iter()
+				.filter(|(_, kind)| *kind != DependencyKind::Dev)
+				.map(|(dependency, _)| *dependency)
+		};
+
+		let mut pending_dependencies = (0..self.members.len())
+			.map(|index| dependencies(index).count())
+			.collect::<Vec<_>>();
+		let mut ready = (0..self.members.len())
+			.filter(|index| pending_dependencies[*index] == 0)
+			.map(|index| (self.members[index].name.as_str(), index))
+			.collect::<BTreeSet<_>>();
+
+		let mut order = Vec::with_capacity(self.members.len());
+		while let Some((name, index)) = ready.pop_first() {
+			order.push(name);
+			for (dependent, pending) in pending_dependencies.iter_mut().enumerate() {
+				for _ in dependencies(dependent).filter(|dependency| *dependency == index) {
+					*pending -= 1;
+					if *pending == 0 {
+						ready.insert((self.members[dependent].name.as_str(), dependent));
+					}
+				}
+			}
+		}
+
+		if order.len() == self.members.len() {
+			return Ok(order);
+		}
+
+		// Some members couldn't be sorted, so there's a cycle among them. Walk the dependencies
+		// between them until a member is visited twice.
+		let mut path = vec![
+			pending_dependencies
+				.iter()
+				.position(|pending| *pending > 0)
+				.expect("Some member wasn't sorted; qed;"),
+		];
+		loop {
+			let current = *path.last().expect("path is never empty; qed;");
+			let next = dependencies(current)
+				.find(|dependency| pending_dependencies[*dependency] > 0)
+				.expect("An unsorted member always depends on another unsorted member; qed;");
+			if let Some(start) = path.iter().position(|index| *index == next) {
+				let cycle = path[start..]
+					.iter()
+					.chain(std::iter::once(&next))
+					.map(|index| self.members[*index].name.as_str())
+					.collect::<Vec<_>>();
+				return Err(Error::Descriptive(format!(
+					"Dependency cycle detected: {}",
+					cycle.join(" -> ")
+				)));
+			}
+			path.push(next);
+		}
+	}
+
+	fn index_of(&self, name: &str) -> Option<usize> {
+		self.members.iter().position(|member| member.name == name)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for WorkspaceGraph {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		#[derive(serde::Serialize)]
+		struct Graph<'a> {
+			members: Vec<Member<'a>>,
+		}
+		#[derive(serde::Serialize)]
+		struct Member<'a> {
+			name: &'a str,
+			manifest_path: &'a Path,
+			dependencies: Vec<Dependency<'a>>,
+		}
+		#[derive(serde::Serialize)]
+		struct Dependency<'a> {
+			name: &'a str,
+			kind: DependencyKind,
+		}
+
+		Graph {
+			members: self
+				.members
+				.iter()
+				.map(|member| Member {
+					name: &member.name,
+					manifest_path: &member.manifest_path,
+					dependencies: self
+						.dependencies_of(&member.name)
+						.into_iter()
+						.map(|(name, kind)| Dependency { name, kind })
+						.collect(),
+				})
+				.collect(),
+		}
+		.serialize(serializer)
+	}
+}
+
+/// Loads a member of the workspace whose manifest is `workspace_doc`, returning it along with its
+/// dependencies on the other members, given as (member index, kind) pairs.
+fn load_member(
+	manifest_path: PathBuf,
+	doc: &DocumentMut,
+	member_dir: &Path,
+	workspace_dir: &Path,
+	workspace_doc: &DocumentMut,
+	member_dirs: &[PathBuf],
+) -> Result<(WorkspaceMember, Vec<(usize, DependencyKind)>), Error> {
+	let workspace_dependencies = workspace_doc
+		.get("workspace")
+		.and_then(|workspace| workspace.get("dependencies"))
+		.and_then(Item::as_table_like);
+	let name = doc
+		.get("package")
+		.and_then(|package| package.get("name"))
+		.and_then(Item::as_str)
+		.ok_or_else(|| {
+			Error::Descriptive(format!(
+				"The member manifest {} doesn't have a package name",
+				manifest_path.display()
+			))
+		})?
+		.to_owned();
+
+	let mut member_dependencies = Vec::new();
+	for (kind, table) in dependency_tables(doc) {
+		for (key, dependency) in table.iter() {
+			let local_path = if dependency.get("workspace").and_then(Item::as_bool).unwrap_or(false)
+			{
+				workspace_dependencies
+					.and_then(|dependencies| dependencies.get(key))
+					.and_then(|dependency| dependency.get("path"))
+					.and_then(Item::as_str)
+					.map(|path| workspace_dir.join(path))
+			} else {
+				dependency.get("path").and_then(Item::as_str).map(|path| member_dir.join(path))
+			};
+
+			if let Some(index) = local_path.and_then(|local_path| {
+				let local_path = normalize(&local_path);
+				member_dirs.iter().position(|member_dir| *member_dir == local_path)
+			}) && !member_dependencies.contains(&(index, kind))
+			{
+				member_dependencies.push((index, kind));
+			}
+		}
+	}
+
+	Ok((WorkspaceMember { name, manifest_path }, member_dependencies))
+}
+
+/// Lexically normalizes a path, resolving `.` and `..` components without touching the
+/// filesystem.
+pub(super) fn normalize(path: &Path) -> PathBuf {
+	let mut normalized = PathBuf::new();
+	for component in path.components() {
+		match component {
+			Component::CurDir => (),
+			Component::ParentDir
+				if matches!(normalized.components().next_back(), Some(Component::Normal(_))) =>
+			{
+				normalized.pop();
+			},
+			component => normalized.push(component),
+		}
+	}
+	normalized
+}
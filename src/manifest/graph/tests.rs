@@ -0,0 +1,306 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+fn workspace(workspace_manifest: &str, members: &[(&str, &str)]) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::write(tempdir.path().join("Cargo.toml"), workspace_manifest)
+		.expect("The manifest should be writable; qed;");
+	for (member, manifest) in members {
+		let member_path = tempdir.path().join(member);
+		std::fs::create_dir_all(&member_path).expect("This should be created; qed;");
+		std::fs::write(member_path.join("Cargo.toml"), manifest)
+			.expect("The manifest should be writable; qed;");
+	}
+	tempdir
+}
+
+#[test]
+fn load_finds_path_and_workspace_dependencies() {
+	let tempdir = workspace(
+		r#"
+[workspace]
+members = ["crates/*"]
+
+[workspace.dependencies]
+b = { path = "crates/b" }
+serde = "1.0"
+"#,
+		&[
+			("crates/a", "[package]\nname = \"a\""),
+			(
+				"crates/b",
+				"[package]\nname = \"b\"\n[dependencies]\na = { path = \"../a\" }\nserde = { workspace = true }",
+			),
+			(
+				"crates/c",
+				r#"
+[package]
+name = "c"
+
+[dependencies]
+b = { workspace = true }
+renamed = { package = "a", path = "./../a/" }
+
+[dev-dependencies]
+b = { workspace = true }
+
+[target.'cfg(unix)'.build-dependencies]
+a = { path = "../a", version = "0.1.0" }
+"#,
+			),
+		],
+	);
+
+	let graph = WorkspaceGraph::load(tempdir.path().join("Cargo.toml"))
+		.expect("The graph should be loaded; qed;");
+
+	assert_eq!(
+		graph.members(),
+		&[
+			WorkspaceMember {
+				name: "a".to_owned(),
+				manifest_path: tempdir.path().join("crates/a/Cargo.toml")
+			},
+			WorkspaceMember {
+				name: "b".to_owned(),
+				manifest_path: tempdir.path().join("crates/b/Cargo.toml")
+			},
+			WorkspaceMember {
+				name: "c".to_owned(),
+				manifest_path: tempdir.path().join("crates/c/Cargo.toml")
+			},
+		]
+	);
+	assert!(graph.dependencies_of("a").is_empty());
+	assert_eq!(graph.dependencies_of("b"), vec![("a", DependencyKind::Normal)]);
+	assert_eq!(
+		graph.dependencies_of("c"),
+		vec![
+			("b", DependencyKind::Normal),
+			("a", DependencyKind::Normal),
+			("b", DependencyKind::Dev),
+			("a", DependencyKind::Build)
+		]
+	);
+	assert_eq!(
+		graph.dependents_of("a"),
+		vec![
+			("b", DependencyKind::Normal),
+			("c", DependencyKind::Normal),
+			("c", DependencyKind::Build)
+		]
+	);
+	assert!(graph.dependents_of("c").is_empty());
+	assert_eq!(graph.member("b"), Some(&graph.members()[1]));
+	assert_eq!(graph.member("d"), None);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn load_parallel_matches_load() {
+	let members = (0..20)
+		.map(|index| {
+			(
+				format!("crates/m{index:02}"),
+				format!(
+					"[package]\nname = \"m{index:02}\"\n[dependencies]\nprevious = {{ package = \"m{:02}\", path = \"../m{:02}\" }}",
+					index.max(1) - 1,
+					index.max(1) - 1
+				),
+			)
+		})
+		.collect::<Vec<_>>();
+	let tempdir = workspace(
+		"[workspace]\nmembers = [\"crates/*\"]",
+		&members
+			.iter()
+			.map(|(dir, manifest)| (dir.as_str(), manifest.as_str()))
+			.collect::<Vec<_>>(),
+	);
+	let workspace_toml = tempdir.path().join("Cargo.toml");
+
+	assert_eq!(
+		WorkspaceGraph::load_parallel(&workspace_toml).expect("The graph should be loaded; qed;"),
+		WorkspaceGraph::load(&workspace_toml).expect("The graph should be loaded; qed;")
+	);
+
+	std::fs::write(tempdir.path().join("crates/m05/Cargo.toml"), "[package]")
+		.expect("The manifest should be writable; qed;");
+	std::fs::write(tempdir.path().join("crates/m15/Cargo.toml"), "[package")
+		.expect("The manifest should be writable; qed;");
+	assert!(matches!(
+		WorkspaceGraph::load_parallel(&workspace_toml),
+		Err(Error::Descriptive(msg)) if msg.ends_with("doesn't have a package name")
+	));
+}
+
+#[test]
+fn load_ignores_dependencies_not_pointing_to_members() {
+	let tempdir = workspace(
+		"[workspace]\nmembers = [\"a\"]",
+		&[(
+			"a",
+			"[package]\nname = \"a\"\n[dependencies]\noutside = { path = \"../outside\" }\nb = { workspace = true }",
+		)],
+	);
+
+	let graph = WorkspaceGraph::load(tempdir.path().join("Cargo.toml"))
+		.expect("The graph should be loaded; qed;");
+	assert!(graph.dependencies_of("a").is_empty());
+}
+
+#[test]
+fn transitive_dependents_works() {
+	let tempdir = workspace(
+		"[workspace]\nmembers = [\"a\", \"b\", \"c\", \"d\"]",
+		&[
+			("a", "[package]\nname = \"a\""),
+			("b", "[package]\nname = \"b\"\n[dependencies]\na = { path = \"../a\" }"),
+			("c", "[package]\nname = \"c\"\n[dev-dependencies]\nb = { path = \"../b\" }"),
+			("d", "[package]\nname = \"d\""),
+		],
+	);
+
+	let graph = WorkspaceGraph::load(tempdir.path().join("Cargo.toml"))
+		.expect("The graph should be loaded; qed;");
+	assert_eq!(graph.transitive_dependents("a"), BTreeSet::from(["b", "c"]));
+	assert_eq!(graph.transitive_dependents("b"), BTreeSet::from(["c"]));
+	assert!(graph.transitive_dependents("d").is_empty());
+	assert!(graph.transitive_dependents("unknown").is_empty());
+}
+
+#[test]
+fn transitive_dependents_terminates_with_cycles() {
+	let tempdir = workspace(
+		"[workspace]\nmembers = [\"a\", \"b\"]",
+		&[
+			("a", "[package]\nname = \"a\"\n[dev-dependencies]\nb = { path = \"../b\" }"),
+			("b", "[package]\nname = \"b\"\n[dependencies]\na = { path = \"../a\" }"),
+		],
+	);
+
+	let graph = WorkspaceGraph::load(tempdir.path().join("Cargo.toml"))
+		.expect("The graph should be loaded; qed;");
+	assert_eq!(graph.transitive_dependents("a"), BTreeSet::from(["a", "b"]));
+}
+
+#[test]
+fn load_fails_if_member_doesnt_have_a_name() {
+	let tempdir =
+		workspace("[workspace]\nmembers = [\"a\"]", &[("a", "[package]\nversion = \"0.1.0\"")]);
+
+	assert!(matches!(
+		WorkspaceGraph::load(tempdir.path().join("Cargo.toml")),
+		Err(Error::Descriptive(msg)) if msg.ends_with("doesn't have a package name")
+	));
+}
+
+#[test]
+fn normalize_works() {
+	assert_eq!(normalize(Path::new("/a/b/../c/./d")), Path::new("/a/c/d"));
+	assert_eq!(normalize(Path::new("a/./b/")), Path::new("a/b"));
+	assert_eq!(normalize(Path::new("../a/../../b")), Path::new("../../b"));
+}
+
+#[test]
+fn graph_outputs_dont_depend_on_the_declaration_order() {
+	let members = [
+		("z", "[package]\nname = \"z\""),
+		("m", "[package]\nname = \"m\"\n[dependencies]\nz = { path = \"../z\" }"),
+		("a", "[package]\nname = \"a\"\n[dependencies]\nz = { path = \"../z\" }"),
+	];
+	let forward = workspace("[workspace]\nmembers = [\"a\", \"m\", \"z\"]", &members);
+	let backward = workspace("[workspace]\nmembers = [\"z\", \"m\", \"a\"]", &members);
+
+	for tempdir in [forward, backward] {
+		let graph = WorkspaceGraph::load(tempdir.path().join("Cargo.toml"))
+			.expect("The graph should be loaded; qed;");
+		assert_eq!(
+			graph.members().iter().map(|member| member.name.as_str()).collect::<Vec<_>>(),
+			vec!["a", "m", "z"]
+		);
+		assert_eq!(
+			graph.dependents_of("z"),
+			vec![("a", DependencyKind::Normal), ("m", DependencyKind::Normal)]
+		);
+	}
+}
+
+#[test]
+fn publish_order_sorts_members_after_their_dependencies() {
+	let tempdir = workspace(
+		"[workspace]\nmembers = [\"*\"]",
+		&[
+			(
+				"a",
+				"[package]\nname = \"a\"\n[dependencies]\nc = { path = \"../c\" }\nd = { path = \"../d\" }",
+			),
+			("b", "[package]\nname = \"b\"\n[build-dependencies]\nd = { path = \"../d\" }"),
+			("c", "[package]\nname = \"c\"\n[dependencies]\nd = { path = \"../d\" }"),
+			("d", "[package]\nname = \"d\"\n[dev-dependencies]\na = { path = \"../a\" }"),
+			("e", "[package]\nname = \"e\""),
+		],
+	);
+
+	let graph = WorkspaceGraph::load(tempdir.path().join("Cargo.toml"))
+		.expect("The graph should be loaded; qed;");
+	assert_eq!(
+		graph.publish_order().expect("There's no cycle; qed;"),
+		vec!["d", "b", "c", "a", "e"]
+	);
+}
+
+#[test]
+fn publish_order_fails_if_there_is_a_cycle() {
+	let tempdir = workspace(
+		"[workspace]\nmembers = [\"*\"]",
+		&[
+			("a", "[package]\nname = \"a\"\n[dependencies]\nb = { path = \"../b\" }"),
+			("b", "[package]\nname = \"b\"\n[build-dependencies]\nc = { path = \"../c\" }"),
+			("c", "[package]\nname = \"c\"\n[dependencies]\nb = { path = \"../b\" }"),
+		],
+	);
+
+	let graph = WorkspaceGraph::load(tempdir.path().join("Cargo.toml"))
+		.expect("The graph should be loaded; qed;");
+	assert!(matches!(
+		graph.publish_order(),
+		Err(Error::Descriptive(msg)) if msg == "Dependency cycle detected: b -> c -> b"
+	));
+}
+
+#[test]
+fn load_with_events_reports_every_member() {
+	let tempdir = workspace(
+		"[workspace]\nmembers = [\"a\", \"b\"]",
+		&[("a", "[package]\nname = \"first\""), ("b", "[package]\nname = \"second\"")],
+	);
+	let events = std::cell::RefCell::new(Vec::new());
+
+	WorkspaceGraph::load_with_events(tempdir.path().join("Cargo.toml"), &|event| {
+		events.borrow_mut().push(event)
+	})
+	.expect("The graph should be loaded; qed;");
+
+	assert_eq!(
+		events.into_inner(),
+		vec![
+			Event::Started { operation: "load_workspace_graph", total: Some(2) },
+			Event::Progress {
+				operation: "load_workspace_graph",
+				current: 1,
+				total: Some(2),
+				item: "first".to_owned()
+			},
+			Event::Progress {
+				operation: "load_workspace_graph",
+				current: 2,
+				total: Some(2),
+				item: "second".to_owned()
+			},
+			Event::Finished { operation: "load_workspace_graph" },
+		]
+	);
+}
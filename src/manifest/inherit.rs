@@ -0,0 +1,321 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Migration of the dependencies declared by the workspace members to `workspace.dependencies`, so
+// old workspaces adopt the dependency inheritance model.
+
+#[cfg(test)]
+mod tests;
+
+use super::{
+	EditFn, dependency_tables, dependency_tables_mut, edit_many, find_workspace_members,
+	get_or_insert_table,
+};
+use crate::{Error, macros::debug};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	path::Path,
+};
+use toml_edit::{Array, DocumentMut, InlineTable, Item, TableLike, Value};
+
+/// The keys describing where a dependency comes from. They must match in every declaration of a
+/// dependency to hoist it.
+const SOURCE_KEYS: [&str; 6] = ["package", "registry", "git", "branch", "tag", "rev"];
+
+/// The keys moved from the members declarations to the workspace, besides the source keys.
+const HOISTED_KEYS: [&str; 3] = ["version", "default-features", "features"];
+
+/// The unified declaration of a dependency, built from its declarations in the members.
+#[derive(Debug, Default)]
+struct Unified {
+	source: Vec<(&'static str, String)>,
+	/// The newest version requirement, together with its lower bound.
+	version: Option<((u64, u64, u64), String)>,
+	features: BTreeSet<String>,
+	/// Whether every declaration disables the default features.
+	no_default_features: bool,
+	/// Whether the declarations cannot be unified.
+	conflicting: bool,
+	/// The number of declarations unified.
+	declarations: usize,
+}
+
+impl Unified {
+	fn add(&mut self, declaration: &dyn TableLike) {
+		let source = SOURCE_KEYS
+			.iter()
+			.filter_map(|key| Some((*key, declaration.get(key)?.as_str()?.to_owned())))
+			.collect::<Vec<_>>();
+		let no_default_features = declaration
+			.get("default-features")
+			.or_else(|| declaration.get("default_features"))
+			.and_then(Item::as_bool) ==
+			Some(false);
+		if self.declarations == 0 {
+			self.source = source;
+			self.no_default_features = no_default_features;
+		} else {
+			self.conflicting |= self.source != source;
+			self.no_default_features &= no_default_features;
+		}
+
+		match declaration.get("version").and_then(Item::as_str) {
+			Some(requirement) => match lower_bound(requirement) {
+				Some(bound) if self.version.as_ref().is_none_or(|(newest, _)| bound > *newest) =>
+					self.version = Some((bound, requirement.trim().to_owned())),
+				Some(_) => (),
+				None => self.conflicting = true,
+			},
+			None => self.conflicting |= !self.source.iter().any(|(key, _)| *key == "git"),
+		}
+
+		self.features.extend(
+			declaration
+				.get("features")
+				.and_then(Item::as_array)
+				.into_iter()
+				.flatten()
+				.filter_map(|feature| feature.as_str().map(str::to_owned)),
+		);
+		self.declarations += 1;
+	}
+
+	/// The declaration written to `workspace.dependencies`.
+	fn to_item(&self) -> Item {
+		let mut declaration = InlineTable::new();
+		if let Some((_, requirement)) = &self.version {
+			declaration.insert("version", requirement.as_str().into());
+		}
+		for (key, value) in &self.source {
+			declaration.insert(*key, value.as_str().into());
+		}
+		if self.no_default_features {
+			declaration.insert("default-features", false.into());
+		}
+		if !self.features.is_empty() {
+			declaration.insert("features", Value::Array(self.features.iter().collect::<Array>()));
+		}
+
+		match (declaration.len(), &self.version) {
+			(1, Some((_, requirement))) => toml_edit::value(requirement.as_str()),
+			_ => toml_edit::value(declaration),
+		}
+	}
+}
+
+/// Given a workspace manifest file path, this function moves the external dependencies declared by
+/// the workspace members to the `workspace.dependencies` section, and rewrites the members
+/// declarations to inherit them (`serde = { workspace = true }`), automating the migration of old
+/// workspaces to the dependency inheritance model.
+///
+/// The declarations of a dependency in every dependency section of the members, including the
+/// platform specific ones, are unified into a single declaration: the newest version requirement
+/// wins, the features are the union of the declared ones, and the default features are only
+/// disabled if every declaration disables them. Members keep the keys that cannot be inherited,
+/// such as `optional`.
+///
+/// The following dependencies are left untouched:
+/// - Dependencies on workspace members, and any other dependency declared with a `path`.
+/// - Dependencies already declared in `workspace.dependencies`, or already inherited by some
+///   member.
+/// - Dependencies whose declarations cannot be unified: declared from different sources (eg, a
+///   registry and a git repository, or different git branches), renamed to different packages, or
+///   with a version requirement that isn't valid.
+///
+/// Every manifest is written at once with [`edit_many`](super::edit_many), so nothing is written if
+/// something fails. Returns the names of the moved dependencies, sorted.
+///
+/// # Errors
+///
+/// - If the workspace members cannot be resolved.
+/// - If some of the manifests cannot be read, parsed or written.
+/// - If the `workspace.dependencies` section isn't a table.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let workspace_manifest_path = tempdir.path().join("Cargo.toml");
+/// for (member, dependencies) in [
+///     ("a", "serde = { version = \"1.0.100\", features = [\"derive\"] }\nb = { path = \"../b\" }"),
+///     ("b", "serde = { version = \"1.0.200\", features = [\"rc\"], optional = true }"),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{member}\"\n\n[dependencies]\n{dependencies}\n"),
+///     ).unwrap();
+/// }
+/// std::fs::write(&workspace_manifest_path, "[workspace]\nmembers = [\"a\", \"b\"]\n").unwrap();
+///
+/// assert_eq!(
+///     rustilities::manifest::generate_workspace_dependencies(&workspace_manifest_path).unwrap(),
+///     vec!["serde"]
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(&workspace_manifest_path).unwrap(),
+///     r#"[workspace]
+/// members = ["a", "b"]
+///
+/// [workspace.dependencies]
+/// serde = { version = "1.0.200", features = ["derive", "rc"] }
+/// "#
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(tempdir.path().join("b").join("Cargo.toml")).unwrap(),
+///     "[package]\nname = \"b\"\n\n[dependencies]\nserde = { workspace = true, optional = true }\n"
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip_all, fields(workspace_toml = %workspace_toml.as_ref().display()))
+)]
+pub fn generate_workspace_dependencies<P: AsRef<Path>>(
+	workspace_toml: P,
+) -> Result<Vec<String>, Error> {
+	fn do_generate_workspace_dependencies(workspace_toml: &Path) -> Result<Vec<String>, Error> {
+		let workspace_doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+		let members = find_workspace_members(workspace_toml)?;
+		let member_docs = members
+			.iter()
+			.map(|member| Ok(std::fs::read_to_string(member)?.parse::<DocumentMut>()?))
+			.collect::<Result<Vec<_>, Error>>()?;
+
+		let mut skipped = member_docs
+			.iter()
+			.filter_map(|doc| doc.get("package")?.get("name")?.as_str().map(str::to_owned))
+			.collect::<BTreeSet<_>>();
+		if let Some(dependencies) = workspace_doc
+			.get("workspace")
+			.and_then(|workspace| workspace.get("dependencies"))
+			.and_then(Item::as_table_like)
+		{
+			skipped.extend(dependencies.iter().map(|(key, _)| key.to_owned()));
+		}
+
+		let mut unified: BTreeMap<String, Unified> = BTreeMap::new();
+		for doc in &member_docs {
+			for (_, table) in dependency_tables(doc) {
+				for (key, dependency) in table.iter() {
+					let Some(declaration) = declaration_of(dependency) else { continue };
+					let package = declaration.get("package").and_then(Value::as_str).unwrap_or(key);
+					if declaration.contains_key("path") ||
+						declaration.contains_key("workspace") ||
+						skipped.contains(package)
+					{
+						skipped.insert(key.to_owned());
+					} else {
+						unified.entry(key.to_owned()).or_default().add(&declaration);
+					}
+				}
+			}
+		}
+		unified.retain(|key, unified| {
+			if unified.conflicting {
+				debug!(dependency = key, "The declarations cannot be unified");
+			}
+			!unified.conflicting && !skipped.contains(key)
+		});
+		if unified.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let hoisted = unified.keys().cloned().collect::<BTreeSet<_>>();
+		let declarations = unified
+			.iter()
+			.map(|(key, unified)| (key.clone(), unified.to_item()))
+			.collect::<Vec<_>>();
+		let mut edits: Vec<(std::path::PathBuf, EditFn)> = vec![(
+			workspace_toml.to_path_buf(),
+			Box::new(move |doc| {
+				let workspace = get_or_insert_table(doc.as_table_mut(), "workspace")?;
+				let dependencies = get_or_insert_table(workspace, "dependencies")?;
+				for (key, declaration) in declarations {
+					dependencies.insert(&key, declaration);
+				}
+				Ok(())
+			}),
+		)];
+		for member in members {
+			let hoisted = hoisted.clone();
+			edits.push((
+				member,
+				Box::new(move |doc| {
+					inherit_dependencies(doc, &hoisted);
+					Ok(())
+				}),
+			));
+		}
+		edit_many(edits)?;
+
+		Ok(hoisted.into_iter().collect())
+	}
+	do_generate_workspace_dependencies(workspace_toml.as_ref())
+}
+
+/// The keys of a dependency declaration as an inline table, turning a bare version into its
+/// `version` key.
+fn declaration_of(dependency: &Item) -> Option<InlineTable> {
+	match dependency {
+		Item::Value(version @ Value::String(_)) =>
+			Some(std::iter::once(("version", version.clone())).collect()),
+		_ => Some(
+			dependency
+				.as_table_like()?
+				.iter()
+				.filter_map(|(key, item)| Some((key, item.as_value()?.clone())))
+				.collect(),
+		),
+	}
+}
+
+/// Rewrites the declarations of the given dependencies to inherit them from the workspace.
+fn inherit_dependencies(doc: &mut DocumentMut, hoisted: &BTreeSet<String>) {
+	for (_, table) in dependency_tables_mut(doc) {
+		for (key, dependency) in table.iter_mut() {
+			if !hoisted.contains(key.get()) {
+				continue;
+			}
+			if let Some(declaration) = dependency.as_table_mut() {
+				for removed in SOURCE_KEYS.iter().chain(&HOISTED_KEYS).chain(&["default_features"])
+				{
+					declaration.remove(removed);
+				}
+				declaration.insert("workspace", toml_edit::value(true));
+				continue;
+			}
+
+			let mut declaration = InlineTable::new();
+			declaration.insert("workspace", true.into());
+			if let Some(kept) = dependency.as_inline_table() {
+				for (key, value) in kept.iter() {
+					if !SOURCE_KEYS.contains(&key) &&
+						!HOISTED_KEYS.contains(&key) &&
+						key != "default_features"
+					{
+						declaration.insert(key, value.clone());
+					}
+				}
+			}
+			let Some(value) = dependency.as_value_mut() else { continue };
+			let decor = value.decor().clone();
+			*value = Value::InlineTable(declaration);
+			*value.decor_mut() = decor;
+		}
+	}
+}
+
+/// The lower bound of a version requirement, used to find the newest one.
+fn lower_bound(requirement: &str) -> Option<(u64, u64, u64)> {
+	let requirement = semver::VersionReq::parse(requirement).ok()?;
+	Some(
+		requirement
+			.comparators
+			.iter()
+			.filter(|comparator| !matches!(comparator.op, semver::Op::Less | semver::Op::LessEq))
+			.map(|comparator| {
+				(comparator.major, comparator.minor.unwrap_or(0), comparator.patch.unwrap_or(0))
+			})
+			.max()
+			.unwrap_or_default(),
+	)
+}
@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use crate::test_utils::workspace;
+use tempfile::TempDir;
+
+fn read(tempdir: &TempDir, path: &str) -> String {
+	std::fs::read_to_string(tempdir.path().join(path))
+		.expect("The manifest should be readable; qed;")
+}
+
+#[test]
+fn generate_workspace_dependencies_unifies_the_declarations_of_the_members() {
+	let tempdir = workspace(
+		"[workspace]\nmembers = [\"a\", \"b\"]\n",
+		&[
+			(
+				"a",
+				r#"[package]
+name = "a"
+
+[dependencies]
+serde = "1.0.100"
+tokio = { version = "1", default-features = false, features = ["rt"] } # Runtime
+b = { path = "../b", version = "0.1" }
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[dev-dependencies.tool]
+git = "https://github.com/org/tool"
+tag = "v1"
+optional = true
+"#,
+			),
+			(
+				"b",
+				r#"[package]
+name = "b"
+
+[dependencies]
+serde = { version = "~1.0.200", features = ["derive"] }
+tokio = { version = ">=1.30, <2", default_features = false, features = ["macros", "rt"] }
+
+[build-dependencies]
+cc = { version = "1", default-features = false }
+"#,
+			),
+		],
+	);
+
+	assert_eq!(
+		generate_workspace_dependencies(tempdir.path().join("Cargo.toml"))
+			.expect("This should be Ok; qed;"),
+		vec!["cc", "libc", "serde", "tokio", "tool"]
+	);
+	assert_eq!(
+		read(&tempdir, "Cargo.toml"),
+		r#"[workspace]
+members = ["a", "b"]
+
+[workspace.dependencies]
+cc = { version = "1", default-features = false }
+libc = "0.2"
+serde = { version = "~1.0.200", features = ["derive"] }
+tokio = { version = ">=1.30, <2", default-features = false, features = ["macros", "rt"] }
+tool = { git = "https://github.com/org/tool", tag = "v1" }
+"#
+	);
+	assert_eq!(
+		read(&tempdir, "a/Cargo.toml"),
+		r#"[package]
+name = "a"
+
+[dependencies]
+serde = { workspace = true }
+tokio = { workspace = true } # Runtime
+b = { path = "../b", version = "0.1" }
+
+[target.'cfg(unix)'.dependencies]
+libc = { workspace = true }
+
+[dev-dependencies.tool]
+optional = true
+workspace = true
+"#
+	);
+	assert_eq!(
+		read(&tempdir, "b/Cargo.toml"),
+		r#"[package]
+name = "b"
+
+[dependencies]
+serde = { workspace = true }
+tokio = { workspace = true }
+
+[build-dependencies]
+cc = { workspace = true }
+"#
+	);
+}
+
+#[test]
+fn generate_workspace_dependencies_skips_the_dependencies_that_cannot_be_moved() {
+	let workspace_manifest = r#"[package]
+name = "root"
+
+[dependencies]
+member = "0.1"
+syn = { git = "https://github.com/dtolnay/syn", branch = "master" }
+
+[workspace]
+members = ["member"]
+
+[workspace.dependencies]
+serde = "1.0"
+"#;
+	let member_manifest = r#"[package]
+name = "member"
+
+[dependencies]
+serde = "1.0.200"
+syn = "2.0"
+quote = { workspace = true }
+rand = { version = "not a version" }
+"#;
+	let other_member_manifest = "[package]\nname = \"other\"\n\n[dependencies]\nquote = \"1.0\"\n";
+	let tempdir = workspace(
+		workspace_manifest,
+		&[("member", member_manifest), ("other", other_member_manifest)],
+	);
+
+	assert!(
+		generate_workspace_dependencies(tempdir.path().join("Cargo.toml"))
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+	assert_eq!(read(&tempdir, "Cargo.toml"), workspace_manifest);
+	assert_eq!(read(&tempdir, "member/Cargo.toml"), member_manifest);
+}
+
+#[test]
+fn lower_bound_works() {
+	assert_eq!(lower_bound("1.2"), Some((1, 2, 0)));
+	assert_eq!(lower_bound(">=1.2.3, <2"), Some((1, 2, 3)));
+	assert_eq!(lower_bound("*"), Some((0, 0, 0)));
+	assert_eq!(lower_bound("<2"), Some((0, 0, 0)));
+	assert_eq!(lower_bound("latest"), None);
+}
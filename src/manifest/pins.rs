@@ -0,0 +1,284 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Audit of how strictly the dependencies of a workspace pin the code they depend on, so teams can
+// enforce reproducibility policies.
+
+#[cfg(test)]
+mod tests;
+
+use super::{dependency_kind, find_workspace_members};
+use crate::{
+	Error,
+	diagnostic::{Diagnostic, Severity, ToDiagnostics},
+};
+use std::{
+	collections::BTreeSet,
+	path::{Path, PathBuf},
+};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// How much a [`PinProblem`] threatens the reproducibility of the builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "snake_case")
+)]
+pub enum PinSeverity {
+	/// The dependency may be updated to compatible releases, which is usually intended.
+	Low,
+	/// The dependency follows a moving target, so the same lockfile-less build may change anytime.
+	Medium,
+	/// The dependency accepts any release, including breaking ones.
+	High,
+}
+
+/// A dependency declaration not pinning the code it depends on.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(tag = "kind", rename_all = "snake_case")
+)]
+pub enum PinProblem {
+	/// The version requirement contains a wildcard, eg `*` or `1.*`.
+	Wildcard { requirement: String },
+	/// The version requirement is a bare version, eg `1.2`, which cargo reads as the caret
+	/// requirement `^1.2`.
+	BareVersion { requirement: String },
+	/// The git dependency follows a branch, or the default branch if `branch` is `None`, instead of
+	/// a `rev` or a `tag`.
+	GitBranch { url: String, branch: Option<String> },
+}
+
+impl PinProblem {
+	/// The severity of the problem.
+	pub fn severity(&self) -> PinSeverity {
+		match self {
+			Self::Wildcard { .. } => PinSeverity::High,
+			Self::GitBranch { .. } => PinSeverity::Medium,
+			Self::BareVersion { .. } => PinSeverity::Low,
+		}
+	}
+}
+
+/// A problem found by [`audit_version_pins`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VersionPin {
+	/// The manifest declaring the dependency.
+	pub manifest: PathBuf,
+	/// The key path of the dependency declaration, eg `["target", "cfg(unix)", "dependencies",
+	/// "libc"]` or `["workspace", "dependencies", "serde"]`.
+	pub key_path: Vec<String>,
+	/// What's wrong with the declaration.
+	pub problem: PinProblem,
+}
+
+impl VersionPin {
+	/// The severity of the problem.
+	pub fn severity(&self) -> PinSeverity {
+		self.problem.severity()
+	}
+}
+
+impl ToDiagnostics for VersionPin {
+	/// A `manifest::wildcard_version`, `manifest::bare_version` or `manifest::git_branch`
+	/// diagnostic pointing to the manifest, whose severity follows the [`PinSeverity`]: high
+	/// problems are errors, medium ones are warnings and low ones are notes.
+	fn to_diagnostics(&self) -> Vec<Diagnostic> {
+		let key = self.key_path.join(".");
+		let (code, message) = match &self.problem {
+			PinProblem::Wildcard { requirement } => (
+				"manifest::wildcard_version",
+				format!("`{key}` accepts any version matching `{requirement}`"),
+			),
+			PinProblem::BareVersion { requirement } => (
+				"manifest::bare_version",
+				format!("`{key}` uses the bare version `{requirement}`, read as `^{requirement}`"),
+			),
+			PinProblem::GitBranch { url, branch: Some(branch) } => (
+				"manifest::git_branch",
+				format!("`{key}` follows the branch `{branch}` of {url} instead of a rev or a tag"),
+			),
+			PinProblem::GitBranch { url, branch: None } => (
+				"manifest::git_branch",
+				format!("`{key}` follows the default branch of {url} instead of a rev or a tag"),
+			),
+		};
+		let severity = match self.severity() {
+			PinSeverity::Low => Severity::Note,
+			PinSeverity::Medium => Severity::Warning,
+			PinSeverity::High => Severity::Error,
+		};
+		vec![Diagnostic::new(severity, code, message).with_path(&self.manifest)]
+	}
+}
+
+/// Given a workspace manifest file path, this function audits the dependencies declared by the
+/// workspace (in `workspace.dependencies`) and by its members, reporting the ones using wildcard
+/// version requirements, bare versions without an explicit operator, or git branches instead of
+/// revs or tags. Each problem has a [`PinSeverity`], so tools can enforce a reproducibility policy
+/// by filtering the results by severity.
+///
+/// Every dependency section is audited, including the platform specific ones. Dependencies
+/// inherited from the workspace (`{ workspace = true }`) are audited where the workspace declares
+/// them. Malformed version requirements are left to cargo and aren't reported.
+///
+/// The returned problems are sorted by manifest and key path.
+///
+/// # Errors
+///
+/// - If the workspace members cannot be resolved.
+/// - If some of the manifests cannot be read or parsed.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::{PinProblem, PinSeverity};
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let workspace_manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::create_dir_all(tempdir.path().join("a")).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("a").join("Cargo.toml"),
+///     "[package]\nname = \"a\"\n\n[dependencies]\nserde = { workspace = true }\nrand = \"*\"\n",
+/// ).unwrap();
+/// std::fs::write(
+///     &workspace_manifest_path,
+///     r#"[workspace]
+/// members = ["a"]
+///
+/// [workspace.dependencies]
+/// serde = "^1.0"
+/// tool = { git = "https://github.com/org/tool", branch = "main" }
+/// "#,
+/// ).unwrap();
+///
+/// let pins = rustilities::manifest::audit_version_pins(&workspace_manifest_path).unwrap();
+/// assert_eq!(
+///     pins.iter().map(|pin| (pin.key_path.join("."), pin.severity())).collect::<Vec<_>>(),
+///     vec![
+///         ("workspace.dependencies.tool".to_owned(), PinSeverity::Medium),
+///         ("dependencies.rand".to_owned(), PinSeverity::High),
+///     ]
+/// );
+/// assert_eq!(pins[1].problem, PinProblem::Wildcard { requirement: "*".to_owned() });
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip_all, fields(workspace_toml = %workspace_toml.as_ref().display()))
+)]
+pub fn audit_version_pins<P: AsRef<Path>>(workspace_toml: P) -> Result<Vec<VersionPin>, Error> {
+	fn do_audit_version_pins(workspace_toml: &Path) -> Result<Vec<VersionPin>, Error> {
+		let mut manifests =
+			find_workspace_members(workspace_toml)?.into_iter().collect::<BTreeSet<_>>();
+		manifests.insert(workspace_toml.to_path_buf());
+
+		let mut pins = Vec::new();
+		for manifest in manifests {
+			pins.extend(audit_manifest_pins(&manifest)?);
+		}
+		pins.sort();
+		Ok(pins)
+	}
+	do_audit_version_pins(workspace_toml.as_ref())
+}
+
+/// Same as [`audit_version_pins`], only auditing the given manifest. The problems follow the order
+/// of the manifest.
+pub(crate) fn audit_manifest_pins(manifest: &Path) -> Result<Vec<VersionPin>, Error> {
+	let doc = std::fs::read_to_string(manifest)?.parse::<DocumentMut>()?;
+	let mut found = Vec::new();
+	audit_dependency_tables(doc.as_table(), &mut Vec::new(), &mut found);
+	if let Some(dependencies) = doc
+		.get("workspace")
+		.and_then(|workspace| workspace.get("dependencies"))
+		.and_then(Item::as_table_like)
+	{
+		let mut key_path = vec!["workspace".to_owned(), "dependencies".to_owned()];
+		audit_dependencies(dependencies, &mut key_path, &mut found);
+	}
+	Ok(found
+		.into_iter()
+		.map(|(key_path, problem)| VersionPin {
+			manifest: manifest.to_path_buf(),
+			key_path,
+			problem,
+		})
+		.collect())
+}
+
+/// Audits the dependency tables of a manifest, including the platform specific ones.
+fn audit_dependency_tables(
+	table: &dyn TableLike,
+	key_path: &mut Vec<String>,
+	found: &mut Vec<(Vec<String>, PinProblem)>,
+) {
+	for (key, item) in table.iter() {
+		let Some(item) = item.as_table_like() else { continue };
+		key_path.push(key.to_owned());
+		if dependency_kind(key).is_some() {
+			audit_dependencies(item, key_path, found);
+		} else if key == "target" && key_path.len() == 1 {
+			for (target, target_item) in item.iter() {
+				if let Some(target_item) = target_item.as_table_like() {
+					key_path.push(target.to_owned());
+					audit_dependency_tables(target_item, key_path, found);
+					key_path.pop();
+				}
+			}
+		}
+		key_path.pop();
+	}
+}
+
+/// Audits the dependencies declared in a dependency table.
+fn audit_dependencies(
+	dependencies: &dyn TableLike,
+	key_path: &mut Vec<String>,
+	found: &mut Vec<(Vec<String>, PinProblem)>,
+) {
+	for (name, dependency) in dependencies.iter() {
+		if let Some(problem) = pin_problem(dependency) {
+			key_path.push(name.to_owned());
+			found.push((key_path.clone(), problem));
+			key_path.pop();
+		}
+	}
+}
+
+/// The pin problem of a dependency declaration, if any.
+fn pin_problem(dependency: &Item) -> Option<PinProblem> {
+	let (version, table) = match dependency.as_table_like() {
+		Some(table) => (table.get("version").and_then(Item::as_str), Some(table)),
+		None => (dependency.as_str(), None),
+	};
+
+	if let Some(table) = table &&
+		let Some(url) = table.get("git").and_then(Item::as_str) &&
+		!table.contains_key("rev") &&
+		!table.contains_key("tag")
+	{
+		return Some(PinProblem::GitBranch {
+			url: url.to_owned(),
+			branch: table.get("branch").and_then(Item::as_str).map(str::to_owned),
+		});
+	}
+
+	let requirement = version?.trim();
+	let parsed = semver::VersionReq::parse(requirement).ok()?;
+	let requirement = requirement.to_owned();
+	if parsed.comparators.is_empty() ||
+		parsed
+			.comparators
+			.iter()
+			.any(|comparator| comparator.op == semver::Op::Wildcard)
+	{
+		Some(PinProblem::Wildcard { requirement })
+	} else if requirement.starts_with(|c: char| c.is_ascii_digit()) {
+		Some(PinProblem::BareVersion { requirement })
+	} else {
+		None
+	}
+}
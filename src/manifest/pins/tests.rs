@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+fn item(declaration: &str) -> Item {
+	format!("dependency = {declaration}")
+		.parse::<DocumentMut>()
+		.expect("The declaration should be valid TOML; qed;")
+		.remove("dependency")
+		.expect("The dependency is declared; qed;")
+}
+
+#[test]
+fn pin_problem_works() {
+	let wildcard =
+		|requirement: &str| Some(PinProblem::Wildcard { requirement: requirement.to_owned() });
+	let bare =
+		|requirement: &str| Some(PinProblem::BareVersion { requirement: requirement.to_owned() });
+	let git = |branch: Option<&str>| {
+		Some(PinProblem::GitBranch {
+			url: "https://github.com/org/repo".to_owned(),
+			branch: branch.map(str::to_owned),
+		})
+	};
+
+	for (declaration, expected) in [
+		(r#""*""#, wildcard("*")),
+		(r#"{ version = "1.*", features = ["derive"] }"#, wildcard("1.*")),
+		(r#"">=1.2, 1.3.*""#, wildcard(">=1.2, 1.3.*")),
+		(r#""1.2""#, bare("1.2")),
+		(r#"{ version = " 1 " }"#, bare("1")),
+		(r#""^1.2""#, None),
+		(r#""~1.2.3""#, None),
+		(r#""=1.2.3""#, None),
+		(r#""not a version""#, None),
+		(r#"{ git = "https://github.com/org/repo", branch = "dev" }"#, git(Some("dev"))),
+		(r#"{ git = "https://github.com/org/repo", version = "^1" }"#, git(None)),
+		(r#"{ git = "https://github.com/org/repo", rev = "abc123" }"#, None),
+		(r#"{ git = "https://github.com/org/repo", tag = "v1.0.0" }"#, None),
+		(r#"{ path = "../local" }"#, None),
+		(r#"{ workspace = true }"#, None),
+	] {
+		assert_eq!(pin_problem(&item(declaration)), expected, "{declaration}");
+	}
+}
+
+#[test]
+fn pin_problem_severity_works() {
+	assert_eq!(PinProblem::Wildcard { requirement: "*".to_owned() }.severity(), PinSeverity::High);
+	assert_eq!(
+		PinProblem::GitBranch { url: String::new(), branch: None }.severity(),
+		PinSeverity::Medium
+	);
+	assert_eq!(
+		PinProblem::BareVersion { requirement: "1".to_owned() }.severity(),
+		PinSeverity::Low
+	);
+	assert!(PinSeverity::Low < PinSeverity::Medium && PinSeverity::Medium < PinSeverity::High);
+}
+
+#[test]
+fn audit_version_pins_audits_every_dependency_section_of_the_workspace() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let workspace_toml = tempdir.path().join("Cargo.toml");
+	let member_toml = tempdir.path().join("member").join("Cargo.toml");
+	std::fs::create_dir_all(tempdir.path().join("member"))
+		.expect("The dir should be created; qed;");
+	std::fs::write(
+		&workspace_toml,
+		r#"[package]
+name = "root"
+
+[workspace]
+members = ["member"]
+
+[workspace.dependencies]
+serde = "1.0"
+
+[build-dependencies]
+cc = "^1"
+"#,
+	)
+	.expect("The manifest should be writable; qed;");
+	std::fs::write(
+		&member_toml,
+		r#"[package]
+name = "member"
+
+[dependencies]
+serde = { workspace = true }
+
+[dev-dependencies.proptest]
+version = "*"
+
+[target.'cfg(unix)'.dependencies]
+libc = { git = "https://github.com/rust-lang/libc" }
+
+[package.metadata.dependencies]
+ignored = "*"
+"#,
+	)
+	.expect("The manifest should be writable; qed;");
+
+	let pin = |manifest: &Path, key_path: &[&str], problem: PinProblem| VersionPin {
+		manifest: manifest.to_path_buf(),
+		key_path: key_path.iter().map(|key| key.to_string()).collect(),
+		problem,
+	};
+	assert_eq!(
+		audit_version_pins(&workspace_toml).expect("This should be Ok; qed;"),
+		vec![
+			pin(
+				&workspace_toml,
+				&["workspace", "dependencies", "serde"],
+				PinProblem::BareVersion { requirement: "1.0".to_owned() }
+			),
+			pin(
+				&member_toml,
+				&["dev-dependencies", "proptest"],
+				PinProblem::Wildcard { requirement: "*".to_owned() }
+			),
+			pin(
+				&member_toml,
+				&["target", "cfg(unix)", "dependencies", "libc"],
+				PinProblem::GitBranch {
+					url: "https://github.com/rust-lang/libc".to_owned(),
+					branch: None
+				}
+			),
+		]
+	);
+	assert!(audit_version_pins(&member_toml).is_err());
+}
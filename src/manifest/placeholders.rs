@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Placeholders in the origins of the dependencies written to manifests, so templated project
+// generators can ship origin specs adapting to the machine they're used on.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+	Error,
+	fs::{FsProvider, StdFs},
+};
+use std::path::{Path, PathBuf};
+use toml_edit::{TableLike, Value};
+
+/// The keys of a dependency declaration whose values may contain placeholders.
+const EXPANDED_KEYS: [&str; 2] = ["path", "git"];
+
+/// Resolves the placeholders found in the `path` and `git` values of the dependencies written to
+/// manifests. A placeholder is written as `${NAME}`, and the resolver receives its name, eg
+/// `WORKSPACE_ROOT` or `ENV:MY_PATH`, returning its value or `None` if it's unknown. `$${` is
+/// written as a literal `${`.
+///
+/// Closures taking the name and returning an `Option<String>` are resolvers.
+pub trait PlaceholderResolver {
+	/// Resolves the placeholder with the given name.
+	fn resolve(&self, placeholder: &str) -> Option<String>;
+}
+
+impl<F: Fn(&str) -> Option<String>> PlaceholderResolver for F {
+	fn resolve(&self, placeholder: &str) -> Option<String> {
+		self(placeholder)
+	}
+}
+
+/// The resolver used by the functions adding dependencies to manifests, supporting:
+/// - `${WORKSPACE_ROOT}`: the dir of the workspace containing the edited manifest, or the dir of
+///   the manifest itself if it isn't part of a workspace.
+/// - `${ENV:NAME}`: the value of the `NAME` environment variable.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DefaultPlaceholders {
+	workspace_root: Option<PathBuf>,
+}
+
+impl DefaultPlaceholders {
+	/// Creates a resolver expanding `${WORKSPACE_ROOT}` to the given dir, if any.
+	pub fn new(workspace_root: Option<PathBuf>) -> Self {
+		Self { workspace_root }
+	}
+
+	/// Creates the resolver used when editing the given manifest.
+	pub fn for_manifest<P: AsRef<Path>>(manifest_path: P) -> Self {
+		Self::for_manifest_with_fs(&StdFs, manifest_path.as_ref())
+	}
+
+	/// Same as [`DefaultPlaceholders::for_manifest`], looking up the workspace through the given
+	/// filesystem provider.
+	pub(super) fn for_manifest_with_fs<F: FsProvider>(fs: &F, manifest_path: &Path) -> Self {
+		let manifest_path = crate::paths::prefix_with_current_dir_cow(manifest_path);
+		let root_manifest = super::find_workspace_manifest_with_fs(fs, &manifest_path)
+			.unwrap_or_else(|| manifest_path.into_owned());
+		Self::new(root_manifest.parent().map(Path::to_path_buf))
+	}
+}
+
+impl PlaceholderResolver for DefaultPlaceholders {
+	fn resolve(&self, placeholder: &str) -> Option<String> {
+		match placeholder.strip_prefix("ENV:") {
+			Some(variable) => std::env::var(variable).ok(),
+			None if placeholder == "WORKSPACE_ROOT" =>
+				self.workspace_root.as_ref().map(|root| root.to_string_lossy().into_owned()),
+			None => None,
+		}
+	}
+}
+
+/// Expands the placeholders contained in a value.
+///
+/// # Errors
+///
+/// - If a placeholder isn't closed.
+/// - If the resolver cannot resolve a placeholder.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::expand_placeholders;
+///
+/// let resolver = |name: &str| (name == "ROOT").then(|| "/home/me".to_owned());
+///
+/// assert_eq!(expand_placeholders("${ROOT}/crates/a", &resolver).unwrap(), "/home/me/crates/a");
+/// assert_eq!(expand_placeholders("$${ROOT}/$HOME", &resolver).unwrap(), "${ROOT}/$HOME");
+/// assert!(expand_placeholders("${OTHER}", &resolver).is_err());
+/// assert!(expand_placeholders("${ROOT", &resolver).is_err());
+/// ```
+pub fn expand_placeholders(
+	value: &str,
+	resolver: &dyn PlaceholderResolver,
+) -> Result<String, Error> {
+	let mut expanded = String::with_capacity(value.len());
+	let mut rest = value;
+	while let Some(start) = rest.find("${") {
+		if rest[..start].ends_with('$') {
+			expanded.push_str(&rest[..start - 1]);
+			expanded.push_str("${");
+			rest = &rest[start + 2..];
+			continue;
+		}
+		expanded.push_str(&rest[..start]);
+		let Some(end) = rest[start..].find('}') else {
+			return Err(Error::Descriptive(format!("Unclosed placeholder in {value}")));
+		};
+		let placeholder = &rest[start + 2..start + end];
+		let resolved = resolver.resolve(placeholder).ok_or_else(|| {
+			Error::Descriptive(format!("The placeholder `${{{placeholder}}}` cannot be resolved"))
+		})?;
+		expanded.push_str(&resolved);
+		rest = &rest[start + end + 1..];
+	}
+	expanded.push_str(rest);
+	Ok(expanded)
+}
+
+/// Expands the placeholders of the `path` and `git` values of a dependency declaration.
+pub(super) fn expand_declaration(
+	declaration: &mut dyn TableLike,
+	resolver: &dyn PlaceholderResolver,
+) -> Result<(), Error> {
+	for key in EXPANDED_KEYS {
+		if let Some(Value::String(value)) =
+			declaration.get_mut(key).and_then(|item| item.as_value_mut())
+		{
+			let decor = value.decor().clone();
+			*value = toml_edit::Formatted::new(expand_placeholders(value.value(), resolver)?);
+			*value.decor_mut() = decor;
+		}
+	}
+	Ok(())
+}
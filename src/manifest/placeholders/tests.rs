@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use crate::manifest::{
+	ManifestDependencyConfig, ManifestDependencyOrigin, add_crate_to_all_members,
+	add_crate_to_all_members_with_resolver, add_crate_to_dependencies,
+	add_crate_to_dependencies_with_resolver,
+};
+
+fn resolver(placeholder: &str) -> Option<String> {
+	match placeholder {
+		"ROOT" => Some("/root dir".to_owned()),
+		"EMPTY" => Some(String::new()),
+		_ => None,
+	}
+}
+
+#[test]
+fn expand_placeholders_works() {
+	for (value, expected) in [
+		("no placeholders", "no placeholders"),
+		("${ROOT}", "/root dir"),
+		("${ROOT}/a/${EMPTY}b${ROOT}", "/root dir/a/b/root dir"),
+		("$HOME/{ROOT}/$", "$HOME/{ROOT}/$"),
+		("$${ROOT}/${ROOT}", "${ROOT}//root dir"),
+	] {
+		assert_eq!(
+			expand_placeholders(value, &resolver).expect("This should be Ok; qed;"),
+			expected
+		);
+	}
+
+	assert!(matches!(
+		expand_placeholders("a/${ROOT}/${UNKNOWN}", &resolver),
+		Err(Error::Descriptive(msg)) if msg == "The placeholder `${UNKNOWN}` cannot be resolved"
+	));
+	assert!(matches!(
+		expand_placeholders("a/${ROOT", &resolver),
+		Err(Error::Descriptive(msg)) if msg == "Unclosed placeholder in a/${ROOT"
+	));
+}
+
+#[test]
+fn default_placeholders_resolve_the_workspace_root_and_the_environment() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let crate_dir = tempdir.path().join("crate");
+	std::fs::create_dir_all(&crate_dir).expect("The dir should be created; qed;");
+	std::fs::write(crate_dir.join("Cargo.toml"), "[package]\nname = \"test\"\n")
+		.expect("The manifest should be writable; qed;");
+
+	let placeholders = DefaultPlaceholders::for_manifest(crate_dir.join("Cargo.toml"));
+	assert_eq!(
+		placeholders.resolve("WORKSPACE_ROOT"),
+		Some(crate_dir.to_string_lossy().into_owned())
+	);
+	assert_eq!(placeholders.resolve("ENV:CARGO_PKG_NAME"), Some("rustilities".to_owned()));
+	assert_eq!(placeholders.resolve("ENV:RUSTILITIES_UNSET_VARIABLE"), None);
+	assert_eq!(placeholders.resolve("OTHER"), None);
+	assert_eq!(DefaultPlaceholders::default().resolve("WORKSPACE_ROOT"), None);
+
+	std::fs::write(tempdir.path().join("Cargo.toml"), "[workspace]\nmembers = [\"crate\"]\n")
+		.expect("The manifest should be writable; qed;");
+	assert_eq!(
+		DefaultPlaceholders::for_manifest(crate_dir.join("Cargo.toml")).resolve("WORKSPACE_ROOT"),
+		Some(tempdir.path().to_string_lossy().into_owned())
+	);
+}
+
+#[test]
+fn add_crate_to_dependencies_with_resolver_expands_the_default_placeholders() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let manifest_path = tempdir.path().join("Cargo.toml");
+	std::fs::write(&manifest_path, "[package]\nname = \"test\"\n\n[dependencies]\n")
+		.expect("The manifest should be writable; qed;");
+	let resolver = DefaultPlaceholders::for_manifest(&manifest_path);
+
+	add_crate_to_dependencies_with_resolver(
+		&manifest_path,
+		"local",
+		ManifestDependencyConfig::builder(ManifestDependencyOrigin::local(Path::new(
+			"${WORKSPACE_ROOT}/crates/local",
+		)))
+		.build(),
+		&resolver,
+	)
+	.expect("This should be Ok; qed;");
+	add_crate_to_dependencies_with_resolver(
+		&manifest_path,
+		"remote",
+		ManifestDependencyConfig::builder(ManifestDependencyOrigin::git(
+			"https://github.com/${ENV:CARGO_PKG_NAME}/remote",
+			"main",
+		))
+		.build(),
+		&resolver,
+	)
+	.expect("This should be Ok; qed;");
+	assert_eq!(
+		std::fs::read_to_string(&manifest_path).expect("The manifest should be readable; qed;"),
+		format!(
+			"[package]\nname = \"test\"\n\n[dependencies]\nlocal = {{ path = \"{}/crates/local\" }}\nremote = {{ git = \"https://github.com/rustilities/remote\", branch = \"main\" }}\n",
+			tempdir.path().display()
+		)
+	);
+
+	assert!(matches!(
+		add_crate_to_dependencies_with_resolver(
+			&manifest_path,
+			"other",
+			ManifestDependencyConfig::builder(ManifestDependencyOrigin::local(Path::new(
+				"${ENV:RUSTILITIES_UNSET_VARIABLE}/other",
+			)))
+			.build(),
+			&resolver,
+		),
+		Err(Error::Descriptive(msg)) if msg == "The placeholder `${ENV:RUSTILITIES_UNSET_VARIABLE}` cannot be resolved"
+	));
+}
+
+#[test]
+fn writers_with_resolver_expand_the_placeholders_with_the_given_resolver() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let workspace_toml = tempdir.path().join("Cargo.toml");
+	let member_toml = tempdir.path().join("a/Cargo.toml");
+	std::fs::create_dir_all(tempdir.path().join("a")).expect("The dir should be created; qed;");
+	std::fs::write(&workspace_toml, "[workspace]\nmembers = [\"a\"]\n")
+		.expect("The manifest should be writable; qed;");
+	std::fs::write(&member_toml, "[package]\nname = \"a\"\n")
+		.expect("The manifest should be writable; qed;");
+	let config = ManifestDependencyConfig::builder(ManifestDependencyOrigin::local(Path::new(
+		"${ROOT}/local",
+	)))
+	.build();
+
+	add_crate_to_dependencies_with_resolver(&member_toml, "local", config.clone(), &resolver)
+		.expect("This should be Ok; qed;");
+	assert_eq!(
+		std::fs::read_to_string(&member_toml).expect("The manifest should be readable; qed;"),
+		"[package]\nname = \"a\"\n\n[dependencies]\nlocal = { path = \"/root dir/local\" }\n"
+	);
+
+	add_crate_to_all_members_with_resolver(
+		&workspace_toml,
+		"local",
+		config,
+		true,
+		|_| true,
+		&resolver,
+	)
+	.expect("This should be Ok; qed;");
+	assert_eq!(
+		std::fs::read_to_string(&workspace_toml).expect("The manifest should be readable; qed;"),
+		"[workspace]\nmembers = [\"a\"]\n\n[workspace.dependencies]\nlocal = { path = \"/root dir/local\" }\n"
+	);
+}
+
+#[test]
+fn writers_without_resolver_keep_the_placeholders() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let workspace_toml = tempdir.path().join("Cargo.toml");
+	let member_toml = tempdir.path().join("a/Cargo.toml");
+	std::fs::create_dir_all(tempdir.path().join("a")).expect("The dir should be created; qed;");
+	std::fs::write(&workspace_toml, "[workspace]\nmembers = [\"a\"]\n")
+		.expect("The manifest should be writable; qed;");
+	std::fs::write(&member_toml, "[package]\nname = \"a\"\n")
+		.expect("The manifest should be writable; qed;");
+	let config = ManifestDependencyConfig::builder(ManifestDependencyOrigin::local(Path::new(
+		"${UNKNOWN}/local",
+	)))
+	.build();
+
+	add_crate_to_dependencies(&member_toml, "local", config.clone())
+		.expect("This should be Ok; qed;");
+	assert_eq!(
+		std::fs::read_to_string(&member_toml).expect("The manifest should be readable; qed;"),
+		"[package]\nname = \"a\"\n\n[dependencies]\nlocal = { path = \"${UNKNOWN}/local\" }\n"
+	);
+
+	add_crate_to_all_members(&workspace_toml, "local", config, true, |_| true)
+		.expect("This should be Ok; qed;");
+	assert_eq!(
+		std::fs::read_to_string(&workspace_toml).expect("The manifest should be readable; qed;"),
+		"[workspace]\nmembers = [\"a\"]\n\n[workspace.dependencies]\nlocal = { path = \"${UNKNOWN}/local\" }\n"
+	);
+}
@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// A lightweight check of which top-level sections a manifest declares, used by the manifest
+// finders. The raw text is first scanned for `[package]` and `[workspace]` headers (or top-level
+// keys), and only the manifests declaring some of them are parsed, so malformed manifests declare
+// no section. The results are memoized per manifest path and content, so that lookups from sibling
+// paths don't parse the same parent manifests again.
+
+#[cfg(test)]
+mod tests;
+
+use crate::fs::FsProvider;
+use std::{
+	collections::HashMap,
+	hash::{DefaultHasher, Hash, Hasher},
+	path::{Path, PathBuf},
+	sync::{LazyLock, Mutex},
+};
+use toml_edit::ImDocument;
+
+/// The maximum number of manifests kept in the cache. The cache is cleared once it's reached.
+const CACHE_CAPACITY: usize = 4096;
+
+static CACHE: LazyLock<Mutex<HashMap<PathBuf, CachedProbe>>> =
+	LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The top-level sections declared by a manifest.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(super) struct ManifestProbe {
+	pub(super) package: bool,
+	pub(super) workspace: bool,
+}
+
+impl ManifestProbe {
+	pub(super) fn is_crate_or_workspace(&self) -> bool {
+		self.package || self.workspace
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CachedProbe {
+	/// The hash of the content the probe was computed from. The hasher only needs to be stable
+	/// within the process, as the cache isn't persisted.
+	content_hash: u64,
+	probe: ManifestProbe,
+}
+
+/// Probes the manifest at the given path. A missing, unreadable or malformed file declares no
+/// section. The file is read every time, but the cached result is reused as long as its content
+/// doesn't change, whatever its modification time says.
+pub(super) fn probe(manifest_path: &Path) -> ManifestProbe {
+	if !manifest_path.is_file() {
+		return ManifestProbe::default();
+	}
+	std::fs::read_to_string(manifest_path)
+		.map(|content| cached_scan(manifest_path, &content))
+		.unwrap_or_default()
+}
+
+/// Probes the manifest at the given path through a filesystem provider, as [`probe`] does. The
+/// results share the cache of [`probe`]: as it's keyed by content, a provider exposing different
+/// contents at the same path never reuses a stale result.
+pub(super) fn probe_with_fs(fs: &dyn FsProvider, manifest_path: &Path) -> ManifestProbe {
+	if !fs.is_file(manifest_path) {
+		return ManifestProbe::default();
+	}
+	fs.read_to_string(manifest_path)
+		.map(|content| cached_scan(manifest_path, &content))
+		.unwrap_or_default()
+}
+
+/// Returns the cached probe of the manifest at the given path if it was computed from the same
+/// content, and scans the content otherwise.
+fn cached_scan(manifest_path: &Path, content: &str) -> ManifestProbe {
+	let mut hasher = DefaultHasher::new();
+	content.hash(&mut hasher);
+	let content_hash = hasher.finish();
+
+	let mut cache = CACHE.lock().expect("The cache lock cannot be poisoned; qed;");
+	if let Some(cached) = cache.get(manifest_path) &&
+		cached.content_hash == content_hash
+	{
+		return cached.probe;
+	}
+
+	let probe = validated_scan(content);
+	if cache.len() >= CACHE_CAPACITY {
+		cache.clear();
+	}
+	cache.insert(manifest_path.to_path_buf(), CachedProbe { content_hash, probe });
+	probe
+}
+
+/// Scans the raw text of a manifest as [`scan`] does, parsing it if some section is found so that
+/// malformed manifests declare no section.
+fn validated_scan(content: &str) -> ManifestProbe {
+	let probe = scan(content);
+	if probe.is_crate_or_workspace() && ImDocument::parse(content).is_err() {
+		return ManifestProbe::default();
+	}
+	probe
+}
+
+/// Scans the raw text of a manifest looking for the `package` and `workspace` top-level sections,
+/// either declared as table headers (`[package]`, `[workspace.dependencies]`, ...) or as keys
+/// before the first header (`package = { ... }`, `workspace.members = [...]`). Lines inside
+/// multi-line strings are ignored.
+fn scan(content: &str) -> ManifestProbe {
+	let mut probe = ManifestProbe::default();
+	let mut in_header = false;
+	let mut in_multiline_string = false;
+
+	for line in content.lines() {
+		let line = line.trim_start();
+		let delimiters = line.matches("\"\"\"").count() + line.matches("'''").count();
+		let starts_in_string = in_multiline_string;
+		in_multiline_string ^= delimiters % 2 == 1;
+		if starts_in_string || line.starts_with('#') {
+			continue;
+		}
+
+		let key = if let Some(header) = line.strip_prefix('[') {
+			in_header = true;
+			let header = header.strip_prefix('[').unwrap_or(header);
+			header.split(']').next().unwrap_or_default()
+		} else if !in_header {
+			line.split('=').next().unwrap_or_default()
+		} else {
+			continue;
+		};
+
+		match first_key_segment(key) {
+			"package" => probe.package = true,
+			"workspace" => probe.workspace = true,
+			_ => (),
+		}
+	}
+	probe
+}
+
+fn first_key_segment(key: &str) -> &str {
+	key.split('.')
+		.next()
+		.unwrap_or_default()
+		.trim()
+		.trim_matches(|c| c == '"' || c == '\'')
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+#[test]
+fn scan_finds_table_headers() {
+	assert_eq!(
+		scan("[package]\nname = \"test\"\n\n[workspace.dependencies]\n"),
+		ManifestProbe { package: true, workspace: true }
+	);
+	assert_eq!(
+		scan("  [ \"package\" . metadata ]\n[[workspace.something]]\n"),
+		ManifestProbe { package: true, workspace: true }
+	);
+	assert_eq!(scan("[dependencies]\npackage = \"1.0\"\n"), ManifestProbe::default());
+}
+
+#[test]
+fn scan_finds_top_level_keys() {
+	assert_eq!(
+		scan("package = { name = \"test\" }\nworkspace.members = []\n"),
+		ManifestProbe { package: true, workspace: true }
+	);
+}
+
+#[test]
+fn scan_ignores_comments_and_multiline_strings() {
+	assert_eq!(
+		scan(
+			"# [workspace]\n[package]\ndescription = \"\"\"\n[workspace]\n\"\"\"\nreadme = '''\n[workspace]'''\n"
+		),
+		ManifestProbe { package: true, workspace: false }
+	);
+	assert_eq!(scan(""), ManifestProbe::default());
+}
+
+#[test]
+fn probe_detects_manifest_changes() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let manifest_path = tempdir.path().join("Cargo.toml");
+
+	assert_eq!(probe(&manifest_path), ManifestProbe::default());
+
+	std::fs::write(&manifest_path, "[package]\n").expect("The manifest should be writable; qed;");
+	assert_eq!(probe(&manifest_path), ManifestProbe { package: true, workspace: false });
+	// The cached result is reused
+	assert_eq!(probe(&manifest_path), ManifestProbe { package: true, workspace: false });
+
+	std::fs::write(&manifest_path, "[workspace]\nmembers = []\n")
+		.expect("The manifest should be writable; qed;");
+	assert_eq!(probe(&manifest_path), ManifestProbe { package: false, workspace: true });
+}
+
+#[test]
+fn probe_with_fs_detects_manifest_changes() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let manifest_path = tempdir.path().join("Cargo.toml");
+
+	std::fs::write(&manifest_path, "[package]\n").expect("The manifest should be writable; qed;");
+	assert_eq!(
+		probe_with_fs(&crate::fs::StdFs, &manifest_path),
+		ManifestProbe { package: true, workspace: false }
+	);
+	// The cache is shared with `probe`
+	assert_eq!(probe(&manifest_path), ManifestProbe { package: true, workspace: false });
+
+	std::fs::write(&manifest_path, "[workspace]\nmembers = []\n")
+		.expect("The manifest should be writable; qed;");
+	assert_eq!(
+		probe_with_fs(&crate::fs::StdFs, &manifest_path),
+		ManifestProbe { package: false, workspace: true }
+	);
+}
+
+#[test]
+fn probe_detects_changes_keeping_the_modification_time_and_length() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let manifest_path = tempdir.path().join("Cargo.toml");
+	let write = |content: &str| {
+		std::fs::write(&manifest_path, content).expect("The manifest should be writable; qed;");
+		std::fs::File::options()
+			.write(true)
+			.open(&manifest_path)
+			.and_then(|file| file.set_modified(std::time::SystemTime::UNIX_EPOCH))
+			.expect("The modification time should be settable; qed;");
+	};
+
+	write("[package]\n");
+	assert_eq!(probe(&manifest_path), ManifestProbe { package: true, workspace: false });
+
+	write("[workspa]\n");
+	assert_eq!(probe(&manifest_path), ManifestProbe::default());
+}
+
+#[test]
+fn probe_rejects_malformed_manifests() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let manifest_path = tempdir.path().join("Cargo.toml");
+
+	std::fs::write(&manifest_path, "[package]\nname = \n")
+		.expect("The manifest should be writable; qed;");
+	assert_eq!(probe(&manifest_path), ManifestProbe::default());
+	assert_eq!(probe_with_fs(&crate::fs::StdFs, &manifest_path), ManifestProbe::default());
+}
+
+#[test]
+fn probe_ignores_dirs() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::create_dir(tempdir.path().join("Cargo.toml"))
+		.expect("The dir should be created; qed;");
+
+	assert_eq!(probe(&tempdir.path().join("Cargo.toml")), ManifestProbe::default());
+}
@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Lookups against the crates.io API, cached on disk.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{Error, macros::debug};
+use serde::Deserialize;
+use std::{path::PathBuf, time::Duration};
+
+/// The crates.io API endpoint serving the data of a crate.
+const CRATES_IO_API: &str = "https://crates.io/api/v1/crates";
+
+/// The registry data of a published version of a crate, as returned by [`dependency_metadata`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DependencyMetadata {
+	/// The name of the crate.
+	pub name: String,
+	/// The version looked up.
+	pub version: String,
+	/// The SPDX license expression of the version, if it declares one.
+	pub license: Option<String>,
+	/// The description of the crate, if any.
+	pub description: Option<String>,
+	/// Whether the version has been yanked.
+	pub yanked: bool,
+	/// The latest stable version of the crate, or its latest version if it doesn't have stable
+	/// ones.
+	pub latest_version: String,
+}
+
+/// Where and for how long the registry lookups, eg [`dependency_metadata_with`], cache the registry
+/// responses, and whether they can reach the registry.
+///
+/// The default cache lives in the `rustilities/crates-io` dir of the user cache dir
+/// (`$XDG_CACHE_HOME`, `~/.cache` or `%LOCALAPPDATA%`), keeps the responses for a day, and is
+/// offline if the `CARGO_NET_OFFLINE` environment variable is `true`, as cargo does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryCache {
+	/// The dir where the registry responses are stored, one file per crate.
+	pub dir: PathBuf,
+	/// How long a stored response is reused before fetching it again. [`Duration::ZERO`] fetches
+	/// every crate again.
+	pub ttl: Duration,
+	/// Whether the registry must not be reached. The stored responses are used whatever their
+	/// age, and looking up a crate without a stored response fails.
+	pub offline: bool,
+}
+
+impl Default for RegistryCache {
+	fn default() -> Self {
+		Self {
+			dir: default_cache_dir(),
+			ttl: Duration::from_secs(24 * 60 * 60),
+			offline: std::env::var_os("CARGO_NET_OFFLINE").is_some_and(|offline| offline == "true"),
+		}
+	}
+}
+
+impl RegistryCache {
+	/// Removes the stored responses, so the next lookups fetch them again.
+	///
+	/// # Errors
+	///
+	/// - If the cache dir exists but cannot be removed.
+	pub fn clear(&self) -> Result<(), Error> {
+		debug!(path = %self.dir.display(), "Clearing registry cache");
+		match std::fs::remove_dir_all(&self.dir) {
+			Err(err) if err.kind() != std::io::ErrorKind::NotFound =>
+				Err(Error::IOAt { path: self.dir.clone(), source: err }),
+			_ => Ok(()),
+		}
+	}
+}
+
+/// The subset of the crates.io API response for a crate used by this module.
+#[derive(Deserialize)]
+struct CrateResponse {
+	#[serde(rename = "crate")]
+	krate: CrateData,
+	versions: Vec<VersionData>,
+}
+
+#[derive(Deserialize)]
+struct CrateData {
+	description: Option<String>,
+	max_version: String,
+	max_stable_version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VersionData {
+	num: String,
+	yanked: bool,
+	license: Option<String>,
+}
+
+/// Fetches the crates.io API response for a crate, `None` if the crate doesn't exist.
+type Fetch<'a> = dyn Fn(&str) -> Result<Option<String>, Error> + 'a;
+
+/// Given the name and the version of a crate published in crates.io, this function returns its
+/// license, description, yanked status and latest version, using the default [`RegistryCache`].
+///
+/// # Errors
+///
+/// - If the lookup fails. See [`dependency_metadata_with`].
+///
+/// # Examples
+///
+/// ```no_run
+/// let metadata = rustilities::manifest::dependency_metadata("serde", "1.0.219").unwrap();
+///
+/// assert_eq!(metadata.license.as_deref(), Some("MIT OR Apache-2.0"));
+/// assert!(!metadata.yanked);
+/// ```
+pub fn dependency_metadata(name: &str, version: &str) -> Result<DependencyMetadata, Error> {
+	dependency_metadata_with(name, version, &RegistryCache::default())
+}
+
+/// [`dependency_metadata`] using the given [`RegistryCache`], eg to keep the cache inside the
+/// target dir of a CI job or to run without reaching crates.io.
+///
+/// # Errors
+///
+/// - If the name isn't a valid crate name.
+/// - If the crate or the version doesn't exist in crates.io.
+/// - If crates.io cannot be reached, or its response cannot be understood.
+/// - If the cache is offline and the crate isn't cached.
+/// - If the cache cannot be written.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::{Error, manifest::RegistryCache};
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let cache = RegistryCache {
+///     dir: tempdir.path().to_path_buf(),
+///     offline: true,
+///     ..Default::default()
+/// };
+///
+/// assert!(matches!(
+///     rustilities::manifest::dependency_metadata_with("serde", "1.0.219", &cache),
+///     Err(Error::Descriptive(msg))
+///         if msg == "The crate serde isn't cached, and crates.io cannot be reached offline"
+/// ));
+/// ```
+pub fn dependency_metadata_with(
+	name: &str,
+	version: &str,
+	cache: &RegistryCache,
+) -> Result<DependencyMetadata, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_dependency_metadata_with(
+		name: &str,
+		version: &str,
+		cache: &RegistryCache,
+	) -> Result<DependencyMetadata, Error> {
+		metadata_with(name, version, cache, &fetch_from_crates_io)
+	}
+	do_dependency_metadata_with(name, version, cache)
+}
+
+/// [`dependency_metadata_with`] using the given fetcher.
+fn metadata_with(
+	name: &str,
+	version: &str,
+	cache: &RegistryCache,
+	fetch: &Fetch,
+) -> Result<DependencyMetadata, Error> {
+	let response = cached_crate(name, cache, fetch)?;
+	let Some(version_data) = response.versions.into_iter().find(|data| data.num == version) else {
+		return Err(Error::Descriptive(format!("{name} {version} doesn't exist in crates.io")));
+	};
+	Ok(DependencyMetadata {
+		name: name.to_owned(),
+		version: version_data.num,
+		license: version_data.license,
+		description: response.krate.description,
+		yanked: version_data.yanked,
+		latest_version: response.krate.max_stable_version.unwrap_or(response.krate.max_version),
+	})
+}
+
+/// The crates.io API response for a crate, read from the cache if it was fetched less than the TTL
+/// ago, or if the cache is offline. Unreadable cache entries are fetched again.
+fn cached_crate(name: &str, cache: &RegistryCache, fetch: &Fetch) -> Result<CrateResponse, Error> {
+	if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+		return Err(Error::Descriptive(format!("{name} isn't a valid crate name")));
+	}
+	// crates.io doesn't distinguish case, nor `-` from `_`
+	let cache_file =
+		cache.dir.join(format!("{}.json", name.to_ascii_lowercase().replace('_', "-")));
+	let fresh = cache.offline ||
+		std::fs::metadata(&cache_file)
+			.and_then(|metadata| metadata.modified())
+			.is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < cache.ttl));
+	if fresh &&
+		let Some(response) = std::fs::read_to_string(&cache_file)
+			.ok()
+			.and_then(|content| serde_json::from_str(&content).ok())
+	{
+		debug!(path = %cache_file.display(), "Using cached crate");
+		return Ok(response);
+	}
+	if cache.offline {
+		return Err(Error::Descriptive(format!(
+			"The crate {name} isn't cached, and crates.io cannot be reached offline"
+		)));
+	}
+
+	debug!(name, "Fetching crate from crates.io");
+	let Some(content) = fetch(name)? else {
+		return Err(Error::Descriptive(format!("The crate {name} doesn't exist in crates.io")));
+	};
+	let response = serde_json::from_str(&content)?;
+	crate::paths::write_creating_parents(&cache_file, content)?;
+	Ok(response)
+}
+
+/// The dir where the crates.io API responses are cached by default.
+fn default_cache_dir() -> PathBuf {
+	std::env::var_os("XDG_CACHE_HOME")
+		.filter(|dir| !dir.is_empty())
+		.map(PathBuf::from)
+		.or_else(|| {
+			if cfg!(windows) {
+				std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+			} else {
+				std::env::home_dir().map(|home| home.join(".cache"))
+			}
+		})
+		.unwrap_or_else(std::env::temp_dir)
+		.join("rustilities")
+		.join("crates-io")
+}
+
+/// Fetches a crate from the crates.io API.
+fn fetch_from_crates_io(name: &str) -> Result<Option<String>, Error> {
+	let response = ureq::get(&format!("{CRATES_IO_API}/{name}"))
+		.set("User-Agent", concat!("rustilities/", env!("CARGO_PKG_VERSION")))
+		.call();
+	match response {
+		Ok(response) => Ok(Some(response.into_string()?)),
+		Err(ureq::Error::Status(404, _)) => Ok(None),
+		Err(err) => Err(Error::Descriptive(format!("Cannot reach crates.io: {err}"))),
+	}
+}
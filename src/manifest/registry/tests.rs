@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use std::{cell::Cell, fs::File, time::SystemTime};
+use tempfile::TempDir;
+
+const SERDE_RESPONSE: &str = r#"{
+	"crate": {
+		"name": "serde",
+		"description": "A generic serialization/deserialization framework",
+		"max_version": "2.0.0-rc.1",
+		"max_stable_version": "1.0.219"
+	},
+	"versions": [
+		{ "num": "2.0.0-rc.1", "yanked": false, "license": "MIT OR Apache-2.0" },
+		{ "num": "1.0.219", "yanked": false, "license": "MIT OR Apache-2.0" },
+		{ "num": "1.0.0", "yanked": true, "license": null }
+	]
+}"#;
+
+fn cache(tempdir: &TempDir) -> RegistryCache {
+	RegistryCache { dir: tempdir.path().to_path_buf(), offline: false, ..Default::default() }
+}
+
+fn fetcher(calls: &Cell<usize>) -> impl Fn(&str) -> Result<Option<String>, Error> + '_ {
+	move |name| {
+		calls.set(calls.get() + 1);
+		Ok((name == "serde").then(|| SERDE_RESPONSE.to_owned()))
+	}
+}
+
+#[test]
+fn metadata_with_returns_the_data_of_the_version() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let calls = Cell::new(0);
+
+	assert_eq!(
+		metadata_with("serde", "1.0.219", &cache(&tempdir), &fetcher(&calls))
+			.expect("This should be Ok; qed;"),
+		DependencyMetadata {
+			name: "serde".to_owned(),
+			version: "1.0.219".to_owned(),
+			license: Some("MIT OR Apache-2.0".to_owned()),
+			description: Some("A generic serialization/deserialization framework".to_owned()),
+			yanked: false,
+			latest_version: "1.0.219".to_owned(),
+		}
+	);
+	let yanked = metadata_with("serde", "1.0.0", &cache(&tempdir), &fetcher(&calls))
+		.expect("This should be Ok; qed;");
+	assert!(yanked.yanked);
+	assert_eq!(yanked.license, None);
+}
+
+#[test]
+fn metadata_with_falls_back_to_the_max_version_without_stable_versions() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let fetch = |_: &str| {
+		Ok(Some(
+			r#"{"crate":{"description":null,"max_version":"0.1.0-alpha","max_stable_version":null},"versions":[{"num":"0.1.0-alpha","yanked":false,"license":"MIT"}]}"#
+				.to_owned(),
+		))
+	};
+
+	let metadata = metadata_with("unstable", "0.1.0-alpha", &cache(&tempdir), &fetch)
+		.expect("This should be Ok; qed;");
+	assert_eq!(metadata.latest_version, "0.1.0-alpha");
+	assert_eq!(metadata.description, None);
+}
+
+#[test]
+fn metadata_with_caches_the_responses_on_disk() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let calls = Cell::new(0);
+
+	metadata_with("serde", "1.0.219", &cache(&tempdir), &fetcher(&calls))
+		.expect("This should be Ok; qed;");
+	assert_eq!(calls.get(), 1);
+	assert_eq!(
+		std::fs::read_to_string(tempdir.path().join("serde.json"))
+			.expect("The file should be readable; qed;"),
+		SERDE_RESPONSE
+	);
+	// Other versions and spellings of the same crate reuse the cached response
+	metadata_with("serde", "1.0.0", &cache(&tempdir), &fetcher(&calls))
+		.expect("This should be Ok; qed;");
+	metadata_with("Serde", "1.0.0", &cache(&tempdir), &fetcher(&calls))
+		.expect("This should be Ok; qed;");
+	assert_eq!(calls.get(), 1);
+}
+
+#[test]
+fn metadata_with_fetches_again_stale_or_unreadable_cache_entries() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let cache_file = tempdir.path().join("serde.json");
+	let calls = Cell::new(0);
+
+	std::fs::write(&cache_file, "not json").expect("The file should be writable; qed;");
+	metadata_with("serde", "1.0.219", &cache(&tempdir), &fetcher(&calls))
+		.expect("This should be Ok; qed;");
+	assert_eq!(calls.get(), 1);
+
+	File::options()
+		.write(true)
+		.open(&cache_file)
+		.and_then(|file| {
+			file.set_modified(SystemTime::now() - Duration::from_secs(2 * 24 * 60 * 60))
+		})
+		.expect("The modification time should be writable; qed;");
+	metadata_with("serde", "1.0.219", &cache(&tempdir), &fetcher(&calls))
+		.expect("This should be Ok; qed;");
+	assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn metadata_with_fails_if_the_crate_or_the_version_doesnt_exist() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let calls = Cell::new(0);
+
+	assert!(matches!(
+		metadata_with("missing", "1.0.0", &cache(&tempdir), &fetcher(&calls)),
+		Err(Error::Descriptive(msg)) if msg == "The crate missing doesn't exist in crates.io"
+	));
+	assert!(!tempdir.path().join("missing.json").exists());
+	assert!(matches!(
+		metadata_with("serde", "3.0.0", &cache(&tempdir), &fetcher(&calls)),
+		Err(Error::Descriptive(msg)) if msg == "serde 3.0.0 doesn't exist in crates.io"
+	));
+}
+
+#[test]
+fn metadata_with_fails_if_invalid_input() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let calls = Cell::new(0);
+
+	assert!(matches!(
+		metadata_with("../serde", "1.0.0", &cache(&tempdir), &fetcher(&calls)),
+		Err(Error::Descriptive(msg)) if msg == "../serde isn't a valid crate name"
+	));
+	assert_eq!(calls.get(), 0);
+	assert!(matches!(
+		metadata_with("serde", "1.0.0", &cache(&tempdir), &|_: &str| Ok(Some("{}".to_owned()))),
+		Err(Error::Json(_))
+	));
+}
+
+#[test]
+fn metadata_with_honors_the_ttl() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let calls = Cell::new(0);
+	let cache = RegistryCache { ttl: Duration::ZERO, ..cache(&tempdir) };
+
+	metadata_with("serde", "1.0.219", &cache, &fetcher(&calls)).expect("This should be Ok; qed;");
+	metadata_with("serde", "1.0.219", &cache, &fetcher(&calls)).expect("This should be Ok; qed;");
+	assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn metadata_with_uses_stale_entries_offline() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let calls = Cell::new(0);
+	let cache = RegistryCache { ttl: Duration::ZERO, offline: true, ..cache(&tempdir) };
+
+	std::fs::write(tempdir.path().join("serde.json"), SERDE_RESPONSE)
+		.expect("The file should be writable; qed;");
+	assert_eq!(
+		metadata_with("serde", "1.0.219", &cache, &fetcher(&calls))
+			.expect("This should be Ok; qed;")
+			.latest_version,
+		"1.0.219"
+	);
+	assert!(matches!(
+		metadata_with("regex", "1.0.0", &cache, &fetcher(&calls)),
+		Err(Error::Descriptive(msg))
+			if msg == "The crate regex isn't cached, and crates.io cannot be reached offline"
+	));
+	assert_eq!(calls.get(), 0);
+}
+
+#[test]
+fn clear_removes_the_cached_entries() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let calls = Cell::new(0);
+	let cache = RegistryCache { dir: tempdir.path().join("cache"), ..cache(&tempdir) };
+
+	cache.clear().expect("This should be Ok; qed;");
+	metadata_with("serde", "1.0.219", &cache, &fetcher(&calls)).expect("This should be Ok; qed;");
+	cache.clear().expect("This should be Ok; qed;");
+	assert!(!cache.dir.exists());
+	metadata_with("serde", "1.0.219", &cache, &fetcher(&calls)).expect("This should be Ok; qed;");
+	assert_eq!(calls.get(), 2);
+}
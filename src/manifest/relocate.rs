@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities to move a crate dir without breaking the relative paths pointing at it or out of
+// it.
+
+#[cfg(test)]
+mod tests;
+
+use super::{
+	dependency_tables_mut, find_workspace_manifest, find_workspace_members, graph::normalize,
+};
+use crate::{Error, macros::debug, paths::EditSession};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	path::{Component, Path, PathBuf},
+};
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+
+/// Given the dir of a crate and the dir where it should live, this function moves the crate and
+/// rewrites the relative paths broken by the move, so the crate and the workspace enclosing it
+/// keep building:
+/// - The `path` of the dependencies pointing at the crate or out of it, declared by the crate
+///   itself, by the other workspace members, or in the `workspace.dependencies` and `patch`
+///   sections.
+/// - The `package.workspace` key of the crate, if any.
+/// - The `workspace.members`, `workspace.default-members` and `workspace.exclude` entries pointing
+///   at the crate. Glob patterns aren't rewritten, so a crate moved out of the dirs matched by a
+///   glob stops being a member unless it's added to `members` (see
+///   [`membership_status`](super::membership_status)).
+///
+/// Manifests outside the workspace enclosing the crate aren't looked up, so their paths aren't
+/// rewritten. The missing parent dirs of the new dir are created.
+///
+/// The move is transactional: if it fails, the rewritten manifests are restored before returning
+/// the error. Returns the paths of the rewritten manifests after the move, sorted.
+///
+/// # Errors
+///
+/// - If the old dir doesn't contain a manifest.
+/// - If something already exists at the new dir, or the new dir lives inside the old one.
+/// - If some of the manifests cannot be read, parsed or written.
+/// - If the dir cannot be moved, eg because the new dir lives in another filesystem.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// for (path, content) in [
+///     ("Cargo.toml", "[workspace]\nmembers = [\"core\", \"app\"]\n"),
+///     ("core/Cargo.toml", "[package]\nname = \"core\"\n\n[dependencies]\nutils = { path = \"../utils\" }\n"),
+///     ("utils/Cargo.toml", "[package]\nname = \"utils\"\n"),
+///     ("app/Cargo.toml", "[package]\nname = \"app\"\n\n[dependencies]\ncore = { path = \"../core\" }\n"),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(path).parent().unwrap()).unwrap();
+///     std::fs::write(tempdir.path().join(path), content).unwrap();
+/// }
+///
+/// let touched = rustilities::manifest::relocate_crate(
+///     tempdir.path().join("core"),
+///     tempdir.path().join("crates/core"),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(
+///     touched,
+///     vec![
+///         tempdir.path().join("Cargo.toml"),
+///         tempdir.path().join("app/Cargo.toml"),
+///         tempdir.path().join("crates/core/Cargo.toml"),
+///     ]
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(tempdir.path().join("Cargo.toml")).unwrap(),
+///     "[workspace]\nmembers = [\"crates/core\", \"app\"]\n"
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(tempdir.path().join("crates/core/Cargo.toml")).unwrap(),
+///     "[package]\nname = \"core\"\n\n[dependencies]\nutils = { path = \"../../utils\" }\n"
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(tempdir.path().join("app/Cargo.toml")).unwrap(),
+///     "[package]\nname = \"app\"\n\n[dependencies]\ncore = { path = \"../crates/core\" }\n"
+/// );
+/// ```
+pub fn relocate_crate<P: AsRef<Path>, Q: AsRef<Path>>(
+	old_dir: P,
+	new_dir: Q,
+) -> Result<Vec<PathBuf>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_relocate_crate(old_dir: &Path, new_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+		let old_dir = normalize(&std::path::absolute(old_dir)?);
+		let new_dir = normalize(&std::path::absolute(new_dir)?);
+		let crate_manifest = old_dir.join("Cargo.toml");
+		if !crate_manifest.is_file() {
+			return Err(Error::Descriptive("The provided path isn't a crate".to_owned()));
+		}
+		if new_dir.exists() {
+			return Err(Error::Descriptive(format!("{} already exists", new_dir.display())));
+		}
+		if new_dir.starts_with(&old_dir) {
+			return Err(Error::Descriptive("A crate cannot be moved inside itself".to_owned()));
+		}
+
+		let mut manifests = BTreeSet::from([crate_manifest]);
+		if let Some(workspace_toml) = find_workspace_manifest(&old_dir)
+			.and_then(|workspace_toml| std::path::absolute(workspace_toml).ok())
+		{
+			manifests.extend(
+				find_workspace_members(&workspace_toml)?.iter().map(|member| normalize(member)),
+			);
+			manifests.insert(normalize(&workspace_toml));
+		}
+		let relocation = Relocation { old_dir: &old_dir, new_dir: &new_dir };
+		// The rewritten manifests, keyed by their path before the move
+		let mut rewritten = BTreeMap::new();
+		for manifest in manifests {
+			let mut doc = std::fs::read_to_string(&manifest)?.parse::<DocumentMut>()?;
+			if relocation.update_manifest(
+				&mut doc,
+				manifest.parent().expect("A file always lives inside a dir; qed"),
+			) {
+				rewritten.insert(manifest, doc);
+			}
+		}
+
+		EditSession::run(|session| {
+			for (manifest, doc) in &rewritten {
+				debug!(path = %manifest.display(), "Writing manifest");
+				session.write(manifest, doc.to_string())?;
+			}
+			// Tracking a file in the new dir records its missing parents, so they're removed if
+			// the move fails
+			session.track(new_dir.join("Cargo.toml"))?;
+			crate::paths::ensure_dir(new_dir.parent().unwrap_or(&new_dir))?;
+			debug!(from = %old_dir.display(), to = %new_dir.display(), "Moving crate dir");
+			std::fs::rename(&old_dir, &new_dir)
+				.map_err(|source| Error::IOAt { path: old_dir.clone(), source })
+		})?;
+
+		Ok(rewritten.into_keys().map(|manifest| relocation.moved(&manifest)).collect())
+	}
+	do_relocate_crate(old_dir.as_ref(), new_dir.as_ref())
+}
+
+/// The move applied by [`relocate_crate`]. Both dirs are absolute and normalized.
+struct Relocation<'a> {
+	old_dir: &'a Path,
+	new_dir: &'a Path,
+}
+
+impl Relocation<'_> {
+	/// Where the given path lives after the move.
+	fn moved(&self, path: &Path) -> PathBuf {
+		match path.strip_prefix(self.old_dir) {
+			Ok(relative_path) => self.new_dir.join(relative_path),
+			Err(_) => path.to_path_buf(),
+		}
+	}
+
+	/// Rewrites the relative paths of the manifest living in `manifest_dir` broken by the move.
+	/// Returns whether the manifest changed.
+	fn update_manifest(&self, doc: &mut DocumentMut, manifest_dir: &Path) -> bool {
+		let mut changed = false;
+		for (_, table) in dependency_tables_mut(doc) {
+			changed |= self.update_dependencies(table, manifest_dir);
+		}
+		if let Some(patches) = doc.get_mut("patch").and_then(Item::as_table_like_mut) {
+			for (_, table) in patches.iter_mut() {
+				if let Some(table) = table.as_table_like_mut() {
+					changed |= self.update_dependencies(table, manifest_dir);
+				}
+			}
+		}
+		if let Some(path) = doc
+			.get_mut("package")
+			.and_then(|package| package.get_mut("workspace"))
+			.and_then(Item::as_value_mut)
+		{
+			changed |= self.update_value(path, manifest_dir);
+		}
+
+		let Some(workspace) = doc.get_mut("workspace").and_then(Item::as_table_like_mut) else {
+			return changed;
+		};
+		for key in ["members", "default-members", "exclude"] {
+			let Some(array) = workspace.get_mut(key).and_then(Item::as_array_mut) else {
+				continue;
+			};
+			for entry in array.iter_mut() {
+				if !entry.as_str().is_some_and(|entry| entry.contains(['*', '?', '['])) {
+					changed |= self.update_value(entry, manifest_dir);
+				}
+			}
+		}
+		if let Some(dependencies) =
+			workspace.get_mut("dependencies").and_then(Item::as_table_like_mut)
+		{
+			changed |= self.update_dependencies(dependencies, manifest_dir);
+		}
+		changed
+	}
+
+	/// Rewrites the `path` of the dependencies declared in a table of the manifest living in
+	/// `manifest_dir`. Returns whether the table changed.
+	fn update_dependencies(&self, table: &mut dyn TableLike, manifest_dir: &Path) -> bool {
+		let mut changed = false;
+		for (_, dependency) in table.iter_mut() {
+			if let Some(path) = dependency
+				.as_table_like_mut()
+				.and_then(|dependency| dependency.get_mut("path"))
+				.and_then(Item::as_value_mut)
+			{
+				changed |= self.update_value(path, manifest_dir);
+			}
+		}
+		changed
+	}
+
+	/// Rewrites a string value holding a path relative to `base_dir`, keeping its decor. Returns
+	/// whether the value changed.
+	fn update_value(&self, value: &mut Value, base_dir: &Path) -> bool {
+		let Some(path) = value.as_str() else {
+			return false;
+		};
+		let target = normalize(&base_dir.join(path));
+		let (new_base_dir, new_target) = (self.moved(base_dir), self.moved(&target));
+		// Paths whose ends are both moved, or both kept, aren't broken by the move
+		if (new_base_dir == base_dir) == (new_target == target) {
+			return false;
+		}
+		let Some(new_path) = relative_path(&new_base_dir, &new_target) else {
+			return false;
+		};
+		let decor = value.decor().clone();
+		*value = new_path.into();
+		*value.decor_mut() = decor;
+		true
+	}
+}
+
+/// The path leading from a dir to another, both absolute and normalized, using `/` as separator.
+/// Returns `None` if some component isn't valid UTF-8.
+fn relative_path(from: &Path, to: &Path) -> Option<String> {
+	let from = from.components().collect::<Vec<_>>();
+	let to = to.components().collect::<Vec<_>>();
+	let common = from.iter().zip(&to).take_while(|(from, to)| from == to).count();
+	let mut segments = vec![".."; from.len() - common];
+	for component in &to[common..] {
+		match component {
+			Component::Normal(segment) => segments.push(segment.to_str()?),
+			_ => return None,
+		}
+	}
+	Some(if segments.is_empty() { ".".to_owned() } else { segments.join("/") })
+}
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use crate::test_utils::tempdir_with_files;
+use tempfile::TempDir;
+
+fn read(tempdir: &TempDir, path: &str) -> String {
+	std::fs::read_to_string(tempdir.path().join(path)).expect("The file should be readable; qed;")
+}
+
+#[test]
+fn relocate_crate_rewrites_paths_pointing_at_or_out_of_the_crate() {
+	let tempdir = tempdir_with_files(&[
+		(
+			"Cargo.toml",
+			r#"[workspace]
+members = ["core", "app", "libs/*"]
+default-members = ["core"]
+exclude = ["core/fixtures"]
+
+[workspace.dependencies]
+core = { path = "core", version = "1.0" } # The core crate
+utils = { path = "libs/utils" }
+
+[patch.crates-io]
+core = { path = "./core" }
+"#,
+		),
+		(
+			"core/Cargo.toml",
+			r#"[package]
+name = "core"
+workspace = ".."
+
+[dependencies]
+utils = { workspace = true }
+macros = { path = "../libs/macros" }
+fixtures = { path = "fixtures" }
+
+[target.'cfg(unix)'.dev-dependencies]
+app = { path = "../app" }
+"#,
+		),
+		("core/fixtures/Cargo.toml", "[package]\nname = \"fixtures\"\n"),
+		("libs/utils/Cargo.toml", "[package]\nname = \"utils\"\n"),
+		(
+			"libs/macros/Cargo.toml",
+			"[package]\nname = \"macros\"\n\n[dev-dependencies]\ncore = { path = \"../../core\" }\n",
+		),
+		("app/Cargo.toml", "[package]\nname = \"app\"\n\n[dependencies]\ncore.workspace = true\n"),
+	]);
+
+	let touched = relocate_crate(tempdir.path().join("core"), tempdir.path().join("crates/core"))
+		.expect("This should be Ok; qed;");
+
+	assert_eq!(
+		touched,
+		vec![
+			tempdir.path().join("Cargo.toml"),
+			tempdir.path().join("crates/core/Cargo.toml"),
+			tempdir.path().join("libs/macros/Cargo.toml"),
+		]
+	);
+	assert!(!tempdir.path().join("core").exists());
+	assert_eq!(
+		read(&tempdir, "Cargo.toml"),
+		r#"[workspace]
+members = ["crates/core", "app", "libs/*"]
+default-members = ["crates/core"]
+exclude = ["crates/core/fixtures"]
+
+[workspace.dependencies]
+core = { path = "crates/core", version = "1.0" } # The core crate
+utils = { path = "libs/utils" }
+
+[patch.crates-io]
+core = { path = "crates/core" }
+"#
+	);
+	assert_eq!(
+		read(&tempdir, "crates/core/Cargo.toml"),
+		r#"[package]
+name = "core"
+workspace = "../.."
+
+[dependencies]
+utils = { workspace = true }
+macros = { path = "../../libs/macros" }
+fixtures = { path = "fixtures" }
+
+[target.'cfg(unix)'.dev-dependencies]
+app = { path = "../../app" }
+"#
+	);
+	assert_eq!(
+		read(&tempdir, "libs/macros/Cargo.toml"),
+		"[package]\nname = \"macros\"\n\n[dev-dependencies]\ncore = { path = \"../../crates/core\" }\n"
+	);
+	assert_eq!(
+		read(&tempdir, "app/Cargo.toml"),
+		"[package]\nname = \"app\"\n\n[dependencies]\ncore.workspace = true\n"
+	);
+}
+
+#[test]
+fn relocate_crate_moves_a_crate_outside_a_workspace() {
+	let tempdir = tempdir_with_files(&[
+		("a/Cargo.toml", "[package]\nname = \"a\"\n\n[dependencies]\nb = { path = \"../b\" }\n"),
+		("a/src/lib.rs", "pub fn a() {}"),
+		("b/Cargo.toml", "[package]\nname = \"b\"\n"),
+	]);
+
+	assert_eq!(
+		relocate_crate(tempdir.path().join("a"), tempdir.path().join("b/nested/a"))
+			.expect("This should be Ok; qed;"),
+		vec![tempdir.path().join("b/nested/a/Cargo.toml")]
+	);
+	assert_eq!(
+		read(&tempdir, "b/nested/a/Cargo.toml"),
+		"[package]\nname = \"a\"\n\n[dependencies]\nb = { path = \"../..\" }\n"
+	);
+	assert_eq!(read(&tempdir, "b/nested/a/src/lib.rs"), "pub fn a() {}");
+}
+
+#[test]
+fn relocate_crate_doesnt_write_manifests_without_broken_paths() {
+	let tempdir = tempdir_with_files(&[
+		("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n"),
+		("crates/a/Cargo.toml", "[package]\nname = \"a\"\n"),
+	]);
+
+	assert!(
+		relocate_crate(tempdir.path().join("crates/a"), tempdir.path().join("crates/b"))
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+	assert_eq!(read(&tempdir, "crates/b/Cargo.toml"), "[package]\nname = \"a\"\n");
+	assert_eq!(read(&tempdir, "Cargo.toml"), "[workspace]\nmembers = [\"crates/*\"]\n");
+}
+
+#[test]
+fn relocate_crate_rolls_back_if_the_crate_cannot_be_moved() {
+	let workspace_manifest = "[workspace]\nmembers = [\"a\"]\n";
+	let tempdir = tempdir_with_files(&[
+		("Cargo.toml", workspace_manifest),
+		("a/Cargo.toml", "[package]\nname = \"a\"\n"),
+		("file", ""),
+	]);
+
+	assert!(matches!(
+		relocate_crate(tempdir.path().join("a"), tempdir.path().join("file/a")),
+		Err(Error::IOAt { .. })
+	));
+	assert_eq!(read(&tempdir, "Cargo.toml"), workspace_manifest);
+	assert_eq!(read(&tempdir, "a/Cargo.toml"), "[package]\nname = \"a\"\n");
+}
+
+#[test]
+fn relocate_crate_fails_if_invalid_input() {
+	let tempdir = tempdir_with_files(&[
+		("a/Cargo.toml", "[package]\nname = \"a\"\n"),
+		("b/Cargo.toml", "[package]\nname = \"b\"\n"),
+		("c/src/lib.rs", ""),
+	]);
+
+	assert!(matches!(
+		relocate_crate(tempdir.path().join("c"), tempdir.path().join("d")),
+		Err(Error::Descriptive(msg)) if msg == "The provided path isn't a crate"
+	));
+	assert!(matches!(
+		relocate_crate(tempdir.path().join("a"), tempdir.path().join("b")),
+		Err(Error::Descriptive(msg)) if msg == format!("{} already exists", tempdir.path().join("b").display())
+	));
+	assert!(matches!(
+		relocate_crate(tempdir.path().join("a"), tempdir.path().join("a/nested")),
+		Err(Error::Descriptive(msg)) if msg == "A crate cannot be moved inside itself"
+	));
+}
+
+#[test]
+fn relative_path_works() {
+	assert_eq!(relative_path(Path::new("/a/b"), Path::new("/a/c/d")), Some("../c/d".to_owned()));
+	assert_eq!(relative_path(Path::new("/a/b"), Path::new("/a/b")), Some(".".to_owned()));
+	assert_eq!(relative_path(Path::new("/a/b/c"), Path::new("/a")), Some("../..".to_owned()));
+	assert_eq!(relative_path(Path::new("/a"), Path::new("/a/b")), Some("b".to_owned()));
+}
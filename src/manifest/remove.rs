@@ -0,0 +1,335 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Removal of manifest keys that doesn't leave stray comments and blank lines behind.
+
+#[cfg(test)]
+mod tests;
+
+use super::roundtrip::test_verify;
+use crate::{Error, macros::debug};
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, RawString, Table, TableLike, Value};
+
+/// Given the path to a manifest and the key path of an entry, eg `["dependencies", "serde"]` or
+/// `["target", "cfg(unix)", "dependencies"]`, this function removes the entry from the manifest.
+/// Returns whether the entry existed.
+///
+/// The comments describing the entry go away with it, as well as the comments at the end of a
+/// removed table, while the blank lines separating the surrounding entries are kept as they were:
+/// - The comment lines right above the entry, or above the header of a table, are removed. Comments
+///   separated from the entry by a blank line are kept.
+/// - The comment lines ending the body of a removed table, ie those following its last entry and
+///   separated from the next header by a blank line, are removed.
+/// - Removing the first entry of a table, or the first table of the manifest, doesn't leave blank
+///   lines at its beginning.
+///
+/// # Errors
+///
+/// - If the key path is empty.
+/// - If the manifest cannot be read, parsed or written.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(
+///     &manifest_path,
+///     r#"[package]
+/// name = "test"
+///
+/// [dependencies]
+/// ## Only needed by the old API
+/// legacy = "0.1"
+///
+/// serde = "1.0"
+///
+/// ## Local crates
+/// core = { path = "../core" }
+/// "#,
+/// ).unwrap();
+///
+/// assert!(rustilities::manifest::remove_key(&manifest_path, &["dependencies", "legacy"]).unwrap());
+/// assert!(!rustilities::manifest::remove_key(&manifest_path, &["dependencies", "legacy"]).unwrap());
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&manifest_path).unwrap(),
+///     r#"[package]
+/// name = "test"
+///
+/// [dependencies]
+/// serde = "1.0"
+///
+/// ## Local crates
+/// core = { path = "../core" }
+/// "#
+/// );
+/// ```
+pub fn remove_key<P: AsRef<Path>>(manifest_path: P, key_path: &[&str]) -> Result<bool, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_remove_key(manifest_path: &Path, key_path: &[&str]) -> Result<bool, Error> {
+		if key_path.is_empty() {
+			return Err(Error::Descriptive("The key path cannot be empty".to_owned()));
+		}
+		let content = std::fs::read_to_string(manifest_path)?;
+		let mut doc = content.parse::<DocumentMut>()?;
+		if remove_item(&mut doc, key_path).is_none() {
+			return Ok(false);
+		}
+		let edited = doc.to_string();
+		test_verify(&content, &edited, &[key_path]);
+		debug!(path = %manifest_path.display(), "Writing manifest");
+		std::fs::write(manifest_path, edited)?;
+		Ok(true)
+	}
+	do_remove_key(manifest_path.as_ref(), key_path)
+}
+
+/// Given the path to a manifest and the key path of a table, this function removes the table if
+/// it doesn't contain any entry, as [`remove_key`] does. Returns whether the table was removed, so
+/// callers removing entries one by one can tidy up the tables they leave empty.
+///
+/// # Errors
+///
+/// - If the key path is empty.
+/// - If the entry exists but isn't a table.
+/// - If the manifest cannot be read, parsed or written.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(
+///     &manifest_path,
+///     "[package]\nname = \"test\"\n\n## Used by the tests\n[dev-dependencies]\n\n[features]\nstd = []\n",
+/// ).unwrap();
+///
+/// assert!(rustilities::manifest::remove_table_if_empty(&manifest_path, &["dev-dependencies"]).unwrap());
+/// assert!(!rustilities::manifest::remove_table_if_empty(&manifest_path, &["features"]).unwrap());
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&manifest_path).unwrap(),
+///     "[package]\nname = \"test\"\n\n[features]\nstd = []\n"
+/// );
+/// ```
+pub fn remove_table_if_empty<P: AsRef<Path>>(
+	manifest_path: P,
+	key_path: &[&str],
+) -> Result<bool, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_remove_table_if_empty(manifest_path: &Path, key_path: &[&str]) -> Result<bool, Error> {
+		if key_path.is_empty() {
+			return Err(Error::Descriptive("The key path cannot be empty".to_owned()));
+		}
+		let content = std::fs::read_to_string(manifest_path)?;
+		let mut doc = content.parse::<DocumentMut>()?;
+		let Some(item) = key_path.iter().try_fold(doc.as_item(), |item, key| item.get(key)) else {
+			return Ok(false);
+		};
+		let is_empty = match item {
+			Item::Table(table) => table.is_empty(),
+			Item::Value(Value::InlineTable(table)) => table.is_empty(),
+			_ =>
+				return Err(Error::Descriptive(format!(
+					"The `{}` key isn't a table",
+					key_path.join(".")
+				))),
+		};
+		if !is_empty {
+			return Ok(false);
+		}
+		remove_item(&mut doc, key_path);
+		let edited = doc.to_string();
+		test_verify(&content, &edited, &[key_path]);
+		debug!(path = %manifest_path.display(), "Writing manifest");
+		std::fs::write(manifest_path, edited)?;
+		Ok(true)
+	}
+	do_remove_table_if_empty(manifest_path.as_ref(), key_path)
+}
+
+/// Removes the entry at the given key path from the document, cleaning up its comments as
+/// [`remove_key`] describes. Returns the removed item, if any.
+pub(super) fn remove_item(doc: &mut DocumentMut, key_path: &[&str]) -> Option<Item> {
+	let (last_key, parent_keys) = key_path.split_last()?;
+	let parent = parent_keys.iter().try_fold(doc.as_item(), |item, key| item.get(key))?;
+	let item = parent.get(last_key)?;
+	if is_value_like(item) {
+		let parent = parent_keys
+			.iter()
+			.try_fold(doc.as_item_mut(), |item, key| item.get_mut(key))?
+			.as_table_like_mut()?;
+		return remove_entry(parent, last_key);
+	}
+
+	// The header is the first one of the removed tables, eg the header of `[target.'cfg(unix)'
+	// .dependencies]` for the implicit `target` table
+	let mut removed_headers = Vec::new();
+	collect_headers(item, &mut removed_headers);
+	let removed_header = removed_headers
+		.into_iter()
+		.filter_map(|table| Some((table.position()?, raw_decor(table.decor().prefix()).to_owned())))
+		.min_by_key(|(position, _)| *position);
+
+	let removed = parent_keys
+		.iter()
+		.try_fold(doc.as_item_mut(), |item, key| item.get_mut(key))?
+		.as_table_like_mut()?
+		.remove(last_key)?;
+
+	if let Some((position, removed_prefix)) = removed_header {
+		let mut positions = Vec::new();
+		for_each_header_mut(doc.as_table_mut(), &mut |table| positions.extend(table.position()));
+		let at_start = !doc.iter().any(|(_, item)| is_value_like(item)) &&
+			!positions.iter().any(|other| *other < position);
+		match positions.into_iter().filter(|other| *other > position).min() {
+			Some(next) => for_each_header_mut(doc.as_table_mut(), &mut |table| {
+				if table.position() == Some(next) {
+					let prefix = merge_prefixes(
+						&removed_prefix,
+						raw_decor(table.decor().prefix()),
+						true,
+						at_start,
+					);
+					table.decor_mut().set_prefix(prefix);
+				}
+			}),
+			// The removed table was the last one, so the comments ending its body are at the end of
+			// the document
+			None => {
+				let trailing = doc.trailing().as_str().unwrap_or_default();
+				let (lines, indent) = prefix_lines(trailing);
+				let comments = lines.iter().take_while(|line| !is_blank(line)).count();
+				let trailing = format!("{}{indent}", lines[comments..].concat());
+				doc.set_trailing(trailing);
+			},
+		}
+	}
+	Some(removed)
+}
+
+/// Removes a key-value pair, or a dotted table, from a table, merging its decor into the decor of
+/// the following pair. Other items, such as subtables, are just removed.
+pub(super) fn remove_entry(table: &mut dyn TableLike, key: &str) -> Option<Item> {
+	if !table.get(key).is_some_and(is_value_like) {
+		return table.remove(key);
+	}
+	let keys = table
+		.iter()
+		.filter(|(_, item)| is_value_like(item))
+		.map(|(key, _)| key.to_owned())
+		.collect::<Vec<_>>();
+	let index = keys.iter().position(|other| other == key)?;
+	let removed_prefix = table
+		.key(key)
+		.map(|key| raw_decor(key.leaf_decor().prefix()).to_owned())
+		.unwrap_or_default();
+	let removed = table.remove(key)?;
+
+	if let Some(next) = keys.get(index + 1) &&
+		let Some(mut next) = table.key_mut(next)
+	{
+		let prefix = merge_prefixes(
+			&removed_prefix,
+			raw_decor(next.leaf_decor().prefix()),
+			false,
+			index == 0,
+		);
+		next.leaf_decor_mut().set_prefix(prefix);
+	}
+	Some(removed)
+}
+
+/// Whether an item is written as a `key = value` line, rather than under a header.
+fn is_value_like(item: &Item) -> bool {
+	match item {
+		Item::Value(_) => true,
+		Item::Table(table) => table.is_dotted(),
+		_ => false,
+	}
+}
+
+/// Collects the tables written with a header found in an item, including the item itself.
+fn collect_headers<'a>(item: &'a Item, headers: &mut Vec<&'a Table>) {
+	match item {
+		Item::Table(table) => {
+			if !table.is_implicit() && !table.is_dotted() {
+				headers.push(table);
+			}
+			table.iter().for_each(|(_, item)| collect_headers(item, headers));
+		},
+		Item::ArrayOfTables(array) =>
+			for table in array.iter() {
+				headers.push(table);
+				table.iter().for_each(|(_, item)| collect_headers(item, headers));
+			},
+		_ => (),
+	}
+}
+
+/// Calls `f` with every table written with a header found inside a table.
+fn for_each_header_mut(table: &mut Table, f: &mut dyn FnMut(&mut Table)) {
+	for (_, item) in table.iter_mut() {
+		match item {
+			Item::Table(table) => {
+				if !table.is_implicit() && !table.is_dotted() {
+					f(table);
+				}
+				for_each_header_mut(table, f);
+			},
+			Item::ArrayOfTables(array) =>
+				for table in array.iter_mut() {
+					f(table);
+					for_each_header_mut(table, f);
+				},
+			_ => (),
+		}
+	}
+}
+
+/// Builds the decor preceding the entry that follows a removed one:
+/// - The comment lines right above the removed entry described it, so they're dropped, while the
+///   blank lines and comments before them are kept, followed by the decor of the next entry.
+/// - If `ends_body` is set, the comment lines starting the next decor ended the body of the removed
+///   table, so they're dropped if a blank line separates them from the next entry.
+/// - The blank lines at the junction are merged, and removed if `at_start` is set.
+fn merge_prefixes(removed: &str, next: &str, ends_body: bool, at_start: bool) -> String {
+	let (removed_lines, _) = prefix_lines(removed);
+	let attached = removed_lines.iter().rev().take_while(|line| !is_blank(line)).count();
+	let kept = &removed_lines[..removed_lines.len() - attached];
+
+	let (mut next_lines, indent) = prefix_lines(next);
+	if ends_body {
+		let comments = next_lines.iter().take_while(|line| !is_blank(line)).count();
+		if comments < next_lines.len() {
+			next_lines.drain(..comments);
+		}
+	}
+	if !kept.is_empty() || at_start {
+		let blanks = next_lines.iter().take_while(|line| is_blank(line)).count();
+		next_lines.drain(..blanks);
+	}
+
+	let mut lines = kept.iter().chain(&next_lines).copied().collect::<Vec<_>>();
+	if at_start {
+		let blanks = lines.iter().take_while(|line| is_blank(line)).count();
+		lines.drain(..blanks);
+	}
+	format!("{}{indent}", lines.concat())
+}
+
+/// Splits a decor into its lines, each of them ending with a line break, and the indentation of the
+/// line the decor precedes.
+fn prefix_lines(decor: &str) -> (Vec<&str>, &str) {
+	let end = decor.rfind('\n').map_or(0, |index| index + 1);
+	(decor[..end].split_inclusive('\n').collect(), &decor[end..])
+}
+
+fn is_blank(line: &str) -> bool {
+	line.trim().is_empty()
+}
+
+fn raw_decor(raw: Option<&RawString>) -> &str {
+	raw.and_then(RawString::as_str).unwrap_or_default()
+}
@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+fn manifest(content: &str) -> (TempDir, std::path::PathBuf) {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let manifest_path = tempdir.path().join("Cargo.toml");
+	std::fs::write(&manifest_path, content).expect("The manifest should be writable; qed;");
+	(tempdir, manifest_path)
+}
+
+fn read(manifest_path: &Path) -> String {
+	std::fs::read_to_string(manifest_path).expect("The manifest should be readable; qed;")
+}
+
+#[test]
+fn remove_key_removes_the_comments_describing_the_entry() {
+	let (_tempdir, manifest_path) = manifest(
+		r#"[dependencies]
+# Serialization
+serde = "1.0"
+# Remove once the API is stable
+legacy = "0.1" # Pinned
+
+# Async
+tokio = "1"
+"#,
+	);
+
+	assert!(
+		remove_key(&manifest_path, &["dependencies", "legacy"]).expect("This should be Ok; qed;")
+	);
+	assert_eq!(
+		read(&manifest_path),
+		r#"[dependencies]
+# Serialization
+serde = "1.0"
+
+# Async
+tokio = "1"
+"#
+	);
+
+	assert!(
+		remove_key(&manifest_path, &["dependencies", "serde"]).expect("This should be Ok; qed;")
+	);
+	assert_eq!(read(&manifest_path), "[dependencies]\n# Async\ntokio = \"1\"\n");
+}
+
+#[test]
+fn remove_key_keeps_detached_comments_and_group_separators() {
+	let (_tempdir, manifest_path) = manifest(
+		r#"[dependencies]
+a = "1"
+b = "1"
+
+c = "1"
+
+# ---- Local crates ----
+
+# The core
+core = { path = "../core" }
+utils = { path = "../utils" }
+"#,
+	);
+
+	assert!(remove_key(&manifest_path, &["dependencies", "b"]).expect("This should be Ok; qed;"));
+	assert!(
+		remove_key(&manifest_path, &["dependencies", "core"]).expect("This should be Ok; qed;")
+	);
+	assert_eq!(
+		read(&manifest_path),
+		r#"[dependencies]
+a = "1"
+
+c = "1"
+
+# ---- Local crates ----
+
+utils = { path = "../utils" }
+"#
+	);
+}
+
+#[test]
+fn remove_key_removes_tables_with_their_comments() {
+	let (_tempdir, manifest_path) = manifest(
+		r#"[package]
+name = "test"
+
+# Runtime dependencies
+[dependencies]
+serde = "1.0"
+# tokio = "1"
+
+# Used by the tests
+[dev-dependencies]
+tempfile = "3"
+
+[features]
+std = []
+"#,
+	);
+
+	assert!(remove_key(&manifest_path, &["dependencies"]).expect("This should be Ok; qed;"));
+	assert_eq!(
+		read(&manifest_path),
+		r#"[package]
+name = "test"
+
+# Used by the tests
+[dev-dependencies]
+tempfile = "3"
+
+[features]
+std = []
+"#
+	);
+
+	assert!(remove_key(&manifest_path, &["package"]).expect("This should be Ok; qed;"));
+	assert_eq!(
+		read(&manifest_path),
+		"# Used by the tests\n[dev-dependencies]\ntempfile = \"3\"\n\n[features]\nstd = []\n"
+	);
+}
+
+#[test]
+fn remove_key_removes_the_comments_ending_the_last_table() {
+	let (_tempdir, manifest_path) = manifest(
+		r#"[package]
+name = "test"
+
+[dependencies]
+serde = "1.0"
+# tokio = "1"
+"#,
+	);
+
+	assert!(remove_key(&manifest_path, &["dependencies"]).expect("This should be Ok; qed;"));
+	assert_eq!(read(&manifest_path), "[package]\nname = \"test\"\n");
+}
+
+#[test]
+fn remove_key_removes_implicit_tables_and_inline_entries() {
+	let (_tempdir, manifest_path) = manifest(
+		r#"[package]
+name = "test"
+
+[target.'cfg(unix)'.dependencies]
+libc = { version = "0.2", default-features = false }
+
+[target.'cfg(windows)'.dependencies]
+windows = "0.58"
+
+[features]
+std = []
+"#,
+	);
+
+	assert!(
+		remove_key(&manifest_path, &["target", "cfg(unix)", "dependencies", "libc", "version"])
+			.expect("This should be Ok; qed;")
+	);
+	assert!(remove_key(&manifest_path, &["target"]).expect("This should be Ok; qed;"));
+	assert_eq!(read(&manifest_path), "[package]\nname = \"test\"\n\n[features]\nstd = []\n");
+}
+
+#[test]
+fn remove_key_returns_false_if_the_key_doesnt_exist() {
+	let content = "[package]\nname = \"test\"\n";
+	let (_tempdir, manifest_path) = manifest(content);
+
+	assert!(
+		!remove_key(&manifest_path, &["dependencies", "serde"]).expect("This should be Ok; qed;")
+	);
+	assert!(
+		!remove_key(&manifest_path, &["package", "name", "x"]).expect("This should be Ok; qed;")
+	);
+	assert_eq!(read(&manifest_path), content);
+	assert!(matches!(
+		remove_key(&manifest_path, &[]),
+		Err(Error::Descriptive(msg)) if msg == "The key path cannot be empty"
+	));
+}
+
+#[test]
+fn remove_table_if_empty_only_removes_empty_tables() {
+	let (_tempdir, manifest_path) = manifest(
+		r#"[package]
+name = "test"
+metadata = {}
+
+[dependencies]
+serde = { features = [] }
+"#,
+	);
+
+	assert!(
+		!remove_table_if_empty(&manifest_path, &["dependencies"]).expect("This should be Ok; qed;")
+	);
+	assert!(
+		!remove_table_if_empty(&manifest_path, &["dev-dependencies"])
+			.expect("This should be Ok; qed;")
+	);
+	assert!(
+		remove_table_if_empty(&manifest_path, &["package", "metadata"])
+			.expect("This should be Ok; qed;")
+	);
+	assert!(
+		remove_key(&manifest_path, &["dependencies", "serde", "features"])
+			.expect("This should be Ok; qed;")
+	);
+	assert!(
+		remove_table_if_empty(&manifest_path, &["dependencies", "serde"])
+			.expect("This should be Ok; qed;")
+	);
+	assert!(
+		remove_table_if_empty(&manifest_path, &["dependencies"]).expect("This should be Ok; qed;")
+	);
+	assert_eq!(read(&manifest_path), "[package]\nname = \"test\"\n");
+
+	assert!(matches!(
+		remove_table_if_empty(&manifest_path, &["package", "name"]),
+		Err(Error::Descriptive(msg)) if msg == "The `package.name` key isn't a table"
+	));
+}
+
+#[test]
+fn merge_prefixes_works() {
+	assert_eq!(merge_prefixes("# removed\n", "\n# next\n", false, false), "\n# next\n");
+	assert_eq!(merge_prefixes("# removed\n", "\n# next\n", false, true), "# next\n");
+	assert_eq!(merge_prefixes("\n# kept\n\n# removed\n", "\n", false, false), "\n# kept\n\n");
+	assert_eq!(merge_prefixes("\n", "# body\n\n# next\n", true, false), "\n# next\n");
+	assert_eq!(merge_prefixes("\n", "# next\n", true, false), "\n# next\n");
+	assert_eq!(merge_prefixes("", "\n  # next\n  ", false, true), "  # next\n  ");
+}
@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Checks that programmatic edits of a manifest only touch the keys they're meant to.
+
+#[cfg(test)]
+mod tests;
+
+use crate::Error;
+use std::collections::BTreeMap;
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+/// The data contained in a TOML item, ignoring how it's written: comments, whitespace, the quotes
+/// of the strings, or whether a table is inline or has a header don't matter.
+#[derive(Debug, PartialEq)]
+enum Node {
+	Table(BTreeMap<String, Node>),
+	Array(Vec<Node>),
+	/// A scalar value, rendered so values of different types never compare equal, eg `"1"` and `1`.
+	Scalar(String),
+}
+
+impl Node {
+	fn from_item(item: &Item) -> Option<Self> {
+		match item {
+			Item::None => None,
+			Item::Value(value) => Some(Self::from_value(value)),
+			Item::Table(table) => Some(Self::from_table(table)),
+			Item::ArrayOfTables(array) =>
+				Some(Self::Array(array.iter().map(Self::from_table).collect())),
+		}
+	}
+
+	fn from_table(table: &Table) -> Self {
+		Self::Table(
+			table
+				.iter()
+				.filter_map(|(key, item)| Some((key.to_owned(), Self::from_item(item)?)))
+				.collect(),
+		)
+	}
+
+	fn from_value(value: &Value) -> Self {
+		match value {
+			Value::String(string) => Self::Scalar(format!("{:?}", string.value())),
+			Value::Integer(integer) => Self::Scalar(format!("integer {}", integer.value())),
+			Value::Float(float) => Self::Scalar(format!("float {:?}", float.value())),
+			Value::Boolean(boolean) => Self::Scalar(boolean.value().to_string()),
+			Value::Datetime(datetime) => Self::Scalar(format!("datetime {}", datetime.value())),
+			Value::Array(array) => Self::Array(array.iter().map(Self::from_value).collect()),
+			Value::InlineTable(table) => Self::Table(
+				table
+					.iter()
+					.map(|(key, value)| (key.to_owned(), Self::from_value(value)))
+					.collect(),
+			),
+		}
+	}
+}
+
+/// Given two versions of a TOML document, typically a manifest before and after an edit, this
+/// function returns the key paths whose data differ between them, sorted. Formatting is ignored,
+/// so reformatting a value or turning an inline table into a table with a header isn't a change.
+///
+/// Tables are compared key by key, so the paths point to the keys added, removed or modified inside
+/// them, while arrays are compared as a whole. The path of a table is only returned if it's an
+/// empty table that was added or removed, or if it was replaced by another kind of value.
+///
+/// # Errors
+///
+/// - If any of the documents isn't valid TOML.
+///
+/// # Examples
+///
+/// ```
+/// let original = "[dependencies]\nserde = { version = \"1.0\" }\nsyn = \"2.0\"\n";
+/// let edited = "[dependencies.serde]\nversion = \"1.0\"\nfeatures = [\"derive\"]\n";
+///
+/// assert_eq!(
+///     rustilities::manifest::changed_keys(original, edited).unwrap(),
+///     vec![vec!["dependencies", "serde", "features"], vec!["dependencies", "syn"]]
+/// );
+/// ```
+pub fn changed_keys(original: &str, edited: &str) -> Result<Vec<Vec<String>>, Error> {
+	let original = Node::from_table(original.parse::<DocumentMut>()?.as_table());
+	let edited = Node::from_table(edited.parse::<DocumentMut>()?.as_table());
+	let mut changed = Vec::new();
+	diff(Some(&original), Some(&edited), &mut Vec::new(), &mut changed);
+	Ok(changed)
+}
+
+/// Given two versions of a TOML document and the key paths an edit was meant to change, this
+/// function checks that the edit didn't change anything else, as found by [`changed_keys`].
+/// Changing a key inside an intended path, eg `["dependencies", "serde", "features"]` when
+/// `["dependencies", "serde"]` is intended, is expected. It's handy to test custom edits, or to
+/// double check the edits of the mutation functions.
+///
+/// # Errors
+///
+/// - If any of the documents isn't valid TOML.
+/// - If the edit changed keys that weren't intended.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::Error;
+///
+/// let original = "[package]\nname = \"test\"\n\n[dependencies]\n";
+/// let edited = "[package]\nname = \"test\"\n\n[dependencies]\nserde = \"1.0\"\n";
+///
+/// assert!(rustilities::manifest::verify_roundtrip(original, edited, &[&["dependencies", "serde"]]).is_ok());
+/// assert!(matches!(
+///     rustilities::manifest::verify_roundtrip(original, edited, &[&["package"]]),
+///     Err(Error::Descriptive(msg)) if msg == "The edit changed unexpected keys: `dependencies.serde`"
+/// ));
+/// ```
+pub fn verify_roundtrip(original: &str, edited: &str, intended: &[&[&str]]) -> Result<(), Error> {
+	let unexpected = changed_keys(original, edited)?
+		.into_iter()
+		.filter(|path| {
+			!intended.iter().any(|intended| {
+				path.len() >= intended.len() &&
+					path.iter().zip(intended.iter()).all(|(key, intended)| key == intended)
+			})
+		})
+		.map(|path| format!("`{}`", path.join(".")))
+		.collect::<Vec<_>>();
+	if unexpected.is_empty() {
+		Ok(())
+	} else {
+		Err(Error::Descriptive(format!(
+			"The edit changed unexpected keys: {}",
+			unexpected.join(", ")
+		)))
+	}
+}
+
+/// Asserts that an edit didn't change keys that weren't intended, as found by
+/// [`verify_roundtrip`], so the tests of the mutation functions catch edits touching unrelated
+/// keys. It does nothing outside the tests of this crate, so mutation functions never panic.
+#[cfg_attr(not(test), allow(unused_variables))]
+pub(super) fn test_verify(original: &str, edited: &str, intended: &[&[&str]]) {
+	#[cfg(test)]
+	if let Err(err) = verify_roundtrip(original, edited, intended) {
+		panic!("{err}");
+	}
+}
+
+/// Collects the paths whose data differ between the given nodes, found at `path`.
+fn diff(
+	original: Option<&Node>,
+	edited: Option<&Node>,
+	path: &mut Vec<String>,
+	changed: &mut Vec<Vec<String>>,
+) {
+	let empty = BTreeMap::new();
+	let (original_keys, edited_keys) = match (original, edited) {
+		(Some(Node::Table(original)), Some(Node::Table(edited))) => (original, edited),
+		(Some(Node::Table(original)), None) if !original.is_empty() => (original, &empty),
+		(None, Some(Node::Table(edited))) if !edited.is_empty() => (&empty, edited),
+		_ => {
+			if original != edited {
+				changed.push(path.clone());
+			}
+			return;
+		},
+	};
+
+	let mut keys = original_keys.keys().chain(edited_keys.keys()).collect::<Vec<_>>();
+	keys.sort();
+	keys.dedup();
+	for key in keys {
+		path.push(key.clone());
+		diff(original_keys.get(key), edited_keys.get(key), path, changed);
+		path.pop();
+	}
+}
@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+#[test]
+fn changed_keys_ignores_formatting() {
+	let original = r#"
+[package]
+name = "test" # The name
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+
+[[bin]]
+name = 'cli'
+"#;
+	let edited = r#"[package]
+name="test"
+
+[dependencies.serde]
+features = [
+    "derive",
+]
+version = '1.0'
+
+[[bin]]
+name = "cli"
+"#;
+
+	assert!(changed_keys(original, edited).expect("This should be Ok; qed;").is_empty());
+}
+
+#[test]
+fn changed_keys_finds_every_change() {
+	let original = r#"[package]
+name = "test"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+
+[features]
+std = []
+
+[[bin]]
+name = "cli"
+"#;
+	let edited = r#"[package]
+name = "test"
+version = 1
+edition = "2024"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive", "rc"] }
+syn = { version = "2.0", features = ["full"] }
+
+[features]
+
+[dev-dependencies]
+
+[[bin]]
+name = "cli"
+
+[[bin]]
+name = "other"
+"#;
+
+	assert_eq!(
+		changed_keys(original, edited).expect("This should be Ok; qed;"),
+		[
+			vec!["bin"],
+			vec!["dependencies", "serde", "features"],
+			vec!["dependencies", "syn", "features"],
+			vec!["dependencies", "syn", "version"],
+			vec!["dev-dependencies"],
+			vec!["features", "std"],
+			vec!["package", "edition"],
+			vec!["package", "version"],
+		]
+	);
+	assert!(matches!(changed_keys("[package", ""), Err(Error::TomlEdit(_))));
+}
+
+#[test]
+fn verify_roundtrip_accepts_changes_inside_the_intended_keys() {
+	let original = "[dependencies]\nserde = \"1.0\"\n";
+	let edited = "[dependencies]\nserde = { version = \"1.0\", optional = true }\n\n[features]\nserde = [\"dep:serde\"]\n";
+
+	assert!(
+		verify_roundtrip(original, edited, &[&["dependencies", "serde"], &["features"]]).is_ok()
+	);
+	assert!(matches!(
+		verify_roundtrip(original, edited, &[&["dependencies", "serde", "version"]]),
+		Err(Error::Descriptive(msg)) if msg == "The edit changed unexpected keys: `dependencies.serde`, `features.serde`"
+	));
+	assert!(matches!(
+		verify_roundtrip(original, edited, &[]),
+		Err(Error::Descriptive(msg)) if msg == "The edit changed unexpected keys: `dependencies.serde`, `features.serde`"
+	));
+}
@@ -0,0 +1,279 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! This module provides the [`Workspace`] type, a session object caching the files read by the
+//! analysis functions of this crate.
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "parsing")]
+use super::{FeatureConsistencyReport, sources};
+use super::{
+	WorkspaceGraph,
+	features::{closure_of, cycles_of},
+	graph::normalize,
+	workspace_members_of,
+};
+use crate::{Error, events::NoopSink};
+#[cfg(feature = "parsing")]
+use std::collections::BTreeMap;
+use std::{
+	cell::RefCell,
+	collections::{BTreeSet, HashMap},
+	path::{Path, PathBuf},
+	rc::Rc,
+	time::SystemTime,
+};
+use toml_edit::DocumentMut;
+#[cfg(feature = "parsing")]
+use toml_edit::Item;
+
+/// A parsed file, together with the modification time and size of the file when it was parsed.
+#[derive(Debug)]
+struct CachedFile<T> {
+	modified: SystemTime,
+	len: u64,
+	value: Rc<T>,
+}
+
+/// Parsed files keyed by path.
+#[derive(Debug)]
+struct FileCache<T> {
+	entries: RefCell<HashMap<PathBuf, CachedFile<T>>>,
+}
+
+impl<T> Default for FileCache<T> {
+	fn default() -> Self {
+		Self { entries: RefCell::new(HashMap::new()) }
+	}
+}
+
+impl<T> FileCache<T> {
+	fn get(
+		&self,
+		path: &Path,
+		load: impl FnOnce(&Path) -> Result<T, Error>,
+	) -> Result<Rc<T>, Error> {
+		let path = normalize(path);
+		let metadata = std::fs::metadata(&path)?;
+		let (modified, len) = (metadata.modified()?, metadata.len());
+		if let Some(cached) = self.entries.borrow().get(&path) &&
+			cached.modified == modified &&
+			cached.len == len
+		{
+			return Ok(Rc::clone(&cached.value));
+		}
+
+		let value = Rc::new(load(&path)?);
+		self.entries
+			.borrow_mut()
+			.insert(path, CachedFile { modified, len, value: Rc::clone(&value) });
+		Ok(value)
+	}
+
+	fn clear(&self) {
+		self.entries.borrow_mut().clear();
+	}
+}
+
+/// A session over a workspace, caching the parsed manifests and source files so tools running
+/// many queries (graph, features, unused dependencies...) don't read and parse the same files
+/// repeatedly.
+///
+/// The methods of this type behave like the functions of the same name in this module, but every
+/// file is read through the cache. A cached file is parsed again if its modification time or size
+/// changed since it was cached, so the session can live across edits. Edits that keep both of
+/// them, which are unlikely but possible in filesystems with coarse timestamps, require calling
+/// [`Workspace::clear`].
+///
+/// The session isn't meant to be shared between threads: use one session per thread instead.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::Workspace;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// for (member, manifest) in [
+///     ("core", "[package]\nname = \"core\"\n\n[features]\nstd = []\nfull = [\"std\"]"),
+///     ("cli", "[package]\nname = \"cli\"\n\n[dependencies]\ncore = { path = \"../core\" }"),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(tempdir.path().join(member).join("Cargo.toml"), manifest).unwrap();
+/// }
+/// std::fs::write(tempdir.path().join("Cargo.toml"), "[workspace]\nmembers = [\"*\"]").unwrap();
+///
+/// let workspace = Workspace::new(tempdir.path().join("Cargo.toml"));
+/// let graph = workspace.graph().unwrap();
+/// assert_eq!(graph.dependents_of("core"), vec![("cli", rustilities::manifest::DependencyKind::Normal)]);
+/// // The core manifest is already cached by the graph query.
+/// assert_eq!(
+///     workspace
+///         .feature_closure(tempdir.path().join("core/Cargo.toml"), &["full"])
+///         .unwrap()
+///         .into_iter()
+///         .collect::<Vec<_>>(),
+///     vec!["full", "std"]
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Workspace {
+	workspace_toml: PathBuf,
+	manifests: FileCache<DocumentMut>,
+	#[cfg(feature = "parsing")]
+	sources: FileCache<syn::File>,
+}
+
+impl Workspace {
+	/// Creates a session over the workspace defined by the given workspace manifest. Nothing is
+	/// read until the session is queried.
+	pub fn new<P: AsRef<Path>>(workspace_toml: P) -> Self {
+		Self {
+			workspace_toml: workspace_toml.as_ref().to_path_buf(),
+			manifests: FileCache::default(),
+			#[cfg(feature = "parsing")]
+			sources: FileCache::default(),
+		}
+	}
+
+	/// The workspace manifest of the session.
+	pub fn workspace_toml(&self) -> &Path {
+		&self.workspace_toml
+	}
+
+	/// Drops every cached file, so they're read again by the next queries.
+	pub fn clear(&self) {
+		self.manifests.clear();
+		#[cfg(feature = "parsing")]
+		self.sources.clear();
+	}
+
+	/// The parsed manifest living in the given path.
+	///
+	/// # Errors
+	///
+	/// - If the manifest cannot be read or parsed.
+	pub fn manifest<P: AsRef<Path>>(&self, manifest_path: P) -> Result<Rc<DocumentMut>, Error> {
+		self.manifests.get(manifest_path.as_ref(), |path| {
+			Ok(std::fs::read_to_string(path)?.parse::<DocumentMut>()?)
+		})
+	}
+
+	/// The parsed Rust file living in the given path.
+	///
+	/// # Errors
+	///
+	/// - If the file cannot be read or parsed.
+	#[cfg(feature = "parsing")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
+	pub fn source_file<P: AsRef<Path>>(&self, path: P) -> Result<Rc<syn::File>, Error> {
+		self.sources.get(path.as_ref(), crate::parsing::source_tree::parse_file)
+	}
+
+	/// Same as [`find_workspace_members`](super::find_workspace_members).
+	pub fn members(&self) -> Result<Vec<PathBuf>, Error> {
+		workspace_members_of(&*self.manifest(&self.workspace_toml)?, &self.workspace_toml)
+	}
+
+	/// Same as [`WorkspaceGraph::load`].
+	pub fn graph(&self) -> Result<WorkspaceGraph, Error> {
+		WorkspaceGraph::load_from(
+			&self.workspace_toml,
+			&*self.manifest(&self.workspace_toml)?,
+			&|manifest_path| self.manifest(manifest_path),
+			&NoopSink,
+		)
+	}
+
+	/// Same as [`feature_closure`](super::feature_closure).
+	pub fn feature_closure<P: AsRef<Path>>(
+		&self,
+		manifest_path: P,
+		enabled: &[&str],
+	) -> Result<BTreeSet<String>, Error> {
+		closure_of(&*self.manifest(manifest_path)?, enabled)
+	}
+
+	/// Same as [`detect_feature_cycles`](super::detect_feature_cycles).
+	pub fn detect_feature_cycles<P: AsRef<Path>>(
+		&self,
+		manifest_path: P,
+	) -> Result<Vec<Vec<String>>, Error> {
+		Ok(cycles_of(&*self.manifest(manifest_path)?))
+	}
+
+	/// Same as [`unused_dependencies`](super::unused_dependencies).
+	#[cfg(feature = "parsing")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
+	pub fn unused_dependencies<P: AsRef<Path>>(&self, crate_dir: P) -> Result<Vec<String>, Error> {
+		let crate_dir = crate_dir.as_ref();
+		sources::unused_dependencies_in(
+			&*self.manifest(crate_dir.join("Cargo.toml"))?,
+			crate_dir,
+			&|path| self.parse(path),
+		)
+	}
+
+	/// Same as [`unused_dependencies_all_members`](super::unused_dependencies_all_members), but
+	/// the members are always analyzed sequentially.
+	#[cfg(feature = "parsing")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
+	pub fn unused_dependencies_all_members(&self) -> Result<BTreeMap<String, Vec<String>>, Error> {
+		let mut unused = BTreeMap::new();
+		for manifest_path in self.members()? {
+			let name = self
+				.manifest(&manifest_path)?
+				.get("package")
+				.and_then(|package| package.get("name"))
+				.and_then(Item::as_str)
+				.map(str::to_owned)
+				.ok_or_else(|| {
+					Error::Descriptive(format!(
+						"The member manifest {} doesn't have a package name",
+						manifest_path.display()
+					))
+				})?;
+			let dependencies = self.unused_dependencies(
+				manifest_path.parent().expect("A file always lives inside a dir; qed"),
+			)?;
+			if !dependencies.is_empty() {
+				unused.insert(name, dependencies);
+			}
+		}
+		Ok(unused)
+	}
+
+	/// Same as [`undeclared_crates`](super::undeclared_crates).
+	#[cfg(feature = "parsing")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
+	pub fn undeclared_crates<P: AsRef<Path>>(&self, crate_dir: P) -> Result<Vec<String>, Error> {
+		let crate_dir = crate_dir.as_ref();
+		sources::undeclared_crates_in(
+			&*self.manifest(crate_dir.join("Cargo.toml"))?,
+			crate_dir,
+			&|path| self.parse(path),
+		)
+	}
+
+	/// Same as [`check_feature_consistency`](super::check_feature_consistency).
+	#[cfg(feature = "parsing")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "parsing")))]
+	pub fn check_feature_consistency<P: AsRef<Path>>(
+		&self,
+		crate_dir: P,
+	) -> Result<FeatureConsistencyReport, Error> {
+		let crate_dir = crate_dir.as_ref();
+		sources::feature_consistency_in(
+			&*self.manifest(crate_dir.join("Cargo.toml"))?,
+			crate_dir,
+			&|path| self.parse(path),
+		)
+	}
+
+	// The source trees own their files, so the cached file is cloned, which is still cheaper than
+	// reading and parsing it again.
+	#[cfg(feature = "parsing")]
+	fn parse(&self, path: &Path) -> Result<syn::File, Error> {
+		self.source_file(path).map(|file| (*file).clone())
+	}
+}
@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use crate::test_utils::tempdir_with_files;
+use std::{fs::File, time::Duration};
+
+// The files of a workspace whose crates have a bit of everything the session inspects.
+const WORKSPACE: &[(&str, &str)] = &[
+	("Cargo.toml", "[workspace]\nmembers = [\"*\"]\n"),
+	(
+		"core/Cargo.toml",
+		"[package]\nname = \"core\"\n\n[dependencies]\nregex = \"1\"\n\n[features]\nstd = []\nfull = [\"std\"]\nloop-a = [\"loop-b\"]\nloop-b = [\"loop-a\"]\n",
+	),
+	("core/src/lib.rs", "#[cfg(feature = \"serde\")]\nfn f() {}\n"),
+	(
+		"cli/Cargo.toml",
+		"[package]\nname = \"cli\"\n\n[dependencies]\ncore = { path = \"../core\" }\nanyhow = \"1\"\n",
+	),
+	("cli/src/main.rs", "fn main() -> anyhow::Result<()> { undeclared::run() }\n"),
+];
+
+// Moves the modification time away from the cached one, whatever the timestamp granularity.
+fn touch(path: &Path) {
+	let file = File::options().write(true).open(path).expect("The file should exist; qed;");
+	let modified = file
+		.metadata()
+		.and_then(|metadata| metadata.modified())
+		.expect("The metadata should be readable; qed;");
+	file.set_modified(modified + Duration::from_secs(10))
+		.expect("The modification time should be writable; qed;");
+}
+
+#[test]
+fn manifest_is_cached_until_the_file_changes() {
+	let tempdir = tempdir_with_files(WORKSPACE);
+	let manifest_path = tempdir.path().join("core/Cargo.toml");
+	let workspace = Workspace::new(tempdir.path().join("Cargo.toml"));
+
+	let first = workspace.manifest(&manifest_path).expect("This should be Ok; qed;");
+	let second = workspace.manifest(&manifest_path).expect("This should be Ok; qed;");
+	assert!(Rc::ptr_eq(&first, &second));
+	// The key is normalized
+	let third = workspace
+		.manifest(tempdir.path().join("cli/../core/Cargo.toml"))
+		.expect("This should be Ok; qed;");
+	assert!(Rc::ptr_eq(&first, &third));
+
+	std::fs::write(&manifest_path, "[package]\nname = \"renamed\"\n")
+		.expect("The file should be writable; qed;");
+	touch(&manifest_path);
+	let edited = workspace.manifest(&manifest_path).expect("This should be Ok; qed;");
+	assert!(!Rc::ptr_eq(&first, &edited));
+	assert_eq!(edited["package"]["name"].as_str(), Some("renamed"));
+
+	workspace.clear();
+	assert!(!Rc::ptr_eq(
+		&edited,
+		&workspace.manifest(&manifest_path).expect("This should be Ok; qed;")
+	));
+}
+
+#[cfg(feature = "parsing")]
+#[test]
+fn source_file_is_cached_until_the_file_changes() {
+	let tempdir = tempdir_with_files(WORKSPACE);
+	let path = tempdir.path().join("core/src/lib.rs");
+	let workspace = Workspace::new(tempdir.path().join("Cargo.toml"));
+
+	let first = workspace.source_file(&path).expect("This should be Ok; qed;");
+	assert!(Rc::ptr_eq(&first, &workspace.source_file(&path).expect("This should be Ok; qed;")));
+
+	std::fs::write(&path, "fn g() {}\n").expect("The file should be writable; qed;");
+	touch(&path);
+	let edited = workspace.source_file(&path).expect("This should be Ok; qed;");
+	assert!(!Rc::ptr_eq(&first, &edited));
+	assert_eq!(*edited, syn::parse_quote! { fn g() {} });
+}
+
+#[test]
+fn queries_match_the_uncached_functions() {
+	let tempdir = tempdir_with_files(WORKSPACE);
+	let workspace_toml = tempdir.path().join("Cargo.toml");
+	let core_toml = tempdir.path().join("core/Cargo.toml");
+	let workspace = Workspace::new(&workspace_toml);
+
+	assert_eq!(workspace.workspace_toml(), workspace_toml);
+	assert_eq!(
+		workspace.members().expect("This should be Ok; qed;"),
+		crate::manifest::find_workspace_members(&workspace_toml).expect("This should be Ok; qed;")
+	);
+	assert_eq!(
+		workspace.graph().expect("This should be Ok; qed;"),
+		WorkspaceGraph::load(&workspace_toml).expect("This should be Ok; qed;")
+	);
+	assert_eq!(
+		workspace
+			.feature_closure(&core_toml, &["full"])
+			.expect("This should be Ok; qed;"),
+		crate::manifest::feature_closure(&core_toml, &["full"]).expect("This should be Ok; qed;")
+	);
+	assert_eq!(
+		workspace.detect_feature_cycles(&core_toml).expect("This should be Ok; qed;"),
+		crate::manifest::detect_feature_cycles(&core_toml).expect("This should be Ok; qed;")
+	);
+}
+
+#[cfg(feature = "parsing")]
+#[test]
+fn source_queries_match_the_uncached_functions() {
+	let tempdir = tempdir_with_files(WORKSPACE);
+	let workspace_toml = tempdir.path().join("Cargo.toml");
+	let workspace = Workspace::new(&workspace_toml);
+
+	assert_eq!(
+		workspace.unused_dependencies_all_members().expect("This should be Ok; qed;"),
+		crate::manifest::unused_dependencies_all_members(&workspace_toml)
+			.expect("This should be Ok; qed;")
+	);
+	for member in ["core", "cli"] {
+		let crate_dir = tempdir.path().join(member);
+		assert_eq!(
+			workspace.undeclared_crates(&crate_dir).expect("This should be Ok; qed;"),
+			crate::manifest::undeclared_crates(&crate_dir).expect("This should be Ok; qed;")
+		);
+		assert_eq!(
+			workspace
+				.check_feature_consistency(&crate_dir)
+				.expect("This should be Ok; qed;"),
+			crate::manifest::check_feature_consistency(&crate_dir)
+				.expect("This should be Ok; qed;")
+		);
+	}
+}
+
+#[cfg(feature = "parsing")]
+#[test]
+fn queries_see_the_edits_made_during_the_session() {
+	let tempdir = tempdir_with_files(WORKSPACE);
+	let workspace = Workspace::new(tempdir.path().join("Cargo.toml"));
+	let main_path = tempdir.path().join("cli/src/main.rs");
+
+	assert_eq!(
+		workspace
+			.unused_dependencies(tempdir.path().join("cli"))
+			.expect("This should be Ok; qed;"),
+		vec!["core"]
+	);
+
+	std::fs::write(&main_path, "fn main() -> anyhow::Result<()> { core::run() }\n")
+		.expect("The file should be writable; qed;");
+	touch(&main_path);
+	assert!(
+		workspace
+			.unused_dependencies(tempdir.path().join("cli"))
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+}
+
+#[test]
+fn queries_fail_if_files_cannot_be_read_or_parsed() {
+	let tempdir = tempdir_with_files(WORKSPACE);
+	let workspace = Workspace::new(tempdir.path().join("missing/Cargo.toml"));
+
+	assert!(matches!(workspace.graph(), Err(Error::IO(_))));
+	assert!(matches!(workspace.members(), Err(Error::IO(_))));
+	assert!(matches!(
+		workspace.manifest(tempdir.path().join("core/src/lib.rs")),
+		Err(Error::TomlEdit(_))
+	));
+}
+
+#[cfg(feature = "parsing")]
+#[test]
+fn source_queries_fail_if_code_cannot_be_parsed() {
+	let tempdir = tempdir_with_files(WORKSPACE);
+	let workspace = Workspace::new(tempdir.path().join("Cargo.toml"));
+
+	std::fs::write(tempdir.path().join("core/src/lib.rs"), "fn f(")
+		.expect("The file should be writable; qed;");
+	assert!(matches!(
+		workspace.unused_dependencies(tempdir.path().join("core")),
+		Err(Error::Syn(_))
+	));
+}
@@ -0,0 +1,922 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities crossing the manifest of a crate with its source code.
+
+#[cfg(test)]
+mod tests;
+
+use super::{
+	MembershipStatus, dependency_tables, dependency_tables_mut, features::FeatureGraph,
+	find_crate_name, find_workspace_manifest, find_workspace_members, graph::normalize,
+	membership_status,
+};
+use crate::{
+	Error,
+	diagnostic::{Diagnostic, Severity, ToDiagnostics},
+	macros::debug,
+	parsing::{
+		source_tree::{ParseFile, SourceTree, parse_file},
+		target_roots, target_trees_with,
+	},
+};
+use proc_macro2::{Ident, Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	path::{Path, PathBuf},
+};
+use syn::{
+	Attribute, ItemExternCrate, ItemUse, Lit, Token, UseTree, punctuated::Punctuated, visit::Visit,
+};
+use toml_edit::{DocumentMut, Item, Key, TableLike, Value};
+
+/// Names that can be the first segment of a path without referring to an external crate.
+const NON_CRATE_ROOTS: [&str; 26] = [
+	"std",
+	"core",
+	"alloc",
+	"proc_macro",
+	"test",
+	"crate",
+	"self",
+	"super",
+	"Self",
+	"bool",
+	"char",
+	"str",
+	"u8",
+	"u16",
+	"u32",
+	"u64",
+	"u128",
+	"usize",
+	"i8",
+	"i16",
+	"i32",
+	"i64",
+	"i128",
+	"isize",
+	"f32",
+	"f64",
+];
+
+/// Given a crate dir, this function returns the crates referenced in its source code that aren't
+/// declared as dependencies in its manifest. This catches the situations where the code only
+/// compiles because a crate is a transitive dependency, which breaks as soon as that dependency
+/// stops depending on it.
+///
+/// The source code of every target following cargo conventions is analyzed (see
+/// [`SourceTree::load_crate`]). A crate is considered referenced if it's the first segment of a
+/// `use` declaration, an `extern crate` item, or a path with several segments (eg,
+/// `serde::Serialize` or `#[serde_with::serde_as]`). Paths starting by the standard crates
+/// (`std`, `core`, `alloc`, `proc_macro`, `test`), primitive types, local modules or names
+/// imported by `use` declarations aren't taken into account, neither are capitalized names, as
+/// they're types rather than crates. Code inside macro invocations isn't analyzed.
+///
+/// The dependencies of every kind are taken into account, and the crate itself can be referenced
+/// by its name (as binaries do). The output is sorted and uses the crate names as they appear in
+/// code, ie with `_` instead of `-`.
+///
+/// # Errors
+///
+/// - If the crate manifest cannot be read or parsed.
+/// - If the source code cannot be read or parsed.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir_all(tempdir.path().join("src")).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("Cargo.toml"),
+///     "[package]\nname = \"test\"\n\n[dependencies]\nserde = \"1.0\"",
+/// ).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("src").join("lib.rs"),
+///     r#"
+/// mod utils;
+/// use serde::Serialize;
+/// use std::fmt;
+///
+/// #[derive(Serialize)]
+/// struct Foo;
+///
+/// fn foo() -> fmt::Result {
+///     let _ = serde_json::to_string(&Foo);
+///     utils::bar();
+///     Ok(())
+/// }
+/// "#,
+/// ).unwrap();
+/// std::fs::write(tempdir.path().join("src").join("utils.rs"), "pub fn bar() {}").unwrap();
+///
+/// assert_eq!(rustilities::manifest::undeclared_crates(tempdir.path()).unwrap(), vec!["serde_json"]);
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip_all, fields(crate_dir = %crate_dir.as_ref().display()))
+)]
+pub fn undeclared_crates<P: AsRef<Path>>(crate_dir: P) -> Result<Vec<String>, Error> {
+	let crate_dir = crate_dir.as_ref();
+	let doc = std::fs::read_to_string(crate_dir.join("Cargo.toml"))?.parse::<DocumentMut>()?;
+	undeclared_crates_in(&doc, crate_dir, &parse_file)
+}
+
+/// Same as [`undeclared_crates`], given the manifest of the crate and the way to parse its files.
+pub(super) fn undeclared_crates_in(
+	doc: &DocumentMut,
+	crate_dir: &Path,
+	parse: &ParseFile,
+) -> Result<Vec<String>, Error> {
+	let mut declared = dependency_tables(doc)
+		.into_iter()
+		.flat_map(|(_, table)| table.iter().map(|(key, _)| key.replace('-', "_")))
+		.collect::<BTreeSet<_>>();
+	for key in ["package", "lib"] {
+		if let Some(name) = doc.get(key).and_then(|table| table.get("name")).and_then(Item::as_str)
+		{
+			declared.insert(name.replace('-', "_"));
+		}
+	}
+
+	let mut referenced = BTreeSet::new();
+	for tree in SourceTree::load_crate_with(crate_dir, parse)? {
+		let mut collector = CrateReferences::default();
+		tree.files().iter().for_each(|file| collector.visit_file(&file.ast));
+		let local = tree
+			.module_names()
+			.into_iter()
+			.chain(collector.imported)
+			.collect::<BTreeSet<_>>();
+		referenced.extend(collector.roots.into_iter().filter(|root| !local.contains(root)));
+	}
+
+	Ok(referenced
+		.into_iter()
+		.filter(|root| {
+			!declared.contains(root) &&
+				!NON_CRATE_ROOTS.contains(&root.as_str()) &&
+				!root.starts_with(char::is_uppercase)
+		})
+		.collect())
+}
+
+/// Collects the first segments of the paths found in the code, as well as the names imported by
+/// `use` declarations.
+#[derive(Default)]
+struct CrateReferences {
+	roots: BTreeSet<String>,
+	imported: BTreeSet<String>,
+}
+
+impl CrateReferences {
+	fn visit_use_tree_root(&mut self, tree: &UseTree) {
+		match tree {
+			UseTree::Path(path) => self.roots.insert(path.ident.to_string()),
+			UseTree::Name(name) => self.roots.insert(name.ident.to_string()),
+			UseTree::Rename(rename) => self.roots.insert(rename.ident.to_string()),
+			UseTree::Group(group) => {
+				group.items.iter().for_each(|tree| self.visit_use_tree_root(tree));
+				true
+			},
+			UseTree::Glob(_) => true,
+		};
+	}
+
+	fn collect_imported(&mut self, tree: &UseTree) {
+		match tree {
+			UseTree::Path(path) => self.collect_imported(&path.tree),
+			UseTree::Name(name) => {
+				self.imported.insert(name.ident.to_string());
+			},
+			UseTree::Rename(rename) => {
+				self.imported.insert(rename.rename.to_string());
+			},
+			UseTree::Group(group) =>
+				group.items.iter().for_each(|tree| self.collect_imported(tree)),
+			UseTree::Glob(_) => (),
+		}
+	}
+}
+
+impl<'ast> Visit<'ast> for CrateReferences {
+	fn visit_item_use(&mut self, item_use: &'ast ItemUse) {
+		self.visit_use_tree_root(&item_use.tree);
+		// `use foo::bar;` makes `bar::baz` a valid path, but `use foo;` doesn't make `foo` local
+		if !matches!(item_use.tree, UseTree::Name(_)) {
+			self.collect_imported(&item_use.tree);
+		}
+	}
+
+	fn visit_item_extern_crate(&mut self, item: &'ast ItemExternCrate) {
+		self.roots.insert(item.ident.to_string());
+		if let Some((_, rename)) = &item.rename {
+			self.imported.insert(rename.to_string());
+		}
+	}
+
+	fn visit_attribute(&mut self, attr: &'ast Attribute) {
+		// The derived macros are parsed as tokens, so they're visited explicitly
+		if attr.path().is_ident("derive") &&
+			let Ok(paths) =
+				attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+		{
+			paths.iter().for_each(|path| self.visit_path(path));
+		}
+		syn::visit::visit_attribute(self, attr);
+	}
+
+	fn visit_path(&mut self, path: &'ast syn::Path) {
+		if (path.leading_colon.is_some() || path.segments.len() > 1) &&
+			let Some(first) = path.segments.first()
+		{
+			self.roots.insert(first.ident.to_string());
+		}
+		syn::visit::visit_path(self, path);
+	}
+}
+
+/// Given a crate dir, this function returns the dependencies declared in its manifest that aren't
+/// referenced by its source code, so they can be removed.
+///
+/// The source code of every target is analyzed, including the integration tests, the benches, the
+/// examples (`examples/*.rs` and `examples/*/main.rs`) and the build script, as well as the code
+/// inside macro invocations. A dependency is referenced if its name (with `-` replaced by `_`)
+/// starts a path (eg, `serde::Serialize` or `serde_json::json!`), or follows a `use` or an `extern
+/// crate`. The dependencies of every kind are taken into account, regardless of the target using
+/// them. Dependencies only needed for their side effects, eg to enable a feature of a transitive
+/// dependency, are reported as unused. The output is sorted and uses the names of the manifest.
+///
+/// # Errors
+///
+/// - If the crate manifest cannot be read or parsed.
+/// - If the source code cannot be read or parsed.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir_all(tempdir.path().join("src")).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("Cargo.toml"),
+///     "[package]\nname = \"test\"\n\n[dependencies]\nserde = \"1.0\"\nserde-json = \"1.0\"\nregex = \"1\"\n",
+/// )
+/// .unwrap();
+/// std::fs::write(
+///     tempdir.path().join("src/lib.rs"),
+///     "use serde::Serialize;\n\npub fn f() {\n    println!(\"{}\", serde_json::json!(1));\n}\n",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(rustilities::manifest::unused_dependencies(tempdir.path()).unwrap(), vec!["regex"]);
+/// ```
+pub fn unused_dependencies<P: AsRef<Path>>(crate_dir: P) -> Result<Vec<String>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_unused_dependencies(crate_dir: &Path) -> Result<Vec<String>, Error> {
+		let doc = std::fs::read_to_string(crate_dir.join("Cargo.toml"))?.parse::<DocumentMut>()?;
+		unused_dependencies_in(&doc, crate_dir, &parse_file)
+	}
+	do_unused_dependencies(crate_dir.as_ref())
+}
+
+/// Same as [`unused_dependencies`], given the manifest of the crate and the way to parse its files.
+pub(super) fn unused_dependencies_in(
+	doc: &DocumentMut,
+	crate_dir: &Path,
+	parse: &ParseFile,
+) -> Result<Vec<String>, Error> {
+	let mut trees = target_trees_with(crate_dir, parse)?;
+	let build_script = crate_dir.join("build.rs");
+	for root in target_roots(&crate_dir.join("examples"))
+		.into_iter()
+		.chain(build_script.is_file().then_some(build_script))
+	{
+		trees.push(SourceTree::load_with(&root, parse)?);
+	}
+	let mut referenced = BTreeSet::new();
+	for tree in &trees {
+		for file in tree.files() {
+			crate_roots(file.ast.to_token_stream(), &mut referenced);
+		}
+	}
+
+	Ok(dependency_tables(doc)
+		.into_iter()
+		.flat_map(|(_, table)| table.iter().map(|(key, _)| key.to_owned()))
+		.filter(|key| !referenced.contains(&key.replace('-', "_")))
+		.collect::<BTreeSet<_>>()
+		.into_iter()
+		.collect())
+}
+
+/// Given the path to a workspace manifest, this function runs [`unused_dependencies`] on every
+/// member of the workspace, returning the unused dependencies of each member by package name.
+/// Members without unused dependencies are omitted.
+///
+/// With the `rayon` feature, the members are analyzed in parallel. The output doesn't depend on
+/// it: if several members fail, the error of the first one in [`find_workspace_members`] order is
+/// returned.
+///
+/// # Errors
+///
+/// - If the workspace members cannot be resolved.
+/// - If some member doesn't have a package name.
+/// - If some member cannot be analyzed, see [`unused_dependencies`].
+pub fn unused_dependencies_all_members<P: AsRef<Path>>(
+	workspace_toml: P,
+) -> Result<BTreeMap<String, Vec<String>>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_unused_dependencies_all_members(
+		workspace_toml: &Path,
+	) -> Result<BTreeMap<String, Vec<String>>, Error> {
+		let member_unused = |manifest_path: &PathBuf| {
+			let name = find_crate_name(manifest_path).ok_or_else(|| {
+				Error::Descriptive(format!(
+					"The member manifest {} doesn't have a package name",
+					manifest_path.display()
+				))
+			})?;
+			let crate_dir = manifest_path.parent().expect("A file always lives inside a dir; qed");
+			Ok((name, unused_dependencies(crate_dir)?))
+		};
+
+		let manifest_paths = find_workspace_members(workspace_toml)?;
+		#[cfg(feature = "rayon")]
+		let results: Vec<Result<_, Error>> = {
+			use rayon::prelude::*;
+			manifest_paths.par_iter().map(member_unused).collect()
+		};
+		#[cfg(not(feature = "rayon"))]
+		let results: Vec<Result<_, Error>> = manifest_paths.iter().map(member_unused).collect();
+
+		let mut unused = BTreeMap::new();
+		for result in results {
+			let (name, dependencies) = result?;
+			if !dependencies.is_empty() {
+				unused.insert(name, dependencies);
+			}
+		}
+		Ok(unused)
+	}
+	do_unused_dependencies_all_members(workspace_toml.as_ref())
+}
+
+/// Collects the identifiers starting a path or following `use` or `extern crate`, found at any
+/// depth of the stream.
+fn crate_roots(stream: TokenStream, roots: &mut BTreeSet<String>) {
+	let tokens: Vec<TokenTree> = stream.into_iter().collect();
+	for (index, token) in tokens.iter().enumerate() {
+		match token {
+			TokenTree::Ident(ident) => {
+				let starts_path = matches!(
+					(tokens.get(index + 1), tokens.get(index + 2)),
+					(Some(TokenTree::Punct(first)), Some(TokenTree::Punct(second)))
+						if first.as_char() == ':' &&
+							first.spacing() == Spacing::Joint &&
+							second.as_char() == ':'
+				);
+				let follows_keyword = index > 0 &&
+					matches!(&tokens[index - 1], TokenTree::Ident(previous) if previous == "use" || previous == "crate");
+				if starts_path || follows_keyword {
+					roots.insert(ident.to_string());
+				}
+			},
+			TokenTree::Group(group) => crate_roots(group.stream(), roots),
+			_ => (),
+		}
+	}
+}
+
+/// The mismatches between the features declared by a manifest and the features checked by the
+/// source code, as found by [`check_feature_consistency`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeatureConsistencyReport {
+	/// The declared features never checked by the code, sorted.
+	pub unused: Vec<String>,
+	/// The features checked by the code but not declared by the manifest, sorted.
+	pub undeclared: Vec<String>,
+}
+
+impl FeatureConsistencyReport {
+	/// Whether the features declared and the features checked match.
+	pub fn is_consistent(&self) -> bool {
+		self.unused.is_empty() && self.undeclared.is_empty()
+	}
+}
+
+impl ToDiagnostics for FeatureConsistencyReport {
+	/// A `manifest::unused_feature` warning per unused feature, followed by a
+	/// `manifest::undeclared_feature` warning per undeclared one.
+	fn to_diagnostics(&self) -> Vec<Diagnostic> {
+		let unused = self.unused.iter().map(|feature| {
+			Diagnostic::new(
+				Severity::Warning,
+				"manifest::unused_feature",
+				format!("The feature `{feature}` is never checked by the code"),
+			)
+		});
+		let undeclared = self.undeclared.iter().map(|feature| {
+			Diagnostic::new(
+				Severity::Warning,
+				"manifest::undeclared_feature",
+				format!("The feature `{feature}` is checked by the code but isn't declared"),
+			)
+		});
+		unused.chain(undeclared).collect()
+	}
+}
+
+/// Given a crate dir, this function compares the features declared in its manifest against the
+/// features checked by its source code, reporting the features never referenced in code and the
+/// checks of undeclared features. The `unexpected_cfgs` lint only covers the latter, and only for
+/// the code compiled in a given build.
+///
+/// A feature is referenced by a `feature = "..."` predicate anywhere inside a `cfg` or `cfg_attr`
+/// attribute or a `cfg!` call, including the calls nested in other macros. The code of every target
+/// is analyzed, including the integration tests and the benches. The implicit features of
+/// optional dependencies count as declared, while `default` and the features enabling other
+/// features of the manifest (eg, `full = ["a", "b"]`) aren't expected to be referenced.
+///
+/// # Errors
+///
+/// - If the crate manifest cannot be read or parsed.
+/// - If the source code cannot be read or parsed.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir_all(tempdir.path().join("src")).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("Cargo.toml"),
+///     "[package]\nname = \"test\"\n\n[features]\ndefault = [\"std\"]\nstd = []\nlegacy = []\n",
+/// )
+/// .unwrap();
+/// std::fs::write(
+///     tempdir.path().join("src/lib.rs"),
+///     "#[cfg(feature = \"std\")]\npub fn f() {}\n#[cfg(feature = \"serde\")]\npub fn g() {}\n",
+/// )
+/// .unwrap();
+///
+/// let report = rustilities::manifest::check_feature_consistency(tempdir.path()).unwrap();
+/// assert_eq!(report.unused, vec!["legacy"]);
+/// assert_eq!(report.undeclared, vec!["serde"]);
+/// ```
+pub fn check_feature_consistency<P: AsRef<Path>>(
+	crate_dir: P,
+) -> Result<FeatureConsistencyReport, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_check_feature_consistency(crate_dir: &Path) -> Result<FeatureConsistencyReport, Error> {
+		let doc = std::fs::read_to_string(crate_dir.join("Cargo.toml"))?.parse::<DocumentMut>()?;
+		feature_consistency_in(&doc, crate_dir, &parse_file)
+	}
+	do_check_feature_consistency(crate_dir.as_ref())
+}
+
+/// Same as [`check_feature_consistency`], given the manifest of the crate and the way to parse its
+/// files.
+pub(super) fn feature_consistency_in(
+	doc: &DocumentMut,
+	crate_dir: &Path,
+	parse: &ParseFile,
+) -> Result<FeatureConsistencyReport, Error> {
+	let features = FeatureGraph::new(doc);
+	let declared = features.declared_features();
+
+	let mut referenced = BTreeSet::new();
+	for tree in target_trees_with(crate_dir, parse)? {
+		for file in tree.files() {
+			referenced_features(file.ast.to_token_stream(), &mut referenced);
+		}
+	}
+
+	Ok(FeatureConsistencyReport {
+		unused: declared
+			.iter()
+			.filter(|feature| {
+				**feature != "default" &&
+					!features.enables_features(feature) &&
+					!referenced.contains(**feature)
+			})
+			.map(|feature| (*feature).to_owned())
+			.collect(),
+		undeclared: referenced
+			.into_iter()
+			.filter(|feature| !declared.contains(feature.as_str()))
+			.collect(),
+	})
+}
+
+/// Collects the features referenced by the `cfg` and `cfg_attr` attributes and the `cfg!` calls
+/// found at any depth of the stream.
+fn referenced_features(stream: TokenStream, referenced: &mut BTreeSet<String>) {
+	let tokens: Vec<TokenTree> = stream.into_iter().collect();
+	for (index, token) in tokens.iter().enumerate() {
+		match token {
+			TokenTree::Ident(ident) if ident == "cfg" || ident == "cfg_attr" => {
+				let predicate = match (tokens.get(index + 1), tokens.get(index + 2)) {
+					(Some(TokenTree::Group(group)), _) => group,
+					(Some(TokenTree::Punct(bang)), Some(TokenTree::Group(group)))
+						if bang.as_char() == '!' =>
+						group,
+					_ => continue,
+				};
+				feature_predicates(predicate.stream(), referenced);
+			},
+			TokenTree::Group(group) => referenced_features(group.stream(), referenced),
+			_ => (),
+		}
+	}
+}
+
+/// Collects the features of the `feature = "..."` predicates found at any depth of the stream.
+fn feature_predicates(stream: TokenStream, referenced: &mut BTreeSet<String>) {
+	let tokens: Vec<TokenTree> = stream.into_iter().collect();
+	for (index, token) in tokens.iter().enumerate() {
+		match (token, tokens.get(index + 1), tokens.get(index + 2)) {
+			(
+				TokenTree::Ident(ident),
+				Some(TokenTree::Punct(eq)),
+				Some(TokenTree::Literal(lit)),
+			) if ident == "feature" && eq.as_char() == '=' =>
+				if let Lit::Str(lit) = Lit::new(lit.clone()) {
+					referenced.insert(lit.value());
+				},
+			(TokenTree::Group(group), _, _) => feature_predicates(group.stream(), referenced),
+			_ => (),
+		}
+	}
+}
+
+/// Given a crate dir and a new name, this function renames the crate, updating every reference to
+/// it so the workspace keeps compiling:
+/// - The `package.name` key of the crate manifest.
+/// - If `rename_dir` is `true`, the crate dir, which is renamed after the new name, together with
+///   the `members` and `default-members` entries and the `path` dependencies pointing to it.
+/// - The dependency keys referring to the crate in the manifests of the workspace (including
+///   `workspace.dependencies`). Renamed dependencies (`alias = { package = "name", ... }`) keep
+///   their alias, and only their `package` key is updated.
+/// - The source code referring to the crate by its old name, in the crate itself (eg, in its
+///   binaries) and in the workspace members depending on it without an alias: `use` declarations,
+///   `extern crate` items and paths starting by the crate name (eg, `old_name::foo()`). Code inside
+///   macro invocations isn't updated. If the crate sets a `lib.name`, the code doesn't refer to the
+///   package name, so it's left untouched.
+///
+/// Only the workspace enclosing the crate is updated, provided the crate is a member (see
+/// [`super::membership_status`]).
+///
+/// Returns the paths of every file written, sorted. The paths point to the renamed dir if
+/// `rename_dir` is `true`.
+///
+/// # Errors
+///
+/// - If the new name isn't a valid crate name.
+/// - If the crate manifest cannot be read or doesn't declare a package name.
+/// - If `rename_dir` is `true` and a file or dir named after the new name already exists next to
+///   the crate dir.
+/// - If some of the manifests or source files cannot be read, parsed or written.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// for (path, content) in [
+///     ("Cargo.toml", "[workspace]\nmembers = [\"old-name\", \"app\"]\n"),
+///     ("old-name/Cargo.toml", "[package]\nname = \"old-name\"\n"),
+///     ("old-name/src/lib.rs", "pub fn foo() {}"),
+///     ("app/Cargo.toml", "[package]\nname = \"app\"\n\n[dependencies]\nold-name = { path = \"../old-name\" }\n"),
+///     ("app/src/main.rs", "use old_name::foo;\n\nfn main() { foo() }"),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(path).parent().unwrap()).unwrap();
+///     std::fs::write(tempdir.path().join(path), content).unwrap();
+/// }
+///
+/// let touched =
+///     rustilities::manifest::rename_crate(tempdir.path().join("old-name"), "new-name", true)
+///         .unwrap();
+///
+/// assert_eq!(
+///     touched,
+///     vec![
+///         tempdir.path().join("Cargo.toml"),
+///         tempdir.path().join("app/Cargo.toml"),
+///         tempdir.path().join("app/src/main.rs"),
+///         tempdir.path().join("new-name/Cargo.toml"),
+///     ]
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(tempdir.path().join("app/Cargo.toml")).unwrap(),
+///     "[package]\nname = \"app\"\n\n[dependencies]\nnew-name = { path = \"../new-name\" }\n"
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(tempdir.path().join("app/src/main.rs")).unwrap(),
+///     "use new_name::foo;\n\nfn main() { foo() }"
+/// );
+/// ```
+pub fn rename_crate<P: AsRef<Path>>(
+	crate_dir: P,
+	new_name: &str,
+	rename_dir: bool,
+) -> Result<Vec<PathBuf>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_rename_crate(
+		crate_dir: &Path,
+		new_name: &str,
+		rename_dir: bool,
+	) -> Result<Vec<PathBuf>, Error> {
+		if new_name.is_empty() ||
+			!new_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+		{
+			return Err(Error::Descriptive(format!("{new_name} isn't a valid crate name")));
+		}
+
+		let crate_dir = std::path::absolute(crate_dir)?;
+		let crate_manifest = crate_dir.join("Cargo.toml");
+		let mut doc = std::fs::read_to_string(&crate_manifest)?.parse::<DocumentMut>()?;
+		let package = doc
+			.get_mut("package")
+			.and_then(Item::as_table_like_mut)
+			.ok_or_else(|| Error::Descriptive("The provided path isn't a crate".to_owned()))?;
+		let Some(name) = package.get_mut("name").and_then(Item::as_value_mut) else {
+			return Err(Error::Descriptive("The crate doesn't declare a name".to_owned()));
+		};
+		let Some(old_name) = name.as_str().map(str::to_owned) else {
+			return Err(Error::Descriptive("The crate name isn't a string".to_owned()));
+		};
+		let new_dir = if rename_dir {
+			let new_dir = crate_dir.parent().unwrap_or(&crate_dir).join(new_name);
+			if new_dir != crate_dir && new_dir.exists() {
+				return Err(Error::Descriptive(format!("{} already exists", new_dir.display())));
+			}
+			new_dir
+		} else {
+			crate_dir.clone()
+		};
+		if old_name == new_name && new_dir == crate_dir {
+			return Ok(Vec::new());
+		}
+		replace_value(name, new_name);
+		let lib_named = doc.get("lib").and_then(|lib| lib.get("name")).is_some();
+
+		let workspace_toml = find_workspace_manifest(&crate_dir)
+			.and_then(|workspace_toml| std::path::absolute(workspace_toml).ok())
+			.filter(|workspace_toml| *workspace_toml != crate_manifest);
+		let dependents = match &workspace_toml {
+			Some(workspace_toml)
+				if membership_status(&crate_manifest)? == Some(MembershipStatus::Member) =>
+			{
+				let mut dependents = find_workspace_members(workspace_toml)?;
+				dependents.retain(|dependent| *dependent != crate_manifest);
+				if !dependents.contains(workspace_toml) {
+					dependents.push(workspace_toml.clone());
+				}
+				dependents
+			},
+			_ => Vec::new(),
+		};
+
+		let renaming =
+			Renaming { old_name: &old_name, new_name, old_dir: &crate_dir, new_dir: &new_dir };
+		let mut touched = BTreeSet::new();
+		// The dirs of the crates whose code refers to the renamed crate by its name
+		let mut referencing_dirs = vec![crate_dir.clone()];
+		for dependent in dependents {
+			let dependent_dir = dependent.parent().expect("A file always lives inside a dir; qed");
+			let mut dependent_doc = std::fs::read_to_string(&dependent)?.parse::<DocumentMut>()?;
+			let mut changed = false;
+			let mut referencing = false;
+			for (_, table) in dependency_tables_mut(&mut dependent_doc) {
+				let (table_changed, table_referencing) =
+					renaming.update_dependencies(table, dependent_dir);
+				changed |= table_changed;
+				referencing |= table_referencing;
+			}
+			if Some(&dependent) == workspace_toml.as_ref() {
+				changed |= renaming.update_workspace(&mut dependent_doc, dependent_dir);
+			}
+			if referencing {
+				referencing_dirs.push(dependent_dir.to_path_buf());
+			}
+			if changed {
+				debug!(path = %dependent.display(), "Writing manifest");
+				std::fs::write(&dependent, dependent_doc.to_string())?;
+				touched.insert(dependent);
+			}
+		}
+
+		let old_ident = old_name.replace('-', "_");
+		let new_ident = new_name.replace('-', "_");
+		if !lib_named && old_ident != new_ident {
+			for dir in referencing_dirs {
+				for tree in SourceTree::load_crate(&dir)? {
+					for file in tree.files() {
+						let mut references =
+							CrateIdents { name: &old_ident, ranges: BTreeMap::new() };
+						references.visit_file(&file.ast);
+						if references.ranges.is_empty() {
+							continue;
+						}
+						let mut content = std::fs::read_to_string(&file.path)?;
+						for (start, end) in references.ranges.into_iter().rev() {
+							content.replace_range(start..end, &new_ident);
+						}
+						debug!(path = %file.path.display(), "Writing source file");
+						std::fs::write(&file.path, content)?;
+						touched.insert(file.path.clone());
+					}
+				}
+			}
+		}
+
+		debug!(path = %crate_manifest.display(), "Writing manifest");
+		std::fs::write(&crate_manifest, doc.to_string())?;
+		touched.insert(crate_manifest);
+		if new_dir != crate_dir {
+			debug!(from = %crate_dir.display(), to = %new_dir.display(), "Renaming crate dir");
+			std::fs::rename(&crate_dir, &new_dir)?;
+		}
+
+		Ok(touched
+			.into_iter()
+			.map(|path| match path.strip_prefix(&crate_dir) {
+				Ok(relative_path) => new_dir.join(relative_path),
+				Err(_) => path,
+			})
+			.collect::<BTreeSet<_>>()
+			.into_iter()
+			.collect())
+	}
+	do_rename_crate(crate_dir.as_ref(), new_name, rename_dir)
+}
+
+/// The changes applied by [`rename_crate`].
+struct Renaming<'a> {
+	old_name: &'a str,
+	new_name: &'a str,
+	old_dir: &'a Path,
+	new_dir: &'a Path,
+}
+
+impl Renaming<'_> {
+	/// Updates the dependencies on the renamed crate found in a dependency table of a manifest
+	/// living in `manifest_dir`. Returns whether the table changed, and whether it declares a
+	/// dependency referred to by the crate name (ie, without alias).
+	fn update_dependencies(&self, table: &mut dyn TableLike, manifest_dir: &Path) -> (bool, bool) {
+		let mut changed = false;
+		let mut referencing = false;
+		let mut renamed_key = None;
+		for (key, dependency) in table.iter_mut() {
+			let Some(dependency_table) = dependency.as_table_like_mut() else {
+				if key == self.old_name {
+					renamed_key = Some(key.get().to_owned());
+					referencing = true;
+				}
+				continue;
+			};
+			match dependency_table.get_mut("package").and_then(Item::as_value_mut) {
+				Some(package) if package.as_str() == Some(self.old_name) => {
+					if self.old_name != self.new_name {
+						replace_value(package, self.new_name);
+						changed = true;
+					}
+				},
+				Some(_) => continue,
+				None if key == self.old_name => {
+					renamed_key = Some(key.get().to_owned());
+					referencing = true;
+				},
+				None => continue,
+			}
+			if let Some(path) = dependency_table.get_mut("path").and_then(Item::as_value_mut) &&
+				let Some(new_path) =
+					path.as_str().and_then(|path| self.relocate(manifest_dir, path))
+			{
+				replace_value(path, &new_path);
+				changed = true;
+			}
+		}
+
+		if let Some(key) = renamed_key &&
+			self.old_name != self.new_name
+		{
+			rename_key(table, &key, self.new_name);
+			changed = true;
+		}
+		(changed, referencing)
+	}
+
+	/// Updates the `workspace` section of the workspace manifest living in `workspace_dir`.
+	/// Returns whether it changed.
+	fn update_workspace(&self, doc: &mut DocumentMut, workspace_dir: &Path) -> bool {
+		let Some(workspace) = doc.get_mut("workspace").and_then(Item::as_table_like_mut) else {
+			return false;
+		};
+		let mut changed = false;
+		for key in ["members", "default-members"] {
+			let Some(array) = workspace.get_mut(key).and_then(Item::as_array_mut) else {
+				continue;
+			};
+			for entry in array.iter_mut() {
+				if let Some(new_path) =
+					entry.as_str().and_then(|path| self.relocate(workspace_dir, path))
+				{
+					replace_value(entry, &new_path);
+					changed = true;
+				}
+			}
+		}
+		if let Some(dependencies) =
+			workspace.get_mut("dependencies").and_then(Item::as_table_like_mut)
+		{
+			changed |= self.update_dependencies(dependencies, workspace_dir).0;
+		}
+		changed
+	}
+
+	/// If `path`, relative to `base_dir`, points to the old crate dir and the dir is renamed,
+	/// returns the path pointing to the new dir.
+	fn relocate(&self, base_dir: &Path, path: &str) -> Option<String> {
+		if self.old_dir == self.new_dir || normalize(&base_dir.join(path)) != self.old_dir {
+			return None;
+		}
+		let new_dir_name = self.new_dir.file_name()?.to_str()?;
+		let new_path = Path::new(path).with_file_name(new_dir_name);
+		new_path.to_str().map(str::to_owned)
+	}
+}
+
+/// Replaces the content of a string value, keeping its decor.
+fn replace_value(value: &mut Value, new_value: &str) {
+	let decor = value.decor().clone();
+	*value = new_value.into();
+	*value.decor_mut() = decor;
+}
+
+/// Renames a key of a table keeping the order of the entries and the key's decor.
+fn rename_key(table: &mut dyn TableLike, old_key: &str, new_key: &str) {
+	let entries = table
+		.iter()
+		.map(|(key, _)| key.to_owned())
+		.collect::<Vec<_>>()
+		.into_iter()
+		.filter_map(|key| {
+			let formatted_key = table.key(&key)?.clone();
+			Some((formatted_key, table.remove(&key)?))
+		})
+		.collect::<Vec<_>>();
+	for (key, item) in entries {
+		let key = if key.get() == old_key {
+			Key::new(new_key).with_leaf_decor(key.leaf_decor().clone())
+		} else {
+			key
+		};
+		table.entry_format(&key).or_insert(item);
+	}
+}
+
+/// Collects the byte ranges of the references to a crate found in the code: the first segment of
+/// `use` declarations and paths, and `extern crate` items.
+struct CrateIdents<'a> {
+	name: &'a str,
+	ranges: BTreeMap<usize, usize>,
+}
+
+impl CrateIdents<'_> {
+	fn collect(&mut self, ident: &Ident) {
+		if ident == self.name {
+			let range = ident.span().byte_range();
+			self.ranges.insert(range.start, range.end);
+		}
+	}
+
+	fn visit_use_tree_root(&mut self, tree: &UseTree) {
+		match tree {
+			UseTree::Path(path) => self.collect(&path.ident),
+			UseTree::Name(name) => self.collect(&name.ident),
+			UseTree::Rename(rename) => self.collect(&rename.ident),
+			UseTree::Group(group) =>
+				group.items.iter().for_each(|tree| self.visit_use_tree_root(tree)),
+			UseTree::Glob(_) => (),
+		}
+	}
+}
+
+impl<'ast> Visit<'ast> for CrateIdents<'_> {
+	fn visit_item_use(&mut self, item_use: &'ast ItemUse) {
+		self.visit_use_tree_root(&item_use.tree);
+	}
+
+	fn visit_item_extern_crate(&mut self, item: &'ast ItemExternCrate) {
+		self.collect(&item.ident);
+	}
+
+	fn visit_path(&mut self, path: &'ast syn::Path) {
+		if (path.leading_colon.is_some() || path.segments.len() > 1) &&
+			let Some(first) = path.segments.first()
+		{
+			self.collect(&first.ident);
+		}
+		syn::visit::visit_path(self, path);
+	}
+}
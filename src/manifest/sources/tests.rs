@@ -0,0 +1,467 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+fn crate_with_files(files: &[(&str, &str)]) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	for (path, content) in files {
+		let path = tempdir.path().join(path);
+		std::fs::create_dir_all(path.parent().expect("A file always lives inside a dir; qed"))
+			.expect("This should be created; qed;");
+		std::fs::write(path, content).expect("The file should be writable; qed;");
+	}
+	tempdir
+}
+
+#[test]
+fn undeclared_crates_finds_crates_used_without_being_declared() {
+	let tempdir = crate_with_files(&[
+		(
+			"Cargo.toml",
+			r#"
+[package]
+name = "my-crate"
+
+[dependencies]
+serde = "1.0"
+renamed-dep = { package = "other", version = "1.0" }
+
+[dev-dependencies]
+tempfile = "3.0"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+"#,
+		),
+		(
+			"src/lib.rs",
+			r#"
+mod module;
+extern crate undeclared_extern;
+use ::undeclared_leading_colon::Item;
+use undeclared_use::{self as alias, Other};
+use {serde::Serialize, std::fmt};
+use self::module::helper;
+
+#[derive(Serialize, undeclared_derive::Derive)]
+struct Foo(Vec<u8>);
+
+fn foo() -> fmt::Result {
+	let _ = String::new();
+	let _ = u32::MAX;
+	let _ = renamed_dep::run();
+	let _ = libc::getpid();
+	let _ = undeclared_expr::call();
+	let _ = helper::call();
+	let _ = alias::call();
+	let _ = module::call();
+	undeclared_macro::mac!();
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use tempfile::tempdir;
+}
+"#,
+		),
+		("src/module.rs", "pub mod helper; pub fn call() { let _: core::cell::Cell<u8>; }"),
+		("src/module/helper.rs", "pub fn call() {}"),
+		("src/main.rs", "fn main() { my_crate::foo(); undeclared_bin::run(); }"),
+	]);
+
+	assert_eq!(
+		undeclared_crates(tempdir.path()).expect("This should be Ok; qed;"),
+		vec![
+			"undeclared_bin",
+			"undeclared_derive",
+			"undeclared_expr",
+			"undeclared_extern",
+			"undeclared_leading_colon",
+			"undeclared_macro",
+			"undeclared_use"
+		]
+	);
+}
+
+#[test]
+fn undeclared_crates_returns_empty_if_every_crate_is_declared() {
+	let tempdir = crate_with_files(&[
+		("Cargo.toml", "[package]\nname = \"test\"\n\n[dependencies]\nserde = \"1.0\""),
+		("src/lib.rs", "use serde::Serialize;"),
+	]);
+
+	assert!(undeclared_crates(tempdir.path()).expect("This should be Ok; qed;").is_empty());
+}
+
+#[test]
+fn undeclared_crates_fails_if_manifest_cannot_be_read() {
+	let tempdir = crate_with_files(&[("src/lib.rs", "")]);
+
+	assert!(matches!(
+		undeclared_crates(tempdir.path()),
+		Err(Error::IO(err)) if err.kind() == std::io::ErrorKind::NotFound
+	));
+}
+
+#[test]
+fn undeclared_crates_fails_if_code_cannot_be_parsed() {
+	let tempdir =
+		crate_with_files(&[("Cargo.toml", "[package]\nname = \"test\""), ("src/lib.rs", "fn {")]);
+
+	assert!(matches!(undeclared_crates(tempdir.path()), Err(Error::Syn(_))));
+}
+
+#[test]
+fn check_feature_consistency_compares_manifest_and_code() {
+	let tempdir = crate_with_files(&[
+		(
+			"Cargo.toml",
+			r#"[package]
+name = "test"
+
+[dependencies]
+serde = { version = "1.0", optional = true }
+tokio = { version = "1.0", optional = true }
+
+[features]
+default = ["std"]
+std = []
+full = ["std", "async"]
+async = ["dep:tokio"]
+unused = ["serde/std"]
+in_tests = []
+"#,
+		),
+		(
+			"src/lib.rs",
+			r#"
+			#![cfg_attr(not(feature = "std"), no_std)]
+			#[cfg(all(feature = "serde", not(feature = "missing")))]
+			mod ser;
+			pub fn f() -> bool {
+				cfg!(feature = "async") || vec![cfg!(feature = "nested")].is_empty()
+			}
+			#[cfg_attr(docsrs, doc(cfg(feature = "documented")))]
+			pub fn g() {}
+			// A feature = "comment" isn't a check
+			pub const NAME: &str = "feature";
+			"#,
+		),
+		("tests/it.rs", "#[cfg(feature = \"in_tests\")]\n#[test]\nfn t() {}\n"),
+	]);
+
+	assert_eq!(
+		check_feature_consistency(tempdir.path()).expect("This should be Ok; qed;"),
+		FeatureConsistencyReport {
+			unused: vec!["unused".to_owned()],
+			undeclared: vec!["documented".to_owned(), "missing".to_owned(), "nested".to_owned()],
+		}
+	);
+}
+
+#[test]
+fn check_feature_consistency_is_consistent_without_features() {
+	let tempdir = crate_with_files(&[
+		("Cargo.toml", "[package]\nname = \"test\"\n"),
+		("src/main.rs", "fn main() {}"),
+	]);
+
+	assert!(
+		check_feature_consistency(tempdir.path())
+			.expect("This should be Ok; qed;")
+			.is_consistent()
+	);
+}
+
+#[test]
+fn check_feature_consistency_fails_if_manifest_cannot_be_read() {
+	let tempdir = crate_with_files(&[("src/lib.rs", "")]);
+
+	assert!(matches!(check_feature_consistency(tempdir.path()), Err(Error::IO(_))));
+}
+
+#[test]
+fn unused_dependencies_finds_dependencies_never_referenced() {
+	let tempdir = crate_with_files(&[
+		(
+			"Cargo.toml",
+			r#"
+[package]
+name = "test"
+
+[dependencies]
+serde = "1.0"
+serde-json = "1.0"
+anyhow = "1.0"
+renamed = { package = "other", version = "1.0" }
+regex = "1"
+unused-dep = "1"
+
+[dev-dependencies]
+tempfile = "3.0"
+criterion = "0.5"
+
+[build-dependencies]
+cc = "1.0"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+"#,
+		),
+		(
+			"src/lib.rs",
+			r#"
+use serde::{Deserialize, Serialize};
+extern crate renamed as alias;
+
+#[derive(Serialize, Deserialize)]
+pub struct S;
+
+pub fn f() -> anyhow::Result<()> {
+	println!("{}", serde_json::json!(1));
+	Ok(())
+}
+"#,
+		),
+		(
+			"tests/it.rs",
+			"#[test]
+fn t() {
+    let _ = tempfile::tempdir();
+}
+",
+		),
+		(
+			"examples/example.rs",
+			"use regex;
+fn main() {}
+",
+		),
+		(
+			"build.rs",
+			"fn main() {
+    cc::Build::new();
+}
+",
+		),
+	]);
+
+	assert_eq!(
+		unused_dependencies(tempdir.path()).expect("This should be Ok; qed;"),
+		vec!["criterion", "libc", "unused-dep"]
+	);
+}
+
+#[test]
+fn unused_dependencies_fails_if_code_cannot_be_parsed() {
+	let tempdir = crate_with_files(&[
+		("Cargo.toml", "[package]\nname = \"test\"\n"),
+		("build.rs", "fn main( {}"),
+	]);
+
+	assert!(matches!(unused_dependencies(tempdir.path()), Err(Error::Syn(_))));
+}
+
+#[test]
+fn unused_dependencies_all_members_reports_members_by_name() {
+	let tempdir = crate_with_files(&[
+		("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n"),
+		("crates/a/Cargo.toml", "[package]\nname = \"a\"\n[dependencies]\nserde = \"1.0\"\n"),
+		("crates/a/src/lib.rs", ""),
+		("crates/b/Cargo.toml", "[package]\nname = \"b\"\n[dependencies]\nserde = \"1.0\"\n"),
+		("crates/b/src/lib.rs", "use serde::Serialize;"),
+		(
+			"crates/c/Cargo.toml",
+			"[package]\nname = \"c\"\n[dependencies]\nlibc = \"0.2\"\nregex = \"1\"\n",
+		),
+		("crates/c/src/main.rs", "fn main() {}"),
+	]);
+
+	assert_eq!(
+		unused_dependencies_all_members(tempdir.path().join("Cargo.toml"))
+			.expect("This should be Ok; qed;"),
+		BTreeMap::from([
+			("a".to_owned(), vec!["serde".to_owned()]),
+			("c".to_owned(), vec!["libc".to_owned(), "regex".to_owned()]),
+		])
+	);
+}
+
+#[test]
+fn unused_dependencies_all_members_returns_the_first_error() {
+	let tempdir = crate_with_files(&[
+		("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n"),
+		("crates/a/Cargo.toml", "[package]\nname = \"a\"\n"),
+		("crates/a/src/lib.rs", ""),
+		("crates/b/Cargo.toml", "[package]\nname = \"b\"\n"),
+		("crates/b/src/lib.rs", "fn f( {}"),
+		("crates/c/Cargo.toml", "[package]\n"),
+	]);
+
+	assert!(matches!(
+		unused_dependencies_all_members(tempdir.path().join("Cargo.toml")),
+		Err(Error::Syn(_))
+	));
+}
+
+fn read(tempdir: &TempDir, path: &str) -> String {
+	std::fs::read_to_string(tempdir.path().join(path)).expect("This should be Ok; qed;")
+}
+
+#[test]
+fn rename_crate_updates_manifests_and_code() {
+	let tempdir = crate_with_files(&[
+		(
+			"Cargo.toml",
+			r#"[package]
+name = "root"
+
+[workspace]
+members = ["crates/*"]
+
+[workspace.dependencies]
+# The crate being renamed
+old-name = { path = "crates/old-name" }
+serde = "1.0"
+
+[dependencies]
+alias = { package = "old-name", path = "crates/old-name" }
+"#,
+		),
+		("crates/old-name/Cargo.toml", "[package]\nname = \"old-name\"\n"),
+		("crates/old-name/src/lib.rs", "pub fn foo() {}"),
+		("crates/old-name/src/main.rs", "fn main() { old_name::foo() }"),
+		(
+			"crates/user/Cargo.toml",
+			"[package]\nname = \"user\"\n\n[dev-dependencies]\nold-name.workspace = true\nserde = \"1.0\"\n",
+		),
+		(
+			"crates/user/src/lib.rs",
+			"extern crate old_name;\nuse {old_name::{self, foo}, std::fmt};\n\nfn bar() {\n\tuse old_name as other;\n\t::old_name::foo();\n\tlet old_name = 1;\n}\n",
+		),
+		("src/lib.rs", "use alias::foo;"),
+	]);
+
+	let touched = rename_crate(tempdir.path().join("crates/old-name"), "new_name", false)
+		.expect("This should be Ok; qed;");
+
+	assert_eq!(
+		touched,
+		vec![
+			tempdir.path().join("Cargo.toml"),
+			tempdir.path().join("crates/old-name/Cargo.toml"),
+			tempdir.path().join("crates/old-name/src/main.rs"),
+			tempdir.path().join("crates/user/Cargo.toml"),
+			tempdir.path().join("crates/user/src/lib.rs"),
+		]
+	);
+	assert_eq!(
+		read(&tempdir, "Cargo.toml"),
+		r#"[package]
+name = "root"
+
+[workspace]
+members = ["crates/*"]
+
+[workspace.dependencies]
+# The crate being renamed
+new_name = { path = "crates/old-name" }
+serde = "1.0"
+
+[dependencies]
+alias = { package = "new_name", path = "crates/old-name" }
+"#
+	);
+	assert_eq!(read(&tempdir, "crates/old-name/Cargo.toml"), "[package]\nname = \"new_name\"\n");
+	assert_eq!(read(&tempdir, "crates/old-name/src/main.rs"), "fn main() { new_name::foo() }");
+	assert_eq!(
+		read(&tempdir, "crates/user/Cargo.toml"),
+		"[package]\nname = \"user\"\n\n[dev-dependencies]\nnew_name.workspace = true\nserde = \"1.0\"\n"
+	);
+	assert_eq!(
+		read(&tempdir, "crates/user/src/lib.rs"),
+		"extern crate new_name;\nuse {new_name::{self, foo}, std::fmt};\n\nfn bar() {\n\tuse new_name as other;\n\t::new_name::foo();\n\tlet old_name = 1;\n}\n"
+	);
+	assert_eq!(read(&tempdir, "src/lib.rs"), "use alias::foo;");
+}
+
+#[test]
+fn rename_crate_renames_dir() {
+	let tempdir = crate_with_files(&[
+		(
+			"Cargo.toml",
+			"[workspace]\nmembers = [\"./old\", \"app\"]\ndefault-members = [\"old\"]\n",
+		),
+		("old/Cargo.toml", "[package]\nname = \"old\"\n\n[lib]\nname = \"lib_name\"\n"),
+		("old/src/lib.rs", ""),
+		(
+			"app/Cargo.toml",
+			"[package]\nname = \"app\"\n\n[target.'cfg(unix)'.dependencies]\nold = { path = \"../old\" }\n",
+		),
+		("app/src/main.rs", "use lib_name as _;\n\nfn main() {}"),
+	]);
+
+	let touched =
+		rename_crate(tempdir.path().join("old"), "new", true).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		touched,
+		vec![
+			tempdir.path().join("Cargo.toml"),
+			tempdir.path().join("app/Cargo.toml"),
+			tempdir.path().join("new/Cargo.toml"),
+		]
+	);
+	assert!(!tempdir.path().join("old").exists());
+	assert_eq!(
+		read(&tempdir, "Cargo.toml"),
+		"[workspace]\nmembers = [\"./new\", \"app\"]\ndefault-members = [\"new\"]\n"
+	);
+	assert_eq!(
+		read(&tempdir, "app/Cargo.toml"),
+		"[package]\nname = \"app\"\n\n[target.'cfg(unix)'.dependencies]\nnew = { path = \"../new\" }\n"
+	);
+	assert_eq!(read(&tempdir, "app/src/main.rs"), "use lib_name as _;\n\nfn main() {}");
+}
+
+#[test]
+fn rename_crate_ignores_workspace_if_crate_isnt_a_member() {
+	let tempdir = crate_with_files(&[
+		("Cargo.toml", "[workspace]\nmembers = []\n\n[workspace.dependencies]\nold = \"1.0\"\n"),
+		("old/Cargo.toml", "[package]\nname = \"old\"\n"),
+	]);
+
+	assert_eq!(
+		rename_crate(tempdir.path().join("old"), "new", false).expect("This should be Ok; qed;"),
+		vec![tempdir.path().join("old/Cargo.toml")]
+	);
+	assert_eq!(
+		read(&tempdir, "Cargo.toml"),
+		"[workspace]\nmembers = []\n\n[workspace.dependencies]\nold = \"1.0\"\n"
+	);
+}
+
+#[test]
+fn rename_crate_fails_if_invalid_input() {
+	let tempdir = crate_with_files(&[
+		("Cargo.toml", "[workspace]\nmembers = [\"a\", \"b\"]\n"),
+		("a/Cargo.toml", "[package]\nname = \"a\"\n"),
+		("b/Cargo.toml", "[package]\nname = \"b\"\n"),
+	]);
+
+	assert!(matches!(
+		rename_crate(tempdir.path().join("a"), "a b", false),
+		Err(Error::Descriptive(msg)) if msg == "a b isn't a valid crate name"
+	));
+	assert!(matches!(
+		rename_crate(tempdir.path(), "c", false),
+		Err(Error::Descriptive(msg)) if msg == "The provided path isn't a crate"
+	));
+	assert!(matches!(
+		rename_crate(tempdir.path().join("a"), "b", true),
+		Err(Error::Descriptive(msg)) if msg.ends_with("already exists")
+	));
+	assert_eq!(read(&tempdir, "a/Cargo.toml"), "[package]\nname = \"a\"\n");
+}
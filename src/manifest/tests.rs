@@ -340,6 +340,71 @@ fn find_innermost_manifest_doesnt_find_manifest_if_not_rust_dir() {
 	})
 }
 
+#[test]
+fn find_innermost_manifests_finds_right_manifest_for_every_path() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.with_non_crate()
+		.build()
+		.execute(|builder| {
+			let paths = builder
+				.crate_paths
+				.iter()
+				.chain(builder.non_crate_paths.iter())
+				.cloned()
+				.collect::<Vec<_>>();
+
+			let manifests = find_innermost_manifests(&paths);
+
+			assert_eq!(manifests.len(), paths.len());
+			builder.crate_paths.iter().for_each(|path| {
+				assert_eq!(manifests[path], Some(builder.crate_manifest.clone()));
+			});
+			builder.non_crate_paths.iter().for_each(|path| {
+				assert_eq!(manifests[path], Some(builder.workspace_manifest.clone()));
+			});
+		})
+}
+
+#[test]
+fn find_innermost_manifests_agrees_with_find_innermost_manifest() {
+	TestBuilder::default()
+		.with_crate()
+		.with_non_crate()
+		.with_calling_dir_override(CallingDirOverride::CrateRoot)
+		.build()
+		.execute(|builder| {
+			let paths = builder
+				.crate_paths
+				.iter()
+				.chain(builder.non_crate_paths.iter())
+				.cloned()
+				.collect::<Vec<_>>();
+
+			let manifests = find_innermost_manifests(&paths);
+
+			paths.iter().for_each(|path| {
+				assert_eq!(manifests[path], find_innermost_manifest(path));
+			});
+		})
+}
+
+#[test]
+fn find_innermost_manifests_returns_the_paths_sorted() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let paths = ["c.rs", "a.rs", "b/lib.rs", "b.rs"]
+		.into_iter()
+		.map(|path| tempdir.path().join(path))
+		.collect::<Vec<_>>();
+
+	let manifests = find_innermost_manifests(&paths);
+
+	let mut sorted = paths.clone();
+	sorted.sort();
+	assert_eq!(manifests.into_keys().collect::<Vec<_>>(), sorted);
+}
+
 #[test]
 fn find_workspace_manifest_finds_manifest_from_different_parts_of_a_workspace() {
 	TestBuilder::default()
@@ -405,6 +470,53 @@ fn find_workspace_manifest_doesnt_find_manifest_if_not_workspace() {
 	})
 }
 
+#[test]
+fn find_member_workspace_manifest_skips_workspaces_not_including_the_crate() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let write = |path: &str, content: &str| {
+		let path = tempdir.path().join(path);
+		std::fs::create_dir_all(path.parent().expect("The path has a parent; qed;"))
+			.expect("The dir should be created; qed;");
+		std::fs::write(path, content).expect("The manifest should be writable; qed;");
+	};
+	write(
+		"Cargo.toml",
+		"[workspace]\nmembers = [\"crates/*\", \"nested/tools/lint\"]\nexclude = [\"crates/excluded\"]\n",
+	);
+	write("crates/a/Cargo.toml", "[package]\nname = \"a\"\n");
+	write("crates/excluded/Cargo.toml", "[package]\nname = \"excluded\"\n");
+	write("nested/Cargo.toml", "[workspace]\nmembers = [\"app\"]\n");
+	write("nested/app/Cargo.toml", "[package]\nname = \"app\"\n");
+	write("nested/tools/lint/Cargo.toml", "[package]\nname = \"lint\"\n");
+	write("nested/root/Cargo.toml", "[package]\nname = \"root\"\n\n[workspace]\n");
+	write("nested/explicit/Cargo.toml", "[package]\nname = \"explicit\"\nworkspace = \"..\"\n");
+	write("nested/unknown/Cargo.toml", "[package]\nname = \"unknown\"\n");
+
+	let root = tempdir.path().join("Cargo.toml");
+	let nested = tempdir.path().join("nested/Cargo.toml");
+	for (path, expected) in [
+		("crates/a/src/lib.rs", Some(&root)),
+		("crates/excluded", None),
+		("nested/app/src", Some(&nested)),
+		("nested/tools/lint", Some(&root)),
+		("nested/explicit", Some(&nested)),
+		("nested/unknown", None),
+		("nested/src", Some(&nested)),
+		("README.md", Some(&root)),
+	] {
+		assert_eq!(
+			find_member_workspace_manifest(tempdir.path().join(path)).as_ref(),
+			expected,
+			"{path}"
+		);
+	}
+	assert_eq!(
+		find_member_workspace_manifest(tempdir.path().join("nested/root")),
+		Some(tempdir.path().join("nested/root/Cargo.toml"))
+	);
+	assert_eq!(find_workspace_manifest(tempdir.path().join("nested/tools/lint")), Some(nested));
+}
+
 #[test]
 fn find_crate_name_finds_name_if_crate_manifest_path_used() {
 	TestBuilder::default().with_crate().build().execute(|builder| {
@@ -437,7 +549,10 @@ fn add_dependency_to_dependencies_table_workspace_dependency() {
 				vec![],
 				false,
 			),
-		);
+			DependencyStyle::Inline,
+			None,
+		)
+		.expect("This should be Ok; qed;");
 
 		assert_eq!(dependencies.to_string(), "dependency = { workspace = true }\n");
 	});
@@ -458,7 +573,10 @@ fn add_dependency_to_dependencies_table_crates_io_dependency() {
 				vec![],
 				false,
 			),
-		);
+			DependencyStyle::Inline,
+			None,
+		)
+		.expect("This should be Ok; qed;");
 
 		assert_eq!(dependencies.to_string(), "dependency = { version = \"1.0.0\" }\n");
 	});
@@ -479,7 +597,10 @@ fn add_dependency_to_dependencies_table_git_dependency() {
 				vec![],
 				false,
 			),
-		);
+			DependencyStyle::Inline,
+			None,
+		)
+		.expect("This should be Ok; qed;");
 
 		assert_eq!(
 			dependencies.to_string(),
@@ -503,7 +624,10 @@ fn add_dependency_to_dependencies_table_local_dependency() {
 				vec![],
 				false,
 			),
-		);
+			DependencyStyle::Inline,
+			None,
+		)
+		.expect("This should be Ok; qed;");
 
 		assert_eq!(dependencies.to_string(), "dependency = { path = \"../path\" }\n");
 	});
@@ -524,7 +648,10 @@ fn add_dependency_to_dependencies_table_dependency_no_default_features() {
 				vec![],
 				false,
 			),
-		);
+			DependencyStyle::Inline,
+			None,
+		)
+		.expect("This should be Ok; qed;");
 
 		assert_eq!(
 			dependencies.to_string(),
@@ -548,7 +675,10 @@ fn add_dependency_to_dependencies_table_dependency_with_features() {
 				vec!["feature_a", "feature_b"],
 				false,
 			),
-		);
+			DependencyStyle::Inline,
+			None,
+		)
+		.expect("This should be Ok; qed;");
 
 		assert_eq!(
 			dependencies.to_string(),
@@ -572,7 +702,10 @@ fn add_dependency_to_dependencies_table_optional_dependency() {
 				vec![],
 				true,
 			),
-		);
+			DependencyStyle::Inline,
+			None,
+		)
+		.expect("This should be Ok; qed;");
 
 		assert_eq!(
 			dependencies.to_string(),
@@ -756,6 +889,33 @@ dependency = { workspace = true }
 	});
 }
 
+#[test]
+fn add_crate_to_dependencies_fails_if_version_is_invalid() {
+	TestBuilder::default().with_crate().build().execute(|builder| {
+		let manifest_content = std::fs::read_to_string(&builder.crate_manifest)
+			.expect("This should be readable; qed;");
+		assert!(matches!(
+			add_crate_to_dependencies(
+				&builder.crate_manifest,
+				"dependency",
+				ManifestDependencyConfig::new(
+					ManifestDependencyOrigin::crates_io("not a version"),
+					true,
+					vec![],
+					false
+				)
+			),
+			Err(Error::Descriptive(msg)) if msg.starts_with("Invalid version requirement not a version: ")
+		));
+		// The manifest isn't touched
+		assert_eq!(
+			std::fs::read_to_string(&builder.crate_manifest)
+				.expect("This should be readable; qed;"),
+			manifest_content
+		);
+	});
+}
+
 #[test]
 fn add_crate_to_dependencies_fails_if_manifest_path_isnt_readable() {
 	TestBuilder::default().build().execute(|builder| {
@@ -800,7 +960,7 @@ fn add_crate_to_dependencies_fails_if_manifest_path_cannot_be_written() {
 				add_crate_to_dependencies(
 				&builder.workspace_manifest,
 					"dependency",
-			ManifestDependencyConfig::new(ManifestDependencyOrigin::workspace(), false, vec![], false)
+			ManifestDependencyConfig::new(ManifestDependencyOrigin::crates_io("1.0"), false, vec![], false)
 				),
 				Err(Error::IO(err)) if err.kind() == ErrorKind::PermissionDenied
 			));
@@ -1011,3 +1171,1000 @@ fn add_crate_to_workspace_fails_if_manifest_path_cannot_be_written() {
 			));
 		});
 }
+
+#[test]
+fn find_workspace_members_works() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			assert_eq!(
+				find_workspace_members(&builder.workspace_manifest)
+					.expect("This should be Ok; qed;"),
+				vec![builder.crate_manifest.clone()]
+			);
+		});
+}
+
+#[test]
+fn find_workspace_members_resolves_globs_and_excluded_paths() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			for member in ["crates/a", "crates/b", "crates/no_manifest"] {
+				let member_path = builder.tempdir.path().join(member);
+				std::fs::create_dir_all(&member_path).expect("This should be created; qed;");
+				if !member.ends_with("no_manifest") {
+					std::fs::write(member_path.join("Cargo.toml"), "[package]\nname = \"member\"")
+						.expect("Manifest should be writable; qed;");
+				}
+			}
+			std::fs::write(
+				&builder.workspace_manifest,
+				r#"
+[workspace]
+members = ["crate", "crates/*"]
+exclude = ["crates/b"]
+"#,
+			)
+			.expect("Manifest should be writable; qed;");
+
+			assert_eq!(
+				find_workspace_members(&builder.workspace_manifest)
+					.expect("This should be Ok; qed;"),
+				vec![
+					builder.crate_manifest.clone(),
+					builder.tempdir.path().join("crates").join("a").join("Cargo.toml")
+				]
+			);
+		});
+}
+
+#[test]
+fn find_workspace_members_includes_root_package() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			std::fs::write(
+				&builder.workspace_manifest,
+				r#"
+[package]
+name = "root"
+
+[workspace]
+members = ["crate"]
+"#,
+			)
+			.expect("Manifest should be writable; qed;");
+
+			let mut expected =
+				vec![builder.workspace_manifest.clone(), builder.crate_manifest.clone()];
+			expected.sort();
+			assert_eq!(
+				find_workspace_members(&builder.workspace_manifest)
+					.expect("This should be Ok; qed;"),
+				expected
+			);
+		});
+}
+
+#[test]
+fn find_workspace_members_fails_if_invalid_glob_pattern() {
+	TestBuilder::default().tempdir_is_workspace().build().execute(|builder| {
+		std::fs::write(&builder.workspace_manifest, "[workspace]\nmembers = [\"crates/[\"]")
+			.expect("Manifest should be writable; qed;");
+		assert!(matches!(
+			find_workspace_members(&builder.workspace_manifest),
+			Err(Error::Descriptive(msg)) if msg.starts_with("Invalid workspace member pattern crates/[")
+		));
+	});
+}
+
+#[test]
+fn find_workspace_members_fails_if_not_workspace_manifest() {
+	TestBuilder::default().with_crate().build().execute(|builder| {
+		assert!(matches!(
+			find_workspace_members(&builder.crate_manifest),
+			Err(Error::Descriptive(msg)) if msg == "The provided manifest path isn't a workspace manifest"
+		));
+	});
+}
+
+#[test]
+fn add_crate_to_all_members_adds_dependency_to_every_member() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			let other_crate_manifest =
+				builder.tempdir.path().join("other_crate").join("Cargo.toml");
+			std::fs::create_dir_all(builder.tempdir.path().join("other_crate"))
+				.expect("This should be created; qed;");
+			std::fs::write(&other_crate_manifest, "[package]\nname = \"other\"\n")
+				.expect("Manifest should be writable; qed;");
+			std::fs::write(
+				&builder.workspace_manifest,
+				r#"
+[workspace]
+resolver = "2"
+members = ["crate", "other_crate"]
+"#,
+			)
+			.expect("Manifest should be writable; qed;");
+			std::fs::write(
+				&builder.crate_manifest,
+				r#"
+[package]
+name = "test"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+"#,
+			)
+			.expect("Manifest should be writable; qed;");
+
+			assert!(
+				add_crate_to_all_members(
+					&builder.workspace_manifest,
+					"dependency",
+					ManifestDependencyConfig::new(
+						ManifestDependencyOrigin::crates_io("0.1.0"),
+						false,
+						vec![],
+						true
+					),
+					false,
+					|_| true
+				)
+				.is_ok()
+			);
+
+			assert_eq!(
+				std::fs::read_to_string(&builder.crate_manifest)
+					.expect("This should be readable; qed;"),
+				r#"
+[package]
+name = "test"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+dependency = { version = "0.1.0", default-features = false, optional = true }
+"#
+			);
+			assert_eq!(
+				std::fs::read_to_string(&other_crate_manifest)
+					.expect("This should be readable; qed;"),
+				r#"[package]
+name = "other"
+
+[dependencies]
+dependency = { version = "0.1.0", default-features = false, optional = true }
+"#
+			);
+			// The workspace manifest isn't touched
+			assert!(
+				!std::fs::read_to_string(&builder.workspace_manifest)
+					.expect("This should be readable; qed;")
+					.contains("dependency")
+			);
+		});
+}
+
+#[test]
+fn add_crate_to_all_members_through_workspace_skipping_filtered_members() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			let other_crate_manifest =
+				builder.tempdir.path().join("other_crate").join("Cargo.toml");
+			std::fs::create_dir_all(builder.tempdir.path().join("other_crate"))
+				.expect("This should be created; qed;");
+			std::fs::write(&other_crate_manifest, "[package]\nname = \"other\"\n")
+				.expect("Manifest should be writable; qed;");
+			std::fs::write(
+				&builder.workspace_manifest,
+				r#"
+[workspace]
+resolver = "2"
+members = ["crate", "other_crate"]
+"#,
+			)
+			.expect("Manifest should be writable; qed;");
+			std::fs::write(
+				&builder.crate_manifest,
+				r#"
+[package]
+name = "test"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+"#,
+			)
+			.expect("Manifest should be writable; qed;");
+
+			assert!(
+				add_crate_to_all_members(
+					&builder.workspace_manifest,
+					"dependency",
+					ManifestDependencyConfig::new(
+						ManifestDependencyOrigin::git("https://some_url.com", "main"),
+						true,
+						vec!["feature"],
+						true
+					),
+					true,
+					|member| member != other_crate_manifest
+				)
+				.is_ok()
+			);
+
+			assert_eq!(
+				std::fs::read_to_string(&builder.workspace_manifest)
+					.expect("This should be readable; qed;"),
+				r#"
+[workspace]
+resolver = "2"
+members = ["crate", "other_crate"]
+
+[workspace.dependencies]
+dependency = { git = "https://some_url.com", branch = "main", features = ["feature"] }
+"#
+			);
+			assert_eq!(
+				std::fs::read_to_string(&builder.crate_manifest)
+					.expect("This should be readable; qed;"),
+				r#"
+[package]
+name = "test"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+dependency = { workspace = true, optional = true }
+"#
+			);
+			assert_eq!(
+				std::fs::read_to_string(&other_crate_manifest)
+					.expect("This should be readable; qed;"),
+				"[package]\nname = \"other\"\n"
+			);
+		});
+}
+
+#[test]
+fn add_crate_to_all_members_fails_if_dependencies_section_isnt_a_table() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			std::fs::write(
+				&builder.crate_manifest,
+				"dependencies = 1\n\n[package]\nname = \"test\"\n",
+			)
+			.expect("Manifest should be writable; qed;");
+			assert!(matches!(
+				add_crate_to_all_members(
+					&builder.workspace_manifest,
+					"dependency",
+					ManifestDependencyConfig::new(ManifestDependencyOrigin::workspace(), true, vec![], false),
+					false,
+					|_| true
+				),
+				Err(Error::Descriptive(msg)) if msg == "The `dependencies` section isn't a table"
+			));
+		});
+}
+
+#[test]
+fn add_crate_to_all_members_doesnt_write_anything_if_some_member_cannot_be_edited() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			let workspace_manifest = std::fs::read_to_string(&builder.workspace_manifest)
+				.expect("Manifest should be readable; qed;");
+			std::fs::write(
+				&builder.crate_manifest,
+				"dependencies = 1\n\n[package]\nname = \"test\"\n",
+			)
+			.expect("Manifest should be writable; qed;");
+			assert!(matches!(
+				add_crate_to_all_members(
+					&builder.workspace_manifest,
+					"dependency",
+					ManifestDependencyConfig::new(ManifestDependencyOrigin::crates_io("1.0"), true, vec![], false),
+					true,
+					|_| true
+				),
+				Err(Error::Descriptive(msg)) if msg == "The `dependencies` section isn't a table"
+			));
+			assert_eq!(
+				std::fs::read_to_string(&builder.workspace_manifest)
+					.expect("Manifest should be readable; qed;"),
+				workspace_manifest
+			);
+		});
+}
+
+#[test]
+fn add_crate_to_all_members_fails_if_not_workspace_manifest() {
+	TestBuilder::default().with_crate().build().execute(|builder| {
+		assert!(matches!(
+			add_crate_to_all_members(
+				&builder.crate_manifest,
+				"dependency",
+				ManifestDependencyConfig::new(ManifestDependencyOrigin::workspace(), true, vec![], false),
+				false,
+				|_| true
+			),
+			Err(Error::Descriptive(msg)) if msg == "The provided manifest path isn't a workspace manifest"
+		));
+	});
+}
+
+#[test]
+fn members_depending_on_scans_every_dependency_section() {
+	TestBuilder::default().tempdir_is_workspace().build().execute(|builder| {
+		let members = [
+			("a", "[dependencies]\nserde = \"1.0\""),
+			("b", "[dev-dependencies]\nserde = { version = \"1.0\" }"),
+			("c", "[build-dependencies.serde]\nversion = \"1.0\""),
+			("d", "[target.'cfg(unix)'.dependencies]\nserde = \"1.0\""),
+			("e", "[dependencies]\nserde_alias = { package = \"serde\", version = \"1.0\" }"),
+			("f", "[dependencies]\nsyn = \"2.0\"\nserde_json = \"1.0\""),
+			("g", "[dependencies]\nserde = { package = \"other\", version = \"1.0\" }"),
+		];
+		for (member, dependencies) in members {
+			let member_path = builder.tempdir.path().join(member);
+			std::fs::create_dir_all(&member_path).expect("This should be created; qed;");
+			std::fs::write(
+				member_path.join("Cargo.toml"),
+				format!("[package]\nname = \"{member}\"\n{dependencies}"),
+			)
+			.expect("Manifest should be writable; qed;");
+		}
+		std::fs::write(&builder.workspace_manifest, "[workspace]\nmembers = [\"*\"]\n")
+			.expect("Manifest should be writable; qed;");
+
+		assert_eq!(
+			members_depending_on(&builder.workspace_manifest, "serde")
+				.expect("This should be Ok; qed;"),
+			["a", "b", "c", "d", "e"]
+				.iter()
+				.map(|member| builder.tempdir.path().join(member).join("Cargo.toml"))
+				.collect::<Vec<_>>()
+		);
+	});
+}
+
+#[test]
+fn members_depending_on_returns_empty_if_no_member_depends_on_the_crate() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			assert!(
+				members_depending_on(&builder.workspace_manifest, "serde")
+					.expect("This should be Ok; qed;")
+					.is_empty()
+			);
+		});
+}
+
+#[test]
+fn members_depending_on_fails_if_member_manifest_cannot_be_parsed() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			std::fs::write(&builder.crate_manifest, "[package")
+				.expect("Manifest should be writable; qed;");
+			assert!(matches!(
+				members_depending_on(&builder.workspace_manifest, "serde"),
+				Err(Error::TomlEdit(_))
+			));
+		});
+}
+
+#[test]
+fn crates_affected_by_includes_owners_and_transitive_dependents() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			for (member, dependencies) in [
+				("dependent", "[dependencies]\ntest = { path = \"../crate\" }"),
+				("transitive", "[build-dependencies]\ndependent = { path = \"../dependent\" }"),
+				("unrelated", ""),
+			] {
+				let member_path = builder.tempdir.path().join(member);
+				std::fs::create_dir_all(member_path.join("src"))
+					.expect("This should be created; qed;");
+				std::fs::write(
+					member_path.join("Cargo.toml"),
+					format!("[package]\nname = \"{member}\"\n{dependencies}"),
+				)
+				.expect("Manifest should be writable; qed;");
+			}
+			std::fs::write(&builder.workspace_manifest, "[workspace]\nmembers = [\"*\"]\n")
+				.expect("Manifest should be writable; qed;");
+
+			assert_eq!(
+				crates_affected_by(
+					&[builder.crate_paths[3].clone(), builder.tempdir.path().join("README.md")],
+					&builder.workspace_manifest
+				)
+				.expect("This should be Ok; qed;"),
+				vec!["dependent", "test", "transitive"]
+			);
+			assert_eq!(
+				crates_affected_by(
+					&[
+						builder.tempdir.path().join("unrelated").join("src").join("deleted.rs"),
+						builder.tempdir.path().join("transitive").join("Cargo.toml")
+					],
+					&builder.workspace_manifest
+				)
+				.expect("This should be Ok; qed;"),
+				vec!["transitive", "unrelated"]
+			);
+			assert!(
+				crates_affected_by(&[], &builder.workspace_manifest)
+					.expect("This should be Ok; qed;")
+					.is_empty()
+			);
+		});
+}
+
+#[test]
+fn crates_affected_by_works_with_relative_paths() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.with_calling_dir_override(CallingDirOverride::WorkspaceRoot)
+		.build()
+		.execute(|builder| {
+			assert_eq!(
+				crates_affected_by(&[builder.crate_paths[3].clone()], &builder.workspace_manifest)
+					.expect("This should be Ok; qed;"),
+				vec!["test"]
+			);
+		});
+}
+
+#[test]
+fn prepare_for_publish_strips_local_keys_and_writes_if_asked() {
+	TestBuilder::default().with_crate().build().execute(|builder| {
+		std::fs::write(
+			&builder.crate_manifest,
+			r#"[package]
+name = "test"
+
+[dependencies]
+local = { path = "../local", version = "0.1.0", features = ["feature"] }
+remote = { git = "https://some_url.com", branch = "main", version = "1.0.0" }
+inherited = { workspace = true }
+registry = "1.0.0"
+
+[dev-dependencies]
+local-dev = { path = "../local-dev" }
+versioned-dev = { path = "../versioned-dev", version = "0.2.0" }
+
+[target.'cfg(unix)'.build-dependencies.local-build]
+path = "../local-build"
+version = "0.3.0"
+
+[patch.crates-io]
+registry = { path = "../registry" }
+"#,
+		)
+		.expect("Manifest should be writable; qed;");
+
+		let expected = r#"[package]
+name = "test"
+
+[dependencies]
+local = { version = "0.1.0", features = ["feature"] }
+remote = { version = "1.0.0" }
+inherited = { workspace = true }
+registry = "1.0.0"
+
+[dev-dependencies]
+versioned-dev = { version = "0.2.0" }
+
+[target.'cfg(unix)'.build-dependencies.local-build]
+version = "0.3.0"
+"#;
+		assert_eq!(
+			prepare_for_publish(&builder.crate_manifest, true).expect("This should be Ok; qed;"),
+			expected
+		);
+		assert_eq!(
+			std::fs::read_to_string(&builder.crate_manifest)
+				.expect("This should be readable; qed;"),
+			expected
+		);
+	});
+}
+
+#[test]
+fn prepare_for_publish_fails_if_local_dependency_without_version() {
+	TestBuilder::default().with_crate().build().execute(|builder| {
+		std::fs::write(
+			&builder.crate_manifest,
+			"[package]\nname = \"test\"\n\n[target.'cfg(unix)'.dependencies]\nlocal = { git = \"https://some_url.com\" }\n",
+		)
+		.expect("Manifest should be writable; qed;");
+
+		assert!(matches!(
+			prepare_for_publish(&builder.crate_manifest, true),
+			Err(Error::Descriptive(msg)) if msg == "The dependency local doesn't specify a version, so it cannot be published"
+		));
+		// The manifest isn't touched
+		assert!(
+			std::fs::read_to_string(&builder.crate_manifest)
+				.expect("This should be readable; qed;")
+				.contains("git")
+		);
+	});
+}
+
+#[test]
+fn prepare_for_publish_fails_if_manifest_path_cannot_be_parsed() {
+	TestBuilder::default().with_crate().build().execute(|builder| {
+		assert!(matches!(
+			prepare_for_publish(&builder.crate_paths[3], false), // main.rs path
+			Err(Error::TomlEdit(_))
+		));
+	});
+}
+
+#[test]
+fn copy_section_replaces_section_and_preserves_comments() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			std::fs::write(
+				&builder.workspace_manifest,
+				r#"[workspace]
+members = ["crate"]
+
+# Shared lints
+[workspace.lints.rust]
+unsafe_code = "forbid" # No unsafe
+
+[workspace.lints.clippy]
+all = "deny"
+
+[profile.dev]
+opt-level = 1
+"#,
+			)
+			.expect("Manifest should be writable; qed;");
+			std::fs::write(
+				&builder.crate_manifest,
+				r#"[package]
+name = "test"
+
+[workspace.lints.rust]
+unsafe_code = "allow"
+
+[dependencies]
+"#,
+			)
+			.expect("Manifest should be writable; qed;");
+
+			copy_section(
+				&builder.workspace_manifest,
+				&builder.crate_manifest,
+				&["workspace", "lints"],
+			)
+			.expect("This should be Ok; qed;");
+
+			assert_eq!(
+				std::fs::read_to_string(&builder.crate_manifest)
+					.expect("This should be readable; qed;"),
+				r#"[package]
+name = "test"
+
+[dependencies]
+
+# Shared lints
+[workspace.lints.rust]
+unsafe_code = "forbid" # No unsafe
+
+[workspace.lints.clippy]
+all = "deny"
+"#
+			);
+		});
+}
+
+#[test]
+fn copy_section_creates_missing_parent_sections() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			std::fs::write(
+				&builder.workspace_manifest,
+				"[workspace]\n\n[package.metadata.docs.rs]\nall-features = true\n",
+			)
+			.expect("Manifest should be writable; qed;");
+			std::fs::write(&builder.crate_manifest, "[package]\nname = \"test\"\n")
+				.expect("Manifest should be writable; qed;");
+
+			copy_section(
+				&builder.workspace_manifest,
+				&builder.crate_manifest,
+				&["package", "metadata", "docs", "rs"],
+			)
+			.expect("This should be Ok; qed;");
+
+			assert_eq!(
+				std::fs::read_to_string(&builder.crate_manifest)
+					.expect("This should be readable; qed;"),
+				"[package]\nname = \"test\"\n\n[package.metadata.docs.rs]\nall-features = true\n"
+			);
+		});
+}
+
+#[test]
+fn copy_section_fails_if_section_cannot_be_copied() {
+	TestBuilder::default().tempdir_is_workspace().with_crate().build().execute(|builder| {
+		std::fs::write(&builder.workspace_manifest, "[workspace]\n\n[lints.rust]\nunsafe_code = \"forbid\"\n")
+			.expect("Manifest should be writable; qed;");
+		std::fs::write(&builder.crate_manifest, "lints = 1\n")
+			.expect("Manifest should be writable; qed;");
+
+		assert!(matches!(
+			copy_section(&builder.workspace_manifest, &builder.crate_manifest, &[]),
+			Err(Error::Descriptive(msg)) if msg == "The section key path cannot be empty"
+		));
+		assert!(matches!(
+			copy_section(&builder.workspace_manifest, &builder.crate_manifest, &["profile", "release"]),
+			Err(Error::Descriptive(msg)) if msg == "The `profile.release` section doesn't exist in the source manifest"
+		));
+		assert!(matches!(
+			copy_section(&builder.workspace_manifest, &builder.crate_manifest, &["lints", "rust"]),
+			Err(Error::Descriptive(msg)) if msg == "The `lints` section isn't a table"
+		));
+	});
+}
+
+#[test]
+fn target_dir_prefers_env_var() {
+	TestBuilder::default().with_crate().build().execute(|builder| {
+		assert_eq!(
+			do_target_dir(
+				&builder.crate_paths[0],
+				Some(builder.tempdir.path().join("env_target")),
+				None
+			)
+			.expect("This should be Ok; qed;"),
+			builder.tempdir.path().join("env_target")
+		);
+	});
+}
+
+#[test]
+fn target_dir_uses_innermost_config_file() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			let crate_dir = builder.crate_manifest.parent().expect("This should be Ok; qed;");
+			let write_config = |dir: &Path, name: &str, content: &str| {
+				std::fs::create_dir_all(dir.join(".cargo")).expect("This should be created; qed;");
+				std::fs::write(dir.join(".cargo").join(name), content)
+					.expect("Config should be writable; qed;");
+			};
+			write_config(builder.tempdir.path(), "config.toml", "[build]\ntarget-dir = \"outer\"");
+			write_config(crate_dir, "config.toml", "[alias]\nb = \"build\"");
+
+			assert_eq!(
+				do_target_dir(&builder.crate_paths[0], None, None)
+					.expect("This should be Ok; qed;"),
+				builder.tempdir.path().join("outer")
+			);
+
+			// The legacy config file takes precedence, and absolute paths are kept
+			let absolute_target = builder.tempdir.path().join("absolute");
+			write_config(
+				crate_dir,
+				"config",
+				&format!("[build]\ntarget-dir = \"{}\"", absolute_target.display()),
+			);
+			assert_eq!(
+				do_target_dir(&builder.crate_paths[0], None, None)
+					.expect("This should be Ok; qed;"),
+				absolute_target
+			);
+		});
+}
+
+#[test]
+fn target_dir_uses_cargo_home_config_file() {
+	TestBuilder::default().with_crate().build().execute(|builder| {
+		let cargo_home = builder.tempdir.path().join("home").join(".cargo");
+		std::fs::create_dir_all(&cargo_home).expect("This should be created; qed;");
+		std::fs::write(cargo_home.join("config.toml"), "[build]\ntarget-dir = \"shared\"")
+			.expect("Config should be writable; qed;");
+
+		assert_eq!(
+			do_target_dir(&builder.crate_paths[0], None, Some(cargo_home))
+				.expect("This should be Ok; qed;"),
+			builder.tempdir.path().join("home").join("shared")
+		);
+	});
+}
+
+#[test]
+fn target_dir_defaults_to_workspace_root() {
+	TestBuilder::default()
+		.tempdir_is_workspace()
+		.with_crate()
+		.build()
+		.execute(|builder| {
+			assert_eq!(
+				do_target_dir(&builder.crate_paths[0], None, None)
+					.expect("This should be Ok; qed;"),
+				builder.tempdir.path().join("target")
+			);
+		});
+	TestBuilder::default().with_crate().build().execute(|builder| {
+		assert_eq!(
+			do_target_dir(&builder.crate_paths[0], None, None).expect("This should be Ok; qed;"),
+			builder.crate_manifest.parent().expect("This should be Ok; qed;").join("target")
+		);
+	});
+}
+
+#[test]
+fn target_dir_skips_workspaces_not_including_the_crate() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	for member in ["member", "vendor/standalone"] {
+		std::fs::create_dir_all(tempdir.path().join(member).join("src"))
+			.expect("This should be created; qed;");
+		std::fs::write(
+			tempdir.path().join(member).join("Cargo.toml"),
+			format!("[package]\nname = \"{}\"", member.replace('/', "-")),
+		)
+		.expect("The manifest should be writable; qed;");
+	}
+	std::fs::write(tempdir.path().join("Cargo.toml"), "[workspace]\nmembers = [\"member\"]\n")
+		.expect("The manifest should be writable; qed;");
+
+	assert_eq!(
+		do_target_dir(&tempdir.path().join("member/src"), None, None)
+			.expect("This should be Ok; qed;"),
+		tempdir.path().join("target")
+	);
+	assert_eq!(
+		do_target_dir(&tempdir.path().join("vendor/standalone/src"), None, None)
+			.expect("This should be Ok; qed;"),
+		tempdir.path().join("vendor/standalone/target")
+	);
+}
+
+#[test]
+fn target_dir_fails_if_not_rust_dir_or_invalid_config() {
+	TestBuilder::default().with_non_crate().build().execute(|builder| {
+		assert!(matches!(
+			do_target_dir(&builder.non_crate_paths[0], None, None),
+			Err(Error::Descriptive(msg)) if msg == "The provided path isn't part of a crate or workspace"
+		));
+
+		std::fs::create_dir_all(builder.tempdir.path().join(".cargo"))
+			.expect("This should be created; qed;");
+		std::fs::write(
+			builder.tempdir.path().join(".cargo").join("config.toml"),
+			"[build]\ntarget-dir = 1",
+		)
+		.expect("Config should be writable; qed;");
+		assert!(matches!(
+			do_target_dir(&builder.non_crate_paths[0], None, None),
+			Err(Error::Descriptive(msg)) if msg.ends_with("isn't a string")
+		));
+	});
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn manifest_functions_work_with_memory_fs() {
+	use crate::testing::MemoryFs;
+
+	let fs = MemoryFs::new()
+		.with_file("/ws/Cargo.toml", "[workspace]\nmembers = [\"a\"]\n")
+		.with_file("/ws/a/Cargo.toml", "[package]\nname = \"a\"\nversion = \"0.1.0\"\n")
+		.with_file("/ws/a/src/lib.rs", "");
+
+	assert_eq!(
+		find_innermost_manifest_with_fs(&fs, "/ws/a/src/lib.rs"),
+		Some(PathBuf::from("/ws/a/Cargo.toml"))
+	);
+	assert_eq!(
+		find_workspace_manifest_with_fs(&fs, "/ws/a/src/lib.rs"),
+		Some(PathBuf::from("/ws/Cargo.toml"))
+	);
+	assert_eq!(find_innermost_manifest_with_fs(&fs, "/elsewhere"), None);
+	assert_eq!(find_crate_name_with_fs(&fs, "/ws/a/Cargo.toml"), Some("a".to_owned()));
+	assert_eq!(find_crate_name_with_fs(&fs, "/ws/Cargo.toml"), None);
+
+	add_crate_to_dependencies_with_fs(
+		&fs,
+		"/ws/a/Cargo.toml",
+		"serde",
+		ManifestDependencyConfig::new(ManifestDependencyOrigin::workspace(), true, vec![], false),
+	)
+	.expect("This should be Ok; qed;");
+	assert_eq!(
+		fs.file("/ws/a/Cargo.toml"),
+		Some(
+			"[package]\nname = \"a\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = { workspace = true }\n"
+				.to_owned()
+		)
+	);
+
+	add_crate_to_workspace_with_fs(&fs, "/ws/Cargo.toml", "/ws/b")
+		.expect("This should be Ok; qed;");
+	assert_eq!(
+		fs.file("/ws/Cargo.toml"),
+		Some("[workspace]\nmembers = [\"a\", \"b\"]\n".to_owned())
+	);
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn manifest_functions_fail_if_memory_fs_manifest_is_read_only() {
+	use crate::testing::MemoryFs;
+
+	let fs = MemoryFs::new().with_file("/ws/Cargo.toml", "[workspace]\nmembers = []\n");
+	fs.set_read_only("/ws/Cargo.toml");
+
+	assert!(matches!(
+		add_crate_to_dependencies_with_fs(
+			&fs,
+			"/ws/Cargo.toml",
+			"dependency",
+			ManifestDependencyConfig::new(ManifestDependencyOrigin::crates_io("1.0"), false, vec![], false)
+		),
+		Err(Error::IO(err)) if err.kind() == ErrorKind::PermissionDenied
+	));
+	assert!(matches!(
+		add_crate_to_workspace_with_fs(&fs, "/ws/Cargo.toml", "/ws/dependency"),
+		Err(Error::IO(err)) if err.kind() == ErrorKind::PermissionDenied
+	));
+	assert!(matches!(
+		add_crate_to_workspace_with_fs(&fs, "/other/Cargo.toml", "/other/dependency"),
+		Err(Error::IO(err)) if err.kind() == ErrorKind::NotFound
+	));
+	assert_eq!(fs.file("/ws/Cargo.toml"), Some("[workspace]\nmembers = []\n".to_owned()));
+}
+
+#[test]
+fn add_crate_to_dependencies_str_works() {
+	let config = || {
+		ManifestDependencyConfig::new(ManifestDependencyOrigin::workspace(), true, vec![], false)
+	};
+
+	assert_eq!(
+		add_crate_to_dependencies_str("", "serde", config()).expect("This should be Ok; qed;"),
+		"[dependencies]\nserde = { workspace = true }\n"
+	);
+	assert_eq!(
+		add_crate_to_dependencies_str(
+			"[workspace]\nmembers = []\n",
+			"serde",
+			ManifestDependencyConfig::builder(ManifestDependencyOrigin::crates_io("1.0")).build()
+		)
+		.expect("This should be Ok; qed;"),
+		"[workspace]\nmembers = []\n\n[workspace.dependencies]\nserde = { version = \"1.0\" }\n"
+	);
+	assert!(matches!(
+		add_crate_to_dependencies_str("[workspace]\nmembers = []\n", "serde", config()),
+		Err(Error::Descriptive(msg)) if msg == "The dependencies of `workspace.dependencies` cannot be inherited from the workspace"
+	));
+	assert!(matches!(
+		add_crate_to_dependencies_str("[package", "serde", config()),
+		Err(Error::TomlEdit(_))
+	));
+	assert!(matches!(
+		add_crate_to_dependencies_str("dependencies = [\"serde\"]\n", "serde", config()),
+		Err(Error::Descriptive(msg)) if msg == "The `dependencies` section isn't a table"
+	));
+}
+
+#[test]
+fn add_crate_to_dependencies_str_with_style_writes_table_style_dependencies() {
+	let manifest = r#"[package]
+name = "test"
+
+[dependencies]
+core = { path = "../core" }
+
+[features]
+std = []
+"#;
+	let config = ManifestDependencyConfig::builder(ManifestDependencyOrigin::crates_io("1.0"))
+		.no_default_features()
+		.features(&["derive", "rc"])
+		.build();
+
+	let content = add_crate_to_dependencies_str_with_style(
+		manifest,
+		"serde",
+		config.clone(),
+		DependencyStyle::TableIfMoreKeysThan(2),
+	)
+	.expect("This should be Ok; qed;");
+	assert_eq!(
+		content,
+		r#"[package]
+name = "test"
+
+[dependencies]
+core = { path = "../core" }
+
+[dependencies.serde]
+version = "1.0"
+default-features = false
+features = ["derive", "rc"]
+
+[features]
+std = []
+"#
+	);
+	assert_eq!(
+		ManifestDependencyConfig::read_from_manifest(&content, "serde")
+			.expect("This should be Ok; qed;"),
+		Some((config, DependencyStyle::Table))
+	);
+
+	// Adding it again with the inline style replaces the table
+	let content = add_crate_to_dependencies_str(
+		&content,
+		"serde",
+		ManifestDependencyConfig::new(
+			ManifestDependencyOrigin::crates_io("1.0"),
+			true,
+			vec![],
+			false,
+		),
+	)
+	.expect("This should be Ok; qed;");
+	assert_eq!(
+		content,
+		"[package]\nname = \"test\"\n\n[dependencies]\ncore = { path = \"../core\" }\nserde = { version = \"1.0\" }\n\n[features]\nstd = []\n"
+	);
+}
+
+#[test]
+fn find_crate_name_str_works() {
+	assert_eq!(
+		find_crate_name_str("[package]\nname = \"test\"\nversion = \"0.1.0\"\n"),
+		Some("test".to_owned())
+	);
+	assert_eq!(find_crate_name_str("[workspace]\nmembers = []\n"), None);
+	assert_eq!(find_crate_name_str("[package"), None);
+}
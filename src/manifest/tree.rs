@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities to render the dependencies of a workspace as text.
+
+#[cfg(test)]
+mod tests;
+
+use super::{DependencyKind, WorkspaceGraph, dependency_package_name, dependency_tables};
+use crate::Error;
+use std::{collections::BTreeSet, path::Path};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// The dependency kinds in the order they're rendered, together with their section headers.
+const RENDERED_KINDS: [(DependencyKind, &str); 3] = [
+	(DependencyKind::Normal, ""),
+	(DependencyKind::Build, "[build-dependencies]"),
+	(DependencyKind::Dev, "[dev-dependencies]"),
+];
+
+/// Given a workspace manifest file path, this function renders the dependencies of the workspace
+/// members as an ASCII tree similar to the output of `cargo tree --workspace`, without invoking
+/// cargo nor resolving the dependencies. This is handy for quick diagnostics.
+///
+/// Every member is rendered as the root of a tree, in the order returned by
+/// [`super::find_workspace_members`], and the trees are separated by a blank line. The
+/// dependencies on other members are expanded recursively, while the external dependencies are
+/// rendered as leaves showing their declared version requirement (or their git/path source if
+/// they don't declare a version). As cargo does, members already expanded in a tree are marked
+/// with `(*)` instead of being expanded again, and dev dependencies are only rendered for the
+/// roots.
+///
+/// # Errors
+///
+/// - If the workspace graph cannot be built (see [`WorkspaceGraph::load`]).
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// for (member, dependencies) in [
+///     ("core", "[dependencies]\nserde = \"1.0\""),
+///     ("cli", "[dependencies]\ncore = { path = \"../core\" }\nclap = { version = \"4\" }"),
+/// ] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{member}\"\nversion = \"0.1.0\"\n{dependencies}"),
+///     ).unwrap();
+/// }
+/// std::fs::write(tempdir.path().join("Cargo.toml"), "[workspace]\nmembers = [\"*\"]").unwrap();
+///
+/// assert_eq!(
+///     rustilities::manifest::render_dependency_tree(tempdir.path().join("Cargo.toml")).unwrap(),
+///     r#"cli v0.1.0
+/// ├── clap 4
+/// └── core v0.1.0
+///     └── serde 1.0
+///
+/// core v0.1.0
+/// └── serde 1.0
+/// "#
+/// );
+/// ```
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip_all, fields(workspace_toml = %workspace_toml.as_ref().display()))
+)]
+pub fn render_dependency_tree<P: AsRef<Path>>(workspace_toml: P) -> Result<String, Error> {
+	let workspace_toml = workspace_toml.as_ref();
+	let graph = WorkspaceGraph::load(workspace_toml)?;
+	let workspace_doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+	let workspace = workspace_doc.get("workspace");
+	let workspace_dependencies = workspace
+		.and_then(|workspace| workspace.get("dependencies"))
+		.and_then(Item::as_table_like);
+	let workspace_version = workspace
+		.and_then(|workspace| workspace.get("package"))
+		.and_then(|package| package.get("version"))
+		.and_then(Item::as_str);
+
+	let docs = graph
+		.members()
+		.iter()
+		.map(|member| Ok(std::fs::read_to_string(&member.manifest_path)?.parse::<DocumentMut>()?))
+		.collect::<Result<Vec<_>, Error>>()?;
+	let labels = graph
+		.members()
+		.iter()
+		.zip(&docs)
+		.map(|(member, doc)| {
+			let version =
+				doc.get("package")
+					.and_then(|package| package.get("version"))
+					.and_then(|version| match version.get("workspace").and_then(Item::as_bool) {
+						Some(true) => workspace_version,
+						_ => version.as_str(),
+					});
+			match version {
+				Some(version) => format!("{} v{version}", member.name),
+				None => member.name.clone(),
+			}
+		})
+		.collect::<Vec<_>>();
+
+	let mut nodes = Vec::with_capacity(docs.len());
+	for (member, doc) in graph.members().iter().zip(&docs) {
+		let internal = graph.dependencies_of(&member.name);
+		let mut dependencies = internal
+			.iter()
+			.filter_map(|(name, kind)| {
+				let index = graph.members().iter().position(|member| member.name == *name)?;
+				Some((*kind, labels[index].clone(), Some(index)))
+			})
+			.collect::<Vec<_>>();
+		for (kind, table) in dependency_tables(doc) {
+			for (key, dependency) in table.iter() {
+				let package = dependency_package_name(key, dependency);
+				if internal.contains(&(package, kind)) {
+					continue;
+				}
+				let dependency = match dependency.get("workspace").and_then(Item::as_bool) {
+					Some(true) => workspace_dependencies
+						.and_then(|dependencies| dependencies.get(key))
+						.unwrap_or(dependency),
+					_ => dependency,
+				};
+				dependencies.push((kind, external_label(package, dependency), None));
+			}
+		}
+		dependencies.sort();
+		dependencies.dedup();
+		nodes.push(dependencies);
+	}
+
+	let trees = (0..nodes.len())
+		.map(|root| {
+			let mut output = format!("{}\n", labels[root]);
+			let mut expanded = BTreeSet::from([root]);
+			render_dependencies(&nodes, root, "", true, &mut expanded, &mut output);
+			output
+		})
+		.collect::<Vec<_>>();
+	Ok(trees.join("\n"))
+}
+
+/// The dependencies of a member as (kind, label, member index) triples. The member index is
+/// `None` for external dependencies.
+type Dependencies = Vec<(DependencyKind, String, Option<usize>)>;
+
+fn render_dependencies(
+	nodes: &[Dependencies],
+	index: usize,
+	prefix: &str,
+	is_root: bool,
+	expanded: &mut BTreeSet<usize>,
+	output: &mut String,
+) {
+	for (kind, header) in RENDERED_KINDS {
+		if kind == DependencyKind::Dev && !is_root {
+			continue;
+		}
+		let dependencies = nodes[index]
+			.iter()
+			.filter(|(dependency_kind, _, _)| *dependency_kind == kind)
+			.collect::<Vec<_>>();
+		if dependencies.is_empty() {
+			continue;
+		}
+		if !header.is_empty() {
+			output.push_str(&format!("{prefix}{header}\n"));
+		}
+		for (position, (_, label, member)) in dependencies.iter().enumerate() {
+			let is_last = position == dependencies.len() - 1;
+			let connector = if is_last { "└── " } else { "├── " };
+			output.push_str(&format!("{prefix}{connector}{label}"));
+			match member {
+				Some(member) if !expanded.insert(*member) => output.push_str(" (*)\n"),
+				Some(member) => {
+					output.push('\n');
+					let child_prefix = format!("{prefix}{}", if is_last { "    " } else { "│   " });
+					render_dependencies(nodes, *member, &child_prefix, false, expanded, output);
+				},
+				None => output.push('\n'),
+			}
+		}
+	}
+}
+
+/// The label of an external dependency: its package name followed by its version requirement, or
+/// its source if it doesn't declare a version.
+fn external_label(package: &str, dependency: &Item) -> String {
+	if let Some(version) = dependency.as_str() {
+		return format!("{package} {version}");
+	}
+	let dependency: Option<&dyn TableLike> = dependency.as_table_like();
+	let get = |key: &str| dependency.and_then(|dependency| dependency.get(key)?.as_str());
+	match (get("version"), get("git"), get("path")) {
+		(Some(version), _, _) => format!("{package} {version}"),
+		(None, Some(git), _) => format!("{package} (git: {git})"),
+		(None, None, Some(path)) => format!("{package} (path: {path})"),
+		_ => package.to_owned(),
+	}
+}
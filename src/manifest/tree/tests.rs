@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use crate::test_utils::workspace;
+
+#[test]
+fn render_dependency_tree_renders_every_kind_and_source() {
+	let tempdir = workspace(
+		r#"
+[workspace]
+members = ["a", "b", "c"]
+
+[workspace.package]
+version = "1.0.0"
+
+[workspace.dependencies]
+b = { path = "b" }
+serde = { version = "1.0", features = ["derive"] }
+"#,
+		&[
+			(
+				"a",
+				r#"
+[package]
+name = "a"
+version.workspace = true
+
+[dependencies]
+b.workspace = true
+c = { path = "../c" }
+serde.workspace = true
+local = { path = "../local" }
+remote = { git = "https://github.com/foo/remote" }
+renamed = { package = "original", version = "0.3" }
+
+[build-dependencies]
+cc = "1"
+
+[dev-dependencies]
+tempfile = "3"
+"#,
+			),
+			(
+				"b",
+				"[package]\nname = \"b\"\nversion = \"0.2.0\"\n\n[dependencies]\nc = { path = \"../c\" }\n\n[dev-dependencies]\nproptest = \"1\"\n",
+			),
+			("c", "[package]\nname = \"c\"\n\n[target.'cfg(unix)'.dependencies]\nlibc = \"0.2\"\n"),
+		],
+	);
+
+	assert_eq!(
+		render_dependency_tree(tempdir.path().join("Cargo.toml")).expect("This should be Ok; qed;"),
+		r#"a v1.0.0
+├── b v0.2.0
+│   └── c
+│       └── libc 0.2
+├── c (*)
+├── local (path: ../local)
+├── original 0.3
+├── remote (git: https://github.com/foo/remote)
+└── serde 1.0
+[build-dependencies]
+└── cc 1
+[dev-dependencies]
+└── tempfile 3
+
+b v0.2.0
+└── c
+    └── libc 0.2
+[dev-dependencies]
+└── proptest 1
+
+c
+└── libc 0.2
+"#
+	);
+}
+
+#[test]
+fn render_dependency_tree_marks_cycles() {
+	let tempdir = workspace(
+		"[workspace]\nmembers = [\"a\", \"b\"]\n",
+		&[
+			("a", "[package]\nname = \"a\"\n\n[dependencies]\nb = { path = \"../b\" }\n"),
+			("b", "[package]\nname = \"b\"\n\n[dev-dependencies]\na = { path = \"../a\" }\n"),
+		],
+	);
+
+	assert_eq!(
+		render_dependency_tree(tempdir.path().join("Cargo.toml")).expect("This should be Ok; qed;"),
+		"a\n└── b\n\nb\n[dev-dependencies]\n└── a\n    └── b (*)\n"
+	);
+}
+
+#[test]
+fn render_dependency_tree_fails_if_not_a_workspace() {
+	let tempdir = workspace("[package]\nname = \"test\"\n", &[]);
+	assert!(matches!(
+		render_dependency_tree(tempdir.path().join("Cargo.toml")),
+		Err(Error::Descriptive(msg)) if msg == "The provided manifest path isn't a workspace manifest"
+	));
+}
@@ -3,7 +3,9 @@
 #[cfg(test)]
 mod tests;
 
-use std::path::Path;
+use crate::Error;
+use std::{fmt, path::Path};
+use toml_edit::{Array, ImDocument, InlineTable, Item, Table, TableLike, Value};
 
 /// A struct representing how a dependency should look like in a Rust manifest.
 #[derive(Debug, Clone, PartialEq)]
@@ -14,6 +16,19 @@ pub struct ManifestDependencyConfig<'a> {
 	pub optional: bool,
 }
 
+/// How a dependency is written to a manifest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DependencyStyle {
+	/// An inline table, eg `serde = { version = "1.0", features = ["derive"] }`.
+	#[default]
+	Inline,
+	/// A table of its own, eg `[dependencies.serde]` followed by a line per key.
+	Table,
+	/// A table of its own if the declaration has more keys than the given number, an inline table
+	/// otherwise.
+	TableIfMoreKeysThan(usize),
+}
+
 impl<'a> ManifestDependencyConfig<'a> {
 	/// Creates a new instance of ManifestDependencyConfig specifying:
 	/// - The dependency origin.
@@ -29,10 +44,583 @@ impl<'a> ManifestDependencyConfig<'a> {
 		Self { origin, default_features, features, optional }
 	}
 
+	/// Creates a builder for a ManifestDependencyConfig with the given origin. Unless the builder
+	/// says otherwise, the dependency uses its default features, no extra features and isn't
+	/// optional.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use rustilities::manifest::{ManifestDependencyConfig, ManifestDependencyOrigin};
+	///
+	/// let config = ManifestDependencyConfig::builder(ManifestDependencyOrigin::crates_io("1.0"))
+	///     .no_default_features()
+	///     .feature("derive")
+	///     .optional()
+	///     .build();
+	///
+	/// assert_eq!(
+	///     config,
+	///     ManifestDependencyConfig::new(
+	///         ManifestDependencyOrigin::crates_io("1.0"),
+	///         false,
+	///         vec!["derive"],
+	///         true
+	///     )
+	/// );
+	/// ```
+	pub fn builder(origin: ManifestDependencyOrigin<'a>) -> ManifestDependencyConfigBuilder<'a> {
+		ManifestDependencyConfigBuilder { config: Self { origin, ..Default::default() } }
+	}
+
 	/// Add some features to an existing ManifestDependencyConfig
 	pub fn add_features(&mut self, features: &[&'a str]) {
 		self.features.extend_from_slice(features);
 	}
+
+	/// Parses a dependency spec following the `cargo add` syntax, returning the dependency name
+	/// together with its config, so user input can be passed straight to
+	/// [`add_crate_to_dependencies`](super::add_crate_to_dependencies). The spec contains the
+	/// dependency name, optionally followed by `@<version>`, and the following options:
+	/// - `--git <url>`, where the url may end with `#<branch>`, and `--branch <branch>`.
+	/// - `--path <path>` (or `path=<path>`). A spec starting with a path, such as `../my-crate`, is
+	///   also accepted: the dependency name is then the last component of the path.
+	/// - `--features <features>` (or `-F <features>`), a list separated by commas or spaces which
+	///   can be repeated. Space separated lists must be quoted, eg `--features "derive rc"`.
+	/// - `--no-default-features`, `--default-features` and `--optional`.
+	///
+	/// Options can also be written as `--option=value`. As with `cargo add`, a bare dependency name
+	/// refers to the latest version in the registry: since finding it needs a registry lookup, the
+	/// config requires any version (`*`) instead.
+	///
+	/// # Errors
+	///
+	/// - If the spec doesn't contain a dependency name, or contains more than one.
+	/// - If the spec contains an unknown option, or an option is missing its value.
+	/// - If the spec specifies more than one of a version, a git repository and a path.
+	/// - If a git dependency doesn't specify a branch, or a branch is given without a git
+	///   repository.
+	/// - If the version isn't a valid version requirement.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use rustilities::manifest::{ManifestDependencyConfig, ManifestDependencyOrigin};
+	///
+	/// let (name, config) =
+	///     ManifestDependencyConfig::parse_spec("serde@1.0 --features derive,rc --optional").unwrap();
+	/// assert_eq!(name, "serde");
+	/// assert_eq!(
+	///     config,
+	///     ManifestDependencyConfig::new(
+	///         ManifestDependencyOrigin::crates_io("1.0"),
+	///         true,
+	///         vec!["derive", "rc"],
+	///         true
+	///     )
+	/// );
+	///
+	/// let (name, config) = ManifestDependencyConfig::parse_spec(
+	///     "my-crate --git https://github.com/me/my-crate#main --no-default-features",
+	/// ).unwrap();
+	/// assert_eq!(name, "my-crate");
+	/// assert_eq!(
+	///     config.origin,
+	///     ManifestDependencyOrigin::git("https://github.com/me/my-crate", "main")
+	/// );
+	/// assert!(!config.default_features);
+	/// ```
+	pub fn parse_spec(spec: &'a str) -> Result<(&'a str, Self), Error> {
+		let mut name = None;
+		let mut version = None;
+		let mut git = None;
+		let mut branch = None;
+		let mut path = None;
+		let mut config = Self::default();
+
+		let mut tokens = spec_tokens(spec);
+		while let Some(token) = tokens.next() {
+			let (option, inline_value) = match token.split_once('=') {
+				Some((option, value)) if option.starts_with('-') || option == "path" =>
+					(option, Some(value)),
+				_ => (token, None),
+			};
+			let mut value = || {
+				inline_value.or_else(|| tokens.next()).map(unquote).ok_or_else(|| {
+					Error::Descriptive(format!("The {option} option expects a value"))
+				})
+			};
+
+			match option {
+				"--git" => git = Some(value()?),
+				"--branch" => branch = Some(value()?),
+				"--path" | "path" => path = Some(value()?),
+				"--features" | "-F" => config.features.extend(
+					value()?
+						.split(|c: char| c == ',' || c.is_whitespace())
+						.filter(|feature| !feature.is_empty()),
+				),
+				"--no-default-features" => config.default_features = false,
+				"--default-features" => config.default_features = true,
+				"--optional" => config.optional = true,
+				option if option.starts_with('-') =>
+					return Err(Error::Descriptive(format!(
+						"Unknown option {option} in dependency spec"
+					))),
+				_ if name.is_some() =>
+					return Err(Error::Descriptive(format!(
+						"The dependency spec {spec} specifies more than one crate"
+					))),
+				_ if token.starts_with('.') || token.starts_with('/') => {
+					path = Some(token);
+					name = Path::new(token).file_name().and_then(|name| name.to_str());
+				},
+				_ => match token.split_once('@') {
+					Some((dependency_name, dependency_version)) => {
+						name = Some(dependency_name);
+						version = Some(dependency_version);
+					},
+					None => name = Some(token),
+				},
+			}
+		}
+
+		let name = name.ok_or_else(|| {
+			Error::Descriptive(format!("The dependency spec {spec} doesn't specify a crate"))
+		})?;
+		if branch.is_some() && git.is_none() {
+			return Err(Error::Descriptive(
+				"The --branch option requires the --git option".to_owned(),
+			));
+		}
+		config.origin = match (version, git, path) {
+			(Some(version), None, None) => ManifestDependencyOrigin::crates_io(version),
+			(None, Some(git), None) => {
+				let (url, url_branch) = match git.split_once('#') {
+					Some((url, url_branch)) => (url, Some(url_branch)),
+					None => (git, None),
+				};
+				let branch = branch.or(url_branch).ok_or_else(|| {
+					Error::Descriptive(format!(
+						"The git dependency spec {spec} doesn't specify a branch"
+					))
+				})?;
+				ManifestDependencyOrigin::git(url, branch)
+			},
+			(None, None, Some(path)) => ManifestDependencyOrigin::local(Path::new(path)),
+			(None, None, None) => ManifestDependencyOrigin::crates_io("*"),
+			_ =>
+				return Err(Error::Descriptive(format!(
+					"The dependency spec {spec} specifies more than one origin"
+				))),
+		};
+		config.origin.validate()?;
+		Ok((name, config))
+	}
+}
+
+/// Splits a dependency spec into whitespace separated tokens, keeping together the whitespace
+/// separated words enclosed in quotes, eg `--features "a b"` or `--features='a b'`.
+fn spec_tokens(spec: &str) -> impl Iterator<Item = &str> {
+	let mut rest = spec;
+	std::iter::from_fn(move || {
+		rest = rest.trim_start();
+		if rest.is_empty() {
+			return None;
+		}
+		let mut quote = None;
+		let end = rest
+			.char_indices()
+			.find(|&(_, c)| match quote {
+				Some(open) => {
+					if c == open {
+						quote = None;
+					}
+					false
+				},
+				None if c == '"' || c == '\'' => {
+					quote = Some(c);
+					false
+				},
+				None => c.is_whitespace(),
+			})
+			.map_or(rest.len(), |(index, _)| index);
+		let (token, remaining) = rest.split_at(end);
+		rest = remaining;
+		Some(token)
+	})
+}
+
+/// Removes the quotes enclosing an option value, if any.
+fn unquote(value: &str) -> &str {
+	['"', '\'']
+		.into_iter()
+		.find_map(|quote| value.strip_prefix(quote)?.strip_suffix(quote))
+		.unwrap_or(value)
+}
+
+/// The default config is a dependency inherited from the workspace, using its default features,
+/// without extra features and not optional.
+impl Default for ManifestDependencyConfig<'_> {
+	fn default() -> Self {
+		Self {
+			origin: ManifestDependencyOrigin::Workspace,
+			default_features: true,
+			features: Vec::new(),
+			optional: false,
+		}
+	}
+}
+
+/// A builder for [`ManifestDependencyConfig`], created with [`ManifestDependencyConfig::builder`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestDependencyConfigBuilder<'a> {
+	config: ManifestDependencyConfig<'a>,
+}
+
+impl<'a> ManifestDependencyConfigBuilder<'a> {
+	/// Sets whether the dependency uses its default features.
+	pub fn default_features(mut self, default_features: bool) -> Self {
+		self.config.default_features = default_features;
+		self
+	}
+
+	/// Disables the default features of the dependency.
+	pub fn no_default_features(self) -> Self {
+		self.default_features(false)
+	}
+
+	/// Adds a feature to the dependency.
+	pub fn feature(mut self, feature: &'a str) -> Self {
+		self.config.features.push(feature);
+		self
+	}
+
+	/// Adds some features to the dependency.
+	pub fn features(mut self, features: &[&'a str]) -> Self {
+		self.config.add_features(features);
+		self
+	}
+
+	/// Makes the dependency optional.
+	pub fn optional(mut self) -> Self {
+		self.config.optional = true;
+		self
+	}
+
+	/// Builds the ManifestDependencyConfig.
+	pub fn build(self) -> ManifestDependencyConfig<'a> {
+		self.config
+	}
+}
+
+impl ManifestDependencyConfig<'_> {
+	/// The item declaring the dependency in a dependencies table, following the given style.
+	pub(super) fn to_item(&self, style: DependencyStyle) -> Item {
+		let inline_table = self.to_inline_table();
+		let as_table = match style {
+			DependencyStyle::Inline => false,
+			DependencyStyle::Table => true,
+			DependencyStyle::TableIfMoreKeysThan(max_keys) => inline_table.len() > max_keys,
+		};
+		if as_table {
+			let mut table = Table::new();
+			table.extend(inline_table);
+			Item::Table(table)
+		} else {
+			toml_edit::value(inline_table)
+		}
+	}
+
+	/// The inline table declaring the dependency in a manifest.
+	pub(super) fn to_inline_table(&self) -> InlineTable {
+		let mut dependency_declaration = InlineTable::new();
+		match &self.origin {
+			ManifestDependencyOrigin::Workspace => {
+				dependency_declaration.insert(
+					"workspace",
+					toml_edit::value(true)
+						.into_value()
+						.expect("true is bool, so value(true) is Value::Boolean;qed;"),
+				);
+			},
+			ManifestDependencyOrigin::Git { url, branch } => {
+				dependency_declaration.insert(
+					"git",
+					toml_edit::value(url.to_owned())
+						.into_value()
+						.expect("url is String, so value(url) is Value::String; qed;"),
+				);
+				dependency_declaration.insert(
+					"branch",
+					toml_edit::value(branch.to_owned())
+						.into_value()
+						.expect("branch is String, so value(branch) is Value::String; qed;"),
+				);
+			},
+			ManifestDependencyOrigin::CratesIO { version } => {
+				dependency_declaration.insert(
+					"version",
+					toml_edit::value(version.to_owned())
+						.into_value()
+						.expect("version is String, so value(version) is Value::String; qed;"),
+				);
+			},
+			ManifestDependencyOrigin::Local { relative_path } => {
+				dependency_declaration.insert(
+					"path",
+					toml_edit::value(relative_path.to_string_lossy().into_owned())
+						.into_value()
+						.expect(
+							"relative_path is String, so value(relative_path) is Value::String; qed;",
+						),
+				);
+			},
+		}
+
+		if !self.default_features {
+			dependency_declaration.insert(
+				"default-features",
+				toml_edit::value(false)
+					.into_value()
+					.expect("false is bool so value(false) is Value::Boolean; qed;"),
+			);
+		}
+
+		if !self.features.is_empty() {
+			let mut features = Array::new();
+			self.features.iter().for_each(|feature| features.push(feature.to_owned()));
+			dependency_declaration.insert(
+				"features",
+				toml_edit::value(features)
+					.into_value()
+					.expect("features is Array, so value(features) is Value::Array; qed;"),
+			);
+		}
+
+		if self.optional {
+			dependency_declaration.insert(
+				"optional",
+				toml_edit::value(true)
+					.into_value()
+					.expect("true is bool so value(true) is Value::Boolean; qed;"),
+			);
+		}
+
+		dependency_declaration
+	}
+}
+
+/// Renders the config as the inline table that would be written to a manifest, e.g.
+/// `{ version = "1.0", features = ["derive"] }`.
+impl fmt::Display for ManifestDependencyConfig<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.to_inline_table())
+	}
+}
+
+/// Parses a dependency declaration, as rendered by the [`Display`](fmt::Display) implementation:
+/// either an inline table or a version string. The body of a `[dependencies.<name>]` table, ie a
+/// line per key, is accepted as well. The config doesn't record how it was declared: the style of
+/// the written declaration is chosen by
+/// [`add_crate_to_dependencies_with_style`](super::add_crate_to_dependencies_with_style). As the
+/// config borrows its values from the declaration, this is the counterpart of `FromStr` for this
+/// type.
+///
+/// The supported keys are `version`, `git` (together with `branch`), `path`, `workspace`,
+/// `default-features` (or `default_features`), `features` and `optional`. Strings containing escape
+/// sequences aren't supported, as they cannot be borrowed from the declaration.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::{ManifestDependencyConfig, ManifestDependencyOrigin};
+///
+/// let config = ManifestDependencyConfig::new(
+///     ManifestDependencyOrigin::crates_io("1.0"),
+///     false,
+///     vec!["derive"],
+///     false,
+/// );
+/// let declaration = config.to_string();
+/// assert_eq!(declaration, r#"{ version = "1.0", default-features = false, features = ["derive"] }"#);
+/// assert_eq!(ManifestDependencyConfig::try_from(declaration.as_str()).unwrap(), config);
+///
+/// let table_body = "version = \"1.0\"\ndefault-features = false\nfeatures = [\"derive\"]\n";
+/// assert_eq!(ManifestDependencyConfig::try_from(table_body).unwrap(), config);
+/// ```
+impl<'a> TryFrom<&'a str> for ManifestDependencyConfig<'a> {
+	type Error = Error;
+
+	fn try_from(declaration: &'a str) -> Result<Self, Self::Error> {
+		const INLINE_PREFIX: &str = "dependency = ";
+		const TABLE_PREFIX: &str = "[dependency]\n";
+
+		// Parsing the declaration as part of a document keeps the spans of the values, which are
+		// needed to borrow the strings from the declaration.
+		let prefix = if declaration.trim_start().starts_with(['{', '"', '\'']) {
+			INLINE_PREFIX
+		} else {
+			TABLE_PREFIX
+		};
+		let doc = ImDocument::parse(format!("{prefix}{declaration}"))?;
+		let item = doc.get("dependency").expect("The document declares a dependency; qed;");
+		Self::from_item(item, declaration, prefix.len())
+	}
+}
+
+impl<'a> ManifestDependencyConfig<'a> {
+	/// Given the contents of a manifest, this function reads the declaration of a dependency from
+	/// the dependencies section the functions adding dependencies write to (`dependencies`, or
+	/// `workspace.dependencies` in a virtual manifest). Both the inline syntax and the
+	/// `[dependencies.<name>]` syntax are understood, and the config is returned together with the
+	/// [`DependencyStyle`] of the declaration. Returns `None` if the dependency isn't declared.
+	///
+	/// # Errors
+	///
+	/// - If the contents aren't valid TOML.
+	/// - If the declaration isn't supported, as described in the [`TryFrom`] implementation.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use rustilities::manifest::{DependencyStyle, ManifestDependencyConfig, ManifestDependencyOrigin};
+	///
+	/// let manifest = r#"[package]
+	/// name = "test"
+	///
+	/// [dependencies]
+	/// core = { path = "../core" }
+	///
+	/// [dependencies.serde]
+	/// version = "1.0"
+	/// features = ["derive"]
+	/// "#;
+	///
+	/// let (serde, style) =
+	///     ManifestDependencyConfig::read_from_manifest(manifest, "serde").unwrap().unwrap();
+	/// assert_eq!(serde.origin, ManifestDependencyOrigin::crates_io("1.0"));
+	/// assert_eq!(serde.features, vec!["derive"]);
+	/// assert_eq!(style, DependencyStyle::Table);
+	///
+	/// let (_, style) =
+	///     ManifestDependencyConfig::read_from_manifest(manifest, "core").unwrap().unwrap();
+	/// assert_eq!(style, DependencyStyle::Inline);
+	/// assert!(ManifestDependencyConfig::read_from_manifest(manifest, "syn").unwrap().is_none());
+	/// ```
+	pub fn read_from_manifest(
+		content: &'a str,
+		dependency_name: &str,
+	) -> Result<Option<(Self, DependencyStyle)>, Error> {
+		let doc = ImDocument::parse(content)?;
+		let dependencies = match doc.get("dependencies") {
+			Some(dependencies) => Some(dependencies),
+			None => doc.get("workspace").and_then(|workspace| workspace.get("dependencies")),
+		};
+		dependencies
+			.and_then(|dependencies| dependencies.get(dependency_name))
+			.map(|item| {
+				let style = match item {
+					Item::Table(table) if !table.is_dotted() => DependencyStyle::Table,
+					_ => DependencyStyle::Inline,
+				};
+				Ok((Self::from_item(item, content, 0)?, style))
+			})
+			.transpose()
+	}
+
+	/// Reads the config from the item declaring a dependency, borrowing the strings from `source`.
+	/// The spans of the values are shifted by `offset` bytes with respect to `source`.
+	fn from_item(item: &Item, source: &'a str, offset: usize) -> Result<Self, Error> {
+		let borrow_str = |key: &str, value: &Value| -> Result<&'a str, Error> {
+			let invalid = || {
+				Error::Descriptive(format!(
+					"The `{key}` key of the dependency declaration isn't a string without escape sequences"
+				))
+			};
+			let decoded = value.as_str().ok_or_else(invalid)?;
+			let span = value.span().ok_or_else(invalid)?;
+			source
+				.get(span.start - offset + 1..span.end - offset - 1)
+				.filter(|raw| *raw == decoded)
+				.ok_or_else(invalid)
+		};
+		let bool_value = |key: &str, value: &Value| {
+			value.as_bool().ok_or_else(|| {
+				Error::Descriptive(format!(
+					"The `{key}` key of the dependency declaration isn't a boolean"
+				))
+			})
+		};
+
+		let mut config = Self::default();
+		let table: &dyn TableLike = match item {
+			Item::Table(table) => table,
+			Item::Value(Value::InlineTable(table)) => table,
+			Item::Value(value) => {
+				config.origin = ManifestDependencyOrigin::crates_io(borrow_str("version", value)?);
+				return Ok(config);
+			},
+			_ =>
+				return Err(Error::Descriptive(
+					"The dependency declaration isn't a table or a version string".to_owned(),
+				)),
+		};
+
+		let (mut version, mut git, mut branch, mut path, mut workspace) =
+			(None, None, None, None, false);
+		for (key, item) in table.iter() {
+			let value = item.as_value().ok_or_else(|| {
+				Error::Descriptive(format!("Unsupported key {key} in dependency declaration"))
+			})?;
+			match key {
+				"version" => version = Some(borrow_str(key, value)?),
+				"git" => git = Some(borrow_str(key, value)?),
+				"branch" => branch = Some(borrow_str(key, value)?),
+				"path" => path = Some(borrow_str(key, value)?),
+				"workspace" => workspace = bool_value(key, value)?,
+				"default-features" | "default_features" =>
+					config.default_features = bool_value(key, value)?,
+				"optional" => config.optional = bool_value(key, value)?,
+				"features" =>
+					for feature in value.as_array().map(Array::iter).ok_or_else(|| {
+						Error::Descriptive(
+							"The `features` key of the dependency declaration isn't an array"
+								.to_owned(),
+						)
+					})? {
+						config.features.push(borrow_str(key, feature)?);
+					},
+				key =>
+					return Err(Error::Descriptive(format!(
+						"Unsupported key {key} in dependency declaration"
+					))),
+			}
+		}
+
+		config.origin = match (version, git, path, workspace) {
+			(Some(version), None, None, false) => ManifestDependencyOrigin::crates_io(version),
+			(None, Some(url), None, false) => ManifestDependencyOrigin::git(
+				url,
+				branch.ok_or_else(|| {
+					Error::Descriptive(
+						"The git dependency declaration doesn't specify a branch".to_owned(),
+					)
+				})?,
+			),
+			(None, None, Some(path), false) => ManifestDependencyOrigin::local(Path::new(path)),
+			(None, None, None, true) => ManifestDependencyOrigin::Workspace,
+			(None, None, None, false) =>
+				return Err(Error::Descriptive(
+					"The dependency declaration doesn't specify an origin".to_owned(),
+				)),
+			_ =>
+				return Err(Error::Descriptive(
+					"The dependency declaration specifies more than one origin".to_owned(),
+				)),
+		};
+		Ok(config)
+	}
 }
 
 /// Different origins available for a dependency in a Rust manifest.
@@ -50,6 +638,81 @@ impl<'a> ManifestDependencyOrigin<'a> {
 		Self::CratesIO { version }
 	}
 
+	/// Creates a dependency origin from a caret version requirement in
+	/// [crates.io](https://crates.io), such as `1.2` or `^1.2`.
+	///
+	/// # Errors
+	///
+	/// - If the version isn't a valid caret version requirement.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use rustilities::manifest::ManifestDependencyOrigin;
+	///
+	/// assert!(ManifestDependencyOrigin::crates_io_caret("1.2").is_ok());
+	/// assert!(ManifestDependencyOrigin::crates_io_caret("^1.2.3").is_ok());
+	/// assert!(ManifestDependencyOrigin::crates_io_caret(">=1.2").is_err());
+	/// assert!(ManifestDependencyOrigin::crates_io_caret("latest").is_err());
+	/// ```
+	pub fn crates_io_caret(version: &'a str) -> Result<Self, Error> {
+		match &parse_version_req(version)?.comparators[..] {
+			[comparator] if comparator.op == semver::Op::Caret => Ok(Self::CratesIO { version }),
+			_ => Err(Error::Descriptive(format!("{version} isn't a caret version requirement"))),
+		}
+	}
+
+	/// Creates a dependency origin from an exact version requirement in
+	/// [crates.io](https://crates.io), such as `=1.2.3`.
+	///
+	/// # Errors
+	///
+	/// - If the version isn't a valid exact version requirement including the major, minor and
+	///   patch versions.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use rustilities::manifest::ManifestDependencyOrigin;
+	///
+	/// assert!(ManifestDependencyOrigin::crates_io_exact("=1.2.3").is_ok());
+	/// assert!(ManifestDependencyOrigin::crates_io_exact("=1.2").is_err());
+	/// assert!(ManifestDependencyOrigin::crates_io_exact("1.2.3").is_err());
+	/// ```
+	pub fn crates_io_exact(version: &'a str) -> Result<Self, Error> {
+		match &parse_version_req(version)?.comparators[..] {
+			[comparator]
+				if comparator.op == semver::Op::Exact &&
+					comparator.minor.is_some() &&
+					comparator.patch.is_some() =>
+				Ok(Self::CratesIO { version }),
+			_ => Err(Error::Descriptive(format!("{version} isn't an exact version requirement"))),
+		}
+	}
+
+	/// Checks that the origin can be written to a manifest. Currently, this means that the version
+	/// of a [crates.io](https://crates.io) origin must be a valid version requirement. The functions
+	/// adding dependencies to manifests perform this check before writing anything.
+	///
+	/// # Errors
+	///
+	/// - If the version of a crates.io origin isn't a valid version requirement.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use rustilities::manifest::ManifestDependencyOrigin;
+	///
+	/// assert!(ManifestDependencyOrigin::crates_io(">=1.2, <1.5").validate().is_ok());
+	/// assert!(ManifestDependencyOrigin::crates_io("one point two").validate().is_err());
+	/// ```
+	pub fn validate(&self) -> Result<(), Error> {
+		if let Self::CratesIO { version } = self {
+			parse_version_req(version)?;
+		}
+		Ok(())
+	}
+
 	/// Creates a dependency origin from a specific branch in a git repository.
 	pub fn git(url: &'a str, branch: &'a str) -> Self {
 		Self::Git { url, branch }
@@ -65,3 +728,24 @@ impl<'a> ManifestDependencyOrigin<'a> {
 		Self::Workspace
 	}
 }
+
+fn parse_version_req(version: &str) -> Result<semver::VersionReq, Error> {
+	semver::VersionReq::parse(version)
+		.map_err(|err| Error::Descriptive(format!("Invalid version requirement {version}: {err}")))
+}
+
+/// The different kinds of dependencies a Rust manifest can declare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "snake_case")
+)]
+pub enum DependencyKind {
+	/// A dependency declared under `dependencies`.
+	Normal,
+	/// A dependency declared under `dev-dependencies`.
+	Dev,
+	/// A dependency declared under `build-dependencies`.
+	Build,
+}
@@ -63,3 +63,415 @@ fn manifest_dependency_origin_local_works() {
 fn manifest_dependency_origin_workspace_works() {
 	assert_eq!(ManifestDependencyOrigin::workspace(), ManifestDependencyOrigin::Workspace);
 }
+
+#[test]
+fn manifest_dependency_config_parse_spec_works() {
+	assert_eq!(
+		ManifestDependencyConfig::parse_spec("serde@1.0.219").expect("This should be Ok; qed;"),
+		(
+			"serde",
+			ManifestDependencyConfig::new(
+				ManifestDependencyOrigin::crates_io("1.0.219"),
+				true,
+				vec![],
+				false
+			)
+		)
+	);
+	assert_eq!(
+		ManifestDependencyConfig::parse_spec(
+			"dep --git=https://some_url.com --branch main -F a,b --features c --no-default-features"
+		)
+		.expect("This should be Ok; qed;"),
+		(
+			"dep",
+			ManifestDependencyConfig::new(
+				ManifestDependencyOrigin::git("https://some_url.com", "main"),
+				false,
+				vec!["a", "b", "c"],
+				false
+			)
+		)
+	);
+	assert_eq!(
+		ManifestDependencyConfig::parse_spec("dep path=../dep --optional")
+			.expect("This should be Ok; qed;"),
+		(
+			"dep",
+			ManifestDependencyConfig::new(
+				ManifestDependencyOrigin::local("../dep".as_ref()),
+				true,
+				vec![],
+				true
+			)
+		)
+	);
+	assert_eq!(
+		ManifestDependencyConfig::parse_spec("../crates/my-dep --features=std")
+			.expect("This should be Ok; qed;"),
+		(
+			"my-dep",
+			ManifestDependencyConfig::new(
+				ManifestDependencyOrigin::local("../crates/my-dep".as_ref()),
+				true,
+				vec!["std"],
+				false
+			)
+		)
+	);
+}
+
+#[test]
+fn manifest_dependency_config_parse_spec_requires_any_version_for_bare_names() {
+	assert_eq!(
+		ManifestDependencyConfig::parse_spec("serde --features derive")
+			.expect("This should be Ok; qed;"),
+		(
+			"serde",
+			ManifestDependencyConfig::new(
+				ManifestDependencyOrigin::crates_io("*"),
+				true,
+				vec!["derive"],
+				false
+			)
+		)
+	);
+}
+
+#[test]
+fn manifest_dependency_config_parse_spec_splits_features_on_commas_and_spaces() {
+	assert_eq!(
+		ManifestDependencyConfig::parse_spec(
+			"serde@1 --features \"derive rc\" -F='std, alloc' --features=unstable --optional"
+		)
+		.expect("This should be Ok; qed;"),
+		(
+			"serde",
+			ManifestDependencyConfig::new(
+				ManifestDependencyOrigin::crates_io("1"),
+				true,
+				vec!["derive", "rc", "std", "alloc", "unstable"],
+				true
+			)
+		)
+	);
+}
+
+#[test]
+fn manifest_dependency_config_parse_spec_fails_if_spec_is_invalid() {
+	let error_of = |spec| match ManifestDependencyConfig::parse_spec(spec) {
+		Err(Error::Descriptive(msg)) => msg,
+		other => panic!("Unexpected result {other:?}"),
+	};
+
+	assert_eq!(error_of(""), "The dependency spec  doesn't specify a crate");
+	assert_eq!(error_of("a@1 b@2"), "The dependency spec a@1 b@2 specifies more than one crate");
+	assert_eq!(error_of("a@1 --locked"), "Unknown option --locked in dependency spec");
+	assert_eq!(error_of("a@1 --features"), "The --features option expects a value");
+	assert_eq!(
+		error_of("a@1 --path ../a"),
+		"The dependency spec a@1 --path ../a specifies more than one origin"
+	);
+	assert_eq!(
+		error_of("a --git https://some_url.com"),
+		"The git dependency spec a --git https://some_url.com doesn't specify a branch"
+	);
+	assert_eq!(error_of("a@1 --branch main"), "The --branch option requires the --git option");
+}
+
+#[test]
+fn manifest_dependency_config_display_and_try_from_roundtrip() {
+	let configs = [
+		ManifestDependencyConfig::new(
+			ManifestDependencyOrigin::crates_io("1.0"),
+			true,
+			vec![],
+			false,
+		),
+		ManifestDependencyConfig::new(
+			ManifestDependencyOrigin::git("https://some_url.com", "main"),
+			false,
+			vec!["a", "b"],
+			true,
+		),
+		ManifestDependencyConfig::new(
+			ManifestDependencyOrigin::local("../dep".as_ref()),
+			true,
+			vec!["a"],
+			false,
+		),
+		ManifestDependencyConfig::new(ManifestDependencyOrigin::workspace(), true, vec![], true),
+	];
+	let declarations = [
+		r#"{ version = "1.0" }"#,
+		r#"{ git = "https://some_url.com", branch = "main", default-features = false, features = ["a", "b"], optional = true }"#,
+		r#"{ path = "../dep", features = ["a"] }"#,
+		r#"{ workspace = true, optional = true }"#,
+	];
+
+	configs.iter().zip(declarations).for_each(|(config, declaration)| {
+		assert_eq!(config.to_string(), declaration);
+		assert_eq!(
+			&ManifestDependencyConfig::try_from(declaration).expect("This should be Ok; qed;"),
+			config
+		);
+	});
+}
+
+#[test]
+fn manifest_dependency_config_try_from_accepts_version_strings_and_literal_strings() {
+	assert_eq!(
+		ManifestDependencyConfig::try_from(r#""1.0.0""#).expect("This should be Ok; qed;"),
+		ManifestDependencyConfig::new(
+			ManifestDependencyOrigin::crates_io("1.0.0"),
+			true,
+			vec![],
+			false
+		)
+	);
+	assert_eq!(
+		ManifestDependencyConfig::try_from("{ path = '../dep', default_features = false }")
+			.expect("This should be Ok; qed;"),
+		ManifestDependencyConfig::new(
+			ManifestDependencyOrigin::local("../dep".as_ref()),
+			false,
+			vec![],
+			false
+		)
+	);
+}
+
+#[test]
+fn manifest_dependency_config_try_from_fails_if_declaration_is_invalid() {
+	let error_of = |declaration| match ManifestDependencyConfig::try_from(declaration) {
+		Err(Error::Descriptive(msg)) => msg,
+		other => panic!("Unexpected result {other:?}"),
+	};
+
+	assert_eq!(
+		error_of(r#"{ version = "1\u002E0" }"#),
+		"The `version` key of the dependency declaration isn't a string without escape sequences"
+	);
+	assert_eq!(
+		error_of("{ optional = 1, version = \"1.0\" }"),
+		"The `optional` key of the dependency declaration isn't a boolean"
+	);
+	assert_eq!(
+		error_of("{ features = \"a\", version = \"1.0\" }"),
+		"The `features` key of the dependency declaration isn't an array"
+	);
+	assert_eq!(
+		error_of("{ package = \"a\" }"),
+		"Unsupported key package in dependency declaration"
+	);
+	assert_eq!(
+		error_of("{ git = \"https://some_url.com\" }"),
+		"The git dependency declaration doesn't specify a branch"
+	);
+	assert_eq!(
+		error_of("{ optional = true }"),
+		"The dependency declaration doesn't specify an origin"
+	);
+	assert_eq!(
+		error_of("{ version = \"1.0\", workspace = true }"),
+		"The dependency declaration specifies more than one origin"
+	);
+	assert!(matches!(ManifestDependencyConfig::try_from("{ version = "), Err(Error::TomlEdit(_))));
+}
+
+#[test]
+fn manifest_dependency_config_default_works() {
+	assert_eq!(
+		ManifestDependencyConfig::default(),
+		ManifestDependencyConfig::new(ManifestDependencyOrigin::workspace(), true, vec![], false)
+	);
+}
+
+#[test]
+fn manifest_dependency_config_builder_works() {
+	let origin = ManifestDependencyOrigin::git("https://some_url.com", "main");
+
+	assert_eq!(
+		ManifestDependencyConfig::builder(origin.clone()).build(),
+		ManifestDependencyConfig::new(origin.clone(), true, vec![], false)
+	);
+	assert_eq!(
+		ManifestDependencyConfig::builder(origin.clone())
+			.no_default_features()
+			.feature("feature1")
+			.features(&["feature2", "feature3"])
+			.optional()
+			.build(),
+		ManifestDependencyConfig::new(
+			origin.clone(),
+			false,
+			vec!["feature1", "feature2", "feature3"],
+			true
+		)
+	);
+	assert_eq!(
+		ManifestDependencyConfig::builder(origin.clone())
+			.no_default_features()
+			.default_features(true)
+			.build(),
+		ManifestDependencyConfig::new(origin, true, vec![], false)
+	);
+}
+
+#[test]
+fn manifest_dependency_origin_crates_io_caret_works() {
+	["1", "1.2", "1.2.3", "^1.2", "^0.1.0-alpha.1"].into_iter().for_each(|version| {
+		assert_eq!(
+			ManifestDependencyOrigin::crates_io_caret(version).expect("This should be Ok; qed;"),
+			ManifestDependencyOrigin::CratesIO { version }
+		)
+	});
+	assert!(matches!(
+		ManifestDependencyOrigin::crates_io_caret("~1.2"),
+		Err(Error::Descriptive(msg)) if msg == "~1.2 isn't a caret version requirement"
+	));
+	assert!(matches!(
+		ManifestDependencyOrigin::crates_io_caret("^1.2, <1.5"),
+		Err(Error::Descriptive(msg)) if msg == "^1.2, <1.5 isn't a caret version requirement"
+	));
+	assert!(matches!(
+		ManifestDependencyOrigin::crates_io_caret("1.x.y"),
+		Err(Error::Descriptive(msg)) if msg.starts_with("Invalid version requirement 1.x.y: ")
+	));
+}
+
+#[test]
+fn manifest_dependency_origin_crates_io_exact_works() {
+	assert_eq!(
+		ManifestDependencyOrigin::crates_io_exact("=1.2.3").expect("This should be Ok; qed;"),
+		ManifestDependencyOrigin::CratesIO { version: "=1.2.3" }
+	);
+	["=1.2", "1.2.3", "=1.2.3, <2"].into_iter().for_each(|version| {
+		assert!(matches!(
+			ManifestDependencyOrigin::crates_io_exact(version),
+			Err(Error::Descriptive(msg)) if msg == format!("{version} isn't an exact version requirement")
+		))
+	});
+}
+
+#[test]
+fn manifest_dependency_origin_validate_works() {
+	assert!(ManifestDependencyOrigin::crates_io("*").validate().is_ok());
+	assert!(ManifestDependencyOrigin::git("not a url", "main").validate().is_ok());
+	assert!(ManifestDependencyOrigin::workspace().validate().is_ok());
+	assert!(matches!(
+		ManifestDependencyOrigin::crates_io("").validate(),
+		Err(Error::Descriptive(msg)) if msg.starts_with("Invalid version requirement : ")
+	));
+}
+
+#[test]
+fn manifest_dependency_config_parse_spec_fails_if_version_is_invalid() {
+	assert!(matches!(
+		ManifestDependencyConfig::parse_spec("serde@latest"),
+		Err(Error::Descriptive(msg)) if msg.starts_with("Invalid version requirement latest: ")
+	));
+}
+
+#[test]
+fn manifest_dependency_config_to_item_follows_the_style() {
+	let config = ManifestDependencyConfig::builder(ManifestDependencyOrigin::crates_io("1.0"))
+		.feature("derive")
+		.optional()
+		.build();
+	let render = |style| {
+		let mut table = toml_edit::Table::new();
+		table.insert("serde", config.to_item(style));
+		let mut doc = toml_edit::DocumentMut::new();
+		doc.insert("dependencies", toml_edit::Item::Table(table));
+		doc.to_string()
+	};
+
+	let inline =
+		"[dependencies]\nserde = { version = \"1.0\", features = [\"derive\"], optional = true }\n";
+	let table = "[dependencies]\n\n[dependencies.serde]\nversion = \"1.0\"\nfeatures = [\"derive\"]\noptional = true\n";
+	assert_eq!(render(DependencyStyle::Inline), inline);
+	assert_eq!(render(DependencyStyle::Table), table);
+	assert_eq!(render(DependencyStyle::TableIfMoreKeysThan(3)), inline);
+	assert_eq!(render(DependencyStyle::TableIfMoreKeysThan(2)), table);
+}
+
+#[test]
+fn manifest_dependency_config_try_from_accepts_table_bodies() {
+	assert_eq!(
+		ManifestDependencyConfig::try_from(
+			"git = \"https://some_url.com\"\nbranch = \"main\"\n\n# Needed by the CLI\noptional = true\n"
+		)
+		.expect("This should be Ok; qed;"),
+		ManifestDependencyConfig::builder(ManifestDependencyOrigin::git(
+			"https://some_url.com",
+			"main"
+		))
+		.optional()
+		.build()
+	);
+	assert!(matches!(
+		ManifestDependencyConfig::try_from("version = \"1.0\"\nregistry.name = \"x\"\n"),
+		Err(Error::Descriptive(msg)) if msg == "Unsupported key registry in dependency declaration"
+	));
+}
+
+#[test]
+fn manifest_dependency_config_read_from_manifest_understands_every_syntax() {
+	let manifest = r#"[package]
+name = "test"
+
+[dependencies]
+inline = { version = "1.0", default-features = false }
+string = "0.2"
+dotted.workspace = true
+dotted.features = ["a"]
+
+[dependencies.table]
+path = "../table"
+"#;
+	let read = |name, style| {
+		let (config, read_style) = ManifestDependencyConfig::read_from_manifest(manifest, name)
+			.expect("This should be Ok; qed;")
+			.expect("The dependency is declared; qed;");
+		assert_eq!(read_style, style);
+		config
+	};
+
+	assert_eq!(
+		read("inline", DependencyStyle::Inline),
+		ManifestDependencyConfig::builder(ManifestDependencyOrigin::crates_io("1.0"))
+			.no_default_features()
+			.build()
+	);
+	assert_eq!(
+		read("string", DependencyStyle::Inline),
+		ManifestDependencyConfig::builder(ManifestDependencyOrigin::crates_io("0.2")).build()
+	);
+	assert_eq!(
+		read("dotted", DependencyStyle::Inline),
+		ManifestDependencyConfig::builder(ManifestDependencyOrigin::workspace())
+			.feature("a")
+			.build()
+	);
+	assert_eq!(
+		read("table", DependencyStyle::Table),
+		ManifestDependencyConfig::builder(ManifestDependencyOrigin::local("../table".as_ref()))
+			.build()
+	);
+	assert_eq!(
+		ManifestDependencyConfig::read_from_manifest(manifest, "missing")
+			.expect("This should be Ok; qed;"),
+		None
+	);
+
+	let workspace = "[workspace]\n\n[workspace.dependencies.serde]\nversion = \"1.0\"\n";
+	assert_eq!(
+		ManifestDependencyConfig::read_from_manifest(workspace, "serde")
+			.expect("This should be Ok; qed;"),
+		Some((
+			ManifestDependencyConfig::builder(ManifestDependencyOrigin::crates_io("1.0")).build(),
+			DependencyStyle::Table
+		))
+	);
+}
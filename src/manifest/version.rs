@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities related to the version declared in a manifest.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{BumpKind, Error, macros::debug};
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, Value};
+
+/// Given a manifest file path and a kind of bump, this function bumps the version declared in the
+/// manifest, returning the new version. The version of the `package` section is bumped, or the one
+/// of the `workspace.package` section if the manifest doesn't declare a package.
+///
+/// The version is bumped following semantic versioning: the bumped component is incremented and
+/// the smaller ones are reset to 0, while pre-release and build metadata are removed.
+///
+/// # Errors
+///
+/// - If the path cannot be read.
+/// - If the path doesn't correspond to a valid Rust manifest.
+/// - If the manifest doesn't declare a version, or it's inherited from the workspace.
+/// - If the version isn't a valid semver version.
+/// - If the path cannot be overwritten.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::BumpKind;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(&manifest_path, "[package]\nname = \"test\"\nversion = \"1.2.3-alpha.1\"\n")
+///     .unwrap();
+///
+/// assert_eq!(
+///     rustilities::manifest::bump_version(&manifest_path, BumpKind::Minor).unwrap(),
+///     "1.3.0"
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(&manifest_path).unwrap(),
+///     "[package]\nname = \"test\"\nversion = \"1.3.0\"\n"
+/// );
+/// ```
+pub fn bump_version<P: AsRef<Path>>(manifest_path: P, kind: BumpKind) -> Result<String, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_bump_version(manifest_path: &Path, kind: BumpKind) -> Result<String, Error> {
+		let mut doc = std::fs::read_to_string(manifest_path)?.parse::<DocumentMut>()?;
+		let package = if doc.contains_key("package") {
+			doc.get_mut("package")
+		} else {
+			doc.get_mut("workspace")
+				.and_then(Item::as_table_like_mut)
+				.and_then(|workspace| workspace.get_mut("package"))
+		};
+		let Some(version_item) = package
+			.and_then(Item::as_table_like_mut)
+			.and_then(|package| package.get_mut("version"))
+		else {
+			return Err(Error::Descriptive("The manifest doesn't declare a version".to_owned()));
+		};
+		let Some(value) = version_item.as_value_mut() else {
+			return Err(Error::Descriptive(
+				"The package version is inherited from the workspace".to_owned(),
+			));
+		};
+		let Some(version) = value.as_str() else {
+			return Err(Error::Descriptive(
+				"The package version is inherited from the workspace".to_owned(),
+			));
+		};
+
+		let mut version = semver::Version::parse(version)
+			.map_err(|err| Error::Descriptive(format!("Invalid version {version}: {err}")))?;
+		match kind {
+			BumpKind::Major => {
+				version.major += 1;
+				version.minor = 0;
+				version.patch = 0;
+			},
+			BumpKind::Minor => {
+				version.minor += 1;
+				version.patch = 0;
+			},
+			BumpKind::Patch => version.patch += 1,
+		}
+		version.pre = semver::Prerelease::EMPTY;
+		version.build = semver::BuildMetadata::EMPTY;
+
+		let version = version.to_string();
+		let decor = value.decor().clone();
+		*value = Value::from(&version);
+		*value.decor_mut() = decor;
+
+		debug!(path = %manifest_path.display(), "Writing manifest");
+		std::fs::write(manifest_path, doc.to_string())?;
+		Ok(version)
+	}
+	do_bump_version(manifest_path.as_ref(), kind)
+}
@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+fn manifest(content: &str) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::write(tempdir.path().join("Cargo.toml"), content)
+		.expect("The manifest should be writable; qed;");
+	tempdir
+}
+
+#[test]
+fn bump_version_bumps_every_component() {
+	let tempdir = manifest("[package]\nname = \"test\"\nversion = \"1.2.3\" # The version\n");
+	let manifest_path = tempdir.path().join("Cargo.toml");
+
+	assert_eq!(bump_version(&manifest_path, BumpKind::Patch).ok(), Some("1.2.4".to_owned()));
+	assert_eq!(bump_version(&manifest_path, BumpKind::Minor).ok(), Some("1.3.0".to_owned()));
+	assert_eq!(bump_version(&manifest_path, BumpKind::Major).ok(), Some("2.0.0".to_owned()));
+	assert_eq!(
+		std::fs::read_to_string(&manifest_path).expect("This should be Ok; qed;"),
+		"[package]\nname = \"test\"\nversion = \"2.0.0\" # The version\n"
+	);
+}
+
+#[test]
+fn bump_version_bumps_workspace_package_version() {
+	let tempdir = manifest("[workspace.package]\nversion = \"0.1.0+build\"\n");
+
+	assert_eq!(
+		bump_version(tempdir.path().join("Cargo.toml"), BumpKind::Patch).ok(),
+		Some("0.1.1".to_owned())
+	);
+}
+
+#[test]
+fn bump_version_fails_if_version_isnt_declared() {
+	let tempdir =
+		manifest("[package]\nname = \"test\"\n\n[workspace.package]\nversion = \"1.0.0\"\n");
+	assert!(matches!(
+		bump_version(tempdir.path().join("Cargo.toml"), BumpKind::Patch),
+		Err(Error::Descriptive(msg)) if msg == "The manifest doesn't declare a version"
+	));
+}
+
+#[test]
+fn bump_version_fails_if_version_is_inherited() {
+	let tempdir = manifest("[package]\nname = \"test\"\nversion.workspace = true\n");
+	assert!(matches!(
+		bump_version(tempdir.path().join("Cargo.toml"), BumpKind::Patch),
+		Err(Error::Descriptive(msg)) if msg == "The package version is inherited from the workspace"
+	));
+}
+
+#[test]
+fn bump_version_fails_if_version_isnt_valid() {
+	let tempdir = manifest("[package]\nname = \"test\"\nversion = \"1.0\"\n");
+	assert!(matches!(
+		bump_version(tempdir.path().join("Cargo.toml"), BumpKind::Patch),
+		Err(Error::Descriptive(msg)) if msg.starts_with("Invalid version 1.0: ")
+	));
+}
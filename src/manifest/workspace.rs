@@ -0,0 +1,330 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities related to the `[workspace]` keys controlling which crates are part of a
+// workspace.
+
+#[cfg(test)]
+mod tests;
+
+use super::{find_workspace_manifest, find_workspace_members};
+use crate::{Error, macros::debug};
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, Item, Table};
+
+/// Given a workspace manifest file path and some crate paths, this function sets the
+/// `default-members` section of the workspace to those crates, replacing the previous value. The
+/// crate paths are written relative to the workspace root, so the root package is written as `.`.
+///
+/// # Errors
+///
+/// - If the workspace path cannot be read.
+/// - If the workspace path doesn't correspond to a workspace manifest.
+/// - If some crate path isn't prefixed by the workspace path.
+/// - If some crate isn't a workspace member (see [`super::find_workspace_members`]).
+/// - If the path cannot be overwritten.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let workspace_manifest_path = tempdir.path().join("Cargo.toml");
+/// for member in ["a", "b"] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{member}\""),
+///     ).unwrap();
+/// }
+/// std::fs::write(&workspace_manifest_path, "[workspace]\nmembers = [\"*\"]\n").unwrap();
+///
+/// rustilities::manifest::set_default_members(
+///     &workspace_manifest_path,
+///     &[tempdir.path().join("b")],
+/// ).unwrap();
+/// assert_eq!(
+///     std::fs::read_to_string(&workspace_manifest_path).unwrap(),
+///     "[workspace]\nmembers = [\"*\"]\ndefault-members = [\"b\"]\n"
+/// );
+///
+/// assert!(rustilities::manifest::set_default_members(
+///     &workspace_manifest_path,
+///     &[tempdir.path().join("c")],
+/// ).is_err());
+/// ```
+pub fn set_default_members<P: AsRef<Path>, Q: AsRef<Path>>(
+	workspace_toml: P,
+	crate_paths: &[Q],
+) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_set_default_members(workspace_toml: &Path, crate_paths: &[&Path]) -> Result<(), Error> {
+		let mut doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+		let workspace_dir = workspace_toml.parent().expect("A file always lives inside a dir; qed");
+		let entries = crate_paths
+			.iter()
+			.map(|crate_path| relative_entry(workspace_dir, crate_path))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let workspace_table = workspace_table(&mut doc)?;
+		let member_dirs = member_dirs(workspace_toml)?;
+		if let Some(entry) =
+			entries.iter().find(|entry| !member_dirs.contains(&workspace_dir.join(entry)))
+		{
+			return Err(Error::Descriptive(format!(
+				"The default member {entry} isn't a workspace member"
+			)));
+		}
+		workspace_table["default-members"] = toml_edit::value(Array::from_iter(entries));
+
+		debug!(path = %workspace_toml.display(), "Writing manifest");
+		std::fs::write(workspace_toml, doc.to_string())?;
+		Ok(())
+	}
+	do_set_default_members(
+		workspace_toml.as_ref(),
+		&crate_paths.iter().map(AsRef::as_ref).collect::<Vec<_>>(),
+	)
+}
+
+/// Given a workspace manifest file path, this function checks that every path listed in the
+/// `default-members` section of the workspace is a workspace member, as cargo requires. A missing
+/// `default-members` section is valid.
+///
+/// # Errors
+///
+/// - If the workspace path cannot be read.
+/// - If the workspace path doesn't correspond to a workspace manifest.
+/// - If the `default-members` section isn't an array of strings.
+/// - If some default member isn't a workspace member (see [`super::find_workspace_members`]).
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let workspace_manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::create_dir_all(tempdir.path().join("a")).unwrap();
+/// std::fs::write(tempdir.path().join("a").join("Cargo.toml"), "[package]\nname = \"a\"").unwrap();
+/// std::fs::write(
+///     &workspace_manifest_path,
+///     "[workspace]\nmembers = [\"a\"]\ndefault-members = [\"a\"]\n",
+/// ).unwrap();
+/// assert!(rustilities::manifest::validate_default_members(&workspace_manifest_path).is_ok());
+///
+/// std::fs::write(
+///     &workspace_manifest_path,
+///     "[workspace]\nmembers = [\"a\"]\ndefault-members = [\"b\"]\n",
+/// ).unwrap();
+/// assert!(rustilities::manifest::validate_default_members(&workspace_manifest_path).is_err());
+/// ```
+pub fn validate_default_members<P: AsRef<Path>>(workspace_toml: P) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_validate_default_members(workspace_toml: &Path) -> Result<(), Error> {
+		let mut doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+		let workspace_dir = workspace_toml.parent().expect("A file always lives inside a dir; qed");
+		let workspace_table = workspace_table(&mut doc)?;
+		let Some(default_members) = workspace_table.get("default-members") else {
+			return Ok(());
+		};
+		let entries = default_members
+			.as_array()
+			.and_then(|array| array.iter().map(|entry| entry.as_str()).collect::<Option<Vec<_>>>())
+			.ok_or_else(|| {
+				Error::Descriptive(
+					"The `default-members` field isn't an array of strings".to_owned(),
+				)
+			})?;
+
+		let member_dirs = member_dirs(workspace_toml)?;
+		match entries
+			.into_iter()
+			.find(|entry| !member_dirs.contains(&workspace_dir.join(entry)))
+		{
+			Some(entry) => Err(Error::Descriptive(format!(
+				"The default member {entry} isn't a workspace member"
+			))),
+			None => Ok(()),
+		}
+	}
+	do_validate_default_members(workspace_toml.as_ref())
+}
+
+/// Given a workspace manifest file path and a path inside the workspace, this function adds the
+/// path to the `exclude` section of the workspace, so crates under it aren't considered members
+/// even if they match a `members` glob. The path is written relative to the workspace root, and
+/// nothing is done if it's already excluded.
+///
+/// # Errors
+///
+/// - If the workspace path cannot be read.
+/// - If the workspace path doesn't correspond to a workspace manifest.
+/// - If the path isn't prefixed by the workspace path.
+/// - If the `exclude` section isn't an array.
+/// - If the workspace path cannot be overwritten.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let workspace_manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(&workspace_manifest_path, "[workspace]\nmembers = [\"crates/*\"]\n").unwrap();
+///
+/// rustilities::manifest::add_excluded_path(
+///     &workspace_manifest_path,
+///     tempdir.path().join("crates").join("experimental"),
+/// ).unwrap();
+/// assert_eq!(
+///     std::fs::read_to_string(&workspace_manifest_path).unwrap(),
+///     "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/experimental\"]\n"
+/// );
+/// ```
+pub fn add_excluded_path<P: AsRef<Path>, Q: AsRef<Path>>(
+	workspace_toml: P,
+	path: Q,
+) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_add_excluded_path(workspace_toml: &Path, path: &Path) -> Result<(), Error> {
+		let mut doc = std::fs::read_to_string(workspace_toml)?.parse::<DocumentMut>()?;
+		let workspace_dir = workspace_toml.parent().expect("A file always lives inside a dir; qed");
+		let entry = relative_entry(workspace_dir, path)?;
+
+		let exclude = workspace_table(&mut doc)?
+			.entry("exclude")
+			.or_insert_with(|| toml_edit::value(Array::new()))
+			.as_array_mut()
+			.ok_or_else(|| Error::Descriptive("The `exclude` field isn't an array".to_owned()))?;
+		if exclude.iter().any(|excluded| excluded.as_str() == Some(entry.as_str())) {
+			return Ok(());
+		}
+		exclude.push(entry);
+
+		debug!(path = %workspace_toml.display(), "Writing manifest");
+		std::fs::write(workspace_toml, doc.to_string())?;
+		Ok(())
+	}
+	do_add_excluded_path(workspace_toml.as_ref(), path.as_ref())
+}
+
+/// The relation between a crate and the workspace enclosing it. See [`membership_status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "snake_case")
+)]
+pub enum MembershipStatus {
+	/// The crate is a workspace member.
+	Member,
+	/// The crate is left out of the workspace by its `exclude` section.
+	Excluded,
+	/// The crate isn't listed in the `members` section of the workspace, nor matched by any of
+	/// its globs. Cargo refuses to build such a crate unless it's added to `members` or `exclude`.
+	NotAMember,
+}
+
+/// Given a crate manifest file path, this function checks if the crate is a member of the
+/// workspace enclosing it (found by [`super::find_workspace_manifest`]), catching the classic
+/// mistake of creating a crate inside a workspace without adding it to `members`. A crate that is
+/// also the workspace root is always a member.
+///
+/// Returns `None` if the crate isn't enclosed by a workspace.
+///
+/// # Errors
+///
+/// - If the workspace members cannot be resolved (see [`super::find_workspace_members`]).
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::manifest::MembershipStatus;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// for member in ["a", "b", "c"] {
+///     std::fs::create_dir_all(tempdir.path().join(member)).unwrap();
+///     std::fs::write(
+///         tempdir.path().join(member).join("Cargo.toml"),
+///         format!("[package]\nname = \"{member}\""),
+///     ).unwrap();
+/// }
+/// std::fs::write(
+///     tempdir.path().join("Cargo.toml"),
+///     "[workspace]\nmembers = [\"a\"]\nexclude = [\"b\"]\n",
+/// ).unwrap();
+///
+/// let status = |member: &str| {
+///     rustilities::manifest::membership_status(tempdir.path().join(member).join("Cargo.toml"))
+///         .unwrap()
+/// };
+/// assert_eq!(status("a"), Some(MembershipStatus::Member));
+/// assert_eq!(status("b"), Some(MembershipStatus::Excluded));
+/// assert_eq!(status("c"), Some(MembershipStatus::NotAMember));
+/// ```
+pub fn membership_status<P: AsRef<Path>>(
+	crate_manifest: P,
+) -> Result<Option<MembershipStatus>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", ret))]
+	fn do_membership_status(crate_manifest: &Path) -> Result<Option<MembershipStatus>, Error> {
+		let crate_dir = crate_manifest.parent().unwrap_or(crate_manifest);
+		let Some(workspace_toml) = find_workspace_manifest(crate_dir) else {
+			return Ok(None);
+		};
+		let crate_manifest = std::path::absolute(crate_manifest)?;
+		let workspace_toml = std::path::absolute(workspace_toml)?;
+		if crate_manifest == workspace_toml {
+			return Ok(Some(MembershipStatus::Member));
+		}
+
+		let is_member = find_workspace_members(&workspace_toml)?
+			.iter()
+			.any(|member| std::path::absolute(member).is_ok_and(|member| member == crate_manifest));
+		if is_member {
+			return Ok(Some(MembershipStatus::Member));
+		}
+
+		let mut doc = std::fs::read_to_string(&workspace_toml)?.parse::<DocumentMut>()?;
+		let workspace_dir = workspace_toml.parent().expect("A file always lives inside a dir; qed");
+		let crate_dir = crate_manifest.parent().expect("A file always lives inside a dir; qed");
+		let is_excluded = workspace_table(&mut doc)?
+			.get("exclude")
+			.and_then(Item::as_array)
+			.is_some_and(|exclude| {
+				exclude
+					.iter()
+					.filter_map(|excluded| excluded.as_str())
+					.any(|excluded| crate_dir.starts_with(workspace_dir.join(excluded)))
+			});
+		Ok(Some(if is_excluded {
+			MembershipStatus::Excluded
+		} else {
+			MembershipStatus::NotAMember
+		}))
+	}
+	do_membership_status(crate_manifest.as_ref())
+}
+
+fn workspace_table(doc: &mut DocumentMut) -> Result<&mut Table, Error> {
+	match doc.get_mut("workspace") {
+		Some(Item::Table(workspace_table)) => Ok(workspace_table),
+		_ => Err(Error::Descriptive(
+			"The provided manifest path isn't a workspace manifest".to_owned(),
+		)),
+	}
+}
+
+/// The dirs of the workspace members.
+fn member_dirs(workspace_toml: &Path) -> Result<Vec<PathBuf>, Error> {
+	Ok(find_workspace_members(workspace_toml)?
+		.into_iter()
+		.map(|member| member.parent().expect("A file always lives inside a dir; qed").to_path_buf())
+		.collect())
+}
+
+/// The path relative to the workspace dir, as written in the workspace keys.
+fn relative_entry(workspace_dir: &Path, path: &Path) -> Result<String, Error> {
+	let relative_path = path.strip_prefix(workspace_dir)?;
+	if relative_path.as_os_str().is_empty() {
+		return Ok(".".to_owned());
+	}
+	Ok(relative_path
+		.to_str()
+		.expect("Path::to_str() is always a valid string; qed")
+		.to_owned())
+}
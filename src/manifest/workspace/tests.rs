@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+fn workspace(manifest: &str, members: &[&str]) -> TempDir {
+	let manifests = members
+		.iter()
+		.map(|member| format!("[package]\nname = \"{member}\""))
+		.collect::<Vec<_>>();
+	let members = members
+		.iter()
+		.zip(&manifests)
+		.map(|(member, manifest)| (*member, manifest.as_str()))
+		.collect::<Vec<_>>();
+	crate::test_utils::workspace(manifest, &members)
+}
+
+fn read_manifest(tempdir: &TempDir) -> String {
+	std::fs::read_to_string(tempdir.path().join("Cargo.toml")).expect("This should be Ok; qed;")
+}
+
+#[test]
+fn set_default_members_works() {
+	let tempdir = workspace(
+		"[package]\nname = \"root\"\n\n[workspace]\nmembers = [\"a\", \"b\"]\n",
+		&["a", "b"],
+	);
+	let manifest_path = tempdir.path().join("Cargo.toml");
+
+	set_default_members(&manifest_path, &[tempdir.path().to_path_buf(), tempdir.path().join("b")])
+		.expect("This should be Ok; qed;");
+	assert_eq!(
+		read_manifest(&tempdir),
+		"[package]\nname = \"root\"\n\n[workspace]\nmembers = [\"a\", \"b\"]\ndefault-members = [\".\", \"b\"]\n"
+	);
+	validate_default_members(&manifest_path).expect("This should be Ok; qed;");
+
+	// The previous value is replaced
+	set_default_members(&manifest_path, &[tempdir.path().join("a")])
+		.expect("This should be Ok; qed;");
+	assert_eq!(
+		read_manifest(&tempdir),
+		"[package]\nname = \"root\"\n\n[workspace]\nmembers = [\"a\", \"b\"]\ndefault-members = [\"a\"]\n"
+	);
+}
+
+#[test]
+fn set_default_members_fails_if_crate_isnt_a_member() {
+	let tempdir = workspace("[workspace]\nmembers = [\"a\"]\nexclude = [\"b\"]\n", &["a", "b"]);
+	let manifest_path = tempdir.path().join("Cargo.toml");
+
+	assert!(matches!(
+		set_default_members(&manifest_path, &[tempdir.path().join("b")]),
+		Err(Error::Descriptive(msg)) if msg == "The default member b isn't a workspace member"
+	));
+	assert!(matches!(
+		set_default_members(&manifest_path, &[PathBuf::from("/elsewhere")]),
+		Err(Error::StripPrefixError(_))
+	));
+	assert_eq!(read_manifest(&tempdir), "[workspace]\nmembers = [\"a\"]\nexclude = [\"b\"]\n");
+}
+
+#[test]
+fn validate_default_members_fails_if_default_member_isnt_a_member() {
+	let tempdir = workspace("[workspace]\nmembers = [\"a\"]\ndefault-members = [\"c\"]\n", &["a"]);
+	assert!(matches!(
+		validate_default_members(tempdir.path().join("Cargo.toml")),
+		Err(Error::Descriptive(msg)) if msg == "The default member c isn't a workspace member"
+	));
+
+	let tempdir = workspace("[workspace]\nmembers = [\"a\"]\ndefault-members = \"a\"\n", &["a"]);
+	assert!(matches!(
+		validate_default_members(tempdir.path().join("Cargo.toml")),
+		Err(Error::Descriptive(msg)) if msg == "The `default-members` field isn't an array of strings"
+	));
+
+	let tempdir = workspace("[package]\nname = \"test\"\n", &[]);
+	assert!(matches!(
+		validate_default_members(tempdir.path().join("Cargo.toml")),
+		Err(Error::Descriptive(msg)) if msg == "The provided manifest path isn't a workspace manifest"
+	));
+}
+
+#[test]
+fn add_excluded_path_works() {
+	let tempdir = workspace("[workspace]\nmembers = [\"*\"]\nexclude = [\"a\"]\n", &["a", "b"]);
+	let manifest_path = tempdir.path().join("Cargo.toml");
+
+	add_excluded_path(&manifest_path, tempdir.path().join("b")).expect("This should be Ok; qed;");
+	add_excluded_path(&manifest_path, tempdir.path().join("a")).expect("This should be Ok; qed;");
+	assert_eq!(
+		read_manifest(&tempdir),
+		"[workspace]\nmembers = [\"*\"]\nexclude = [\"a\", \"b\"]\n"
+	);
+	assert!(
+		find_workspace_members(&manifest_path)
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+}
+
+#[test]
+fn add_excluded_path_fails_if_exclude_isnt_an_array() {
+	let tempdir = workspace("[workspace]\nexclude = \"a\"\n", &[]);
+	assert!(matches!(
+		add_excluded_path(tempdir.path().join("Cargo.toml"), tempdir.path().join("b")),
+		Err(Error::Descriptive(msg)) if msg == "The `exclude` field isn't an array"
+	));
+}
+
+#[test]
+fn membership_status_works() {
+	let tempdir = workspace(
+		"[package]\nname = \"root\"\n\n[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/b\"]\n",
+		&["crates/a", "crates/b", "tools/c"],
+	);
+	let status = |path: PathBuf| membership_status(path).expect("This should be Ok; qed;");
+
+	assert_eq!(status(tempdir.path().join("Cargo.toml")), Some(MembershipStatus::Member));
+	assert_eq!(status(tempdir.path().join("crates/a/Cargo.toml")), Some(MembershipStatus::Member));
+	assert_eq!(
+		status(tempdir.path().join("crates/b/Cargo.toml")),
+		Some(MembershipStatus::Excluded)
+	);
+	assert_eq!(
+		status(tempdir.path().join("tools/c/Cargo.toml")),
+		Some(MembershipStatus::NotAMember)
+	);
+}
+
+#[test]
+fn membership_status_is_none_outside_workspaces() {
+	let tempdir = workspace("[package]\nname = \"root\"\n", &["a"]);
+	assert_eq!(
+		membership_status(tempdir.path().join("a/Cargo.toml")).expect("This should be Ok; qed;"),
+		None
+	);
+}
@@ -5,12 +5,68 @@ mod tests;
 
 pub mod attrs;
 pub mod attrs_mut;
+pub mod deps;
+pub mod lit_utils;
+pub mod signature_flags;
+pub mod source_tree;
+pub mod use_tree;
+pub mod vis;
 
+mod accessors;
+mod api;
+mod conditional;
+mod coverage;
+mod delegate;
+mod edit;
+mod edition;
+mod generics;
+mod inventory;
+mod invocations;
+mod markers;
+mod variants;
+
+pub use accessors::{AccessorOptions, generate_accessors};
+#[cfg(feature = "manifest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
+pub use api::suggest_semver_bump;
+pub use api::{ApiDiff, api_fingerprint, item_fingerprint, public_api_diff};
+pub use conditional::{CfgPredicate, cfg_map};
+pub use coverage::{DocCoverageReport, ModuleCoverage, doc_coverage};
+pub use delegate::delegate_impl;
+#[cfg(feature = "manifest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
+pub use edit::add_feature_gated_module;
+#[cfg(feature = "codegen")]
+pub(crate) use edit::module_dir;
+#[cfg(feature = "fmt")]
+pub(crate) use edit::render_visibility;
+pub use edit::{add_mod_declaration, add_reexport};
+#[cfg(feature = "manifest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
+pub use edition::parse_file_with_edition;
+pub use edition::{Edition, EditionFile, parse_str_with_edition};
+pub use generics::{
+	SyntaxNode, phantom_for_unused_generics, predicates_mentioning, substitute_type_param,
+};
+pub use inventory::{TestFn, TestKind, list_tests};
+#[cfg(feature = "manifest")]
+pub(crate) use inventory::{target_roots, target_trees_with};
+pub use invocations::{Invocation, InvocationPosition, find_macro_invocations};
+pub use markers::{Marker, MarkerKind, MarkerKinds, find_markers};
+pub use variants::{
+	discriminants, ensure_contiguous_discriminants, ensure_unique_discriminants,
+	generate_as_methods, generate_is_methods,
+};
+
+use quote::ToTokens;
+use std::{collections::HashSet, ops::BitOr};
 use syn::{
-	GenericParam, Generics, Token, WhereClause, WherePredicate, parse_quote, punctuated::Punctuated,
+	Attribute, Data, DataEnum, DataStruct, DeriveInput, ExprPath, Fields, FieldsNamed,
+	GenericParam, Generics, Item, Lifetime, Path, PathArguments, Token, Type, WhereClause,
+	WherePredicate, parse_quote, punctuated::Punctuated, visit::Visit,
 };
 
-use proc_macro2::{TokenStream, TokenTree};
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree, token_stream};
 
 /// Given a [Generics](https://docs.rs/syn/latest/syn/struct.Generics.html), this function will
 /// return:
@@ -57,6 +113,9 @@ use proc_macro2::{TokenStream, TokenTree};
 /// impl<const N:usize> OtherStruct<N>{}
 /// ```
 ///
+/// The attributes of the generic params are stripped from both outputs. Use
+/// [`extract_generics_with_attrs`] to keep or collect them.
+///
 /// # Example
 /// ```
 /// use syn::{Generics, WhereClause, punctuated::Punctuated, GenericParam, parse_quote, Token};
@@ -91,37 +150,115 @@ pub fn extract_generics(
 	generics: &Generics,
 ) -> (Punctuated<GenericParam, Token![,]>, Punctuated<GenericParam, Token![,]>, Option<WhereClause>)
 {
+	let extracted = extract_generics_with_attrs(generics, GenericParamAttrs::Strip);
+	(extracted.declarations, extracted.idents, extracted.where_clause)
+}
+
+/// How [`extract_generics_with_attrs`] handles the attributes of the generic params (eg,
+/// `#[cfg(feature = "std")] T`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenericParamAttrs {
+	/// The attributes are kept in both the declarations and the idents.
+	Preserve,
+	/// The attributes are removed from the declarations and the idents.
+	Strip,
+	/// The attributes are removed from the declarations and the idents, and returned separately.
+	Collect,
+}
+
+/// The output of [`extract_generics_with_attrs`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedGenerics {
+	/// The generics declarations without trait bounds.
+	pub declarations: Punctuated<GenericParam, Token![,]>,
+	/// The generics idents.
+	pub idents: Punctuated<GenericParam, Token![,]>,
+	/// The where clause collecting the trait bounds.
+	pub where_clause: Option<WhereClause>,
+	/// The attributes of every generic param, in the same order as the params. It's only filled
+	/// using [`GenericParamAttrs::Collect`], being empty otherwise.
+	pub attrs: Vec<Vec<Attribute>>,
+}
+
+/// Same as [`extract_generics`], but the attributes of the generic params are handled as
+/// specified by `attrs` (`extract_generics` strips them).
+///
+/// # Example
+///
+/// ```
+/// use rustilities::parsing::{GenericParamAttrs, extract_generics_with_attrs};
+/// use syn::{GenericParam, Generics, Token, parse_quote, punctuated::Punctuated};
+///
+/// let input: Generics = parse_quote! { <#[cfg(feature = "std")] T: Clone, const N: usize> };
+///
+/// let preserved = extract_generics_with_attrs(&input, GenericParamAttrs::Preserve);
+/// let expected: Punctuated<GenericParam, Token![,]> =
+///     parse_quote! { #[cfg(feature = "std")] T, const N: usize };
+/// assert_eq!(preserved.declarations, expected);
+/// assert!(preserved.attrs.is_empty());
+///
+/// let collected = extract_generics_with_attrs(&input, GenericParamAttrs::Collect);
+/// let expected: Punctuated<GenericParam, Token![,]> = parse_quote! { T, N };
+/// assert_eq!(collected.idents, expected);
+/// assert_eq!(collected.attrs, vec![vec![parse_quote!(#[cfg(feature = "std")])], vec![]]);
+/// ```
+pub fn extract_generics_with_attrs(
+	generics: &Generics,
+	attrs: GenericParamAttrs,
+) -> ExtractedGenerics {
 	let mut where_clauses: Punctuated<WherePredicate, Token![,]> = Punctuated::new();
 	let mut generics_idents: Punctuated<GenericParam, Token![,]> = Punctuated::new();
+	let mut collected_attrs = Vec::new();
 	let generics_declarations: Punctuated<GenericParam, Token![,]> = generics
 		.params
 		.iter()
-		.map(|item| match item {
-			GenericParam::Type(generic_type) => {
-				let ident = &generic_type.ident;
-				let bounds = &generic_type.bounds;
-				if !bounds.is_empty() {
-					where_clauses.push(parse_quote! {#ident: #bounds});
-				}
-				let ident = GenericParam::Type(parse_quote! { #ident });
-				generics_idents.push(ident.clone());
-				ident
-			},
-			GenericParam::Lifetime(lifetime) => {
-				let lifetime_dec = &lifetime.lifetime;
-				let bounds = &lifetime.bounds;
-				if !bounds.is_empty() {
-					where_clauses.push(parse_quote! {#lifetime_dec: #bounds});
-				}
-				let lifetime = GenericParam::Lifetime(parse_quote! {#lifetime_dec});
-				generics_idents.push(lifetime.clone());
-				lifetime
-			},
-			GenericParam::Const(generic_const) => {
-				let ident = &generic_const.ident;
-				generics_idents.push(GenericParam::Type(parse_quote! {#ident}));
-				item.clone()
-			},
+		.map(|item| {
+			let param_attrs = match item {
+				GenericParam::Type(generic_type) => &generic_type.attrs,
+				GenericParam::Lifetime(lifetime) => &lifetime.attrs,
+				GenericParam::Const(generic_const) => &generic_const.attrs,
+			};
+			let kept_attrs = match attrs {
+				GenericParamAttrs::Preserve => param_attrs.clone(),
+				GenericParamAttrs::Strip => Vec::new(),
+				GenericParamAttrs::Collect => {
+					collected_attrs.push(param_attrs.clone());
+					Vec::new()
+				},
+			};
+
+			let (declaration, ident) = match item {
+				GenericParam::Type(generic_type) => {
+					let ident = &generic_type.ident;
+					let bounds = &generic_type.bounds;
+					if !bounds.is_empty() {
+						where_clauses.push(parse_quote! {#ident: #bounds});
+					}
+					let ident = GenericParam::Type(parse_quote! { #(#kept_attrs)* #ident });
+					(ident.clone(), ident)
+				},
+				GenericParam::Lifetime(lifetime) => {
+					let lifetime_dec = &lifetime.lifetime;
+					let bounds = &lifetime.bounds;
+					if !bounds.is_empty() {
+						where_clauses.push(parse_quote! {#lifetime_dec: #bounds});
+					}
+					let lifetime =
+						GenericParam::Lifetime(parse_quote! { #(#kept_attrs)* #lifetime_dec });
+					(lifetime.clone(), lifetime)
+				},
+				GenericParam::Const(generic_const) => {
+					let ident = &generic_const.ident;
+					let mut declaration = generic_const.clone();
+					declaration.attrs = kept_attrs.clone();
+					(
+						GenericParam::Const(declaration),
+						GenericParam::Type(parse_quote! { #(#kept_attrs)* #ident }),
+					)
+				},
+			};
+			generics_idents.push(ident);
+			declaration
 		})
 		.collect();
 
@@ -139,7 +276,12 @@ pub fn extract_generics(
 				None
 			}
 		});
-	(generics_declarations, generics_idents, where_clause)
+	ExtractedGenerics {
+		declarations: generics_declarations,
+		idents: generics_idents,
+		where_clause,
+		attrs: collected_attrs,
+	}
 }
 
 /// Compares two [`TokenTree`](https://docs.rs/proc-macro2/latest/proc_macro2/enum.TokenTree.html) based solely
@@ -375,3 +517,554 @@ pub fn syntactic_token_stream_contains(small: TokenStream, large: TokenStream) -
 
 	false
 }
+
+/// Given a [`DeriveInput`], this function returns its struct data, or a [`syn::Error`] pointing to
+/// the `enum`/`union` keyword if the derive was applied to another kind of item. This is meant to
+/// be used by derive macros that only support structs.
+///
+/// # Example
+///
+/// ```
+/// use syn::{DeriveInput, parse_quote};
+///
+/// let input: DeriveInput = parse_quote! { struct Point { x: u8, y: u8 } };
+/// assert_eq!(rustilities::parsing::ensure_struct(&input).unwrap().fields.len(), 2);
+///
+/// let input: DeriveInput = parse_quote! { enum Direction { Up, Down } };
+/// assert_eq!(
+///     rustilities::parsing::ensure_struct(&input).unwrap_err().to_string(),
+///     "expected a struct"
+/// );
+/// ```
+pub fn ensure_struct(input: &DeriveInput) -> syn::Result<&DataStruct> {
+	match &input.data {
+		Data::Struct(data) => Ok(data),
+		Data::Enum(data) => Err(syn::Error::new_spanned(data.enum_token, "expected a struct")),
+		Data::Union(data) => Err(syn::Error::new_spanned(data.union_token, "expected a struct")),
+	}
+}
+
+/// Given a [`DeriveInput`], this function returns its enum data, or a [`syn::Error`] pointing to
+/// the `struct`/`union` keyword if the derive was applied to another kind of item. This is meant to
+/// be used by derive macros that only support enums.
+///
+/// # Example
+///
+/// ```
+/// use syn::{DeriveInput, parse_quote};
+///
+/// let input: DeriveInput = parse_quote! { enum Direction { Up, Down } };
+/// assert_eq!(rustilities::parsing::ensure_enum(&input).unwrap().variants.len(), 2);
+///
+/// let input: DeriveInput = parse_quote! { struct Point { x: u8, y: u8 } };
+/// assert_eq!(
+///     rustilities::parsing::ensure_enum(&input).unwrap_err().to_string(),
+///     "expected an enum"
+/// );
+/// ```
+pub fn ensure_enum(input: &DeriveInput) -> syn::Result<&DataEnum> {
+	match &input.data {
+		Data::Enum(data) => Ok(data),
+		Data::Struct(data) => Err(syn::Error::new_spanned(data.struct_token, "expected an enum")),
+		Data::Union(data) => Err(syn::Error::new_spanned(data.union_token, "expected an enum")),
+	}
+}
+
+/// Given a [`DeriveInput`], this function returns its named fields, or a [`syn::Error`] if the
+/// derive wasn't applied to a struct with named fields. The error points to the tuple fields of a
+/// tuple struct, to the name of a unit struct, or to the `enum`/`union` keyword otherwise. This is
+/// meant to be used by derive macros that only support structs with named fields.
+///
+/// # Example
+///
+/// ```
+/// use syn::{DeriveInput, parse_quote};
+///
+/// let input: DeriveInput = parse_quote! { struct Point { x: u8, y: u8 } };
+/// assert_eq!(rustilities::parsing::ensure_named_fields(&input).unwrap().named.len(), 2);
+///
+/// let input: DeriveInput = parse_quote! { struct Point(u8, u8); };
+/// assert_eq!(
+///     rustilities::parsing::ensure_named_fields(&input).unwrap_err().to_string(),
+///     "expected a struct with named fields"
+/// );
+/// ```
+pub fn ensure_named_fields(input: &DeriveInput) -> syn::Result<&FieldsNamed> {
+	const MESSAGE: &str = "expected a struct with named fields";
+	match &input.data {
+		Data::Struct(DataStruct { fields: Fields::Named(fields), .. }) => Ok(fields),
+		Data::Struct(DataStruct { fields: Fields::Unnamed(fields), .. }) =>
+			Err(syn::Error::new_spanned(fields, MESSAGE)),
+		Data::Struct(DataStruct { fields: Fields::Unit, .. }) =>
+			Err(syn::Error::new_spanned(&input.ident, MESSAGE)),
+		Data::Enum(data) => Err(syn::Error::new_spanned(data.enum_token, MESSAGE)),
+		Data::Union(data) => Err(syn::Error::new_spanned(data.union_token, MESSAGE)),
+	}
+}
+
+/// Given a [`Generics`] and a [`TokenStream`], this function returns an ident for a new type
+/// parameter that doesn't clash with any ident used in them. The ident is `prefix` itself if it's
+/// free, or `prefix` followed by the smallest number making it free otherwise. This is useful for
+/// macros that need to introduce a type parameter in user-provided generics, e.g. to write an
+/// `impl<__T> ...` block.
+///
+/// # Panics
+///
+/// If `prefix` isn't a valid ident.
+///
+/// # Example
+///
+/// ```
+/// use proc_macro2::TokenStream;
+/// use syn::{Generics, parse_quote};
+///
+/// let generics: Generics = parse_quote! { <T, T1: Into<T>> };
+/// let body: TokenStream = "fn convert(value: T2) {}".parse().unwrap();
+///
+/// assert_eq!(rustilities::parsing::fresh_type_param(&generics, "U", &body), "U");
+/// assert_eq!(rustilities::parsing::fresh_type_param(&generics, "T", &body), "T3");
+/// ```
+pub fn fresh_type_param(generics: &Generics, prefix: &str, tokens: &TokenStream) -> Ident {
+	Ident::new(&fresh_name(generics, prefix, tokens), Span::call_site())
+}
+
+/// Given a [`Generics`] and a [`TokenStream`], this function returns a new lifetime that doesn't
+/// clash with any lifetime or ident used in them, following the same naming rules as
+/// [`fresh_type_param`]. The prefix may include the leading `'`.
+///
+/// # Panics
+///
+/// If `prefix` isn't a valid lifetime name.
+///
+/// # Example
+///
+/// ```
+/// use proc_macro2::TokenStream;
+/// use syn::{Generics, parse_quote};
+///
+/// let generics: Generics = parse_quote! { <'a, T: 'a> };
+/// let body: TokenStream = "struct S<'b>(&'b T);".parse().unwrap();
+///
+/// assert_eq!(rustilities::parsing::fresh_lifetime(&generics, "'a", &body).to_string(), "'a1");
+/// assert_eq!(rustilities::parsing::fresh_lifetime(&generics, "b", &body).to_string(), "'b1");
+/// assert_eq!(rustilities::parsing::fresh_lifetime(&generics, "c", &body).to_string(), "'c");
+/// ```
+pub fn fresh_lifetime(generics: &Generics, prefix: &str, tokens: &TokenStream) -> Lifetime {
+	let prefix = prefix.strip_prefix('\'').unwrap_or(prefix);
+	Lifetime::new(&format!("'{}", fresh_name(generics, prefix, tokens)), Span::call_site())
+}
+
+/// Inserts a generic parameter in a [`Generics`], keeping the conventional order: lifetimes first,
+/// then type parameters, and const parameters last. The parameter is placed after the existing
+/// parameters of its kind.
+///
+/// # Example
+///
+/// ```
+/// use syn::{Generics, parse_quote};
+///
+/// let mut generics: Generics = parse_quote! { <'a, T, const N: usize> };
+/// rustilities::parsing::insert_param(&mut generics, parse_quote! { 'b });
+/// rustilities::parsing::insert_param(&mut generics, parse_quote! { U: Clone });
+/// rustilities::parsing::insert_param(&mut generics, parse_quote! { const M: u8 });
+///
+/// let expected: Generics = parse_quote! { <'a, 'b, T, U: Clone, const N: usize, const M: u8> };
+/// assert_eq!(generics, expected);
+/// ```
+pub fn insert_param(generics: &mut Generics, param: GenericParam) {
+	let precedes = |existing: &GenericParam| match param {
+		GenericParam::Lifetime(_) => matches!(existing, GenericParam::Lifetime(_)),
+		GenericParam::Type(_) => !matches!(existing, GenericParam::Const(_)),
+		GenericParam::Const(_) => true,
+	};
+	let index = generics.params.iter().take_while(|existing| precedes(existing)).count();
+	generics.params.insert(index, param);
+}
+
+fn fresh_name(generics: &Generics, prefix: &str, tokens: &TokenStream) -> String {
+	struct IdentCollector(HashSet<String>);
+
+	impl Visit<'_> for IdentCollector {
+		fn visit_ident(&mut self, ident: &Ident) {
+			self.0.insert(ident.to_string());
+		}
+	}
+
+	fn collect_token_idents(tokens: TokenStream, idents: &mut HashSet<String>) {
+		tokens.into_iter().for_each(|token| match token {
+			TokenTree::Ident(ident) => {
+				idents.insert(ident.to_string());
+			},
+			TokenTree::Group(group) => collect_token_idents(group.stream(), idents),
+			_ => (),
+		});
+	}
+
+	let mut collector = IdentCollector(HashSet::new());
+	collector.visit_generics(generics);
+	let mut used = collector.0;
+	collect_token_idents(tokens.clone(), &mut used);
+
+	std::iter::once(prefix.to_owned())
+		.chain((1..).map(|i| format!("{prefix}{i}")))
+		.find(|name| !used.contains(name))
+		.expect("The used idents are finite; qed;")
+}
+
+/// Given a [`Path`], this function returns it in turbofish form, as needed in expression position:
+/// `Vec<T>` becomes `Vec::<T>`. Segments without generic arguments, or already in turbofish form,
+/// are left untouched.
+///
+/// # Example
+///
+/// ```
+/// use syn::{Path, parse_quote};
+///
+/// let path: Path = parse_quote! { std::collections::HashMap<K, Vec<V>> };
+/// let expected: Path = parse_quote! { std::collections::HashMap::<K, Vec<V>> };
+///
+/// assert_eq!(rustilities::parsing::turbofish(&path), expected);
+/// ```
+pub fn turbofish(path: &Path) -> Path {
+	let mut path = path.clone();
+	path.segments.iter_mut().for_each(|segment| {
+		if let PathArguments::AngleBracketed(args) = &mut segment.arguments {
+			args.colon2_token.get_or_insert_with(Default::default);
+		}
+	});
+	path
+}
+
+/// Given a [`Path`], this function returns it without turbofish, as written in type position:
+/// `Vec::<T>` becomes `Vec<T>`. This is the inverse of [`turbofish`].
+///
+/// # Example
+///
+/// ```
+/// use syn::{Path, parse_quote};
+///
+/// let path: Path = parse_quote! { Option::<u8>::Some };
+/// let expected: Path = parse_quote! { Option<u8>::Some };
+///
+/// assert_eq!(rustilities::parsing::strip_turbofish(&path), expected);
+/// ```
+pub fn strip_turbofish(path: &Path) -> Path {
+	let mut path = path.clone();
+	path.segments.iter_mut().for_each(|segment| {
+		if let PathArguments::AngleBracketed(args) = &mut segment.arguments {
+			args.colon2_token = None;
+		}
+	});
+	path
+}
+
+/// Given a [`Path`], this function returns it without any generic arguments, including the
+/// parenthesized arguments of `Fn`-like traits: `a::B<T>::C<U>` becomes `a::B::C`.
+///
+/// # Example
+///
+/// ```
+/// use syn::{Path, parse_quote};
+///
+/// let path: Path = parse_quote! { core::result::Result::<T, E>::Ok };
+/// let expected: Path = parse_quote! { core::result::Result::Ok };
+///
+/// assert_eq!(rustilities::parsing::strip_generic_args(&path), expected);
+/// ```
+pub fn strip_generic_args(path: &Path) -> Path {
+	let mut path = path.clone();
+	path.segments
+		.iter_mut()
+		.for_each(|segment| segment.arguments = PathArguments::None);
+	path
+}
+
+/// Given a type, a trait path and a method name, this function builds the fully qualified path
+/// `<Type as Trait>::method`, which unambiguously refers to the trait method even if the type has
+/// an inherent method, or implements another trait, with the same name.
+///
+/// # Example
+///
+/// ```
+/// use syn::{ExprPath, Ident, Path, Type, parse_quote};
+///
+/// let ty: Type = parse_quote! { Self };
+/// let trait_path: Path = parse_quote! { core::convert::From<u8> };
+/// let method: Ident = parse_quote! { from };
+/// let expected: ExprPath = parse_quote! { <Self as core::convert::From<u8>>::from };
+///
+/// assert_eq!(rustilities::parsing::qualified_method_path(&ty, &trait_path, &method), expected);
+/// ```
+pub fn qualified_method_path(ty: &Type, trait_path: &Path, method: &Ident) -> ExprPath {
+	let trait_path = strip_turbofish(trait_path);
+	parse_quote! { <#ty as #trait_path>::#method }
+}
+
+/// Given a [`TokenStream`] and a path of group indices, this function returns the content of the
+/// group located by the path. Each index selects a group among the groups (not the token trees)
+/// directly contained in the current stream, starting from the given stream: `&[1, 0]` is the
+/// first group inside the second group of the stream. An empty path returns the stream itself.
+///
+/// The paths of all the groups in a stream can be obtained with [`groups`].
+///
+/// # Example
+///
+/// ```
+/// use proc_macro2::TokenStream;
+///
+/// let stream: TokenStream = "fn f(a: u8) { let b = [a; 2]; g(b) }".parse().unwrap();
+///
+/// let inner = rustilities::parsing::group_at(stream.clone(), &[1, 0]).unwrap();
+/// assert_eq!(inner.to_string(), "a ; 2");
+/// assert!(rustilities::parsing::group_at(stream, &[2]).is_none());
+/// ```
+pub fn group_at(stream: TokenStream, path: &[usize]) -> Option<TokenStream> {
+	path.iter().try_fold(stream, |stream, index| {
+		stream
+			.into_iter()
+			.filter_map(|token| match token {
+				TokenTree::Group(group) => Some(group.stream()),
+				_ => None,
+			})
+			.nth(*index)
+	})
+}
+
+/// A group found by [`groups`].
+#[derive(Debug, Clone)]
+pub struct GroupEntry {
+	/// The path locating the group, as used by [`group_at`].
+	pub path: Vec<usize>,
+	/// The delimiter of the group.
+	pub delimiter: Delimiter,
+	/// The nesting depth of the group, 0 for the groups directly contained in the stream.
+	pub depth: usize,
+	/// The content of the group.
+	pub stream: TokenStream,
+}
+
+/// The iterator returned by [`groups`].
+pub struct Groups {
+	// The streams being traversed, with the path of their group and the number of groups already
+	// found in them
+	stack: Vec<(token_stream::IntoIter, Vec<usize>, usize)>,
+}
+
+impl Iterator for Groups {
+	type Item = GroupEntry;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			let (tokens, path, found) = self.stack.last_mut()?;
+			match tokens.next() {
+				Some(TokenTree::Group(group)) => {
+					let mut group_path = path.clone();
+					group_path.push(*found);
+					*found += 1;
+					self.stack.push((group.stream().into_iter(), group_path.clone(), 0));
+					return Some(GroupEntry {
+						depth: group_path.len() - 1,
+						path: group_path,
+						delimiter: group.delimiter(),
+						stream: group.stream(),
+					});
+				},
+				Some(_) => (),
+				None => {
+					self.stack.pop();
+				},
+			}
+		}
+	}
+}
+
+/// Given a [`TokenStream`], this function returns an iterator over all the groups it contains, at
+/// any depth, in the order they appear (a group comes before the groups it contains).
+///
+/// # Example
+///
+/// ```
+/// use proc_macro2::{Delimiter, TokenStream};
+///
+/// let stream: TokenStream = "fn f(a: u8) { g([a]) }".parse().unwrap();
+///
+/// let groups = rustilities::parsing::groups(stream)
+///     .map(|group| (group.path, group.delimiter, group.depth))
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(
+///     groups,
+///     vec![
+///         (vec![0], Delimiter::Parenthesis, 0),
+///         (vec![1], Delimiter::Brace, 0),
+///         (vec![1, 0], Delimiter::Parenthesis, 1),
+///         (vec![1, 0, 0], Delimiter::Bracket, 2),
+///     ]
+/// );
+/// ```
+pub fn groups(stream: TokenStream) -> Groups {
+	Groups { stack: vec![(stream.into_iter(), Vec::new(), 0)] }
+}
+
+/// A relaxation of the equality checked by [`items_equivalent`]. Several relaxations can be
+/// combined with `|` into an [`Equivalences`] set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Equivalence {
+	/// Doc comments (`///`, `//!` and `#[doc = ...]` attributes) are ignored.
+	IgnoreDocs,
+	/// Every attribute, including doc comments, is ignored.
+	IgnoreAttrs,
+	/// The order of consecutive attributes is ignored, so `#[a] #[b]` is equivalent to `#[b] #[a]`.
+	IgnoreAttrOrder,
+}
+
+/// A set of [`Equivalence`] relaxations. The empty set, returned by [`Equivalences::default`],
+/// checks strict syntactic equality.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Equivalences(u8);
+
+impl Equivalences {
+	/// Whether the set contains the given relaxation.
+	pub fn contains(&self, equivalence: Equivalence) -> bool {
+		self.0 & Self::from(equivalence).0 != 0
+	}
+}
+
+impl From<Equivalence> for Equivalences {
+	fn from(equivalence: Equivalence) -> Self {
+		Self(1 << equivalence as u8)
+	}
+}
+
+impl BitOr for Equivalence {
+	type Output = Equivalences;
+
+	fn bitor(self, rhs: Self) -> Self::Output {
+		Equivalences::from(self) | rhs
+	}
+}
+
+impl BitOr<Equivalence> for Equivalences {
+	type Output = Self;
+
+	fn bitor(self, rhs: Equivalence) -> Self::Output {
+		Self(self.0 | Self::from(rhs).0)
+	}
+}
+
+/// Given two [`Item`], this function compares them syntactically, as
+/// [`syntactic_token_stream_compare`] does, after relaxing the comparison with the given
+/// [`Equivalences`]. Unlike [`tt_without_docs`](attrs_mut::tt_without_docs) and
+/// [`tt_without_attrs`](attrs_mut::tt_without_attrs), the relaxations also apply to the attributes
+/// nested in the items, such as those of struct fields or impl items.
+///
+/// # Example
+///
+/// ```
+/// use rustilities::parsing::{Equivalence, Equivalences};
+/// use syn::{Item, parse_quote};
+///
+/// let a: Item = parse_quote! {
+///     /// Some docs.
+///     #[derive(Debug)]
+///     #[repr(C)]
+///     struct S {
+///         /// Field docs.
+///         a: u8,
+///     }
+/// };
+/// let b: Item = parse_quote! {
+///     #[repr(C)]
+///     #[derive(Debug)]
+///     struct S {
+///         a: u8,
+///     }
+/// };
+///
+/// assert!(!rustilities::parsing::items_equivalent(&a, &b, Equivalences::default()));
+/// assert!(!rustilities::parsing::items_equivalent(&a, &b, Equivalence::IgnoreDocs));
+/// assert!(rustilities::parsing::items_equivalent(
+///     &a,
+///     &b,
+///     Equivalence::IgnoreDocs | Equivalence::IgnoreAttrOrder
+/// ));
+/// assert!(rustilities::parsing::items_equivalent(&a, &b, Equivalence::IgnoreAttrs));
+/// ```
+pub fn items_equivalent(a: &Item, b: &Item, equivalences: impl Into<Equivalences>) -> bool {
+	let equivalences = equivalences.into();
+	syntactic_token_stream_compare(
+		normalize_attrs(a.to_token_stream(), equivalences),
+		normalize_attrs(b.to_token_stream(), equivalences),
+	)
+}
+
+/// Removes or sorts the attributes found at any depth of the stream, as requested by the
+/// equivalences.
+fn normalize_attrs(stream: TokenStream, equivalences: Equivalences) -> TokenStream {
+	let tokens: Vec<TokenTree> = stream.into_iter().collect();
+	let mut output = TokenStream::new();
+	// The attributes found since the last token that isn't part of an attribute
+	let mut attrs: Vec<TokenStream> = Vec::new();
+	let flush = |attrs: &mut Vec<TokenStream>, output: &mut TokenStream| {
+		if equivalences.contains(Equivalence::IgnoreAttrOrder) {
+			attrs.sort_by_cached_key(TokenStream::to_string);
+		}
+		output.extend(attrs.drain(..));
+	};
+
+	let mut index = 0;
+	while index < tokens.len() {
+		let Some(len) = attribute_len(&tokens[index..]) else {
+			flush(&mut attrs, &mut output);
+			output.extend([normalize_tree(tokens[index].clone(), equivalences)]);
+			index += 1;
+			continue;
+		};
+
+		let attr = &tokens[index..index + len];
+		let is_doc = matches!(
+			attr.last().and_then(|tree| match tree {
+				TokenTree::Group(group) => group.stream().into_iter().next(),
+				_ => None,
+			}),
+			Some(TokenTree::Ident(ident)) if ident == "doc"
+		);
+		let ignored = equivalences.contains(Equivalence::IgnoreAttrs) ||
+			(is_doc && equivalences.contains(Equivalence::IgnoreDocs));
+		if !ignored {
+			attrs.push(
+				attr.iter().cloned().map(|tree| normalize_tree(tree, equivalences)).collect(),
+			);
+		}
+		index += len;
+	}
+	flush(&mut attrs, &mut output);
+	output
+}
+
+fn normalize_tree(tree: TokenTree, equivalences: Equivalences) -> TokenTree {
+	match tree {
+		TokenTree::Group(group) => {
+			let mut normalized =
+				Group::new(group.delimiter(), normalize_attrs(group.stream(), equivalences));
+			normalized.set_span(group.span());
+			TokenTree::Group(normalized)
+		},
+		tree => tree,
+	}
+}
+
+/// The number of tokens of the attribute (`#[...]` or `#![...]`) the tokens start with, if any.
+fn attribute_len(tokens: &[TokenTree]) -> Option<usize> {
+	match tokens {
+		[TokenTree::Punct(pound), TokenTree::Group(group), ..]
+			if pound.as_char() == '#' && group.delimiter() == Delimiter::Bracket =>
+			Some(2),
+		[TokenTree::Punct(pound), TokenTree::Punct(bang), TokenTree::Group(group), ..]
+			if pound.as_char() == '#' &&
+				bang.as_char() == '!' &&
+				group.delimiter() == Delimiter::Bracket =>
+			Some(3),
+		_ => None,
+	}
+}
@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities generating getters and setters for the fields of a struct.
+
+#[cfg(test)]
+mod tests;
+
+use quote::format_ident;
+use syn::{
+	Attribute, Fields, Ident, ImplItem, ItemImpl, ItemStruct, Meta, Token, Visibility,
+	ext::IdentExt, parse_quote, punctuated::Punctuated,
+};
+
+/// The options used by [`generate_accessors`] to build the accessors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessorOptions<'a> {
+	/// Whether getters are generated.
+	pub getters: bool,
+	/// Whether setters are generated.
+	pub setters: bool,
+	/// The visibility of the accessors.
+	pub vis: Visibility,
+	/// The prefix of the setter names, prepended to the field names.
+	pub setter_prefix: &'a str,
+	/// The name of the field attribute skipping the accessors of a field: `#[skip]` skips both
+	/// accessors, while `#[skip(getter)]` and `#[skip(setter)]` skip only one of them.
+	pub skip_attr: &'a str,
+}
+
+/// The default options generate public getters and setters prefixed with `set_`, skipping the
+/// fields with a `#[skip]` attribute.
+impl Default for AccessorOptions<'_> {
+	fn default() -> Self {
+		Self {
+			getters: true,
+			setters: true,
+			vis: parse_quote! { pub },
+			setter_prefix: "set_",
+			skip_attr: "skip",
+		}
+	}
+}
+
+/// Given a struct with named fields, this function generates an impl block with accessors for its
+/// fields, as configured by the [`AccessorOptions`]:
+/// - A getter named after the field, `fn field(&self) -> &T`.
+/// - A setter named after the field with the setter prefix, `fn set_field(&mut self, field: T)`.
+///
+/// Raw identifiers are unraw-ed in the setter names, so the setter of `r#type` is `set_type`. The
+/// skip attribute is only read, so it still has to be declared as a helper attribute by the derive
+/// macro, or removed from the struct by the attribute macro, using this function.
+///
+/// # Errors
+///
+/// - If the struct doesn't have named fields.
+/// - If a skip attribute has an argument other than `getter` or `setter`.
+///
+/// # Example
+///
+/// ```
+/// use rustilities::parsing::{AccessorOptions, generate_accessors};
+/// use syn::{ItemImpl, ItemStruct, parse_quote};
+///
+/// let item_struct: ItemStruct = parse_quote! {
+///     struct Config<T> {
+///         name: String,
+///         #[skip(setter)]
+///         value: T,
+///         #[skip]
+///         cache: Vec<u8>,
+///     }
+/// };
+/// let options = AccessorOptions { vis: parse_quote! { pub(crate) }, ..Default::default() };
+///
+/// let expected: ItemImpl = parse_quote! {
+///     impl<T> Config<T> {
+///         pub(crate) fn name(&self) -> &String {
+///             &self.name
+///         }
+///         pub(crate) fn set_name(&mut self, name: String) {
+///             self.name = name;
+///         }
+///         pub(crate) fn value(&self) -> &T {
+///             &self.value
+///         }
+///     }
+/// };
+///
+/// assert_eq!(generate_accessors(&item_struct, &options).unwrap(), expected);
+/// ```
+pub fn generate_accessors(
+	item_struct: &ItemStruct,
+	options: &AccessorOptions,
+) -> syn::Result<ItemImpl> {
+	let Fields::Named(fields) = &item_struct.fields else {
+		return Err(syn::Error::new_spanned(
+			&item_struct.ident,
+			"expected a struct with named fields",
+		));
+	};
+
+	let vis = &options.vis;
+	let mut items: Vec<ImplItem> = Vec::new();
+	for field in &fields.named {
+		let (skip_getter, skip_setter) = skipped_accessors(&field.attrs, options.skip_attr)?;
+		let ident = field.ident.as_ref().expect("The fields are named; qed;");
+		let ty = &field.ty;
+		if options.getters && !skip_getter {
+			items.push(parse_quote! {
+				#vis fn #ident(&self) -> &#ty {
+					&self.#ident
+				}
+			});
+		}
+		if options.setters && !skip_setter {
+			let setter = format_ident!("{}{}", options.setter_prefix, ident.unraw());
+			items.push(parse_quote! {
+				#vis fn #setter(&mut self, #ident: #ty) {
+					self.#ident = #ident;
+				}
+			});
+		}
+	}
+
+	let name = &item_struct.ident;
+	let (impl_generics, ty_generics, where_clause) = item_struct.generics.split_for_impl();
+	Ok(parse_quote! {
+		impl #impl_generics #name #ty_generics #where_clause {
+			#(#items)*
+		}
+	})
+}
+
+// Returns whether the getter and the setter of a field are skipped.
+fn skipped_accessors(attrs: &[Attribute], skip_attr: &str) -> syn::Result<(bool, bool)> {
+	let mut skipped = (false, false);
+	for attr in attrs.iter().filter(|attr| attr.path().is_ident(skip_attr)) {
+		let Meta::List(list) = &attr.meta else {
+			return Ok((true, true));
+		};
+		for accessor in list.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)? {
+			match accessor.to_string().as_str() {
+				"getter" => skipped.0 = true,
+				"setter" => skipped.1 = true,
+				_ => return Err(syn::Error::new_spanned(accessor, "expected `getter` or `setter`")),
+			}
+		}
+	}
+	Ok(skipped)
+}
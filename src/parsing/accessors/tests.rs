@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+#[test]
+fn generate_accessors_respects_options() {
+	let item_struct: ItemStruct = parse_quote! {
+		pub struct Point<T: Copy> where T: Default {
+			x: T,
+			#[skip(getter, setter)]
+			y: T,
+			r#type: u8,
+		}
+	};
+	let options = AccessorOptions {
+		getters: false,
+		setter_prefix: "with_",
+		vis: Visibility::Inherited,
+		..Default::default()
+	};
+	let expected: ItemImpl = parse_quote! {
+		impl<T: Copy> Point<T> where T: Default {
+			fn with_x(&mut self, x: T) {
+				self.x = x;
+			}
+			fn with_type(&mut self, r#type: u8) {
+				self.r#type = r#type;
+			}
+		}
+	};
+
+	assert_eq!(
+		generate_accessors(&item_struct, &options).expect("This should be Ok; qed;"),
+		expected
+	);
+}
+
+#[test]
+fn generate_accessors_uses_custom_skip_attr() {
+	let item_struct: ItemStruct = parse_quote! {
+		struct S {
+			#[accessors_skip]
+			a: u8,
+			#[skip]
+			#[accessors_skip(setter)]
+			b: u8,
+		}
+	};
+	let options = AccessorOptions { skip_attr: "accessors_skip", ..Default::default() };
+	let expected: ItemImpl = parse_quote! {
+		impl S {
+			pub fn b(&self) -> &u8 {
+				&self.b
+			}
+		}
+	};
+
+	assert_eq!(
+		generate_accessors(&item_struct, &options).expect("This should be Ok; qed;"),
+		expected
+	);
+}
+
+#[test]
+fn generate_accessors_fails_without_named_fields() {
+	["struct S(u8);", "struct S;"].into_iter().for_each(|item| {
+		let item_struct: ItemStruct = syn::parse_str(item).expect("This should be Ok; qed;");
+		assert_eq!(
+			generate_accessors(&item_struct, &AccessorOptions::default())
+				.expect_err("This should be Err; qed;")
+				.to_string(),
+			"expected a struct with named fields"
+		);
+	});
+}
+
+#[test]
+fn generate_accessors_fails_on_unknown_skip_args() {
+	let item_struct: ItemStruct = parse_quote! {
+		struct S {
+			#[skip(getter, other)]
+			a: u8,
+		}
+	};
+
+	assert_eq!(
+		generate_accessors(&item_struct, &AccessorOptions::default())
+			.expect_err("This should be Err; qed;")
+			.to_string(),
+		"expected `getter` or `setter`"
+	);
+}
@@ -0,0 +1,584 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities inspecting the public API of Rust source code.
+
+#[cfg(test)]
+mod tests;
+
+use super::{
+	Equivalence, normalize_attrs,
+	source_tree::{SourceTree, item_name, use_bindings},
+};
+use crate::diagnostic::{Diagnostic, Severity, ToDiagnostics};
+#[cfg(feature = "manifest")]
+use crate::{BumpKind, Error};
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use std::collections::{BTreeMap, HashSet};
+#[cfg(feature = "manifest")]
+use std::path::Path;
+use syn::{
+	Expr, Field, Fields, ForeignItem, Ident, ImplItem, Item, ItemImpl, TraitItem, Type, Visibility,
+	ext::IdentExt, parse_quote,
+};
+
+/// The differences between the public APIs of two versions of a source tree, as computed by
+/// [`public_api_diff`]. The items are identified by their paths, sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ApiDiff {
+	/// The public items only found in the new tree.
+	pub added: Vec<String>,
+	/// The public items only found in the old tree.
+	pub removed: Vec<String>,
+	/// The public items found in both trees whose signature changed.
+	pub changed: Vec<String>,
+}
+
+impl ApiDiff {
+	/// Whether the public APIs are the same.
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+	}
+}
+
+impl ToDiagnostics for ApiDiff {
+	/// An `api::removed` and an `api::changed` warning per removed and changed item, as they break
+	/// the dependents, followed by an `api::added` note per added item.
+	fn to_diagnostics(&self) -> Vec<Diagnostic> {
+		let diagnostics = |items: &[String], severity, code, what| {
+			items
+				.iter()
+				.map(move |item| Diagnostic::new(severity, code, format!("`{item}` was {what}")))
+				.collect::<Vec<_>>()
+		};
+		[
+			diagnostics(&self.removed, Severity::Warning, "api::removed", "removed"),
+			diagnostics(&self.changed, Severity::Warning, "api::changed", "changed"),
+			diagnostics(&self.added, Severity::Note, "api::added", "added"),
+		]
+		.concat()
+	}
+}
+
+/// Given an item, this function returns a fingerprint of its public signature, or `None` if the
+/// item isn't part of the public API. Two items have the same fingerprint if their signatures are
+/// the same, regardless of their bodies, doc comments and formatting. The fingerprint is stable
+/// across executions and platforms, so it can be stored and compared later.
+///
+/// The public signature of an item is made of:
+/// - For `pub` functions, their signature, without their body.
+/// - For `pub` consts and statics, their name and type, without their value.
+/// - For `pub` structs and unions, their name, generics and `pub` fields. The private fields aren't
+///   included, but whether there's any of them is, as it prevents building the type.
+/// - For `pub` enums, their name, generics and variants, without their discriminants.
+/// - For `pub` traits, their header and the signatures of their items, without default bodies.
+/// - For `pub` modules, their name, as their items are fingerprinted on their own.
+/// - For `pub` type aliases, trait aliases, `use` and `extern crate` declarations, the whole item.
+/// - For `#[macro_export]` macros, the whole macro.
+/// - For impl blocks, their header and the signatures of their items, only considering the `pub`
+///   items of inherent impls. Inherent impls without `pub` items aren't part of the public API.
+/// - For `extern` blocks, their `pub` items.
+///
+/// The attributes other than doc comments are part of the signature.
+///
+/// # Example
+///
+/// ```
+/// use syn::{Item, parse_quote};
+///
+/// let a: Item = parse_quote! {
+///     /// Adds one.
+///     pub fn add_one(x: u8) -> u8 { x + 1 }
+/// };
+/// let b: Item = parse_quote! {
+///     pub fn add_one(x: u8) -> u8 {
+///         1 + x
+///     }
+/// };
+/// let c: Item = parse_quote! { pub fn add_one(x: u16) -> u16 { x + 1 } };
+/// let d: Item = parse_quote! { fn add_one(x: u8) -> u8 { x + 1 } };
+///
+/// let fingerprint = rustilities::parsing::item_fingerprint(&a);
+/// assert!(fingerprint.is_some());
+/// assert_eq!(fingerprint, rustilities::parsing::item_fingerprint(&b));
+/// assert_ne!(fingerprint, rustilities::parsing::item_fingerprint(&c));
+/// assert!(rustilities::parsing::item_fingerprint(&d).is_none());
+/// ```
+pub fn item_fingerprint(item: &Item) -> Option<u64> {
+	public_signature(item).map(|signature| signature_hash(&signature))
+}
+
+/// Given a file, this function returns a fingerprint of its public API, made of the fingerprints
+/// returned by [`item_fingerprint`] for its items and the items of its `pub` inline modules. The
+/// order of the items doesn't affect the fingerprint, but the module where they live does.
+///
+/// # Example
+///
+/// ```
+/// use syn::{File, parse_quote};
+///
+/// let a: File = parse_quote! {
+///     pub struct A;
+///     pub mod inner { pub fn f() {} }
+///     fn private() {}
+/// };
+/// let b: File = parse_quote! {
+///     pub mod inner { pub fn f() { println!("Hello"); } }
+///     pub struct A;
+/// };
+/// let c: File = parse_quote! {
+///     pub struct A;
+///     pub mod inner {}
+///     pub fn f() {}
+/// };
+///
+/// let fingerprint = rustilities::parsing::api_fingerprint(&a);
+/// assert_eq!(fingerprint, rustilities::parsing::api_fingerprint(&b));
+/// assert_ne!(fingerprint, rustilities::parsing::api_fingerprint(&c));
+/// ```
+pub fn api_fingerprint(file: &syn::File) -> u64 {
+	fn collect(items: &[Item], module_path: &mut Vec<String>, fingerprints: &mut Vec<String>) {
+		for item in items {
+			if let Some(fingerprint) = item_fingerprint(item) {
+				fingerprints.push(format!("{}::{fingerprint:016x}", module_path.join("::")));
+			}
+			if let Item::Mod(item_mod) = item &&
+				is_pub(&item_mod.vis) &&
+				let Some((_, items)) = &item_mod.content
+			{
+				module_path.push(item_mod.ident.to_string());
+				collect(items, module_path, fingerprints);
+				module_path.pop();
+			}
+		}
+	}
+
+	let mut fingerprints = Vec::new();
+	collect(&file.items, &mut Vec::new(), &mut fingerprints);
+	fingerprints.sort_unstable();
+	hash(&fingerprints.join("\n"))
+}
+
+/// Given two versions of a source tree, this function lists the public items added, removed or
+/// changed by the new version, comparing their signatures with [`item_fingerprint`].
+///
+/// The public items are the items of [`item_fingerprint`] living in a module reachable through
+/// `pub` modules from the root of the tree. They're identified by their path from the root of the
+/// tree, starting by `crate`, with some special cases:
+/// - Every name bound by a `pub use` declaration is an item on its own, pointing to the imported
+///   path, while glob re-exports are identified by the globbed path (eg, `crate::a::*`).
+/// - Impl blocks are identified by their header, such as `crate::<impl Display for Foo>`. The
+///   inherent impls of a type living in the same module are merged.
+/// - The `pub` items of `extern` blocks are identified by their name.
+/// - Exported macros live at the crate root, marked with a `!` (eg, `crate::my_macro!`).
+///
+/// # Example
+///
+/// ```
+/// use rustilities::parsing::{ApiDiff, public_api_diff, source_tree::SourceTree};
+///
+/// let old_dir = tempfile::tempdir().unwrap();
+/// std::fs::write(
+///     old_dir.path().join("lib.rs"),
+///     "pub fn f(a: u8) {} pub fn g() {} pub mod m { pub struct S; }",
+/// )
+/// .unwrap();
+/// let new_dir = tempfile::tempdir().unwrap();
+/// std::fs::write(
+///     new_dir.path().join("lib.rs"),
+///     "pub fn f(a: u16) {} pub mod m { pub struct S; pub struct T; } fn g() {}",
+/// )
+/// .unwrap();
+///
+/// let old = SourceTree::load(old_dir.path().join("lib.rs")).unwrap();
+/// let new = SourceTree::load(new_dir.path().join("lib.rs")).unwrap();
+/// assert_eq!(
+///     public_api_diff(&old, &new),
+///     ApiDiff {
+///         added: vec!["crate::m::T".to_owned()],
+///         removed: vec!["crate::g".to_owned()],
+///         changed: vec!["crate::f".to_owned()],
+///     }
+/// );
+/// ```
+pub fn public_api_diff(old: &SourceTree, new: &SourceTree) -> ApiDiff {
+	let old = public_items(old);
+	let new = public_items(new);
+	let mut diff = ApiDiff::default();
+	for (path, fingerprints) in &new {
+		match old.get(path) {
+			None => diff.added.push(path.clone()),
+			Some(old_fingerprints) if old_fingerprints != fingerprints =>
+				diff.changed.push(path.clone()),
+			Some(_) => (),
+		}
+	}
+	diff.removed = old.into_keys().filter(|path| !new.contains_key(path)).collect();
+	diff
+}
+
+/// Given the dirs of two versions of a crate, this function suggests the version bump needed to
+/// release the new version, based on the [`public_api_diff`] of their libraries:
+/// - [`BumpKind::Major`] if some public item was removed or changed.
+/// - [`BumpKind::Minor`] if some public item was added.
+/// - [`BumpKind::Patch`] otherwise.
+///
+/// Adding a library counts as an addition and removing it as a removal, while crates without a
+/// library always get a patch bump. The library is expected at `src/lib.rs`.
+///
+/// As cargo does, the suggestion follows the version of the old crate: for `0.y.z` versions, a
+/// breaking change only requires a minor bump and an addition a patch bump, while every change of
+/// a `0.0.z` version is breaking, so a patch bump is always suggested. The version may be
+/// inherited from the workspace.
+///
+/// # Errors
+///
+/// - If the old manifest cannot be read or doesn't declare a valid version.
+/// - If the libraries cannot be loaded.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::BumpKind;
+///
+/// let old_dir = tempfile::tempdir().unwrap();
+/// let new_dir = tempfile::tempdir().unwrap();
+/// for (dir, lib) in [(&old_dir, "pub fn f() {}"), (&new_dir, "pub fn f() {}\npub fn g() {}")] {
+///     std::fs::create_dir_all(dir.path().join("src")).unwrap();
+///     std::fs::write(dir.path().join("src/lib.rs"), lib).unwrap();
+///     std::fs::write(
+///         dir.path().join("Cargo.toml"),
+///         "[package]\nname = \"test\"\nversion = \"1.2.3\"\n",
+///     )
+///     .unwrap();
+/// }
+///
+/// assert_eq!(
+///     rustilities::parsing::suggest_semver_bump(old_dir.path(), new_dir.path()).unwrap(),
+///     BumpKind::Minor
+/// );
+/// assert_eq!(
+///     rustilities::parsing::suggest_semver_bump(new_dir.path(), old_dir.path()).unwrap(),
+///     BumpKind::Major
+/// );
+/// ```
+#[cfg(feature = "manifest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
+pub fn suggest_semver_bump<P: AsRef<Path>, Q: AsRef<Path>>(
+	old_crate_dir: P,
+	new_crate_dir: Q,
+) -> Result<BumpKind, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_suggest_semver_bump(
+		old_crate_dir: &Path,
+		new_crate_dir: &Path,
+	) -> Result<BumpKind, Error> {
+		let version = package_version(old_crate_dir)?;
+		let load_lib = |crate_dir: &Path| {
+			let lib_path = crate_dir.join("src").join("lib.rs");
+			lib_path.is_file().then(|| SourceTree::load(lib_path)).transpose()
+		};
+
+		let kind = match (load_lib(old_crate_dir)?, load_lib(new_crate_dir)?) {
+			(Some(old), Some(new)) => {
+				let diff = public_api_diff(&old, &new);
+				if !diff.removed.is_empty() || !diff.changed.is_empty() {
+					BumpKind::Major
+				} else if !diff.added.is_empty() {
+					BumpKind::Minor
+				} else {
+					BumpKind::Patch
+				}
+			},
+			(Some(_), None) => BumpKind::Major,
+			(None, Some(_)) => BumpKind::Minor,
+			(None, None) => BumpKind::Patch,
+		};
+
+		Ok(match (version.major, version.minor, kind) {
+			(0, 0, _) => BumpKind::Patch,
+			(0, _, BumpKind::Major) => BumpKind::Minor,
+			(0, _, _) => BumpKind::Patch,
+			_ => kind,
+		})
+	}
+	do_suggest_semver_bump(old_crate_dir.as_ref(), new_crate_dir.as_ref())
+}
+
+/// The version of the package living in `crate_dir`, resolving the workspace inheritance.
+#[cfg(feature = "manifest")]
+fn package_version(crate_dir: &Path) -> Result<semver::Version, Error> {
+	use toml_edit::{DocumentMut, Item};
+
+	fn read_version(manifest_path: &Path, table: &[&str]) -> Result<Option<Item>, Error> {
+		let doc = std::fs::read_to_string(manifest_path)?.parse::<DocumentMut>()?;
+		Ok(table
+			.iter()
+			.try_fold(doc.as_item(), |item, key| item.get(key))
+			.and_then(|package| package.get("version"))
+			.cloned())
+	}
+
+	let version = match read_version(&crate_dir.join("Cargo.toml"), &["package"])? {
+		Some(version) if version.get("workspace").and_then(Item::as_bool) == Some(true) =>
+			match crate::manifest::find_workspace_manifest(crate_dir) {
+				Some(workspace_manifest) =>
+					read_version(&workspace_manifest, &["workspace", "package"])?,
+				None => None,
+			},
+		version => version,
+	};
+	let Some(version) = version.as_ref().and_then(Item::as_str) else {
+		return Err(Error::Descriptive("The manifest doesn't declare a version".to_owned()));
+	};
+	semver::Version::parse(version)
+		.map_err(|err| Error::Descriptive(format!("Invalid version {version}: {err}")))
+}
+
+/// The fingerprints of the public items of a tree, by path.
+fn public_items(tree: &SourceTree) -> BTreeMap<String, Vec<u64>> {
+	let mut public_items: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+	for_each_public_module(tree, |module_path, items| {
+		let prefix = module_prefix(module_path);
+		let mut insert = |name: String, fingerprint: u64| {
+			public_items.entry(name).or_default().push(fingerprint);
+		};
+		for item in items {
+			match item {
+				Item::Use(item_use) if is_pub(&item_use.vis) => {
+					let mut bindings = Vec::new();
+					use_bindings(&item_use.tree, &mut Vec::new(), &mut bindings);
+					for (binding, target) in bindings {
+						let target = target.join("::");
+						match binding {
+							Some(binding) => insert(format!("{prefix}::{binding}"), hash(&target)),
+							None => insert(format!("{prefix}::{target}::*"), 0),
+						}
+					}
+				},
+				Item::ForeignMod(item_foreign_mod) =>
+					for item in &item_foreign_mod.items {
+						let (vis, ident) = match item {
+							ForeignItem::Fn(item) => (&item.vis, &item.sig.ident),
+							ForeignItem::Static(item) => (&item.vis, &item.ident),
+							ForeignItem::Type(item) => (&item.vis, &item.ident),
+							_ => continue,
+						};
+						if is_pub(vis) {
+							insert(format!("{prefix}::{}", ident.unraw()), signature_hash(item));
+						}
+					},
+				Item::Impl(item_impl) =>
+					if let Some(fingerprint) = item_fingerprint(item) {
+						insert(format!("{prefix}::{}", impl_label(item_impl)), fingerprint);
+					},
+				Item::Macro(_) =>
+					if let Some(fingerprint) = item_fingerprint(item) &&
+						let Some(name) = item_name(item)
+					{
+						insert(format!("crate::{name}!"), fingerprint);
+					},
+				_ =>
+					if let Some(fingerprint) = item_fingerprint(item) &&
+						let Some(name) = item_name(item)
+					{
+						insert(format!("{prefix}::{name}"), fingerprint);
+					},
+			}
+		}
+	});
+	public_items.values_mut().for_each(|fingerprints| fingerprints.sort_unstable());
+	public_items
+}
+
+/// Calls `f` with the path and the items of every module of the tree reachable through `pub`
+/// modules from the root of the tree, visiting every module after its parent.
+pub(super) fn for_each_public_module(tree: &SourceTree, mut f: impl FnMut(&[String], &[Item])) {
+	fn visit(
+		items: &[Item],
+		module_path: &mut Vec<String>,
+		public_modules: &mut HashSet<Vec<String>>,
+		f: &mut impl FnMut(&[String], &[Item]),
+	) {
+		f(module_path, items);
+		for item in items {
+			if let Item::Mod(item_mod) = item &&
+				is_pub(&item_mod.vis)
+			{
+				module_path.push(item_mod.ident.unraw().to_string());
+				public_modules.insert(module_path.clone());
+				if let Some((_, items)) = &item_mod.content {
+					visit(items, module_path, public_modules, f);
+				}
+				module_path.pop();
+			}
+		}
+	}
+
+	// The files are sorted so every file is visited after the file declaring its module
+	let mut public_modules = HashSet::from([Vec::new()]);
+	for file in tree.files() {
+		if public_modules.contains(&file.module_path) {
+			visit(&file.ast.items, &mut file.module_path.clone(), &mut public_modules, &mut f);
+		}
+	}
+}
+
+/// The path of a module starting by `crate`, such as `crate::foo::bar`.
+pub(super) fn module_prefix(module_path: &[String]) -> String {
+	std::iter::once("crate")
+		.chain(module_path.iter().map(String::as_str))
+		.collect::<Vec<_>>()
+		.join("::")
+}
+
+/// A label identifying an impl block, such as `<impl Display for Foo<T>>`.
+pub(super) fn impl_label(item_impl: &ItemImpl) -> String {
+	let trait_ = item_impl
+		.trait_
+		.as_ref()
+		.map(|(bang, path, _)| {
+			format!("{}{} for ", if bang.is_some() { "!" } else { "" }, compact(path))
+		})
+		.unwrap_or_default();
+	format!("<impl {trait_}{}>", compact(&item_impl.self_ty))
+}
+
+/// The tokens of a node without the spaces added around punctuation by the token stream display.
+fn compact(node: &impl ToTokens) -> String {
+	[(" :: ", "::"), (":: ", "::"), (" <", "<"), ("< ", "<"), (" >", ">"), (" ,", ","), ("& ", "&")]
+		.into_iter()
+		.fold(node.to_token_stream().to_string(), |text, (from, to)| text.replace(from, to))
+}
+
+pub(super) fn is_pub(vis: &Visibility) -> bool {
+	matches!(vis, Visibility::Public(_))
+}
+
+/// The item reduced to its public signature, as described by [`item_fingerprint`], or `None` if
+/// it isn't part of the public API.
+pub(super) fn public_signature(item: &Item) -> Option<Item> {
+	let mut item = item.clone();
+	match &mut item {
+		Item::Const(item) if is_pub(&item.vis) => *item.expr = hidden_expr(),
+		Item::Static(item) if is_pub(&item.vis) => *item.expr = hidden_expr(),
+		Item::Fn(item) if is_pub(&item.vis) => item.block.stmts.clear(),
+		Item::Struct(item) if is_pub(&item.vis) => hide_private_fields(&mut item.fields),
+		Item::Union(item) if is_pub(&item.vis) => {
+			let mut fields = Fields::Named(item.fields.clone());
+			hide_private_fields(&mut fields);
+			let Fields::Named(fields) = fields else {
+				unreachable!("The fields are still named; qed;")
+			};
+			item.fields = fields;
+		},
+		Item::Enum(item) if is_pub(&item.vis) =>
+			item.variants.iter_mut().for_each(|variant| variant.discriminant = None),
+		Item::Trait(item) if is_pub(&item.vis) =>
+			item.items.iter_mut().for_each(|item| match item {
+				TraitItem::Fn(item) if item.default.is_some() => {
+					item.default = None;
+					item.semi_token = Some(Default::default());
+				},
+				TraitItem::Const(item) => item.default = None,
+				_ => (),
+			}),
+		Item::Mod(item) if is_pub(&item.vis) => {
+			item.content = None;
+			item.semi = Some(Default::default());
+		},
+		Item::Type(item) if is_pub(&item.vis) => (),
+		Item::TraitAlias(item) if is_pub(&item.vis) => (),
+		Item::Use(item) if is_pub(&item.vis) => (),
+		Item::ExternCrate(item) if is_pub(&item.vis) => (),
+		Item::Macro(item) if item.attrs.iter().any(|attr| attr.path().is_ident("macro_export")) =>
+			(),
+		Item::Impl(item) => {
+			let inherent = item.trait_.is_none();
+			item.items.retain(|item| match item {
+				ImplItem::Fn(item) => !inherent || is_pub(&item.vis),
+				ImplItem::Const(item) => !inherent || is_pub(&item.vis),
+				ImplItem::Type(item) => !inherent || is_pub(&item.vis),
+				_ => !inherent,
+			});
+			if inherent && item.items.is_empty() {
+				return None;
+			}
+			item.items.iter_mut().for_each(|item| match item {
+				ImplItem::Fn(item) => item.block.stmts.clear(),
+				ImplItem::Const(item) => item.expr = hidden_expr(),
+				_ => (),
+			});
+		},
+		Item::ForeignMod(item) => {
+			item.items.retain(|item| match item {
+				ForeignItem::Fn(item) => is_pub(&item.vis),
+				ForeignItem::Static(item) => is_pub(&item.vis),
+				ForeignItem::Type(item) => is_pub(&item.vis),
+				_ => false,
+			});
+			if item.items.is_empty() {
+				return None;
+			}
+		},
+		_ => return None,
+	}
+	Some(item)
+}
+
+// Hashes the tokens of a node, ignoring the doc comments.
+fn signature_hash(node: &impl ToTokens) -> u64 {
+	let tokens = normalize_attrs(node.to_token_stream(), Equivalence::IgnoreDocs.into());
+	hash(&tokens.to_string())
+}
+
+fn hidden_expr() -> Expr {
+	Expr::Verbatim(TokenStream::new())
+}
+
+// Removes the private fields, leaving a marker if there was any of them. The position of the
+// private fields of tuple structs is kept, as it affects the public ones.
+fn hide_private_fields(fields: &mut Fields) {
+	match fields {
+		Fields::Named(fields) => {
+			let len = fields.named.len();
+			fields.named = std::mem::take(&mut fields.named)
+				.into_iter()
+				.filter(|field| is_pub(&field.vis))
+				.collect();
+			if fields.named.len() < len {
+				fields
+					.named
+					.push(private_field(Some(Ident::new("__private", Span::call_site()))));
+			}
+		},
+		Fields::Unnamed(fields) => fields
+			.unnamed
+			.iter_mut()
+			.filter(|field| !is_pub(&field.vis))
+			.for_each(|field| *field = private_field(None)),
+		Fields::Unit => (),
+	}
+}
+
+fn private_field(ident: Option<Ident>) -> Field {
+	let ty: Type = parse_quote! { () };
+	Field {
+		attrs: Vec::new(),
+		vis: Visibility::Inherited,
+		mutability: syn::FieldMutability::None,
+		colon_token: ident.as_ref().map(|_| Default::default()),
+		ident,
+		ty,
+	}
+}
+
+// Hashes the text with FNV-1a, which is stable across executions and platforms unlike the std
+// hashers. The text of a token stream doesn't depend on the formatting of the source code.
+fn hash(text: &str) -> u64 {
+	const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+	const PRIME: u64 = 0x0000_0100_0000_01b3;
+	text.bytes()
+		.fold(OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
+}
@@ -0,0 +1,347 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+fn assert_same_fingerprint(a: Item, b: Item) {
+	assert!(item_fingerprint(&a).is_some());
+	assert_eq!(item_fingerprint(&a), item_fingerprint(&b));
+}
+
+fn assert_different_fingerprint(a: Item, b: Item) {
+	assert_ne!(item_fingerprint(&a), item_fingerprint(&b));
+}
+
+#[test]
+fn item_fingerprint_ignores_private_items() {
+	let items: Vec<Item> = vec![
+		parse_quote! { fn f() {} },
+		parse_quote! { pub(crate) struct S; },
+		parse_quote! { const C: u8 = 0; },
+		parse_quote! { mod m {} },
+		parse_quote! { use a::b; },
+		parse_quote! { macro_rules! m { () => {} } },
+		parse_quote! { impl S { fn f() {} pub(crate) const C: u8 = 0; } },
+		parse_quote! { extern "C" { fn f(); } },
+	];
+
+	items.iter().for_each(|item| assert!(item_fingerprint(item).is_none()));
+}
+
+#[test]
+fn item_fingerprint_ignores_values_and_bodies() {
+	assert_same_fingerprint(
+		parse_quote! { pub const C: u8 = 1; },
+		parse_quote! { pub const C: u8 = 2; },
+	);
+	assert_same_fingerprint(
+		parse_quote! { pub static mut S: u8 = 1; },
+		parse_quote! { pub static mut S: u8 = 2; },
+	);
+	assert_same_fingerprint(
+		parse_quote! { pub enum E { A = 1, B } },
+		parse_quote! { pub enum E { A = 2, /** Docs. */ B } },
+	);
+	assert_same_fingerprint(
+		parse_quote! { pub trait T { const C: u8 = 1; fn f(&self) { todo!() } } },
+		parse_quote! { pub trait T { const C: u8; fn f(&self); } },
+	);
+	assert_same_fingerprint(
+		parse_quote! { pub mod m { pub fn f() {} } },
+		parse_quote! { pub mod m; },
+	);
+	assert_same_fingerprint(
+		parse_quote! { impl T for S { const C: u8 = 1; fn f(&self) { todo!() } } },
+		parse_quote! { impl T for S { const C: u8 = 2; fn f(&self) {} } },
+	);
+}
+
+#[test]
+fn item_fingerprint_detects_signature_changes() {
+	assert_different_fingerprint(
+		parse_quote! { pub fn f(a: u8) {} },
+		parse_quote! { pub fn f(a: u8, b: u8) {} },
+	);
+	assert_different_fingerprint(
+		parse_quote! { pub fn f() {} },
+		parse_quote! { #[deprecated] pub fn f() {} },
+	);
+	assert_different_fingerprint(
+		parse_quote! { pub enum E { A } },
+		parse_quote! { pub enum E { A, B } },
+	);
+	assert_different_fingerprint(parse_quote! { pub use a::b; }, parse_quote! { pub use a::c; });
+	assert_different_fingerprint(
+		parse_quote! { #[macro_export] macro_rules! m { () => {} } },
+		parse_quote! { #[macro_export] macro_rules! m { ($e:expr) => {} } },
+	);
+}
+
+#[test]
+fn item_fingerprint_only_considers_public_fields() {
+	assert_same_fingerprint(
+		parse_quote! { pub struct S { pub a: u8, b: u8 } },
+		parse_quote! { pub struct S { c: Vec<u8>, pub a: u8 } },
+	);
+	assert_different_fingerprint(
+		parse_quote! { pub struct S { pub a: u8, b: u8 } },
+		parse_quote! { pub struct S { pub a: u8 } },
+	);
+	assert_same_fingerprint(
+		parse_quote! { pub struct S(pub u8, u8); },
+		parse_quote! { pub struct S(pub u8, String); },
+	);
+	assert_different_fingerprint(
+		parse_quote! { pub struct S(pub u8, u8); },
+		parse_quote! { pub struct S(u8, pub u8); },
+	);
+	assert_same_fingerprint(
+		parse_quote! { pub union U { pub a: u8, b: u16 } },
+		parse_quote! { pub union U { pub a: u8, c: u32 } },
+	);
+}
+
+#[test]
+fn item_fingerprint_only_considers_public_items_of_inherent_impls() {
+	assert_same_fingerprint(
+		parse_quote! { impl S { pub fn f(&self) {} fn g() {} } },
+		parse_quote! { impl S { pub fn f(&self) { self.h() } fn h(&self) {} } },
+	);
+	assert_different_fingerprint(
+		parse_quote! { impl S { pub fn f(&self) {} } },
+		parse_quote! { impl S { pub fn f(&mut self) {} } },
+	);
+	assert_same_fingerprint(
+		parse_quote! { extern "C" { pub fn f(); fn g(); } },
+		parse_quote! { extern "C" { pub fn f(); } },
+	);
+}
+
+#[test]
+fn api_fingerprint_ignores_private_modules() {
+	let a: syn::File = parse_quote! {
+		pub struct S;
+		mod private { pub fn f() {} }
+	};
+	let b: syn::File = parse_quote! {
+		pub struct S;
+		mod private { pub fn g() {} }
+	};
+	let c: syn::File = parse_quote! {
+		pub struct S;
+		pub mod private { pub fn g() {} }
+	};
+
+	assert_eq!(api_fingerprint(&a), api_fingerprint(&b));
+	assert_ne!(api_fingerprint(&a), api_fingerprint(&c));
+}
+
+#[test]
+fn hash_is_stable() {
+	assert_eq!(hash(""), 0xcbf2_9ce4_8422_2325);
+	assert_eq!(hash("a"), 0xaf63_dc4c_8601_ec8c);
+}
+
+fn tree_with_files(files: &[(&str, &str)]) -> (tempfile::TempDir, SourceTree) {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	for (path, content) in files {
+		let path = tempdir.path().join(path);
+		std::fs::create_dir_all(path.parent().expect("A file always lives inside a dir; qed"))
+			.expect("This should be created; qed;");
+		std::fs::write(&path, content).expect("The file should be writable; qed;");
+	}
+	let tree = SourceTree::load(tempdir.path().join("lib.rs")).expect("This should be Ok; qed;");
+	(tempdir, tree)
+}
+
+#[test]
+fn public_api_diff_only_considers_reachable_modules() {
+	let (_old_dir, old) = tree_with_files(&[
+		("lib.rs", "mod private; pub mod public; mod inline { pub fn f() {} }"),
+		("private.rs", "pub fn g() {}"),
+		("public.rs", "pub fn h() {} pub(crate) fn i() {}"),
+	]);
+	let (_new_dir, new) = tree_with_files(&[
+		("lib.rs", "mod private; pub mod public;"),
+		("private.rs", "pub fn g2() {}"),
+		("public.rs", "pub fn h() { todo!() } pub fn i() {}"),
+	]);
+
+	assert_eq!(
+		public_api_diff(&old, &new),
+		ApiDiff { added: vec!["crate::public::i".to_owned()], ..Default::default() }
+	);
+}
+
+#[test]
+fn public_api_diff_tracks_reexports_impls_and_macros() {
+	let (_old_dir, old) = tree_with_files(&[(
+		"lib.rs",
+		r#"
+		pub use a::{b, c as d};
+		pub use e::*;
+		pub struct S<T>(T);
+		impl<T> S<T> { pub fn f(&self) {} }
+		impl<T> S<T> { pub fn g(&self) {} }
+		impl<T: Clone> Clone for S<T> { fn clone(&self) -> Self { todo!() } }
+		extern "C" { pub fn ext(); }
+		pub mod m {
+			#[macro_export]
+			macro_rules! mac { () => {} }
+		}
+		"#,
+	)]);
+	let (_new_dir, new) = tree_with_files(&[(
+		"lib.rs",
+		r#"
+		pub use a::{b, x as d};
+		pub use f::*;
+		pub struct S<T>(T);
+		impl<T> S<T> { pub fn f(&self) {} pub fn g(&self) {} }
+		impl<T> core::fmt::Debug for S<T> {}
+		extern "C" { pub fn ext(a: u8); }
+		pub mod m {
+			#[macro_export]
+			macro_rules! mac { ($e:expr) => {} }
+		}
+		"#,
+	)]);
+
+	assert_eq!(
+		public_api_diff(&old, &new),
+		ApiDiff {
+			added: vec![
+				"crate::<impl core::fmt::Debug for S<T>>".to_owned(),
+				"crate::f::*".to_owned()
+			],
+			removed: vec!["crate::<impl Clone for S<T>>".to_owned(), "crate::e::*".to_owned()],
+			changed: vec![
+				"crate::<impl S<T>>".to_owned(),
+				"crate::d".to_owned(),
+				"crate::ext".to_owned(),
+				"crate::mac!".to_owned()
+			],
+		}
+	);
+}
+
+#[test]
+fn public_api_diff_is_empty_for_equivalent_trees() {
+	let (_old_dir, old) = tree_with_files(&[("lib.rs", "/// Docs.\npub fn f() {}\nfn g() {}")]);
+	let (_new_dir, new) = tree_with_files(&[("lib.rs", "pub fn f() { g() }\nfn g() {}")]);
+
+	assert!(public_api_diff(&old, &new).is_empty());
+}
+
+#[cfg(feature = "manifest")]
+fn crate_with_lib(manifest: &str, lib: Option<&str>) -> tempfile::TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::create_dir_all(tempdir.path().join("src")).expect("This should be created; qed;");
+	std::fs::write(tempdir.path().join("Cargo.toml"), manifest)
+		.expect("The file should be writable; qed;");
+	if let Some(lib) = lib {
+		std::fs::write(tempdir.path().join("src/lib.rs"), lib)
+			.expect("The file should be writable; qed;");
+	}
+	tempdir
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn suggest_semver_bump_follows_the_api_diff() {
+	const MANIFEST: &str = "[package]\nname = \"test\"\nversion = \"1.0.0\"\n";
+	let base = crate_with_lib(MANIFEST, Some("pub fn f() {}"));
+
+	[
+		(Some("pub fn f() { todo!() }\nfn g() {}"), BumpKind::Patch),
+		(Some("pub fn f() {}\npub struct S;"), BumpKind::Minor),
+		(Some("pub fn f(a: u8) {}"), BumpKind::Major),
+		(Some(""), BumpKind::Major),
+		(None, BumpKind::Major),
+	]
+	.into_iter()
+	.for_each(|(lib, expected)| {
+		let new = crate_with_lib(MANIFEST, lib);
+		assert_eq!(
+			suggest_semver_bump(base.path(), new.path()).expect("This should be Ok; qed;"),
+			expected
+		);
+	});
+
+	let without_lib = crate_with_lib(MANIFEST, None);
+	assert_eq!(
+		suggest_semver_bump(without_lib.path(), base.path()).expect("This should be Ok; qed;"),
+		BumpKind::Minor
+	);
+	assert_eq!(
+		suggest_semver_bump(without_lib.path(), without_lib.path())
+			.expect("This should be Ok; qed;"),
+		BumpKind::Patch
+	);
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn suggest_semver_bump_handles_pre_1_0_versions() {
+	[
+		("0.3.1", "pub fn f(a: u8) {}", BumpKind::Minor),
+		("0.3.1", "pub fn f() {}\npub fn g() {}", BumpKind::Patch),
+		("0.0.4", "pub fn f(a: u8) {}", BumpKind::Patch),
+	]
+	.into_iter()
+	.for_each(|(version, lib, expected)| {
+		let manifest = format!("[package]\nname = \"test\"\nversion = \"{version}\"\n");
+		let old = crate_with_lib(&manifest, Some("pub fn f() {}"));
+		let new = crate_with_lib(&manifest, Some(lib));
+		assert_eq!(
+			suggest_semver_bump(old.path(), new.path()).expect("This should be Ok; qed;"),
+			expected
+		);
+	});
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn suggest_semver_bump_resolves_inherited_versions() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::write(
+		tempdir.path().join("Cargo.toml"),
+		"[workspace]\nmembers = [\"old\"]\n\n[workspace.package]\nversion = \"0.2.0\"\n",
+	)
+	.expect("The file should be writable; qed;");
+	let old = tempdir.path().join("old");
+	std::fs::create_dir_all(old.join("src")).expect("This should be created; qed;");
+	std::fs::write(old.join("Cargo.toml"), "[package]\nname = \"old\"\nversion.workspace = true\n")
+		.expect("The file should be writable; qed;");
+	std::fs::write(old.join("src/lib.rs"), "pub fn f() {}")
+		.expect("The file should be writable; qed;");
+	let new = crate_with_lib("", Some(""));
+
+	assert_eq!(
+		suggest_semver_bump(&old, new.path()).expect("This should be Ok; qed;"),
+		BumpKind::Minor
+	);
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn suggest_semver_bump_fails_without_valid_version() {
+	[
+		("[package]\nname = \"test\"\n", "The manifest doesn't declare a version"),
+		(
+			"[package]\nname = \"test\"\nversion.workspace = true\n",
+			"The manifest doesn't declare a version",
+		),
+		(
+			"[package]\nname = \"test\"\nversion = \"1.0\"\n",
+			"Invalid version 1.0: unexpected end of input while parsing minor version number",
+		),
+	]
+	.into_iter()
+	.for_each(|(manifest, message)| {
+		let old = crate_with_lib(manifest, None);
+		assert!(matches!(
+			suggest_semver_bump(old.path(), old.path()),
+			Err(Error::Descriptive(msg)) if msg == message
+		));
+	});
+}
@@ -0,0 +1,313 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities analyzing the conditional compilation of a source tree.
+
+#[cfg(test)]
+mod tests;
+
+use super::{
+	api::{impl_label, module_prefix},
+	attrs::Attrs,
+	source_tree::{SourceTree, item_name, use_bindings},
+};
+use crate::Error;
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt::{self, Display},
+	path::Path,
+};
+use syn::{
+	Attribute, Expr, ExprLit, ImplItem, Item, Lit, Meta, Token, TraitItem, ext::IdentExt,
+	punctuated::Punctuated, spanned::Spanned,
+};
+
+/// A `cfg` predicate, such as `all(unix, feature = "std")`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfgPredicate {
+	/// A configuration option, such as `unix` or `feature = "std"`.
+	Option {
+		/// The name of the option.
+		name: String,
+		/// The value of the option, if any.
+		value: Option<String>,
+	},
+	/// `all(...)`, which holds if every predicate holds. It's used for the items without `cfg`
+	/// attributes, as `all()` always holds.
+	All(Vec<CfgPredicate>),
+	/// `any(...)`, which holds if any predicate holds.
+	Any(Vec<CfgPredicate>),
+	/// `not(...)`, which holds if the predicate doesn't hold.
+	Not(Box<CfgPredicate>),
+}
+
+impl CfgPredicate {
+	/// Parses the predicate of a `cfg` attribute, such as the `feature = "std"` in
+	/// `#[cfg(feature = "std")]`.
+	///
+	/// # Errors
+	///
+	/// - If the meta isn't a valid `cfg` predicate.
+	pub fn from_meta(meta: &Meta) -> syn::Result<Self> {
+		let invalid = || syn::Error::new(meta.span(), "expected a cfg predicate");
+		let name = meta.path().get_ident().ok_or_else(invalid)?.unraw().to_string();
+		match meta {
+			Meta::Path(_) => Ok(Self::Option { name, value: None }),
+			Meta::NameValue(meta) => match &meta.value {
+				Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }) =>
+					Ok(Self::Option { name, value: Some(lit.value()) }),
+				_ => Err(invalid()),
+			},
+			Meta::List(list) => {
+				let mut predicates = list
+					.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?
+					.iter()
+					.map(Self::from_meta)
+					.collect::<syn::Result<Vec<_>>>()?;
+				match name.as_str() {
+					"all" => Ok(Self::All(predicates)),
+					"any" => Ok(Self::Any(predicates)),
+					"not" if predicates.len() == 1 => Ok(Self::Not(Box::new(
+						predicates.pop().expect("There's a predicate; qed;"),
+					))),
+					_ => Err(invalid()),
+				}
+			},
+		}
+	}
+
+	/// Evaluates the predicate, given a function telling whether a configuration option is set:
+	/// `Some(true)` if it's set, `Some(false)` if it isn't and `None` if it's unknown. The result
+	/// follows the same convention, so the predicates depending on unknown options can still be
+	/// decided when the known options are enough.
+	///
+	/// # Example
+	///
+	/// ```
+	/// use rustilities::parsing::CfgPredicate;
+	/// use syn::parse_quote;
+	///
+	/// let predicate = CfgPredicate::from_meta(&parse_quote!(all(unix, feature = "std"))).unwrap();
+	/// let std_off =
+	///     |name: &str, value: Option<&str>| (name == "feature" && value == Some("std")).then_some(false);
+	///
+	/// assert_eq!(predicate.evaluate(std_off), Some(false));
+	/// assert_eq!(predicate.evaluate(|_, _| Some(true)), Some(true));
+	/// assert_eq!(predicate.evaluate(|_, _| None), None);
+	/// ```
+	pub fn evaluate(&self, option: impl Fn(&str, Option<&str>) -> Option<bool>) -> Option<bool> {
+		self.evaluate_with(&option)
+	}
+
+	fn evaluate_with(&self, option: &impl Fn(&str, Option<&str>) -> Option<bool>) -> Option<bool> {
+		match self {
+			Self::Option { name, value } => option(name, value.as_deref()),
+			Self::Not(predicate) => predicate.evaluate_with(option).map(|holds| !holds),
+			Self::All(predicates) => predicates
+				.iter()
+				.try_fold(true, |all, predicate| match predicate.evaluate_with(option) {
+					Some(false) => Err(false),
+					Some(true) => Ok(all),
+					None => Ok(false),
+				})
+				.map_or(Some(false), |all| all.then_some(true)),
+			Self::Any(predicates) => predicates
+				.iter()
+				.try_fold(false, |unknown, predicate| match predicate.evaluate_with(option) {
+					Some(true) => Err(true),
+					Some(false) => Ok(unknown),
+					None => Ok(true),
+				})
+				.map_or(Some(true), |unknown| (!unknown).then_some(false)),
+		}
+	}
+
+	/// The conjunction of the predicates, without wrapping a single predicate in `all(...)`.
+	fn and(mut predicates: Vec<CfgPredicate>) -> Self {
+		if predicates.len() == 1 {
+			predicates.pop().expect("There's a predicate; qed;")
+		} else {
+			Self::All(predicates)
+		}
+	}
+}
+
+impl Display for CfgPredicate {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let list = |f: &mut fmt::Formatter<'_>, name: &str, predicates: &[CfgPredicate]| {
+			let predicates: Vec<String> = predicates.iter().map(ToString::to_string).collect();
+			write!(f, "{name}({})", predicates.join(", "))
+		};
+		match self {
+			Self::Option { name, value: None } => write!(f, "{name}"),
+			Self::Option { name, value: Some(value) } => write!(f, "{name} = {value:?}"),
+			Self::All(predicates) => list(f, "all", predicates),
+			Self::Any(predicates) => list(f, "any", predicates),
+			Self::Not(predicate) => write!(f, "not({predicate})"),
+		}
+	}
+}
+
+/// Given a crate dir, this function maps every item of its library to its effective `cfg`
+/// predicate: the conjunction of the `cfg` attributes of the item and its enclosing modules, from
+/// the outermost to the innermost, including the inner `#![cfg(...)]` attributes of module files.
+/// This allows tools to answer questions such as "which code is compiled when feature X is off"
+/// without invoking the compiler, by [evaluating](CfgPredicate::evaluate) the predicates. The
+/// items without `cfg` attributes get `all()`, which always holds.
+///
+/// The items are identified by their paths, such as `crate::m::Name`, including private items,
+/// modules, the items of impls and traits (`crate::m::<impl Trait for S>::f`), the names bound by
+/// `use` declarations and the macros (`crate::m::name!`). A path may be declared several times
+/// under different predicates, such as a function defined once for `unix` and once for `windows`,
+/// so every path maps to the predicates of all its declarations. The library is expected at
+/// `src/lib.rs`, and `cfg_attr` attributes aren't expanded.
+///
+/// # Errors
+///
+/// - If the crate doesn't have a library.
+/// - If the library cannot be loaded.
+/// - If some `cfg` attribute isn't valid.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir_all(tempdir.path().join("src")).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("src/lib.rs"),
+///     "#[cfg(feature = \"std\")]\nmod io {\n    #[cfg(unix)]\n    pub fn read() {}\n}\npub fn f() {}\n",
+/// )
+/// .unwrap();
+///
+/// let map = rustilities::parsing::cfg_map(tempdir.path()).unwrap();
+/// assert_eq!(map["crate::io::read"][0].to_string(), "all(feature = \"std\", unix)");
+/// assert_eq!(map["crate::f"][0].to_string(), "all()");
+///
+/// let std_off =
+///     |name: &str, value: Option<&str>| (name == "feature" && value == Some("std")).then_some(false);
+/// let compiled = map
+///     .iter()
+///     .filter(|(_, predicates)| predicates.iter().any(|predicate| predicate.evaluate(std_off) != Some(false)))
+///     .map(|(path, _)| path.as_str())
+///     .collect::<Vec<_>>();
+/// assert_eq!(compiled, vec!["crate::f"]);
+/// ```
+pub fn cfg_map<P: AsRef<Path>>(crate_dir: P) -> Result<BTreeMap<String, Vec<CfgPredicate>>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_cfg_map(crate_dir: &Path) -> Result<BTreeMap<String, Vec<CfgPredicate>>, Error> {
+		let lib_path = crate_dir.join("src").join("lib.rs");
+		if !lib_path.is_file() {
+			return Err(Error::Descriptive("The crate doesn't have a library".to_owned()));
+		}
+		let tree = SourceTree::load(lib_path)?;
+
+		let mut map = BTreeMap::new();
+		// The files are sorted so every file is visited after the file declaring its module
+		let mut module_cfgs: HashMap<Vec<String>, Vec<CfgPredicate>> = HashMap::new();
+		for file in tree.files() {
+			let mut cfgs = module_cfgs.remove(&file.module_path).unwrap_or_default();
+			cfgs.extend(cfg_predicates(&file.ast.attrs)?);
+			collect_cfgs(
+				&file.ast.items,
+				&mut file.module_path.clone(),
+				&cfgs,
+				&mut module_cfgs,
+				&mut map,
+			)?;
+		}
+		Ok(map)
+	}
+	do_cfg_map(crate_dir.as_ref())
+}
+
+fn collect_cfgs(
+	items: &[Item],
+	module_path: &mut Vec<String>,
+	cfgs: &[CfgPredicate],
+	module_cfgs: &mut HashMap<Vec<String>, Vec<CfgPredicate>>,
+	map: &mut BTreeMap<String, Vec<CfgPredicate>>,
+) -> Result<(), Error> {
+	let prefix = module_prefix(module_path);
+	for item in items {
+		let Some(attrs) = item.attrs() else { continue };
+		let item_cfgs = [cfgs, &cfg_predicates(attrs)?].concat();
+		let mut insert = |path: String, cfgs: Vec<CfgPredicate>| {
+			map.entry(path).or_default().push(CfgPredicate::and(cfgs));
+		};
+		match item {
+			Item::Mod(item_mod) => {
+				insert(format!("{prefix}::{}", item_mod.ident.unraw()), item_cfgs.clone());
+				module_path.push(item_mod.ident.unraw().to_string());
+				match &item_mod.content {
+					Some((_, items)) =>
+						collect_cfgs(items, module_path, &item_cfgs, module_cfgs, map)?,
+					None => {
+						module_cfgs.insert(module_path.clone(), item_cfgs);
+					},
+				}
+				module_path.pop();
+			},
+			Item::Use(item_use) => {
+				let mut bindings = Vec::new();
+				use_bindings(&item_use.tree, &mut Vec::new(), &mut bindings);
+				for (binding, target) in bindings {
+					match binding {
+						Some(binding) => insert(format!("{prefix}::{binding}"), item_cfgs.clone()),
+						None =>
+							insert(format!("{prefix}::{}::*", target.join("::")), item_cfgs.clone()),
+					}
+				}
+			},
+			Item::Impl(item_impl) => {
+				let label = format!("{prefix}::{}", impl_label(item_impl));
+				for impl_item in &item_impl.items {
+					let ident = match impl_item {
+						ImplItem::Fn(impl_item) => &impl_item.sig.ident,
+						ImplItem::Const(impl_item) => &impl_item.ident,
+						ImplItem::Type(impl_item) => &impl_item.ident,
+						_ => continue,
+					};
+					let attrs = impl_item.attrs().expect("The item has attrs; qed;");
+					insert(
+						format!("{label}::{}", ident.unraw()),
+						[item_cfgs.as_slice(), &cfg_predicates(attrs)?].concat(),
+					);
+				}
+				insert(label, item_cfgs);
+			},
+			Item::Trait(item_trait) => {
+				let path = format!("{prefix}::{}", item_trait.ident.unraw());
+				for trait_item in &item_trait.items {
+					let ident = match trait_item {
+						TraitItem::Fn(trait_item) => &trait_item.sig.ident,
+						TraitItem::Const(trait_item) => &trait_item.ident,
+						TraitItem::Type(trait_item) => &trait_item.ident,
+						_ => continue,
+					};
+					let attrs = trait_item.attrs().expect("The item has attrs; qed;");
+					insert(
+						format!("{path}::{}", ident.unraw()),
+						[item_cfgs.as_slice(), &cfg_predicates(attrs)?].concat(),
+					);
+				}
+				insert(path, item_cfgs);
+			},
+			Item::Macro(_) =>
+				if let Some(name) = item_name(item) {
+					insert(format!("{prefix}::{name}!"), item_cfgs);
+				},
+			_ =>
+				if let Some(name) = item_name(item) {
+					insert(format!("{prefix}::{name}"), item_cfgs);
+				},
+		}
+	}
+	Ok(())
+}
+
+fn cfg_predicates(attrs: &[Attribute]) -> Result<Vec<CfgPredicate>, Error> {
+	attrs
+		.iter()
+		.filter(|attr| attr.path().is_ident("cfg"))
+		.map(|attr| Ok(CfgPredicate::from_meta(&attr.parse_args()?)?))
+		.collect()
+}
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use syn::parse_quote;
+
+fn predicate(meta: Meta) -> CfgPredicate {
+	CfgPredicate::from_meta(&meta).expect("This should be Ok; qed;")
+}
+
+fn option(name: &str, value: Option<&str>) -> CfgPredicate {
+	CfgPredicate::Option { name: name.to_owned(), value: value.map(str::to_owned) }
+}
+
+fn crate_with_files(files: &[(&str, &str)]) -> tempfile::TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	for (path, content) in files {
+		let path = tempdir.path().join("src").join(path);
+		std::fs::create_dir_all(path.parent().expect("A file always lives inside a dir; qed"))
+			.expect("This should be created; qed;");
+		std::fs::write(path, content).expect("The file should be writable; qed;");
+	}
+	tempdir
+}
+
+#[test]
+fn from_meta_parses_nested_predicates() {
+	assert_eq!(
+		predicate(parse_quote!(any(unix, all(feature = "std", not(r#test))))),
+		CfgPredicate::Any(vec![
+			option("unix", None),
+			CfgPredicate::All(vec![
+				option("feature", Some("std")),
+				CfgPredicate::Not(Box::new(option("test", None))),
+			]),
+		])
+	);
+	assert_eq!(predicate(parse_quote!(all())), CfgPredicate::All(vec![]));
+}
+
+#[test]
+fn from_meta_fails_on_invalid_predicates() {
+	let metas: Vec<Meta> = vec![
+		parse_quote!(a::b),
+		parse_quote!(feature = 1),
+		parse_quote!(not(a, b)),
+		parse_quote!(not()),
+		parse_quote!(other(a)),
+	];
+
+	metas.iter().for_each(|meta| {
+		assert!(
+			matches!(CfgPredicate::from_meta(meta), Err(err) if err.to_string() == "expected a cfg predicate")
+		)
+	});
+}
+
+#[test]
+fn predicates_display_as_cfg_syntax() {
+	let meta: Meta = parse_quote!(all(unix, any(feature = "a", not(target_os = "linux"))));
+
+	assert_eq!(
+		predicate(meta).to_string(),
+		"all(unix, any(feature = \"a\", not(target_os = \"linux\")))"
+	);
+}
+
+#[test]
+fn evaluate_uses_three_valued_logic() {
+	let a_on_b_off = |name: &str, _: Option<&str>| match name {
+		"a" => Some(true),
+		"b" => Some(false),
+		_ => None,
+	};
+	let cases: Vec<(Meta, Option<bool>)> = vec![
+		(parse_quote!(a), Some(true)),
+		(parse_quote!(c), None),
+		(parse_quote!(not(b)), Some(true)),
+		(parse_quote!(not(c)), None),
+		(parse_quote!(all()), Some(true)),
+		(parse_quote!(all(a, not(b))), Some(true)),
+		(parse_quote!(all(a, c)), None),
+		(parse_quote!(all(c, b)), Some(false)),
+		(parse_quote!(any()), Some(false)),
+		(parse_quote!(any(b, c)), None),
+		(parse_quote!(any(c, a)), Some(true)),
+		(parse_quote!(any(b, not(a))), Some(false)),
+	];
+
+	cases
+		.into_iter()
+		.for_each(|(meta, expected)| assert_eq!(predicate(meta).evaluate(a_on_b_off), expected));
+}
+
+#[test]
+fn cfg_map_accumulates_predicates_from_enclosing_modules() {
+	let tempdir = crate_with_files(&[
+		(
+			"lib.rs",
+			r#"
+			#[cfg(feature = "std")]
+			pub mod io;
+			mod inline {
+				#[cfg(unix)]
+				use std::os::unix::*;
+				#[cfg(test)]
+				macro_rules! m { () => {} }
+			}
+			#[cfg(unix)]
+			fn f() {}
+			#[cfg(windows)]
+			fn f() {}
+			pub struct S;
+			#[cfg(feature = "a")]
+			impl Clone for S {
+				#[cfg(feature = "b")]
+				fn clone(&self) -> Self { S }
+			}
+			pub trait T {
+				#[cfg(feature = "c")]
+				const C: u8;
+			}
+			"#,
+		),
+		("io.rs", "#![cfg(not(miri))]\n\npub use std::io::{Read, Write as W};\n"),
+	]);
+
+	let map = cfg_map(tempdir.path()).expect("This should be Ok; qed;");
+	let map: Vec<(&str, Vec<String>)> = map
+		.iter()
+		.map(|(path, predicates)| {
+			(path.as_str(), predicates.iter().map(ToString::to_string).collect())
+		})
+		.collect();
+
+	assert_eq!(
+		map,
+		vec![
+			("crate::<impl Clone for S>", vec!["feature = \"a\"".to_owned()]),
+			(
+				"crate::<impl Clone for S>::clone",
+				vec!["all(feature = \"a\", feature = \"b\")".to_owned()]
+			),
+			("crate::S", vec!["all()".to_owned()]),
+			("crate::T", vec!["all()".to_owned()]),
+			("crate::T::C", vec!["feature = \"c\"".to_owned()]),
+			("crate::f", vec!["unix".to_owned(), "windows".to_owned()]),
+			("crate::inline", vec!["all()".to_owned()]),
+			("crate::inline::m!", vec!["test".to_owned()]),
+			("crate::inline::std::os::unix::*", vec!["unix".to_owned()]),
+			("crate::io", vec!["feature = \"std\"".to_owned()]),
+			("crate::io::Read", vec!["all(feature = \"std\", not(miri))".to_owned()]),
+			("crate::io::W", vec!["all(feature = \"std\", not(miri))".to_owned()]),
+		]
+	);
+}
+
+#[test]
+fn cfg_map_fails_on_invalid_cfgs() {
+	let tempdir = crate_with_files(&[("lib.rs", "#[cfg(a::b)]\nfn f() {}\n")]);
+
+	assert!(matches!(
+		cfg_map(tempdir.path()),
+		Err(Error::Syn(err)) if err.to_string() == "expected a cfg predicate"
+	));
+}
+
+#[test]
+fn cfg_map_fails_without_library() {
+	let tempdir = crate_with_files(&[("main.rs", "fn main() {}")]);
+
+	assert!(matches!(
+		cfg_map(tempdir.path()),
+		Err(Error::Descriptive(msg)) if msg == "The crate doesn't have a library"
+	));
+}
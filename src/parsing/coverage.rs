@@ -0,0 +1,226 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities measuring the documentation coverage of a crate.
+
+#[cfg(test)]
+mod tests;
+
+use super::{
+	api::{for_each_public_module, is_pub, module_prefix, public_signature},
+	attrs::Attrs,
+	source_tree::{SourceTree, item_name},
+};
+use crate::{
+	Error,
+	diagnostic::{Diagnostic, Severity, ToDiagnostics},
+};
+use std::path::Path;
+use syn::{Attribute, ImplItem, Item, Meta, ext::IdentExt};
+
+/// The documentation coverage of a module, as reported by [`doc_coverage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleCoverage {
+	/// The path of the module, starting by `crate`.
+	pub module: String,
+	/// The number of documented public items of the module.
+	pub documented: usize,
+	/// The number of public items of the module.
+	pub total: usize,
+}
+
+impl ModuleCoverage {
+	/// The percentage of documented public items, 100 if the module doesn't have public items.
+	pub fn percentage(&self) -> f64 {
+		percentage(self.documented, self.total)
+	}
+}
+
+/// The documentation coverage of a crate, as computed by [`doc_coverage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocCoverageReport {
+	/// The coverage of every public module, sorted by path.
+	pub modules: Vec<ModuleCoverage>,
+	/// The paths of the public items lacking doc comments, sorted.
+	pub missing: Vec<String>,
+}
+
+impl DocCoverageReport {
+	/// The percentage of documented public items in the whole crate, 100 if the crate doesn't have
+	/// public items.
+	pub fn percentage(&self) -> f64 {
+		percentage(
+			self.modules.iter().map(|module| module.documented).sum(),
+			self.modules.iter().map(|module| module.total).sum(),
+		)
+	}
+}
+
+impl ToDiagnostics for DocCoverageReport {
+	/// A `docs::missing` warning per public item lacking doc comments.
+	fn to_diagnostics(&self) -> Vec<Diagnostic> {
+		self.missing
+			.iter()
+			.map(|item| {
+				Diagnostic::new(
+					Severity::Warning,
+					"docs::missing",
+					format!("`{item}` lacks doc comments"),
+				)
+			})
+			.collect()
+	}
+}
+
+/// Given a crate dir, this function reports the public items of its library lacking doc comments,
+/// together with the coverage of every public module. This allows gating CI on documentation
+/// coverage without relying on nightly rustdoc flags.
+///
+/// The public items are the named `pub` items living in a module reachable through `pub` modules
+/// from the crate root, the `pub` items of their inherent impls, exported macros and the crate
+/// root itself, which is counted in its own module. Items hidden with `#[doc(hidden)]` are
+/// skipped, as are the items of hidden modules. A module is documented by an outer doc comment on
+/// its declaration or by an inner doc comment in its file. The library is expected at
+/// `src/lib.rs`.
+///
+/// # Errors
+///
+/// - If the crate doesn't have a library.
+/// - If the library cannot be loaded.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir_all(tempdir.path().join("src")).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("src/lib.rs"),
+///     "//! Crate docs.\n\n/// Docs.\npub struct S;\n\nimpl S {\n    pub fn new() -> Self { S }\n}\n",
+/// )
+/// .unwrap();
+///
+/// let report = rustilities::parsing::doc_coverage(tempdir.path()).unwrap();
+/// assert_eq!(report.missing, vec!["crate::S::new"]);
+/// assert!((report.percentage() - 200.0 / 3.0).abs() < 1e-9);
+/// ```
+pub fn doc_coverage<P: AsRef<Path>>(crate_dir: P) -> Result<DocCoverageReport, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_doc_coverage(crate_dir: &Path) -> Result<DocCoverageReport, Error> {
+		let lib_path = crate_dir.join("src").join("lib.rs");
+		if !lib_path.is_file() {
+			return Err(Error::Descriptive("The crate doesn't have a library".to_owned()));
+		}
+		let tree = SourceTree::load(lib_path)?;
+
+		let mut modules = Vec::new();
+		let mut missing = Vec::new();
+		let mut hidden_modules: Vec<Vec<String>> = Vec::new();
+		for_each_public_module(&tree, |module_path, items| {
+			if hidden_modules.iter().any(|hidden| module_path.starts_with(hidden)) {
+				return;
+			}
+			let prefix = module_prefix(module_path);
+			let mut coverage = ModuleCoverage { module: prefix.clone(), documented: 0, total: 0 };
+			let mut count = |path: String, attrs: &[Attribute]| {
+				coverage.total += 1;
+				if has_docs(attrs) {
+					coverage.documented += 1;
+				} else {
+					missing.push(path);
+				}
+			};
+
+			if module_path.is_empty() {
+				count(prefix.clone(), &tree.files()[0].ast.attrs);
+			}
+			for item in items {
+				let Some(attrs) = item.attrs() else { continue };
+				if is_hidden(attrs) {
+					if let Item::Mod(item_mod) = item {
+						hidden_modules
+							.push([module_path, &[item_mod.ident.unraw().to_string()]].concat());
+					}
+					continue;
+				}
+				match item {
+					Item::Mod(item_mod) if is_pub(&item_mod.vis) => {
+						let child_path =
+							[module_path, &[item_mod.ident.unraw().to_string()]].concat();
+						let file_attrs = tree
+							.files()
+							.iter()
+							.find(|file| file.module_path == child_path)
+							.map(|file| file.ast.attrs.as_slice())
+							.unwrap_or_default();
+						count(
+							format!("{prefix}::{}", item_mod.ident.unraw()),
+							&[attrs.as_slice(), file_attrs].concat(),
+						);
+					},
+					Item::Macro(_)
+						if attrs.iter().any(|attr| attr.path().is_ident("macro_export")) =>
+						if let Some(name) = item_name(item) {
+							count(format!("crate::{name}!"), attrs);
+						},
+					Item::Impl(item_impl) if item_impl.trait_.is_none() => {
+						let Some(self_name) = self_type_name(item_impl) else { continue };
+						for impl_item in &item_impl.items {
+							let (vis, ident) = match impl_item {
+								ImplItem::Fn(impl_item) => (&impl_item.vis, &impl_item.sig.ident),
+								ImplItem::Const(impl_item) => (&impl_item.vis, &impl_item.ident),
+								ImplItem::Type(impl_item) => (&impl_item.vis, &impl_item.ident),
+								_ => continue,
+							};
+							let impl_item_attrs =
+								impl_item.attrs().expect("The item has attrs; qed;");
+							if is_pub(vis) && !is_hidden(impl_item_attrs) {
+								count(
+									format!("{prefix}::{self_name}::{}", ident.unraw()),
+									impl_item_attrs,
+								);
+							}
+						}
+					},
+					Item::Use(_) | Item::ExternCrate(_) | Item::ForeignMod(_) => (),
+					_ if public_signature(item).is_some() =>
+						if let Some(name) = item_name(item) {
+							count(format!("{prefix}::{name}"), attrs);
+						},
+					_ => (),
+				}
+			}
+			modules.push(coverage);
+		});
+
+		modules.sort_by(|a, b| a.module.cmp(&b.module));
+		missing.sort();
+		Ok(DocCoverageReport { modules, missing })
+	}
+	do_doc_coverage(crate_dir.as_ref())
+}
+
+fn percentage(documented: usize, total: usize) -> f64 {
+	if total == 0 { 100.0 } else { documented as f64 * 100.0 / total as f64 }
+}
+
+fn has_docs(attrs: &[Attribute]) -> bool {
+	attrs
+		.iter()
+		.any(|attr| matches!(&attr.meta, Meta::NameValue(meta) if meta.path.is_ident("doc")))
+}
+
+fn is_hidden(attrs: &[Attribute]) -> bool {
+	attrs.iter().any(|attr| {
+		matches!(&attr.meta, Meta::List(list) if list.path.is_ident("doc") && list.tokens.to_string() == "hidden")
+	})
+}
+
+/// The name of the type an inherent impl is for, such as `Foo` for `impl<T> Foo<T>`.
+fn self_type_name(item_impl: &syn::ItemImpl) -> Option<String> {
+	match &*item_impl.self_ty {
+		syn::Type::Path(ty) if ty.qself.is_none() =>
+			ty.path.segments.last().map(|segment| segment.ident.unraw().to_string()),
+		_ => None,
+	}
+}
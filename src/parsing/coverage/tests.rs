@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+fn crate_with_files(files: &[(&str, &str)]) -> tempfile::TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	for (path, content) in files {
+		let path = tempdir.path().join("src").join(path);
+		std::fs::create_dir_all(path.parent().expect("The path has a parent; qed;"))
+			.expect("This should be created; qed;");
+		std::fs::write(path, content).expect("This should be written; qed;");
+	}
+	tempdir
+}
+
+#[test]
+fn doc_coverage_reports_undocumented_public_items() {
+	let tempdir = crate_with_files(&[(
+		"lib.rs",
+		r#"
+			//! Crate docs.
+
+			/// Docs.
+			pub struct Documented;
+			pub enum Undocumented { A }
+			struct Private;
+			pub(crate) fn internal() {}
+			pub use other::Thing;
+
+			/// Docs.
+			#[macro_export]
+			macro_rules! documented { () => {} }
+			#[macro_export]
+			macro_rules! undocumented { () => {} }
+			macro_rules! local { () => {} }
+
+			impl Documented {
+				/// Docs.
+				pub fn new() -> Self { Documented }
+				pub const ZERO: u8 = 0;
+				fn private() {}
+			}
+
+			impl Default for Documented {
+				fn default() -> Self { Documented }
+			}
+			"#,
+	)]);
+
+	let report = doc_coverage(tempdir.path()).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		report.modules,
+		vec![ModuleCoverage { module: "crate".to_owned(), documented: 4, total: 7 }]
+	);
+	assert_eq!(
+		report.missing,
+		vec!["crate::Documented::ZERO", "crate::Undocumented", "crate::undocumented!"]
+	);
+}
+
+#[test]
+fn doc_coverage_reports_every_public_module() {
+	let tempdir = crate_with_files(&[
+		(
+			"lib.rs",
+			r#"
+			pub mod file;
+			mod private;
+			/// Docs.
+			pub mod inline {
+				pub fn f() {}
+				/// Docs.
+				pub mod nested { }
+			}
+			"#,
+		),
+		("file.rs", "//! Docs.\n\n/// Docs.\npub struct S;\n"),
+		("private.rs", "pub struct Hidden;\n"),
+	]);
+
+	let report = doc_coverage(tempdir.path()).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		report.modules,
+		vec![
+			ModuleCoverage { module: "crate".to_owned(), documented: 2, total: 3 },
+			ModuleCoverage { module: "crate::file".to_owned(), documented: 1, total: 1 },
+			ModuleCoverage { module: "crate::inline".to_owned(), documented: 1, total: 2 },
+			ModuleCoverage { module: "crate::inline::nested".to_owned(), documented: 0, total: 0 },
+		]
+	);
+	assert_eq!(report.missing, vec!["crate", "crate::inline::f"]);
+	assert_eq!(report.modules[3].percentage(), 100.0);
+	assert_eq!(report.percentage(), 400.0 / 6.0);
+}
+
+#[test]
+fn doc_coverage_skips_hidden_items() {
+	let tempdir = crate_with_files(&[
+		(
+			"lib.rs",
+			r#"
+			//! Crate docs.
+
+			#[doc(hidden)]
+			pub mod hidden;
+			#[doc(hidden)]
+			pub fn hidden() {}
+			pub struct S;
+			impl S {
+				#[doc(hidden)]
+				pub fn hidden() {}
+			}
+			"#,
+		),
+		("hidden.rs", "pub struct Hidden;\npub mod nested { pub fn f() {} }\n"),
+	]);
+
+	let report = doc_coverage(tempdir.path()).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		report.modules,
+		vec![ModuleCoverage { module: "crate".to_owned(), documented: 1, total: 2 }]
+	);
+	assert_eq!(report.missing, vec!["crate::S"]);
+}
+
+#[test]
+fn doc_coverage_fails_without_library() {
+	let tempdir = crate_with_files(&[("main.rs", "fn main() {}")]);
+
+	assert!(matches!(
+		doc_coverage(tempdir.path()),
+		Err(Error::Descriptive(msg)) if msg == "The crate doesn't have a library"
+	));
+}
@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities generating impls that delegate a trait to a field of a type, as done by newtype
+// delegation macros.
+
+#[cfg(test)]
+mod tests;
+
+use super::extract_generics;
+use proc_macro2::{Span, TokenStream};
+use quote::{ToTokens, format_ident, quote};
+use syn::{
+	Attribute, FnArg, GenericParam, ImplItem, ItemImpl, ItemTrait, Member, Pat, PatIdent,
+	Signature, TraitItem, TraitItemConst, TraitItemFn, TraitItemType, Type, TypeImplTrait,
+	parse_quote,
+	visit::{self, Visit},
+};
+
+/// Given a trait definition, a type, one of its fields and the type of that field, this function
+/// generates an impl of the trait for the type forwarding every trait item to the field:
+/// - Methods call the trait method on `self.field`, passing it by value, shared or mutable
+///   reference as the receiver of the method does. Async methods await the call.
+/// - Associated consts and types are set to the ones of the field type.
+///
+/// The impl declares the generics of the trait and requires the field type to implement the trait
+/// in its where clause. If the type has generics, they have to be added to the returned impl. The
+/// `cfg` attributes of the trait items are kept in the delegated items, while the other attributes
+/// and the default bodies are dropped. Macro invocations in the trait are ignored.
+///
+/// # Errors
+///
+/// The function returns a [`syn::Error`] pointing to the offending method if the trait has:
+/// - A method without receiver.
+/// - A method whose receiver isn't `self`, `&self` or `&mut self` (eg, `self: Box<Self>`).
+/// - A method mentioning `Self` in its arguments or return type, as the field type cannot stand in
+///   for it.
+///
+/// # Example
+///
+/// ```
+/// use syn::{ItemImpl, ItemTrait, Member, Type, parse_quote};
+///
+/// let trait_def: ItemTrait = parse_quote! {
+///     trait Storage<K> {
+///         const CAPACITY: usize;
+///         type Value;
+///
+///         fn get(&self, key: &K) -> Option<&Self::Value>;
+///         fn insert(&mut self, key: K, value: Self::Value) {}
+///     }
+/// };
+/// let self_ty: Type = parse_quote! { Cache };
+/// let field: Member = parse_quote! { inner };
+/// let field_ty: Type = parse_quote! { MemoryStorage };
+///
+/// let expected: ItemImpl = parse_quote! {
+///     impl<K> Storage<K> for Cache where MemoryStorage: Storage<K> {
+///         const CAPACITY: usize = <MemoryStorage as Storage<K>>::CAPACITY;
+///         type Value = <MemoryStorage as Storage<K>>::Value;
+///
+///         fn get(&self, key: &K) -> Option<&Self::Value> {
+///             <MemoryStorage as Storage<K>>::get(&self.inner, key)
+///         }
+///         fn insert(&mut self, key: K, value: Self::Value) {
+///             <MemoryStorage as Storage<K>>::insert(&mut self.inner, key, value)
+///         }
+///     }
+/// };
+///
+/// assert_eq!(
+///     rustilities::parsing::delegate_impl(&trait_def, &self_ty, &field, &field_ty).unwrap(),
+///     expected
+/// );
+/// ```
+pub fn delegate_impl(
+	trait_def: &ItemTrait,
+	self_ty: &Type,
+	field: &Member,
+	field_ty: &Type,
+) -> syn::Result<ItemImpl> {
+	let (declarations, idents, where_clause) = extract_generics(&trait_def.generics);
+	let trait_ident = &trait_def.ident;
+	let trait_path = if idents.is_empty() {
+		quote! { #trait_ident }
+	} else {
+		quote! { #trait_ident<#idents> }
+	};
+	let impl_generics = (!declarations.is_empty()).then(|| quote! { <#declarations> });
+	let mut where_clause = where_clause.unwrap_or_else(|| parse_quote! { where });
+	where_clause.predicates.push(parse_quote! { #field_ty: #trait_path });
+
+	let delegated_ty = quote! { <#field_ty as #trait_path> };
+	let items = trait_def
+		.items
+		.iter()
+		.filter_map(|item| match item {
+			TraitItem::Const(item) => Some(Ok(delegate_const(item, &delegated_ty))),
+			TraitItem::Type(item) => Some(Ok(delegate_type(item, &delegated_ty))),
+			TraitItem::Fn(item) => Some(delegate_fn(item, &delegated_ty, field)),
+			_ => None,
+		})
+		.collect::<syn::Result<Vec<ImplItem>>>()?;
+
+	let unsafety = &trait_def.unsafety;
+	Ok(parse_quote! {
+		#unsafety impl #impl_generics #trait_path for #self_ty #where_clause {
+			#(#items)*
+		}
+	})
+}
+
+fn cfg_attrs(attrs: &[Attribute]) -> Vec<&Attribute> {
+	attrs.iter().filter(|attr| attr.path().is_ident("cfg")).collect()
+}
+
+fn delegate_const(item: &TraitItemConst, delegated_ty: &TokenStream) -> ImplItem {
+	let attrs = cfg_attrs(&item.attrs);
+	let TraitItemConst { ident, ty, .. } = item;
+	parse_quote! { #(#attrs)* const #ident: #ty = #delegated_ty::#ident; }
+}
+
+fn delegate_type(item: &TraitItemType, delegated_ty: &TokenStream) -> ImplItem {
+	let attrs = cfg_attrs(&item.attrs);
+	let TraitItemType { ident, generics, .. } = item;
+	let (_, idents, _) = extract_generics(generics);
+	let args = (!idents.is_empty()).then(|| quote! { <#idents> });
+	let where_clause = &generics.where_clause;
+	parse_quote! { #(#attrs)* type #ident #generics = #delegated_ty::#ident #args #where_clause; }
+}
+
+fn delegate_fn(
+	item: &TraitItemFn,
+	delegated_ty: &TokenStream,
+	field: &Member,
+) -> syn::Result<ImplItem> {
+	let mut sig = item.sig.clone();
+	let receiver = sig.receiver().ok_or_else(|| {
+		syn::Error::new_spanned(&sig, "expected a method with a receiver to delegate it")
+	})?;
+	let receiver = match &*receiver.ty {
+		Type::Path(ty) if ty.path.is_ident("Self") => quote! { self.#field },
+		Type::Reference(ty) if matches!(&*ty.elem, Type::Path(elem) if elem.path.is_ident("Self")) =>
+		{
+			let mutability = &ty.mutability;
+			quote! { &#mutability self.#field }
+		},
+		_ =>
+			return Err(syn::Error::new_spanned(
+				receiver,
+				"expected `self`, `&self` or `&mut self` to delegate the method",
+			)),
+	};
+
+	// The receiver is only forwarded, so it doesn't need to be mutable.
+	if let Some(FnArg::Receiver(receiver)) = sig.inputs.first_mut() &&
+		receiver.reference.is_none()
+	{
+		receiver.mutability = None;
+	}
+
+	let mut scan = SignatureScan::default();
+	sig.inputs.iter().skip(1).for_each(|input| scan.visit_fn_arg(input));
+	scan.visit_return_type(&sig.output);
+	if scan.mentions_self {
+		return Err(syn::Error::new_spanned(
+			&sig,
+			"expected a method not mentioning `Self` to delegate it",
+		));
+	}
+
+	let args = std::iter::once(receiver)
+		.chain(sig.inputs.iter_mut().enumerate().filter_map(|(index, input)| match input {
+			FnArg::Typed(input) => Some(forwarded_arg(&mut input.pat, index)),
+			FnArg::Receiver(_) => None,
+		}))
+		.collect::<Vec<_>>();
+	let turbofish = turbofish(&sig, scan.impl_trait);
+
+	let method = &sig.ident;
+	let await_call = sig.asyncness.map(|_| quote! { .await });
+	let attrs = cfg_attrs(&item.attrs);
+	Ok(parse_quote! {
+		#(#attrs)*
+		#sig {
+			#delegated_ty::#method #turbofish(#(#args),*) #await_call
+		}
+	})
+}
+
+// Turns the pattern of an argument into a plain ident, which is returned to forward the argument.
+fn forwarded_arg(pat: &mut Pat, index: usize) -> TokenStream {
+	let ident = match pat {
+		Pat::Ident(PatIdent { ident, subpat: None, .. }) => ident.clone(),
+		_ => format_ident!("arg{}", index, span = Span::call_site()),
+	};
+	*pat = parse_quote! { #ident };
+	ident.into_token_stream()
+}
+
+// The explicit generic args of the call, needed if the generic params of the method cannot be
+// inferred. Lifetimes cannot be explicitly passed if they're late bound, and no generic args can
+// be if the method takes an `impl Trait` argument, so they're skipped in those cases.
+fn turbofish(sig: &Signature, impl_trait: bool) -> Option<TokenStream> {
+	let args: Vec<_> = sig
+		.generics
+		.params
+		.iter()
+		.filter_map(|param| match param {
+			GenericParam::Type(param) => Some(&param.ident),
+			GenericParam::Const(param) => Some(&param.ident),
+			GenericParam::Lifetime(_) => None,
+		})
+		.collect();
+	(!args.is_empty() && !impl_trait).then(|| quote! { ::<#(#args),*> })
+}
+
+#[derive(Default)]
+struct SignatureScan {
+	mentions_self: bool,
+	impl_trait: bool,
+}
+
+impl Visit<'_> for SignatureScan {
+	fn visit_type(&mut self, ty: &Type) {
+		self.mentions_self |=
+			matches!(ty, Type::Path(ty) if ty.qself.is_none() && ty.path.is_ident("Self"));
+		visit::visit_type(self, ty);
+	}
+
+	fn visit_type_impl_trait(&mut self, ty: &TypeImplTrait) {
+		self.impl_trait = true;
+		visit::visit_type_impl_trait(self, ty);
+	}
+}
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+#[test]
+fn delegate_impl_forwards_receivers() {
+	let trait_def: ItemTrait = parse_quote! {
+		pub unsafe trait Handle {
+			fn by_ref(&self) -> u8;
+			fn by_mut(&mut self, (a, b): (u8, u8), mut c: u8);
+			fn by_value(mut self) -> u8;
+			fn by_typed_ref(self: &Self, _: bool);
+		}
+	};
+	let expected: ItemImpl = parse_quote! {
+		unsafe impl Handle for Wrapper where Inner: Handle {
+			fn by_ref(&self) -> u8 {
+				<Inner as Handle>::by_ref(&self.inner)
+			}
+			fn by_mut(&mut self, arg1: (u8, u8), c: u8) {
+				<Inner as Handle>::by_mut(&mut self.inner, arg1, c)
+			}
+			fn by_value(self) -> u8 {
+				<Inner as Handle>::by_value(self.inner)
+			}
+			fn by_typed_ref(self: &Self, arg1: bool) {
+				<Inner as Handle>::by_typed_ref(&self.inner, arg1)
+			}
+		}
+	};
+
+	assert_eq!(
+		delegate_impl(
+			&trait_def,
+			&parse_quote! { Wrapper },
+			&parse_quote! { inner },
+			&parse_quote! { Inner }
+		)
+		.expect("This should be Ok; qed;"),
+		expected
+	);
+}
+
+#[test]
+fn delegate_impl_handles_generics_and_async() {
+	let trait_def: ItemTrait = parse_quote! {
+		trait Fetch<'a, T: Clone, const N: usize> where T: 'a {
+			#[cfg(feature = "std")]
+			type Output<'b>: Iterator<Item = &'b T> where Self: 'b;
+
+			/// Docs.
+			#[inline]
+			async fn fetch<U: From<T>>(&self, keys: [&'a T; N]) -> U;
+			fn each(&self, f: impl Fn(&T));
+		}
+	};
+	let expected: ItemImpl = parse_quote! {
+		impl<'a, T, const N: usize> Fetch<'a, T, N> for Client<T>
+		where
+			T: 'a,
+			T: Clone,
+			Backend<T>: Fetch<'a, T, N>
+		{
+			#[cfg(feature = "std")]
+			type Output<'b> = <Backend<T> as Fetch<'a, T, N>>::Output<'b> where Self: 'b;
+
+			async fn fetch<U: From<T>>(&self, keys: [&'a T; N]) -> U {
+				<Backend<T> as Fetch<'a, T, N>>::fetch::<U>(&self.backend, keys).await
+			}
+			fn each(&self, f: impl Fn(&T)) {
+				<Backend<T> as Fetch<'a, T, N>>::each(&self.backend, f)
+			}
+		}
+	};
+
+	assert_eq!(
+		delegate_impl(
+			&trait_def,
+			&parse_quote! { Client<T> },
+			&parse_quote! { backend },
+			&parse_quote! { Backend<T> }
+		)
+		.expect("This should be Ok; qed;"),
+		expected
+	);
+}
+
+#[test]
+fn delegate_impl_fails_on_methods_that_cannot_be_forwarded() {
+	[
+		(
+			quote! { trait T { fn new() -> u8; } },
+			"expected a method with a receiver to delegate it",
+		),
+		(
+			quote! { trait T { fn boxed(self: Box<Self>); } },
+			"expected `self`, `&self` or `&mut self` to delegate the method",
+		),
+		(
+			quote! { trait T { fn duplicate(&self) -> Self; } },
+			"expected a method not mentioning `Self` to delegate it",
+		),
+		(
+			quote! { trait T { fn merge(&mut self, other: &Self); } },
+			"expected a method not mentioning `Self` to delegate it",
+		),
+	]
+	.into_iter()
+	.for_each(|(trait_def, message)| {
+		let trait_def: ItemTrait = syn::parse2(trait_def).expect("This should be Ok; qed;");
+		assert_eq!(
+			delegate_impl(
+				&trait_def,
+				&parse_quote! { W },
+				&parse_quote! { 0 },
+				&parse_quote! { I }
+			)
+			.expect_err("This should be Err; qed;")
+			.to_string(),
+			message
+		);
+	});
+}
+
+#[test]
+fn delegate_impl_allows_associated_paths_of_self() {
+	let trait_def: ItemTrait = parse_quote! {
+		trait Source {
+			type Item;
+			fn next(&mut self) -> Option<Self::Item>;
+		}
+	};
+
+	assert!(
+		delegate_impl(&trait_def, &parse_quote! { W }, &parse_quote! { 0 }, &parse_quote! { I })
+			.is_ok()
+	);
+}
@@ -0,0 +1,23 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! Re-exports of the parsing crates used by this module. Macro crates built on top of
+//! [`parsing`](crate::parsing) can use them instead of depending on [`syn`], [`proc_macro2`] and
+//! [`quote`] directly, which guarantees that the types they pass to the helpers of this crate come
+//! from the same versions of those crates.
+//!
+//! ```
+//! use rustilities::parsing::deps::{proc_macro2::TokenStream, quote::quote, syn::Item};
+//!
+//! let tokens: TokenStream = quote! { struct S; };
+//! let item: Item = rustilities::parsing::deps::syn::parse2(tokens).unwrap();
+//!
+//! assert!(rustilities::parsing::items_equivalent(
+//!     &item,
+//!     &rustilities::parsing::deps::syn::parse_quote! { struct S; },
+//!     rustilities::parsing::Equivalences::default()
+//! ));
+//! ```
+
+pub use proc_macro2;
+pub use quote;
+pub use syn;
@@ -0,0 +1,401 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities editing Rust source files in place. The edits are applied to the raw text of the
+// files, using the spans of the parsed items to locate them, so the formatting and comments of the
+// rest of the file are kept.
+
+#[cfg(test)]
+mod tests;
+
+use super::use_tree::{flatten_use_tree, merge_use_trees, render_use_tree};
+use crate::{Error, macros::debug};
+use proc_macro2::Ident;
+use std::{
+	collections::HashSet,
+	path::{Path, PathBuf},
+};
+use syn::{Item, ItemMod, UseTree, Visibility, ext::IdentExt, spanned::Spanned};
+
+/// Given the path to a Rust file, a module name and a visibility, this function declares the module
+/// in the file (eg, `pub mod name;`) and creates the module file if it doesn't exist yet, returning
+/// its path.
+///
+/// The declaration is inserted in sorted position among the module declarations of the file, or
+/// before its first item if it doesn't declare any module. If the module is already declared, the
+/// file is left untouched.
+///
+/// The module file is created next to the given file if it's a `lib.rs`, `main.rs` or `mod.rs`
+/// file, or in a dir named after it otherwise. Existing `name.rs` and `name/mod.rs` files are
+/// reused.
+///
+/// # Errors
+///
+/// - If the module name isn't a valid identifier.
+/// - If the file cannot be read or parsed.
+/// - If the module is already declared inline in the file.
+/// - If the file cannot be overwritten or the module file cannot be created.
+///
+/// # Examples
+///
+/// ```
+/// use syn::parse_quote;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let lib_path = tempdir.path().join("lib.rs");
+/// std::fs::write(&lib_path, "mod a;\nmod c;\n\nfn f() {}\n").unwrap();
+///
+/// let module_path =
+///     rustilities::parsing::add_mod_declaration(&lib_path, "b", &parse_quote!(pub)).unwrap();
+///
+/// assert_eq!(module_path, tempdir.path().join("b.rs"));
+/// assert!(module_path.is_file());
+/// assert_eq!(
+///     std::fs::read_to_string(&lib_path).unwrap(),
+///     "mod a;\npub mod b;\nmod c;\n\nfn f() {}\n"
+/// );
+/// ```
+pub fn add_mod_declaration<P: AsRef<Path>>(
+	parent_file_path: P,
+	mod_name: &str,
+	visibility: &Visibility,
+) -> Result<PathBuf, Error> {
+	insert_mod_declaration(parent_file_path.as_ref(), mod_name, visibility, &[])
+}
+
+/// Given the path to a crate dir, a module name and a feature name, this function creates a public
+/// module only compiled if the feature is enabled, as this crate does with its own modules:
+/// - The module is declared in the `src/lib.rs` file of the crate, as [`add_mod_declaration`] does,
+///   behind a `#[cfg(feature = "...")]` attribute and documented as such on docs.rs with a
+///   `#[cfg_attr(docsrs, doc(cfg(feature = "...")))]` attribute.
+/// - The feature is added to the manifest of the crate, as
+///   [`add_feature`](crate::manifest::add_feature) does.
+///
+/// The path to the module file is returned. If the module is already declared, its declaration
+/// is left untouched.
+///
+/// # Errors
+///
+/// - If the module cannot be declared, see [`add_mod_declaration`].
+/// - If the feature cannot be added, see [`add_feature`](crate::manifest::add_feature).
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir_all(tempdir.path().join("src")).unwrap();
+/// std::fs::write(tempdir.path().join("Cargo.toml"), "[package]\nname = \"test\"\n").unwrap();
+/// std::fs::write(tempdir.path().join("src/lib.rs"), "pub mod a;\n").unwrap();
+///
+/// let module_path =
+///     rustilities::parsing::add_feature_gated_module(tempdir.path(), "b", "b-support").unwrap();
+///
+/// assert_eq!(module_path, tempdir.path().join("src/b.rs"));
+/// assert_eq!(
+///     std::fs::read_to_string(tempdir.path().join("src/lib.rs")).unwrap(),
+///     r#"pub mod a;
+///
+/// #[cfg(feature = "b-support")]
+/// #[cfg_attr(docsrs, doc(cfg(feature = "b-support")))]
+/// pub mod b;
+/// "#
+/// );
+/// assert_eq!(
+///     std::fs::read_to_string(tempdir.path().join("Cargo.toml")).unwrap(),
+///     "[package]\nname = \"test\"\n\n[features]\nb-support = []\n"
+/// );
+/// ```
+#[cfg(feature = "manifest")]
+#[cfg_attr(
+	feature = "tracing",
+	tracing::instrument(level = "debug", skip(crate_dir), fields(crate_dir = %crate_dir.as_ref().display()))
+)]
+pub fn add_feature_gated_module<P: AsRef<Path>>(
+	crate_dir: P,
+	mod_name: &str,
+	feature_name: &str,
+) -> Result<PathBuf, Error> {
+	let crate_dir = crate_dir.as_ref();
+	let module_file = insert_mod_declaration(
+		&crate_dir.join("src").join("lib.rs"),
+		mod_name,
+		&Visibility::Public(Default::default()),
+		&[
+			format!("#[cfg(feature = {feature_name:?})]"),
+			format!("#[cfg_attr(docsrs, doc(cfg(feature = {feature_name:?})))]"),
+		],
+	)?;
+	crate::manifest::add_feature(crate_dir.join("Cargo.toml"), feature_name, &[])?;
+	Ok(module_file)
+}
+
+/// Inserts a module declaration, preceded by the given attributes, as [`add_mod_declaration`]
+/// does. Declarations with attributes are separated from their neighbours by a blank line.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(visibility)))]
+fn insert_mod_declaration(
+	parent_file_path: &Path,
+	mod_name: &str,
+	visibility: &Visibility,
+	attrs: &[String],
+) -> Result<PathBuf, Error> {
+	let ident = syn::parse_str::<Ident>(mod_name)
+		.map_err(|_| Error::Descriptive(format!("{mod_name} isn't a valid module name")))?;
+	let name = ident.unraw().to_string();
+	let content = std::fs::read_to_string(parent_file_path)?;
+	let file = syn::parse_file(&content)?;
+
+	let mut declarations = Vec::new();
+	for item in &file.items {
+		let Item::Mod(item_mod) = item else { continue };
+		match (module_name(item_mod) == name, &item_mod.content) {
+			(true, Some(_)) =>
+				return Err(Error::Descriptive(format!(
+					"The module {name} is declared inline in {}",
+					parent_file_path.display()
+				))),
+			(true, None) => return create_module_file(parent_file_path, &name),
+			(false, None) => declarations.push(item_mod),
+			(false, Some(_)) => (),
+		}
+	}
+
+	let mut declaration = attrs.join("\n");
+	if !declaration.is_empty() {
+		declaration.push('\n');
+	}
+	declaration.push_str(&format!("{}mod {ident};", render_visibility(visibility)));
+	let separator = if attrs.is_empty() { "" } else { "\n" };
+
+	let content = match declarations.iter().find(|declaration| module_name(declaration) > name) {
+		Some(next) => insert_before(
+			&content,
+			next.span().byte_range().start,
+			&format!("{declaration}{separator}"),
+		),
+		None => match (declarations.last(), file.items.first()) {
+			(Some(last), _) => insert_after(
+				&content,
+				last.span().byte_range(),
+				&format!("{separator}{declaration}"),
+			),
+			(None, Some(first)) => insert_before(
+				&content,
+				first.span().byte_range().start,
+				&format!("{declaration}\n"),
+			),
+			(None, None) => append(&content, &declaration),
+		},
+	};
+
+	debug!(path = %parent_file_path.display(), "Writing source file");
+	std::fs::write(parent_file_path, content)?;
+	create_module_file(parent_file_path, &name)
+}
+
+/// Given the path to a Rust file (usually a `lib.rs` file), a path and an optional alias, this
+/// function re-exports the path from the file, eg, `pub use path as alias;`.
+///
+/// If the file already contains a `pub use` declaration without attributes starting by the same
+/// segment as the path, the path is merged into it, as
+/// [`merge_use_trees`](super::use_tree::merge_use_trees) does. Otherwise, a new declaration is
+/// inserted after the last `pub use` declaration of the file, or after its last module declaration
+/// if there's none. Nothing is done if the path is already re-exported with the same alias.
+///
+/// # Errors
+///
+/// - If the path, together with the alias, isn't a valid use tree.
+/// - If the file cannot be read or parsed.
+/// - If the file cannot be overwritten.
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let lib_path = tempdir.path().join("lib.rs");
+/// std::fs::write(&lib_path, "mod a;\nmod b;\n\npub use a::A;\n").unwrap();
+///
+/// rustilities::parsing::add_reexport(&lib_path, "b::B", Some("Renamed")).unwrap();
+/// rustilities::parsing::add_reexport(&lib_path, "a::Other", None).unwrap();
+/// rustilities::parsing::add_reexport(&lib_path, "a::A", None).unwrap();
+///
+/// assert_eq!(
+///     std::fs::read_to_string(&lib_path).unwrap(),
+///     "mod a;\nmod b;\n\npub use a::{A, Other};\npub use b::B as Renamed;\n"
+/// );
+/// ```
+pub fn add_reexport<P: AsRef<Path>>(
+	lib_rs_path: P,
+	path: &str,
+	alias: Option<&str>,
+) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_add_reexport(lib_rs_path: &Path, path: &str, alias: Option<&str>) -> Result<(), Error> {
+		let declaration = match alias {
+			Some(alias) => format!("{path} as {alias}"),
+			None => path.to_owned(),
+		};
+		let tree = syn::parse_str::<UseTree>(&declaration)
+			.map_err(|_| Error::Descriptive(format!("{declaration} isn't a valid use tree")))?;
+		let content = std::fs::read_to_string(lib_rs_path)?;
+		let file = syn::parse_file(&content)?;
+
+		let reexports = file
+			.items
+			.iter()
+			.filter_map(|item| match item {
+				Item::Use(item_use) if matches!(item_use.vis, Visibility::Public(_)) =>
+					Some(item_use),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		let reexported = reexports
+			.iter()
+			.filter(|item_use| item_use.attrs.is_empty())
+			.flat_map(|item_use| flatten_use_tree(&item_use.tree))
+			.map(|tree| render_use_tree(&tree))
+			.collect::<HashSet<_>>();
+		if flatten_use_tree(&tree)
+			.iter()
+			.all(|tree| reexported.contains(&render_use_tree(tree)))
+		{
+			return Ok(());
+		}
+
+		let first_segment = |tree: &UseTree| match tree {
+			UseTree::Path(path) => Some(path.ident.to_string()),
+			_ => None,
+		};
+		let mergeable = reexports.iter().find(|item_use| {
+			item_use.attrs.is_empty() &&
+				item_use.leading_colon.is_none() &&
+				first_segment(&item_use.tree).is_some() &&
+				first_segment(&item_use.tree) == first_segment(&tree)
+		});
+		let last_declaration = file
+			.items
+			.iter()
+			.rfind(|item| matches!(item, Item::Mod(item_mod) if item_mod.content.is_none()));
+
+		let content = match (mergeable, reexports.last(), last_declaration, file.items.first()) {
+			(Some(item_use), ..) => {
+				let merged = merge_use_trees([&item_use.tree, &tree]);
+				let range = item_use.span().byte_range();
+				format!(
+					"{}pub use {};{}",
+					&content[..range.start],
+					render_use_tree(&merged[0]),
+					&content[range.end..]
+				)
+			},
+			(None, Some(last), ..) => insert_after(
+				&content,
+				last.span().byte_range(),
+				&format!("pub use {};", render_use_tree(&tree)),
+			),
+			(None, None, Some(last), _) => insert_after(
+				&content,
+				last.span().byte_range(),
+				&format!("\npub use {};", render_use_tree(&tree)),
+			),
+			(None, None, None, Some(first)) => insert_before(
+				&content,
+				first.span().byte_range().start,
+				&format!("pub use {};\n", render_use_tree(&tree)),
+			),
+			(None, None, None, None) =>
+				append(&content, &format!("pub use {};", render_use_tree(&tree))),
+		};
+
+		debug!(path = %lib_rs_path.display(), "Writing source file");
+		std::fs::write(lib_rs_path, content)?;
+		Ok(())
+	}
+	do_add_reexport(lib_rs_path.as_ref(), path, alias)
+}
+
+fn module_name(item_mod: &ItemMod) -> String {
+	item_mod.ident.unraw().to_string()
+}
+
+/// Renders a visibility as written in source code, followed by a space unless it's inherited.
+pub(crate) fn render_visibility(visibility: &Visibility) -> String {
+	match visibility {
+		Visibility::Inherited => String::new(),
+		Visibility::Public(_) => "pub ".to_owned(),
+		Visibility::Restricted(restricted) => format!(
+			"pub({}{}) ",
+			if restricted.in_token.is_some() { "in " } else { "" },
+			restricted
+				.path
+				.segments
+				.iter()
+				.map(|segment| segment.ident.to_string())
+				.collect::<Vec<_>>()
+				.join("::")
+		),
+	}
+}
+
+/// The byte offset where the line containing `offset` starts, and its indentation.
+fn line_start(content: &str, offset: usize) -> (usize, &str) {
+	let start = content[..offset].rfind('\n').map_or(0, |index| index + 1);
+	let indentation = &content[start..offset];
+	(start, if indentation.trim().is_empty() { indentation } else { "" })
+}
+
+/// Indents every non-empty line of `text`.
+fn indent(text: &str, indentation: &str) -> String {
+	text.split('\n')
+		.map(|line| if line.is_empty() { line.to_owned() } else { format!("{indentation}{line}") })
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+/// Inserts `text` in its own lines, before the line containing `offset` and with its indentation.
+fn insert_before(content: &str, offset: usize, text: &str) -> String {
+	let (start, indentation) = line_start(content, offset);
+	format!("{}{}\n{}", &content[..start], indent(text, indentation), &content[start..])
+}
+
+/// Inserts `text` in its own lines, after the line where `range` ends and with the indentation of
+/// the line where `range` starts.
+fn insert_after(content: &str, range: std::ops::Range<usize>, text: &str) -> String {
+	let (_, indentation) = line_start(content, range.start);
+	let end = content[range.end..].find('\n').map_or(content.len(), |index| range.end + index);
+	format!("{}\n{}{}", &content[..end], indent(text, indentation), &content[end..])
+}
+
+/// Appends `text` in its own line at the end of `content`.
+fn append(content: &str, text: &str) -> String {
+	if content.is_empty() || content.ends_with('\n') {
+		format!("{content}{text}\n")
+	} else {
+		format!("{content}\n{text}\n")
+	}
+}
+
+/// The dir containing the files of the modules declared in `parent_file_path`.
+pub(crate) fn module_dir(parent_file_path: &Path) -> PathBuf {
+	let parent_dir = parent_file_path.parent().expect("A file always lives inside a dir; qed");
+	match parent_file_path.file_name().and_then(|file_name| file_name.to_str()) {
+		Some("lib.rs" | "main.rs" | "mod.rs") => parent_dir.to_path_buf(),
+		_ => parent_dir
+			.join(parent_file_path.file_stem().expect("The file has a name as it was read; qed")),
+	}
+}
+
+/// Creates the file of the module `name` declared in `parent_file_path` if it doesn't exist.
+fn create_module_file(parent_file_path: &Path, name: &str) -> Result<PathBuf, Error> {
+	let module_dir = module_dir(parent_file_path);
+
+	let mod_rs = module_dir.join(name).join("mod.rs");
+	if mod_rs.is_file() {
+		return Ok(mod_rs);
+	}
+	let module_file = module_dir.join(format!("{name}.rs"));
+	if !module_file.is_file() {
+		std::fs::create_dir_all(&module_dir)?;
+		debug!(path = %module_file.display(), "Creating module file");
+		std::fs::write(&module_file, "")?;
+	}
+	Ok(module_file)
+}
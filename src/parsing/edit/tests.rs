@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use syn::parse_quote;
+use tempfile::TempDir;
+
+fn dir_with_file(path: &str, content: &str) -> (TempDir, PathBuf) {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let path = tempdir.path().join(path);
+	std::fs::create_dir_all(path.parent().expect("A file always lives inside a dir; qed"))
+		.expect("This should be created; qed;");
+	std::fs::write(&path, content).expect("The file should be writable; qed;");
+	(tempdir, path)
+}
+
+#[test]
+fn add_mod_declaration_inserts_declaration_in_sorted_position() {
+	let (tempdir, lib_path) = dir_with_file(
+		"src/lib.rs",
+		"//! Docs.\n\n#[cfg(test)]\nmod b;\n/// Docs of d.\nmod d;\n\nfn f() {}\n",
+	);
+
+	assert_eq!(
+		add_mod_declaration(&lib_path, "c", &parse_quote!(pub(crate)))
+			.expect("This should be Ok; qed;"),
+		tempdir.path().join("src/c.rs")
+	);
+	assert_eq!(
+		add_mod_declaration(&lib_path, "a", &Visibility::Inherited)
+			.expect("This should be Ok; qed;"),
+		tempdir.path().join("src/a.rs")
+	);
+	assert_eq!(
+		add_mod_declaration(&lib_path, "e", &parse_quote!(pub(in crate::foo)))
+			.expect("This should be Ok; qed;"),
+		tempdir.path().join("src/e.rs")
+	);
+	assert_eq!(
+		std::fs::read_to_string(&lib_path).expect("This should be Ok; qed;"),
+		"//! Docs.\n\nmod a;\n#[cfg(test)]\nmod b;\npub(crate) mod c;\n/// Docs of d.\nmod \
+		 d;\npub(in crate::foo) mod e;\n\nfn f() {}\n"
+	);
+	for module in ["a", "c", "e"] {
+		assert!(tempdir.path().join("src").join(format!("{module}.rs")).is_file());
+	}
+}
+
+#[test]
+fn add_mod_declaration_keeps_indentation_and_inserts_before_first_item() {
+	let (tempdir, file_path) = dir_with_file("src/foo.rs", "// Header.\n\nuse std::fmt;\n");
+
+	add_mod_declaration(&file_path, "r#type", &parse_quote!(pub)).expect("This should be Ok; qed;");
+	add_mod_declaration(&file_path, "bar", &parse_quote!(pub)).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		std::fs::read_to_string(&file_path).expect("This should be Ok; qed;"),
+		"// Header.\n\npub mod bar;\npub mod r#type;\n\nuse std::fmt;\n"
+	);
+	assert!(tempdir.path().join("src/foo/type.rs").is_file());
+	assert!(tempdir.path().join("src/foo/bar.rs").is_file());
+}
+
+#[test]
+fn add_mod_declaration_appends_to_empty_file() {
+	let (tempdir, file_path) = dir_with_file("src/main.rs", "");
+
+	add_mod_declaration(&file_path, "cli", &Visibility::Inherited)
+		.expect("This should be Ok; qed;");
+
+	assert_eq!(std::fs::read_to_string(&file_path).expect("This should be Ok; qed;"), "mod cli;\n");
+	assert!(tempdir.path().join("src/cli.rs").is_file());
+}
+
+#[test]
+fn add_mod_declaration_reuses_existing_declarations_and_files() {
+	let (tempdir, lib_path) = dir_with_file("src/lib.rs", "mod a;\n");
+	std::fs::create_dir_all(tempdir.path().join("src/b")).expect("This should be created; qed;");
+	std::fs::write(tempdir.path().join("src/b/mod.rs"), "struct B;")
+		.expect("The file should be writable; qed;");
+
+	assert_eq!(
+		add_mod_declaration(&lib_path, "a", &parse_quote!(pub)).expect("This should be Ok; qed;"),
+		tempdir.path().join("src/a.rs")
+	);
+	assert_eq!(
+		add_mod_declaration(&lib_path, "b", &parse_quote!(pub)).expect("This should be Ok; qed;"),
+		tempdir.path().join("src/b/mod.rs")
+	);
+	assert_eq!(
+		std::fs::read_to_string(&lib_path).expect("This should be Ok; qed;"),
+		"mod a;\npub mod b;\n"
+	);
+	assert_eq!(
+		std::fs::read_to_string(tempdir.path().join("src/b/mod.rs"))
+			.expect("This should be Ok; qed;"),
+		"struct B;"
+	);
+}
+
+#[test]
+fn add_mod_declaration_fails_if_module_is_declared_inline() {
+	let (_tempdir, lib_path) = dir_with_file("src/lib.rs", "mod a {}\n");
+	assert!(matches!(
+		add_mod_declaration(&lib_path, "a", &parse_quote!(pub)),
+		Err(Error::Descriptive(msg)) if msg == format!("The module a is declared inline in {}", lib_path.display())
+	));
+}
+
+#[test]
+fn add_mod_declaration_fails_if_name_isnt_valid() {
+	let (_tempdir, lib_path) = dir_with_file("src/lib.rs", "");
+	assert!(matches!(
+		add_mod_declaration(&lib_path, "a-b", &parse_quote!(pub)),
+		Err(Error::Descriptive(msg)) if msg == "a-b isn't a valid module name"
+	));
+}
+
+#[test]
+fn add_mod_declaration_fails_if_file_cannot_be_parsed() {
+	let (_tempdir, lib_path) = dir_with_file("src/lib.rs", "fn {");
+	assert!(matches!(add_mod_declaration(&lib_path, "a", &parse_quote!(pub)), Err(Error::Syn(_))));
+}
+
+#[test]
+fn add_reexport_merges_into_existing_reexports() {
+	let (_tempdir, lib_path) =
+		dir_with_file("src/lib.rs", "mod a;\n\npub use a::{A, B};\n#[cfg(test)]\npub use b::B;\n");
+
+	add_reexport(&lib_path, "a::C", None).expect("This should be Ok; qed;");
+	add_reexport(&lib_path, "a::B", None).expect("This should be Ok; qed;");
+	add_reexport(&lib_path, "b::B", Some("Other")).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		std::fs::read_to_string(&lib_path).expect("This should be Ok; qed;"),
+		"mod a;\n\npub use a::{A, B, C};\n#[cfg(test)]\npub use b::B;\npub use b::B as Other;\n"
+	);
+}
+
+#[test]
+fn add_reexport_inserts_after_mod_declarations() {
+	let (_tempdir, lib_path) = dir_with_file("src/lib.rs", "mod a;\nuse std::fmt;\n");
+
+	add_reexport(&lib_path, "a::*", None).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		std::fs::read_to_string(&lib_path).expect("This should be Ok; qed;"),
+		"mod a;\n\npub use a::*;\nuse std::fmt;\n"
+	);
+}
+
+#[test]
+fn add_reexport_inserts_before_first_item_or_appends() {
+	let (_tempdir, lib_path) = dir_with_file("src/lib.rs", "//! Docs.\n\nfn f() {}\n");
+	let (_other_tempdir, empty_path) = dir_with_file("src/lib.rs", "");
+
+	add_reexport(&lib_path, "crate::f", Some("g")).expect("This should be Ok; qed;");
+	add_reexport(&empty_path, "foo::Bar", None).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		std::fs::read_to_string(&lib_path).expect("This should be Ok; qed;"),
+		"//! Docs.\n\npub use crate::f as g;\n\nfn f() {}\n"
+	);
+	assert_eq!(
+		std::fs::read_to_string(&empty_path).expect("This should be Ok; qed;"),
+		"pub use foo::Bar;\n"
+	);
+}
+
+#[test]
+fn add_reexport_fails_if_path_isnt_valid() {
+	let (_tempdir, lib_path) = dir_with_file("src/lib.rs", "");
+	assert!(matches!(
+		add_reexport(&lib_path, "a::", Some("b")),
+		Err(Error::Descriptive(msg)) if msg == "a:: as b isn't a valid use tree"
+	));
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn add_feature_gated_module_declares_module_and_feature() {
+	let (tempdir, lib_path) = dir_with_file(
+		"src/lib.rs",
+		"mod error;\n\n#[cfg(feature = \"paths\")]\npub mod paths;\n\npub use error::Error;\n",
+	);
+	std::fs::write(tempdir.path().join("Cargo.toml"), "[package]\nname = \"test\"\n")
+		.expect("The manifest should be writable; qed;");
+
+	assert_eq!(
+		add_feature_gated_module(tempdir.path(), "fmt", "fmt").expect("This should be Ok; qed;"),
+		tempdir.path().join("src/fmt.rs")
+	);
+	assert_eq!(
+		add_feature_gated_module(tempdir.path(), "zip", "zip").expect("This should be Ok; qed;"),
+		tempdir.path().join("src/zip.rs")
+	);
+	assert_eq!(
+		std::fs::read_to_string(&lib_path).expect("This should be Ok; qed;"),
+		r#"mod error;
+
+#[cfg(feature = "fmt")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fmt")))]
+pub mod fmt;
+
+#[cfg(feature = "paths")]
+pub mod paths;
+
+#[cfg(feature = "zip")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+pub mod zip;
+
+pub use error::Error;
+"#
+	);
+	assert_eq!(
+		std::fs::read_to_string(tempdir.path().join("Cargo.toml"))
+			.expect("This should be Ok; qed;"),
+		"[package]\nname = \"test\"\n\n[features]\nfmt = []\nzip = []\n"
+	);
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn add_feature_gated_module_fails_if_manifest_doesnt_exist() {
+	let (tempdir, _lib_path) = dir_with_file("src/lib.rs", "");
+	assert!(matches!(
+		add_feature_gated_module(tempdir.path(), "a", "a"),
+		Err(Error::IO(err)) if err.kind() == std::io::ErrorKind::NotFound
+	));
+}
@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities parsing code according to the edition of the crate containing it.
+
+#[cfg(test)]
+mod tests;
+
+use crate::Error;
+use proc_macro2::{Group, Ident, TokenStream, TokenTree};
+use quote::ToTokens;
+#[cfg(feature = "manifest")]
+use std::path::Path;
+
+/// The keywords reserved by the 2018 edition that 2015 code may use as identifiers.
+const KEYWORDS_2018: [&str; 3] = ["async", "await", "try"];
+
+/// A Rust edition.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Edition {
+	/// The edition used by crates not declaring one.
+	#[default]
+	Edition2015,
+	Edition2018,
+	Edition2021,
+	Edition2024,
+}
+
+impl Edition {
+	/// The value of the `edition` key of a manifest declaring this edition, eg `2021`.
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Edition2015 => "2015",
+			Self::Edition2018 => "2018",
+			Self::Edition2021 => "2021",
+			Self::Edition2024 => "2024",
+		}
+	}
+}
+
+impl std::str::FromStr for Edition {
+	type Err = Error;
+
+	fn from_str(edition: &str) -> Result<Self, Self::Err> {
+		match edition {
+			"2015" => Ok(Self::Edition2015),
+			"2018" => Ok(Self::Edition2018),
+			"2021" => Ok(Self::Edition2021),
+			"2024" => Ok(Self::Edition2024),
+			_ => Err(Error::Descriptive(format!("Unknown edition {edition}"))),
+		}
+	}
+}
+
+/// A source file parsed by [`parse_file_with_edition`], along with the edition used to parse it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditionFile {
+	/// The edition of the crate containing the file.
+	pub edition: Edition,
+	/// The parsed file.
+	pub file: syn::File,
+}
+
+/// Given the path of a source file, this function reads the edition of the crate containing it from
+/// the innermost manifest, following the workspace inheritance, and parses the file as
+/// [`parse_str_with_edition`] does. Crates not declaring an edition use the 2015 edition, as cargo
+/// does.
+///
+/// # Errors
+///
+/// - If the file isn't part of a crate.
+/// - If the manifests cannot be read or parsed, or they declare an unknown edition.
+/// - If the file cannot be read or parsed with the edition of the crate.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::parsing::Edition;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir(tempdir.path().join("src")).unwrap();
+/// std::fs::write(tempdir.path().join("Cargo.toml"), "[package]\nname = \"legacy\"\n").unwrap();
+/// std::fs::write(tempdir.path().join("src/lib.rs"), "pub fn async() {}").unwrap();
+///
+/// let parsed =
+///     rustilities::parsing::parse_file_with_edition(tempdir.path().join("src/lib.rs")).unwrap();
+/// assert_eq!(parsed.edition, Edition::Edition2015);
+/// assert_eq!(parsed.file, syn::parse_quote! { pub fn r#async() {} });
+/// ```
+#[cfg(feature = "manifest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "manifest")))]
+pub fn parse_file_with_edition<P: AsRef<Path>>(path: P) -> Result<EditionFile, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_parse_file_with_edition(path: &Path) -> Result<EditionFile, Error> {
+		let manifest_path = crate::manifest::find_innermost_manifest(path).ok_or_else(|| {
+			Error::Descriptive(format!("{} isn't part of a crate", path.display()))
+		})?;
+		let edition = crate_edition(&manifest_path)?;
+		let file = parse_str_with_edition(&std::fs::read_to_string(path)?, edition)?;
+		Ok(EditionFile { edition, file })
+	}
+	do_parse_file_with_edition(path.as_ref())
+}
+
+/// Given the content of a source file and an edition, this function parses the file as the compiler
+/// does for that edition:
+/// - In the 2015 edition, the keywords introduced by the 2018 edition (`async`, `await` and `try`)
+///   are valid identifiers. They're parsed as raw identifiers, eg `r#async`, so the parsed file can
+///   be printed back as valid code.
+/// - Since the 2024 edition, `gen` is a reserved keyword, so it cannot be used as identifier.
+///
+/// # Errors
+///
+/// - If the content cannot be parsed with the given edition.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::{Error, parsing::Edition};
+///
+/// let code = "fn gen() {}";
+///
+/// assert!(rustilities::parsing::parse_str_with_edition(code, Edition::Edition2021).is_ok());
+/// assert!(matches!(
+///     rustilities::parsing::parse_str_with_edition(code, Edition::Edition2024),
+///     Err(Error::Syn(_))
+/// ));
+/// ```
+pub fn parse_str_with_edition(content: &str, edition: Edition) -> Result<syn::File, Error> {
+	let file = if edition == Edition::Edition2015 {
+		parse_2015(content)?
+	} else {
+		syn::parse_file(content)?
+	};
+	if edition >= Edition::Edition2024 &&
+		let Some(ident) = find_ident(file.to_token_stream(), "gen")
+	{
+		return Err(syn::Error::new(
+			ident.span(),
+			"`gen` is a reserved keyword since the 2024 edition",
+		)
+		.into());
+	}
+	Ok(file)
+}
+
+/// The edition of the crate whose manifest lives at the given path.
+#[cfg(feature = "manifest")]
+fn crate_edition(manifest_path: &Path) -> Result<Edition, Error> {
+	crate::manifest::package_edition(manifest_path)?
+		.map_or(Ok(Edition::default()), |edition| edition.parse())
+}
+
+/// Parses 2015 code, turning the identifiers that became keywords in the 2018 edition into raw
+/// identifiers.
+fn parse_2015(content: &str) -> Result<syn::File, Error> {
+	let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+	// As `syn::parse_file`, a first line starting with `#!` is a shebang unless it starts an inner
+	// attribute
+	let (shebang, content) = match content.strip_prefix("#!") {
+		Some(rest) if !rest.trim_start().starts_with('[') => {
+			let end = content.find('\n').unwrap_or(content.len());
+			(Some(content[..end].to_owned()), &content[end..])
+		},
+		_ => (None, content),
+	};
+	let tokens = content.parse::<TokenStream>().map_err(syn::Error::from)?;
+	let mut file = syn::parse2::<syn::File>(raw_2018_keywords(tokens))?;
+	file.shebang = shebang;
+	Ok(file)
+}
+
+/// Turns the 2018 keywords found in the tokens into raw identifiers.
+fn raw_2018_keywords(tokens: TokenStream) -> TokenStream {
+	tokens
+		.into_iter()
+		.map(|token| match token {
+			TokenTree::Ident(ident) if KEYWORDS_2018.contains(&ident.to_string().as_str()) =>
+				TokenTree::Ident(Ident::new_raw(&ident.to_string(), ident.span())),
+			TokenTree::Group(group) => {
+				let mut new_group =
+					Group::new(group.delimiter(), raw_2018_keywords(group.stream()));
+				new_group.set_span(group.span());
+				TokenTree::Group(new_group)
+			},
+			token => token,
+		})
+		.collect()
+}
+
+/// Finds the first occurrence of the given non-raw identifier in the tokens.
+fn find_ident(tokens: TokenStream, name: &str) -> Option<Ident> {
+	tokens.into_iter().find_map(|token| match token {
+		TokenTree::Ident(ident) if ident == name => Some(ident),
+		TokenTree::Group(group) => find_ident(group.stream(), name),
+		_ => None,
+	})
+}
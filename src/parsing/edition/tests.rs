@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use syn::parse_quote;
+
+#[test]
+fn edition_from_str_works() {
+	for edition in
+		[Edition::Edition2015, Edition::Edition2018, Edition::Edition2021, Edition::Edition2024]
+	{
+		assert_eq!(edition.as_str().parse::<Edition>().expect("This should be Ok; qed;"), edition);
+	}
+	assert!(matches!(
+		"2019".parse::<Edition>(),
+		Err(Error::Descriptive(msg)) if msg == "Unknown edition 2019"
+	));
+}
+
+#[test]
+fn parse_str_with_edition_accepts_2018_keywords_as_identifiers_in_2015() {
+	let code =
+		"#!/usr/bin/env run-cargo-script\nfn async(try: u8) -> u8 { macro_call!(await); try }";
+
+	let file = parse_str_with_edition(code, Edition::Edition2015).expect("This should be Ok; qed;");
+	assert_eq!(file.shebang.as_deref(), Some("#!/usr/bin/env run-cargo-script"));
+	let expected: syn::File =
+		parse_quote! { fn r#async(r#try: u8) -> u8 { macro_call!(r#await); r#try } };
+	assert_eq!(file.items, expected.items);
+	assert!(matches!(parse_str_with_edition(code, Edition::Edition2018), Err(Error::Syn(_))));
+}
+
+#[test]
+fn parse_str_with_edition_keeps_inner_attributes_in_2015() {
+	let file = parse_str_with_edition("\u{feff}#![allow(unused)]\nfn f() {}", Edition::Edition2015)
+		.expect("This should be Ok; qed;");
+
+	assert_eq!(file.shebang, None);
+	assert_eq!(file, parse_quote! { #![allow(unused)] fn f() {} });
+}
+
+#[test]
+fn parse_str_with_edition_rejects_gen_since_2024() {
+	let code = "fn f() { let r#gen = 1; m!(gen); }";
+
+	assert!(parse_str_with_edition(code, Edition::Edition2021).is_ok());
+	assert!(matches!(
+		parse_str_with_edition(code, Edition::Edition2024),
+		Err(Error::Syn(err)) if err.to_string() == "`gen` is a reserved keyword since the 2024 edition"
+			&& err.span().start().column == 27
+	));
+	assert!(parse_str_with_edition("fn f() { let r#gen = 1; }", Edition::Edition2024).is_ok());
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn parse_file_with_edition_reads_the_edition_of_the_crate() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	for (path, content) in [
+		(
+			"Cargo.toml",
+			"[workspace]\nmembers = [\"*\"]\n\n[workspace.package]\nedition = \"2024\"\n",
+		),
+		("inherited/Cargo.toml", "[package]\nname = \"inherited\"\nedition.workspace = true\n"),
+		("inherited/src/lib.rs", "fn f() {}"),
+		("own/Cargo.toml", "[package]\nname = \"own\"\nedition = \"2018\"\n"),
+		("own/src/lib.rs", "fn f() {}"),
+		("default/Cargo.toml", "[package]\nname = \"default\"\n"),
+		("default/src/lib.rs", "fn f() {}"),
+	] {
+		let path = tempdir.path().join(path);
+		std::fs::create_dir_all(path.parent().expect("A file always lives inside a dir; qed"))
+			.expect("This should be created; qed;");
+		std::fs::write(path, content).expect("The file should be writable; qed;");
+	}
+
+	for (member, edition) in [
+		("inherited", Edition::Edition2024),
+		("own", Edition::Edition2018),
+		("default", Edition::Edition2015),
+	] {
+		assert_eq!(
+			parse_file_with_edition(tempdir.path().join(member).join("src/lib.rs"))
+				.expect("This should be Ok; qed;"),
+			EditionFile { edition, file: parse_quote! { fn f() {} } }
+		);
+	}
+}
+
+#[cfg(feature = "manifest")]
+#[test]
+fn parse_file_with_edition_fails_if_the_edition_cannot_be_found() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let lib_path = tempdir.path().join("src/lib.rs");
+	std::fs::create_dir_all(tempdir.path().join("src")).expect("This should be created; qed;");
+	std::fs::write(&lib_path, "fn f() {}").expect("The file should be writable; qed;");
+
+	assert!(matches!(
+		parse_file_with_edition(&lib_path),
+		Err(Error::Descriptive(msg)) if msg == format!("{} isn't part of a crate", lib_path.display())
+	));
+
+	std::fs::write(
+		tempdir.path().join("Cargo.toml"),
+		"[package]\nname = \"a\"\nedition.workspace = true\n",
+	)
+	.expect("The file should be writable; qed;");
+	assert!(matches!(
+		parse_file_with_edition(&lib_path),
+		Err(Error::Descriptive(msg)) if msg == format!(
+			"{} inherits its edition from a workspace that doesn't exist",
+			tempdir.path().join("Cargo.toml").display()
+		)
+	));
+
+	std::fs::write(
+		tempdir.path().join("Cargo.toml"),
+		"[package]\nname = \"a\"\nedition = \"2027\"\n",
+	)
+	.expect("The file should be writable; qed;");
+	assert!(matches!(
+		parse_file_with_edition(&lib_path),
+		Err(Error::Descriptive(msg)) if msg == "Unknown edition 2027"
+	));
+}
@@ -0,0 +1,314 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities inspecting and rewriting the generic parameters of syntax trees.
+
+#[cfg(test)]
+mod tests;
+
+use proc_macro2::Ident;
+use std::collections::HashSet;
+use syn::{
+	Block, DeriveInput, Expr, ExprPath, Fields, File, GenericParam, Generics, ImplItem, Item,
+	ItemEnum, ItemFn, ItemImpl, ItemStruct, ItemTrait, ItemType, ItemUnion, Lifetime, Path,
+	PathSegment, Signature, Token, TraitItem, Type, TypePath, WhereClause, WherePredicate,
+	parse_quote,
+	punctuated::Punctuated,
+	visit::{self, Visit},
+	visit_mut::{self, VisitMut},
+};
+
+/// A syntax tree node that can be walked by a
+/// [VisitMut](https://docs.rs/syn/latest/syn/visit_mut/trait.VisitMut.html) visitor, starting from
+/// the visitor method matching the node type.
+pub trait SyntaxNode {
+	/// Walks the node with the given visitor.
+	fn visit_with(&mut self, visitor: &mut impl VisitMut);
+}
+
+macro_rules! syntax_nodes {
+	($($node:ty => $method:ident),* $(,)?) => {
+		$(
+			impl SyntaxNode for $node {
+				fn visit_with(&mut self, visitor: &mut impl VisitMut) {
+					visitor.$method(self);
+				}
+			}
+		)*
+	};
+}
+
+syntax_nodes! {
+	Block => visit_block_mut,
+	DeriveInput => visit_derive_input_mut,
+	Expr => visit_expr_mut,
+	File => visit_file_mut,
+	Generics => visit_generics_mut,
+	ImplItem => visit_impl_item_mut,
+	Item => visit_item_mut,
+	ItemEnum => visit_item_enum_mut,
+	ItemFn => visit_item_fn_mut,
+	ItemImpl => visit_item_impl_mut,
+	ItemStruct => visit_item_struct_mut,
+	ItemTrait => visit_item_trait_mut,
+	ItemType => visit_item_type_mut,
+	ItemUnion => visit_item_union_mut,
+	Signature => visit_signature_mut,
+	TraitItem => visit_trait_item_mut,
+	Type => visit_type_mut,
+	WhereClause => visit_where_clause_mut,
+}
+
+/// Given a syntax tree node, a type parameter and a concrete type, this function returns the node
+/// with every use of the parameter replaced by the concrete type, including the uses in bounds,
+/// where clauses and bodies. Associated paths are qualified: `T::Item` becomes `<Concrete>::Item`.
+///
+/// If the node declares the parameter, the declaration is removed and its bounds are moved to the
+/// where clause, so `fn f<T: Clone>(t: T)` becomes `fn f(t: u8) where u8: Clone`. The items nested
+/// in a node declaring the parameter are left untouched, as they cannot refer to it. The content
+/// of macro invocations isn't rewritten either.
+///
+/// # Example
+///
+/// ```
+/// use syn::{Ident, ItemFn, Type, parse_quote};
+///
+/// let item: ItemFn = parse_quote! {
+///     fn first<T: IntoIterator, U>(value: T, other: U) -> Option<T::Item> where U: Into<T> {
+///         value.into_iter().next()
+///     }
+/// };
+/// let param: Ident = parse_quote! { T };
+/// let concrete: Type = parse_quote! { Vec<u8> };
+/// let expected: ItemFn = parse_quote! {
+///     fn first<U>(value: Vec<u8>, other: U) -> Option<<Vec<u8>>::Item>
+///     where U: Into<Vec<u8>>, Vec<u8>: IntoIterator {
+///         value.into_iter().next()
+///     }
+/// };
+///
+/// assert_eq!(rustilities::parsing::substitute_type_param(&item, &param, &concrete), expected);
+/// ```
+pub fn substitute_type_param<N: SyntaxNode + Clone>(node: &N, param: &Ident, concrete: &Type) -> N {
+	let mut node = node.clone();
+	node.visit_with(&mut Substitution { param, concrete, declared: false });
+	node
+}
+
+struct Substitution<'a> {
+	param: &'a Ident,
+	concrete: &'a Type,
+	// Whether the param is declared by an enclosing item, hiding it from the nested items.
+	declared: bool,
+}
+
+impl Substitution<'_> {
+	// Returns the path without the leading param if the path is an associated path of the param.
+	fn associated_path(&mut self, path: &Path) -> Option<Punctuated<PathSegment, Token![::]>> {
+		let first = path.segments.first()?;
+		if path.leading_colon.is_some() ||
+			path.segments.len() < 2 ||
+			first.ident != *self.param ||
+			!first.arguments.is_none()
+		{
+			return None;
+		}
+		let mut rest: Punctuated<PathSegment, Token![::]> =
+			path.segments.iter().skip(1).cloned().collect();
+		rest.iter_mut().for_each(|segment| self.visit_path_segment_mut(segment));
+		Some(rest)
+	}
+}
+
+impl VisitMut for Substitution<'_> {
+	fn visit_item_mut(&mut self, item: &mut Item) {
+		if self.declared {
+			return;
+		}
+		visit_mut::visit_item_mut(self, item);
+		self.declared = false;
+	}
+
+	fn visit_impl_item_mut(&mut self, item: &mut ImplItem) {
+		let declared = self.declared;
+		visit_mut::visit_impl_item_mut(self, item);
+		self.declared = declared;
+	}
+
+	fn visit_trait_item_mut(&mut self, item: &mut TraitItem) {
+		let declared = self.declared;
+		visit_mut::visit_trait_item_mut(self, item);
+		self.declared = declared;
+	}
+
+	fn visit_generics_mut(&mut self, generics: &mut Generics) {
+		let mut bounds = None;
+		generics.params = std::mem::take(&mut generics.params)
+			.into_iter()
+			.filter(|param| match param {
+				GenericParam::Type(param) if param.ident == *self.param => {
+					bounds = Some(param.bounds.clone());
+					false
+				},
+				_ => true,
+			})
+			.collect();
+		visit_mut::visit_generics_mut(self, generics);
+
+		let Some(mut bounds) = bounds else {
+			return;
+		};
+		self.declared = true;
+		if generics.params.is_empty() {
+			generics.lt_token = None;
+			generics.gt_token = None;
+		}
+		if !bounds.is_empty() {
+			bounds.iter_mut().for_each(|bound| self.visit_type_param_bound_mut(bound));
+			let concrete = self.concrete;
+			let predicate: WherePredicate = parse_quote! { #concrete: #bounds };
+			generics.make_where_clause().predicates.push(predicate);
+		}
+	}
+
+	fn visit_type_mut(&mut self, ty: &mut Type) {
+		if let Type::Path(TypePath { qself: None, path }) = ty {
+			if path.is_ident(self.param) {
+				*ty = self.concrete.clone();
+				return;
+			}
+			if let Some(rest) = self.associated_path(path) {
+				let concrete = self.concrete;
+				*ty = parse_quote! { <#concrete>::#rest };
+				return;
+			}
+		}
+		visit_mut::visit_type_mut(self, ty);
+	}
+
+	fn visit_expr_path_mut(&mut self, expr: &mut ExprPath) {
+		if expr.qself.is_none() &&
+			let Some(rest) = self.associated_path(&expr.path)
+		{
+			let concrete = self.concrete;
+			let attrs = std::mem::take(&mut expr.attrs);
+			*expr = parse_quote! { <#concrete>::#rest };
+			expr.attrs = attrs;
+			expr.attrs.iter_mut().for_each(|attr| self.visit_attribute_mut(attr));
+			return;
+		}
+		visit_mut::visit_expr_path_mut(self, expr);
+	}
+}
+
+/// Given a [`WhereClause`] and a set of generic parameters, this function returns the predicates of
+/// the clause mentioning any of the parameters, either as bounded type or inside their bounds.
+///
+/// A parameter is mentioned by the paths starting with it, such as `T` or `T::Item`, and lifetimes
+/// are matched by their name without the leading `'`. This is useful to carry over exactly the
+/// bounds needed by an impl targeting only some of the parameters of a type.
+///
+/// # Example
+///
+/// ```
+/// use syn::{Ident, WhereClause, WherePredicate, parse_quote};
+///
+/// let where_clause: WhereClause = parse_quote! {
+///     where T: Clone, T::Item: Debug, U: Into<T>, V: 'a, Vec<W>: Default, other::T: Copy
+/// };
+/// let params: [Ident; 2] = [parse_quote! { T }, parse_quote! { a }];
+/// let expected: Vec<WherePredicate> =
+///     vec![parse_quote! { T: Clone }, parse_quote! { T::Item: Debug }, parse_quote! { U: Into<T> }, parse_quote! { V: 'a }];
+///
+/// assert_eq!(rustilities::parsing::predicates_mentioning(&where_clause, &params), expected);
+/// ```
+pub fn predicates_mentioning(where_clause: &WhereClause, params: &[Ident]) -> Vec<WherePredicate> {
+	where_clause
+		.predicates
+		.iter()
+		.filter(|predicate| {
+			let mut finder = ParamFinder { params, found: HashSet::new() };
+			finder.visit_where_predicate(predicate);
+			!finder.found.is_empty()
+		})
+		.cloned()
+		.collect()
+}
+
+/// Given the fields of a struct or variant and their [`Generics`], this function returns the
+/// `PhantomData` type needed to use the type and lifetime parameters that no field uses, or `None`
+/// if every parameter is used. Uses in the where clause don't count, as the compiler requires the
+/// parameters to appear in the fields.
+///
+/// Unused type parameters are included as is and unused lifetimes as `&'a ()`, wrapped in a tuple
+/// if there are several of them. Const parameters don't need to be used, so they're ignored.
+///
+/// # Example
+///
+/// ```
+/// use syn::{Fields, Generics, Type, parse_quote};
+///
+/// let generics: Generics = parse_quote! { <'a, 'b, T, U, const N: usize> };
+/// let fields: Fields = Fields::Named(parse_quote! { { value: &'a [U; N] } });
+/// let expected: Type = parse_quote! { ::core::marker::PhantomData<(&'b (), T)> };
+///
+/// assert_eq!(rustilities::parsing::phantom_for_unused_generics(&fields, &generics), Some(expected));
+/// ```
+pub fn phantom_for_unused_generics(fields: &Fields, generics: &Generics) -> Option<Type> {
+	let params: Vec<Ident> = generics
+		.params
+		.iter()
+		.filter_map(|param| match param {
+			GenericParam::Lifetime(param) => Some(param.lifetime.ident.clone()),
+			GenericParam::Type(param) => Some(param.ident.clone()),
+			GenericParam::Const(_) => None,
+		})
+		.collect();
+	let mut finder = ParamFinder { params: &params, found: HashSet::new() };
+	finder.visit_fields(fields);
+
+	let unused: Vec<Type> = generics
+		.params
+		.iter()
+		.filter_map(|param| match param {
+			GenericParam::Lifetime(param) if !finder.found.contains(&param.lifetime.ident) => {
+				let lifetime = &param.lifetime;
+				Some(parse_quote! { &#lifetime () })
+			},
+			GenericParam::Type(param) if !finder.found.contains(&param.ident) => {
+				let ident = &param.ident;
+				Some(parse_quote! { #ident })
+			},
+			_ => None,
+		})
+		.collect();
+
+	match unused.as_slice() {
+		[] => None,
+		[ty] => Some(parse_quote! { ::core::marker::PhantomData<#ty> }),
+		types => Some(parse_quote! { ::core::marker::PhantomData<(#(#types),*)> }),
+	}
+}
+
+// Collects the params mentioned by the visited nodes.
+struct ParamFinder<'a> {
+	params: &'a [Ident],
+	found: HashSet<Ident>,
+}
+
+impl Visit<'_> for ParamFinder<'_> {
+	fn visit_path(&mut self, path: &Path) {
+		if path.leading_colon.is_none() &&
+			let Some(segment) = path.segments.first() &&
+			self.params.contains(&segment.ident)
+		{
+			self.found.insert(segment.ident.clone());
+		}
+		visit::visit_path(self, path);
+	}
+
+	fn visit_lifetime(&mut self, lifetime: &Lifetime) {
+		if self.params.contains(&lifetime.ident) {
+			self.found.insert(lifetime.ident.clone());
+		}
+	}
+}
@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+#[test]
+fn substitute_type_param_replaces_free_uses_in_types() {
+	let ty: Type = parse_quote! { HashMap<T, Vec<(T, &'a [T; 2])>> };
+	let expected: Type = parse_quote! { HashMap<u8, Vec<(u8, &'a [u8; 2])>> };
+
+	assert_eq!(substitute_type_param(&ty, &parse_quote! { T }, &parse_quote! { u8 }), expected);
+}
+
+#[test]
+fn substitute_type_param_qualifies_associated_paths() {
+	let item: ItemFn = parse_quote! {
+		fn f<T: Default + Iterator>() -> T::Item {
+			let mut value = T::default();
+			<T as Iterator>::next(&mut value).unwrap()
+		}
+	};
+	let expected: ItemFn = parse_quote! {
+		fn f() -> <Counter<u8>>::Item where Counter<u8>: Default + Iterator {
+			let mut value = <Counter<u8>>::default();
+			<Counter<u8> as Iterator>::next(&mut value).unwrap()
+		}
+	};
+
+	assert_eq!(
+		substitute_type_param(&item, &parse_quote! { T }, &parse_quote! { Counter<u8> }),
+		expected
+	);
+}
+
+#[test]
+fn substitute_type_param_moves_bounds_and_keeps_other_params() {
+	let item: ItemImpl = parse_quote! {
+		impl<'a, T: Clone + 'a, const N: usize> Wrapper<'a, T, N> where Self: Sized {
+			fn get<U: From<T>>(&self) -> U {
+				U::from(self.0.clone())
+			}
+		}
+	};
+	let expected: ItemImpl = parse_quote! {
+		impl<'a, const N: usize> Wrapper<'a, String, N> where Self: Sized, String: Clone + 'a {
+			fn get<U: From<String>>(&self) -> U {
+				U::from(self.0.clone())
+			}
+		}
+	};
+
+	assert_eq!(
+		substitute_type_param(&item, &parse_quote! { T }, &parse_quote! { String }),
+		expected
+	);
+}
+
+#[test]
+fn substitute_type_param_leaves_nested_items_untouched() {
+	let item: ItemFn = parse_quote! {
+		fn f<T>(value: T) -> T {
+			struct T;
+			fn g(t: T) -> T { t }
+			let _ = |t: T| t;
+			value
+		}
+	};
+	let expected: ItemFn = parse_quote! {
+		fn f(value: bool) -> bool {
+			struct T;
+			fn g(t: T) -> T { t }
+			let _ = |t: bool| t;
+			value
+		}
+	};
+
+	assert_eq!(substitute_type_param(&item, &parse_quote! { T }, &parse_quote! { bool }), expected);
+}
+
+#[test]
+fn substitute_type_param_doesnt_reapply_to_the_concrete_type() {
+	let item: Item = parse_quote! { struct S<T, U>(T, U::Output); };
+	let expected: Item = parse_quote! { struct S<T>(T, <Option<T>>::Output); };
+
+	assert_eq!(
+		substitute_type_param(&item, &parse_quote! { U }, &parse_quote! { Option<T> }),
+		expected
+	);
+}
+
+#[test]
+fn substitute_type_param_ignores_other_paths() {
+	let item: Item = parse_quote! {
+		fn f<T>(a: ::T, b: other::T, c: Tx) -> crate::T::Assoc {}
+	};
+	let expected: Item = parse_quote! {
+		fn f(a: ::T, b: other::T, c: Tx) -> crate::T::Assoc {}
+	};
+
+	assert_eq!(substitute_type_param(&item, &parse_quote! { T }, &parse_quote! { u8 }), expected);
+}
+
+#[test]
+fn predicates_mentioning_selects_predicates_involving_params() {
+	let where_clause: WhereClause = parse_quote! {
+		where
+			T: Clone,
+			U: Iterator<Item = T>,
+			<V as Trait>::Assoc: Into<U>,
+			[W; N]: Default,
+			X: 'b,
+			for<'c> &'c Y: IntoIterator,
+			Z: Copy
+	};
+	let params: [Ident; 3] = [parse_quote! { T }, parse_quote! { N }, parse_quote! { b }];
+	let expected: Vec<WherePredicate> = vec![
+		parse_quote! { T: Clone },
+		parse_quote! { U: Iterator<Item = T> },
+		parse_quote! { [W; N]: Default },
+		parse_quote! { X: 'b },
+	];
+
+	assert_eq!(predicates_mentioning(&where_clause, &params), expected);
+}
+
+#[test]
+fn predicates_mentioning_returns_nothing_without_params() {
+	let where_clause: WhereClause = parse_quote! { where T: Clone, 'a: 'b };
+
+	assert!(predicates_mentioning(&where_clause, &[]).is_empty());
+}
+
+#[test]
+fn phantom_for_unused_generics_covers_unused_params() {
+	let mut generics: Generics = parse_quote! { <'a, T, U: Iterator> };
+	generics.where_clause = Some(parse_quote! { where U::Item: Clone, T: Clone });
+	let fields: Fields = Fields::Unnamed(parse_quote! { (Vec<U::Item>) });
+
+	assert_eq!(
+		phantom_for_unused_generics(&fields, &generics),
+		Some(parse_quote! { ::core::marker::PhantomData<(&'a (), T)> })
+	);
+}
+
+#[test]
+fn phantom_for_unused_generics_doesnt_wrap_a_single_param() {
+	let mut generics: Generics = parse_quote! { <'a, T> };
+	generics.where_clause = Some(parse_quote! { where T: 'a });
+	let fields: Fields = Fields::Named(parse_quote! { { inner: Box<dyn Fn() + 'a> } });
+
+	assert_eq!(
+		phantom_for_unused_generics(&fields, &generics),
+		Some(parse_quote! { ::core::marker::PhantomData<T> })
+	);
+}
+
+#[test]
+fn phantom_for_unused_generics_is_none_if_every_param_is_used() {
+	let generics: Generics = parse_quote! { <'a, T, const N: usize> };
+
+	assert!(
+		phantom_for_unused_generics(&Fields::Unnamed(parse_quote! { (&'a [T; 2]) }), &generics)
+			.is_none()
+	);
+	assert!(
+		phantom_for_unused_generics(&Fields::Unit, &parse_quote! { <const N: usize> }).is_none()
+	);
+}
@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities listing the test functions of a crate.
+
+#[cfg(test)]
+mod tests;
+
+use super::{
+	api::module_prefix,
+	source_tree::{ParseFile, SourceTree, parse_file},
+};
+use crate::Error;
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+};
+use syn::{Attribute, Item, Meta, ext::IdentExt};
+
+/// The attribute marking a function as a test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "snake_case")
+)]
+pub enum TestKind {
+	/// `#[test]`.
+	Test,
+	/// `#[tokio::test]`, with or without arguments.
+	Tokio,
+	/// `#[bench]`.
+	Bench,
+}
+
+/// A test function found by [`list_tests`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TestFn {
+	/// The name of the function.
+	pub name: String,
+	/// The path of the module containing the function, starting by `crate`.
+	pub module_path: String,
+	/// The path to the file containing the function.
+	pub file: PathBuf,
+	/// The attribute marking the function as a test.
+	pub kind: TestKind,
+	/// The predicates of the `cfg` attributes gating the function, either on the function itself
+	/// or on its enclosing modules, outermost first.
+	#[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::serialize_tokens_seq"))]
+	pub cfgs: Vec<Meta>,
+}
+
+/// Given a crate dir, this function lists the test functions of every target of the crate: the
+/// library, the binaries, the integration tests (`tests/*.rs` and `tests/*/main.rs`) and the
+/// benches (`benches/*.rs` and `benches/*/main.rs`). A test function is a function marked with
+/// `#[test]`, `#[tokio::test]` or `#[bench]`, and it's listed together with its module path and the
+/// `cfg` gates it's compiled under, so custom test selection tools can be built on top of it.
+///
+/// Module paths are relative to the target the function belongs to, and the tests are listed in
+/// the order they're declared, target by target.
+///
+/// # Errors
+///
+/// - If some of the targets cannot be loaded.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::parsing::TestKind;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir_all(tempdir.path().join("src")).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("src/lib.rs"),
+///     "#[cfg(test)]\nmod tests {\n    #[test]\n    fn works() {}\n}\n",
+/// )
+/// .unwrap();
+///
+/// let tests = rustilities::parsing::list_tests(tempdir.path()).unwrap();
+/// assert_eq!(tests.len(), 1);
+/// assert_eq!(tests[0].name, "works");
+/// assert_eq!(tests[0].module_path, "crate::tests");
+/// assert_eq!(tests[0].kind, TestKind::Test);
+/// assert_eq!(tests[0].cfgs, vec![syn::parse_quote!(test)]);
+/// ```
+pub fn list_tests<P: AsRef<Path>>(crate_dir: P) -> Result<Vec<TestFn>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_list_tests(crate_dir: &Path) -> Result<Vec<TestFn>, Error> {
+		let mut tests = Vec::new();
+		for tree in &target_trees(crate_dir)? {
+			// The files are sorted so every file is visited after the file declaring its module
+			let mut module_cfgs: HashMap<Vec<String>, Vec<Meta>> = HashMap::new();
+			for file in tree.files() {
+				let mut cfgs = module_cfgs.remove(&file.module_path).unwrap_or_default();
+				cfgs.extend(cfg_predicates(&file.ast.attrs));
+				collect_tests(
+					&file.ast.items,
+					&mut file.module_path.clone(),
+					&cfgs,
+					&file.path,
+					&mut module_cfgs,
+					&mut tests,
+				);
+			}
+		}
+		Ok(tests)
+	}
+	do_list_tests(crate_dir.as_ref())
+}
+
+/// Loads the source trees of every target of the crate living in `crate_dir`: the targets loaded by
+/// [`SourceTree::load_crate`] followed by the integration tests and the benches.
+pub(crate) fn target_trees(crate_dir: &Path) -> Result<Vec<SourceTree>, Error> {
+	target_trees_with(crate_dir, &parse_file)
+}
+
+/// Same as [`target_trees`], but the files are parsed by `parse`, eg to use a cache.
+pub(crate) fn target_trees_with(
+	crate_dir: &Path,
+	parse: &ParseFile,
+) -> Result<Vec<SourceTree>, Error> {
+	let mut trees = SourceTree::load_crate_with(crate_dir, parse)?;
+	for dir in ["tests", "benches"] {
+		trees.extend(
+			target_roots(&crate_dir.join(dir))
+				.into_iter()
+				.map(|root| SourceTree::load_with(&root, parse))
+				.collect::<Result<Vec<_>, _>>()?,
+		);
+	}
+	Ok(trees)
+}
+
+/// The root files of the targets living in `dir`, such as `tests/foo.rs` or `tests/bar/main.rs`.
+pub(crate) fn target_roots(dir: &Path) -> Vec<PathBuf> {
+	let Ok(entries) = std::fs::read_dir(dir) else {
+		return Vec::new();
+	};
+	let mut roots = entries
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.filter_map(|path| {
+			if path.is_dir() {
+				Some(path.join("main.rs")).filter(|path| path.is_file())
+			} else {
+				path.extension().is_some_and(|extension| extension == "rs").then_some(path)
+			}
+		})
+		.collect::<Vec<_>>();
+	roots.sort();
+	roots
+}
+
+fn collect_tests(
+	items: &[Item],
+	module_path: &mut Vec<String>,
+	cfgs: &[Meta],
+	file: &Path,
+	module_cfgs: &mut HashMap<Vec<String>, Vec<Meta>>,
+	tests: &mut Vec<TestFn>,
+) {
+	for item in items {
+		match item {
+			Item::Fn(item_fn) => {
+				let Some(kind) = item_fn.attrs.iter().find_map(test_kind) else { continue };
+				tests.push(TestFn {
+					name: item_fn.sig.ident.unraw().to_string(),
+					module_path: module_prefix(module_path),
+					file: file.to_path_buf(),
+					kind,
+					cfgs: [cfgs, &cfg_predicates(&item_fn.attrs)].concat(),
+				});
+			},
+			Item::Mod(item_mod) => {
+				let cfgs = [cfgs, &cfg_predicates(&item_mod.attrs)].concat();
+				module_path.push(item_mod.ident.unraw().to_string());
+				match &item_mod.content {
+					Some((_, items)) =>
+						collect_tests(items, module_path, &cfgs, file, module_cfgs, tests),
+					None => {
+						module_cfgs.insert(module_path.clone(), cfgs);
+					},
+				}
+				module_path.pop();
+			},
+			_ => (),
+		}
+	}
+}
+
+fn test_kind(attr: &Attribute) -> Option<TestKind> {
+	let path = attr.path();
+	if path.is_ident("test") {
+		Some(TestKind::Test)
+	} else if path.is_ident("bench") {
+		Some(TestKind::Bench)
+	} else if path.leading_colon.is_none() &&
+		path.segments.len() == 2 &&
+		path.segments[0].ident == "tokio" &&
+		path.segments[1].ident == "test"
+	{
+		Some(TestKind::Tokio)
+	} else {
+		None
+	}
+}
+
+fn cfg_predicates(attrs: &[Attribute]) -> Vec<Meta> {
+	attrs
+		.iter()
+		.filter(|attr| attr.path().is_ident("cfg"))
+		.filter_map(|attr| attr.parse_args().ok())
+		.collect()
+}
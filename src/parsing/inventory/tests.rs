@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use syn::parse_quote;
+
+fn crate_with_files(files: &[(&str, &str)]) -> tempfile::TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	for (path, content) in files {
+		let path = tempdir.path().join(path);
+		std::fs::create_dir_all(path.parent().expect("A file always lives inside a dir; qed"))
+			.expect("This should be created; qed;");
+		std::fs::write(path, content).expect("The file should be writable; qed;");
+	}
+	tempdir
+}
+
+fn test_fn(
+	name: &str,
+	module_path: &str,
+	file: PathBuf,
+	kind: TestKind,
+	cfgs: Vec<Meta>,
+) -> TestFn {
+	TestFn { name: name.to_owned(), module_path: module_path.to_owned(), file, kind, cfgs }
+}
+
+#[test]
+fn list_tests_finds_tests_with_their_cfg_gates() {
+	let tempdir = crate_with_files(&[
+		(
+			"src/lib.rs",
+			r#"
+			#[test]
+			fn top_level() {}
+
+			fn not_a_test() {}
+
+			#[cfg(test)]
+			mod tests;
+
+			#[cfg(feature = "async")]
+			mod r#async {
+				#[cfg(test)]
+				mod tests {
+					#[tokio::test(flavor = "multi_thread")]
+					async fn r#await() {}
+					#[cfg(unix)]
+					#[bench]
+					fn bench(b: &mut test::Bencher) {}
+				}
+			}
+			"#,
+		),
+		("src/tests.rs", "#![cfg(not(miri))]\n\n#[test]\n#[ignore]\nfn slow() {}\n"),
+	]);
+	let src = tempdir.path().join("src");
+
+	assert_eq!(
+		list_tests(tempdir.path()).expect("This should be Ok; qed;"),
+		vec![
+			test_fn("top_level", "crate", src.join("lib.rs"), TestKind::Test, vec![]),
+			test_fn(
+				"await",
+				"crate::async::tests",
+				src.join("lib.rs"),
+				TestKind::Tokio,
+				vec![parse_quote!(feature = "async"), parse_quote!(test)]
+			),
+			test_fn(
+				"bench",
+				"crate::async::tests",
+				src.join("lib.rs"),
+				TestKind::Bench,
+				vec![parse_quote!(feature = "async"), parse_quote!(test), parse_quote!(unix)]
+			),
+			test_fn(
+				"slow",
+				"crate::tests",
+				src.join("tests.rs"),
+				TestKind::Test,
+				vec![parse_quote!(test), parse_quote!(not(miri))]
+			),
+		]
+	);
+}
+
+#[test]
+fn list_tests_covers_every_target() {
+	let tempdir = crate_with_files(&[
+		("src/main.rs", "fn main() {}\n#[test]\nfn in_main() {}\n"),
+		("src/bin/tool.rs", "fn main() {}\n#[test]\nfn in_bin() {}\n"),
+		("tests/integration.rs", "#[test]\nfn in_integration() {}\n"),
+		("tests/suite/main.rs", "mod cases;\n"),
+		("tests/suite/cases.rs", "#[test]\nfn in_suite() {}\n"),
+		("benches/speed.rs", "#[bench]\nfn in_bench(b: &mut test::Bencher) {}\n"),
+	]);
+	let path = tempdir.path();
+
+	assert_eq!(
+		list_tests(path).expect("This should be Ok; qed;"),
+		vec![
+			test_fn("in_main", "crate", path.join("src/main.rs"), TestKind::Test, vec![]),
+			test_fn("in_bin", "crate", path.join("src/bin/tool.rs"), TestKind::Test, vec![]),
+			test_fn(
+				"in_integration",
+				"crate",
+				path.join("tests/integration.rs"),
+				TestKind::Test,
+				vec![]
+			),
+			test_fn(
+				"in_suite",
+				"crate::cases",
+				path.join("tests/suite/cases.rs"),
+				TestKind::Test,
+				vec![]
+			),
+			test_fn("in_bench", "crate", path.join("benches/speed.rs"), TestKind::Bench, vec![]),
+		]
+	);
+}
+
+#[test]
+fn list_tests_is_empty_without_targets() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+
+	assert!(list_tests(tempdir.path()).expect("This should be Ok; qed;").is_empty());
+}
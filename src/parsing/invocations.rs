@@ -0,0 +1,327 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities locating the invocations of a macro.
+
+#[cfg(test)]
+mod tests;
+
+use super::groups;
+use crate::Error;
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::path::{Path, PathBuf};
+use syn::{
+	Attribute, ExprMacro, ImplItemMacro, ItemMacro, Macro, Meta, Path as SynPath, StmtMacro,
+	TraitItemMacro, TypeMacro,
+	visit::{self, Visit},
+};
+
+/// The syntactic position of an [`Invocation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "snake_case")
+)]
+pub enum InvocationPosition {
+	/// A macro invoked where an item is expected, including impl and trait items.
+	Item,
+	/// A macro invoked where an expression, a statement or a pattern is expected.
+	Expr,
+	/// A macro invoked where a type is expected.
+	Type,
+	/// An attribute macro, either outer or inner.
+	Attribute,
+	/// A macro or attribute macro found among the arguments of another macro, whose position
+	/// cannot be known without expanding it.
+	Nested,
+}
+
+/// An invocation found by [`find_macro_invocations`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Invocation {
+	/// The path to the file containing the invocation.
+	pub file: PathBuf,
+	/// The line where the invocation starts, starting by 1.
+	pub line: usize,
+	/// The column where the invocation starts, in chars and starting by 1.
+	pub column: usize,
+	/// The position of the invocation.
+	pub position: InvocationPosition,
+	/// The arguments of the invocation: the content of the delimiters of a macro call or
+	/// attribute list, the value of a name-value attribute, or nothing for a path attribute.
+	#[cfg_attr(feature = "serde", serde(serialize_with = "crate::json::serialize_tokens"))]
+	pub args: TokenStream,
+}
+
+/// Given a file, or a dir whose `.rs` files are scanned recursively (skipping hidden and `target`
+/// dirs), and the path of a macro, this function returns every invocation of the macro: macro
+/// calls in item, expression and type positions, attribute macros, and the calls and attributes
+/// nested in the arguments of other macros, which are found by scanning their tokens.
+///
+/// An invocation matches if its path ends with the segments of `macro_path`, so `json` matches
+/// `json!`, `serde_json::json!` and `::serde_json::json!`, while `serde_json::json` doesn't match
+/// `json!`. A `macro_path` with a leading `::` only matches the invocations written exactly the
+/// same way. The invocations are returned file by file, sorted by position.
+///
+/// # Errors
+///
+/// - If some of the files cannot be read.
+/// - If some of the files cannot be parsed.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::parsing::InvocationPosition;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let file = tempdir.path().join("lib.rs");
+/// std::fs::write(
+///     &file,
+///     "#[my::route(GET)]\nfn f() -> Vec<u8> {\n    vec![route!(1)]\n}\n",
+/// )
+/// .unwrap();
+///
+/// let invocations =
+///     rustilities::parsing::find_macro_invocations(&file, &syn::parse_quote!(route)).unwrap();
+/// let found = invocations
+///     .iter()
+///     .map(|invocation| (invocation.line, invocation.position, invocation.args.to_string()))
+///     .collect::<Vec<_>>();
+/// assert_eq!(
+///     found,
+///     vec![
+///         (1, InvocationPosition::Attribute, "GET".to_owned()),
+///         (3, InvocationPosition::Nested, "1".to_owned()),
+///     ]
+/// );
+/// ```
+pub fn find_macro_invocations<P: AsRef<Path>>(
+	file_or_dir: P,
+	macro_path: &SynPath,
+) -> Result<Vec<Invocation>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(macro_path)))]
+	fn do_find_macro_invocations(
+		file_or_dir: &Path,
+		macro_path: &SynPath,
+	) -> Result<Vec<Invocation>, Error> {
+		let files = if file_or_dir.is_dir() {
+			rust_files(file_or_dir)?
+		} else {
+			vec![file_or_dir.to_path_buf()]
+		};
+		let target: Vec<String> =
+			macro_path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+
+		let mut invocations = Vec::new();
+		for file in files {
+			let ast = syn::parse_file(&std::fs::read_to_string(&file)?)?;
+			let mut finder = InvocationFinder {
+				file: &file,
+				target: &target,
+				leading_colon: macro_path.leading_colon.is_some(),
+				position: InvocationPosition::Item,
+				invocations: Vec::new(),
+			};
+			finder.visit_file(&ast);
+			finder
+				.invocations
+				.sort_by_key(|invocation| (invocation.line, invocation.column));
+			invocations.extend(finder.invocations);
+		}
+		Ok(invocations)
+	}
+	do_find_macro_invocations(file_or_dir.as_ref(), macro_path)
+}
+
+struct InvocationFinder<'a> {
+	file: &'a Path,
+	target: &'a [String],
+	leading_colon: bool,
+	// The position of the macro being visited
+	position: InvocationPosition,
+	invocations: Vec<Invocation>,
+}
+
+impl InvocationFinder<'_> {
+	fn matches(&self, leading_colon: bool, segments: &[String]) -> bool {
+		if self.leading_colon {
+			leading_colon && segments == self.target
+		} else {
+			segments.ends_with(self.target)
+		}
+	}
+
+	fn push(&mut self, span: Span, position: InvocationPosition, args: TokenStream) {
+		let start = span.start();
+		self.invocations.push(Invocation {
+			file: self.file.to_path_buf(),
+			line: start.line,
+			column: start.column + 1,
+			position,
+			args,
+		});
+	}
+
+	fn with_position(&mut self, position: InvocationPosition, visit: impl FnOnce(&mut Self)) {
+		let previous = std::mem::replace(&mut self.position, position);
+		visit(self);
+		self.position = previous;
+	}
+
+	/// Finds the invocations nested in the arguments of a macro, at any depth.
+	fn visit_nested(&mut self, stream: TokenStream) {
+		self.scan_tokens(stream.clone());
+		groups(stream).for_each(|group| self.scan_tokens(group.stream));
+	}
+
+	/// Finds the invocations directly contained in the stream.
+	fn scan_tokens(&mut self, stream: TokenStream) {
+		let tokens: Vec<TokenTree> = stream.into_iter().collect();
+		for (index, token) in tokens.iter().enumerate() {
+			match (token, tokens.get(index + 1)) {
+				(TokenTree::Punct(bang), Some(TokenTree::Group(group)))
+					if bang.as_char() == '!' =>
+				{
+					let Some((start, leading_colon, segments)) = path_before(&tokens[..index])
+					else {
+						continue;
+					};
+					if self.matches(leading_colon, &segments) {
+						self.push(start, InvocationPosition::Nested, group.stream());
+					}
+				},
+				(TokenTree::Punct(pound), Some(next)) if pound.as_char() == '#' => {
+					let content = match (next, tokens.get(index + 2)) {
+						(TokenTree::Group(group), _) if group.delimiter() == Delimiter::Bracket =>
+							group.stream(),
+						(TokenTree::Punct(bang), Some(TokenTree::Group(group)))
+							if bang.as_char() == '!' && group.delimiter() == Delimiter::Bracket =>
+							group.stream(),
+						_ => continue,
+					};
+					if let Ok(attr_meta) = syn::parse2::<Meta>(content) &&
+						self.matches_path(attr_meta.path())
+					{
+						self.push(pound.span(), InvocationPosition::Nested, meta_args(&attr_meta));
+					}
+				},
+				_ => (),
+			}
+		}
+	}
+
+	fn matches_path(&self, path: &SynPath) -> bool {
+		let segments: Vec<String> =
+			path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+		self.matches(path.leading_colon.is_some(), &segments)
+	}
+}
+
+impl Visit<'_> for InvocationFinder<'_> {
+	fn visit_item_macro(&mut self, item: &ItemMacro) {
+		self.with_position(InvocationPosition::Item, |finder| {
+			visit::visit_item_macro(finder, item)
+		});
+	}
+
+	fn visit_impl_item_macro(&mut self, item: &ImplItemMacro) {
+		self.with_position(InvocationPosition::Item, |finder| {
+			visit::visit_impl_item_macro(finder, item)
+		});
+	}
+
+	fn visit_trait_item_macro(&mut self, item: &TraitItemMacro) {
+		self.with_position(InvocationPosition::Item, |finder| {
+			visit::visit_trait_item_macro(finder, item)
+		});
+	}
+
+	fn visit_stmt_macro(&mut self, stmt: &StmtMacro) {
+		self.with_position(InvocationPosition::Expr, |finder| {
+			visit::visit_stmt_macro(finder, stmt)
+		});
+	}
+
+	fn visit_expr_macro(&mut self, expr: &ExprMacro) {
+		self.with_position(InvocationPosition::Expr, |finder| {
+			visit::visit_expr_macro(finder, expr)
+		});
+	}
+
+	fn visit_type_macro(&mut self, ty: &TypeMacro) {
+		self.with_position(InvocationPosition::Type, |finder| visit::visit_type_macro(finder, ty));
+	}
+
+	fn visit_macro(&mut self, mac: &Macro) {
+		if self.matches_path(&mac.path) {
+			let span = match &mac.path.leading_colon {
+				Some(colon) => colon.spans[0],
+				None => mac.path.segments[0].ident.span(),
+			};
+			self.push(span, self.position, mac.tokens.clone());
+		}
+		self.visit_nested(mac.tokens.clone());
+	}
+
+	fn visit_attribute(&mut self, attr: &Attribute) {
+		if self.matches_path(attr.path()) {
+			self.push(attr.pound_token.span, InvocationPosition::Attribute, meta_args(&attr.meta));
+		}
+	}
+}
+
+/// The path ending the tokens, with the span where it starts and whether it has a leading `::`.
+fn path_before(tokens: &[TokenTree]) -> Option<(Span, bool, Vec<String>)> {
+	let is_colon =
+		|token: &TokenTree| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ':');
+	let mut segments = Vec::new();
+	let mut end = tokens.len();
+	loop {
+		let TokenTree::Ident(ident) = &tokens[end.checked_sub(1)?] else {
+			return None;
+		};
+		segments.push(ident.to_string());
+		end -= 1;
+		if end < 2 || !is_colon(&tokens[end - 1]) || !is_colon(&tokens[end - 2]) {
+			segments.reverse();
+			return Some((ident.span(), false, segments));
+		}
+		end -= 2;
+		if end == 0 || !matches!(tokens[end - 1], TokenTree::Ident(_)) {
+			segments.reverse();
+			return Some((tokens[end].span(), true, segments));
+		}
+	}
+}
+
+fn meta_args(meta: &Meta) -> TokenStream {
+	match meta {
+		Meta::Path(_) => TokenStream::new(),
+		Meta::List(list) => list.tokens.clone(),
+		Meta::NameValue(name_value) => name_value.value.to_token_stream(),
+	}
+}
+
+/// The `.rs` files inside a dir, recursively and sorted by path. Hidden and `target` dirs are
+/// skipped.
+fn rust_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+	let mut files = Vec::new();
+	let mut dirs = vec![dir.to_path_buf()];
+	while let Some(dir) = dirs.pop() {
+		for entry in std::fs::read_dir(&dir)? {
+			let path = entry?.path();
+			let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+			if path.is_dir() {
+				if !name.starts_with('.') && name != "target" {
+					dirs.push(path);
+				}
+			} else if path.extension().is_some_and(|extension| extension == "rs") {
+				files.push(path);
+			}
+		}
+	}
+	files.sort();
+	Ok(files)
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use syn::parse_quote;
+
+fn summary(invocations: &[Invocation]) -> Vec<(usize, usize, InvocationPosition, String)> {
+	invocations
+		.iter()
+		.map(|invocation| {
+			(invocation.line, invocation.column, invocation.position, invocation.args.to_string())
+		})
+		.collect()
+}
+
+fn file_with(content: &str) -> (tempfile::TempDir, PathBuf) {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let path = tempdir.path().join("lib.rs");
+	std::fs::write(&path, content).expect("The file should be writable; qed;");
+	(tempdir, path)
+}
+
+#[test]
+fn find_macro_invocations_finds_every_position() {
+	let (_tempdir, path) = file_with(
+		r#"m!(item);
+#[m]
+struct S(m![ty]);
+impl S {
+	m!(impl_item);
+	#[m = "value"]
+	fn f() {
+		m!(stmt);
+		let _ = m!{expr};
+		let v = vec![1, other::m!(nested), { m!(deeper) }];
+		other!(#[m(nested_attr)] fn g() {});
+	}
+}
+"#,
+	);
+
+	let invocations =
+		find_macro_invocations(&path, &parse_quote!(m)).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		summary(&invocations),
+		vec![
+			(1, 1, InvocationPosition::Item, "item".to_owned()),
+			(2, 1, InvocationPosition::Attribute, String::new()),
+			(3, 10, InvocationPosition::Type, "ty".to_owned()),
+			(5, 2, InvocationPosition::Item, "impl_item".to_owned()),
+			(6, 2, InvocationPosition::Attribute, "\"value\"".to_owned()),
+			(8, 3, InvocationPosition::Expr, "stmt".to_owned()),
+			(9, 11, InvocationPosition::Expr, "expr".to_owned()),
+			(10, 19, InvocationPosition::Nested, "nested".to_owned()),
+			(10, 40, InvocationPosition::Nested, "deeper".to_owned()),
+			(11, 10, InvocationPosition::Nested, "nested_attr".to_owned()),
+		]
+	);
+	assert!(invocations.iter().all(|invocation| invocation.file == path));
+}
+
+#[test]
+fn find_macro_invocations_matches_path_suffixes() {
+	let (_tempdir, path) = file_with(
+		"fn f() {\n\tjson!(1);\n\tserde_json::json!(2);\n\t::serde_json::json!(3);\n\tvec![other::json!(4), ::serde_json::json!(5)];\n}\n",
+	);
+	let lines = |macro_path: SynPath| {
+		find_macro_invocations(&path, &macro_path)
+			.expect("This should be Ok; qed;")
+			.iter()
+			.map(|invocation| (invocation.line, invocation.column))
+			.collect::<Vec<_>>()
+	};
+
+	assert_eq!(lines(parse_quote!(json)), vec![(2, 2), (3, 2), (4, 2), (5, 7), (5, 24)]);
+	assert_eq!(lines(parse_quote!(serde_json::json)), vec![(3, 2), (4, 2), (5, 24)]);
+	assert_eq!(lines(parse_quote!(::serde_json::json)), vec![(4, 2), (5, 24)]);
+	assert!(lines(parse_quote!(missing)).is_empty());
+}
+
+#[test]
+fn find_macro_invocations_scans_dirs_recursively() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let path = tempdir.path();
+	for (file, content) in [
+		("b.rs", "m!();"),
+		("a/c.rs", "fn f() { m!(); }"),
+		("target/d.rs", "m!();"),
+		(".hidden/e.rs", "m!();"),
+		("f.txt", "m!();"),
+	] {
+		let file = path.join(file);
+		std::fs::create_dir_all(file.parent().expect("A file always lives inside a dir; qed"))
+			.expect("This should be created; qed;");
+		std::fs::write(file, content).expect("The file should be writable; qed;");
+	}
+
+	assert_eq!(
+		find_macro_invocations(path, &parse_quote!(m))
+			.expect("This should be Ok; qed;")
+			.into_iter()
+			.map(|invocation| invocation.file)
+			.collect::<Vec<_>>(),
+		vec![path.join("a/c.rs"), path.join("b.rs")]
+	);
+}
+
+#[test]
+fn find_macro_invocations_fails_if_a_file_cannot_be_parsed() {
+	let (_tempdir, path) = file_with("fn f( {");
+
+	assert!(matches!(find_macro_invocations(&path, &parse_quote!(m)), Err(Error::Syn(_))));
+}
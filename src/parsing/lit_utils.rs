@@ -0,0 +1,258 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! This module provides helpers to turn Rust values into literals and back, as needed when
+//! generating code:
+//! - [`string_literal`] and [`byte_string`] build the most readable literal for a value, choosing
+//!   between the raw and the escaped forms.
+//! - [`doc_attr`] turns a text into doc attributes, wrapping the long lines.
+//! - The [`FromLit`] trait reads the value of a [`Lit`].
+
+#[cfg(test)]
+mod tests;
+
+use crate::Error;
+use proc_macro2::{Literal, Span};
+use syn::{Attribute, Lit, parse_quote};
+
+/// The maximum width of the doc comments rendered from the attributes built by [`doc_attr`],
+/// including the `/// ` prefix.
+const DOC_WIDTH: usize = 100;
+
+/// Builds a string literal containing the given value. The raw form, eg `r#"say "hi""#`, is used
+/// if the escaped form would need escaping quotes or backslashes, unless the value contains
+/// control characters other than newlines and tabs, which are always escaped.
+///
+/// ```rust
+/// use rustilities::parsing::lit_utils::string_literal;
+///
+/// assert_eq!(string_literal("plain").to_string(), r#""plain""#);
+/// assert_eq!(string_literal(r"C:\Users").to_string(), r#"r"C:\Users""#);
+/// assert_eq!(string_literal(r##"say "#hi""##).to_string(), r###"r##"say "#hi""##"###);
+/// assert_eq!(string_literal("a\"\0").to_string(), r#""a\"\0""#);
+/// ```
+pub fn string_literal(value: &str) -> Literal {
+	if needs_escaping(value.chars()) && !value.chars().any(is_escaped_control) {
+		raw_literal("r", value)
+	} else {
+		Literal::string(value)
+	}
+}
+
+/// Builds a byte string literal containing the given value. As [`string_literal`], the raw form
+/// is used if the escaped form would need escaping quotes or backslashes, as long as the value only
+/// contains printable ASCII, newlines and tabs.
+///
+/// ```rust
+/// use rustilities::parsing::lit_utils::byte_string;
+///
+/// assert_eq!(byte_string(b"plain").to_string(), r#"b"plain""#);
+/// assert_eq!(byte_string(br#"a "quote""#).to_string(), r##"br#"a "quote""#"##);
+/// assert_eq!(byte_string(b"\xff\\").to_string(), r#"b"\xFF\\""#);
+/// ```
+pub fn byte_string(value: &[u8]) -> Literal {
+	let chars = || value.iter().map(|byte| char::from(*byte));
+	if needs_escaping(chars()) &&
+		value.is_ascii() &&
+		!chars().any(is_escaped_control) &&
+		let Ok(value) = std::str::from_utf8(value)
+	{
+		raw_literal("br", value)
+	} else {
+		Literal::byte_string(value)
+	}
+}
+
+/// Turns a text into doc attributes, one per line, so the resulting doc comment reads as the text.
+/// Lines are wrapped at word boundaries so the rendered `/// ` comments fit in 100 columns, except
+/// inside code blocks, which are kept as is.
+///
+/// ```rust
+/// use quote::quote;
+///
+/// let attrs = rustilities::parsing::lit_utils::doc_attr("Summary.\n\n```\nlet x = 1;\n```");
+///
+/// assert_eq!(
+///     quote! { #(#attrs)* }.to_string(),
+///     quote! {
+///         #[doc = " Summary."]
+///         #[doc = ""]
+///         #[doc = " ```"]
+///         #[doc = " let x = 1;"]
+///         #[doc = " ```"]
+///     }
+///     .to_string()
+/// );
+/// ```
+pub fn doc_attr(text: &str) -> Vec<Attribute> {
+	let mut lines = Vec::new();
+	let mut in_code_block = false;
+	for line in text.lines() {
+		if line.trim_start().starts_with("```") {
+			in_code_block = !in_code_block;
+			lines.push(line.to_owned());
+		} else if in_code_block {
+			lines.push(line.to_owned());
+		} else {
+			wrap(line, DOC_WIDTH - "/// ".len(), &mut lines);
+		}
+	}
+
+	lines
+		.into_iter()
+		.map(|line| {
+			let line = if line.is_empty() { line } else { format!(" {line}") };
+			let literal = string_literal(&line);
+			parse_quote! { #[doc = #literal] }
+		})
+		.collect()
+}
+
+/// Reading the value of a [`Lit`].
+///
+/// ```rust
+/// use rustilities::parsing::lit_utils::FromLit;
+/// use syn::{Lit, parse_quote};
+///
+/// let lit: Lit = parse_quote! { 42u16 };
+/// assert_eq!(u16::from_lit(&lit).unwrap(), 42);
+/// assert!(u8::from_lit(&parse_quote! { 256 }).is_err());
+///
+/// let lit: Lit = parse_quote! { r"C:\Users" };
+/// assert_eq!(String::from_lit(&lit).unwrap(), r"C:\Users");
+/// ```
+pub trait FromLit: Sized {
+	/// Reads the value of the literal.
+	///
+	/// # Errors
+	///
+	/// - If the literal isn't of the expected kind, or its value doesn't fit in the type. The error
+	///   is a [`Error::Syn`] pointing to the literal.
+	fn from_lit(lit: &Lit) -> Result<Self, Error>;
+}
+
+impl FromLit for String {
+	fn from_lit(lit: &Lit) -> Result<Self, Error> {
+		match lit {
+			Lit::Str(lit) => Ok(lit.value()),
+			_ => Err(unexpected(lit, "a string literal")),
+		}
+	}
+}
+
+impl FromLit for Vec<u8> {
+	fn from_lit(lit: &Lit) -> Result<Self, Error> {
+		match lit {
+			Lit::ByteStr(lit) => Ok(lit.value()),
+			_ => Err(unexpected(lit, "a byte string literal")),
+		}
+	}
+}
+
+impl FromLit for char {
+	fn from_lit(lit: &Lit) -> Result<Self, Error> {
+		match lit {
+			Lit::Char(lit) => Ok(lit.value()),
+			_ => Err(unexpected(lit, "a char literal")),
+		}
+	}
+}
+
+impl FromLit for bool {
+	fn from_lit(lit: &Lit) -> Result<Self, Error> {
+		match lit {
+			Lit::Bool(lit) => Ok(lit.value),
+			_ => Err(unexpected(lit, "a bool literal")),
+		}
+	}
+}
+
+macro_rules! impl_from_lit_for_ints {
+	($($int: ty),*) => {
+		$(
+			impl FromLit for $int {
+				fn from_lit(lit: &Lit) -> Result<Self, Error> {
+					match lit {
+						Lit::Int(lit) => Ok(lit.base10_parse()?),
+						_ => Err(unexpected(lit, "an integer literal")),
+					}
+				}
+			}
+		)*
+	};
+}
+
+impl_from_lit_for_ints!(u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl FromLit for u8 {
+	fn from_lit(lit: &Lit) -> Result<Self, Error> {
+		match lit {
+			Lit::Int(lit) => Ok(lit.base10_parse()?),
+			Lit::Byte(lit) => Ok(lit.value()),
+			_ => Err(unexpected(lit, "an integer or byte literal")),
+		}
+	}
+}
+
+macro_rules! impl_from_lit_for_floats {
+	($($float: ty),*) => {
+		$(
+			impl FromLit for $float {
+				fn from_lit(lit: &Lit) -> Result<Self, Error> {
+					match lit {
+						Lit::Float(lit) => Ok(lit.base10_parse()?),
+						Lit::Int(lit) => Ok(lit.base10_parse()?),
+						_ => Err(unexpected(lit, "a float literal")),
+					}
+				}
+			}
+		)*
+	};
+}
+
+impl_from_lit_for_floats!(f32, f64);
+
+/// The error returned by [`FromLit`] if the literal isn't of the expected kind.
+fn unexpected(lit: &Lit, expected: &str) -> Error {
+	syn::Error::new(lit.span(), format!("Expected {expected}")).into()
+}
+
+/// Whether the escaped form of a literal containing the given chars needs escaping some of them.
+fn needs_escaping(mut chars: impl Iterator<Item = char>) -> bool {
+	chars.any(|c| c == '"' || c == '\\')
+}
+
+/// Whether a char is a control char escaped by the literals, which are hard to read in raw form.
+fn is_escaped_control(c: char) -> bool {
+	c.is_control() && c != '\n' && c != '\t'
+}
+
+/// Builds the raw literal with the given prefix and content, using the minimum number of `#`
+/// needed to delimit it.
+fn raw_literal(prefix: &str, value: &str) -> Literal {
+	let mut hashes = String::new();
+	while value.contains(&format!("\"{hashes}")) {
+		hashes.push('#');
+	}
+	let mut literal = format!("{prefix}{hashes}\"{value}\"{hashes}")
+		.parse::<Literal>()
+		.expect("The raw literal is well formed as it contains no CR and enough hashes; qed;");
+	literal.set_span(Span::call_site());
+	literal
+}
+
+/// Wraps a line at word boundaries so its pieces don't exceed the given width, keeping its
+/// indentation. Words longer than the width are kept in their own piece.
+fn wrap(line: &str, width: usize, lines: &mut Vec<String>) {
+	let indent = &line[..line.len() - line.trim_start().len()];
+	let mut current = indent.to_owned();
+	for word in line.split_whitespace() {
+		if current.len() > indent.len() && current.len() + 1 + word.len() > width {
+			lines.push(std::mem::replace(&mut current, indent.to_owned()));
+		}
+		if current.len() > indent.len() {
+			current.push(' ');
+		}
+		current.push_str(word);
+	}
+	lines.push(if current.trim().is_empty() { String::new() } else { current });
+}
@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use quote::quote;
+
+#[test]
+fn string_literal_roundtrips() {
+	for value in [
+		"",
+		"plain",
+		"multi\nline\ttext",
+		r"C:\Users",
+		r#"say "hi""#,
+		r###"a "## "# b"###,
+		"bell\u{7}",
+		"cr\r\n",
+		"unicode ñ 🦀",
+	] {
+		let literal = string_literal(value);
+		let lit: Lit = parse_quote! { #literal };
+		assert_eq!(String::from_lit(&lit).expect("This should be Ok; qed;"), value);
+	}
+}
+
+#[test]
+fn string_literal_chooses_the_most_readable_form() {
+	assert_eq!(string_literal("multi\nline").to_string(), "\"multi\\nline\"");
+	assert_eq!(string_literal(r#"say "hi""#).to_string(), r###"r#"say "hi""#"###);
+	assert_eq!(string_literal(r###"a "## "# b"###).to_string(), r####"r###"a "## "# b"###"####);
+	assert_eq!(string_literal("cr\r\"").to_string(), r#""cr\r\"""#);
+}
+
+#[test]
+fn byte_string_roundtrips() {
+	for value in [&b""[..], b"plain", br"C:\Users", br#"a "quote""#, b"\xff\"", b"\x00\\"] {
+		let literal = byte_string(value);
+		let lit: Lit = parse_quote! { #literal };
+		assert_eq!(Vec::<u8>::from_lit(&lit).expect("This should be Ok; qed;"), value);
+	}
+	assert_eq!(byte_string(b"\x00\\").to_string(), r#"b"\0\\""#);
+}
+
+#[test]
+fn doc_attr_wraps_long_lines_outside_code_blocks() {
+	let long_line = ["word"; 30].join(" ");
+	let text = format!("{long_line}\n  - indented {long_line}\n\n```\n{long_line}\n```");
+
+	let attrs = doc_attr(&text);
+	let lines = attrs
+		.iter()
+		.map(|attr| {
+			let syn::Meta::NameValue(meta) = &attr.meta else { panic!("Expected a doc attr") };
+			let syn::Expr::Lit(syn::ExprLit { lit, .. }) = &meta.value else {
+				panic!("Expected a literal")
+			};
+			String::from_lit(lit).expect("This should be Ok; qed;")
+		})
+		.collect::<Vec<_>>();
+
+	assert_eq!(
+		lines,
+		[
+			format!(" {}", ["word"; 19].join(" ")),
+			format!(" {}", ["word"; 11].join(" ")),
+			format!("   - indented {}", ["word"; 16].join(" ")),
+			format!("   {}", ["word"; 14].join(" ")),
+			String::new(),
+			" ```".to_owned(),
+			format!(" {long_line}"),
+			" ```".to_owned(),
+		]
+	);
+	assert!(lines.iter().take(4).all(|line| line.len() + "///".len() <= DOC_WIDTH));
+}
+
+#[test]
+fn doc_attr_escapes_quotes() {
+	let attrs = doc_attr(r#"Use "quotes""#);
+
+	assert_eq!(
+		quote! { #(#attrs)* }.to_string(),
+		quote! { #[doc = r#" Use "quotes""#] }.to_string()
+	);
+}
+
+#[test]
+fn from_lit_reads_every_kind_of_literal() {
+	assert_eq!(u8::from_lit(&parse_quote! { b'a' }).expect("This should be Ok; qed;"), b'a');
+	assert_eq!(u8::from_lit(&parse_quote! { 0x10 }).expect("This should be Ok; qed;"), 16);
+	assert_eq!(i64::from_lit(&parse_quote! { 1_000i64 }).expect("This should be Ok; qed;"), 1000);
+	assert_eq!(f64::from_lit(&parse_quote! { 1.5e3 }).expect("This should be Ok; qed;"), 1500.0);
+	assert_eq!(f32::from_lit(&parse_quote! { 2 }).expect("This should be Ok; qed;"), 2.0);
+	assert!(bool::from_lit(&parse_quote! { true }).expect("This should be Ok; qed;"));
+	assert_eq!(char::from_lit(&parse_quote! { '🦀' }).expect("This should be Ok; qed;"), '🦀');
+	assert_eq!(
+		Vec::<u8>::from_lit(&parse_quote! { b"bytes" }).expect("This should be Ok; qed;"),
+		b"bytes"
+	);
+}
+
+#[test]
+fn from_lit_fails_if_the_literal_doesnt_fit() {
+	assert!(matches!(
+		String::from_lit(&parse_quote! { 1 }),
+		Err(Error::Syn(err)) if err.to_string() == "Expected a string literal"
+	));
+	assert!(matches!(u8::from_lit(&parse_quote! { 256 }), Err(Error::Syn(_))));
+	assert!(matches!(
+		i32::from_lit(&parse_quote! { 1.5 }),
+		Err(Error::Syn(err)) if err.to_string() == "Expected an integer literal"
+	));
+}
@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities locating the TODO-like markers left in the code of a crate.
+
+#[cfg(test)]
+mod tests;
+
+use super::inventory::target_trees;
+use crate::Error;
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::{
+	collections::HashSet,
+	ops::BitOr,
+	path::{Path, PathBuf},
+};
+use syn::Lit;
+
+/// A kind of marker located by [`find_markers`]. Several kinds can be combined with `|` into a
+/// [`MarkerKinds`] set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "snake_case")
+)]
+pub enum MarkerKind {
+	/// A `todo!()` call.
+	Todo,
+	/// An `unimplemented!()` call.
+	Unimplemented,
+	/// A `panic!()` call whose message starts with `TODO`.
+	PanicTodo,
+	/// A comment containing the word `TODO`.
+	TodoComment,
+	/// A comment containing the word `FIXME`.
+	FixmeComment,
+}
+
+/// A set of [`MarkerKind`]. The empty set is returned by [`MarkerKinds::default`], while
+/// [`MarkerKinds::all`] contains every kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MarkerKinds(u8);
+
+impl MarkerKinds {
+	/// The set containing every kind of marker.
+	pub fn all() -> Self {
+		MarkerKind::Todo |
+			MarkerKind::Unimplemented |
+			MarkerKind::PanicTodo |
+			MarkerKind::TodoComment |
+			MarkerKind::FixmeComment
+	}
+
+	/// Whether the set contains the given kind.
+	pub fn contains(&self, kind: MarkerKind) -> bool {
+		self.0 & Self::from(kind).0 != 0
+	}
+}
+
+impl From<MarkerKind> for MarkerKinds {
+	fn from(kind: MarkerKind) -> Self {
+		Self(1 << kind as u8)
+	}
+}
+
+impl BitOr for MarkerKind {
+	type Output = MarkerKinds;
+
+	fn bitor(self, rhs: Self) -> Self::Output {
+		MarkerKinds::from(self) | rhs
+	}
+}
+
+impl BitOr<MarkerKind> for MarkerKinds {
+	type Output = Self;
+
+	fn bitor(self, rhs: MarkerKind) -> Self::Output {
+		Self(self.0 | Self::from(rhs).0)
+	}
+}
+
+/// A marker located by [`find_markers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Marker {
+	/// The kind of marker.
+	pub kind: MarkerKind,
+	/// The path to the file containing the marker.
+	pub file: PathBuf,
+	/// The line of the marker, starting by 1.
+	pub line: usize,
+	/// The column of the marker, in chars and starting by 1.
+	pub column: usize,
+	/// The message of the marker: the string literal passed to the macro, if any, or the comment
+	/// text from the `TODO`/`FIXME` word to the end of its line.
+	pub message: String,
+}
+
+/// Given a crate dir and a set of [`MarkerKinds`], this function locates the markers of those
+/// kinds left in the files of every target of the crate, including the integration tests and the
+/// benches: `todo!()`, `unimplemented!()` and `panic!("TODO...")` calls, and the comments
+/// containing the words `TODO` or `FIXME`.
+///
+/// Macro calls are located by scanning the tokens of the files, so calls nested in the arguments
+/// of other macros are found too, while comments are located by scanning the raw source, skipping
+/// the string and char literals. The markers are returned file by file, sorted by position.
+///
+/// # Errors
+///
+/// - If some of the targets cannot be loaded.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::parsing::{MarkerKind, MarkerKinds};
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir_all(tempdir.path().join("src")).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("src/lib.rs"),
+///     "// TODO: document\npub fn f() -> u8 {\n    todo!(\"later\")\n}\n",
+/// )
+/// .unwrap();
+///
+/// let markers = rustilities::parsing::find_markers(tempdir.path(), MarkerKinds::all()).unwrap();
+/// assert_eq!(markers.len(), 2);
+/// assert_eq!((markers[0].kind, markers[0].line), (MarkerKind::TodoComment, 1));
+/// assert_eq!(markers[0].message, "TODO: document");
+/// assert_eq!((markers[1].kind, markers[1].line, markers[1].column), (MarkerKind::Todo, 3, 5));
+/// assert_eq!(markers[1].message, "later");
+///
+/// let markers = rustilities::parsing::find_markers(tempdir.path(), MarkerKind::Todo).unwrap();
+/// assert_eq!(markers.len(), 1);
+/// ```
+pub fn find_markers<P: AsRef<Path>>(
+	crate_dir: P,
+	kinds: impl Into<MarkerKinds>,
+) -> Result<Vec<Marker>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_find_markers(crate_dir: &Path, kinds: MarkerKinds) -> Result<Vec<Marker>, Error> {
+		let mut visited = HashSet::new();
+		let mut markers = Vec::new();
+		for tree in target_trees(crate_dir)? {
+			for file in tree.files() {
+				if !visited.insert(file.path.clone()) {
+					continue;
+				}
+				let mut file_markers = Vec::new();
+				macro_markers(file.ast.to_token_stream(), &file.path, kinds, &mut file_markers);
+				comment_markers(
+					&std::fs::read_to_string(&file.path)?,
+					&file.path,
+					kinds,
+					&mut file_markers,
+				);
+				file_markers.sort_by_key(|marker| (marker.line, marker.column));
+				markers.extend(file_markers);
+			}
+		}
+		Ok(markers)
+	}
+	do_find_markers(crate_dir.as_ref(), kinds.into())
+}
+
+/// Collects the marker macro calls found at any depth of the stream.
+fn macro_markers(stream: TokenStream, file: &Path, kinds: MarkerKinds, markers: &mut Vec<Marker>) {
+	let tokens: Vec<TokenTree> = stream.into_iter().collect();
+	for (index, token) in tokens.iter().enumerate() {
+		let TokenTree::Group(group) = token else { continue };
+		if let [TokenTree::Ident(ident), TokenTree::Punct(bang)] =
+			&tokens[index.saturating_sub(2)..index] &&
+			bang.as_char() == '!'
+		{
+			let message = match group.stream().into_iter().next() {
+				Some(TokenTree::Literal(literal)) => match Lit::new(literal) {
+					Lit::Str(lit) => Some(lit.value()),
+					_ => None,
+				},
+				_ => None,
+			};
+			let kind = match ident.to_string().as_str() {
+				"todo" => Some(MarkerKind::Todo),
+				"unimplemented" => Some(MarkerKind::Unimplemented),
+				"panic" if message.as_ref().is_some_and(|message| message.starts_with("TODO")) =>
+					Some(MarkerKind::PanicTodo),
+				_ => None,
+			};
+			if let Some(kind) = kind.filter(|kind| kinds.contains(*kind)) {
+				markers.push(marker(kind, file, ident.span(), message.unwrap_or_default()));
+			}
+		}
+		macro_markers(group.stream(), file, kinds, markers);
+	}
+}
+
+fn marker(kind: MarkerKind, file: &Path, span: Span, message: String) -> Marker {
+	let start = span.start();
+	Marker { kind, file: file.to_path_buf(), line: start.line, column: start.column + 1, message }
+}
+
+/// Collects the TODO and FIXME comments of the source.
+fn comment_markers(source: &str, file: &Path, kinds: MarkerKinds, markers: &mut Vec<Marker>) {
+	for (start, end) in comment_ranges(source) {
+		let comment = &source[start..end];
+		for (word, kind) in [("TODO", MarkerKind::TodoComment), ("FIXME", MarkerKind::FixmeComment)]
+		{
+			if !kinds.contains(kind) {
+				continue;
+			}
+			for (offset, _) in comment.match_indices(word) {
+				let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+				let before = comment[..offset].chars().next_back();
+				let after = comment[offset + word.len()..].chars().next();
+				if before.is_some_and(is_ident_char) || after.is_some_and(is_ident_char) {
+					continue;
+				}
+				let text = comment[offset..].lines().next().unwrap_or_default();
+				let text = if comment.starts_with("/*") {
+					text.strip_suffix("*/").unwrap_or(text)
+				} else {
+					text
+				};
+				let position = start + offset;
+				let line_start = source[..position].rfind('\n').map_or(0, |index| index + 1);
+				markers.push(Marker {
+					kind,
+					file: file.to_path_buf(),
+					line: source[..position].matches('\n').count() + 1,
+					column: source[line_start..position].chars().count() + 1,
+					message: text.trim_end().to_owned(),
+				});
+			}
+		}
+	}
+}
+
+/// The byte ranges of the comments of the source, skipping the string and char literals.
+fn comment_ranges(source: &str) -> Vec<(usize, usize)> {
+	let bytes = source.as_bytes();
+	let mut ranges = Vec::new();
+	let mut index = 0;
+	while index < bytes.len() {
+		match bytes[index] {
+			b'/' if bytes.get(index + 1) == Some(&b'/') => {
+				let end = source[index..].find('\n').map_or(source.len(), |end| index + end);
+				ranges.push((index, end));
+				index = end;
+			},
+			b'/' if bytes.get(index + 1) == Some(&b'*') => {
+				let start = index;
+				let mut depth = 0;
+				while index < bytes.len() {
+					if bytes[index..].starts_with(b"/*") {
+						depth += 1;
+						index += 2;
+					} else if bytes[index..].starts_with(b"*/") {
+						depth -= 1;
+						index += 2;
+						if depth == 0 {
+							break;
+						}
+					} else {
+						index += 1;
+					}
+				}
+				ranges.push((start, index));
+			},
+			b'"' => index = string_end(bytes, index + 1),
+			b'r' if starts_raw_string(bytes, index) => {
+				let hashes = bytes[index + 1..].iter().take_while(|byte| **byte == b'#').count();
+				let terminator = format!("\"{}", "#".repeat(hashes));
+				let content = index + 2 + hashes;
+				index = source[content..]
+					.find(&terminator)
+					.map_or(source.len(), |end| content + end + terminator.len());
+			},
+			b'\'' => {
+				// A char literal is either escaped or made of a single char, otherwise it's a
+				// lifetime or a label
+				let next = source[index + 1..].chars().next();
+				let next_len = next.map_or(0, char::len_utf8);
+				if next == Some('\\') {
+					index =
+						source[index + 3..].find('\'').map_or(source.len(), |end| index + end + 4);
+				} else if bytes.get(index + 1 + next_len) == Some(&b'\'') {
+					index += next_len + 2;
+				} else {
+					index += 1;
+				}
+			},
+			_ => index += 1,
+		}
+	}
+	ranges
+}
+
+/// The index after the closing quote of the string starting at `index`.
+fn string_end(bytes: &[u8], mut index: usize) -> usize {
+	while index < bytes.len() {
+		match bytes[index] {
+			b'\\' => index += 2,
+			b'"' => return index + 1,
+			_ => index += 1,
+		}
+	}
+	bytes.len()
+}
+
+/// Whether the `r` at `index` starts a raw string, either alone or prefixed by `b` or `c`.
+fn starts_raw_string(bytes: &[u8], index: usize) -> bool {
+	let prefix_start = match index.checked_sub(1).map(|index| bytes[index]) {
+		Some(b'b' | b'c') => index - 1,
+		_ => index,
+	};
+	let hashes = bytes[index + 1..].iter().take_while(|byte| **byte == b'#').count();
+	(prefix_start == 0 || !is_ident_byte(bytes[prefix_start - 1])) &&
+		bytes.get(index + 1 + hashes) == Some(&b'"')
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+	byte.is_ascii_alphanumeric() || byte == b'_'
+}
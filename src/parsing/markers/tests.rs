@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+fn crate_with_lib(lib: &str) -> tempfile::TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::create_dir_all(tempdir.path().join("src")).expect("This should be created; qed;");
+	std::fs::write(tempdir.path().join("src/lib.rs"), lib)
+		.expect("The file should be writable; qed;");
+	tempdir
+}
+
+fn summary(markers: &[Marker]) -> Vec<(MarkerKind, usize, usize, &str)> {
+	markers
+		.iter()
+		.map(|marker| (marker.kind, marker.line, marker.column, marker.message.as_str()))
+		.collect()
+}
+
+#[test]
+fn find_markers_locates_macro_calls() {
+	let tempdir = crate_with_lib(
+		r#"pub fn a() { todo!() }
+pub fn b() -> Vec<u8> { vec![unimplemented!("not yet")] }
+pub fn c() { panic!("TODO: handle it"); panic!("not a marker {}", "TODO"); }
+pub fn d() { std::todo!("{}", 1) }
+macro_rules! todo { () => {} }
+"#,
+	);
+
+	let markers =
+		find_markers(tempdir.path(), MarkerKinds::all()).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		summary(&markers),
+		vec![
+			(MarkerKind::Todo, 1, 14, ""),
+			(MarkerKind::Unimplemented, 2, 30, "not yet"),
+			(MarkerKind::PanicTodo, 3, 14, "TODO: handle it"),
+			(MarkerKind::Todo, 4, 19, "{}"),
+		]
+	);
+	assert!(markers.iter().all(|marker| marker.file == tempdir.path().join("src/lib.rs")));
+}
+
+#[test]
+fn find_markers_locates_comments() {
+	let tempdir = crate_with_lib(
+		r##"// TODO: first
+/// FIXME in docs
+pub fn f() {
+	let _ = "// TODO in a string";
+	let _ = r#"/* FIXME in a raw string */"#;
+	let _ = ('"', '\'', '\u{1F600}'); // FIXME: after literals
+	let _: &'static str = "TODOS and NOTODO aren't markers";
+	/* a block
+	   comment with a TODO */
+}
+"##,
+	);
+
+	let markers =
+		find_markers(tempdir.path(), MarkerKinds::all()).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		summary(&markers),
+		vec![
+			(MarkerKind::TodoComment, 1, 4, "TODO: first"),
+			(MarkerKind::FixmeComment, 2, 5, "FIXME in docs"),
+			(MarkerKind::FixmeComment, 6, 39, "FIXME: after literals"),
+			(MarkerKind::TodoComment, 9, 20, "TODO"),
+		]
+	);
+}
+
+#[test]
+fn find_markers_filters_by_kind_and_covers_every_target() {
+	let tempdir = crate_with_lib("// TODO: lib\nmod foo;\n");
+	let path = tempdir.path();
+	std::fs::write(path.join("src/foo.rs"), "fn f() { todo!() } // FIXME\n")
+		.expect("The file should be writable; qed;");
+	std::fs::create_dir_all(path.join("tests")).expect("This should be created; qed;");
+	std::fs::write(path.join("tests/it.rs"), "#[test]\nfn t() { todo!() }\n")
+		.expect("The file should be writable; qed;");
+
+	let markers = find_markers(path, MarkerKind::Todo | MarkerKind::FixmeComment)
+		.expect("This should be Ok; qed;");
+
+	assert_eq!(
+		markers
+			.iter()
+			.map(|marker| (marker.kind, marker.file.clone()))
+			.collect::<Vec<_>>(),
+		vec![
+			(MarkerKind::Todo, path.join("src/foo.rs")),
+			(MarkerKind::FixmeComment, path.join("src/foo.rs")),
+			(MarkerKind::Todo, path.join("tests/it.rs")),
+		]
+	);
+	assert!(
+		find_markers(path, MarkerKinds::default())
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+}
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! This module provides helpers to add or remove the `const`, `async` and `unsafe` qualifiers and
+//! the ABI of a function signature, as needed by macros generating wrappers of a function (eg, a
+//! sync twin of an async function).
+//!
+//! The helpers work on any type implementing the [`SignatureMut`] trait, so they can be applied to
+//! a [`Signature`] as well as to the functions containing it. The added tokens take the span of the
+//! `fn` token, so the errors pointing to them point to the function. The helpers don't check that
+//! the resulting combination of qualifiers is valid (eg, `const async fn` isn't).
+
+#[cfg(test)]
+mod tests;
+
+use syn::{Abi, ForeignItemFn, ImplItemFn, ItemFn, LitStr, Signature, Token, TraitItemFn};
+
+/// The [`SignatureMut`] trait offers a convenient way to retrieve a mutable reference to the
+/// [`Signature`] of a function.
+///
+/// It's currently implemented for [`Signature`], [`ItemFn`], [`ImplItemFn`], [`TraitItemFn`] and
+/// [`ForeignItemFn`], but this will be updated as needed.
+pub trait SignatureMut {
+	fn sig_mut(&mut self) -> &mut Signature;
+}
+
+/// Adds or removes the `async` qualifier of a function.
+///
+/// ```rust
+/// use syn::{parse_quote, ItemFn};
+///
+/// let mut item_fn: ItemFn = parse_quote! { pub async fn f() -> u8 { 0 } };
+/// rustilities::parsing::signature_flags::set_async(&mut item_fn, false);
+///
+/// let expected_item_fn: ItemFn = parse_quote! { pub fn f() -> u8 { 0 } };
+/// assert_eq!(item_fn, expected_item_fn);
+/// ```
+pub fn set_async<T: SignatureMut>(item: &mut T, enabled: bool) {
+	let sig = item.sig_mut();
+	let span = sig.fn_token.span;
+	sig.asyncness = enabled.then(|| Token![async](span));
+}
+
+/// Adds or removes the `const` qualifier of a function.
+///
+/// ```rust
+/// use syn::{parse_quote, ItemFn};
+///
+/// let mut item_fn: ItemFn = parse_quote! { pub unsafe fn f() -> u8 { 0 } };
+/// rustilities::parsing::signature_flags::set_const(&mut item_fn, true);
+///
+/// let expected_item_fn: ItemFn = parse_quote! { pub const unsafe fn f() -> u8 { 0 } };
+/// assert_eq!(item_fn, expected_item_fn);
+/// ```
+pub fn set_const<T: SignatureMut>(item: &mut T, enabled: bool) {
+	let sig = item.sig_mut();
+	let span = sig.fn_token.span;
+	sig.constness = enabled.then(|| Token![const](span));
+}
+
+/// Adds or removes the `unsafe` qualifier of a function.
+///
+/// ```rust
+/// use syn::{parse_quote, Signature};
+///
+/// let mut sig: Signature = parse_quote! { async fn f(ptr: *const u8) -> u8 };
+/// rustilities::parsing::signature_flags::set_unsafe(&mut sig, true);
+///
+/// let expected_sig: Signature = parse_quote! { async unsafe fn f(ptr: *const u8) -> u8 };
+/// assert_eq!(sig, expected_sig);
+/// ```
+pub fn set_unsafe<T: SignatureMut>(item: &mut T, enabled: bool) {
+	let sig = item.sig_mut();
+	let span = sig.fn_token.span;
+	sig.unsafety = enabled.then(|| Token![unsafe](span));
+}
+
+/// Sets the ABI of a function: `Some("C")` makes it `extern "C"`, `Some("")` makes it a bare
+/// `extern` and `None` removes the ABI.
+///
+/// ```rust
+/// use syn::{parse_quote, ItemFn};
+///
+/// let mut item_fn: ItemFn = parse_quote! { pub unsafe fn f() {} };
+/// rustilities::parsing::signature_flags::set_abi(&mut item_fn, Some("C"));
+///
+/// let expected_item_fn: ItemFn = parse_quote! { pub unsafe extern "C" fn f() {} };
+/// assert_eq!(item_fn, expected_item_fn);
+/// ```
+pub fn set_abi<T: SignatureMut>(item: &mut T, abi: Option<&str>) {
+	let sig = item.sig_mut();
+	let span = sig.fn_token.span;
+	sig.abi = abi.map(|abi| Abi {
+		extern_token: Token![extern](span),
+		name: (!abi.is_empty()).then(|| LitStr::new(abi, span)),
+	});
+}
+
+impl SignatureMut for Signature {
+	fn sig_mut(&mut self) -> &mut Signature {
+		self
+	}
+}
+
+impl SignatureMut for ItemFn {
+	fn sig_mut(&mut self) -> &mut Signature {
+		&mut self.sig
+	}
+}
+
+impl SignatureMut for ImplItemFn {
+	fn sig_mut(&mut self) -> &mut Signature {
+		&mut self.sig
+	}
+}
+
+impl SignatureMut for TraitItemFn {
+	fn sig_mut(&mut self) -> &mut Signature {
+		&mut self.sig
+	}
+}
+
+impl SignatureMut for ForeignItemFn {
+	fn sig_mut(&mut self) -> &mut Signature {
+		&mut self.sig
+	}
+}
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use syn::parse_quote;
+
+#[test]
+fn flags_are_added_in_order() {
+	let mut impl_item_fn: ImplItemFn = parse_quote! { pub fn f(&self) {} };
+
+	set_abi(&mut impl_item_fn, Some("C"));
+	set_unsafe(&mut impl_item_fn, true);
+	set_const(&mut impl_item_fn, true);
+
+	let expected_impl_item_fn: ImplItemFn =
+		parse_quote! { pub const unsafe extern "C" fn f(&self) {} };
+	assert_eq!(impl_item_fn, expected_impl_item_fn);
+}
+
+#[test]
+fn flags_are_removed() {
+	let mut trait_item_fn: TraitItemFn =
+		parse_quote! { async unsafe extern "system" fn f(&self) -> u8; };
+
+	set_async(&mut trait_item_fn, false);
+	set_unsafe(&mut trait_item_fn, false);
+	set_abi(&mut trait_item_fn, None);
+
+	let expected_trait_item_fn: TraitItemFn = parse_quote! { fn f(&self) -> u8; };
+	assert_eq!(trait_item_fn, expected_trait_item_fn);
+}
+
+#[test]
+fn setting_present_flags_is_idempotent() {
+	let mut sig: Signature = parse_quote! { async fn f() };
+
+	set_async(&mut sig, true);
+	set_const(&mut sig, false);
+
+	let expected_sig: Signature = parse_quote! { async fn f() };
+	assert_eq!(sig, expected_sig);
+}
+
+#[test]
+fn set_abi_handles_bare_extern() {
+	let mut foreign_item_fn: ForeignItemFn = parse_quote! { fn f(); };
+
+	set_abi(&mut foreign_item_fn, Some(""));
+	let expected_sig: Signature = parse_quote! { extern fn f() };
+	assert_eq!(foreign_item_fn.sig, expected_sig);
+
+	set_abi(&mut foreign_item_fn, Some("C-unwind"));
+	let expected_sig: Signature = parse_quote! { extern "C-unwind" fn f() };
+	assert_eq!(foreign_item_fn.sig, expected_sig);
+}
+
+#[test]
+fn added_tokens_take_the_span_of_the_fn_token() {
+	let mut sig: Signature = syn::parse_str("fn f()").expect("This should be Ok; qed;");
+
+	set_async(&mut sig, true);
+	set_abi(&mut sig, Some("C"));
+
+	let fn_range = sig.fn_token.span.byte_range();
+	assert_eq!(sig.asyncness.expect("async was added; qed;").span.byte_range(), fn_range);
+	assert_eq!(sig.abi.expect("The ABI was added; qed;").extern_token.span.byte_range(), fn_range);
+}
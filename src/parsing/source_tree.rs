@@ -0,0 +1,488 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! This module provides the [`SourceTree`] type, which loads and parses every file belonging to a
+//! crate target by following its `mod` declarations, starting from the target root file (eg,
+//! `src/lib.rs`). It's the building block of the functionalities of this crate that need to
+//! analyze the whole source code of a crate instead of a single file.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{Error, macros::debug};
+use proc_macro2::{Ident, Span};
+use std::path::{Path, PathBuf};
+use syn::{
+	Expr, ExprLit, Item, ItemMod, Lit, Meta, MetaNameValue, Path as SynPath, PathSegment, UseTree,
+	Visibility, ext::IdentExt,
+};
+
+/// Parses the file at the given path when loading a [`SourceTree`].
+pub(crate) type ParseFile<'a> = dyn Fn(&Path) -> Result<syn::File, Error> + 'a;
+
+/// The maximum number of `use` declarations followed by [`SourceTree::find_item`] to resolve a
+/// path. It also prevents infinite loops on glob re-exports importing each other.
+const MAX_REEXPORT_DEPTH: usize = 16;
+
+/// A source file belonging to a [`SourceTree`].
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+	/// The path to the file.
+	pub path: PathBuf,
+	/// The path of the module defined by the file, relative to the crate root. It's empty for the
+	/// target root file, while eg, `src/foo/bar.rs` usually has module path `["foo", "bar"]`.
+	pub module_path: Vec<String>,
+	/// The parsed file.
+	pub ast: syn::File,
+}
+
+/// The parsed source files of a crate target, found by following the `mod` declarations from the
+/// target root file. `#[path = "..."]` attributes on module declarations are honored, while
+/// modules whose file doesn't exist (eg, because they're only compiled for other platforms) are
+/// skipped.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::parsing::source_tree::SourceTree;
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let src_path = tempdir.path().join("src");
+/// std::fs::create_dir_all(src_path.join("foo")).unwrap();
+/// std::fs::write(src_path.join("lib.rs"), "mod foo; mod inline { mod bar; }").unwrap();
+/// std::fs::write(src_path.join("foo.rs"), "pub mod baz;").unwrap();
+/// std::fs::write(src_path.join("foo").join("baz.rs"), "pub struct Baz;").unwrap();
+/// std::fs::create_dir_all(src_path.join("inline")).unwrap();
+/// std::fs::write(src_path.join("inline").join("bar.rs"), "").unwrap();
+///
+/// let tree = SourceTree::load(src_path.join("lib.rs")).unwrap();
+/// let module_paths = tree.files().iter().map(|file| file.module_path.join("::")).collect::<Vec<_>>();
+/// assert_eq!(module_paths, vec!["", "foo", "foo::baz", "inline::bar"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SourceTree {
+	root: PathBuf,
+	files: Vec<SourceFile>,
+}
+
+impl SourceTree {
+	/// Loads the source tree whose root file is `root_file`.
+	///
+	/// # Errors
+	///
+	/// - If some of the files cannot be read.
+	/// - If some of the files cannot be parsed.
+	#[cfg_attr(
+		feature = "tracing",
+		tracing::instrument(level = "debug", skip_all, fields(root_file = %root_file.as_ref().display()))
+	)]
+	pub fn load<P: AsRef<Path>>(root_file: P) -> Result<Self, Error> {
+		Self::load_with(root_file.as_ref(), &parse_file)
+	}
+
+	/// Same as [`SourceTree::load`], but the files are parsed by `parse`, eg to use a cache.
+	pub(crate) fn load_with(root_file: &Path, parse: &ParseFile) -> Result<Self, Error> {
+		let root = root_file.to_path_buf();
+		let mut files = Vec::new();
+		let root_dir = root.parent().expect("A file always lives inside a dir; qed").to_path_buf();
+		load_file(&root, root_dir, Vec::new(), &mut files, parse)?;
+		Ok(Self { root, files })
+	}
+
+	/// Loads the source trees of every target of the crate living in `crate_dir` that follows the
+	/// cargo conventions: the library (`src/lib.rs`), the main binary (`src/main.rs`) and the
+	/// additional binaries (`src/bin/*.rs` and `src/bin/*/main.rs`), in that order.
+	///
+	/// # Errors
+	///
+	/// - If some of the targets cannot be loaded.
+	pub fn load_crate<P: AsRef<Path>>(crate_dir: P) -> Result<Vec<Self>, Error> {
+		Self::load_crate_with(crate_dir.as_ref(), &parse_file)
+	}
+
+	/// Same as [`SourceTree::load_crate`], but the files are parsed by `parse`, eg to use a cache.
+	pub(crate) fn load_crate_with(crate_dir: &Path, parse: &ParseFile) -> Result<Vec<Self>, Error> {
+		let src_dir = crate_dir.join("src");
+		let mut roots = vec![src_dir.join("lib.rs"), src_dir.join("main.rs")];
+		if let Ok(entries) = std::fs::read_dir(src_dir.join("bin")) {
+			let mut bins = entries
+				.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+				.filter_map(|path| {
+					if path.is_dir() {
+						Some(path.join("main.rs"))
+					} else {
+						path.extension().is_some_and(|extension| extension == "rs").then_some(path)
+					}
+				})
+				.collect::<Vec<_>>();
+			bins.sort();
+			roots.extend(bins);
+		}
+
+		roots
+			.into_iter()
+			.filter(|root| root.is_file())
+			.map(|root| Self::load_with(&root, parse))
+			.collect()
+	}
+
+	/// The path to the root file of the tree.
+	pub fn root(&self) -> &Path {
+		&self.root
+	}
+
+	/// The files of the tree, in the order they're declared, starting by the root file.
+	pub fn files(&self) -> &[SourceFile] {
+		&self.files
+	}
+
+	/// Finds a file of the tree by its path.
+	pub fn file<P: AsRef<Path>>(&self, path: P) -> Option<&SourceFile> {
+		self.files.iter().find(|file| file.path == path.as_ref())
+	}
+
+	/// The names of every module declared in the tree, either in its own file or inline, sorted
+	/// and without duplicates.
+	pub fn module_names(&self) -> Vec<String> {
+		fn collect_names(items: &[Item], names: &mut Vec<String>) {
+			for item in items {
+				if let Item::Mod(item_mod) = item {
+					names.push(module_name(item_mod));
+					if let Some((_, items)) = &item_mod.content {
+						collect_names(items, names);
+					}
+				}
+			}
+		}
+
+		let mut names = Vec::new();
+		for file in &self.files {
+			collect_names(&file.ast.items, &mut names);
+		}
+		names.sort_unstable();
+		names.dedup();
+		names
+	}
+
+	/// The path of an item of the tree, starting by `crate` (eg, `crate::foo::Bar`), or `None` if
+	/// the item isn't part of the tree or doesn't have a name (eg, impl blocks or `use`
+	/// declarations). The item is looked up in every module of the tree, including inline ones.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use rustilities::parsing::source_tree::SourceTree;
+	/// use syn::{Path, parse_quote};
+	///
+	/// let tempdir = tempfile::tempdir().unwrap();
+	/// std::fs::write(tempdir.path().join("lib.rs"), "mod foo { pub struct Bar; }").unwrap();
+	///
+	/// let tree = SourceTree::load(tempdir.path().join("lib.rs")).unwrap();
+	/// let expected: Path = parse_quote!(crate::foo::Bar);
+	/// assert_eq!(tree.path_of(&parse_quote!(pub struct Bar;)), Some(expected));
+	/// ```
+	pub fn path_of(&self, item: &Item) -> Option<SynPath> {
+		fn find(
+			items: &[Item],
+			target: &Item,
+			module_path: &mut Vec<String>,
+		) -> Option<Vec<String>> {
+			for candidate in items {
+				if std::ptr::eq(candidate, target) || candidate == target {
+					let mut path = module_path.clone();
+					path.push(item_name(candidate)?);
+					return Some(path);
+				}
+				if let Item::Mod(item_mod) = candidate &&
+					let Some((_, items)) = &item_mod.content
+				{
+					module_path.push(module_name(item_mod));
+					let path = find(items, target, module_path);
+					module_path.pop();
+					if path.is_some() {
+						return path;
+					}
+				}
+			}
+			None
+		}
+
+		let segments = self
+			.files
+			.iter()
+			.find_map(|file| find(&file.ast.items, item, &mut file.module_path.clone()))?;
+		Some(SynPath {
+			leading_colon: None,
+			segments: std::iter::once(Ident::new("crate", Span::call_site()))
+				.chain(segments.iter().map(|segment| {
+					syn::parse_str::<Ident>(segment)
+						.unwrap_or_else(|_| Ident::new_raw(segment, Span::call_site()))
+				}))
+				.map(PathSegment::from)
+				.collect(),
+		})
+	}
+
+	/// Finds an item of the tree by its path, such as `crate::foo::Bar`. The leading `crate` is
+	/// optional. Besides the items defined in a module, the path can go through the items
+	/// re-exported by a module with a non-private `use` declaration, including glob re-exports.
+	/// Re-exports pointing outside of the crate cannot be resolved.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use rustilities::parsing::source_tree::SourceTree;
+	/// use syn::{Item, parse_quote};
+	///
+	/// let tempdir = tempfile::tempdir().unwrap();
+	/// std::fs::write(
+	///     tempdir.path().join("lib.rs"),
+	///     "mod foo { pub struct Bar; } pub use foo::Bar as Baz;",
+	/// )
+	/// .unwrap();
+	///
+	/// let tree = SourceTree::load(tempdir.path().join("lib.rs")).unwrap();
+	/// let expected: Item = parse_quote!(pub struct Bar;);
+	/// assert_eq!(tree.find_item("crate::foo::Bar"), Some(&expected));
+	/// assert_eq!(tree.find_item("crate::Baz"), Some(&expected));
+	/// assert!(tree.find_item("crate::Bar").is_none());
+	/// ```
+	pub fn find_item(&self, path: &str) -> Option<&Item> {
+		let path = syn::parse_str::<SynPath>(path).ok()?;
+		let mut segments = path
+			.segments
+			.iter()
+			.map(|segment| segment.ident.unraw().to_string())
+			.collect::<Vec<_>>();
+		if segments.first().is_some_and(|segment| segment == "crate") {
+			segments.remove(0);
+		}
+		self.resolve(&[], &segments, MAX_REEXPORT_DEPTH)
+	}
+
+	/// The items of a module, either defined in its own file or inline.
+	fn module_items(&self, module_path: &[String]) -> Option<&[Item]> {
+		self.files.iter().find_map(|file| {
+			let inline_path = module_path.strip_prefix(file.module_path.as_slice())?;
+			inline_path.iter().try_fold(file.ast.items.as_slice(), |items, name| {
+				items.iter().find_map(|item| match item {
+					Item::Mod(item_mod) if module_name(item_mod) == *name =>
+						item_mod.content.as_ref().map(|(_, items)| items.as_slice()),
+					_ => None,
+				})
+			})
+		})
+	}
+
+	/// Resolves the path `segments`, relative to the module `module_path`, following at most
+	/// `depth` re-exports.
+	fn resolve(&self, module_path: &[String], segments: &[String], depth: usize) -> Option<&Item> {
+		let (first, rest) = segments.split_first()?;
+		let items = self.module_items(module_path)?;
+
+		if let Some(item) = items.iter().find(|item| item_name(item).as_ref() == Some(first)) {
+			match item {
+				_ if rest.is_empty() => return Some(item),
+				Item::Mod(_) => {
+					let mut child_module_path = module_path.to_vec();
+					child_module_path.push(first.clone());
+					return self.resolve(&child_module_path, rest, depth);
+				},
+				_ => return None,
+			}
+		}
+
+		let depth = depth.checked_sub(1)?;
+		let mut reexports = Vec::new();
+		for item in items {
+			if let Item::Use(item_use) = item &&
+				!matches!(item_use.vis, Visibility::Inherited)
+			{
+				use_bindings(&item_use.tree, &mut Vec::new(), &mut reexports);
+			}
+		}
+		reexports.into_iter().find_map(|(binding, target)| {
+			let target = absolute_path(module_path, &target)?;
+			match binding {
+				Some(binding) if binding == *first =>
+					self.resolve(&[], &[target, rest.to_vec()].concat(), depth),
+				Some(_) => None,
+				None => self.resolve(&target, segments, depth),
+			}
+		})
+	}
+}
+
+fn module_name(item_mod: &ItemMod) -> String {
+	item_mod.ident.unraw().to_string()
+}
+
+/// The name an item is declared with, if any.
+pub(super) fn item_name(item: &Item) -> Option<String> {
+	let ident = match item {
+		Item::Const(item) => &item.ident,
+		Item::Enum(item) => &item.ident,
+		Item::ExternCrate(item) => item.rename.as_ref().map_or(&item.ident, |(_, rename)| rename),
+		Item::Fn(item) => &item.sig.ident,
+		Item::Macro(item) => item.ident.as_ref()?,
+		Item::Mod(item) => &item.ident,
+		Item::Static(item) => &item.ident,
+		Item::Struct(item) => &item.ident,
+		Item::Trait(item) => &item.ident,
+		Item::TraitAlias(item) => &item.ident,
+		Item::Type(item) => &item.ident,
+		Item::Union(item) => &item.ident,
+		_ => return None,
+	};
+	Some(ident.unraw().to_string())
+}
+
+/// Collects the names bound by a use tree together with the path they point to. Glob imports are
+/// collected without a name, pointing to the globbed module.
+pub(super) fn use_bindings(
+	tree: &UseTree,
+	prefix: &mut Vec<String>,
+	bindings: &mut Vec<(Option<String>, Vec<String>)>,
+) {
+	// `self` imports the module named by the prefix, and it's bound to that name unless renamed
+	let mut bind = |ident: &Ident, rename: Option<&Ident>| {
+		let mut target = prefix.clone();
+		if ident != "self" {
+			target.push(ident.unraw().to_string());
+		}
+		let binding = rename.map(|rename| rename.unraw().to_string()).or(target.last().cloned());
+		bindings.push((binding, target));
+	};
+	match tree {
+		UseTree::Path(path) => {
+			prefix.push(path.ident.unraw().to_string());
+			use_bindings(&path.tree, prefix, bindings);
+			prefix.pop();
+		},
+		UseTree::Name(name) => bind(&name.ident, None),
+		UseTree::Rename(rename) => bind(&rename.ident, Some(&rename.rename)),
+		UseTree::Glob(_) => bindings.push((None, prefix.clone())),
+		UseTree::Group(group) =>
+			group.items.iter().for_each(|tree| use_bindings(tree, prefix, bindings)),
+	}
+}
+
+/// Turns a use path, found in the module `module_path`, into a path relative to the crate root,
+/// resolving its leading `crate`, `self` and `super` segments. Paths without those segments are
+/// deemed relative to the module.
+fn absolute_path(module_path: &[String], path: &[String]) -> Option<Vec<String>> {
+	let mut absolute = module_path.to_vec();
+	let mut segments = path.iter().peekable();
+	match segments.peek().map(|segment| segment.as_str()) {
+		Some("crate") => {
+			absolute.clear();
+			segments.next();
+		},
+		Some("self") => {
+			segments.next();
+		},
+		_ => (),
+	}
+	while segments.next_if(|segment| *segment == "super").is_some() {
+		absolute.pop()?;
+	}
+	absolute.extend(segments.cloned());
+	Some(absolute)
+}
+
+fn load_file(
+	path: &Path,
+	module_dir: PathBuf,
+	module_path: Vec<String>,
+	files: &mut Vec<SourceFile>,
+	parse: &ParseFile,
+) -> Result<(), Error> {
+	let ast = parse(path)?;
+	let items = ast.items.clone();
+	let file_dir = path.parent().expect("A file always lives inside a dir; qed");
+	files.push(SourceFile { path: path.to_path_buf(), module_path: module_path.clone(), ast });
+	load_modules(&items, &module_dir, file_dir, &module_path, files, parse)
+}
+
+/// Reads and parses a source file. It's the [`ParseFile`] used by default to load trees.
+pub(crate) fn parse_file(path: &Path) -> Result<syn::File, Error> {
+	debug!(path = %path.display(), "Loading source file");
+	Ok(syn::parse_file(&std::fs::read_to_string(path)?)?)
+}
+
+/// Loads the files of the modules declared in `items`. `module_dir` is the dir where the files of
+/// those modules live by default, while `path_attr_dir` is the dir `#[path]` attributes are
+/// relative to.
+fn load_modules(
+	items: &[Item],
+	module_dir: &Path,
+	path_attr_dir: &Path,
+	module_path: &[String],
+	files: &mut Vec<SourceFile>,
+	parse: &ParseFile,
+) -> Result<(), Error> {
+	for item in items {
+		let Item::Mod(item_mod) = item else { continue };
+		let name = module_name(item_mod);
+		let mut child_module_path = module_path.to_vec();
+		child_module_path.push(name.clone());
+
+		match &item_mod.content {
+			Some((_, items)) => {
+				let child_module_dir = module_dir.join(&name);
+				load_modules(
+					items,
+					&child_module_dir,
+					&child_module_dir,
+					&child_module_path,
+					files,
+					parse,
+				)?
+			},
+			None => {
+				let Some((file_path, is_mod_rs)) =
+					module_file(item_mod, module_dir, path_attr_dir, &name)
+				else {
+					debug!(module = name, "Module file not found, skipping it");
+					continue;
+				};
+				// Modules defined in a `mod.rs` file (or in a file pointed by a `#[path]`
+				// attribute) keep their submodules files in the same dir, while the others use a
+				// dir named after the module.
+				let child_module_dir = if is_mod_rs {
+					file_path.parent().expect("A file always lives inside a dir; qed").to_path_buf()
+				} else {
+					module_dir.join(&name)
+				};
+				load_file(&file_path, child_module_dir, child_module_path, files, parse)?;
+			},
+		}
+	}
+	Ok(())
+}
+
+/// Finds the file defining a module, and whether it has to be treated as a `mod.rs` file.
+fn module_file(
+	item_mod: &ItemMod,
+	module_dir: &Path,
+	path_attr_dir: &Path,
+	name: &str,
+) -> Option<(PathBuf, bool)> {
+	let path_attr = item_mod.attrs.iter().find_map(|attr| match &attr.meta {
+		Meta::NameValue(MetaNameValue {
+			path,
+			value: Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }),
+			..
+		}) if path.is_ident("path") => Some(lit.value()),
+		_ => None,
+	});
+
+	match path_attr {
+		Some(path) => Some((path_attr_dir.join(path), true)).filter(|(path, _)| path.is_file()),
+		None => [
+			(module_dir.join(format!("{name}.rs")), false),
+			(module_dir.join(name).join("mod.rs"), true),
+		]
+		.into_iter()
+		.find(|(path, _)| path.is_file()),
+	}
+}
@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use syn::parse_quote;
+use tempfile::TempDir;
+
+fn crate_with_files(files: &[(&str, &str)]) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	for (path, content) in files {
+		let path = tempdir.path().join(path);
+		std::fs::create_dir_all(path.parent().expect("A file always lives inside a dir; qed"))
+			.expect("This should be created; qed;");
+		std::fs::write(path, content).expect("The file should be writable; qed;");
+	}
+	tempdir
+}
+
+fn module_paths(tree: &SourceTree) -> Vec<(String, PathBuf)> {
+	tree.files()
+		.iter()
+		.map(|file| (file.module_path.join("::"), file.path.clone()))
+		.collect()
+}
+
+#[test]
+fn load_follows_mod_declarations() {
+	let tempdir = crate_with_files(&[
+		("src/lib.rs", "mod a; pub mod b; mod r#c; mod inline { mod d; }"),
+		("src/a.rs", "mod nested;"),
+		("src/a/nested.rs", "struct Nested;"),
+		("src/b/mod.rs", "mod nested;"),
+		("src/b/nested.rs", ""),
+		("src/c.rs", ""),
+		("src/inline/d.rs", ""),
+	]);
+	let src = tempdir.path().join("src");
+
+	let tree = SourceTree::load(src.join("lib.rs")).expect("The tree should be loaded; qed;");
+	assert_eq!(tree.root(), src.join("lib.rs"));
+	assert_eq!(
+		module_paths(&tree),
+		vec![
+			("".to_owned(), src.join("lib.rs")),
+			("a".to_owned(), src.join("a.rs")),
+			("a::nested".to_owned(), src.join("a/nested.rs")),
+			("b".to_owned(), src.join("b/mod.rs")),
+			("b::nested".to_owned(), src.join("b/nested.rs")),
+			("c".to_owned(), src.join("c.rs")),
+			("inline::d".to_owned(), src.join("inline/d.rs")),
+		]
+	);
+	assert_eq!(
+		tree.file(src.join("a/nested.rs")).map(|file| &file.module_path),
+		Some(&vec!["a".to_owned(), "nested".to_owned()])
+	);
+	assert!(tree.file(src.join("unexisting.rs")).is_none());
+	assert_eq!(tree.module_names(), vec!["a", "b", "c", "d", "inline", "nested"]);
+}
+
+#[test]
+fn load_honors_path_attributes_and_skips_missing_modules() {
+	let tempdir = crate_with_files(&[
+		("src/lib.rs", "#[path = \"other/file.rs\"] mod a; #[cfg(windows)] mod missing;"),
+		("src/other/file.rs", "mod nested;"),
+		("src/other/nested.rs", ""),
+	]);
+	let src = tempdir.path().join("src");
+
+	let tree = SourceTree::load(src.join("lib.rs")).expect("The tree should be loaded; qed;");
+	assert_eq!(
+		module_paths(&tree),
+		vec![
+			("".to_owned(), src.join("lib.rs")),
+			("a".to_owned(), src.join("other/file.rs")),
+			("a::nested".to_owned(), src.join("other/nested.rs")),
+		]
+	);
+}
+
+#[test]
+fn load_crate_loads_every_target() {
+	let tempdir = crate_with_files(&[
+		("src/lib.rs", ""),
+		("src/main.rs", ""),
+		("src/bin/tool.rs", ""),
+		("src/bin/other/main.rs", "mod helper;"),
+		("src/bin/other/helper.rs", ""),
+		("src/bin/README.md", ""),
+	]);
+	let src = tempdir.path().join("src");
+
+	let trees = SourceTree::load_crate(tempdir.path()).expect("The trees should be loaded; qed;");
+	assert_eq!(
+		trees.iter().map(|tree| tree.root().to_path_buf()).collect::<Vec<_>>(),
+		vec![
+			src.join("lib.rs"),
+			src.join("main.rs"),
+			src.join("bin/other/main.rs"),
+			src.join("bin/tool.rs")
+		]
+	);
+	assert_eq!(trees[2].files().len(), 2);
+}
+
+#[test]
+fn load_fails_if_file_cannot_be_parsed() {
+	let tempdir = crate_with_files(&[("src/lib.rs", "mod a;"), ("src/a.rs", "fn {")]);
+	assert!(matches!(SourceTree::load(tempdir.path().join("src/lib.rs")), Err(Error::Syn(_))));
+}
+
+#[test]
+fn load_fails_if_root_file_doesnt_exist() {
+	let tempdir = crate_with_files(&[]);
+	assert!(matches!(
+		SourceTree::load(tempdir.path().join("src/lib.rs")),
+		Err(Error::IO(err)) if err.kind() == std::io::ErrorKind::NotFound
+	));
+}
+
+#[test]
+fn path_of_finds_items_in_files_and_inline_modules() {
+	let tempdir = crate_with_files(&[
+		("src/lib.rs", "mod a; mod inline { mod r#type { fn f() {} } }"),
+		("src/a.rs", "struct A; impl A {}"),
+	]);
+	let tree = SourceTree::load(tempdir.path().join("src/lib.rs"))
+		.expect("The tree should be loaded; qed;");
+
+	let expected: SynPath = parse_quote!(crate::a::A);
+	assert_eq!(
+		tree.path_of(&parse_quote!(
+			struct A;
+		)),
+		Some(expected)
+	);
+	let expected: SynPath = parse_quote!(crate::inline::r#type::f);
+	assert_eq!(
+		tree.path_of(&parse_quote!(
+			fn f() {}
+		)),
+		Some(expected)
+	);
+	let file_item = &tree.files()[1].ast.items[0];
+	let expected: SynPath = parse_quote!(crate::a::A);
+	assert_eq!(tree.path_of(file_item), Some(expected));
+
+	assert!(tree.path_of(&parse_quote!(impl A {})).is_none());
+	assert!(
+		tree.path_of(&parse_quote!(
+			struct B;
+		))
+		.is_none()
+	);
+}
+
+#[test]
+fn find_item_follows_modules_and_reexports() {
+	let tempdir = crate_with_files(&[
+		(
+			"src/lib.rs",
+			"pub mod a; mod inline { pub use crate::a::{self as alias, A as Renamed}; } pub use \
+			 glob::*; mod glob { pub use super::inline::*; } use a::A as Private;",
+		),
+		("src/a.rs", "pub struct A; pub mod nested { pub fn f() {} }"),
+	]);
+	let tree = SourceTree::load(tempdir.path().join("src/lib.rs"))
+		.expect("The tree should be loaded; qed;");
+	let a: Item = parse_quote!(
+		pub struct A;
+	);
+	let f: Item = parse_quote!(
+		pub fn f() {}
+	);
+
+	assert_eq!(tree.find_item("crate::a::A"), Some(&a));
+	assert_eq!(tree.find_item("a::nested::f"), Some(&f));
+	assert_eq!(tree.find_item("crate::inline::Renamed"), Some(&a));
+	assert_eq!(tree.find_item("crate::inline::alias::nested::f"), Some(&f));
+	assert_eq!(tree.find_item("crate::Renamed"), Some(&a));
+	assert_eq!(tree.find_item("crate::alias::nested::f"), Some(&f));
+
+	assert!(tree.find_item("crate::Private").is_none());
+	assert!(tree.find_item("crate::a::A::new").is_none());
+	assert!(tree.find_item("crate::a::B").is_none());
+	assert!(tree.find_item("not a path").is_none());
+}
+
+#[test]
+fn find_item_stops_on_cyclic_reexports() {
+	let tempdir = crate_with_files(&[(
+		"src/lib.rs",
+		"mod a { pub use super::b::*; } mod b { pub use super::a::*; }",
+	)]);
+	let tree = SourceTree::load(tempdir.path().join("src/lib.rs"))
+		.expect("The tree should be loaded; qed;");
+
+	assert!(tree.find_item("crate::a::Missing").is_none());
+}
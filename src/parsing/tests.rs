@@ -73,6 +73,53 @@ fn extract_generics_with_bounds_and_where_clause() {
 	);
 }
 
+#[test]
+fn extract_generics_strips_attrs() {
+	let input: Generics = parse_quote! {
+		<#[cfg(a)] 'a, #[cfg(b)] T: Clone, #[cfg(c)] const N: usize>
+	};
+
+	let output_declarations: Punctuated<GenericParam, Token![,]> =
+		parse_quote! {'a, T, const N: usize};
+	let output_idents: Punctuated<GenericParam, Token![,]> = parse_quote! {'a, T, N};
+	let output_where_clause: WhereClause = parse_quote! {where T: Clone};
+
+	assert_eq!(
+		(output_declarations, output_idents, Some(output_where_clause)),
+		extract_generics(&input)
+	);
+}
+
+#[test]
+fn extract_generics_with_attrs_preserves_or_collects_attrs() {
+	let input: Generics = parse_quote! {
+		<#[cfg(a)] 'a, #[cfg(b)] #[allow(c)] T: Clone, const N: usize>
+	};
+
+	let preserved = extract_generics_with_attrs(&input, GenericParamAttrs::Preserve);
+	let output_declarations: Punctuated<GenericParam, Token![,]> =
+		parse_quote! {#[cfg(a)] 'a, #[cfg(b)] #[allow(c)] T, const N: usize};
+	let output_idents: Punctuated<GenericParam, Token![,]> =
+		parse_quote! {#[cfg(a)] 'a, #[cfg(b)] #[allow(c)] T, N};
+	assert_eq!(preserved.declarations, output_declarations);
+	assert_eq!(preserved.idents, output_idents);
+	assert_eq!(preserved.where_clause, Some(parse_quote! {where T: Clone}));
+	assert!(preserved.attrs.is_empty());
+
+	let collected = extract_generics_with_attrs(&input, GenericParamAttrs::Collect);
+	let output_declarations: Punctuated<GenericParam, Token![,]> =
+		parse_quote! {'a, T, const N: usize};
+	assert_eq!(collected.declarations, output_declarations);
+	assert_eq!(
+		collected.attrs,
+		vec![
+			vec![parse_quote!(#[cfg(a)])],
+			vec![parse_quote!(#[cfg(b)]), parse_quote!(#[allow(c)])],
+			vec![]
+		]
+	);
+}
+
 #[test]
 fn compare_ident_equal() {
 	let id1 = TokenTree::Ident(Ident::new("foo", Span::call_site()));
@@ -560,3 +607,276 @@ fn contained_empty_token_stream() {
 	let stream2 = TokenStream::new();
 	assert!(syntactic_token_stream_contains(stream1, stream2));
 }
+
+#[test]
+fn ensure_struct_works() {
+	let input: DeriveInput = parse_quote! { struct Unit; };
+	assert!(matches!(ensure_struct(&input), Ok(DataStruct { fields: Fields::Unit, .. })));
+
+	let input: DeriveInput = parse_quote! { enum E { A } };
+	let err = ensure_struct(&input).expect_err("This should be Err; qed;");
+	assert_eq!(err.to_string(), "expected a struct");
+	assert!(err.to_compile_error().to_string().contains("expected a struct"));
+
+	let input: DeriveInput = parse_quote! { union U { a: u8 } };
+	assert_eq!(
+		ensure_struct(&input).expect_err("This should be Err; qed;").to_string(),
+		"expected a struct"
+	);
+}
+
+#[test]
+fn ensure_enum_works() {
+	let input: DeriveInput = parse_quote! { enum E { A, B(u8), C { c: u8 } } };
+	assert_eq!(ensure_enum(&input).expect("This should be Ok; qed;").variants.len(), 3);
+
+	["struct S;", "union U { a: u8 }"].into_iter().for_each(|item| {
+		let input: DeriveInput = syn::parse_str(item).expect("This should be Ok; qed;");
+		assert_eq!(
+			ensure_enum(&input).expect_err("This should be Err; qed;").to_string(),
+			"expected an enum"
+		);
+	});
+}
+
+#[test]
+fn ensure_named_fields_works() {
+	let input: DeriveInput = parse_quote! { struct S<T> { a: T, b: u8 } };
+	let fields = ensure_named_fields(&input).expect("This should be Ok; qed;");
+	assert_eq!(
+		fields.named.iter().filter_map(|field| field.ident.as_ref()).collect::<Vec<_>>(),
+		vec!["a", "b"]
+	);
+
+	["struct S(u8);", "struct S;", "enum E { A }", "union U { a: u8 }"]
+		.into_iter()
+		.for_each(|item| {
+			let input: DeriveInput = syn::parse_str(item).expect("This should be Ok; qed;");
+			assert_eq!(
+				ensure_named_fields(&input).expect_err("This should be Err; qed;").to_string(),
+				"expected a struct with named fields"
+			);
+		});
+}
+
+#[test]
+fn fresh_type_param_avoids_generics_and_token_idents() {
+	let mut generics: Generics = parse_quote! { <'T, T: Bound<T1>, const T2: usize> };
+	generics.where_clause = Some(parse_quote! { where T3: Clone });
+	let tokens: TokenStream = "impl Foo { type T4 = (T5, [u8; T6]); }"
+		.parse()
+		.expect("This should be Ok; qed;");
+
+	assert_eq!(fresh_type_param(&generics, "T", &tokens), "T7");
+	assert_eq!(fresh_type_param(&generics, "Foo", &tokens), "Foo1");
+	assert_eq!(fresh_type_param(&generics, "U", &tokens), "U");
+	assert_eq!(fresh_type_param(&Generics::default(), "T", &TokenStream::new()), "T");
+}
+
+#[test]
+fn fresh_lifetime_avoids_generics_and_token_lifetimes() {
+	let generics: Generics = parse_quote! { <'a, 'a1: 'a, T: 'a2> };
+	let tokens: TokenStream = "fn f<'a3>() {}".parse().expect("This should be Ok; qed;");
+
+	assert_eq!(fresh_lifetime(&generics, "'a", &tokens), Lifetime::new("'a4", Span::call_site()));
+	assert_eq!(fresh_lifetime(&generics, "b", &tokens), Lifetime::new("'b", Span::call_site()));
+}
+
+#[test]
+fn insert_param_keeps_conventional_order() {
+	let mut generics = Generics::default();
+	insert_param(&mut generics, parse_quote! { const N: usize });
+	insert_param(&mut generics, parse_quote! { T });
+	insert_param(&mut generics, parse_quote! { 'a });
+	insert_param(&mut generics, parse_quote! { U });
+	insert_param(&mut generics, parse_quote! { 'b });
+	insert_param(&mut generics, parse_quote! { const M: u8 });
+
+	let expected: Generics = parse_quote! { <'a, 'b, T, U, const N: usize, const M: u8> };
+	assert_eq!(generics.params, expected.params);
+}
+
+#[test]
+fn turbofish_and_strip_turbofish_are_inverse() {
+	let type_form: Path = parse_quote! { a::B<T, Vec<U>>::C::D<'a> };
+	let expr_form: Path = parse_quote! { a::B::<T, Vec<U>>::C::D::<'a> };
+
+	assert_eq!(turbofish(&type_form), expr_form);
+	assert_eq!(turbofish(&expr_form), expr_form);
+	assert_eq!(strip_turbofish(&expr_form), type_form);
+	assert_eq!(strip_turbofish(&type_form), type_form);
+}
+
+#[test]
+fn strip_generic_args_removes_every_argument() {
+	let path: Path = parse_quote! { ::a::B<T>::C::<U>::D };
+	let expected: Path = parse_quote! { ::a::B::C::D };
+	assert_eq!(strip_generic_args(&path), expected);
+
+	let fn_trait: syn::TraitBound = parse_quote! { FnOnce(u8) -> u8 };
+	let expected: Path = parse_quote! { FnOnce };
+	assert_eq!(strip_generic_args(&fn_trait.path), expected);
+}
+
+#[test]
+fn qualified_method_path_works_with_turbofish_trait_paths() {
+	let ty: Type = parse_quote! { Vec<T> };
+	let trait_path: Path = parse_quote! { Extend::<&'a T> };
+	let method: Ident = parse_quote! { extend };
+	let expected: ExprPath = parse_quote! { <Vec<T> as Extend<&'a T>>::extend };
+
+	assert_eq!(qualified_method_path(&ty, &trait_path, &method), expected);
+}
+
+#[test]
+fn group_at_navigates_nested_groups() {
+	let stream: TokenStream = "a (b [c] {d (e)}) f [g]".parse().expect("This should be Ok; qed;");
+
+	assert!(syntactic_token_stream_compare(
+		group_at(stream.clone(), &[]).expect("This should be Some; qed;"),
+		stream.clone()
+	));
+	assert_eq!(group_at(stream.clone(), &[1]).map(|group| group.to_string()), Some("g".to_owned()));
+	assert_eq!(
+		group_at(stream.clone(), &[0, 1, 0]).map(|group| group.to_string()),
+		Some("e".to_owned())
+	);
+	assert!(group_at(stream.clone(), &[0, 2]).is_none());
+	assert!(group_at(stream, &[1, 0]).is_none());
+}
+
+#[test]
+fn groups_paths_agree_with_group_at() {
+	let stream: TokenStream = "a (b [c] {d (e)}) f [g]".parse().expect("This should be Ok; qed;");
+
+	let groups = groups(stream.clone()).collect::<Vec<_>>();
+
+	assert_eq!(
+		groups.iter().map(|group| (group.delimiter, group.depth)).collect::<Vec<_>>(),
+		vec![
+			(Delimiter::Parenthesis, 0),
+			(Delimiter::Bracket, 1),
+			(Delimiter::Brace, 1),
+			(Delimiter::Parenthesis, 2),
+			(Delimiter::Bracket, 0),
+		]
+	);
+	groups.into_iter().for_each(|group| {
+		assert!(syntactic_token_stream_compare(
+			group_at(stream.clone(), &group.path).expect("This should be Some; qed;"),
+			group.stream
+		));
+	});
+}
+
+#[test]
+fn groups_of_stream_without_groups_is_empty() {
+	let stream: TokenStream = "a b c".parse().expect("This should be Ok; qed;");
+	assert_eq!(groups(stream).count(), 0);
+}
+
+#[test]
+fn items_equivalent_is_strict_by_default() {
+	let a: Item = parse_quote! {
+		/// Docs.
+		#[inline]
+		fn f() {}
+	};
+	let b: Item = parse_quote! {
+		/// Docs.
+		#[inline]
+		fn f() {}
+	};
+	let c: Item = parse_quote! {
+		#[inline]
+		fn f() {}
+	};
+
+	assert!(items_equivalent(&a, &b, Equivalences::default()));
+	assert!(!items_equivalent(&a, &c, Equivalences::default()));
+}
+
+#[test]
+fn items_equivalent_ignores_nested_docs() {
+	let a: Item = parse_quote! {
+		impl S {
+			//! Inner docs.
+
+			/// Method docs.
+			#[inline]
+			fn f(&self) {}
+		}
+	};
+	let b: Item = parse_quote! {
+		impl S {
+			#[inline]
+			fn f(&self) {}
+		}
+	};
+	let c: Item = parse_quote! {
+		impl S {
+			fn f(&self) {}
+		}
+	};
+
+	assert!(items_equivalent(&a, &b, Equivalence::IgnoreDocs));
+	assert!(!items_equivalent(&a, &c, Equivalence::IgnoreDocs));
+	assert!(items_equivalent(&a, &c, Equivalence::IgnoreAttrs));
+}
+
+#[test]
+fn items_equivalent_ignores_attr_order_only_between_consecutive_attrs() {
+	let a: Item = parse_quote! {
+		#[a]
+		#[b]
+		struct S {
+			#[c]
+			#[d]
+			field: u8,
+		}
+	};
+	let b: Item = parse_quote! {
+		#[b]
+		#[a]
+		struct S {
+			#[d]
+			#[c]
+			field: u8,
+		}
+	};
+	let c: Item = parse_quote! {
+		#[b]
+		#[c]
+		struct S {
+			#[a]
+			#[d]
+			field: u8,
+		}
+	};
+
+	assert!(!items_equivalent(&a, &b, Equivalence::IgnoreDocs));
+	assert!(items_equivalent(&a, &b, Equivalence::IgnoreAttrOrder));
+	assert!(!items_equivalent(&a, &c, Equivalence::IgnoreAttrOrder));
+}
+
+#[test]
+fn items_equivalent_combines_equivalences() {
+	let a: Item = parse_quote! {
+		/// Docs.
+		#[a]
+		#[b]
+		struct S;
+	};
+	let b: Item = parse_quote! {
+		#[b]
+		/// Other docs.
+		#[a]
+		struct S;
+	};
+
+	let equivalences = Equivalence::IgnoreDocs | Equivalence::IgnoreAttrOrder;
+	assert!(equivalences.contains(Equivalence::IgnoreDocs));
+	assert!(!equivalences.contains(Equivalence::IgnoreAttrs));
+	assert!(!items_equivalent(&a, &b, Equivalence::IgnoreAttrOrder));
+	assert!(items_equivalent(&a, &b, equivalences));
+}
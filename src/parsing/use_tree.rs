@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! This module provides utilities to work with [`UseTree`]s, such as flattening them into the
+//! single paths they import, merging several of them into the smallest set of trees importing the
+//! same paths, or rendering them as they're usually written in source code.
+
+#[cfg(test)]
+mod tests;
+
+use proc_macro2::Ident;
+use std::collections::HashSet;
+use syn::{UseGroup, UseName, UsePath, UseTree, punctuated::Punctuated};
+
+/// Flattens a [`UseTree`] into the trees importing each of its paths separately, in the order they
+/// appear. None of the returned trees contain a group, except for those importing `self`, which
+/// keep it as `a::{self}` isn't valid without braces.
+///
+/// # Example
+///
+/// ```
+/// use syn::{UseTree, parse_quote};
+///
+/// let tree: UseTree = parse_quote!(std::{fmt, io::{self, Read as _}});
+/// let flattened = rustilities::parsing::use_tree::flatten_use_tree(&tree)
+///     .iter()
+///     .map(rustilities::parsing::use_tree::render_use_tree)
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(flattened, vec!["std::fmt", "std::io::{self}", "std::io::Read as _"]);
+/// ```
+pub fn flatten_use_tree(tree: &UseTree) -> Vec<UseTree> {
+	let mut leaves = Vec::new();
+	collect_leaves(tree, &mut Vec::new(), &mut leaves);
+	leaves
+		.into_iter()
+		.map(|(prefix, leaf)| build_path(&prefix, vec![leaf]))
+		.collect()
+}
+
+/// Merges several [`UseTree`]s into the smallest set of trees importing the same paths, one per
+/// distinct first segment. Duplicated paths are removed and the imports of each group are sorted,
+/// placing `self` first and globs last.
+///
+/// # Example
+///
+/// ```
+/// use syn::{UseTree, parse_quote};
+///
+/// let trees: Vec<UseTree> = vec![
+///     parse_quote!(std::io::Read),
+///     parse_quote!(crate::Error),
+///     parse_quote!(std::{fmt, io}),
+///     parse_quote!(std::io::Read),
+/// ];
+/// let merged = rustilities::parsing::use_tree::merge_use_trees(&trees)
+///     .iter()
+///     .map(rustilities::parsing::use_tree::render_use_tree)
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(merged, vec!["crate::Error", "std::{fmt, io::{self, Read}}"]);
+/// ```
+pub fn merge_use_trees<'a, I: IntoIterator<Item = &'a UseTree>>(trees: I) -> Vec<UseTree> {
+	let mut leaves = Vec::new();
+	for tree in trees {
+		collect_leaves(tree, &mut Vec::new(), &mut leaves);
+	}
+
+	// Names also used as the prefix of other paths are modules, imported as `module::{self}` to
+	// merge them with those paths
+	let modules = leaves
+		.iter()
+		.flat_map(|(prefix, _)| (1..=prefix.len()).map(|len| path_key(&prefix[..len])))
+		.collect::<HashSet<_>>();
+	for (prefix, leaf) in &mut leaves {
+		if let UseTree::Name(name) = leaf &&
+			name.ident != "self"
+		{
+			prefix.push(name.ident.clone());
+			if modules.contains(&path_key(prefix)) {
+				name.ident = Ident::new("self", name.ident.span());
+			} else {
+				prefix.pop();
+			}
+		}
+	}
+	build_trees(leaves)
+}
+
+/// Renders a [`UseTree`] as it's usually written in source code, eg, `std::{fmt, io::Read}`.
+///
+/// # Example
+///
+/// ```
+/// use syn::{UseTree, parse_quote};
+///
+/// let tree: UseTree = parse_quote!(std :: { fmt , io :: Read as _ });
+///
+/// assert_eq!(rustilities::parsing::use_tree::render_use_tree(&tree), "std::{fmt, io::Read as _}");
+/// ```
+pub fn render_use_tree(tree: &UseTree) -> String {
+	match tree {
+		UseTree::Path(path) => format!("{}::{}", path.ident, render_use_tree(&path.tree)),
+		UseTree::Name(name) => name.ident.to_string(),
+		UseTree::Rename(rename) => format!("{} as {}", rename.ident, rename.rename),
+		UseTree::Glob(_) => "*".to_owned(),
+		UseTree::Group(group) => format!(
+			"{{{}}}",
+			group.items.iter().map(render_use_tree).collect::<Vec<_>>().join(", ")
+		),
+	}
+}
+
+/// Collects the paths imported by a tree, as their prefix and the final name, rename or glob.
+fn collect_leaves(
+	tree: &UseTree,
+	prefix: &mut Vec<Ident>,
+	leaves: &mut Vec<(Vec<Ident>, UseTree)>,
+) {
+	match tree {
+		UseTree::Path(path) => {
+			prefix.push(path.ident.clone());
+			collect_leaves(&path.tree, prefix, leaves);
+			prefix.pop();
+		},
+		UseTree::Group(group) =>
+			group.items.iter().for_each(|tree| collect_leaves(tree, prefix, leaves)),
+		leaf => leaves.push((prefix.clone(), leaf.clone())),
+	}
+}
+
+/// Builds the trees importing the given paths, merging those sharing a prefix.
+fn build_trees(mut leaves: Vec<(Vec<Ident>, UseTree)>) -> Vec<UseTree> {
+	leaves.sort_by_cached_key(|(prefix, leaf)| (path_key(prefix), leaf_order(leaf)));
+	leaves.dedup_by(|(prefix1, leaf1), (prefix2, leaf2)| {
+		prefix1 == prefix2 && render_use_tree(leaf1) == render_use_tree(leaf2)
+	});
+
+	let mut trees = Vec::new();
+	let mut leaves = leaves.into_iter().peekable();
+	while let Some((prefix, leaf)) = leaves.next() {
+		let Some(first) = prefix.first().cloned() else {
+			trees.push(leaf);
+			continue;
+		};
+		let mut children = vec![(prefix[1..].to_vec(), leaf)];
+		while let Some((prefix, leaf)) =
+			leaves.next_if(|(prefix, _)| prefix.first() == Some(&first))
+		{
+			children.push((prefix[1..].to_vec(), leaf));
+		}
+		trees.push(build_path(&[first], build_trees(children)));
+	}
+	trees.sort_by_cached_key(leaf_order);
+	trees
+}
+
+/// Builds the tree `prefix::tree`, or `prefix::{trees}` if there are several trees or the only one
+/// imports `self`.
+fn build_path(prefix: &[Ident], mut trees: Vec<UseTree>) -> UseTree {
+	let imports_self = |tree: &UseTree| match tree {
+		UseTree::Name(name) => name.ident == "self",
+		UseTree::Rename(rename) => rename.ident == "self",
+		_ => false,
+	};
+	let tree = if trees.len() == 1 && (prefix.is_empty() || !imports_self(&trees[0])) {
+		trees.remove(0)
+	} else {
+		UseTree::Group(UseGroup {
+			brace_token: Default::default(),
+			items: trees.into_iter().collect::<Punctuated<_, _>>(),
+		})
+	};
+	prefix.iter().rev().fold(tree, |tree, ident| {
+		UseTree::Path(UsePath {
+			ident: ident.clone(),
+			colon2_token: Default::default(),
+			tree: Box::new(tree),
+		})
+	})
+}
+
+fn path_key(path: &[Ident]) -> Vec<String> {
+	path.iter().map(Ident::to_string).collect()
+}
+
+/// The key sorting the trees of a group: `self` first, then by name and globs last.
+fn leaf_order(tree: &UseTree) -> (u8, String) {
+	match tree {
+		UseTree::Name(UseName { ident }) if ident == "self" => (0, String::new()),
+		UseTree::Glob(_) => (2, String::new()),
+		tree => (1, render_use_tree(tree)),
+	}
+}
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use syn::parse_quote;
+
+fn render_all(trees: &[UseTree]) -> Vec<String> {
+	trees.iter().map(render_use_tree).collect()
+}
+
+#[test]
+fn flatten_use_tree_splits_groups() {
+	let tree: UseTree = parse_quote!(crate::{a::{self, b::*, C as D}, E});
+
+	assert_eq!(
+		render_all(&flatten_use_tree(&tree)),
+		vec!["crate::a::{self}", "crate::a::b::*", "crate::a::C as D", "crate::E"]
+	);
+}
+
+#[test]
+fn flatten_use_tree_keeps_single_paths() {
+	let tree: UseTree = parse_quote!(Foo);
+	assert_eq!(render_all(&flatten_use_tree(&tree)), vec!["Foo"]);
+}
+
+#[test]
+fn merge_use_trees_groups_by_prefix_and_dedups() {
+	let trees: Vec<UseTree> = vec![
+		parse_quote!(std::collections::HashMap),
+		parse_quote!(syn::Item),
+		parse_quote!(std::{collections::{BTreeMap, HashMap}, path::*}),
+		parse_quote!(std::path::Path),
+		parse_quote!(syn::Item as SynItem),
+	];
+
+	assert_eq!(
+		render_all(&merge_use_trees(&trees)),
+		vec![
+			"std::{collections::{BTreeMap, HashMap}, path::{Path, *}}",
+			"syn::{Item, Item as SynItem}"
+		]
+	);
+}
+
+#[test]
+fn merge_use_trees_imports_modules_with_self() {
+	let trees: Vec<UseTree> =
+		vec![parse_quote!(std::io), parse_quote!(std::io::Read), parse_quote!(std::fmt)];
+
+	assert_eq!(render_all(&merge_use_trees(&trees)), vec!["std::{fmt, io::{self, Read}}"]);
+}
+
+#[test]
+fn merge_use_trees_keeps_single_names_and_globs() {
+	let trees: Vec<UseTree> = vec![parse_quote!(*), parse_quote!(Foo), parse_quote!(Bar)];
+
+	assert_eq!(render_all(&merge_use_trees(&trees)), vec!["Bar", "Foo", "*"]);
+}
+
+#[test]
+fn render_use_tree_normalizes_spacing() {
+	let tree: UseTree = parse_quote!(a :: { b :: c , d as e , * });
+	assert_eq!(render_use_tree(&tree), "a::{b::c, d as e, *}");
+}
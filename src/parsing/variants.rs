@@ -0,0 +1,315 @@
+// SPDX-License-Identifier: GPL-3.0
+
+// Functionalities reading the discriminants of enums and generating methods inspecting their
+// variants, as commonly done by derive macros.
+
+#[cfg(test)]
+mod tests;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use std::collections::{BTreeMap, HashMap};
+use syn::{
+	Attribute, Expr, ExprLit, ExprUnary, Fields, Ident, ItemEnum, ItemImpl, Lit, UnOp, Variant,
+	ext::IdentExt, parse_quote,
+};
+
+/// Given an enum, this function returns the discriminant of every variant, following the Rust
+/// rules: a variant without explicit discriminant takes the discriminant of the previous variant
+/// plus one, the first variant defaulting to zero.
+///
+/// # Errors
+///
+/// If an explicit discriminant isn't an integer or byte literal, optionally negated, as other
+/// expressions cannot be evaluated. The error points to the discriminant.
+///
+/// # Example
+///
+/// ```
+/// use syn::{ItemEnum, parse_quote};
+///
+/// let item_enum: ItemEnum = parse_quote! { enum E { A, B = 5, C, D = -1, E } };
+/// let discriminants: Vec<(String, i128)> = rustilities::parsing::discriminants(&item_enum)
+///     .unwrap()
+///     .into_iter()
+///     .map(|(ident, value)| (ident.to_string(), value))
+///     .collect();
+///
+/// assert_eq!(
+///     discriminants,
+///     vec![
+///         ("A".to_owned(), 0),
+///         ("B".to_owned(), 5),
+///         ("C".to_owned(), 6),
+///         ("D".to_owned(), -1),
+///         ("E".to_owned(), 0)
+///     ]
+/// );
+/// ```
+pub fn discriminants(item_enum: &ItemEnum) -> syn::Result<Vec<(&Ident, i128)>> {
+	let mut next = 0;
+	item_enum
+		.variants
+		.iter()
+		.map(|variant| {
+			let value = match &variant.discriminant {
+				Some((_, expr)) => literal_value(expr)?,
+				None => next,
+			};
+			next = value + 1;
+			Ok((&variant.ident, value))
+		})
+		.collect()
+}
+
+/// Given an enum, this function returns its discriminants as [`discriminants`] does, checking that
+/// no two variants share the same discriminant.
+///
+/// # Errors
+///
+/// - If a discriminant cannot be evaluated.
+/// - If a discriminant is repeated. The error points to the second variant using it.
+///
+/// # Example
+///
+/// ```
+/// use syn::{ItemEnum, parse_quote};
+///
+/// let item_enum: ItemEnum = parse_quote! { enum E { A = 1, B, C = 2 } };
+/// assert_eq!(
+///     rustilities::parsing::ensure_unique_discriminants(&item_enum).unwrap_err().to_string(),
+///     "duplicate discriminant 2, already used by B"
+/// );
+/// ```
+pub fn ensure_unique_discriminants(item_enum: &ItemEnum) -> syn::Result<Vec<(&Ident, i128)>> {
+	let discriminants = discriminants(item_enum)?;
+	let mut seen = HashMap::new();
+	for (ident, value) in &discriminants {
+		if let Some(previous) = seen.insert(*value, *ident) {
+			return Err(syn::Error::new_spanned(
+				ident,
+				format!("duplicate discriminant {value}, already used by {previous}"),
+			));
+		}
+	}
+	Ok(discriminants)
+}
+
+/// Given an enum, this function returns its discriminants as [`discriminants`] does, checking that
+/// they're unique and form a range without gaps, in any order. This is useful to convert integers
+/// into variants with a bounds check.
+///
+/// # Errors
+///
+/// - If a discriminant cannot be evaluated or is repeated.
+/// - If a value is missing in the range of the discriminants. The error points to the enum name.
+///
+/// # Example
+///
+/// ```
+/// use syn::{ItemEnum, parse_quote};
+///
+/// let item_enum: ItemEnum = parse_quote! { enum E { B = 1, A = 0, C = 2 } };
+/// assert_eq!(rustilities::parsing::ensure_contiguous_discriminants(&item_enum).unwrap().len(), 3);
+///
+/// let item_enum: ItemEnum = parse_quote! { enum E { A, B = 2 } };
+/// assert_eq!(
+///     rustilities::parsing::ensure_contiguous_discriminants(&item_enum).unwrap_err().to_string(),
+///     "expected contiguous discriminants, but 1 is missing"
+/// );
+/// ```
+pub fn ensure_contiguous_discriminants(item_enum: &ItemEnum) -> syn::Result<Vec<(&Ident, i128)>> {
+	let discriminants = ensure_unique_discriminants(item_enum)?;
+	let sorted: BTreeMap<i128, &Ident> =
+		discriminants.iter().map(|(ident, value)| (*value, *ident)).collect();
+	if let Some(missing) = sorted
+		.keys()
+		.zip(sorted.keys().skip(1))
+		.find_map(|(previous, value)| (*value != previous + 1).then_some(previous + 1))
+	{
+		return Err(syn::Error::new_spanned(
+			&item_enum.ident,
+			format!("expected contiguous discriminants, but {missing} is missing"),
+		));
+	}
+	Ok(discriminants)
+}
+
+/// Given an enum, this function generates an impl block with a `pub fn is_variant(&self) -> bool`
+/// method for every variant, named after the variant in snake case.
+///
+/// The `cfg` attributes of the variants are kept in their methods.
+///
+/// # Example
+///
+/// ```
+/// use syn::{ItemEnum, ItemImpl, parse_quote};
+///
+/// let item_enum: ItemEnum = parse_quote! { enum Shape<T> { Circle(T), HTTPRequest { id: u8 } } };
+/// let expected: ItemImpl = parse_quote! {
+///     impl<T> Shape<T> {
+///         pub fn is_circle(&self) -> bool {
+///             matches!(self, Self::Circle { .. })
+///         }
+///         pub fn is_http_request(&self) -> bool {
+///             matches!(self, Self::HTTPRequest { .. })
+///         }
+///     }
+/// };
+///
+/// assert_eq!(rustilities::parsing::generate_is_methods(&item_enum), expected);
+/// ```
+pub fn generate_is_methods(item_enum: &ItemEnum) -> ItemImpl {
+	let methods = item_enum.variants.iter().map(|variant| {
+		let attrs = cfg_attrs(&variant.attrs);
+		let method = variant_method("is", &variant.ident);
+		let ident = &variant.ident;
+		quote! {
+			#(#attrs)*
+			pub fn #method(&self) -> bool {
+				matches!(self, Self::#ident { .. })
+			}
+		}
+	});
+	enum_impl(item_enum, methods)
+}
+
+/// Given an enum, this function generates an impl block with a `pub fn as_variant(&self)` method
+/// for every variant with fields, named after the variant in snake case. The method returns a
+/// reference to the field of the variant if it has a single field, or a tuple with references to
+/// its fields otherwise, or `None` if `self` is another variant. Unit variants are skipped.
+///
+/// The `cfg` attributes of the variants are kept in their methods.
+///
+/// # Example
+///
+/// ```
+/// use syn::{ItemEnum, ItemImpl, parse_quote};
+///
+/// let item_enum: ItemEnum = parse_quote! {
+///     enum Message { Quit, Write(String), Move { x: i32, y: i32 } }
+/// };
+/// let expected: ItemImpl = parse_quote! {
+///     impl Message {
+///         pub fn as_write(&self) -> Option<&String> {
+///             match self {
+///                 Self::Write(field0) => Some(field0),
+///                 _ => None,
+///             }
+///         }
+///         pub fn as_move(&self) -> Option<(&i32, &i32)> {
+///             match self {
+///                 Self::Move { x, y } => Some((x, y)),
+///                 _ => None,
+///             }
+///         }
+///     }
+/// };
+///
+/// assert_eq!(rustilities::parsing::generate_as_methods(&item_enum), expected);
+/// ```
+pub fn generate_as_methods(item_enum: &ItemEnum) -> ItemImpl {
+	// With a single variant the wildcard arm is unreachable.
+	let allow_unreachable =
+		(item_enum.variants.len() == 1).then(|| quote! { #[allow(unreachable_patterns)] });
+	let methods =
+		item_enum
+			.variants
+			.iter()
+			.filter(|variant| !variant.fields.is_empty())
+			.map(|variant| {
+				let attrs = cfg_attrs(&variant.attrs);
+				let method = variant_method("as", &variant.ident);
+				let ident = &variant.ident;
+				let (pattern, bindings) = variant_pattern(variant);
+				let types = variant.fields.iter().map(|field| &field.ty);
+				let (output, value) = if bindings.len() == 1 {
+					(quote! { #(&#types)* }, quote! { #(#bindings)* })
+				} else {
+					(quote! { (#(&#types),*) }, quote! { (#(#bindings),*) })
+				};
+				quote! {
+					#(#attrs)*
+					pub fn #method(&self) -> Option<#output> {
+						match self {
+							Self::#ident #pattern => Some(#value),
+							#allow_unreachable
+							_ => None,
+						}
+					}
+				}
+			});
+	enum_impl(item_enum, methods)
+}
+
+fn literal_value(expr: &Expr) -> syn::Result<i128> {
+	match expr {
+		Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }) => lit.base10_parse(),
+		Expr::Lit(ExprLit { lit: Lit::Byte(lit), .. }) => Ok(lit.value().into()),
+		Expr::Unary(ExprUnary { op: UnOp::Neg(_), expr, .. }) =>
+			literal_value(expr).map(|value| -value),
+		Expr::Group(group) => literal_value(&group.expr),
+		Expr::Paren(paren) => literal_value(&paren.expr),
+		_ => Err(syn::Error::new_spanned(expr, "expected an integer literal discriminant")),
+	}
+}
+
+fn cfg_attrs(attrs: &[Attribute]) -> Vec<&Attribute> {
+	attrs.iter().filter(|attr| attr.path().is_ident("cfg")).collect()
+}
+
+fn enum_impl(item_enum: &ItemEnum, methods: impl Iterator<Item = TokenStream>) -> ItemImpl {
+	let name = &item_enum.ident;
+	let (impl_generics, ty_generics, where_clause) = item_enum.generics.split_for_impl();
+	parse_quote! {
+		impl #impl_generics #name #ty_generics #where_clause {
+			#(#methods)*
+		}
+	}
+}
+
+// The pattern matching the fields of a variant, and the idents bound by it.
+fn variant_pattern(variant: &Variant) -> (TokenStream, Vec<Ident>) {
+	match &variant.fields {
+		Fields::Named(fields) => {
+			let bindings: Vec<Ident> = fields
+				.named
+				.iter()
+				.map(|field| field.ident.clone().expect("The fields are named; qed;"))
+				.collect();
+			(quote! { { #(#bindings),* } }, bindings)
+		},
+		Fields::Unnamed(fields) => {
+			let bindings: Vec<Ident> = (0..fields.unnamed.len())
+				.map(|index| format_ident!("field{}", index, span = Span::call_site()))
+				.collect();
+			(quote! { (#(#bindings),*) }, bindings)
+		},
+		Fields::Unit => (TokenStream::new(), Vec::new()),
+	}
+}
+
+fn variant_method(prefix: &str, variant: &Ident) -> Ident {
+	format_ident!("{}_{}", prefix, snake_case(&variant.unraw().to_string()))
+}
+
+// Converts an upper camel case name into snake case, keeping acronyms together: `HTTPRequest`
+// becomes `http_request`.
+fn snake_case(name: &str) -> String {
+	let chars: Vec<char> = name.chars().collect();
+	let mut output = String::with_capacity(name.len() + 4);
+	for (index, c) in chars.iter().enumerate() {
+		if c.is_uppercase() && index > 0 {
+			let previous = chars[index - 1];
+			let next_is_lowercase = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+			if previous.is_lowercase() ||
+				previous.is_ascii_digit() ||
+				(previous.is_uppercase() && next_is_lowercase)
+			{
+				output.push('_');
+			}
+		}
+		output.extend(c.to_lowercase());
+	}
+	output
+}
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+fn values(discriminants: Vec<(&Ident, i128)>) -> Vec<(String, i128)> {
+	discriminants
+		.into_iter()
+		.map(|(ident, value)| (ident.to_string(), value))
+		.collect()
+}
+
+#[test]
+fn discriminants_evaluates_literals() {
+	let item_enum: ItemEnum = parse_quote! {
+		#[repr(u8)]
+		enum E { A = 0x10, B, C = b'a', D = (3u8), E = -(2) }
+	};
+
+	assert_eq!(
+		values(discriminants(&item_enum).expect("This should be Ok; qed;")),
+		vec![
+			("A".to_owned(), 16),
+			("B".to_owned(), 17),
+			("C".to_owned(), 97),
+			("D".to_owned(), 3),
+			("E".to_owned(), -2)
+		]
+	);
+}
+
+#[test]
+fn discriminants_fails_on_non_literal_expressions() {
+	let item_enum: ItemEnum = parse_quote! { enum E { A = 1, B = OFFSET + 1 } };
+
+	assert_eq!(
+		discriminants(&item_enum).expect_err("This should be Err; qed;").to_string(),
+		"expected an integer literal discriminant"
+	);
+}
+
+#[test]
+fn ensure_unique_discriminants_works() {
+	let item_enum: ItemEnum = parse_quote! { enum E { A = 2, B = 0, C } };
+	assert_eq!(
+		values(ensure_unique_discriminants(&item_enum).expect("This should be Ok; qed;")),
+		vec![("A".to_owned(), 2), ("B".to_owned(), 0), ("C".to_owned(), 1)]
+	);
+
+	let item_enum: ItemEnum = parse_quote! { enum E { A, B = -1, C } };
+	assert_eq!(
+		ensure_unique_discriminants(&item_enum)
+			.expect_err("This should be Err; qed;")
+			.to_string(),
+		"duplicate discriminant 0, already used by A"
+	);
+}
+
+#[test]
+fn ensure_contiguous_discriminants_works() {
+	let item_enum: ItemEnum = parse_quote! { enum E { A = -1, B = 1, C = 0 } };
+	assert!(ensure_contiguous_discriminants(&item_enum).is_ok());
+
+	let item_enum: ItemEnum = parse_quote! { enum E {} };
+	assert!(
+		ensure_contiguous_discriminants(&item_enum)
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+
+	let item_enum: ItemEnum = parse_quote! { enum E { A = 5, B, C = 9, D = 8 } };
+	assert_eq!(
+		ensure_contiguous_discriminants(&item_enum)
+			.expect_err("This should be Err; qed;")
+			.to_string(),
+		"expected contiguous discriminants, but 7 is missing"
+	);
+}
+
+#[test]
+fn generate_is_methods_keeps_cfg_attrs() {
+	let item_enum: ItemEnum = parse_quote! {
+		enum E<'a> where Self: 'a {
+			/// Docs.
+			#[cfg(feature = "std")]
+			Loaded(&'a str),
+			r#Type,
+		}
+	};
+	let expected: ItemImpl = parse_quote! {
+		impl<'a> E<'a> where Self: 'a {
+			#[cfg(feature = "std")]
+			pub fn is_loaded(&self) -> bool {
+				matches!(self, Self::Loaded { .. })
+			}
+			pub fn is_type(&self) -> bool {
+				matches!(self, Self::r#Type { .. })
+			}
+		}
+	};
+
+	assert_eq!(generate_is_methods(&item_enum), expected);
+}
+
+#[test]
+fn generate_as_methods_handles_single_variant_enums() {
+	let item_enum: ItemEnum = parse_quote! { enum Pair { Both(u8, u16) } };
+	let expected: ItemImpl = parse_quote! {
+		impl Pair {
+			pub fn as_both(&self) -> Option<(&u8, &u16)> {
+				match self {
+					Self::Both(field0, field1) => Some((field0, field1)),
+					#[allow(unreachable_patterns)]
+					_ => None,
+				}
+			}
+		}
+	};
+
+	assert_eq!(generate_as_methods(&item_enum), expected);
+}
+
+#[test]
+fn snake_case_works() {
+	[
+		("Circle", "circle"),
+		("HttpRequest", "http_request"),
+		("HTTPRequest", "http_request"),
+		("IPv4", "i_pv4"),
+		("Version2Beta", "version2_beta"),
+		("ABC", "abc"),
+	]
+	.into_iter()
+	.for_each(|(name, expected)| assert_eq!(snake_case(name), expected));
+}
@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! This module provides the [`VisMut`] trait, a convenient way to read and modify the visibility of
+//! a [`syn`] type if it has one, without pattern matching on every variant of enums such as
+//! [`Item`].
+//!
+//! Additionally, the module provides the [`restrict_visibility`] and [`make_pub_crate`] functions,
+//! which are useful to keep generated code from leaking into the public API of a crate.
+
+#[cfg(test)]
+mod tests;
+
+use syn::{
+	Field, File, ImplItem, Item, Visibility, parse_quote,
+	visit_mut::{self, VisitMut},
+};
+
+/// The [`VisMut`] trait offers a convenient way to read and modify the visibility of a [`syn`] type
+/// if it has one. Items that cannot have a visibility, such as impl blocks, return `None`.
+///
+/// It's currently implemented for [`Item`], [`ImplItem`] and [`Field`], but this will be updated as
+/// needed.
+///
+/// ```rust
+/// use syn::{Item, Visibility, parse_quote};
+/// use rustilities::parsing::vis::VisMut;
+///
+/// let mut item: Item = parse_quote! { pub fn my_function() {} };
+/// *item.vis_mut().unwrap() = parse_quote! { pub(crate) };
+///
+/// let expected_vis: Visibility = parse_quote! { pub(crate) };
+/// assert_eq!(item.vis().unwrap(), &expected_vis);
+///
+/// let item: Item = parse_quote! { impl MyStruct {} };
+/// assert!(item.vis().is_none());
+/// ```
+pub trait VisMut {
+	fn vis(&self) -> Option<&Visibility>;
+	fn vis_mut(&mut self) -> Option<&mut Visibility>;
+}
+
+/// Get a copy of the input whose visibility doesn't exceed the given one: a more public visibility
+/// is replaced by the given one, while an equal or more restricted visibility is kept. Appliable to
+/// any [`syn`] type implementing [`Clone`] and [`VisMut`].
+///
+/// Visibilities are ordered from the most restricted to the most public as follows: private (or
+/// `pub(self)`), `pub(in path)`, `pub(super)`, `pub(crate)` and `pub`.
+///
+/// ```rust
+/// use syn::{parse_quote, Item, Visibility};
+///
+/// let max: Visibility = parse_quote! { pub(crate) };
+///
+/// let item: Item = parse_quote! { pub struct MyStruct; };
+/// let expected_item: Item = parse_quote! { pub(crate) struct MyStruct; };
+/// assert_eq!(rustilities::parsing::vis::restrict_visibility(&item, &max), expected_item);
+///
+/// let item: Item = parse_quote! { pub(super) struct MyStruct; };
+/// assert_eq!(rustilities::parsing::vis::restrict_visibility(&item, &max), item);
+/// ```
+pub fn restrict_visibility<T: VisMut + Clone>(item: &T, max: &Visibility) -> T {
+	let mut output = item.clone();
+	if let Some(vis) = output.vis_mut() {
+		restrict(vis, max);
+	}
+	output
+}
+
+/// Get a copy of the input file where every `pub` visibility, including the ones of nested items,
+/// associated items and fields, is replaced by `pub(crate)`. The other visibilities are kept.
+///
+/// ```rust
+/// use syn::{parse_quote, File};
+///
+/// let file: File = parse_quote! {
+///   pub struct MyStruct { pub field: u8, other: u8 }
+///
+///   impl MyStruct {
+///     pub fn new() -> Self { Self { field: 0, other: 0 } }
+///   }
+///
+///   pub(super) mod inner {
+///     pub fn f() {}
+///   }
+/// };
+///
+/// let expected_file: File = parse_quote! {
+///   pub(crate) struct MyStruct { pub(crate) field: u8, other: u8 }
+///
+///   impl MyStruct {
+///     pub(crate) fn new() -> Self { Self { field: 0, other: 0 } }
+///   }
+///
+///   pub(super) mod inner {
+///     pub(crate) fn f() {}
+///   }
+/// };
+///
+/// assert_eq!(rustilities::parsing::vis::make_pub_crate(&file), expected_file);
+/// ```
+pub fn make_pub_crate(file: &File) -> File {
+	struct PubCrate(Visibility);
+
+	impl VisitMut for PubCrate {
+		fn visit_visibility_mut(&mut self, vis: &mut Visibility) {
+			restrict(vis, &self.0);
+			visit_mut::visit_visibility_mut(self, vis);
+		}
+	}
+
+	let mut output = file.clone();
+	PubCrate(parse_quote! { pub(crate) }).visit_file_mut(&mut output);
+	output
+}
+
+fn restrict(vis: &mut Visibility, max: &Visibility) {
+	if rank(vis) > rank(max) {
+		*vis = max.clone();
+	}
+}
+
+fn rank(vis: &Visibility) -> u8 {
+	match vis {
+		Visibility::Inherited => 0,
+		Visibility::Restricted(restricted) if restricted.path.is_ident("self") => 0,
+		Visibility::Restricted(restricted) if restricted.path.is_ident("super") => 2,
+		Visibility::Restricted(restricted) if restricted.path.is_ident("crate") => 3,
+		Visibility::Restricted(_) => 1,
+		Visibility::Public(_) => 4,
+	}
+}
+
+impl VisMut for Item {
+	fn vis(&self) -> Option<&Visibility> {
+		match self {
+			Item::Const(item) => Some(&item.vis),
+			Item::Enum(item) => Some(&item.vis),
+			Item::ExternCrate(item) => Some(&item.vis),
+			Item::Fn(item) => Some(&item.vis),
+			Item::Mod(item) => Some(&item.vis),
+			Item::Static(item) => Some(&item.vis),
+			Item::Struct(item) => Some(&item.vis),
+			Item::Trait(item) => Some(&item.vis),
+			Item::TraitAlias(item) => Some(&item.vis),
+			Item::Type(item) => Some(&item.vis),
+			Item::Union(item) => Some(&item.vis),
+			Item::Use(item) => Some(&item.vis),
+			_ => None,
+		}
+	}
+
+	fn vis_mut(&mut self) -> Option<&mut Visibility> {
+		match self {
+			Item::Const(item) => Some(&mut item.vis),
+			Item::Enum(item) => Some(&mut item.vis),
+			Item::ExternCrate(item) => Some(&mut item.vis),
+			Item::Fn(item) => Some(&mut item.vis),
+			Item::Mod(item) => Some(&mut item.vis),
+			Item::Static(item) => Some(&mut item.vis),
+			Item::Struct(item) => Some(&mut item.vis),
+			Item::Trait(item) => Some(&mut item.vis),
+			Item::TraitAlias(item) => Some(&mut item.vis),
+			Item::Type(item) => Some(&mut item.vis),
+			Item::Union(item) => Some(&mut item.vis),
+			Item::Use(item) => Some(&mut item.vis),
+			_ => None,
+		}
+	}
+}
+
+impl VisMut for ImplItem {
+	fn vis(&self) -> Option<&Visibility> {
+		match self {
+			ImplItem::Const(item) => Some(&item.vis),
+			ImplItem::Fn(item) => Some(&item.vis),
+			ImplItem::Type(item) => Some(&item.vis),
+			_ => None,
+		}
+	}
+
+	fn vis_mut(&mut self) -> Option<&mut Visibility> {
+		match self {
+			ImplItem::Const(item) => Some(&mut item.vis),
+			ImplItem::Fn(item) => Some(&mut item.vis),
+			ImplItem::Type(item) => Some(&mut item.vis),
+			_ => None,
+		}
+	}
+}
+
+impl VisMut for Field {
+	fn vis(&self) -> Option<&Visibility> {
+		Some(&self.vis)
+	}
+
+	fn vis_mut(&mut self) -> Option<&mut Visibility> {
+		Some(&mut self.vis)
+	}
+}
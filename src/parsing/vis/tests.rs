@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use syn::parse::Parser;
+
+#[test]
+fn vis_mut_item_without_visibility() {
+	let mut items: Vec<Item> = vec![
+		parse_quote! { impl MyStruct {} },
+		parse_quote! { extern "C" {} },
+		parse_quote! { macro_rules! m { () => {} } },
+	];
+
+	items.iter_mut().for_each(|item| {
+		assert!(item.vis().is_none());
+		assert!(item.vis_mut().is_none());
+	});
+}
+
+#[test]
+fn vis_mut_impl_item() {
+	let mut impl_item: ImplItem = parse_quote! { pub(super) const C: u8 = 0; };
+	let expected_vis: Visibility = parse_quote! { pub(super) };
+	assert_eq!(impl_item.vis(), Some(&expected_vis));
+
+	*impl_item.vis_mut().unwrap() = Visibility::Inherited;
+	let expected_item: ImplItem = parse_quote! { const C: u8 = 0; };
+	assert_eq!(impl_item, expected_item);
+
+	let impl_item: ImplItem = parse_quote! { m!(); };
+	assert!(impl_item.vis().is_none());
+}
+
+#[test]
+fn vis_mut_field() {
+	let mut field: Field = Field::parse_named
+		.parse2(quote::quote! { pub field: u8 })
+		.expect("This should be Ok; qed;");
+	*field.vis_mut().unwrap() = parse_quote! { pub(crate) };
+
+	let expected_vis: Visibility = parse_quote! { pub(crate) };
+	assert_eq!(field.vis(), Some(&expected_vis));
+}
+
+#[test]
+fn restrict_visibility_follows_visibility_order() {
+	let visibilities: Vec<Visibility> = vec![
+		Visibility::Inherited,
+		parse_quote! { pub(in crate::a) },
+		parse_quote! { pub(super) },
+		parse_quote! { pub(crate) },
+		parse_quote! { pub },
+	];
+
+	visibilities.iter().enumerate().for_each(|(i, max)| {
+		visibilities.iter().enumerate().for_each(|(j, vis)| {
+			let item: Item = parse_quote! { #vis struct MyStruct; };
+			let expected_vis = if j > i { max } else { vis };
+			let expected_item: Item = parse_quote! { #expected_vis struct MyStruct; };
+			assert_eq!(restrict_visibility(&item, max), expected_item);
+		})
+	});
+}
+
+#[test]
+fn restrict_visibility_treats_pub_self_as_private() {
+	let item: Item = parse_quote! { pub(self) struct MyStruct; };
+
+	assert_eq!(restrict_visibility(&item, &Visibility::Inherited), item);
+}
+
+#[test]
+fn make_pub_crate_reaches_nested_items() {
+	let file: File = parse_quote! {
+		pub use a::b;
+
+		pub mod inner {
+			pub(in crate::inner) struct S(pub u8);
+
+			pub trait Trait {
+				fn f();
+			}
+
+			extern "C" {
+				pub fn g();
+			}
+
+			pub fn h() {
+				pub struct Local;
+			}
+		}
+	};
+	let expected_file: File = parse_quote! {
+		pub(crate) use a::b;
+
+		pub(crate) mod inner {
+			pub(in crate::inner) struct S(pub(crate) u8);
+
+			pub(crate) trait Trait {
+				fn f();
+			}
+
+			extern "C" {
+				pub(crate) fn g();
+			}
+
+			pub(crate) fn h() {
+				pub(crate) struct Local;
+			}
+		}
+	};
+
+	assert_eq!(make_pub_crate(&file), expected_file);
+}
@@ -3,7 +3,15 @@
 #[cfg(test)]
 mod tests;
 
-use std::path::{Component, Path, PathBuf};
+use crate::{Error, macros::debug};
+use std::{
+	borrow::Cow,
+	ffi::OsStr,
+	path::{Component, Path, PathBuf},
+};
+
+/// The greatest suffix tried by [`unique_path`].
+const MAX_UNIQUE_PATH_SUFFIX: usize = 1000;
 
 /// Transforms a path without prefix into a relative path starting at the current directory.
 /// If the path's already prefixed, this function doesn't have any effect.
@@ -20,17 +28,432 @@ use std::path::{Component, Path, PathBuf};
 /// assert_eq!(rustilities::paths::prefix_with_current_dir(path2), path2);
 /// ```
 pub fn prefix_with_current_dir<P: AsRef<Path>>(path: P) -> PathBuf {
-	fn do_prefix_with_current_dir(path: &Path) -> PathBuf {
-		let components = path.components().collect::<Vec<Component>>();
-		if !components.is_empty() {
-			// If the first component is a normal component, we prefix the path with the current dir
-			if let Component::Normal(_) = components[0] {
-				return <Component<'_> as AsRef<Path>>::as_ref(&Component::CurDir).join(path);
-			} else {
-				return path.to_path_buf();
+	prefix_with_current_dir_cow(path.as_ref()).into_owned()
+}
+
+/// Same as [`prefix_with_current_dir`], but the path is borrowed if it's already prefixed, so no
+/// allocation happens in that case. This is meant for hot loops mapping many paths.
+///
+/// ## Example
+///
+/// ```
+/// use std::{borrow::Cow, path::Path};
+///
+/// let path1 = Path::new("path/1");
+/// let path2 = Path::new("../path/2");
+///
+/// assert_eq!(rustilities::paths::prefix_with_current_dir_cow(path1), Path::new("./path/1"));
+/// assert!(matches!(rustilities::paths::prefix_with_current_dir_cow(path2), Cow::Borrowed(path) if path == path2));
+/// ```
+pub fn prefix_with_current_dir_cow(path: &Path) -> Cow<'_, Path> {
+	let current_dir = <Component<'_> as AsRef<Path>>::as_ref(&Component::CurDir);
+	match path.components().next() {
+		// If the first component is a normal component, we prefix the path with the current dir
+		Some(Component::Normal(_)) => Cow::Owned(current_dir.join(path)),
+		Some(_) => Cow::Borrowed(path),
+		None => Cow::Borrowed(current_dir),
+	}
+}
+
+/// Creates the given dir, together with its missing parents. Nothing is done if the dir already
+/// exists.
+///
+/// # Errors
+///
+/// - If the dir cannot be created, eg because the path points to an existing file. The error is an
+///   [`Error::IOAt`] carrying the path.
+///
+/// ## Example
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let dir = tempdir.path().join("a").join("b");
+///
+/// rustilities::paths::ensure_dir(&dir).unwrap();
+/// assert!(dir.is_dir());
+/// rustilities::paths::ensure_dir(&dir).unwrap();
+/// ```
+pub fn ensure_dir<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_ensure_dir(path: &Path) -> Result<(), Error> {
+		if path.is_dir() {
+			return Ok(());
+		}
+		debug!(path = %path.display(), "Creating dir");
+		std::fs::create_dir_all(path)
+			.map_err(|source| Error::IOAt { path: path.to_path_buf(), source })
+	}
+	do_ensure_dir(path.as_ref())
+}
+
+/// Writes the given contents to the file at the given path, replacing it if it exists, after
+/// creating its missing parent dirs.
+///
+/// # Errors
+///
+/// - If the parent dirs cannot be created, or the file cannot be written. The error is an
+///   [`Error::IOAt`] carrying the path that failed.
+///
+/// ## Example
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let file = tempdir.path().join("new_crate").join("src").join("lib.rs");
+///
+/// rustilities::paths::write_creating_parents(&file, "pub fn f() {}").unwrap();
+/// assert_eq!(std::fs::read_to_string(&file).unwrap(), "pub fn f() {}");
+/// ```
+pub fn write_creating_parents<P: AsRef<Path>, C: AsRef<[u8]>>(
+	path: P,
+	contents: C,
+) -> Result<(), Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(contents)))]
+	fn do_write_creating_parents(path: &Path, contents: &[u8]) -> Result<(), Error> {
+		if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+			ensure_dir(parent)?;
+		}
+		debug!(path = %path.display(), "Writing file");
+		std::fs::write(path, contents)
+			.map_err(|source| Error::IOAt { path: path.to_path_buf(), source })
+	}
+	do_write_creating_parents(path.as_ref(), contents.as_ref())
+}
+
+/// Returns `base` if nothing exists at that path, or the first of `base-1`, `base-2`... that
+/// doesn't exist, so generators creating crates or files don't clobber existing ones. The suffix
+/// is added to the file stem, keeping the extension: `notes.txt` becomes `notes-1.txt`. Broken
+/// symlinks count as existing paths.
+///
+/// # Errors
+///
+/// - If `base` doesn't have a file name.
+/// - If every suffix up to 1000 is taken.
+///
+/// ## Example
+///
+/// ```
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let base = tempdir.path().join("my-crate");
+///
+/// assert_eq!(rustilities::paths::unique_path(&base).unwrap(), base);
+/// std::fs::create_dir(&base).unwrap();
+/// assert_eq!(rustilities::paths::unique_path(&base).unwrap(), tempdir.path().join("my-crate-1"));
+/// ```
+pub fn unique_path<P: AsRef<Path>>(base: P) -> Result<PathBuf, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", ret))]
+	fn do_unique_path(base: &Path) -> Result<PathBuf, Error> {
+		let Some(stem) = base.file_stem() else {
+			return Err(Error::Descriptive(format!("{} doesn't have a file name", base.display())));
+		};
+		let exists = |path: &Path| path.symlink_metadata().is_ok();
+		if !exists(base) {
+			return Ok(base.to_path_buf());
+		}
+		for suffix in 1..=MAX_UNIQUE_PATH_SUFFIX {
+			let mut name = stem.to_os_string();
+			name.push(format!("-{suffix}"));
+			if let Some(extension) = base.extension() {
+				name.push(".");
+				name.push(extension);
+			}
+			let candidate = base.with_file_name(name);
+			if !exists(&candidate) {
+				return Ok(candidate);
+			}
+		}
+		Err(Error::Descriptive(format!(
+			"Couldn't find a free path for {} after {MAX_UNIQUE_PATH_SUFFIX} attempts",
+			base.display()
+		)))
+	}
+	do_unique_path(base.as_ref())
+}
+
+/// Appends an extension to the path, keeping its current extensions: `Cargo.toml` becomes
+/// `Cargo.toml.bak`. Unlike [`Path::with_extension`], nothing is replaced. The path is returned
+/// unchanged if it doesn't have a file name or the extension is empty.
+///
+/// ## Example
+///
+/// ```
+/// use std::path::Path;
+///
+/// assert_eq!(
+///     rustilities::paths::with_appended_extension("dir/archive.tar.gz", "bak"),
+///     Path::new("dir/archive.tar.gz.bak")
+/// );
+/// assert_eq!(rustilities::paths::with_appended_extension("LICENSE", "md"), Path::new("LICENSE.md"));
+/// ```
+pub fn with_appended_extension<P: AsRef<Path>>(path: P, extension: &str) -> PathBuf {
+	let path = path.as_ref();
+	match path.file_name() {
+		Some(name) if !extension.is_empty() => {
+			let mut name = name.to_os_string();
+			name.push(".");
+			name.push(extension);
+			path.with_file_name(name)
+		},
+		_ => path.to_path_buf(),
+	}
+}
+
+/// Replaces every extension of the path by the given one, so multi-part extensions are handled as
+/// a whole: `archive.tar.gz` becomes `archive.zip`. An empty extension removes them. A leading dot
+/// doesn't start an extension, so `.config.toml` becomes `.config.json`. Apart from the last one,
+/// the parts starting with a digit are kept as part of the name, so versioned names keep their
+/// version: `app-1.2.3.tar.gz` becomes `app-1.2.3.zip`. The path is returned unchanged if it
+/// doesn't have a file name.
+///
+/// ## Example
+///
+/// ```
+/// use std::path::Path;
+///
+/// assert_eq!(
+///     rustilities::paths::replace_extension_multi("dist/app.tar.gz", "zip"),
+///     Path::new("dist/app.zip")
+/// );
+/// assert_eq!(
+///     rustilities::paths::replace_extension_multi("release-1.2.3.tar.gz", "zip"),
+///     Path::new("release-1.2.3.zip")
+/// );
+/// assert_eq!(rustilities::paths::replace_extension_multi("app.tar.gz", ""), Path::new("app"));
+/// ```
+pub fn replace_extension_multi<P: AsRef<Path>>(path: P, extension: &str) -> PathBuf {
+	let path = path.as_ref();
+	let Some(stem) = multi_stem(path) else {
+		return path.to_path_buf();
+	};
+	let mut name = stem.to_os_string();
+	if !extension.is_empty() {
+		name.push(".");
+		name.push(extension);
+	}
+	path.with_file_name(name)
+}
+
+/// The file name of the path without any of its extensions, as [`replace_extension_multi`]
+/// understands them, converted to UTF-8 lossily: `archive.tar.gz` gives `archive`. Returns `None`
+/// if the path doesn't have a file name.
+///
+/// ## Example
+///
+/// ```
+/// use std::path::Path;
+///
+/// assert_eq!(rustilities::paths::stem_utf8_lossy(Path::new("dist/app.tar.gz")).unwrap(), "app");
+/// assert_eq!(rustilities::paths::stem_utf8_lossy(Path::new(".gitignore")).unwrap(), ".gitignore");
+/// assert!(rustilities::paths::stem_utf8_lossy(Path::new("/")).is_none());
+/// ```
+pub fn stem_utf8_lossy(path: &Path) -> Option<Cow<'_, str>> {
+	multi_stem(path).map(OsStr::to_string_lossy)
+}
+
+/// The file name of the path without any of its extensions. The last extension is always
+/// stripped, the previous ones only if they don't start with a digit, as they're likely part of a
+/// version then.
+fn multi_stem(path: &Path) -> Option<&OsStr> {
+	let mut stem = path.file_stem()?;
+	while let Some(extension) = Path::new(stem).extension() &&
+		!extension.as_encoded_bytes().first().is_some_and(u8::is_ascii_digit)
+	{
+		stem = Path::new(stem).file_stem()?;
+	}
+	Some(stem)
+}
+
+/// Renders a path for humans, choosing the shortest of: the path relative to the current dir (if
+/// it's inside it), the path with the home dir replaced by `~` (if it's inside it), or the
+/// absolute path. This keeps error messages and reports readable. The path is rendered as is if
+/// it cannot be made absolute.
+///
+/// ## Example
+///
+/// ```
+/// let current_dir = std::env::current_dir().unwrap();
+///
+/// assert_eq!(
+///     rustilities::paths::display_compact(current_dir.join("src").join("lib.rs")),
+///     std::path::Path::new("src").join("lib.rs").display().to_string()
+/// );
+/// assert_eq!(rustilities::paths::display_compact(&current_dir), ".");
+/// ```
+pub fn display_compact<P: AsRef<Path>>(path: P) -> String {
+	let path = path.as_ref();
+	let Ok(absolute) = std::path::absolute(path) else {
+		return path.display().to_string();
+	};
+	let home = std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" })
+		.filter(|home| !home.is_empty())
+		.map(PathBuf::from);
+	compact_display(&absolute, std::env::current_dir().ok().as_deref(), home.as_deref())
+}
+
+/// The shortest rendering of an absolute path among the candidates of [`display_compact`].
+fn compact_display(path: &Path, current_dir: Option<&Path>, home: Option<&Path>) -> String {
+	let mut candidates = Vec::new();
+	if let Some(relative) = current_dir.and_then(|current_dir| path.strip_prefix(current_dir).ok())
+	{
+		candidates.push(if relative.as_os_str().is_empty() {
+			".".to_owned()
+		} else {
+			relative.display().to_string()
+		});
+	}
+	if let Some(relative) = home.and_then(|home| path.strip_prefix(home).ok()) {
+		candidates.push(if relative.as_os_str().is_empty() {
+			"~".to_owned()
+		} else {
+			Path::new("~").join(relative).display().to_string()
+		});
+	}
+	candidates.push(path.display().to_string());
+	candidates
+		.into_iter()
+		.min_by_key(String::len)
+		.expect("There's a candidate; qed;")
+}
+
+/// Records the original contents of the files touched by a multi-file edit, so the edit can be
+/// rolled back if some step fails and files are never left half-applied.
+///
+/// Files written through the session are recorded automatically. Files modified by other means,
+/// eg by the [`manifest`](crate::manifest) or [`parsing`](crate::parsing) functions, must be
+/// recorded with [`EditSession::track`] before modifying them. The outermost dirs created to hold
+/// recorded files are recorded as well, and removed on rollback.
+///
+/// Dropping the session keeps the changes. [`EditSession::run`] wraps a whole edit, rolling it
+/// back if it fails.
+///
+/// ## Example
+///
+/// ```
+/// use rustilities::{Error, paths::EditSession};
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// let manifest_path = tempdir.path().join("Cargo.toml");
+/// std::fs::write(&manifest_path, "[package]\nname = \"test\"\n").unwrap();
+///
+/// let result = EditSession::run(|session| {
+///     session.write(tempdir.path().join("src").join("lib.rs"), "pub fn f() {}\n")?;
+///     session.track(&manifest_path)?;
+///     std::fs::write(&manifest_path, "[package]\nname = \"renamed\"\n")?;
+///     Err::<(), _>(Error::Descriptive("Something went wrong".to_owned()))
+/// });
+///
+/// assert!(result.is_err());
+/// assert!(!tempdir.path().join("src").exists());
+/// assert_eq!(std::fs::read_to_string(&manifest_path).unwrap(), "[package]\nname = \"test\"\n");
+/// ```
+#[derive(Debug, Default)]
+pub struct EditSession {
+	// The original contents of the recorded files, `None` if they didn't exist.
+	files: Vec<(PathBuf, Option<Vec<u8>>)>,
+	// The outermost dirs that didn't exist when a file inside them was recorded.
+	dirs: Vec<PathBuf>,
+}
+
+impl EditSession {
+	/// Creates a session without recorded files.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Runs an edit with a new session, rolling it back if it fails.
+	///
+	/// # Errors
+	///
+	/// - The error of the edit, once rolled back.
+	/// - If the edit fails and cannot be rolled back. The error describes both failures.
+	pub fn run<T, F: FnOnce(&mut Self) -> Result<T, Error>>(edit: F) -> Result<T, Error> {
+		let mut session = Self::new();
+		edit(&mut session).or_else(|err| {
+			debug!(%err, "Rolling back the edit session");
+			session.rollback().map_err(|rollback_err| {
+				Error::Descriptive(format!(
+					"{err}. Rolling back the changes also failed: {rollback_err}"
+				))
+			})?;
+			Err(err)
+		})
+	}
+
+	/// Records the current state of a file, so the changes made to it from now on can be rolled
+	/// back. A file that doesn't exist is removed on rollback. Files already recorded are ignored,
+	/// so their first recorded state is the one restored.
+	///
+	/// # Errors
+	///
+	/// - If the path exists but cannot be read. The error is an [`Error::IOAt`] carrying the path.
+	pub fn track<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+		let path = path.as_ref();
+		if self.files.iter().any(|(file, _)| file == path) {
+			return Ok(());
+		}
+		let outermost_missing_dir = path
+			.ancestors()
+			.skip(1)
+			.take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+			.last();
+		if let Some(dir) = outermost_missing_dir {
+			self.dirs.push(dir.to_path_buf());
+		}
+		let original = match std::fs::read(path) {
+			Ok(contents) => Some(contents),
+			Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+			Err(source) => return Err(Error::IOAt { path: path.to_path_buf(), source }),
+		};
+		self.files.push((path.to_path_buf(), original));
+		Ok(())
+	}
+
+	/// Records a file and writes the given contents to it, as [`write_creating_parents`] does.
+	///
+	/// # Errors
+	///
+	/// - If the file cannot be recorded or written. The error is an [`Error::IOAt`] carrying the
+	///   path that failed.
+	pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(
+		&mut self,
+		path: P,
+		contents: C,
+	) -> Result<(), Error> {
+		self.track(path.as_ref())?;
+		write_creating_parents(path, contents)
+	}
+
+	/// The files recorded by the session, in the order they were recorded.
+	pub fn tracked_files(&self) -> impl Iterator<Item = &Path> {
+		self.files.iter().map(|(path, _)| path.as_path())
+	}
+
+	/// Restores the recorded files to their recorded state, removing those that didn't exist, and
+	/// removes the recorded dirs.
+	///
+	/// # Errors
+	///
+	/// - If some file cannot be restored or removed, or some dir cannot be removed. The error is an
+	///   [`Error::IOAt`] carrying the path that failed.
+	pub fn rollback(self) -> Result<(), Error> {
+		#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+		fn do_rollback(session: EditSession) -> Result<(), Error> {
+			for (path, original) in session.files.into_iter().rev() {
+				let result = match original {
+					Some(contents) => std::fs::write(&path, contents),
+					None if path.exists() => std::fs::remove_file(&path),
+					None => Ok(()),
+				};
+				result.map_err(|source| Error::IOAt { path, source })?;
+			}
+			for dir in session.dirs.into_iter().rev() {
+				if dir.exists() {
+					debug!(path = %dir.display(), "Removing dir");
+					std::fs::remove_dir_all(&dir)
+						.map_err(|source| Error::IOAt { path: dir, source })?;
+				}
 			}
+			Ok(())
 		}
-		<Component<'_> as AsRef<Path>>::as_ref(&Component::CurDir).to_path_buf()
+		do_rollback(self)
 	}
-	do_prefix_with_current_dir(path.as_ref())
 }
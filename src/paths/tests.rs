@@ -26,3 +26,282 @@ fn prefix_with_current_dir_works() {
 	);
 	assert_eq!(prefix_with_current_dir::<&Path>("".as_ref()), current_dir_component);
 }
+
+#[test]
+fn prefix_with_current_dir_cow_only_allocates_if_prefixing() {
+	let current_dir_component = <Component<'_> as AsRef<Path>>::as_ref(&Component::CurDir);
+	let parent_dir_component = <Component<'_> as AsRef<Path>>::as_ref(&Component::ParentDir);
+	let root_dir_component = <Component<'_> as AsRef<Path>>::as_ref(&Component::RootDir);
+
+	let path = Path::new("my").join("path");
+	assert!(matches!(
+		prefix_with_current_dir_cow(&path),
+		Cow::Owned(prefixed) if prefixed == current_dir_component.join(&path)
+	));
+	for path in [
+		current_dir_component.join(&path),
+		parent_dir_component.join(&path),
+		root_dir_component.join(&path),
+	] {
+		assert!(
+			matches!(prefix_with_current_dir_cow(&path), Cow::Borrowed(borrowed) if borrowed == path)
+		);
+	}
+	assert!(matches!(
+		prefix_with_current_dir_cow(Path::new("")),
+		Cow::Borrowed(borrowed) if borrowed == current_dir_component
+	));
+}
+
+#[test]
+fn ensure_dir_creates_missing_parents() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let dir = tempdir.path().join("a").join("b").join("c");
+
+	ensure_dir(&dir).expect("This should be Ok; qed;");
+	assert!(dir.is_dir());
+	ensure_dir(&dir).expect("This should be Ok; qed;");
+}
+
+#[test]
+fn ensure_dir_fails_if_path_is_a_file() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let file = tempdir.path().join("file");
+	std::fs::write(&file, "").expect("The file should be writable; qed;");
+
+	assert!(
+		matches!(ensure_dir(file.join("dir")), Err(Error::IOAt { path, .. }) if path == file.join("dir"))
+	);
+}
+
+#[test]
+fn write_creating_parents_writes_files() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let file = tempdir.path().join("a").join("b.txt");
+
+	write_creating_parents(&file, "first").expect("This should be Ok; qed;");
+	write_creating_parents(&file, b"second").expect("This should be Ok; qed;");
+
+	assert_eq!(std::fs::read_to_string(&file).expect("This should be Ok; qed;"), "second");
+}
+
+#[test]
+fn write_creating_parents_fails_if_path_is_a_dir() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+
+	let result = write_creating_parents(tempdir.path(), "");
+	assert!(matches!(&result, Err(Error::IOAt { path, .. }) if path == tempdir.path()));
+	assert!(
+		result
+			.expect_err("This should be Err; qed;")
+			.to_string()
+			.starts_with(&format!("IO error at {}: ", tempdir.path().display()))
+	);
+}
+
+#[test]
+fn unique_path_skips_taken_paths() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let base = tempdir.path().join("notes.txt");
+	for taken in ["notes.txt", "notes-1.txt", "notes-3.txt"] {
+		std::fs::write(tempdir.path().join(taken), "").expect("The file should be writable; qed;");
+	}
+
+	assert_eq!(
+		unique_path(&base).expect("This should be Ok; qed;"),
+		tempdir.path().join("notes-2.txt")
+	);
+	assert_eq!(
+		unique_path(tempdir.path().join("free")).expect("This should be Ok; qed;"),
+		tempdir.path().join("free")
+	);
+}
+
+#[test]
+fn unique_path_fails_if_every_suffix_is_taken() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let base = tempdir.path().join("dir");
+	std::fs::create_dir(&base).expect("This should be created; qed;");
+	for suffix in 1..=MAX_UNIQUE_PATH_SUFFIX {
+		std::fs::create_dir(tempdir.path().join(format!("dir-{suffix}")))
+			.expect("This should be created; qed;");
+	}
+
+	assert!(matches!(
+		unique_path(&base),
+		Err(Error::Descriptive(msg))
+			if msg == format!("Couldn't find a free path for {} after 1000 attempts", base.display())
+	));
+}
+
+#[test]
+fn unique_path_fails_without_file_name() {
+	assert!(matches!(
+		unique_path(".."),
+		Err(Error::Descriptive(msg)) if msg == ".. doesn't have a file name"
+	));
+}
+
+#[test]
+fn with_appended_extension_keeps_current_extensions() {
+	assert_eq!(with_appended_extension("a/b.tar.gz", "bak"), Path::new("a/b.tar.gz.bak"));
+	assert_eq!(with_appended_extension(".env", "local"), Path::new(".env.local"));
+	assert_eq!(with_appended_extension("a/b.rs", ""), Path::new("a/b.rs"));
+	assert_eq!(with_appended_extension("..", "bak"), Path::new(".."));
+}
+
+#[test]
+fn replace_extension_multi_replaces_every_extension() {
+	assert_eq!(replace_extension_multi("a/b.tar.gz", "zip"), Path::new("a/b.zip"));
+	assert_eq!(replace_extension_multi("a/b.rs", "md"), Path::new("a/b.md"));
+	assert_eq!(replace_extension_multi("a/b", "md"), Path::new("a/b.md"));
+	assert_eq!(replace_extension_multi("a/b.tar.gz", ""), Path::new("a/b"));
+	assert_eq!(replace_extension_multi(".config.toml", "json"), Path::new(".config.json"));
+	assert_eq!(replace_extension_multi("/", "md"), Path::new("/"));
+}
+
+#[test]
+fn replace_extension_multi_keeps_versions() {
+	assert_eq!(
+		replace_extension_multi("release-1.2.3.tar.gz", "zip"),
+		Path::new("release-1.2.3.zip")
+	);
+	assert_eq!(replace_extension_multi("release-1.2.3.zip", ""), Path::new("release-1.2.3"));
+	assert_eq!(replace_extension_multi("lib-2.0.so.6", "a"), Path::new("lib-2.0.a"));
+	assert_eq!(replace_extension_multi("archive.7z", "zip"), Path::new("archive.zip"));
+	assert_eq!(
+		stem_utf8_lossy(Path::new("app-0.1.0-x86.tar.gz")).as_deref(),
+		Some("app-0.1.0-x86")
+	);
+}
+
+#[test]
+fn stem_utf8_lossy_strips_every_extension() {
+	assert_eq!(stem_utf8_lossy(Path::new("a/b.tar.gz")).as_deref(), Some("b"));
+	assert_eq!(stem_utf8_lossy(Path::new(".bashrc")).as_deref(), Some(".bashrc"));
+	assert_eq!(stem_utf8_lossy(Path::new("a/..")).as_deref(), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn stem_utf8_lossy_replaces_invalid_utf8() {
+	use std::os::unix::ffi::OsStrExt;
+
+	let path = Path::new(OsStr::from_bytes(b"a\xffb.tar.gz"));
+	assert_eq!(stem_utf8_lossy(path).as_deref(), Some("a\u{fffd}b"));
+}
+
+#[test]
+fn compact_display_picks_the_shortest_rendering() {
+	let root = <Component<'_> as AsRef<Path>>::as_ref(&Component::RootDir);
+	let home = root.join("home").join("user");
+	let project = home.join("projects").join("app");
+
+	assert_eq!(compact_display(&project.join("src"), Some(&project), Some(&home)), "src");
+	assert_eq!(compact_display(&project, Some(&project), Some(&home)), ".");
+	assert_eq!(
+		compact_display(&home.join("notes.md"), Some(&project), Some(&home)),
+		Path::new("~").join("notes.md").display().to_string()
+	);
+	assert_eq!(compact_display(&home, Some(&project), Some(&home)), "~");
+	assert_eq!(
+		compact_display(&root.join("etc"), Some(&project), Some(&home)),
+		root.join("etc").display().to_string()
+	);
+	assert_eq!(compact_display(&home.join("a"), None, None), home.join("a").display().to_string());
+}
+
+#[test]
+fn display_compact_renders_paths_relative_to_the_current_dir() {
+	let current_dir = std::env::current_dir().expect("This should be Ok; qed;");
+
+	assert_eq!(display_compact(current_dir.join("Cargo.toml")), "Cargo.toml");
+	assert_eq!(display_compact("Cargo.toml"), "Cargo.toml");
+}
+
+#[test]
+fn edit_session_rolls_back_recorded_files_and_dirs() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let existing = tempdir.path().join("existing.txt");
+	let new_file = tempdir.path().join("new.txt");
+	let nested_file = tempdir.path().join("a").join("b").join("c.txt");
+	std::fs::write(&existing, "original").expect("The file should be writable; qed;");
+
+	let mut session = EditSession::new();
+	session.write(&existing, "first").expect("This should be Ok; qed;");
+	session.write(&existing, "second").expect("This should be Ok; qed;");
+	session.write(&new_file, "new").expect("This should be Ok; qed;");
+	session.write(&nested_file, "nested").expect("This should be Ok; qed;");
+	assert_eq!(
+		session.tracked_files().collect::<Vec<_>>(),
+		vec![existing.as_path(), new_file.as_path(), nested_file.as_path()]
+	);
+	assert_eq!(std::fs::read_to_string(&existing).expect("This should be Ok; qed;"), "second");
+
+	session.rollback().expect("This should be Ok; qed;");
+
+	assert_eq!(std::fs::read_to_string(&existing).expect("This should be Ok; qed;"), "original");
+	assert!(!new_file.exists());
+	assert!(!tempdir.path().join("a").exists());
+}
+
+#[test]
+fn edit_session_tracks_files_modified_by_other_means() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let file = tempdir.path().join("file.txt");
+	let created = tempdir.path().join("created.txt");
+	std::fs::write(&file, "original").expect("The file should be writable; qed;");
+
+	let mut session = EditSession::new();
+	session.track(&file).expect("This should be Ok; qed;");
+	session.track(&created).expect("This should be Ok; qed;");
+	std::fs::write(&file, "modified").expect("The file should be writable; qed;");
+	std::fs::write(&created, "created").expect("The file should be writable; qed;");
+	session.rollback().expect("This should be Ok; qed;");
+
+	assert_eq!(std::fs::read_to_string(&file).expect("This should be Ok; qed;"), "original");
+	assert!(!created.exists());
+}
+
+#[test]
+fn edit_session_run_keeps_successful_edits() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let file = tempdir.path().join("dir").join("file.txt");
+
+	assert_eq!(
+		EditSession::run(|session| {
+			session.write(&file, "contents")?;
+			Ok(1)
+		})
+		.expect("This should be Ok; qed;"),
+		1
+	);
+	assert_eq!(std::fs::read_to_string(&file).expect("This should be Ok; qed;"), "contents");
+}
+
+#[test]
+fn edit_session_run_rolls_back_failed_edits() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	let file = tempdir.path().join("file.txt");
+	std::fs::write(&file, "original").expect("The file should be writable; qed;");
+
+	let result: Result<(), Error> = EditSession::run(|session| {
+		session.write(&file, "modified")?;
+		session.write(tempdir.path().join("other.txt"), "other")?;
+		Err(Error::Descriptive("Failure".to_owned()))
+	});
+
+	assert!(matches!(result, Err(Error::Descriptive(msg)) if msg == "Failure"));
+	assert_eq!(std::fs::read_to_string(&file).expect("This should be Ok; qed;"), "original");
+	assert!(!tempdir.path().join("other.txt").exists());
+}
+
+#[test]
+fn edit_session_track_fails_if_the_path_cannot_be_read() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+
+	assert!(matches!(
+		EditSession::new().track(tempdir.path()),
+		Err(Error::IOAt { path, .. }) if path == tempdir.path()
+	));
+}
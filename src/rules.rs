@@ -0,0 +1,410 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! A small lint engine running a set of pluggable checks, the [`Rule`]s, over a crate, and
+//! reporting their findings as [`Diagnostic`]s.
+//!
+//! Every rule has a name and a default severity, and crates may tune them in the
+//! `[package.metadata.rustilities.rules.<name>]` section of their manifest:
+//! - `severity`: `"off"` to disable the rule, or the severity of its diagnostics, one of `"note"`,
+//!   `"warning"` and `"error"`.
+//! - `allow`: the subjects the rule must ignore, eg the names of the dependencies that are expected
+//!   to be unused. Every rule documents what its subjects are.
+//! - Any other key configuring the rule, eg the `header` required by [`MissingHeaders`].
+//!
+//! ```toml
+//! [package.metadata.rustilities.rules.unused_dependencies]
+//! severity = "error"
+//! allow = ["openssl"]
+//!
+//! [package.metadata.rustilities.rules.missing_headers]
+//! header = "// SPDX-License-Identifier: GPL-3.0"
+//! allow = ["src/generated.rs"]
+//! ```
+//!
+//! The sections of rules that aren't part of the [`RuleSet`] are ignored, so the configuration
+//! can be shared by tools running different rules.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+	Error,
+	diagnostic::{Diagnostic, Severity, ToDiagnostics},
+	headers::HeaderViolationKind,
+	macros::debug,
+	manifest::PinProblem,
+};
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// A problem found by a [`Rule`], which the engine turns into a [`Diagnostic`] with the configured
+/// severity unless its subject is allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+	/// What the finding is about, matched against the `allow` list of the rule, eg the name of a
+	/// dependency or the path of a file.
+	pub subject: String,
+	/// The description of the problem.
+	pub message: String,
+	/// The file containing the problem, if any.
+	pub path: Option<PathBuf>,
+	/// The line of the file containing the problem, counting from 1, if known.
+	pub line: Option<usize>,
+}
+
+/// The crate checked by a [`Rule`].
+#[derive(Clone, Copy)]
+pub struct RuleContext<'a> {
+	/// The dir of the crate.
+	pub crate_dir: &'a Path,
+	/// The manifest of the crate.
+	pub manifest: &'a DocumentMut,
+	/// The `[package.metadata.rustilities.rules.<name>]` section of the rule, if any.
+	pub config: Option<&'a dyn TableLike>,
+}
+
+impl RuleContext<'_> {
+	/// The path of the manifest of the crate.
+	pub fn manifest_path(&self) -> PathBuf {
+		self.crate_dir.join("Cargo.toml")
+	}
+
+	/// The string value of a key of the configuration of the rule, if any.
+	///
+	/// # Errors
+	///
+	/// - If the key isn't a string.
+	pub fn config_str(&self, key: &str) -> Result<Option<&str>, Error> {
+		self.config
+			.and_then(|config| config.get(key))
+			.map(|item| {
+				item.as_str().ok_or_else(|| {
+					Error::Descriptive(format!("The rule config key `{key}` isn't a string"))
+				})
+			})
+			.transpose()
+	}
+}
+
+/// A check run by [`run`].
+pub trait Rule {
+	/// The name of the rule, eg `unused_dependencies`, naming its configuration section. The
+	/// diagnostics of the rule use `rules::<name>` as their code.
+	fn name(&self) -> &'static str;
+
+	/// The severity of the diagnostics of the rule, unless the manifest configures another one.
+	fn default_severity(&self) -> Severity;
+
+	/// Checks the crate, returning the problems found.
+	///
+	/// # Errors
+	///
+	/// - If the crate cannot be checked.
+	fn check(&self, context: &RuleContext) -> Result<Vec<Finding>, Error>;
+}
+
+/// The rules run by [`run`], in the order their diagnostics are returned.
+#[derive(Default)]
+pub struct RuleSet {
+	rules: Vec<(Box<dyn Rule>, Option<Severity>)>,
+}
+
+impl RuleSet {
+	/// Creates a set without rules.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a set with the rules of this module: [`UnusedDependencies`], [`MissingHeaders`],
+	/// [`WildcardVersions`] and [`FeatureCycles`].
+	pub fn builtin() -> Self {
+		Self::new()
+			.with_rule(UnusedDependencies)
+			.with_rule(MissingHeaders::default())
+			.with_rule(WildcardVersions)
+			.with_rule(FeatureCycles)
+	}
+
+	/// Adds a rule to the set, with its default severity.
+	pub fn with_rule<R: Rule + 'static>(mut self, rule: R) -> Self {
+		let severity = Some(rule.default_severity());
+		self.rules.push((Box::new(rule), severity));
+		self
+	}
+
+	/// Overrides the severity of the rule with the given name, or disables it if `severity` is
+	/// `None`. The configuration found in the manifest takes precedence. Unknown rules are ignored.
+	pub fn with_severity(mut self, name: &str, severity: Option<Severity>) -> Self {
+		for (rule, rule_severity) in &mut self.rules {
+			if rule.name() == name {
+				*rule_severity = severity;
+			}
+		}
+		self
+	}
+
+	/// The names of the rules of the set, in order.
+	pub fn names(&self) -> Vec<&'static str> {
+		self.rules.iter().map(|(rule, _)| rule.name()).collect()
+	}
+}
+
+/// Given a crate dir and a set of rules, this function runs every enabled rule over the crate,
+/// configured by the `[package.metadata.rustilities.rules]` section of its manifest (see the
+/// [module documentation](self)), and returns the diagnostics of the findings whose subject isn't
+/// allowed. The diagnostics are grouped by rule, following the order of the set, and the findings
+/// of each rule keep the order in which the rule reported them.
+///
+/// # Errors
+///
+/// - If the crate manifest cannot be read or parsed.
+/// - If the configuration of some rule is invalid, eg its `severity` is unknown.
+/// - If some rule fails.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::{
+///     diagnostic::Severity,
+///     rules::{RuleSet, UnusedDependencies},
+/// };
+///
+/// let tempdir = tempfile::tempdir().unwrap();
+/// std::fs::create_dir_all(tempdir.path().join("src")).unwrap();
+/// std::fs::write(
+///     tempdir.path().join("Cargo.toml"),
+///     r#"[package]
+/// name = "test"
+///
+/// [dependencies]
+/// regex = "1"
+/// openssl = "0.10"
+///
+/// [package.metadata.rustilities.rules.unused_dependencies]
+/// severity = "error"
+/// allow = ["openssl"]
+/// "#,
+/// )
+/// .unwrap();
+/// std::fs::write(tempdir.path().join("src/lib.rs"), "").unwrap();
+///
+/// let diagnostics =
+///     rustilities::rules::run(tempdir.path(), &RuleSet::new().with_rule(UnusedDependencies))
+///         .unwrap();
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].severity, Severity::Error);
+/// assert_eq!(diagnostics[0].code, "rules::unused_dependencies");
+/// assert_eq!(diagnostics[0].message, "The dependency `regex` isn't used by the code");
+/// ```
+pub fn run<P: AsRef<Path>>(crate_dir: P, rules: &RuleSet) -> Result<Vec<Diagnostic>, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(rules), fields(rules = ?rules.names())))]
+	fn do_run(crate_dir: &Path, rules: &RuleSet) -> Result<Vec<Diagnostic>, Error> {
+		let manifest =
+			std::fs::read_to_string(crate_dir.join("Cargo.toml"))?.parse::<DocumentMut>()?;
+		let configs = ["package", "metadata", "rustilities", "rules"]
+			.iter()
+			.try_fold(manifest.as_item(), |item, key| item.get(key))
+			.and_then(Item::as_table_like);
+
+		let mut diagnostics = Vec::new();
+		for (rule, severity) in &rules.rules {
+			let name = rule.name();
+			let config =
+				configs.and_then(|configs| configs.get(name)).and_then(Item::as_table_like);
+			let Some(severity) = configured_severity(name, config)?.unwrap_or(*severity) else {
+				continue;
+			};
+			let allowed = allowed_subjects(name, config)?;
+
+			let context = RuleContext { crate_dir, manifest: &manifest, config };
+			for finding in rule.check(&context)? {
+				if allowed.contains(&finding.subject) {
+					continue;
+				}
+				let mut diagnostic =
+					Diagnostic::new(severity, format!("rules::{name}"), finding.message);
+				diagnostic.path = finding.path;
+				diagnostic.line = finding.line;
+				diagnostics.push(diagnostic);
+			}
+		}
+		Ok(diagnostics)
+	}
+	do_run(crate_dir.as_ref(), rules)
+}
+
+/// The severity configured for a rule: `None` if it isn't configured, `Some(None)` if the rule is
+/// disabled.
+fn configured_severity(
+	name: &str,
+	config: Option<&dyn TableLike>,
+) -> Result<Option<Option<Severity>>, Error> {
+	let Some(item) = config.and_then(|config| config.get("severity")) else {
+		return Ok(None);
+	};
+	match item.as_str() {
+		Some("off") => Ok(Some(None)),
+		Some("note") => Ok(Some(Some(Severity::Note))),
+		Some("warning") => Ok(Some(Some(Severity::Warning))),
+		Some("error") => Ok(Some(Some(Severity::Error))),
+		_ => Err(Error::Descriptive(format!(
+			"The severity of the `{name}` rule must be one of \"off\", \"note\", \"warning\" or \"error\""
+		))),
+	}
+}
+
+/// The subjects allowed by the configuration of a rule.
+fn allowed_subjects(name: &str, config: Option<&dyn TableLike>) -> Result<Vec<String>, Error> {
+	match config.and_then(|config| config.get("allow")) {
+		None => Ok(Vec::new()),
+		Some(item) => item
+			.as_array()
+			.and_then(|array| array.iter().map(|value| value.as_str().map(str::to_owned)).collect())
+			.ok_or_else(|| {
+				Error::Descriptive(format!(
+					"The `allow` key of the `{name}` rule isn't an array of strings"
+				))
+			}),
+	}
+}
+
+/// The dependencies declared by the crate but not used by its code, as found by
+/// [`unused_dependencies`](crate::manifest::unused_dependencies). The subjects are the names of
+/// the dependencies. Warns by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnusedDependencies;
+
+impl Rule for UnusedDependencies {
+	fn name(&self) -> &'static str {
+		"unused_dependencies"
+	}
+
+	fn default_severity(&self) -> Severity {
+		Severity::Warning
+	}
+
+	fn check(&self, context: &RuleContext) -> Result<Vec<Finding>, Error> {
+		Ok(crate::manifest::unused_dependencies(context.crate_dir)?
+			.into_iter()
+			.map(|dependency| Finding {
+				message: format!("The dependency `{dependency}` isn't used by the code"),
+				subject: dependency,
+				path: Some(context.manifest_path()),
+				line: None,
+			})
+			.collect())
+	}
+}
+
+/// The Rust files of the crate not starting with the required license header, as found by
+/// [`check_headers`](crate::headers::check_headers). The header is the `header` key of the rule
+/// configuration, or the one given to the rule if the configuration doesn't have it. Without a
+/// header, there's nothing to check, so the rule doesn't report anything. The subjects are the
+/// paths of the files relative to the crate dir, using `/` as separator, eg `src/lib.rs`. Warns by
+/// default.
+#[derive(Debug, Clone, Default)]
+pub struct MissingHeaders {
+	/// The required header, eg `// SPDX-License-Identifier: GPL-3.0`.
+	pub header: Option<String>,
+}
+
+impl Rule for MissingHeaders {
+	fn name(&self) -> &'static str {
+		"missing_headers"
+	}
+
+	fn default_severity(&self) -> Severity {
+		Severity::Warning
+	}
+
+	fn check(&self, context: &RuleContext) -> Result<Vec<Finding>, Error> {
+		let Some(header) = context.config_str("header")?.or(self.header.as_deref()) else {
+			debug!("No header configured, skipping the `missing_headers` rule");
+			return Ok(Vec::new());
+		};
+		Ok(crate::headers::check_headers(context.crate_dir, header)?
+			.into_iter()
+			.map(|violation| {
+				let subject = violation
+					.path
+					.strip_prefix(context.crate_dir)
+					.unwrap_or(&violation.path)
+					.components()
+					.map(|component| component.as_os_str().to_string_lossy())
+					.collect::<Vec<_>>()
+					.join("/");
+				let message = match violation.kind {
+					HeaderViolationKind::Missing => "The license header is missing",
+					HeaderViolationKind::Outdated => "The license header is outdated",
+				};
+				Finding {
+					subject,
+					message: message.to_owned(),
+					path: Some(violation.path),
+					line: Some(1),
+				}
+			})
+			.collect())
+	}
+}
+
+/// The dependencies of the crate accepting any version, eg `*` or `1.*`, as found by
+/// [`audit_version_pins`](crate::manifest::audit_version_pins). The subjects are the names of the
+/// dependencies. Denied by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WildcardVersions;
+
+impl Rule for WildcardVersions {
+	fn name(&self) -> &'static str {
+		"wildcard_versions"
+	}
+
+	fn default_severity(&self) -> Severity {
+		Severity::Error
+	}
+
+	fn check(&self, context: &RuleContext) -> Result<Vec<Finding>, Error> {
+		Ok(crate::manifest::audit_manifest_pins(&context.manifest_path())?
+			.into_iter()
+			.filter(|pin| matches!(pin.problem, PinProblem::Wildcard { .. }))
+			.flat_map(|pin| {
+				let subject = pin.key_path.last().cloned().unwrap_or_default();
+				pin.to_diagnostics().into_iter().map(move |diagnostic| Finding {
+					subject: subject.clone(),
+					message: diagnostic.message,
+					path: diagnostic.path,
+					line: diagnostic.line,
+				})
+			})
+			.collect())
+	}
+}
+
+/// The cycles of the `features` section of the crate, as found by
+/// [`detect_feature_cycles`](crate::manifest::detect_feature_cycles). The subject of a cycle is
+/// the feature starting it, which is the smallest one. Denied by default, as cargo rejects them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FeatureCycles;
+
+impl Rule for FeatureCycles {
+	fn name(&self) -> &'static str {
+		"feature_cycles"
+	}
+
+	fn default_severity(&self) -> Severity {
+		Severity::Error
+	}
+
+	fn check(&self, context: &RuleContext) -> Result<Vec<Finding>, Error> {
+		let manifest_path = context.manifest_path();
+		Ok(crate::manifest::detect_feature_cycles(&manifest_path)?
+			.into_iter()
+			.map(|cycle| Finding {
+				subject: cycle[0].clone(),
+				message: format!("The features form a cycle: {}", cycle.join(" -> ")),
+				path: Some(manifest_path.clone()),
+				line: None,
+			})
+			.collect())
+	}
+}
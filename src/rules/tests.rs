@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+use tempfile::TempDir;
+
+const HEADER: &str = "// SPDX-License-Identifier: GPL-3.0";
+
+/// A crate breaking every builtin rule, whose manifest ends with the given rules config.
+fn crate_with_problems(config: &str) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::create_dir_all(tempdir.path().join("src")).expect("The dir should be created; qed;");
+	std::fs::write(
+		tempdir.path().join("Cargo.toml"),
+		format!(
+			r#"[package]
+name = "test"
+
+[dependencies]
+regex = "1.0"
+rand = "*"
+
+[features]
+a = ["b"]
+b = ["a"]
+
+{config}"#
+		),
+	)
+	.expect("The path should be writable; qed;");
+	std::fs::write(tempdir.path().join("src/lib.rs"), format!("{HEADER}\n\nuse rand::Rng;\n"))
+		.expect("The file should be writable; qed;");
+	std::fs::write(tempdir.path().join("src/generated.rs"), "")
+		.expect("The file should be writable; qed;");
+	tempdir
+}
+
+fn rendered(diagnostics: Vec<Diagnostic>) -> Vec<String> {
+	diagnostics
+		.into_iter()
+		.map(|diagnostic| {
+			format!("{}[{}]: {}", diagnostic.severity.as_str(), diagnostic.code, diagnostic.message)
+		})
+		.collect()
+}
+
+#[test]
+fn run_reports_the_findings_of_the_builtin_rules() {
+	let tempdir = crate_with_problems("");
+	let diagnostics = run(tempdir.path(), &RuleSet::builtin()).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		rendered(diagnostics.clone()),
+		vec![
+			"warning[rules::unused_dependencies]: The dependency `regex` isn't used by the code",
+			"error[rules::wildcard_versions]: `dependencies.rand` accepts any version matching `*`",
+			"error[rules::feature_cycles]: The features form a cycle: a -> b -> a",
+		]
+	);
+	assert_eq!(diagnostics[0].path, Some(tempdir.path().join("Cargo.toml")));
+}
+
+#[test]
+fn run_applies_the_manifest_config() {
+	let tempdir = crate_with_problems(&format!(
+		r#"[package.metadata.rustilities.rules.unused_dependencies]
+severity = "note"
+
+[package.metadata.rustilities.rules.missing_headers]
+header = "{HEADER}"
+
+[package.metadata.rustilities.rules.wildcard_versions]
+allow = ["rand"]
+
+[package.metadata.rustilities.rules.feature_cycles]
+severity = "off"
+
+[package.metadata.rustilities.rules.unknown]
+severity = "unknown"
+"#
+	));
+	let diagnostics = run(tempdir.path(), &RuleSet::builtin()).expect("This should be Ok; qed;");
+
+	assert_eq!(
+		rendered(diagnostics.clone()),
+		vec![
+			"note[rules::unused_dependencies]: The dependency `regex` isn't used by the code",
+			"warning[rules::missing_headers]: The license header is missing",
+		]
+	);
+	assert_eq!(diagnostics[1].path, Some(tempdir.path().join("src/generated.rs")));
+	assert_eq!(diagnostics[1].line, Some(1));
+}
+
+#[test]
+fn missing_headers_subjects_are_relative_paths() {
+	let tempdir = crate_with_problems(
+		r#"[package.metadata.rustilities.rules.missing_headers]
+allow = ["src/generated.rs"]
+"#,
+	);
+	let rules = RuleSet::new().with_rule(MissingHeaders { header: Some(HEADER.to_owned()) });
+	assert!(run(tempdir.path(), &rules).expect("This should be Ok; qed;").is_empty());
+}
+
+#[test]
+fn missing_headers_is_skipped_without_header() {
+	let tempdir = crate_with_problems("");
+	assert!(
+		run(tempdir.path(), &RuleSet::new().with_rule(MissingHeaders::default()))
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+}
+
+#[test]
+fn builtin_rules_run_on_unconfigured_crates() {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	std::fs::create_dir_all(tempdir.path().join("src")).expect("The dir should be created; qed;");
+	std::fs::write(tempdir.path().join("Cargo.toml"), "[package]\nname = \"test\"\n")
+		.expect("The path should be writable; qed;");
+	std::fs::write(tempdir.path().join("src/lib.rs"), "pub fn f() {}\n")
+		.expect("The file should be writable; qed;");
+
+	assert!(
+		run(tempdir.path(), &RuleSet::builtin())
+			.expect("This should be Ok; qed;")
+			.is_empty()
+	);
+}
+
+#[test]
+fn run_fails_if_the_config_is_invalid() {
+	let tempdir = crate_with_problems(
+		r#"[package.metadata.rustilities.rules.unused_dependencies]
+severity = "fatal"
+"#,
+	);
+	assert!(matches!(
+		run(tempdir.path(), &RuleSet::builtin()),
+		Err(Error::Descriptive(msg)) if msg == "The severity of the `unused_dependencies` rule must be one of \"off\", \"note\", \"warning\" or \"error\""
+	));
+
+	let tempdir = crate_with_problems(
+		r#"[package.metadata.rustilities.rules.feature_cycles]
+allow = "a"
+"#,
+	);
+	assert!(matches!(
+		run(tempdir.path(), &RuleSet::new().with_rule(FeatureCycles)),
+		Err(Error::Descriptive(msg)) if msg == "The `allow` key of the `feature_cycles` rule isn't an array of strings"
+	));
+}
+
+struct PackageName;
+
+impl Rule for PackageName {
+	fn name(&self) -> &'static str {
+		"package_name"
+	}
+
+	fn default_severity(&self) -> Severity {
+		Severity::Note
+	}
+
+	fn check(&self, context: &RuleContext) -> Result<Vec<Finding>, Error> {
+		let expected = context.config_str("expected")?.unwrap_or("app");
+		let name = context.manifest["package"]["name"].as_str().unwrap_or_default();
+		Ok((name != expected)
+			.then(|| Finding {
+				subject: name.to_owned(),
+				message: format!("The package should be named `{expected}`"),
+				path: None,
+				line: None,
+			})
+			.into_iter()
+			.collect())
+	}
+}
+
+#[test]
+fn custom_rules_can_be_plugged_in() {
+	let tempdir = crate_with_problems(
+		r#"[package.metadata.rustilities.rules.package_name]
+expected = "core"
+"#,
+	);
+	let rules = RuleSet::new().with_rule(PackageName);
+	assert_eq!(rules.names(), vec!["package_name"]);
+	assert_eq!(
+		rendered(run(tempdir.path(), &rules).expect("This should be Ok; qed;")),
+		vec!["note[rules::package_name]: The package should be named `core`"]
+	);
+
+	let rules = rules.with_severity("package_name", Some(Severity::Error));
+	assert_eq!(
+		rendered(run(tempdir.path(), &rules).expect("This should be Ok; qed;")),
+		vec!["error[rules::package_name]: The package should be named `core`"]
+	);
+}
@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! Fixtures shared by the unit tests of the crate.
+
+use tempfile::TempDir;
+
+/// Creates a tempdir with the given files, whose paths are relative to the tempdir.
+pub(crate) fn tempdir_with_files(files: &[(&str, &str)]) -> TempDir {
+	let tempdir = tempfile::tempdir().expect("The tempdir should be created; qed;");
+	for (path, content) in files {
+		let path = tempdir.path().join(path);
+		std::fs::create_dir_all(path.parent().expect("A file always lives inside a dir; qed;"))
+			.expect("The dir should be created; qed;");
+		std::fs::write(path, content).expect("The file should be writable; qed;");
+	}
+	tempdir
+}
+
+/// Creates a tempdir with the given root manifest and the manifests of the given members, as
+/// `(member_dir, manifest)` pairs.
+#[cfg(feature = "manifest")]
+pub(crate) fn workspace(manifest: &str, members: &[(&str, &str)]) -> TempDir {
+	let member_manifests = members
+		.iter()
+		.map(|(member, content)| (format!("{member}/Cargo.toml"), *content))
+		.collect::<Vec<_>>();
+	let mut files = vec![("Cargo.toml", manifest)];
+	files.extend(member_manifests.iter().map(|(path, content)| (path.as_str(), *content)));
+	tempdir_with_files(&files)
+}
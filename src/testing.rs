@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! Utilities to test tools built on top of cargo projects, such as temporary crates and
+//! workspaces.
+
+mod memory_fs;
+#[cfg(test)]
+mod tests;
+
+use crate::Error;
+pub use memory_fs::MemoryFs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// A temporary crate or workspace, removed when the fixture is dropped. Fixtures are created with
+/// [`CrateFixture::builder`].
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::testing::CrateFixture;
+///
+/// let fixture = CrateFixture::builder()
+///     .name("my-crate")
+///     .dependency("serde", r#"{ version = "1.0", features = ["derive"] }"#)
+///     .file("src/main.rs", "fn main() {}")
+///     .build()
+///     .unwrap();
+///
+/// let manifest = std::fs::read_to_string(fixture.manifest_path()).unwrap();
+/// assert!(manifest.contains(r#"name = "my-crate""#));
+/// assert!(manifest.contains(r#"serde = { version = "1.0", features = ["derive"] }"#));
+/// assert!(fixture.join("src/lib.rs").is_file());
+/// assert!(fixture.join("src/main.rs").is_file());
+/// ```
+#[derive(Debug)]
+pub struct CrateFixture {
+	tempdir: TempDir,
+}
+
+impl CrateFixture {
+	/// A builder creating a crate named `test`, with an empty `src/lib.rs` file.
+	pub fn builder() -> CrateFixtureBuilder {
+		CrateFixtureBuilder {
+			name: Some("test".to_owned()),
+			workspace: false,
+			members: Vec::new(),
+			dependencies: Vec::new(),
+			manifest_sections: Vec::new(),
+			files: Vec::new(),
+		}
+	}
+
+	/// The root dir of the fixture.
+	pub fn path(&self) -> &Path {
+		self.tempdir.path()
+	}
+
+	/// Joins a path to the root dir of the fixture.
+	pub fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+		self.tempdir.path().join(path)
+	}
+
+	/// The path to the root manifest of the fixture.
+	pub fn manifest_path(&self) -> PathBuf {
+		self.join("Cargo.toml")
+	}
+}
+
+/// The builder of a [`CrateFixture`].
+#[derive(Debug, Clone)]
+pub struct CrateFixtureBuilder {
+	name: Option<String>,
+	workspace: bool,
+	members: Vec<String>,
+	dependencies: Vec<(String, String)>,
+	manifest_sections: Vec<String>,
+	files: Vec<(PathBuf, String)>,
+}
+
+impl CrateFixtureBuilder {
+	/// Sets the name of the root crate.
+	pub fn name(mut self, name: &str) -> Self {
+		self.name = Some(name.to_owned());
+		self
+	}
+
+	/// Makes the root manifest declare a workspace too.
+	pub fn workspace(mut self) -> Self {
+		self.workspace = true;
+		self
+	}
+
+	/// Makes the root manifest a virtual workspace, which doesn't declare a crate.
+	pub fn virtual_workspace(mut self) -> Self {
+		self.name = None;
+		self.workspace = true;
+		self
+	}
+
+	/// Adds a workspace member at the given path, relative to the root dir. The member is a crate
+	/// named after the last component of the path, with an empty `src/lib.rs` file. Adding a
+	/// member makes the root manifest declare a workspace.
+	pub fn member(mut self, path: &str) -> Self {
+		self.workspace = true;
+		self.members.push(path.to_owned());
+		self
+	}
+
+	/// Adds a dependency to the root manifest, eg `dependency("serde", r#""1.0""#)`. The
+	/// dependency is added to the `workspace.dependencies` section of virtual workspaces, and to
+	/// the `dependencies` section otherwise.
+	pub fn dependency(mut self, name: &str, declaration: &str) -> Self {
+		self.dependencies.push((name.to_owned(), declaration.to_owned()));
+		self
+	}
+
+	/// Appends raw TOML to the root manifest, eg, a `[features]` section.
+	pub fn manifest_section(mut self, section: &str) -> Self {
+		self.manifest_sections.push(section.trim().to_owned());
+		self
+	}
+
+	/// Adds a file at the given path, relative to the root dir. Files are written after the
+	/// manifests and source files generated by the builder, so they can override them.
+	pub fn file<P: AsRef<Path>>(mut self, path: P, content: &str) -> Self {
+		self.files.push((path.as_ref().to_path_buf(), content.to_owned()));
+		self
+	}
+
+	/// Creates the fixture.
+	///
+	/// # Errors
+	///
+	/// - If the temporary dir or some of the files cannot be created.
+	pub fn build(self) -> Result<CrateFixture, Error> {
+		let tempdir = tempfile::tempdir()?;
+		let root = tempdir.path();
+
+		let mut sections = Vec::new();
+		if let Some(name) = &self.name {
+			sections.push(package_section(name));
+			write_file(&root.join("src").join("lib.rs"), "")?;
+		}
+		if self.workspace {
+			let members =
+				self.members.iter().map(|member| format!("{member:?}")).collect::<Vec<_>>();
+			sections
+				.push(format!("[workspace]\nresolver = \"2\"\nmembers = [{}]", members.join(", ")));
+		}
+		if !self.dependencies.is_empty() {
+			let header =
+				if self.name.is_some() { "[dependencies]" } else { "[workspace.dependencies]" };
+			let dependencies = self
+				.dependencies
+				.iter()
+				.map(|(name, declaration)| format!("{name} = {declaration}"))
+				.collect::<Vec<_>>();
+			sections.push(format!("{header}\n{}", dependencies.join("\n")));
+		}
+		sections.extend(self.manifest_sections);
+		write_file(&root.join("Cargo.toml"), &format!("{}\n", sections.join("\n\n")))?;
+
+		for member in &self.members {
+			let member_dir = root.join(member);
+			let name = member_dir
+				.file_name()
+				.map(|name| name.to_string_lossy().into_owned())
+				.unwrap_or_default();
+			write_file(&member_dir.join("Cargo.toml"), &format!("{}\n", package_section(&name)))?;
+			write_file(&member_dir.join("src").join("lib.rs"), "")?;
+		}
+
+		for (path, content) in &self.files {
+			write_file(&root.join(path), content)?;
+		}
+		Ok(CrateFixture { tempdir })
+	}
+}
+
+fn package_section(name: &str) -> String {
+	format!("[package]\nname = {name:?}\nversion = \"0.1.0\"\nedition = \"2021\"")
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), Error> {
+	if let Some(parent) = path.parent() {
+		std::fs::create_dir_all(parent)?;
+	}
+	std::fs::write(path, content)?;
+	Ok(())
+}
@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: GPL-3.0
+
+#[cfg(test)]
+mod tests;
+
+use crate::fs::FsProvider;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	io,
+	path::{Path, PathBuf},
+	sync::Mutex,
+};
+
+/// An in-memory [`FsProvider`], so functions touching the filesystem can be tested
+/// deterministically without creating files on disk. Dirs aren't stored: a dir exists as long as
+/// it contains some file.
+///
+/// Paths can be marked as read-only to simulate write failures without relying on the platform's
+/// permissions: writing to a read-only path, or to a path inside a read-only dir, fails with
+/// [`io::ErrorKind::PermissionDenied`].
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::{fs::FsProvider, testing::MemoryFs};
+/// use std::{io::ErrorKind, path::Path};
+///
+/// let fs = MemoryFs::new().with_file("/crate/Cargo.toml", "[package]\nname = \"test\"\n");
+///
+/// assert!(fs.is_file(Path::new("/crate/Cargo.toml")));
+/// assert!(fs.is_dir(Path::new("/crate")));
+///
+/// fs.set_read_only("/crate");
+/// assert_eq!(
+///     fs.write(Path::new("/crate/Cargo.toml"), "").unwrap_err().kind(),
+///     ErrorKind::PermissionDenied
+/// );
+/// assert_eq!(fs.file("/crate/Cargo.toml").unwrap(), "[package]\nname = \"test\"\n");
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+	files: Mutex<BTreeMap<PathBuf, String>>,
+	read_only: Mutex<BTreeSet<PathBuf>>,
+}
+
+impl MemoryFs {
+	/// Creates an empty filesystem.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a file to the filesystem.
+	pub fn with_file<P: AsRef<Path>>(self, path: P, content: &str) -> Self {
+		self.insert(path, content);
+		self
+	}
+
+	/// Adds a file to the filesystem, replacing it if it exists. Unlike [`FsProvider::write`], this
+	/// also replaces read-only files.
+	pub fn insert<P: AsRef<Path>>(&self, path: P, content: &str) {
+		self.files
+			.lock()
+			.expect("The lock cannot be poisoned; qed;")
+			.insert(path.as_ref().to_path_buf(), content.to_owned());
+	}
+
+	/// Returns the content of a file, if it exists.
+	pub fn file<P: AsRef<Path>>(&self, path: P) -> Option<String> {
+		self.files
+			.lock()
+			.expect("The lock cannot be poisoned; qed;")
+			.get(path.as_ref())
+			.cloned()
+	}
+
+	/// Marks a file or dir as read-only.
+	pub fn set_read_only<P: AsRef<Path>>(&self, path: P) {
+		self.read_only
+			.lock()
+			.expect("The lock cannot be poisoned; qed;")
+			.insert(path.as_ref().to_path_buf());
+	}
+}
+
+impl FsProvider for MemoryFs {
+	fn read_to_string(&self, path: &Path) -> io::Result<String> {
+		self.file(path).ok_or_else(|| {
+			io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display()))
+		})
+	}
+
+	fn write(&self, path: &Path, content: &str) -> io::Result<()> {
+		let read_only = self.read_only.lock().expect("The lock cannot be poisoned; qed;");
+		if read_only.iter().any(|read_only| path.starts_with(read_only)) {
+			return Err(io::Error::new(
+				io::ErrorKind::PermissionDenied,
+				format!("{} is read-only", path.display()),
+			));
+		}
+		self.insert(path, content);
+		Ok(())
+	}
+
+	fn is_file(&self, path: &Path) -> bool {
+		self.files.lock().expect("The lock cannot be poisoned; qed;").contains_key(path)
+	}
+
+	fn is_dir(&self, path: &Path) -> bool {
+		self.files
+			.lock()
+			.expect("The lock cannot be poisoned; qed;")
+			.keys()
+			.any(|file| file != path && file.starts_with(path))
+	}
+}
@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+#[test]
+fn memory_fs_reads_and_writes_files() {
+	let fs = MemoryFs::new();
+	let path = Path::new("/crate/src/lib.rs");
+
+	assert_eq!(fs.read_to_string(path).unwrap_err().kind(), io::ErrorKind::NotFound);
+	assert!(!fs.is_file(path));
+
+	fs.write(path, "pub fn f() {}").expect("This should be Ok; qed;");
+	assert_eq!(fs.read_to_string(path).expect("This should be Ok; qed;"), "pub fn f() {}");
+	assert!(fs.is_file(path));
+	assert!(!fs.is_dir(path));
+	assert!(fs.is_dir(Path::new("/crate/src")));
+	assert!(fs.is_dir(Path::new("/crate")));
+	assert!(!fs.is_dir(Path::new("/other")));
+}
+
+#[test]
+fn memory_fs_read_only_paths_cannot_be_written() {
+	let fs = MemoryFs::new()
+		.with_file("/crate/Cargo.toml", "")
+		.with_file("/other/Cargo.toml", "");
+	fs.set_read_only("/crate");
+
+	assert_eq!(
+		fs.write(Path::new("/crate/Cargo.toml"), "[package]").unwrap_err().kind(),
+		io::ErrorKind::PermissionDenied
+	);
+	assert_eq!(
+		fs.write(Path::new("/crate/src/lib.rs"), "").unwrap_err().kind(),
+		io::ErrorKind::PermissionDenied
+	);
+	assert!(fs.write(Path::new("/other/Cargo.toml"), "[package]").is_ok());
+	assert_eq!(fs.file("/crate/Cargo.toml"), Some(String::new()));
+	assert_eq!(fs.file("/other/Cargo.toml"), Some("[package]".to_owned()));
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+fn read(fixture: &CrateFixture, path: &str) -> String {
+	std::fs::read_to_string(fixture.join(path)).expect("This should be Ok; qed;")
+}
+
+#[test]
+fn default_fixture_is_a_crate() {
+	let fixture = CrateFixture::builder().build().expect("This should be Ok; qed;");
+
+	assert_eq!(fixture.manifest_path(), fixture.path().join("Cargo.toml"));
+	assert_eq!(
+		read(&fixture, "Cargo.toml"),
+		"[package]\nname = \"test\"\nversion = \"0.1.0\"\nedition = \"2021\"\n"
+	);
+	assert_eq!(read(&fixture, "src/lib.rs"), "");
+}
+
+#[test]
+fn fixture_with_members_is_a_workspace() {
+	let fixture = CrateFixture::builder()
+		.name("root")
+		.member("crates/a")
+		.member("b")
+		.dependency("serde", "\"1.0\"")
+		.manifest_section("\n[features]\nstd = []\n")
+		.file("crates/a/src/lib.rs", "pub fn a() {}")
+		.build()
+		.expect("This should be Ok; qed;");
+
+	assert_eq!(
+		read(&fixture, "Cargo.toml"),
+		r#"[package]
+name = "root"
+version = "0.1.0"
+edition = "2021"
+
+[workspace]
+resolver = "2"
+members = ["crates/a", "b"]
+
+[dependencies]
+serde = "1.0"
+
+[features]
+std = []
+"#
+	);
+	assert_eq!(
+		read(&fixture, "crates/a/Cargo.toml"),
+		"[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition = \"2021\"\n"
+	);
+	assert_eq!(read(&fixture, "crates/a/src/lib.rs"), "pub fn a() {}");
+	assert_eq!(read(&fixture, "b/src/lib.rs"), "");
+}
+
+#[test]
+fn virtual_workspace_fixture_doesnt_declare_a_crate() {
+	let fixture = CrateFixture::builder()
+		.virtual_workspace()
+		.dependency("serde", "\"1.0\"")
+		.build()
+		.expect("This should be Ok; qed;");
+
+	assert_eq!(
+		read(&fixture, "Cargo.toml"),
+		"[workspace]\nresolver = \"2\"\nmembers = []\n\n[workspace.dependencies]\nserde = \"1.0\"\n"
+	);
+	assert!(!fixture.join("src").exists());
+}
+
+#[test]
+fn fixture_is_removed_when_dropped() {
+	let fixture = CrateFixture::builder().workspace().build().expect("This should be Ok; qed;");
+	let path = fixture.path().to_path_buf();
+	assert!(path.is_dir());
+
+	drop(fixture);
+	assert!(!path.exists());
+}
@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: GPL-3.0
+
+//! Detection of the cargo and rustc versions in use, so generated code and manifests only use
+//! syntax understood by the toolchain that will build them.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{Error, macros::debug};
+use semver::Version;
+use std::{ffi::OsString, process::Command};
+
+/// The release channel of a toolchain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Channel {
+	/// A stable release, eg `1.85.0`.
+	Stable,
+	/// A beta release, eg `1.86.0-beta.3`.
+	Beta,
+	/// A nightly release, eg `1.87.0-nightly`.
+	Nightly,
+	/// A toolchain built from source.
+	Dev,
+}
+
+/// The version of a toolchain binary, as returned by [`cargo_version`] and [`rustc_version`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolchainVersion {
+	/// The release, eg `1.85.0` or `1.86.0-nightly`.
+	pub version: Version,
+	/// The release channel.
+	pub channel: Channel,
+	/// The hash of the commit the binary was built from, if known.
+	pub commit_hash: Option<String>,
+	/// The date of the commit the binary was built from, formatted as `YYYY-MM-DD`, if known.
+	pub commit_date: Option<String>,
+}
+
+/// A toolchain capability that affects the syntax accepted in manifests or code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ToolchainFeature {
+	/// The `package.rust-version` key and the 2021 edition (1.56).
+	Edition2021,
+	/// The `dep:` prefix and the `?` weak dependency syntax in features (1.60).
+	NamespacedFeatures,
+	/// Inheriting package keys and dependencies from the workspace (1.64).
+	WorkspaceInheritance,
+	/// The sparse protocol for crates.io (1.68).
+	SparseRegistry,
+	/// The `lints` and `workspace.lints` tables (1.74).
+	WorkspaceLints,
+	/// The rust-version aware resolver, `resolver = "3"` (1.84).
+	ResolverV3,
+	/// The 2024 edition (1.85).
+	Edition2024,
+}
+
+impl ToolchainFeature {
+	/// The first stable release supporting the feature.
+	pub fn stabilized_in(self) -> Version {
+		let minor = match self {
+			Self::Edition2021 => 56,
+			Self::NamespacedFeatures => 60,
+			Self::WorkspaceInheritance => 64,
+			Self::SparseRegistry => 68,
+			Self::WorkspaceLints => 74,
+			Self::ResolverV3 => 84,
+			Self::Edition2024 => 85,
+		};
+		Version::new(1, minor, 0)
+	}
+}
+
+impl ToolchainVersion {
+	/// Whether this toolchain supports the given feature. Nightly and dev toolchains of the release
+	/// stabilizing a feature may predate the stabilization, so they're only assumed to support the
+	/// features stabilized in previous releases.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use rustilities::toolchain::{Channel, ToolchainFeature, ToolchainVersion};
+	///
+	/// let nightly = ToolchainVersion {
+	///     version: "1.74.0-nightly".parse().unwrap(),
+	///     channel: Channel::Nightly,
+	///     commit_hash: None,
+	///     commit_date: None,
+	/// };
+	///
+	/// assert!(nightly.supports(ToolchainFeature::SparseRegistry));
+	/// assert!(!nightly.supports(ToolchainFeature::WorkspaceLints));
+	/// ```
+	pub fn supports(&self, feature: ToolchainFeature) -> bool {
+		let release = Version::new(self.version.major, self.version.minor, self.version.patch);
+		let stabilized_in = feature.stabilized_in();
+		release > stabilized_in ||
+			(release == stabilized_in && matches!(self.channel, Channel::Stable | Channel::Beta))
+	}
+}
+
+/// Returns the version of the cargo binary used by cargo commands run from the current dir,
+/// honoring the `CARGO` environment variable set for build scripts and the rustup overrides.
+///
+/// # Errors
+///
+/// - If `cargo -vV` cannot be run or fails.
+/// - If its output cannot be understood.
+///
+/// # Examples
+///
+/// ```
+/// let cargo = rustilities::toolchain::cargo_version().unwrap();
+///
+/// assert_eq!(cargo.version.major, 1);
+/// ```
+pub fn cargo_version() -> Result<ToolchainVersion, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_cargo_version() -> Result<ToolchainVersion, Error> {
+		binary_version(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()), None)
+	}
+	do_cargo_version()
+}
+
+/// Returns the version of the cargo binary of the given rustup toolchain, eg `nightly`, as run by
+/// `cargo +<toolchain>`. Useful to check that a toolchain is actually installed before relying on
+/// it, as rustup may resolve the name to another toolchain or fail to find it.
+///
+/// # Errors
+///
+/// - If `cargo +<toolchain> -vV` cannot be run or fails, eg because the toolchain isn't installed.
+/// - If its output cannot be understood.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::toolchain::Channel;
+///
+/// if let Ok(nightly) = rustilities::toolchain::cargo_version_of("nightly") {
+///     assert_eq!(nightly.channel, Channel::Nightly);
+/// }
+/// ```
+pub fn cargo_version_of(toolchain: &str) -> Result<ToolchainVersion, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_cargo_version_of(toolchain: &str) -> Result<ToolchainVersion, Error> {
+		binary_version("cargo".into(), Some(toolchain))
+	}
+	do_cargo_version_of(toolchain)
+}
+
+/// Returns the version of the rustc binary used by cargo commands run from the current dir,
+/// honoring the `RUSTC` environment variable and the rustup overrides.
+///
+/// # Errors
+///
+/// - If `rustc -vV` cannot be run or fails.
+/// - If its output cannot be understood.
+///
+/// # Examples
+///
+/// ```
+/// let rustc = rustilities::toolchain::rustc_version().unwrap();
+///
+/// assert_eq!(rustc.version.major, 1);
+/// ```
+pub fn rustc_version() -> Result<ToolchainVersion, Error> {
+	#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+	fn do_rustc_version() -> Result<ToolchainVersion, Error> {
+		binary_version(std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()), None)
+	}
+	do_rustc_version()
+}
+
+/// Whether the cargo binary returned by [`cargo_version`] supports the given feature. See
+/// [`ToolchainVersion::supports`].
+///
+/// # Errors
+///
+/// - If the cargo version cannot be detected.
+///
+/// # Examples
+///
+/// ```
+/// use rustilities::toolchain::ToolchainFeature;
+///
+/// let lints = if rustilities::toolchain::supports(ToolchainFeature::WorkspaceLints).unwrap() {
+///     "[lints]\nworkspace = true\n"
+/// } else {
+///     ""
+/// };
+/// ```
+pub fn supports(feature: ToolchainFeature) -> Result<bool, Error> {
+	Ok(cargo_version()?.supports(feature))
+}
+
+/// Runs `<binary> [+<toolchain>] -vV` and parses its output.
+fn binary_version(binary: OsString, toolchain: Option<&str>) -> Result<ToolchainVersion, Error> {
+	debug!(?binary, ?toolchain, "Detecting toolchain version");
+	let mut command = Command::new(&binary);
+	if let Some(toolchain) = toolchain {
+		command.arg(format!("+{toolchain}"));
+	}
+	let output = command.arg("-vV").output()?;
+	if !output.status.success() {
+		return Err(Error::Descriptive(String::from_utf8_lossy(&output.stderr).trim().to_owned()));
+	}
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	parse_verbose_version(&stdout).ok_or_else(|| {
+		Error::Descriptive(format!(
+			"Cannot understand the version of {}: {stdout}",
+			binary.display()
+		))
+	})
+}
+
+/// Parses the output of `cargo -vV` or `rustc -vV`, made of `key: value` lines after the first
+/// one.
+fn parse_verbose_version(output: &str) -> Option<ToolchainVersion> {
+	let field = |key: &str| {
+		output.lines().find_map(|line| {
+			line.strip_prefix(key)
+				.and_then(|line| line.strip_prefix(": "))
+				.map(str::trim)
+				.filter(|value| *value != "unknown")
+		})
+	};
+	let version = Version::parse(field("release")?).ok()?;
+	let channel = match version.pre.split('.').next() {
+		Some("") => Channel::Stable,
+		Some("beta") => Channel::Beta,
+		Some("nightly") => Channel::Nightly,
+		_ => Channel::Dev,
+	};
+	Some(ToolchainVersion {
+		version,
+		channel,
+		commit_hash: field("commit-hash").map(str::to_owned),
+		commit_date: field("commit-date").map(str::to_owned),
+	})
+}
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: GPL-3.0
+
+use super::*;
+
+fn toolchain(version: &str, channel: Channel) -> ToolchainVersion {
+	ToolchainVersion {
+		version: version.parse().expect("This should be Ok; qed;"),
+		channel,
+		commit_hash: None,
+		commit_date: None,
+	}
+}
+
+#[test]
+fn parse_verbose_version_works() {
+	assert_eq!(
+		parse_verbose_version(
+			"rustc 1.90.0 (1159e78c4 2025-09-14)\nbinary: rustc\ncommit-hash: 1159e78c4747b02ef996e55082b704c09b970588\ncommit-date: 2025-09-14\nhost: x86_64-unknown-linux-gnu\nrelease: 1.90.0\nLLVM version: 20.1.8\n"
+		),
+		Some(ToolchainVersion {
+			version: Version::new(1, 90, 0),
+			channel: Channel::Stable,
+			commit_hash: Some("1159e78c4747b02ef996e55082b704c09b970588".to_owned()),
+			commit_date: Some("2025-09-14".to_owned()),
+		})
+	);
+	assert_eq!(
+		parse_verbose_version(
+			"cargo 1.97.0-nightly (4d1f98451 2026-05-15)\nrelease: 1.97.0-nightly\ncommit-hash: 4d1f984518c77fad6eeef4f40153b002a659e662\ncommit-date: 2026-05-15\n"
+		)
+		.map(|version| version.channel),
+		Some(Channel::Nightly)
+	);
+	assert_eq!(
+		parse_verbose_version(
+			"rustc 1.91.0-beta.3\nrelease: 1.91.0-beta.3\ncommit-hash: unknown\n"
+		),
+		Some(ToolchainVersion {
+			version: "1.91.0-beta.3".parse().expect("This should be Ok; qed;"),
+			channel: Channel::Beta,
+			commit_hash: None,
+			commit_date: None,
+		})
+	);
+	assert_eq!(
+		parse_verbose_version("rustc 1.92.0-dev\nrelease: 1.92.0-dev\n")
+			.map(|version| version.channel),
+		Some(Channel::Dev)
+	);
+	assert_eq!(parse_verbose_version("rustc 1.90.0 (1159e78c4 2025-09-14)\n"), None);
+	assert_eq!(parse_verbose_version("release: one\n"), None);
+}
+
+#[test]
+fn supports_compares_against_the_stabilization_release() {
+	assert!(toolchain("1.74.0", Channel::Stable).supports(ToolchainFeature::WorkspaceLints));
+	assert!(toolchain("1.74.0-beta.1", Channel::Beta).supports(ToolchainFeature::WorkspaceLints));
+	assert!(
+		!toolchain("1.74.0-nightly", Channel::Nightly).supports(ToolchainFeature::WorkspaceLints)
+	);
+	assert!(
+		toolchain("1.75.0-nightly", Channel::Nightly).supports(ToolchainFeature::WorkspaceLints)
+	);
+	assert!(!toolchain("1.73.2", Channel::Stable).supports(ToolchainFeature::WorkspaceLints));
+	assert!(toolchain("2.0.0", Channel::Stable).supports(ToolchainFeature::Edition2024));
+}
+
+#[test]
+fn versions_of_the_installed_toolchain_can_be_detected() {
+	let cargo = cargo_version().expect("This should be Ok; qed;");
+	let rustc = rustc_version().expect("This should be Ok; qed;");
+
+	// This crate uses the 2024 edition, so its toolchain supports every feature
+	for toolchain in [&cargo, &rustc] {
+		assert!(toolchain.supports(ToolchainFeature::Edition2024));
+	}
+	assert!(supports(ToolchainFeature::WorkspaceInheritance).expect("This should be Ok; qed;"));
+}
+
+#[test]
+fn cargo_version_of_fails_if_the_toolchain_isnt_installed() {
+	assert!(matches!(
+		cargo_version_of("rustilities-missing-toolchain"),
+		Err(Error::Descriptive(_))
+	));
+}